@@ -0,0 +1,23 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rdfjs_rust::rs::binary::{BinaryReader, BinarySerializer};
+use rdfjs_rust::rs::quad::Quad;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(reader) = BinaryReader::new(data) else {
+        return;
+    };
+    let Ok(quads) = reader.collect::<Result<Vec<Quad>, _>>() else {
+        return;
+    };
+    let mut output = Vec::new();
+    BinarySerializer::new()
+        .serialize(&mut output, &quads)
+        .unwrap();
+    let decoded: Vec<Quad> = BinaryReader::new(output.as_slice())
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(decoded, quads);
+});
@@ -1,39 +1,669 @@
-use rdfjs_rust::js;
-use rdfjs_rust::rs;
+use std::fs::File;
+use std::io::{self, BufWriter, Cursor, Read, Write};
+use std::path::Path;
+use std::process::ExitCode;
 
-use crate::rs::quad_object::QuadObject;
-use crate::rs::quad_predicate::QuadPredicate;
-use crate::rs::quad_subject::QuadSubject;
+use rdfjs_rust::rs::blank_node::BlankNode;
+use rdfjs_rust::rs::canonical::canonical_nquads;
+use rdfjs_rust::rs::dataset::Dataset;
+use rdfjs_rust::rs::diff::diff;
+use rdfjs_rust::rs::format::{RdfFormat, read_quads, sniff, write_quads};
+use rdfjs_rust::rs::literal_check::check_quads;
+use rdfjs_rust::rs::parse_error::ParseError;
+use rdfjs_rust::rs::quad::Quad;
+use rdfjs_rust::rs::quad_graph::QuadGraph;
+use rdfjs_rust::rs::quad_object::QuadObject;
+use rdfjs_rust::rs::quad_subject::QuadSubject;
+use rdfjs_rust::rs::rdf_patch::RdfPatch;
+use rdfjs_rust::rs::sparql::{
+    QueryResults, QueryResultsFormat, evaluate_query, parse_query, write_results,
+};
+use rdfjs_rust::rs::stream::Quads;
+use rdfjs_rust::rs::term_like::TermLike;
 
-fn main() {
-    // JS
-    let mut factory = js::DataFactory::new();
+const USAGE: &str = "\
+usage: rdfjs-rust <command> [options] [FILE...]
 
-    let nn = js::DataFactory::named_node("nn");
-    let _bn = factory.blank_node(None);
-    let l = js::DataFactory::literal("l", None);
-    let _v = js::DataFactory::variable("v");
-    let _dg = js::DataFactory::default_graph();
+Reads each FILE, or standard input if there is none or for `-`.
 
-    let q = js::DataFactory::quad(&nn.to_term(), &nn.to_term(), &l.to_term(), None);
+commands:
+  convert       write the quads of the inputs in another format
+  validate      check that the inputs parse and their literals are consistent
+  count         print the number of quads of the inputs
+  canonicalize  write the inputs as RDFC-1.0 canonical N-Quads
+  query         evaluate a SPARQL query over the inputs
+  diff          compare two inputs, up to blank node labels
+  patch         apply an RDF Patch to the inputs and write the result
 
-    print!("{:#?}", q);
+options:
+  -f, --from FORMAT       input format, by default from the file extension
+                          or the content
+  -t, --to FORMAT         output format: for convert, by default from the
+                          output extension or N-Quads; for query, json, xml,
+                          csv or tsv results, or an RDF format for graphs;
+                          for diff, text (`-`/`+` lines) or patch
+  -o, --output FILE       write to FILE instead of standard output
+  -q, --query QUERY       the SPARQL query to evaluate
+      --query-file FILE   read the SPARQL query from FILE
+  -p, --patch FILE        the RDF Patch to apply
+  -h, --help              print this help
 
-    // RS
-    let mut factory = rs::data_factory::DataFactory::new();
+Formats are given by name (turtle, n-quads...), extension or media type.
 
-    let nn = rs::data_factory::DataFactory::named_node("nn");
-    let _bn = factory.blank_node(None);
-    let l = rs::data_factory::DataFactory::literal("l", None);
-    let _v = rs::data_factory::DataFactory::variable("v");
-    let _dg = rs::data_factory::DataFactory::default_graph();
+exit status: 0 on success, 1 for invalid input, 2 for invalid arguments,
+3 for input or output failures, 4 when diff finds differences.";
 
-    let q = rs::data_factory::DataFactory::quad(
-        &QuadSubject::NamedNode(nn.clone()),
-        &QuadPredicate::NamedNode(nn.clone()),
-        &QuadObject::Literal(l.clone()),
-        None,
-    );
+const INVALID_DATA: u8 = 1;
+const USAGE_ERROR: u8 = 2;
+const IO_ERROR: u8 = 3;
+const DIFFERENT: u8 = 4;
 
-    print!("{:#?}", q);
+/// Up to this many bytes are read to detect the format of an input.
+const SNIFF_LENGTH: u64 = 4096;
+
+struct Failure {
+    status: u8,
+    message: String,
+}
+
+impl Failure {
+    fn new(status: u8, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn usage(message: impl Into<String>) -> Self {
+        Self::new(USAGE_ERROR, message)
+    }
+
+    /// A failure reading or writing `name`.
+    fn io(name: &str, error: io::Error) -> Self {
+        let status = match error.kind() {
+            io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => INVALID_DATA,
+            io::ErrorKind::Unsupported => USAGE_ERROR,
+            _ => IO_ERROR,
+        };
+        Self::new(status, format!("{name}: {error}"))
+    }
+
+    fn parse(name: &str, error: ParseError) -> Self {
+        match error {
+            ParseError::Syntax(error) => Self::new(
+                INVALID_DATA,
+                format!(
+                    "{name}:{}:{}: {}",
+                    error.line(),
+                    error.column(),
+                    error.message()
+                ),
+            ),
+            ParseError::Io(error) => Self::io(name, error),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Convert,
+    Validate,
+    Count,
+    Canonicalize,
+    Query,
+    Diff,
+    Patch,
+}
+
+struct Options {
+    command: Command,
+    from: Option<RdfFormat>,
+    to: Option<String>,
+    output: Option<String>,
+    query: Option<String>,
+    query_file: Option<String>,
+    patch: Option<String>,
+    inputs: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("{USAGE}");
+        return ExitCode::from(USAGE_ERROR);
+    }
+    if args[0] == "-h" || args[0] == "--help" {
+        // Like other output, a closed pipe is not an error.
+        let _ = writeln!(io::stdout(), "{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    match run(&args, &mut io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failures) => {
+            for failure in &failures {
+                eprintln!("rdfjs-rust: {}", failure.message);
+            }
+            ExitCode::from(failures.iter().map(|failure| failure.status).max().unwrap())
+        }
+    }
+}
+
+/// Runs the command of `args`, writing to `stdout` unless an output file is
+/// given. Validation reports every invalid input, other commands stop at the
+/// first failure.
+fn run(args: &[String], stdout: &mut dyn Write) -> Result<(), Vec<Failure>> {
+    let options = parse_args(args).map_err(|failure| vec![failure])?;
+    if options.command == Command::Validate {
+        return validate(&options);
+    }
+    run_command(&options, stdout).map_err(|failure| vec![failure])
+}
+
+fn run_command(options: &Options, stdout: &mut dyn Write) -> Result<(), Failure> {
+    if options.command == Command::Count {
+        let mut count = 0usize;
+        for_each_input(options, |_, _| count += 1)?;
+        return output(options, stdout, |writer| writeln!(writer, "{count}"));
+    }
+    if options.command == Command::Diff {
+        return compare(options, stdout);
+    }
+
+    let quads = read_inputs(options)?;
+    match options.command {
+        Command::Convert => write_output(options, stdout, &quads),
+        Command::Patch => {
+            let path = options.patch.as_deref().unwrap();
+            let text = std::fs::read_to_string(path).map_err(|error| Failure::io(path, error))?;
+            let patch = RdfPatch::parse(&text)
+                .map_err(|error| Failure::parse(path, ParseError::Syntax(error)))?;
+            let mut dataset: Dataset = quads.iter().collect();
+            patch.apply(&mut dataset);
+            let quads: Vec<Quad> = dataset.iter().collect();
+            write_output(options, stdout, &quads)
+        }
+        Command::Canonicalize => {
+            let nquads = canonical_nquads(&quads)
+                .map_err(|error| Failure::new(INVALID_DATA, error.to_string()))?;
+            output(options, stdout, |writer| {
+                writer.write_all(nquads.as_bytes())
+            })
+        }
+        _ => query(options, &quads, stdout),
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, Failure> {
+    let command = match args.first().map(String::as_str) {
+        Some("convert") => Command::Convert,
+        Some("validate") => Command::Validate,
+        Some("count") => Command::Count,
+        Some("canonicalize") => Command::Canonicalize,
+        Some("query") => Command::Query,
+        Some("diff") => Command::Diff,
+        Some("patch") => Command::Patch,
+        Some(command) => return Err(Failure::usage(format!("unknown command {command:?}"))),
+        None => return Err(Failure::usage("missing command")),
+    };
+    let mut options = Options {
+        command,
+        from: None,
+        to: None,
+        output: None,
+        query: None,
+        query_file: None,
+        patch: None,
+        inputs: Vec::new(),
+    };
+
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| Failure::usage(format!("{arg} needs a value")))
+        };
+        match arg.as_str() {
+            "-f" | "--from" => options.from = Some(rdf_format(&value()?)?),
+            "-t" | "--to" => options.to = Some(value()?),
+            "-o" | "--output" => options.output = Some(value()?),
+            "-q" | "--query" => options.query = Some(value()?),
+            "--query-file" => options.query_file = Some(value()?),
+            "-p" | "--patch" => options.patch = Some(value()?),
+            "-" => options.inputs.push(arg.clone()),
+            arg if arg.starts_with('-') => {
+                return Err(Failure::usage(format!("unknown option {arg}")));
+            }
+            _ => options.inputs.push(arg.clone()),
+        }
+    }
+
+    if command == Command::Query && options.query.is_some() == options.query_file.is_some() {
+        return Err(Failure::usage("query needs one of --query or --query-file"));
+    }
+    if command != Command::Query && (options.query.is_some() || options.query_file.is_some()) {
+        return Err(Failure::usage("only query takes a query"));
+    }
+    if command == Command::Patch && options.patch.is_none() {
+        return Err(Failure::usage("patch needs --patch"));
+    }
+    if command != Command::Patch && options.patch.is_some() {
+        return Err(Failure::usage("only patch takes a patch"));
+    }
+    if command == Command::Diff && options.inputs.len() != 2 {
+        return Err(Failure::usage("diff needs two inputs"));
+    }
+    if options.inputs.is_empty() {
+        options.inputs.push("-".to_owned());
+    }
+    Ok(options)
+}
+
+/// Looks up a format by name, extension or media type.
+fn rdf_format(name: &str) -> Result<RdfFormat, Failure> {
+    let normalized: String = name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    RdfFormat::from_extension(name)
+        .or_else(|| RdfFormat::from_media_type(name))
+        .or_else(|| {
+            RdfFormat::ALL.into_iter().find(|format| {
+                let format_name: String = format
+                    .name()
+                    .chars()
+                    .filter(char::is_ascii_alphanumeric)
+                    .collect();
+                format_name.to_ascii_lowercase() == normalized
+                    || normalized == "binary" && *format == RdfFormat::Binary
+            })
+        })
+        .ok_or_else(|| Failure::usage(format!("unknown format {name:?}")))
+}
+
+fn format_from_path(path: &str) -> Option<RdfFormat> {
+    RdfFormat::from_extension(Path::new(path).extension()?.to_str()?)
+}
+
+fn input_name(path: &str) -> &str {
+    if path == "-" { "<stdin>" } else { path }
+}
+
+/// The quads of the input at `path`, in the given format or else the one of
+/// its extension or content.
+fn open_input(path: &str, from: Option<RdfFormat>) -> Result<Quads<'static, ParseError>, Failure> {
+    let name = input_name(path);
+    let mut reader: Box<dyn Read> = if path == "-" {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(path).map_err(|error| Failure::io(name, error))?)
+    };
+    let format = match from.or_else(|| format_from_path(path)) {
+        Some(format) => format,
+        None => {
+            let mut prefix = Vec::new();
+            reader
+                .by_ref()
+                .take(SNIFF_LENGTH)
+                .read_to_end(&mut prefix)
+                .map_err(|error| Failure::io(name, error))?;
+            // Empty inputs have no quads in any format.
+            let sniffed = match prefix.iter().all(u8::is_ascii_whitespace) {
+                true => Some(RdfFormat::NQuads),
+                false => sniff(&prefix),
+            };
+            let format = sniffed.ok_or_else(|| {
+                Failure::new(
+                    INVALID_DATA,
+                    format!("{name}: cannot detect the format, use --from"),
+                )
+            })?;
+            reader = Box::new(Cursor::new(prefix).chain(reader));
+            format
+        }
+    };
+    Ok(read_quads(format, reader))
+}
+
+/// Calls `f` with the index of the input and each of its quads.
+fn for_each_input(options: &Options, mut f: impl FnMut(usize, Quad)) -> Result<(), Failure> {
+    for (index, path) in options.inputs.iter().enumerate() {
+        for quad in open_input(path, options.from)? {
+            f(
+                index,
+                quad.map_err(|error| Failure::parse(input_name(path), error))?,
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Reads all inputs. Blank nodes of different inputs are kept apart.
+fn read_inputs(options: &Options) -> Result<Vec<Quad>, Failure> {
+    let mut quads = Vec::new();
+    let several = options.inputs.len() > 1;
+    for_each_input(options, |index, quad| {
+        quads.push(if several {
+            scope_blank_nodes(&quad, index)
+        } else {
+            quad
+        })
+    })?;
+    Ok(quads)
+}
+
+fn scope_blank_nodes(quad: &Quad, input: usize) -> Quad {
+    let scope = |node: &BlankNode| BlankNode::new(&format!("f{input}_{}", node.value()));
+    let subject = match quad.subject() {
+        QuadSubject::BlankNode(node) => QuadSubject::BlankNode(scope(node)),
+        QuadSubject::Quad(triple) => QuadSubject::Quad(Box::new(scope_blank_nodes(triple, input))),
+        subject => subject.clone(),
+    };
+    let object = match quad.object() {
+        QuadObject::BlankNode(node) => QuadObject::BlankNode(scope(node)),
+        object => object.clone(),
+    };
+    let graph = match quad.graph() {
+        QuadGraph::BlankNode(node) => QuadGraph::BlankNode(scope(node)),
+        graph => graph.clone(),
+    };
+    Quad::new(&subject, quad.predicate(), &object, Some(&graph))
+}
+
+/// Reads the input at `path` on its own.
+fn read_input(path: &str, from: Option<RdfFormat>) -> Result<Vec<Quad>, Failure> {
+    open_input(path, from)?
+        .collect::<Result<_, _>>()
+        .map_err(|error| Failure::parse(input_name(path), error))
+}
+
+fn validate(options: &Options) -> Result<(), Vec<Failure>> {
+    let mut failures = Vec::new();
+    for path in &options.inputs {
+        let name = input_name(path);
+        match read_input(path, options.from) {
+            Ok(quads) => {
+                for inconsistency in check_quads(&quads) {
+                    failures.push(Failure::new(
+                        INVALID_DATA,
+                        format!("{name}: {}", inconsistency.error),
+                    ));
+                }
+            }
+            Err(failure) => failures.push(failure),
+        }
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
+
+/// Writes `quads` in the format of `--to`, of the output extension or else
+/// N-Quads.
+fn write_output(options: &Options, stdout: &mut dyn Write, quads: &[Quad]) -> Result<(), Failure> {
+    let format = match &options.to {
+        Some(name) => rdf_format(name)?,
+        None => options
+            .output
+            .as_deref()
+            .and_then(format_from_path)
+            .unwrap_or(RdfFormat::NQuads),
+    };
+    output(options, stdout, |writer| write_quads(format, writer, quads))
+}
+
+/// Writes the changes from the first input to the second.
+fn compare(options: &Options, stdout: &mut dyn Write) -> Result<(), Failure> {
+    let old = read_input(&options.inputs[0], options.from)?;
+    let new = read_input(&options.inputs[1], options.from)?;
+    let diff = diff(&old, &new).map_err(|error| Failure::new(INVALID_DATA, error.to_string()))?;
+    let text = match options.to.as_deref() {
+        None | Some("text") => diff.to_string(),
+        Some("patch") => RdfPatch::from_diff(&diff).to_string(),
+        Some(name) => return Err(Failure::usage(format!("unknown diff format {name:?}"))),
+    };
+    output(options, stdout, |writer| writer.write_all(text.as_bytes()))?;
+    if diff.is_empty() {
+        Ok(())
+    } else {
+        Err(Failure::new(
+            DIFFERENT,
+            format!("{} and {} differ", options.inputs[0], options.inputs[1]),
+        ))
+    }
+}
+
+fn query(options: &Options, quads: &[Quad], stdout: &mut dyn Write) -> Result<(), Failure> {
+    let (name, text) = match (&options.query, &options.query_file) {
+        (Some(query), _) => ("query", query.clone()),
+        (None, Some(path)) => (
+            path.as_str(),
+            std::fs::read_to_string(path).map_err(|error| Failure::io(path, error))?,
+        ),
+        (None, None) => unreachable!("checked by parse_args"),
+    };
+    let query = parse_query(&text, None).map_err(|error| {
+        Failure::new(
+            INVALID_DATA,
+            format!(
+                "{name}:{}:{}: {}",
+                error.line(),
+                error.column(),
+                error.message()
+            ),
+        )
+    })?;
+    let dataset: Dataset = quads.iter().collect();
+    let results = evaluate_query(&dataset, &query)
+        .map_err(|error| Failure::new(INVALID_DATA, error.to_string()))?;
+
+    match &results {
+        QueryResults::Graph(triples) => {
+            let format = match &options.to {
+                Some(name) => rdf_format(name)?,
+                None => RdfFormat::NTriples,
+            };
+            output(options, stdout, |writer| {
+                write_quads(format, writer, triples)
+            })
+        }
+        results => {
+            let format = match &options.to {
+                Some(name) => QueryResultsFormat::from_extension(name)
+                    .or_else(|| QueryResultsFormat::from_media_type(name))
+                    .ok_or_else(|| Failure::usage(format!("unknown results format {name:?}")))?,
+                None => QueryResultsFormat::Json,
+            };
+            output(options, stdout, |writer| {
+                write_results(writer, results, format)
+            })
+        }
+    }
+}
+
+/// Calls `write` with the output file, or `stdout` if there is none.
+///
+/// A regular output file is written to a temporary file next to it and only
+/// replaced once `write` succeeds, so a failure leaves it as it was.
+fn output(
+    options: &Options,
+    stdout: &mut dyn Write,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> Result<(), Failure> {
+    match &options.output {
+        Some(name) => {
+            let path = Path::new(name);
+            // Devices and pipes, e.g. /dev/stdout, cannot be replaced.
+            if path.metadata().is_ok_and(|metadata| !metadata.is_file()) {
+                let mut writer =
+                    BufWriter::new(File::create(path).map_err(|error| Failure::io(name, error))?);
+                return write(&mut writer)
+                    .and_then(|()| writer.flush())
+                    .map_err(|error| Failure::io(name, error));
+            }
+
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            let temporary = path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()));
+            let result = File::create(&temporary).and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                write(&mut writer)?;
+                writer.flush()?;
+                std::fs::rename(&temporary, path)
+            });
+            if result.is_err() {
+                let _ = std::fs::remove_file(&temporary);
+            }
+            result.map_err(|error| Failure::io(name, error))
+        }
+        None => match write(stdout).and_then(|()| stdout.flush()) {
+            // The reader, e.g. `head`, has what it wants.
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            result => result.map_err(|error| Failure::io("<stdout>", error)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = "tests/fixtures/jelly/graphs.nq";
+
+    fn run_args(args: &[&str]) -> (Result<(), Vec<Failure>>, String) {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut stdout = Vec::new();
+        let result = run(&args, &mut stdout);
+        (result, String::from_utf8(stdout).unwrap())
+    }
+
+    #[test]
+    fn commands_read_and_write_formats() {
+        let (result, output) = run_args(&["count", FIXTURE, FIXTURE]);
+        assert!(result.is_ok());
+        assert_eq!(output, "4\n");
+
+        let (result, output) = run_args(&["convert", "-t", "trig", FIXTURE]);
+        assert!(result.is_ok());
+        assert!(output.contains("<http://example.org/g> {"), "{output}");
+
+        let (result, output) = run_args(&["canonicalize", FIXTURE]);
+        assert!(result.is_ok());
+        assert!(
+            output.contains("_:c14n0 <http://example.org/g> .\n"),
+            "{output}"
+        );
+
+        let (result, output) = run_args(&[
+            "query",
+            "-q",
+            "SELECT ?o WHERE { GRAPH ?g { ?s ?p ?o } }",
+            "-t",
+            "tsv",
+            FIXTURE,
+        ]);
+        assert!(result.is_ok());
+        assert_eq!(output, "?o\n_:o\n");
+    }
+
+    #[test]
+    fn failed_outputs_leave_files_unchanged() {
+        let dir = std::env::temp_dir().join(format!("rdfjs-rust-cli-{}-out", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.rdf");
+        let name = path.to_str().unwrap();
+
+        // RDF/XML cannot hold the named graph of the fixture.
+        let (result, _) = run_args(&["convert", "-t", "rdf", "-o", name, FIXTURE]);
+        assert!(result.is_err());
+        assert!(!path.exists());
+
+        std::fs::write(&path, "old").unwrap();
+        let (result, _) = run_args(&["convert", "-t", "rdf", "-o", name, FIXTURE]);
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let (result, _) = run_args(&["convert", "-t", "nq", "-o", name, FIXTURE]);
+        assert!(result.is_ok());
+        assert!(std::fs::read_to_string(&path).unwrap().contains("_:"));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn diffs_apply_as_patches() {
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        let path = |name: &str| {
+            dir.join(format!("rdfjs-rust-cli-{id}-{name}"))
+                .to_str()
+                .unwrap()
+                .to_owned()
+        };
+        let (old, new, patch) = (path("old.ttl"), path("new.ttl"), path("diff.rdfp"));
+        std::fs::write(
+            &old,
+            "<http://a> <http://b> [ <http://c> 1 ], [ <http://c> 2 ] .\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &new,
+            "<http://a> <http://b> [ <http://c> 2 ], [ <http://c> 3 ] .\n",
+        )
+        .unwrap();
+
+        let (result, output) = run_args(&["diff", &old, &new]);
+        assert_eq!(result.err().unwrap()[0].status, DIFFERENT);
+        assert_eq!(
+            output.lines().filter(|line| line.starts_with("- ")).count(),
+            2
+        );
+        assert!(output.contains("+ _:"), "{output}");
+
+        let (result, output) = run_args(&["diff", "-t", "patch", &old, &new]);
+        assert_eq!(result.err().unwrap()[0].status, DIFFERENT);
+        std::fs::write(&patch, output).unwrap();
+        let (result, output) = run_args(&["patch", "-p", &patch, "-t", "nt", &old]);
+        assert!(result.is_ok());
+        std::fs::write(&old, output).unwrap();
+        let (result, output) = run_args(&["diff", "-f", "turtle", &old, &new]);
+        for file in [&old, &new, &patch] {
+            std::fs::remove_file(file).unwrap();
+        }
+        assert!(result.is_ok());
+        assert_eq!(output, "");
+    }
+
+    #[test]
+    fn failures_have_positions_and_statuses() {
+        let path = std::env::temp_dir().join(format!("rdfjs-rust-cli-{}.ttl", std::process::id()));
+        std::fs::write(
+            &path,
+            "<http://a> <http://b> <http://c> .\n<http://a> <http://b> .\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap().to_owned();
+        let (result, _) = run_args(&["validate", &path]);
+        std::fs::remove_file(&path).unwrap();
+        let failures = result.err().unwrap();
+        assert_eq!(failures[0].status, INVALID_DATA);
+        assert!(
+            failures[0].message.starts_with(&format!("{path}:2:")),
+            "{}",
+            failures[0].message
+        );
+
+        let (result, _) = run_args(&["convert", "-t", "nope", FIXTURE]);
+        assert_eq!(result.err().unwrap()[0].status, USAGE_ERROR);
+        let (result, _) = run_args(&["count", "missing.ttl"]);
+        assert_eq!(result.err().unwrap()[0].status, IO_ERROR);
+        let (result, _) = run_args(&["query", FIXTURE]);
+        assert_eq!(result.err().unwrap()[0].status, USAGE_ERROR);
+        let (result, _) = run_args(&["diff", FIXTURE]);
+        assert_eq!(result.err().unwrap()[0].status, USAGE_ERROR);
+    }
 }
@@ -1,14 +1,43 @@
+#[cfg(feature = "async")]
+pub mod async_turtle;
+pub mod bgp;
+pub mod binary;
 pub mod blank_node;
+pub mod canonical;
 pub mod data_factory;
+pub mod dataset;
 pub mod default_graph;
+pub mod diff;
+pub mod format;
+#[cfg(feature = "hdt")]
+pub mod hdt;
+pub mod iri;
+#[cfg(feature = "jelly")]
+pub mod jelly;
+pub mod jsonld;
 pub mod literal;
+pub mod literal_check;
 pub mod named_node;
+pub mod parse_error;
+pub mod pattern;
+pub mod prefix_map;
 pub mod quad;
 pub mod quad_graph;
 pub mod quad_object;
 pub mod quad_predicate;
 pub mod quad_subject;
+pub mod rdf_json;
+pub mod rdf_patch;
+pub mod rdfxml;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod sparql;
+#[cfg(feature = "store")]
+pub mod store;
+pub mod stream;
 pub mod term;
 pub mod term_like;
 pub mod test_data;
+pub mod turtle;
 pub mod variable;
+pub mod vocab;
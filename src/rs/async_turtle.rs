@@ -0,0 +1,168 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+use futures_core::Stream;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
+
+use crate::rs::parse_error::ParseError;
+use crate::rs::quad::Quad;
+use crate::rs::turtle::{TurtleFormatter, TurtleParser, TurtleSerializer};
+
+const CHUNK_SIZE: usize = 8192;
+
+/// Stream of the quads of a Turtle-family document read from `R`.
+///
+/// Input is only read when the stream is polled and no parsed quad is
+/// pending, so memory stays bounded by one chunk plus the statement being
+/// parsed, a TriG graph block being parsed statement by statement, see
+/// [`TurtleParser`].
+pub struct AsyncTurtleReader<R> {
+    reader: R,
+    parser: TurtleParser,
+    chunk: Box<[u8]>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncTurtleReader<R> {
+    pub fn new(reader: R, parser: TurtleParser) -> Self {
+        Self {
+            reader,
+            parser,
+            chunk: vec![0; CHUNK_SIZE].into_boxed_slice(),
+        }
+    }
+
+    pub fn parser(&self) -> &TurtleParser {
+        &self.parser
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncTurtleReader<R> {
+    type Item = Result<Quad, ParseError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(result) = this.parser.read_next() {
+                return Poll::Ready(Some(result.map_err(ParseError::from)));
+            }
+            if this.parser.is_done() {
+                return Poll::Ready(None);
+            }
+
+            let mut buffer = ReadBuf::new(&mut this.chunk);
+            if let Err(error) = ready!(Pin::new(&mut this.reader).poll_read(cx, &mut buffer)) {
+                return Poll::Ready(Some(Err(error.into())));
+            }
+            if buffer.filled().is_empty() {
+                this.parser.end();
+            } else {
+                this.parser.extend_from_slice(buffer.filled());
+            }
+        }
+    }
+}
+
+/// Writes quads to `W` in a Turtle-family syntax as they come.
+///
+/// Consecutive quads sharing graph and subject are abbreviated; unlike
+/// [`TurtleSerializer::serialize`] nothing is regrouped, so memory does not
+/// grow with the output.
+pub struct AsyncTurtleWriter<W> {
+    writer: W,
+    formatter: TurtleFormatter,
+    buffer: String,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncTurtleWriter<W> {
+    pub fn new(writer: W, serializer: &TurtleSerializer) -> Self {
+        Self {
+            writer,
+            formatter: serializer.formatter(),
+            buffer: String::new(),
+        }
+    }
+
+    /// Buffers `quad`, waiting for the writer once a chunk is full.
+    pub async fn write_quad(&mut self, quad: &Quad) -> io::Result<()> {
+        self.formatter.format(quad, &mut self.buffer)?;
+        if self.buffer.len() >= CHUNK_SIZE {
+            self.writer.write_all(self.buffer.as_bytes()).await?;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+
+    /// Ends the document and flushes it, returning the writer.
+    pub async fn finish(mut self) -> io::Result<W> {
+        self.formatter.finish(&mut self.buffer);
+        self.writer.write_all(self.buffer.as_bytes()).await?;
+        self.writer.flush().await?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::{Future, poll_fn};
+
+    use super::*;
+    use crate::rs::turtle::{TurtleSyntax, parse_str};
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    async fn collect<R: AsyncRead + Unpin>(
+        mut reader: AsyncTurtleReader<R>,
+    ) -> Result<Vec<Quad>, ParseError> {
+        let mut quads = Vec::new();
+        while let Some(quad) = poll_fn(|cx| Pin::new(&mut reader).poll_next(cx)).await {
+            quads.push(quad?);
+        }
+        Ok(quads)
+    }
+
+    #[test]
+    fn streams_round_trip_through_pipes() {
+        let input = "@prefix ex: <http://example.org/> .\n\
+                     ex:s a ex:C ; ex:p \"caf\u{e9}\"@fr, 1.5 .\n\
+                     ex:g { ex:s ex:p ex:o . << ex:s ex:p ex:o >> ex:p _:b . }\n";
+        let expected = parse_str(TurtleSyntax::TriG, input, None).unwrap();
+
+        for syntax in [TurtleSyntax::TriG, TurtleSyntax::NQuads] {
+            let quads = block_on(async {
+                // A tiny pipe makes the writer wait for the reader.
+                let (client, server) = tokio::io::duplex(16);
+                let written = expected.clone();
+                let writing = tokio::spawn(async move {
+                    let serializer =
+                        TurtleSerializer::new(syntax).with_prefix("ex", "http://example.org/");
+                    let mut writer = AsyncTurtleWriter::new(client, &serializer);
+                    for quad in &written {
+                        writer.write_quad(quad).await.unwrap();
+                    }
+                    writer.finish().await.unwrap();
+                });
+                let quads =
+                    collect(AsyncTurtleReader::new(server, TurtleParser::new(syntax))).await;
+                writing.await.unwrap();
+                quads.unwrap()
+            });
+            assert_eq!(quads, expected, "{syntax:?}");
+        }
+    }
+
+    #[test]
+    fn errors_end_the_stream() {
+        let input: &[u8] = b"<http://example.org/s> <http://example.org/p> .\n";
+        let result = block_on(collect(AsyncTurtleReader::new(
+            input,
+            TurtleParser::new(TurtleSyntax::NTriples),
+        )));
+        assert!(matches!(result, Err(ParseError::Syntax(_))));
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::BTreeSet;
+
+use crate::rs::dataset::Dataset;
+use crate::rs::pattern::{Bindings, match_quad_with, substitute};
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term_like::TermLike;
+
+/// Evaluates a basic graph pattern, i.e. the conjunction of `patterns`, and
+/// yields one [`Bindings`] per solution.
+///
+/// Patterns are joined most selective first: the next pattern is always one
+/// sharing a variable with those already joined (if any) with the fewest
+/// matches for its constant terms.
+pub fn evaluate_bgp<'a>(dataset: &'a Dataset, patterns: &[Quad]) -> BgpSolutions<'a> {
+    evaluate_bgp_with(dataset, patterns, Bindings::new())
+}
+
+/// Like [`evaluate_bgp`], starting from (and extending) `bindings`.
+pub fn evaluate_bgp_with<'a>(
+    dataset: &'a Dataset,
+    patterns: &[Quad],
+    bindings: Bindings,
+) -> BgpSolutions<'a> {
+    let patterns = order_by_selectivity(dataset, patterns, &bindings);
+
+    BgpSolutions {
+        dataset,
+        patterns,
+        stack: vec![vec![bindings].into_iter()],
+    }
+}
+
+pub struct BgpSolutions<'a> {
+    dataset: &'a Dataset,
+    patterns: Vec<Quad>,
+    stack: Vec<std::vec::IntoIter<Bindings>>,
+}
+
+impl Iterator for BgpSolutions<'_> {
+    type Item = Bindings;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let depth = self.stack.len() - 1;
+            let Some(bindings) = self.stack.last_mut()?.next() else {
+                self.stack.pop();
+                if self.stack.is_empty() {
+                    return None;
+                }
+                continue;
+            };
+
+            if depth == self.patterns.len() {
+                return Some(bindings);
+            }
+
+            let extensions = extend(self.dataset, &self.patterns[depth], &bindings);
+            self.stack.push(extensions.into_iter());
+        }
+    }
+}
+
+fn extend(dataset: &Dataset, pattern: &Quad, bindings: &Bindings) -> Vec<Bindings> {
+    let bound = substitute(pattern, bindings);
+    let (subject, predicate, object, graph) = constants(&bound);
+
+    dataset
+        .quads_for_pattern(subject, predicate, object, graph)
+        .filter_map(|quad| match_quad_with(&bound, &quad, bindings))
+        .collect()
+}
+
+fn constants(
+    pattern: &Quad,
+) -> (
+    Option<&QuadSubject>,
+    Option<&QuadPredicate>,
+    Option<&QuadObject>,
+    Option<&QuadGraph>,
+) {
+    let subject = match pattern.subject() {
+        QuadSubject::Variable(_) => None,
+        QuadSubject::Quad(quad) if has_variables(quad) => None,
+        subject => Some(subject),
+    };
+    let predicate = match pattern.predicate() {
+        QuadPredicate::Variable(_) => None,
+        predicate => Some(predicate),
+    };
+    let object = match pattern.object() {
+        QuadObject::Variable(_) => None,
+        object => Some(object),
+    };
+    let graph = match pattern.graph() {
+        QuadGraph::Variable(_) => None,
+        graph => Some(graph),
+    };
+
+    (subject, predicate, object, graph)
+}
+
+fn has_variables(pattern: &Quad) -> bool {
+    !variables(pattern).is_empty()
+}
+
+/// Names of the variables occurring in `pattern`, including nested triple terms.
+pub fn variables(pattern: &Quad) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    collect_variables(pattern, &mut names);
+    names
+}
+
+fn collect_variables(pattern: &Quad, names: &mut BTreeSet<String>) {
+    match pattern.subject() {
+        QuadSubject::Variable(variable) => {
+            names.insert(variable.value().to_owned());
+        }
+        QuadSubject::Quad(quad) => collect_variables(quad, names),
+        _ => {}
+    }
+    if let QuadPredicate::Variable(variable) = pattern.predicate() {
+        names.insert(variable.value().to_owned());
+    }
+    if let QuadObject::Variable(variable) = pattern.object() {
+        names.insert(variable.value().to_owned());
+    }
+    if let QuadGraph::Variable(variable) = pattern.graph() {
+        names.insert(variable.value().to_owned());
+    }
+}
+
+fn order_by_selectivity(dataset: &Dataset, patterns: &[Quad], bindings: &Bindings) -> Vec<Quad> {
+    let mut remaining: Vec<(Quad, usize, BTreeSet<String>)> = patterns
+        .iter()
+        .map(|pattern| {
+            let bound = substitute(pattern, bindings);
+            let (subject, predicate, object, graph) = constants(&bound);
+            let count = dataset.count_for_pattern(subject, predicate, object, graph);
+            (pattern.clone(), count, variables(&bound))
+        })
+        .collect();
+
+    let mut joined: BTreeSet<String> = bindings.variables().map(|v| v.value().to_owned()).collect();
+    let mut ordered = Vec::with_capacity(remaining.len());
+
+    while !remaining.is_empty() {
+        let next = remaining
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (_, count, names))| {
+                let connected = joined.is_empty() || !names.is_disjoint(&joined);
+                (!connected, *count)
+            })
+            .map(|(i, _)| i)
+            .unwrap();
+
+        let (pattern, _, names) = remaining.remove(next);
+        joined.extend(names);
+        ordered.push(pattern);
+    }
+
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::named_node::NamedNode;
+    use crate::rs::term::Term;
+    use crate::rs::variable::Variable;
+
+    fn nn(value: &str) -> NamedNode {
+        NamedNode::new(value)
+    }
+
+    fn quad(s: &str, p: &str, o: &str) -> Quad {
+        Quad::new(
+            &QuadSubject::NamedNode(nn(s)),
+            &QuadPredicate::NamedNode(nn(p)),
+            &QuadObject::NamedNode(nn(o)),
+            None,
+        )
+    }
+
+    fn pattern(s: &str, p: &str, o: &str) -> Quad {
+        let subject = match s.strip_prefix('?') {
+            Some(name) => QuadSubject::Variable(Variable::new(name)),
+            None => QuadSubject::NamedNode(nn(s)),
+        };
+        let object = match o.strip_prefix('?') {
+            Some(name) => QuadObject::Variable(Variable::new(name)),
+            None => QuadObject::NamedNode(nn(o)),
+        };
+        Quad::new(&subject, &QuadPredicate::NamedNode(nn(p)), &object, None)
+    }
+
+    fn sample() -> Dataset {
+        let mut quads = vec![
+            quad("alice", "knows", "bob"),
+            quad("bob", "knows", "carol"),
+            quad("carol", "knows", "alice"),
+            quad("alice", "type", "Person"),
+            quad("bob", "type", "Person"),
+        ];
+        for i in 0..20 {
+            quads.push(quad(&format!("thing{i}"), "type", "Thing"));
+        }
+        quads.into_iter().collect()
+    }
+
+    fn names(solutions: impl Iterator<Item = Bindings>, variable: &str) -> BTreeSet<String> {
+        solutions
+            .map(|bindings| bindings.get(variable).unwrap().value().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn conjunctive_patterns_join() {
+        let dataset = sample();
+
+        let solutions = evaluate_bgp(
+            &dataset,
+            &[
+                pattern("?x", "knows", "?y"),
+                pattern("?y", "type", "Person"),
+            ],
+        );
+
+        assert_eq!(
+            names(solutions, "x"),
+            BTreeSet::from(["alice".to_owned(), "carol".to_owned()])
+        );
+    }
+
+    #[test]
+    fn cycles_bind_consistently() {
+        let dataset = sample();
+
+        let solutions: Vec<Bindings> = evaluate_bgp(
+            &dataset,
+            &[
+                pattern("?a", "knows", "?b"),
+                pattern("?b", "knows", "?c"),
+                pattern("?c", "knows", "?a"),
+            ],
+        )
+        .collect();
+
+        assert_eq!(solutions.len(), 3);
+        assert!(solutions.iter().all(|s| s.len() == 3));
+    }
+
+    #[test]
+    fn empty_patterns_yield_one_empty_solution() {
+        let dataset = sample();
+
+        let solutions: Vec<Bindings> = evaluate_bgp(&dataset, &[]).collect();
+
+        assert_eq!(solutions, vec![Bindings::new()]);
+        assert_eq!(
+            evaluate_bgp(&dataset, &[pattern("?x", "knows", "nobody")]).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn initial_bindings_restrict_solutions() {
+        let dataset = sample();
+        let bindings: Bindings = [(Variable::new("x"), Term::NamedNode(nn("bob")))]
+            .into_iter()
+            .collect();
+
+        let solutions = evaluate_bgp_with(&dataset, &[pattern("?x", "knows", "?y")], bindings);
+
+        assert_eq!(names(solutions, "y"), BTreeSet::from(["carol".to_owned()]));
+    }
+
+    #[test]
+    fn selective_patterns_are_joined_first() {
+        let dataset = sample();
+
+        let ordered = order_by_selectivity(
+            &dataset,
+            &[
+                pattern("?x", "type", "?t"),
+                pattern("?y", "unrelated", "?z"),
+                pattern("?x", "knows", "bob"),
+            ],
+            &Bindings::new(),
+        );
+
+        assert_eq!(
+            ordered,
+            vec![
+                pattern("?y", "unrelated", "?z"),
+                pattern("?x", "knows", "bob"),
+                pattern("?x", "type", "?t"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,731 @@
+//! A compact binary encoding of quad sequences, for caching parsed data.
+//!
+//! A file is the magic `RDFB`, a version byte (currently 1) and a flags byte
+//! (bit 0: blocks are zstd frames), followed by blocks. Each block is its
+//! LEB128 length and payload; an empty block ends the file. A payload is the
+//! number of quads, then each quad as four term references (subject,
+//! predicate, object, graph).
+//!
+//! Terms are numbered from 1 in the order they are defined within a block. A
+//! reference is either such a number or 0 followed by the definition of a new
+//! term: a tag byte then its fields, where strings are a LEB128 length and
+//! UTF-8 bytes:
+//!
+//! | tag | term | fields |
+//! |-----|------|--------|
+//! | 0 | default graph | |
+//! | 1 | IRI | IRI |
+//! | 2 | blank node | label |
+//! | 3 | `xsd:string` literal | value |
+//! | 4 | language-tagged literal | value, language |
+//! | 5 | directional literal | value, language, direction (0 `ltr`, 1 `rtl`) |
+//! | 6 | typed literal | value, datatype reference |
+//! | 7 | variable | name |
+//! | 8 | triple term | subject, predicate and object references |
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::io::{self, Read, Write};
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::default_graph::DefaultGraph;
+use crate::rs::literal::{LanguageDirection, Literal};
+use crate::rs::named_node::NamedNode;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::variable::Variable;
+use crate::rs::vocab::xsd;
+
+const MAGIC: &[u8] = b"RDFB";
+const VERSION: u8 = 1;
+const ZSTD: u8 = 1;
+/// Bounds the memory a corrupted or hostile file can make readers allocate.
+const MAX_BLOCK_SIZE: u64 = 64 << 20;
+/// The largest payload written, leaving room for zstd to expand
+/// incompressible blocks without going over [`MAX_BLOCK_SIZE`].
+const MAX_PAYLOAD_SIZE: usize = (MAX_BLOCK_SIZE - (MAX_BLOCK_SIZE >> 7)) as usize;
+const MAX_NESTING: usize = 64;
+
+#[derive(Debug)]
+pub enum BinaryError {
+    Io(io::Error),
+    /// The input is not in the binary format, or not in a supported version.
+    Format(String),
+}
+
+impl Display for BinaryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinaryError::Io(error) => write!(f, "{error}"),
+            BinaryError::Format(message) => write!(f, "invalid binary quads: {message}"),
+        }
+    }
+}
+
+impl Error for BinaryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BinaryError::Io(error) => Some(error),
+            BinaryError::Format(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for BinaryError {
+    fn from(error: io::Error) -> Self {
+        BinaryError::Io(error)
+    }
+}
+
+fn format_error<T>(message: &str) -> Result<T, BinaryError> {
+    Err(BinaryError::Format(message.to_owned()))
+}
+
+/// Writes quads in the binary format, by blocks of up to
+/// [`BinarySerializer::with_block_size`] quads, and smaller when needed to
+/// stay readable. A quad too large for a block on its own fails with
+/// [`io::ErrorKind::InvalidInput`].
+#[derive(Clone)]
+pub struct BinarySerializer {
+    block_size: usize,
+    compression_level: Option<i32>,
+}
+
+impl Default for BinarySerializer {
+    fn default() -> Self {
+        Self {
+            block_size: 4096,
+            compression_level: None,
+        }
+    }
+}
+
+impl BinarySerializer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Larger blocks repeat fewer terms but take more memory to read.
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Compresses each block with zstd at `level`.
+    #[cfg(feature = "zstd")]
+    pub fn with_zstd(mut self, level: i32) -> Self {
+        self.compression_level = Some(level);
+        self
+    }
+
+    pub fn serialize<'a>(
+        &self,
+        mut writer: impl Write,
+        quads: impl IntoIterator<Item = &'a Quad>,
+    ) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        let flags = if self.compression_level.is_some() {
+            ZSTD
+        } else {
+            0
+        };
+        writer.write_all(&[VERSION, flags])?;
+
+        let mut block = BlockEncoder::default();
+        for quad in quads {
+            let (length, terms) = (block.output.len(), block.ids.len());
+            block.quad(quad);
+            if block.payload_size() > MAX_PAYLOAD_SIZE && block.count > 1 {
+                block.truncate(length, terms);
+                self.write_block(&mut writer, &mut block)?;
+                block.quad(quad);
+            }
+            if block.payload_size() > MAX_PAYLOAD_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "a quad is too large for a binary block",
+                ));
+            }
+            if block.count == self.block_size {
+                self.write_block(&mut writer, &mut block)?;
+            }
+        }
+        if block.count > 0 {
+            self.write_block(&mut writer, &mut block)?;
+        }
+        writer.write_all(&[0])?;
+        writer.flush()
+    }
+
+    fn write_block(&self, writer: &mut impl Write, block: &mut BlockEncoder) -> io::Result<()> {
+        let mut payload = Vec::with_capacity(block.output.len() + 4);
+        write_varint(&mut payload, block.count as u64);
+        payload.extend_from_slice(&block.output);
+        *block = BlockEncoder::default();
+
+        let payload = match self.compression_level {
+            #[cfg(feature = "zstd")]
+            Some(level) => zstd::bulk::compress(&payload, level)?,
+            _ => payload,
+        };
+        let mut length = Vec::new();
+        write_varint(&mut length, payload.len() as u64);
+        writer.write_all(&length)?;
+        writer.write_all(&payload)
+    }
+}
+
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn write_string(output: &mut Vec<u8>, value: &str) {
+    write_varint(output, value.len() as u64);
+    output.extend_from_slice(value.as_bytes());
+}
+
+#[derive(Default)]
+struct BlockEncoder {
+    ids: HashMap<Term, u64>,
+    count: usize,
+    output: Vec<u8>,
+}
+
+impl BlockEncoder {
+    /// The size of the block payload, with its quad count.
+    fn payload_size(&self) -> usize {
+        let mut count = Vec::new();
+        write_varint(&mut count, self.count as u64);
+        count.len() + self.output.len()
+    }
+
+    /// Drops the last quad, given the output length and number of terms
+    /// before it.
+    fn truncate(&mut self, length: usize, terms: usize) {
+        self.output.truncate(length);
+        self.ids.retain(|_, id| *id <= terms as u64);
+        self.count -= 1;
+    }
+
+    fn quad(&mut self, quad: &Quad) {
+        self.term(&quad.subject().to_term());
+        self.term(&quad.predicate().to_term());
+        self.term(&quad.object().to_term());
+        self.term(&quad.graph().to_term());
+        self.count += 1;
+    }
+
+    fn term(&mut self, term: &Term) {
+        if let Some(id) = self.ids.get(term) {
+            write_varint(&mut self.output, *id);
+            return;
+        }
+        self.output.push(0);
+        match term {
+            Term::DefaultGraph(_) => self.output.push(0),
+            Term::NamedNode(node) => {
+                self.output.push(1);
+                write_string(&mut self.output, node.value());
+            }
+            Term::BlankNode(node) => {
+                self.output.push(2);
+                write_string(&mut self.output, node.value());
+            }
+            Term::Literal(literal) if !literal.language().is_empty() => {
+                let direction = literal.direction();
+                self.output.push(if direction.is_some() { 5 } else { 4 });
+                write_string(&mut self.output, literal.value());
+                write_string(&mut self.output, literal.language());
+                if let Some(direction) = direction {
+                    self.output
+                        .push(u8::from(*direction == LanguageDirection::RightToLeft));
+                }
+            }
+            Term::Literal(literal) if *literal.datatype() == *xsd::string => {
+                self.output.push(3);
+                write_string(&mut self.output, literal.value());
+            }
+            Term::Literal(literal) => {
+                self.output.push(6);
+                write_string(&mut self.output, literal.value());
+                self.term(&literal.datatype().to_term());
+            }
+            Term::Variable(variable) => {
+                self.output.push(7);
+                write_string(&mut self.output, variable.value());
+            }
+            Term::Quad(triple) => {
+                self.output.push(8);
+                self.term(&triple.subject().to_term());
+                self.term(&triple.predicate().to_term());
+                self.term(&triple.object().to_term());
+            }
+        }
+        // Components are numbered before the term containing them, as the
+        // reader defines them first.
+        let id = self.ids.len() as u64 + 1;
+        self.ids.insert(term.clone(), id);
+    }
+}
+
+/// Iterator over the quads of a binary file read from `R`, one block in
+/// memory at a time.
+pub struct BinaryReader<R> {
+    reader: R,
+    compressed: bool,
+    block: Option<BlockDecoder>,
+    done: bool,
+}
+
+impl<R: Read> BinaryReader<R> {
+    /// Reads the file header.
+    pub fn new(mut reader: R) -> Result<Self, BinaryError> {
+        let mut header = [0; 6];
+        reader
+            .read_exact(&mut header)
+            .map_err(|error| match error.kind() {
+                io::ErrorKind::UnexpectedEof => BinaryError::Format("missing header".to_owned()),
+                _ => error.into(),
+            })?;
+        if &header[..4] != MAGIC {
+            return format_error("not a binary quads file");
+        }
+        if header[4] != VERSION {
+            return format_error(&format!("unsupported version {}", header[4]));
+        }
+        if header[5] & !ZSTD != 0 {
+            return format_error(&format!("unknown flags {:#04x}", header[5]));
+        }
+        if header[5] & ZSTD != 0 && !cfg!(feature = "zstd") {
+            return format_error("the file is zstd-compressed but the zstd feature is disabled");
+        }
+        Ok(Self {
+            reader,
+            compressed: header[5] & ZSTD != 0,
+            block: None,
+            done: false,
+        })
+    }
+
+    fn read_varint(&mut self) -> Result<u64, BinaryError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let mut byte = [0];
+            self.reader.read_exact(&mut byte)?;
+            value |= u64::from(byte[0] & 0x7f) << shift;
+            if byte[0] & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        format_error("invalid variable-length integer")
+    }
+
+    /// Reads the next block, or `None` at the end marker.
+    fn read_block(&mut self) -> Result<Option<BlockDecoder>, BinaryError> {
+        let length = self.read_varint()?;
+        if length == 0 {
+            return Ok(None);
+        }
+        if length > MAX_BLOCK_SIZE {
+            return format_error("block too large");
+        }
+        let mut data = Vec::new();
+        (&mut self.reader).take(length).read_to_end(&mut data)?;
+        if data.len() as u64 != length {
+            return format_error("truncated block");
+        }
+        if self.compressed {
+            data = decompress(&data)?;
+        }
+
+        let mut block = BlockDecoder {
+            data,
+            position: 0,
+            terms: Vec::new(),
+            remaining: 0,
+        };
+        block.remaining = block.varint()?;
+        Ok(Some(block))
+    }
+
+    fn read_next(&mut self) -> Result<Option<Quad>, BinaryError> {
+        loop {
+            if let Some(block) = &mut self.block {
+                if block.remaining > 0 {
+                    block.remaining -= 1;
+                    return block.quad().map(Some);
+                }
+                if block.position != block.data.len() {
+                    return format_error("unexpected data after the last quad of a block");
+                }
+            }
+            self.block = self.read_block()?;
+            if self.block.is_none() {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn decompress(data: &[u8]) -> Result<Vec<u8>, BinaryError> {
+    let mut output = Vec::new();
+    zstd::stream::read::Decoder::new(data)?
+        .take(MAX_BLOCK_SIZE + 1)
+        .read_to_end(&mut output)
+        .map_err(|error| BinaryError::Format(format!("invalid zstd block: {error}")))?;
+    if output.len() as u64 > MAX_BLOCK_SIZE {
+        return format_error("block too large");
+    }
+    Ok(output)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress(_: &[u8]) -> Result<Vec<u8>, BinaryError> {
+    unreachable!("compressed files are rejected by BinaryReader::new")
+}
+
+impl<R: Read> Iterator for BinaryReader<R> {
+    type Item = Result<Quad, BinaryError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_next().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result.map(|result| {
+            result.map_err(|error| match error {
+                BinaryError::Io(error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                    BinaryError::Format("unexpected end of file".to_owned())
+                }
+                error => error,
+            })
+        })
+    }
+}
+
+struct BlockDecoder {
+    data: Vec<u8>,
+    position: usize,
+    terms: Vec<Term>,
+    remaining: u64,
+}
+
+impl BlockDecoder {
+    fn byte(&mut self) -> Result<u8, BinaryError> {
+        let Some(byte) = self.data.get(self.position) else {
+            return format_error("truncated block");
+        };
+        self.position += 1;
+        Ok(*byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, BinaryError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        format_error("invalid variable-length integer")
+    }
+
+    fn string(&mut self) -> Result<String, BinaryError> {
+        let length = self.varint()?;
+        let end = usize::try_from(length)
+            .ok()
+            .and_then(|length| self.position.checked_add(length))
+            .filter(|end| *end <= self.data.len());
+        let Some(end) = end else {
+            return format_error("truncated string");
+        };
+        let Ok(value) = std::str::from_utf8(&self.data[self.position..end]) else {
+            return format_error("invalid UTF-8");
+        };
+        self.position = end;
+        Ok(value.to_owned())
+    }
+
+    fn quad(&mut self) -> Result<Quad, BinaryError> {
+        let subject = self.term(0)?;
+        let predicate = self.term(0)?;
+        let object = self.term(0)?;
+        let graph = self.term(0)?;
+        quad(subject, predicate, object, Some(graph))
+    }
+
+    fn term(&mut self, depth: usize) -> Result<Term, BinaryError> {
+        let id = self.varint()?;
+        if id > 0 {
+            return match self.terms.get(id as usize - 1) {
+                Some(term) => Ok(term.clone()),
+                None => format_error(&format!("undefined term {id}")),
+            };
+        }
+        if depth > MAX_NESTING {
+            return format_error("triple terms are nested too deeply");
+        }
+
+        let term = match self.byte()? {
+            0 => DefaultGraph::new().to_term(),
+            1 => NamedNode::new(&self.string()?).to_term(),
+            2 => BlankNode::new(&self.string()?).to_term(),
+            3 => Literal::new(&self.string()?, None, None, None).to_term(),
+            4 => {
+                let value = self.string()?;
+                language_literal(&value, &self.string()?, None)?
+            }
+            5 => {
+                let value = self.string()?;
+                let language = self.string()?;
+                let direction = match self.byte()? {
+                    0 => LanguageDirection::LeftToRight,
+                    1 => LanguageDirection::RightToLeft,
+                    _ => return format_error("invalid direction"),
+                };
+                language_literal(&value, &language, Some(&direction))?
+            }
+            6 => {
+                let value = self.string()?;
+                let Term::NamedNode(datatype) = self.term(depth + 1)? else {
+                    return format_error("a datatype must be an IRI");
+                };
+                Literal::new(&value, None, None, Some(&datatype)).to_term()
+            }
+            7 => Variable::new(&self.string()?).to_term(),
+            8 => {
+                let subject = self.term(depth + 1)?;
+                let predicate = self.term(depth + 1)?;
+                let object = self.term(depth + 1)?;
+                Term::Quad(Box::new(quad(subject, predicate, object, None)?))
+            }
+            tag => return format_error(&format!("unknown term tag {tag}")),
+        };
+        self.terms.push(term.clone());
+        Ok(term)
+    }
+}
+
+fn quad(
+    subject: Term,
+    predicate: Term,
+    object: Term,
+    graph: Option<Term>,
+) -> Result<Quad, BinaryError> {
+    let invalid = |position: &str| BinaryError::Format(format!("invalid {position}"));
+    let subject = QuadSubject::try_from(subject).map_err(|_| invalid("subject"))?;
+    let predicate = QuadPredicate::try_from(predicate).map_err(|_| invalid("predicate"))?;
+    let object = QuadObject::try_from(object).map_err(|_| invalid("object"))?;
+    let graph = graph
+        .map(QuadGraph::try_from)
+        .transpose()
+        .map_err(|_| invalid("graph"))?;
+    Ok(Quad::new(&subject, &predicate, &object, graph.as_ref()))
+}
+
+/// A literal of tag 4 or 5, whose language cannot be empty as it would not
+/// be written back with the same tag.
+fn language_literal(
+    value: &str,
+    language: &str,
+    direction: Option<&LanguageDirection>,
+) -> Result<Term, BinaryError> {
+    if language.is_empty() {
+        return format_error("a language tag cannot be empty");
+    }
+    Literal::try_new(value, Some(language), direction, None)
+        .map(|literal| literal.to_term())
+        .map_err(|error| BinaryError::Format(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::turtle::{TurtleSyntax, parse_str};
+
+    const INPUT: &str = "@prefix ex: <http://example.org/> .\n\
+                         ex:a ex:p \"caf\u{e9}\"@fr--rtl, \"1\"^^ex:t, \"x\"@en, \"y\", _:b .\n\
+                         << << ex:a ex:p ex:b >> ex:q ex:c >> ex:r ex:a .\n\
+                         ex:g { ex:a ex:p ex:b . }\n";
+
+    /// Version 1 encoding of two quads. Decoding it must keep working in
+    /// later versions.
+    const VERSION_1: &[u8] = &[
+        b'R', b'D', b'F', b'B', 1, 0, // header
+        37, 2, // block of 37 bytes with 2 quads
+        0, 1, 3, b'h', b't', b'p', // <htp>
+        1,    // <htp> again
+        0, 5, 2, b'o', b'k', 1, b'a', 1, // "ok"@a--rtl
+        0, 0, // default graph
+        0, 2, 1, b'b', // _:b
+        1,    // <htp>
+        0, 6, 1, b'1', 0, 1, 3, b'd', b't', b'y', // "1"^^<dty>
+        0, 1, 1, b'g', // <g>
+        0,    // end
+    ];
+
+    #[test]
+    fn quads_round_trip() {
+        let quads = parse_str(TurtleSyntax::TriG, INPUT, None).unwrap();
+        for block_size in [1, 2, 4096] {
+            let mut output = Vec::new();
+            BinarySerializer::new()
+                .with_block_size(block_size)
+                .serialize(&mut output, &quads)
+                .unwrap();
+            let decoded: Vec<Quad> = BinaryReader::new(output.as_slice())
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(decoded, quads);
+        }
+
+        let mut output = Vec::new();
+        BinarySerializer::new()
+            .serialize(&mut output, &quads)
+            .unwrap();
+        output.truncate(output.len() - 3);
+        let error = BinaryReader::new(output.as_slice())
+            .unwrap()
+            .find_map(Result::err)
+            .unwrap();
+        assert!(matches!(error, BinaryError::Format(_)), "{error}");
+    }
+
+    #[test]
+    fn empty_languages_are_rejected() {
+        // "ok"@"" with tag 4, then "ok"@""--rtl with tag 5.
+        for (tag, direction) in [(4, None), (5, Some(1))] {
+            let mut payload = Vec::new();
+            write_varint(&mut payload, 1); // quad count
+            for iri in ["s", "p"] {
+                payload.extend_from_slice(&[0, 1]); // new term, IRI
+                write_string(&mut payload, iri);
+            }
+            payload.extend_from_slice(&[0, tag]); // new term, language literal
+            write_string(&mut payload, "ok"); // value
+            write_string(&mut payload, ""); // language
+            payload.extend(direction);
+            payload.extend_from_slice(&[0, 0]); // new term, default graph
+
+            let mut input = [MAGIC, &[VERSION, 0]].concat(); // uncompressed
+            write_varint(&mut input, payload.len() as u64);
+            input.extend_from_slice(&payload);
+            input.push(0); // end block
+
+            let error = BinaryReader::new(input.as_slice())
+                .unwrap()
+                .find_map(Result::err)
+                .unwrap();
+            assert_eq!(
+                error.to_string(),
+                "invalid binary quads: a language tag cannot be empty"
+            );
+        }
+    }
+
+    #[test]
+    fn version_1_files_stay_readable() {
+        let htp = QuadSubject::NamedNode(NamedNode::new("htp"));
+        let quads = vec![
+            Quad::new(
+                &htp,
+                &QuadPredicate::NamedNode(NamedNode::new("htp")),
+                &QuadObject::Literal(Literal::new(
+                    "ok",
+                    Some("a"),
+                    Some(&LanguageDirection::RightToLeft),
+                    None,
+                )),
+                None,
+            ),
+            Quad::new(
+                &QuadSubject::BlankNode(BlankNode::new("b")),
+                &QuadPredicate::NamedNode(NamedNode::new("htp")),
+                &QuadObject::Literal(Literal::new("1", None, None, Some(&NamedNode::new("dty")))),
+                Some(&QuadGraph::NamedNode(NamedNode::new("g"))),
+            ),
+        ];
+
+        let decoded: Vec<Quad> = BinaryReader::new(VERSION_1)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(decoded, quads);
+
+        let mut output = Vec::new();
+        BinarySerializer::new()
+            .serialize(&mut output, &quads)
+            .unwrap();
+        assert_eq!(output, VERSION_1);
+
+        let mut newer = VERSION_1.to_vec();
+        newer[4] = 2;
+        let error = BinaryReader::new(newer.as_slice()).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "invalid binary quads: unsupported version 2"
+        );
+    }
+
+    #[test]
+    fn large_literals_split_blocks() {
+        let subject = QuadSubject::NamedNode(NamedNode::new("http://example.org/s"));
+        let predicate = QuadPredicate::NamedNode(NamedNode::new("http://example.org/p"));
+        let literal = |size: usize, c: char| {
+            QuadObject::Literal(Literal::new(&c.to_string().repeat(size), None, None, None))
+        };
+        let quads: Vec<Quad> = ['a', 'b', 'c', 'd']
+            .into_iter()
+            .map(|c| Quad::new(&subject, &predicate, &literal(24 << 20, c), None))
+            .collect();
+
+        let mut output = Vec::new();
+        BinarySerializer::new()
+            .serialize(&mut output, &quads)
+            .unwrap();
+        let decoded: Vec<Quad> = BinaryReader::new(output.as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(decoded == quads);
+
+        let huge = Quad::new(&subject, &predicate, &literal(MAX_PAYLOAD_SIZE, 'e'), None);
+        let error = BinarySerializer::new()
+            .serialize(Vec::new(), [&huge])
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn compressed_quads_round_trip() {
+        let quads = parse_str(TurtleSyntax::TriG, INPUT, None).unwrap();
+        let mut output = Vec::new();
+        BinarySerializer::new()
+            .with_zstd(3)
+            .serialize(&mut output, &quads)
+            .unwrap();
+        assert_eq!(output[5], ZSTD);
+        let decoded: Vec<Quad> = BinaryReader::new(output.as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(decoded, quads);
+    }
+}
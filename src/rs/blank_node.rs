@@ -1,3 +1,6 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
 use crate::rs::{term::Term, term_like::TermLike};
 
 #[derive(Clone, Eq, Debug)]
@@ -13,6 +16,99 @@ impl BlankNode {
     }
 }
 
+/// Labels the blank nodes of a parsed document, keeping document labels.
+///
+/// Generated nodes are `genid1`, `genid2`..., skipping the labels of that
+/// form the document used so far. A document label only seen after a node
+/// was generated with it gets a fresh label instead, as that node may
+/// already have been handed out.
+///
+/// Labels are only tracked when they have the generated form, so memory
+/// does not grow with the document.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BlankNodeLabeler {
+    counter: u64,
+    /// The `n` of document labels `genid{n}`.
+    seen: HashSet<u64>,
+    /// Fresh labels of document labels that met a generated one.
+    renamed: HashMap<String, BlankNode>,
+    /// Changes since [`BlankNodeLabeler::commit`], undone by
+    /// [`BlankNodeLabeler::rollback`].
+    committed_counter: u64,
+    journal: Vec<Change>,
+}
+
+#[derive(Clone, Debug)]
+enum Change {
+    Seen(u64),
+    Renamed(String),
+}
+
+impl BlankNodeLabeler {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn fresh(&mut self) -> BlankNode {
+        self.counter += 1;
+        while self.seen.contains(&self.counter) {
+            self.counter += 1;
+        }
+        BlankNode::new(&format!("genid{}", self.counter))
+    }
+
+    /// The node labeled `label` in the document.
+    pub(crate) fn labeled(&mut self, label: &str) -> BlankNode {
+        if let Some(node) = self.renamed.get(label) {
+            return node.clone();
+        }
+        let Some(n) = generated_number(label) else {
+            return BlankNode::new(label);
+        };
+        if n <= self.counter && !self.seen.contains(&n) {
+            let node = self.fresh();
+            self.renamed.insert(label.to_owned(), node.clone());
+            self.journal.push(Change::Renamed(label.to_owned()));
+            return node;
+        }
+        if self.seen.insert(n) {
+            self.journal.push(Change::Seen(n));
+        }
+        BlankNode::new(label)
+    }
+
+    /// Keeps the changes made so far.
+    pub(crate) fn commit(&mut self) {
+        self.committed_counter = self.counter;
+        self.journal.clear();
+    }
+
+    /// Undoes the changes since the last commit, e.g. for a statement that
+    /// will be parsed again.
+    pub(crate) fn rollback(&mut self) {
+        self.counter = self.committed_counter;
+        for change in self.journal.drain(..) {
+            match change {
+                Change::Seen(n) => {
+                    self.seen.remove(&n);
+                }
+                Change::Renamed(label) => {
+                    self.renamed.remove(&label);
+                }
+            }
+        }
+    }
+}
+
+/// The `n` of a label `genid{n}` as [`BlankNodeLabeler::fresh`] writes it.
+fn generated_number(label: &str) -> Option<u64> {
+    let digits = label.strip_prefix("genid")?;
+    if digits.starts_with('0') || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
 impl PartialEq for BlankNode {
     fn eq(&self, other: &Self) -> bool {
         self.value() == other.value()
@@ -23,6 +119,12 @@ impl PartialEq for BlankNode {
     }
 }
 
+impl Hash for BlankNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value().hash(state);
+    }
+}
+
 impl PartialEq<Term> for BlankNode {
     fn eq(&self, other: &Term) -> bool {
         match other {
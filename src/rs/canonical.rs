@@ -0,0 +1,502 @@
+//! RDF Dataset Canonicalization ([RDFC-1.0](https://www.w3.org/TR/rdf-canon/))
+//! with SHA-256.
+//!
+//! Blank nodes inside triple terms are hashed like those of the quad
+//! containing the triple term, in the same position.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{Display, Write};
+
+use sha2::{Digest, Sha256};
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::literal::Literal;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::turtle::format_iri;
+use crate::rs::vocab::xsd;
+
+/// Bounds the permutations tried for blank nodes that first-degree hashes
+/// cannot tell apart, which grow factorially in crafted datasets.
+const MAX_WORK: usize = 100_000;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CanonicalizationError {
+    /// The dataset has too many blank nodes that only differ by their
+    /// position in a symmetric structure.
+    TooComplex,
+}
+
+impl Display for CanonicalizationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CanonicalizationError::TooComplex => {
+                write!(f, "the dataset is too complex to canonicalize")
+            }
+        }
+    }
+}
+
+impl Error for CanonicalizationError {}
+
+/// Maps the label of each blank node of `quads` to its canonical label,
+/// `c14n0`, `c14n1`...
+pub fn canonical_labels<'a>(
+    quads: impl IntoIterator<Item = &'a Quad>,
+) -> Result<HashMap<String, String>, CanonicalizationError> {
+    let quads: Vec<&Quad> = quads
+        .into_iter()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    Canonicalizer::new(&quads).run()
+}
+
+/// The quads of `quads` without duplicates, with canonical blank node
+/// labels, sorted by their canonical N-Quads form.
+pub fn canonicalize<'a>(
+    quads: impl IntoIterator<Item = &'a Quad>,
+) -> Result<Vec<Quad>, CanonicalizationError> {
+    let quads: Vec<&Quad> = quads.into_iter().collect();
+    let labels = canonical_labels(quads.iter().copied())?;
+    let mut lines: BTreeMap<String, Quad> = BTreeMap::new();
+    for quad in quads {
+        let line = nquad(quad, &|label| &labels[label]);
+        lines.insert(line, relabel_quad(quad, &labels));
+    }
+    Ok(lines.into_values().collect())
+}
+
+/// The canonical N-Quads document of `quads`, one sorted line per quad.
+pub fn canonical_nquads<'a>(
+    quads: impl IntoIterator<Item = &'a Quad>,
+) -> Result<String, CanonicalizationError> {
+    let quads: Vec<&Quad> = quads.into_iter().collect();
+    let labels = canonical_labels(quads.iter().copied())?;
+    let lines: BTreeSet<String> = quads
+        .into_iter()
+        .map(|quad| nquad(quad, &|label| &labels[label]))
+        .collect();
+    Ok(lines.into_iter().collect())
+}
+
+#[derive(Clone)]
+struct IdentifierIssuer {
+    prefix: &'static str,
+    issued: HashMap<String, String>,
+    order: Vec<String>,
+}
+
+impl IdentifierIssuer {
+    fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix,
+            issued: HashMap::new(),
+            order: Vec::new(),
+        }
+    }
+
+    fn issue(&mut self, label: &str) -> String {
+        if let Some(issued) = self.issued.get(label) {
+            return issued.clone();
+        }
+        let issued = format!("{}{}", self.prefix, self.order.len());
+        self.issued.insert(label.to_owned(), issued.clone());
+        self.order.push(label.to_owned());
+        issued
+    }
+}
+
+struct Canonicalizer<'a> {
+    /// The quads mentioning each blank node.
+    blank_node_quads: HashMap<String, Vec<&'a Quad>>,
+    first_degree_hashes: HashMap<String, String>,
+    canonical: IdentifierIssuer,
+    work: usize,
+}
+
+impl<'a> Canonicalizer<'a> {
+    fn new(quads: &[&'a Quad]) -> Self {
+        let mut blank_node_quads: HashMap<String, Vec<&Quad>> = HashMap::new();
+        for quad in quads {
+            let mut labels = Vec::new();
+            for term in quad_terms(quad) {
+                blank_nodes(&term, &mut labels);
+            }
+            labels.sort();
+            labels.dedup();
+            for label in labels {
+                blank_node_quads.entry(label).or_default().push(quad);
+            }
+        }
+        Self {
+            blank_node_quads,
+            first_degree_hashes: HashMap::new(),
+            canonical: IdentifierIssuer::new("c14n"),
+            work: 0,
+        }
+    }
+
+    fn run(mut self) -> Result<HashMap<String, String>, CanonicalizationError> {
+        let mut by_hash: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut labels: Vec<&String> = self.blank_node_quads.keys().collect();
+        labels.sort();
+        for label in labels {
+            let hash = self.hash_first_degree(label);
+            by_hash.entry(hash.clone()).or_default().push(label.clone());
+            self.first_degree_hashes.insert(label.clone(), hash);
+        }
+
+        let mut shared = Vec::new();
+        for (_, labels) in by_hash {
+            match labels.as_slice() {
+                [label] => {
+                    self.canonical.issue(label);
+                }
+                _ => shared.push(labels),
+            }
+        }
+
+        for labels in shared {
+            let mut results = Vec::new();
+            for label in labels {
+                if self.canonical.issued.contains_key(&label) {
+                    continue;
+                }
+                let mut issuer = IdentifierIssuer::new("b");
+                issuer.issue(&label);
+                results.push(self.hash_n_degree(&label, issuer)?);
+            }
+            results.sort_by(|(left, _), (right, _)| left.cmp(right));
+            for (_, issuer) in results {
+                for label in issuer.order {
+                    self.canonical.issue(&label);
+                }
+            }
+        }
+        Ok(self.canonical.issued)
+    }
+
+    fn hash_first_degree(&self, reference: &str) -> String {
+        let mut lines: Vec<String> = self.blank_node_quads[reference]
+            .iter()
+            .map(|quad| nquad(quad, &|label| if label == reference { "a" } else { "z" }))
+            .collect();
+        lines.sort();
+        sha256(&lines.concat())
+    }
+
+    fn hash_related(
+        &self,
+        related: &str,
+        quad: &Quad,
+        issuer: &IdentifierIssuer,
+        position: char,
+    ) -> String {
+        let mut input = position.to_string();
+        if position != 'g' {
+            input.push_str(&format_iri(quad.predicate().to_term().value()));
+        }
+        match self
+            .canonical
+            .issued
+            .get(related)
+            .or_else(|| issuer.issued.get(related))
+        {
+            Some(issued) => write!(input, "_:{issued}").unwrap(),
+            None => input.push_str(&self.first_degree_hashes[related]),
+        }
+        sha256(&input)
+    }
+
+    fn hash_n_degree(
+        &mut self,
+        reference: &str,
+        mut issuer: IdentifierIssuer,
+    ) -> Result<(String, IdentifierIssuer), CanonicalizationError> {
+        let mut related_by_hash: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for quad in &self.blank_node_quads[reference] {
+            for (position, term) in ['s', 'p', 'o', 'g'].into_iter().zip(quad_terms(quad)) {
+                let mut labels = Vec::new();
+                blank_nodes(&term, &mut labels);
+                for label in labels {
+                    if label != reference {
+                        let hash = self.hash_related(&label, quad, &issuer, position);
+                        related_by_hash.entry(hash).or_default().push(label);
+                    }
+                }
+            }
+        }
+
+        let mut data = String::new();
+        for (hash, mut related) in related_by_hash {
+            data.push_str(&hash);
+            let mut chosen_path = String::new();
+            let mut chosen_issuer = None;
+
+            related.sort();
+            loop {
+                self.work += 1;
+                if self.work > MAX_WORK {
+                    return Err(CanonicalizationError::TooComplex);
+                }
+                if let Some((path, path_issuer)) =
+                    self.permutation_path(&related, &issuer, &chosen_path)?
+                    && (chosen_path.is_empty() || path < chosen_path)
+                {
+                    chosen_path = path;
+                    chosen_issuer = Some(path_issuer);
+                }
+                if !next_permutation(&mut related) {
+                    break;
+                }
+            }
+
+            data.push_str(&chosen_path);
+            issuer = chosen_issuer.unwrap();
+        }
+        Ok((sha256(&data), issuer))
+    }
+
+    /// The path of one permutation of blank nodes with the same related hash,
+    /// or `None` once it cannot be shorter than `chosen_path`.
+    fn permutation_path(
+        &mut self,
+        permutation: &[String],
+        issuer: &IdentifierIssuer,
+        chosen_path: &str,
+    ) -> Result<Option<(String, IdentifierIssuer)>, CanonicalizationError> {
+        let worse = |path: &str| {
+            !chosen_path.is_empty() && path.len() >= chosen_path.len() && path > chosen_path
+        };
+        let mut issuer = issuer.clone();
+        let mut path = String::new();
+        let mut recursion = Vec::new();
+        for related in permutation {
+            match self.canonical.issued.get(related) {
+                Some(issued) => write!(path, "_:{issued}").unwrap(),
+                None => {
+                    if !issuer.issued.contains_key(related) {
+                        recursion.push(related);
+                    }
+                    write!(path, "_:{}", issuer.issue(related)).unwrap();
+                }
+            }
+            if worse(&path) {
+                return Ok(None);
+            }
+        }
+        for related in recursion {
+            let (hash, result_issuer) = self.hash_n_degree(related, issuer.clone())?;
+            issuer = result_issuer;
+            write!(path, "_:{}<{hash}>", issuer.issue(related)).unwrap();
+            if worse(&path) {
+                return Ok(None);
+            }
+        }
+        Ok(Some((path, issuer)))
+    }
+}
+
+/// Rearranges `items` into the next greater permutation, returning `false`
+/// after the last one.
+fn next_permutation(items: &mut [String]) -> bool {
+    let Some(pivot) = (1..items.len()).rev().find(|&i| items[i - 1] < items[i]) else {
+        return false;
+    };
+    let successor = (pivot..items.len())
+        .rev()
+        .find(|&i| items[i] > items[pivot - 1])
+        .unwrap();
+    items.swap(pivot - 1, successor);
+    items[pivot..].reverse();
+    true
+}
+
+fn sha256(input: &str) -> String {
+    Sha256::digest(input.as_bytes())
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            write!(hex, "{byte:02x}").unwrap();
+            hex
+        })
+}
+
+pub(crate) fn quad_terms(quad: &Quad) -> [Term; 4] {
+    [
+        quad.subject().to_term(),
+        quad.predicate().to_term(),
+        quad.object().to_term(),
+        quad.graph().to_term(),
+    ]
+}
+
+/// Pushes the labels of the blank nodes in `term`, including those nested in
+/// triple terms.
+pub(crate) fn blank_nodes(term: &Term, labels: &mut Vec<String>) {
+    match term {
+        Term::BlankNode(blank_node) => labels.push(blank_node.value().to_owned()),
+        Term::Quad(triple) => {
+            for term in quad_terms(triple) {
+                blank_nodes(&term, labels);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `quad` in canonical N-Quads, with blank node labels mapped by `label`.
+fn nquad<'l>(quad: &Quad, label: &dyn Fn(&str) -> &'l str) -> String {
+    let mut line = String::new();
+    for term in quad_terms(quad) {
+        if !matches!(term, Term::DefaultGraph(_)) {
+            write_term(&term, label, &mut line);
+            line.push(' ');
+        }
+    }
+    line.push_str(".\n");
+    line
+}
+
+fn write_term<'l>(term: &Term, label: &dyn Fn(&str) -> &'l str, out: &mut String) {
+    match term {
+        Term::NamedNode(named_node) => out.push_str(&format_iri(named_node.value())),
+        Term::BlankNode(blank_node) => write!(out, "_:{}", label(blank_node.value())).unwrap(),
+        Term::Literal(literal) => write_literal(literal, out),
+        Term::Quad(triple) => {
+            out.push_str("<<( ");
+            for term in &quad_terms(triple)[..3] {
+                write_term(term, label, out);
+                out.push(' ');
+            }
+            out.push_str(")>>");
+        }
+        Term::Variable(variable) => write!(out, "?{}", variable.value()).unwrap(),
+        Term::DefaultGraph(_) => {}
+    }
+}
+
+/// Escapes as canonical N-Quads does: `\b \t \n \f \r \" \\` with their
+/// short escapes, other control characters as `\uXXXX`.
+fn write_literal(literal: &Literal, out: &mut String) {
+    out.push('"');
+    for c in literal.value().chars() {
+        match c {
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\0'..='\u{1f}' | '\u{7f}' => write!(out, "\\u{:04X}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    if !literal.language().is_empty() {
+        write!(out, "@{}", literal.language()).unwrap();
+        if let Some(direction) = literal.direction() {
+            write!(out, "--{direction}").unwrap();
+        }
+    } else if *literal.datatype() != *xsd::string {
+        write!(out, "^^{}", format_iri(literal.datatype().value())).unwrap();
+    }
+}
+
+fn relabel(term: &Term, labels: &HashMap<String, String>) -> Term {
+    match term {
+        Term::BlankNode(blank_node) => match labels.get(blank_node.value()) {
+            Some(label) => BlankNode::new(label).to_term(),
+            None => term.clone(),
+        },
+        Term::Quad(triple) => Term::Quad(Box::new(relabel_quad(triple, labels))),
+        term => term.clone(),
+    }
+}
+
+/// `quad` with its blank nodes renamed by `labels`; unmapped ones are kept.
+pub(crate) fn relabel_quad(quad: &Quad, labels: &HashMap<String, String>) -> Quad {
+    let [subject, predicate, object, graph] = quad_terms(quad).map(|term| relabel(&term, labels));
+    // Relabeling keeps the kind of each term, so each stays valid in its
+    // position.
+    Quad::new(
+        &QuadSubject::try_from(subject).ok().unwrap(),
+        &QuadPredicate::try_from(predicate).ok().unwrap(),
+        &QuadObject::try_from(object).ok().unwrap(),
+        Some(&QuadGraph::try_from(graph).ok().unwrap()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::turtle::{TurtleSyntax, parse_str};
+
+    fn canonical(input: &str) -> String {
+        let quads = parse_str(TurtleSyntax::NQuads, input, None).unwrap();
+        canonical_nquads(&quads).unwrap()
+    }
+
+    #[test]
+    fn blank_nodes_get_canonical_labels() {
+        // Test rdfc10/test002 of the W3C suite.
+        let input = "<http://example.com/#p> <http://example.com/#q> _:e0 .\n\
+                     <http://example.com/#p> <http://example.com/#r> _:e1 .\n\
+                     _:e0 <http://example.com/#s> <http://example.com/#u> .\n\
+                     _:e1 <http://example.com/#t> <http://example.com/#u> .\n";
+        let expected = "<http://example.com/#p> <http://example.com/#q> _:c14n0 .\n\
+                        <http://example.com/#p> <http://example.com/#r> _:c14n1 .\n\
+                        _:c14n0 <http://example.com/#s> <http://example.com/#u> .\n\
+                        _:c14n1 <http://example.com/#t> <http://example.com/#u> .\n";
+        assert_eq!(canonical(input), expected);
+
+        let quads = parse_str(TurtleSyntax::NQuads, input, None).unwrap();
+        let canonicalized = canonicalize(&quads).unwrap();
+        assert_eq!(
+            *canonicalized[2].subject(),
+            QuadSubject::BlankNode(BlankNode::new("c14n0"))
+        );
+    }
+
+    #[test]
+    fn symmetric_blank_nodes_are_told_apart() {
+        // Test rdfc10/test005 of the W3C suite: two blank nodes that only
+        // differ by their relation to each other.
+        let input = "<http://example.com/#p> <http://example.com/#q> _:e0 .\n\
+                     <http://example.com/#p> <http://example.com/#q> _:e1 .\n\
+                     _:e0 <http://example.com/#p> _:e2 .\n\
+                     _:e1 <http://example.com/#p> _:e3 .\n\
+                     _:e2 <http://example.com/#r> _:e3 .\n";
+        let expected = "<http://example.com/#p> <http://example.com/#q> _:c14n2 .\n\
+                        <http://example.com/#p> <http://example.com/#q> _:c14n3 .\n\
+                        _:c14n0 <http://example.com/#r> _:c14n1 .\n\
+                        _:c14n2 <http://example.com/#p> _:c14n1 .\n\
+                        _:c14n3 <http://example.com/#p> _:c14n0 .\n";
+        assert_eq!(canonical(input), expected);
+
+        // Relabeling the input does not change the output.
+        let relabeled = input
+            .replace("_:e0", "_:x")
+            .replace("_:e3", "_:e0")
+            .replace("_:x", "_:e3");
+        assert_eq!(canonical(&relabeled), expected);
+    }
+
+    #[test]
+    fn triple_terms_parse_back() {
+        let input = "<< _:e0 <http://example.com/#p> \"o\" >> <http://example.com/#q> _:e0 .\n";
+        let output = canonical(input);
+        assert_eq!(
+            output,
+            "<<( _:c14n0 <http://example.com/#p> \"o\" )>> <http://example.com/#q> _:c14n0 .\n"
+        );
+        assert_eq!(canonical(&output), output);
+    }
+}
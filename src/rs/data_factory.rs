@@ -1,6 +1,25 @@
+//! Term and quad construction, mirroring the RDF/JS `DataFactory` interface.
+//!
+//! Breaking change: `DataFactory` used to be the concrete factory of `rs`
+//! terms with associated functions. It is now a trait, and that factory is
+//! [`DefaultDataFactory`], whose functions are methods. No alias is kept, as
+//! the trait takes the name: `DataFactory::named_node("...")` becomes
+//! `DefaultDataFactory::new().named_node("...")`, with the trait in scope.
+//!
+//! Parsers and serializers only work with `rs` terms. The parser entry
+//! points taking a factory, such as
+//! [`TurtleReader::with_factory`](crate::rs::turtle::TurtleReader::with_factory)
+//! and [`read_quads_with_factory`](crate::rs::format::read_quads_with_factory),
+//! parse each quad as `rs` terms and then rebuild it with the factory through
+//! [`QuadImport`]. They hand out the factory's own types, but do not save the
+//! cost of building the `rs` terms.
+
+use std::error::Error;
+use std::fmt::Display;
+
 use crate::rs::blank_node::BlankNode;
 use crate::rs::default_graph::DefaultGraph;
-use crate::rs::literal::{LanguageDirection, Literal};
+use crate::rs::literal::{LanguageDirection, Literal, is_valid_language_tag};
 use crate::rs::named_node::NamedNode;
 use crate::rs::quad::Quad;
 use crate::rs::variable::Variable;
@@ -9,23 +28,80 @@ use crate::rs::quad_graph::QuadGraph;
 use crate::rs::quad_object::QuadObject;
 use crate::rs::quad_predicate::QuadPredicate;
 use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term_like::TermLike;
+
+/// Creates terms and quads, mirroring the RDF/JS `DataFactory` interface.
+///
+/// The associated types let implementors return their own term
+/// representations (e.g. interned or arena-backed terms). Parsers only
+/// convert their quads to these types, see the module documentation.
+pub trait DataFactory {
+    type NamedNode;
+    type BlankNode;
+    type Literal;
+    type Variable;
+    type DefaultGraph;
+
+    type Subject;
+    type Predicate;
+    type Object;
+    type Graph;
+    type Quad;
+
+    fn named_node(&self, value: &str) -> Self::NamedNode;
+
+    fn blank_node(&mut self, value: Option<&str>) -> Self::BlankNode;
+
+    fn literal(
+        &self,
+        value: &str,
+        language_or_datatype: Option<&LanguageOrDatatype>,
+    ) -> Self::Literal;
 
-pub struct DataFactory {
+    fn variable(&self, value: &str) -> Self::Variable;
+
+    fn default_graph(&self) -> Self::DefaultGraph;
+
+    fn quad(
+        &self,
+        subject: &Self::Subject,
+        predicate: &Self::Predicate,
+        object: &Self::Object,
+        graph: Option<&Self::Graph>,
+    ) -> Self::Quad;
+}
+
+#[derive(Default)]
+pub struct DefaultDataFactory {
     blank_node_value_counter: usize,
 }
 
-impl DataFactory {
+impl DefaultDataFactory {
     pub fn new() -> Self {
         Self {
             blank_node_value_counter: 0,
         }
     }
+}
+
+impl DataFactory for DefaultDataFactory {
+    type NamedNode = NamedNode;
+    type BlankNode = BlankNode;
+    type Literal = Literal;
+    type Variable = Variable;
+    type DefaultGraph = DefaultGraph;
 
-    pub fn named_node(value: &str) -> NamedNode {
+    type Subject = QuadSubject;
+    type Predicate = QuadPredicate;
+    type Object = QuadObject;
+    type Graph = QuadGraph;
+    type Quad = Quad;
+
+    fn named_node(&self, value: &str) -> NamedNode {
         NamedNode::new(value)
     }
 
-    pub fn blank_node(&mut self, value: Option<&str>) -> BlankNode {
+    fn blank_node(&mut self, value: Option<&str>) -> BlankNode {
         if let Some(value) = value {
             BlankNode::new(value)
         } else {
@@ -34,7 +110,7 @@ impl DataFactory {
         }
     }
 
-    pub fn literal(value: &str, language_or_datatype: Option<&LanguageOrDatatype>) -> Literal {
+    fn literal(&self, value: &str, language_or_datatype: Option<&LanguageOrDatatype>) -> Literal {
         match language_or_datatype {
             Some(language_or_datatype) => match language_or_datatype {
                 LanguageOrDatatype::Language(language) => {
@@ -54,15 +130,16 @@ impl DataFactory {
         }
     }
 
-    pub fn variable(value: &str) -> Variable {
+    fn variable(&self, value: &str) -> Variable {
         Variable::new(value)
     }
 
-    pub fn default_graph() -> DefaultGraph {
+    fn default_graph(&self) -> DefaultGraph {
         DefaultGraph::new()
     }
 
-    pub fn quad(
+    fn quad(
+        &self,
         subject: &QuadSubject,
         predicate: &QuadPredicate,
         object: &QuadObject,
@@ -72,6 +149,101 @@ impl DataFactory {
     }
 }
 
+/// A [`DataFactory`] that can rebuild `rs` quads with its own terms.
+///
+/// Implemented for every factory whose term types convert into the quad
+/// positions they can take.
+pub trait QuadImport: DataFactory {
+    fn import_quad(&mut self, quad: &Quad) -> Self::Quad;
+}
+
+impl<F> QuadImport for F
+where
+    F: DataFactory,
+    F::NamedNode: Into<F::Subject> + Into<F::Predicate> + Into<F::Object> + Into<F::Graph>,
+    F::BlankNode: Into<F::Subject> + Into<F::Object> + Into<F::Graph>,
+    F::Literal: Into<F::Object>,
+    F::Variable: Into<F::Subject> + Into<F::Predicate> + Into<F::Object> + Into<F::Graph>,
+    F::DefaultGraph: Into<F::Graph>,
+    F::Quad: Into<F::Subject>,
+{
+    fn import_quad(&mut self, quad: &Quad) -> F::Quad {
+        let subject = match quad.subject() {
+            QuadSubject::NamedNode(named_node) => self.named_node(named_node.value()).into(),
+            QuadSubject::BlankNode(blank_node) => self.blank_node(Some(blank_node.value())).into(),
+            QuadSubject::Variable(variable) => self.variable(variable.value()).into(),
+            QuadSubject::Quad(triple) => self.import_quad(triple).into(),
+        };
+        let predicate = match quad.predicate() {
+            QuadPredicate::NamedNode(named_node) => self.named_node(named_node.value()).into(),
+            QuadPredicate::Variable(variable) => self.variable(variable.value()).into(),
+        };
+        let object = match quad.object() {
+            QuadObject::NamedNode(named_node) => self.named_node(named_node.value()).into(),
+            QuadObject::Literal(literal) => {
+                let language_or_datatype = if literal.language().is_empty() {
+                    LanguageOrDatatype::Datatype(literal.datatype().clone())
+                } else if literal.direction().is_some() {
+                    LanguageOrDatatype::DirectionalLanguage(DirectionalLanguage {
+                        language: literal.language().to_owned(),
+                        direction: literal.direction().cloned(),
+                    })
+                } else {
+                    LanguageOrDatatype::Language(literal.language().to_owned())
+                };
+                self.literal(literal.value(), Some(&language_or_datatype))
+                    .into()
+            }
+            QuadObject::BlankNode(blank_node) => self.blank_node(Some(blank_node.value())).into(),
+            QuadObject::Variable(variable) => self.variable(variable.value()).into(),
+        };
+        let graph = match quad.graph() {
+            QuadGraph::DefaultGraph(_) => self.default_graph().into(),
+            QuadGraph::NamedNode(named_node) => self.named_node(named_node.value()).into(),
+            QuadGraph::BlankNode(blank_node) => self.blank_node(Some(blank_node.value())).into(),
+            QuadGraph::Variable(variable) => self.variable(variable.value()).into(),
+        };
+        self.quad(&subject, &predicate, &object, Some(&graph))
+    }
+}
+
+/// The quads of a parser rebuilt with a factory, see [`with_factory`].
+pub struct FactoryQuads<I, F> {
+    quads: I,
+    factory: F,
+}
+
+impl<I, F> FactoryQuads<I, F> {
+    pub fn factory(&self) -> &F {
+        &self.factory
+    }
+}
+
+impl<I, F, E> Iterator for FactoryQuads<I, F>
+where
+    I: Iterator<Item = Result<Quad, E>>,
+    F: QuadImport,
+{
+    type Item = Result<F::Quad, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let quad = self.quads.next()?;
+        Some(quad.map(|quad| self.factory.import_quad(&quad)))
+    }
+}
+
+/// Rebuilds the quads of `quads`, e.g. a parser, with `factory`.
+pub fn with_factory<I, F, E>(quads: I, factory: F) -> FactoryQuads<I::IntoIter, F>
+where
+    I: IntoIterator<Item = Result<Quad, E>>,
+    F: QuadImport,
+{
+    FactoryQuads {
+        quads: quads.into_iter(),
+        factory,
+    }
+}
+
 pub enum LanguageOrDatatype {
     Language(String),
     Datatype(NamedNode),
@@ -82,3 +254,253 @@ pub struct DirectionalLanguage {
     language: String,
     direction: Option<LanguageDirection>,
 }
+
+impl DirectionalLanguage {
+    pub fn builder() -> DirectionalLanguageBuilder {
+        DirectionalLanguageBuilder::default()
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn direction(&self) -> Option<&LanguageDirection> {
+        self.direction.as_ref()
+    }
+}
+
+#[derive(Default)]
+pub struct DirectionalLanguageBuilder {
+    language: Option<String>,
+    direction: Option<LanguageDirection>,
+}
+
+impl DirectionalLanguageBuilder {
+    pub fn language(mut self, language: &str) -> Self {
+        self.language = Some(language.to_owned());
+        self
+    }
+
+    pub fn direction(mut self, direction: LanguageDirection) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    pub fn build(self) -> Result<DirectionalLanguage, DirectionalLanguageError> {
+        let Some(language) = self.language else {
+            return Err(DirectionalLanguageError::MissingLanguage);
+        };
+
+        if !is_valid_language_tag(&language) {
+            return Err(DirectionalLanguageError::InvalidLanguage(language));
+        }
+
+        Ok(DirectionalLanguage {
+            language,
+            direction: self.direction,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DirectionalLanguageError {
+    MissingLanguage,
+    InvalidLanguage(String),
+}
+
+impl Display for DirectionalLanguageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DirectionalLanguageError::MissingLanguage => {
+                write!(f, "a language direction requires a language")
+            }
+            DirectionalLanguageError::InvalidLanguage(language) => {
+                write!(f, "invalid language tag \"{language}\"")
+            }
+        }
+    }
+}
+
+impl Error for DirectionalLanguageError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::turtle::{TurtleParser, TurtleReader, TurtleSyntax};
+
+    struct StringFactory {
+        blank_nodes: usize,
+    }
+
+    impl DataFactory for StringFactory {
+        type NamedNode = String;
+        type BlankNode = String;
+        type Literal = String;
+        type Variable = String;
+        type DefaultGraph = String;
+
+        type Subject = String;
+        type Predicate = String;
+        type Object = String;
+        type Graph = String;
+        type Quad = String;
+
+        fn named_node(&self, value: &str) -> String {
+            format!("<{value}>")
+        }
+
+        fn blank_node(&mut self, value: Option<&str>) -> String {
+            match value {
+                Some(value) => format!("_:{value}"),
+                None => {
+                    self.blank_nodes += 1;
+                    format!("_:b{}", self.blank_nodes)
+                }
+            }
+        }
+
+        fn literal(&self, value: &str, _: Option<&LanguageOrDatatype>) -> String {
+            format!("\"{value}\"")
+        }
+
+        fn variable(&self, value: &str) -> String {
+            format!("?{value}")
+        }
+
+        fn default_graph(&self) -> String {
+            "".to_owned()
+        }
+
+        fn quad(&self, s: &String, p: &String, o: &String, g: Option<&String>) -> String {
+            match g {
+                Some(g) if !g.is_empty() => format!("{s} {p} {o} {g} ."),
+                _ => format!("{s} {p} {o} ."),
+            }
+        }
+    }
+
+    fn build_quad<F>(factory: &mut F) -> F::Quad
+    where
+        F: DataFactory,
+        F::BlankNode: Into<F::Subject>,
+        F::NamedNode: Into<F::Predicate>,
+        F::Literal: Into<F::Object>,
+    {
+        let subject = factory.blank_node(None).into();
+        let predicate = factory.named_node("p").into();
+        let object = factory.literal("o", None).into();
+
+        factory.quad(&subject, &predicate, &object, None)
+    }
+
+    #[test]
+    fn default_factory_creates_terms() {
+        let mut factory = DefaultDataFactory::new();
+
+        assert_eq!(factory.named_node("foo"), NamedNode::new("foo"));
+        assert_eq!(factory.blank_node(None), BlankNode::new("1"));
+        assert_eq!(factory.blank_node(None), BlankNode::new("2"));
+        assert_eq!(factory.blank_node(Some("foo")), BlankNode::new("foo"));
+        assert_eq!(factory.variable("foo"), Variable::new("foo"));
+        assert_eq!(factory.default_graph(), DefaultGraph::new());
+
+        let literal = factory.literal("foo", Some(&LanguageOrDatatype::Language("en".to_owned())));
+
+        assert_eq!(literal.value(), "foo");
+        assert_eq!(literal.language(), "en");
+    }
+
+    #[test]
+    fn default_factory_imports_quads() {
+        let quad = Quad::new(
+            &QuadSubject::BlankNode(BlankNode::new("b")),
+            &QuadPredicate::NamedNode(NamedNode::new("http://p")),
+            &QuadObject::Literal(Literal::new(
+                "o",
+                Some("ar"),
+                Some(&LanguageDirection::RightToLeft),
+                None,
+            )),
+            None,
+        );
+
+        assert_eq!(DefaultDataFactory::new().import_quad(&quad), quad);
+    }
+
+    #[test]
+    fn directional_language_literals_can_be_built() {
+        let factory = DefaultDataFactory::new();
+
+        let directional_language = DirectionalLanguage::builder()
+            .language("ar")
+            .direction("rtl".parse().unwrap())
+            .build()
+            .unwrap();
+        let literal = factory.literal(
+            "foo",
+            Some(&LanguageOrDatatype::DirectionalLanguage(
+                directional_language,
+            )),
+        );
+
+        assert_eq!(literal.language(), "ar");
+        assert_eq!(literal.direction(), Some(&LanguageDirection::RightToLeft));
+        assert_eq!(
+            literal.datatype().value(),
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#dirLangString"
+        );
+    }
+
+    #[test]
+    fn directional_language_requires_valid_language() {
+        assert_eq!(
+            DirectionalLanguage::builder()
+                .direction(LanguageDirection::LeftToRight)
+                .build()
+                .err(),
+            Some(DirectionalLanguageError::MissingLanguage)
+        );
+        assert_eq!(
+            DirectionalLanguage::builder()
+                .language("not a tag")
+                .build()
+                .err(),
+            Some(DirectionalLanguageError::InvalidLanguage(
+                "not a tag".to_owned()
+            ))
+        );
+
+        let directional_language = DirectionalLanguage::builder()
+            .language("en-GB")
+            .build()
+            .unwrap();
+
+        assert_eq!(directional_language.language(), "en-GB");
+        assert_eq!(directional_language.direction(), None);
+    }
+
+    #[test]
+    fn factories_are_interchangeable() {
+        let mut factory = StringFactory { blank_nodes: 0 };
+
+        assert_eq!(build_quad(&mut factory), "_:b1 <p> \"o\" .");
+    }
+
+    #[test]
+    fn parsers_build_terms_with_factories() {
+        let input = "<http://s> <http://p> \"o\"@en _:g .\n<http://s> <http://p> _:o .\n";
+        let reader = TurtleReader::new(input.as_bytes(), TurtleParser::new(TurtleSyntax::NQuads));
+        let quads: Vec<String> = reader
+            .with_factory(StringFactory { blank_nodes: 0 })
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(
+            quads,
+            [
+                "<http://s> <http://p> \"o\" _:g .",
+                "<http://s> <http://p> _:o ."
+            ]
+        );
+    }
+}
@@ -0,0 +1,431 @@
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+
+use crate::rs::default_graph::DefaultGraph;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+
+type EncodedQuad = [u32; 4];
+
+pub(crate) const SUBJECT: usize = 0;
+pub(crate) const PREDICATE: usize = 1;
+pub(crate) const OBJECT: usize = 2;
+pub(crate) const GRAPH: usize = 3;
+
+/// Orders of the quad positions in each index, e.g. `[P, O, S, G]` for POSG.
+pub(crate) const INDEX_ORDERS: [[usize; 4]; 4] = [
+    [SUBJECT, PREDICATE, OBJECT, GRAPH],
+    [PREDICATE, OBJECT, SUBJECT, GRAPH],
+    [OBJECT, SUBJECT, PREDICATE, GRAPH],
+    [GRAPH, SUBJECT, PREDICATE, OBJECT],
+];
+
+/// In-memory set of quads with SPOG/POSG/OSPG/GSPO indexes over
+/// dictionary-encoded terms.
+#[derive(Clone, Debug, Default)]
+pub struct Dataset {
+    terms: Vec<Term>,
+    ids: HashMap<Term, u32>,
+    indexes: [BTreeSet<EncodedQuad>; 4],
+}
+
+impl Dataset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.indexes[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indexes[0].is_empty()
+    }
+
+    /// Adds `quad`, returning whether it was not present yet.
+    pub fn insert(&mut self, quad: &Quad) -> bool {
+        let encoded = [
+            self.encode(quad.subject().to_term()),
+            self.encode(quad.predicate().to_term()),
+            self.encode(quad.object().to_term()),
+            self.encode(quad.graph().to_term()),
+        ];
+
+        self.insert_encoded(encoded)
+    }
+
+    /// Removes `quad`, returning whether it was present.
+    pub fn remove(&mut self, quad: &Quad) -> bool {
+        let Some(encoded) = self.lookup(quad) else {
+            return false;
+        };
+
+        let removed = self.indexes[0].remove(&permute(&INDEX_ORDERS[0], &encoded));
+        for (index, order) in self.indexes.iter_mut().zip(INDEX_ORDERS.iter()).skip(1) {
+            index.remove(&permute(order, &encoded));
+        }
+
+        removed
+    }
+
+    pub fn contains(&self, quad: &Quad) -> bool {
+        self.lookup(quad).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.terms.clear();
+        self.ids.clear();
+        for index in &mut self.indexes {
+            index.clear();
+        }
+    }
+
+    /// Iterates over all quads, grouped by graph, then by subject.
+    pub fn iter(&self) -> impl Iterator<Item = Quad> + '_ {
+        let order = &INDEX_ORDERS[3];
+        self.indexes[3]
+            .iter()
+            .map(move |permuted| self.decode(&unpermute(order, permuted)))
+    }
+
+    /// Distinct graphs holding at least one quad, including the default graph.
+    pub fn graphs(&self) -> Vec<QuadGraph> {
+        let mut graphs = Vec::new();
+        let mut previous = None;
+
+        for encoded in &self.indexes[3] {
+            if previous != Some(encoded[0]) {
+                previous = Some(encoded[0]);
+                if let Ok(graph) = QuadGraph::try_from(self.terms[encoded[0] as usize].clone()) {
+                    graphs.push(graph);
+                }
+            }
+        }
+
+        graphs
+    }
+
+    /// Iterates over the quads matching every given term (`None` matches
+    /// anything), like RDF/JS `DatasetCore.match`.
+    pub fn quads_for_pattern<'a>(
+        &'a self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> Box<dyn Iterator<Item = Quad> + 'a> {
+        let mut pattern = [None; 4];
+
+        for (position, term) in [
+            subject.map(|s| s.to_term()),
+            predicate.map(|p| p.to_term()),
+            object.map(|o| o.to_term()),
+            graph.map(|g| g.to_term()),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if let Some(term) = term {
+                match self.ids.get(&term) {
+                    Some(id) => pattern[position] = Some(*id),
+                    None => return Box::new(std::iter::empty()),
+                }
+            }
+        }
+
+        Box::new(
+            self.encoded_for_pattern(pattern)
+                .map(move |encoded| self.decode(&encoded)),
+        )
+    }
+
+    /// Number of quads [`Dataset::quads_for_pattern`] would return.
+    pub fn count_for_pattern(
+        &self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> usize {
+        let mut pattern = [None; 4];
+
+        for (position, term) in [
+            subject.map(|s| s.to_term()),
+            predicate.map(|p| p.to_term()),
+            object.map(|o| o.to_term()),
+            graph.map(|g| g.to_term()),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if let Some(term) = term {
+                match self.ids.get(&term) {
+                    Some(id) => pattern[position] = Some(*id),
+                    None => return 0,
+                }
+            }
+        }
+
+        self.encoded_for_pattern(pattern).count()
+    }
+
+    fn encoded_for_pattern(
+        &self,
+        pattern: [Option<u32>; 4],
+    ) -> impl Iterator<Item = EncodedQuad> + '_ {
+        let (index, prefix_length) = choose_index(&pattern);
+        let order = &INDEX_ORDERS[index];
+
+        let mut lower = [0; 4];
+        let mut upper = [u32::MAX; 4];
+        for i in 0..prefix_length {
+            let id = pattern[order[i]].unwrap();
+            lower[i] = id;
+            upper[i] = id;
+        }
+
+        self.indexes[index]
+            .range((Bound::Included(lower), Bound::Included(upper)))
+            .map(move |permuted| unpermute(order, permuted))
+            .filter(move |encoded| {
+                pattern
+                    .iter()
+                    .zip(encoded.iter())
+                    .all(|(expected, id)| expected.is_none_or(|expected| expected == *id))
+            })
+    }
+
+    fn encode(&mut self, term: Term) -> u32 {
+        if let Some(id) = self.ids.get(&term) {
+            return *id;
+        }
+
+        let id = u32::try_from(self.terms.len()).expect("at most 2^32 distinct terms");
+        self.terms.push(term.clone());
+        self.ids.insert(term, id);
+        id
+    }
+
+    fn insert_encoded(&mut self, encoded: EncodedQuad) -> bool {
+        let inserted = self.indexes[0].insert(permute(&INDEX_ORDERS[0], &encoded));
+        if inserted {
+            for (index, order) in self.indexes.iter_mut().zip(INDEX_ORDERS.iter()).skip(1) {
+                index.insert(permute(order, &encoded));
+            }
+        }
+        inserted
+    }
+
+    fn lookup(&self, quad: &Quad) -> Option<EncodedQuad> {
+        let encoded = [
+            *self.ids.get(&quad.subject().to_term())?,
+            *self.ids.get(&quad.predicate().to_term())?,
+            *self.ids.get(&quad.object().to_term())?,
+            *self.ids.get(&quad.graph().to_term())?,
+        ];
+
+        if self.indexes[0].contains(&encoded) {
+            Some(encoded)
+        } else {
+            None
+        }
+    }
+
+    fn decode(&self, encoded: &EncodedQuad) -> Quad {
+        let term = |position: usize| self.terms[encoded[position] as usize].clone();
+
+        let subject = QuadSubject::try_from(term(SUBJECT)).expect("subject term");
+        let predicate = QuadPredicate::try_from(term(PREDICATE)).expect("predicate term");
+        let object = QuadObject::try_from(term(OBJECT)).expect("object term");
+        let graph = QuadGraph::try_from(term(GRAPH))
+            .unwrap_or_else(|_| QuadGraph::DefaultGraph(DefaultGraph::new()));
+
+        Quad::new(&subject, &predicate, &object, Some(&graph))
+    }
+}
+
+impl PartialEq for Dataset {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|quad| other.contains(&quad))
+    }
+}
+
+impl Eq for Dataset {}
+
+impl Extend<Quad> for Dataset {
+    fn extend<T: IntoIterator<Item = Quad>>(&mut self, iter: T) {
+        for quad in iter {
+            self.insert(&quad);
+        }
+    }
+}
+
+impl<'a> Extend<&'a Quad> for Dataset {
+    fn extend<T: IntoIterator<Item = &'a Quad>>(&mut self, iter: T) {
+        for quad in iter {
+            self.insert(quad);
+        }
+    }
+}
+
+impl FromIterator<Quad> for Dataset {
+    fn from_iter<T: IntoIterator<Item = Quad>>(iter: T) -> Self {
+        let mut dataset = Self::new();
+        dataset.extend(iter);
+        dataset
+    }
+}
+
+impl<'a> FromIterator<&'a Quad> for Dataset {
+    fn from_iter<T: IntoIterator<Item = &'a Quad>>(iter: T) -> Self {
+        let mut dataset = Self::new();
+        dataset.extend(iter);
+        dataset
+    }
+}
+
+/// Index whose order starts with the most bound positions of `pattern`,
+/// along with how many positions it has bound in a row.
+pub(crate) fn choose_index<T>(pattern: &[Option<T>; 4]) -> (usize, usize) {
+    INDEX_ORDERS
+        .iter()
+        .enumerate()
+        .map(|(index, order)| {
+            let prefix_length = order
+                .iter()
+                .take_while(|position| pattern[**position].is_some())
+                .count();
+            (index, prefix_length)
+        })
+        .max_by_key(|(index, prefix_length)| (*prefix_length, usize::MAX - index))
+        .unwrap()
+}
+
+pub(crate) fn permute<T: Copy>(order: &[usize; 4], encoded: &[T; 4]) -> [T; 4] {
+    [
+        encoded[order[0]],
+        encoded[order[1]],
+        encoded[order[2]],
+        encoded[order[3]],
+    ]
+}
+
+pub(crate) fn unpermute<T: Copy + Default>(order: &[usize; 4], permuted: &[T; 4]) -> [T; 4] {
+    let mut encoded = [T::default(); 4];
+    for (i, position) in order.iter().enumerate() {
+        encoded[*position] = permuted[i];
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::literal::Literal;
+    use crate::rs::named_node::NamedNode;
+
+    fn nn(value: &str) -> NamedNode {
+        NamedNode::new(value)
+    }
+
+    fn quad(s: &str, p: &str, o: &str, g: Option<&str>) -> Quad {
+        Quad::new(
+            &QuadSubject::NamedNode(nn(s)),
+            &QuadPredicate::NamedNode(nn(p)),
+            &QuadObject::NamedNode(nn(o)),
+            g.map(|g| QuadGraph::NamedNode(nn(g))).as_ref(),
+        )
+    }
+
+    fn sample() -> Dataset {
+        [
+            quad("a", "p", "b", None),
+            quad("a", "q", "c", None),
+            quad("b", "p", "c", None),
+            quad("a", "p", "b", Some("g")),
+            quad("c", "p", "a", Some("g")),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn quads_insert_and_remove() {
+        let mut dataset = sample();
+
+        assert_eq!(dataset.len(), 5);
+        assert!(!dataset.insert(&quad("a", "p", "b", None)));
+        assert!(dataset.contains(&quad("a", "p", "b", Some("g"))));
+        assert!(!dataset.contains(&quad("a", "p", "c", None)));
+
+        assert!(dataset.remove(&quad("a", "p", "b", None)));
+        assert!(!dataset.remove(&quad("a", "p", "b", None)));
+        assert_eq!(dataset.len(), 4);
+        assert!(!dataset.contains(&quad("a", "p", "b", None)));
+        assert_eq!(dataset.count_for_pattern(None, None, None, None), 4);
+    }
+
+    #[test]
+    fn patterns_use_any_combination_of_positions() {
+        let dataset = sample();
+        let a = QuadSubject::NamedNode(nn("a"));
+        let p = QuadPredicate::NamedNode(nn("p"));
+        let c = QuadObject::NamedNode(nn("c"));
+        let g = QuadGraph::NamedNode(nn("g"));
+        let default_graph = QuadGraph::DefaultGraph(DefaultGraph::new());
+
+        assert_eq!(dataset.count_for_pattern(Some(&a), None, None, None), 3);
+        assert_eq!(dataset.count_for_pattern(None, Some(&p), None, None), 4);
+        assert_eq!(dataset.count_for_pattern(None, None, Some(&c), None), 2);
+        assert_eq!(dataset.count_for_pattern(None, None, None, Some(&g)), 2);
+        assert_eq!(
+            dataset.count_for_pattern(None, None, None, Some(&default_graph)),
+            3
+        );
+        assert_eq!(dataset.count_for_pattern(Some(&a), None, None, Some(&g)), 1);
+        assert_eq!(dataset.count_for_pattern(Some(&a), None, Some(&c), None), 1);
+        assert_eq!(dataset.count_for_pattern(None, Some(&p), None, Some(&g)), 2);
+
+        let found: Vec<Quad> = dataset
+            .quads_for_pattern(Some(&a), Some(&p), None, Some(&g))
+            .collect();
+
+        assert_eq!(found, vec![quad("a", "p", "b", Some("g"))]);
+
+        let unknown = QuadObject::Literal(Literal::new("unknown", None, None, None));
+
+        assert_eq!(
+            dataset.count_for_pattern(None, None, Some(&unknown), None),
+            0
+        );
+    }
+
+    #[test]
+    fn graphs_are_listed() {
+        let dataset = sample();
+
+        let graphs = dataset.graphs();
+
+        assert_eq!(graphs.len(), 2);
+        assert!(graphs.contains(&QuadGraph::DefaultGraph(DefaultGraph::new())));
+        assert!(graphs.contains(&QuadGraph::NamedNode(nn("g"))));
+
+        let mut runs: Vec<QuadGraph> = dataset.iter().map(|quad| quad.graph().clone()).collect();
+        runs.dedup();
+        assert_eq!(runs.len(), 2);
+    }
+
+    #[test]
+    fn datasets_compare_by_content() {
+        let mut reversed: Vec<Quad> = sample().iter().collect();
+        reversed.reverse();
+
+        assert_eq!(sample(), reversed.into_iter().collect::<Dataset>());
+        assert_ne!(sample(), Dataset::new());
+    }
+}
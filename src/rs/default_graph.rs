@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 use crate::rs::{term::Term, term_like::TermLike};
 
 #[derive(Clone, Eq, Debug)]
@@ -19,6 +21,10 @@ impl PartialEq for DefaultGraph {
     }
 }
 
+impl Hash for DefaultGraph {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
 impl PartialEq<Term> for DefaultGraph {
     fn eq(&self, other: &Term) -> bool {
         match other {
@@ -0,0 +1,320 @@
+//! Differences between two collections of quads, up to blank node renaming.
+//!
+//! Quads without blank nodes are compared as they are. Quads with blank nodes
+//! are grouped into components, the quads connected through shared blank
+//! nodes, and a component is unchanged when the other side has one with the
+//! same canonical form. A changed component is reported as a whole, removed
+//! and added, which keeps e.g. an edited OWL restriction together.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use crate::rs::canonical::{
+    CanonicalizationError, blank_nodes, canonical_nquads, quad_terms, relabel_quad,
+};
+use crate::rs::quad::Quad;
+use crate::rs::turtle::{TurtleSerializer, TurtleSyntax};
+
+/// The quads removed from and added to a collection of quads.
+///
+/// Displays as one `- ` or `+ ` line per quad in N-Quads, sorted so that
+/// changes to the same subject are next to each other.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Diff {
+    removed: Vec<Quad>,
+    added: Vec<Quad>,
+}
+
+impl Diff {
+    /// The quads of the old side missing from the new one, with the blank
+    /// node labels of the old side.
+    pub fn removed(&self) -> &[Quad] {
+        &self.removed
+    }
+
+    /// The quads of the new side missing from the old one. Their blank nodes
+    /// never share a label with one of the old side.
+    pub fn added(&self) -> &[Quad] {
+        &self.added
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines: Vec<(String, char)> = self
+            .removed
+            .iter()
+            .map(|quad| (nquad(quad), '-'))
+            .chain(self.added.iter().map(|quad| (nquad(quad), '+')))
+            .collect();
+        lines.sort();
+        for (line, sign) in lines {
+            write!(f, "{sign} {line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares `old` with `new`. Duplicate quads count once.
+pub fn diff<'a>(
+    old: impl IntoIterator<Item = &'a Quad>,
+    new: impl IntoIterator<Item = &'a Quad>,
+) -> Result<Diff, CanonicalizationError> {
+    let (old_ground, old_components) = split(old);
+    let (new_ground, new_components) = split(new);
+    let mut diff = Diff::default();
+
+    let old_set: HashSet<&Quad> = old_ground.iter().copied().collect();
+    let new_set: HashSet<&Quad> = new_ground.iter().copied().collect();
+    diff.removed.extend(
+        old_ground
+            .into_iter()
+            .filter(|quad| !new_set.contains(quad))
+            .cloned(),
+    );
+    let mut added: Vec<Quad> = new_ground
+        .into_iter()
+        .filter(|quad| !old_set.contains(quad))
+        .cloned()
+        .collect();
+
+    let mut unmatched: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, component) in old_components.iter().enumerate().rev() {
+        unmatched
+            .entry(canonical_nquads(component.iter().copied())?)
+            .or_default()
+            .push(index);
+    }
+    let mut added_components = Vec::new();
+    for component in new_components {
+        let form = canonical_nquads(component.iter().copied())?;
+        if unmatched.get_mut(&form).and_then(Vec::pop).is_none() {
+            added_components.push(component);
+        }
+    }
+    let mut removed_components: Vec<usize> = unmatched.into_values().flatten().collect();
+    removed_components.sort_unstable();
+    for index in removed_components {
+        diff.removed
+            .extend(old_components[index].iter().map(|quad| (*quad).clone()));
+    }
+
+    // Added blank nodes are distinct from the old ones, so their labels must
+    // be too for the diff to apply to the old side.
+    let taken: HashSet<String> = old_components
+        .iter()
+        .chain(&added_components)
+        .flatten()
+        .flat_map(|quad| labels(quad))
+        .collect();
+    let old_labels: HashSet<String> = old_components
+        .iter()
+        .flatten()
+        .flat_map(|quad| labels(quad))
+        .collect();
+    let mut renamed = HashMap::new();
+    let mut counter = 0;
+    for quad in added_components.iter().flatten() {
+        for label in labels(quad) {
+            if old_labels.contains(&label) && !renamed.contains_key(&label) {
+                let fresh = loop {
+                    counter += 1;
+                    let fresh = format!("{label}_{counter}");
+                    if !taken.contains(&fresh) {
+                        break fresh;
+                    }
+                };
+                renamed.insert(label, fresh);
+            }
+        }
+        added.push(relabel_quad(quad, &renamed));
+    }
+    diff.added = added;
+    Ok(diff)
+}
+
+fn labels(quad: &Quad) -> Vec<String> {
+    let mut labels = Vec::new();
+    for term in quad_terms(quad) {
+        blank_nodes(&term, &mut labels);
+    }
+    labels
+}
+
+/// Splits the distinct quads of `quads` into those without blank nodes and
+/// the components connected by blank nodes, in order of first appearance.
+fn split<'a>(quads: impl IntoIterator<Item = &'a Quad>) -> (Vec<&'a Quad>, Vec<Vec<&'a Quad>>) {
+    let mut seen = HashSet::new();
+    let mut ground = Vec::new();
+    let mut linked = Vec::new();
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut parents: Vec<usize> = Vec::new();
+
+    fn root(parents: &mut [usize], mut id: usize) -> usize {
+        while parents[id] != id {
+            parents[id] = parents[parents[id]];
+            id = parents[id];
+        }
+        id
+    }
+
+    for quad in quads {
+        if !seen.insert(quad) {
+            continue;
+        }
+        let quad_ids: Vec<usize> = labels(quad)
+            .into_iter()
+            .map(|label| {
+                *ids.entry(label).or_insert_with(|| {
+                    parents.push(parents.len());
+                    parents.len() - 1
+                })
+            })
+            .collect();
+        let Some(&first) = quad_ids.first() else {
+            ground.push(quad);
+            continue;
+        };
+        for &id in &quad_ids[1..] {
+            let (a, b) = (root(&mut parents, first), root(&mut parents, id));
+            parents[b] = a;
+        }
+        linked.push((quad, first));
+    }
+
+    let mut components: Vec<Vec<&Quad>> = Vec::new();
+    let mut component_index: HashMap<usize, usize> = HashMap::new();
+    for (quad, id) in linked {
+        let index = *component_index
+            .entry(root(&mut parents, id))
+            .or_insert_with(|| {
+                components.push(Vec::new());
+                components.len() - 1
+            });
+        components[index].push(quad);
+    }
+    (ground, components)
+}
+
+/// `quad` as an N-Quads line.
+pub(crate) fn nquad(quad: &Quad) -> String {
+    let mut line = String::new();
+    TurtleSerializer::new(TurtleSyntax::NQuads)
+        .formatter()
+        .format(quad, &mut line)
+        .expect("N-Quads can write any quad");
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::turtle::parse_str;
+
+    fn quads(input: &str) -> Vec<Quad> {
+        parse_str(TurtleSyntax::TriG, input, None).unwrap()
+    }
+
+    #[test]
+    fn renamed_blank_nodes_are_unchanged() {
+        let old = quads(
+            "@prefix ex: <http://example.org/> .\n\
+             ex:C ex:sub [ ex:on ex:p ; ex:some ex:D ] .\n\
+             ex:C ex:label \"C\" .\n",
+        );
+        let new = quads(
+            "@prefix ex: <http://example.org/> .\n\
+             ex:C ex:label \"C\" .\n\
+             ex:C ex:sub _:r .\n\
+             _:r ex:some ex:D ; ex:on ex:p .\n",
+        );
+        let diff = diff(&old, &new).unwrap();
+        assert!(diff.is_empty(), "{diff}");
+    }
+
+    #[test]
+    fn changed_components_are_removed_and_added() {
+        let old = quads(
+            "@prefix ex: <http://example.org/> .\n\
+             ex:C ex:sub _:a . _:a ex:on ex:p ; ex:some ex:D .\n\
+             ex:C ex:sub _:b . _:b ex:on ex:q .\n\
+             ex:C ex:label \"C\" .\n",
+        );
+        let new = quads(
+            "@prefix ex: <http://example.org/> .\n\
+             ex:C ex:sub _:b . _:b ex:on ex:p ; ex:some ex:E .\n\
+             ex:C ex:sub _:x . _:x ex:on ex:q .\n\
+             ex:C ex:label \"C\"@en .\n",
+        );
+        let diff = diff(&old, &new).unwrap();
+        assert_eq!(diff.removed().len(), 4);
+        assert_eq!(diff.added().len(), 4);
+        // `_:b` of the new side is another node than `_:b` of the old one.
+        assert!(!diff.added().iter().any(|quad| nquad(quad).contains("_:b ")));
+        assert_eq!(
+            diff.to_string()
+                .lines()
+                .filter(|line| line.contains("example.org/label"))
+                .collect::<Vec<_>>(),
+            [
+                "- <http://example.org/C> <http://example.org/label> \"C\" .",
+                "+ <http://example.org/C> <http://example.org/label> \"C\"@en .",
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicates_count_once_and_equal_components_each_count() {
+        let old = quads(
+            "@prefix ex: <http://example.org/> .\n\
+             ex:a ex:p ex:b . ex:a ex:p ex:b .\n\
+             ex:C ex:sub [ ex:on ex:p ] , [ ex:on ex:p ] .\n\
+             _:g { ex:a ex:p ex:b . }\n",
+        );
+        let new = quads(
+            "@prefix ex: <http://example.org/> .\n\
+             ex:a ex:p ex:b .\n\
+             ex:C ex:sub [ ex:on ex:p ] .\n\
+             _:h { ex:a ex:p ex:b . }\n",
+        );
+        let diff = diff(&old, &new).unwrap();
+        assert_eq!(diff.removed().len(), 2, "{diff}");
+        assert!(diff.added().is_empty(), "{diff}");
+
+        let everything = super::diff([], &new).unwrap();
+        assert!(everything.removed().is_empty());
+        assert_eq!(everything.added().len(), new.len());
+    }
+
+    #[test]
+    fn added_labels_avoid_every_taken_label() {
+        let old = quads(
+            "@prefix ex: <http://example.org/> .\n\
+             ex:C ex:sub _:b . _:b ex:on ex:q .\n",
+        );
+        let new = quads(
+            "@prefix ex: <http://example.org/> .\n\
+             ex:C ex:sub _:b . _:b ex:on ex:r .\n\
+             ex:C ex:sub _:b_1 . _:b_1 ex:on ex:s .\n",
+        );
+        let diff = diff(&old, &new).unwrap();
+        assert_eq!(diff.removed(), &old[..]);
+        let mut added: Vec<String> = diff.added().iter().flat_map(labels).collect();
+        added.sort();
+        added.dedup();
+        // `_:b` is renamed past `_:b_1`, which the new side already uses.
+        assert_eq!(added, ["b_1", "b_2"], "{diff}");
+
+        let mut patched: Vec<Quad> = old
+            .iter()
+            .filter(|quad| !diff.removed().contains(quad))
+            .cloned()
+            .collect();
+        patched.extend(diff.added().iter().cloned());
+        assert!(super::diff(&patched, &new).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,530 @@
+use std::io::{self, BufReader, Read, Write};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::rs::binary::{BinaryReader, BinarySerializer};
+use crate::rs::data_factory::{FactoryQuads, QuadImport, with_factory};
+use crate::rs::jsonld::JsonLdProcessor;
+use crate::rs::parse_error::{ParseError, SyntaxError};
+use crate::rs::quad::Quad;
+use crate::rs::rdf_json;
+use crate::rs::rdfxml::{RdfXmlParser, RdfXmlSerializer};
+use crate::rs::stream::Quads;
+use crate::rs::turtle::{TurtleParser, TurtleReader, TurtleSerializer, TurtleSyntax};
+
+/// The RDF serializations this crate reads and writes.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RdfFormat {
+    Turtle,
+    TriG,
+    NTriples,
+    NQuads,
+    RdfXml,
+    JsonLd,
+    RdfJson,
+    /// The format of [`crate::rs::binary`].
+    Binary,
+    /// Needs the `jelly` feature.
+    Jelly,
+    /// Read only, and needs the `hdt` feature.
+    Hdt,
+}
+
+impl RdfFormat {
+    pub const ALL: [RdfFormat; 10] = [
+        RdfFormat::Turtle,
+        RdfFormat::TriG,
+        RdfFormat::NTriples,
+        RdfFormat::NQuads,
+        RdfFormat::RdfXml,
+        RdfFormat::JsonLd,
+        RdfFormat::RdfJson,
+        RdfFormat::Binary,
+        RdfFormat::Jelly,
+        RdfFormat::Hdt,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RdfFormat::Turtle => "Turtle",
+            RdfFormat::TriG => "TriG",
+            RdfFormat::NTriples => "N-Triples",
+            RdfFormat::NQuads => "N-Quads",
+            RdfFormat::RdfXml => "RDF/XML",
+            RdfFormat::JsonLd => "JSON-LD",
+            RdfFormat::RdfJson => "RDF/JSON",
+            RdfFormat::Binary => "binary RDF",
+            RdfFormat::Jelly => "Jelly",
+            RdfFormat::Hdt => "HDT",
+        }
+    }
+
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            RdfFormat::Turtle => "text/turtle",
+            RdfFormat::TriG => "application/trig",
+            RdfFormat::NTriples => "application/n-triples",
+            RdfFormat::NQuads => "application/n-quads",
+            RdfFormat::RdfXml => "application/rdf+xml",
+            RdfFormat::JsonLd => "application/ld+json",
+            RdfFormat::RdfJson => "application/rdf+json",
+            RdfFormat::Binary => "application/x-rdf-binary",
+            RdfFormat::Jelly => "application/x-jelly-rdf",
+            RdfFormat::Hdt => "application/vnd.hdt",
+        }
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            RdfFormat::Turtle => "ttl",
+            RdfFormat::TriG => "trig",
+            RdfFormat::NTriples => "nt",
+            RdfFormat::NQuads => "nq",
+            RdfFormat::RdfXml => "rdf",
+            RdfFormat::JsonLd => "jsonld",
+            RdfFormat::RdfJson => "rj",
+            RdfFormat::Binary => "rdfb",
+            RdfFormat::Jelly => "jelly",
+            RdfFormat::Hdt => "hdt",
+        }
+    }
+
+    /// Whether the format has named graphs.
+    pub fn supports_datasets(&self) -> bool {
+        matches!(
+            self,
+            RdfFormat::TriG
+                | RdfFormat::NQuads
+                | RdfFormat::JsonLd
+                | RdfFormat::Binary
+                | RdfFormat::Jelly
+        )
+    }
+
+    /// Looks up a format by media type, ignoring parameters such as
+    /// `charset` and letter case.
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        let essence = media_type.split(';').next()?.trim().to_ascii_lowercase();
+        match essence.as_str() {
+            "text/turtle" | "application/x-turtle" => Some(RdfFormat::Turtle),
+            "application/trig" | "application/x-trig" => Some(RdfFormat::TriG),
+            "application/n-triples" | "text/plain" => Some(RdfFormat::NTriples),
+            "application/n-quads" | "text/x-nquads" => Some(RdfFormat::NQuads),
+            "application/rdf+xml" | "application/xml" | "text/xml" => Some(RdfFormat::RdfXml),
+            "application/ld+json" | "application/json" => Some(RdfFormat::JsonLd),
+            "application/rdf+json" => Some(RdfFormat::RdfJson),
+            "application/x-rdf-binary" => Some(RdfFormat::Binary),
+            "application/x-jelly-rdf" => Some(RdfFormat::Jelly),
+            "application/vnd.hdt" => Some(RdfFormat::Hdt),
+            _ => None,
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "ttl" => Some(RdfFormat::Turtle),
+            "trig" => Some(RdfFormat::TriG),
+            "nt" => Some(RdfFormat::NTriples),
+            "nq" => Some(RdfFormat::NQuads),
+            "rdf" | "owl" | "xml" => Some(RdfFormat::RdfXml),
+            "jsonld" | "json" => Some(RdfFormat::JsonLd),
+            "rj" => Some(RdfFormat::RdfJson),
+            "rdfb" => Some(RdfFormat::Binary),
+            "jelly" => Some(RdfFormat::Jelly),
+            "hdt" => Some(RdfFormat::Hdt),
+            _ => None,
+        }
+    }
+
+    fn turtle_syntax(&self) -> Option<TurtleSyntax> {
+        match self {
+            RdfFormat::Turtle => Some(TurtleSyntax::Turtle),
+            RdfFormat::TriG => Some(TurtleSyntax::TriG),
+            RdfFormat::NTriples => Some(TurtleSyntax::NTriples),
+            RdfFormat::NQuads => Some(TurtleSyntax::NQuads),
+            _ => None,
+        }
+    }
+}
+
+static XML_ELEMENT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^<[A-Za-z_][\w.-]*(:[A-Za-z_][\w.-]*)?[\s/>]").unwrap());
+
+static RDF_JSON_TERM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""type"\s*:\s*"(uri|bnode|literal|triple)""#).unwrap());
+
+/// A line holding a single N-Triples or N-Quads statement. Triple terms are
+/// matched one level deep.
+static LINE_STATEMENT: LazyLock<Regex> = LazyLock::new(|| {
+    let term = r#"(?:<[^<>"{}|^`\\\s]*>|_:[^\s<>().]+(?:\.[^\s<>().]+)*|"(?:[^"\\\n]|\\.)*"(?:@[A-Za-z]+(?:-[A-Za-z0-9]+)*(?:--(?:ltr|rtl))?|\^\^<[^<>\s]*>)?)"#;
+    let nested = format!(r"<<\(?\s*{term}\s*{term}\s*{term}\s*\)?>>");
+    let any = format!("(?:{term}|{nested})");
+    Regex::new(&format!(
+        r"^\s*{any}\s*{any}\s*{any}\s*({any}\s*)?\.\s*(?:#.*)?$"
+    ))
+    .unwrap()
+});
+
+/// Strings and IRIs, whose content is ignored when looking for TriG graphs.
+static TURTLE_TOKEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?s)"""(?:[^"\\]|\\.|"[^"]|""[^"])*"""|'''(?:[^'\\]|\\.|'[^']|''[^'])*'''|"(?:[^"\\\n]|\\.)*"|'(?:[^'\\\n]|\\.)*'|<[^<>\s]*>|#[^\n]*"#).unwrap()
+});
+
+/// Guesses the format of a document from its first bytes, or `None` if they
+/// do not look like RDF.
+///
+/// Binary formats are recognized by their signature. Text is told apart by
+/// its first characters and keywords; statements that each fit on a line
+/// are taken for N-Triples or N-Quads, which are also valid Turtle or TriG.
+pub fn sniff(prefix: &[u8]) -> Option<RdfFormat> {
+    if prefix.starts_with(b"RDFB") {
+        return Some(RdfFormat::Binary);
+    }
+    if prefix.starts_with(b"$HDT") {
+        return Some(RdfFormat::Hdt);
+    }
+    if looks_like_jelly(prefix) {
+        return Some(RdfFormat::Jelly);
+    }
+
+    // The prefix may end in the middle of a character.
+    let text = match std::str::from_utf8(prefix) {
+        Ok(text) => text,
+        Err(error) if error.error_len().is_none() => {
+            std::str::from_utf8(&prefix[..error.valid_up_to()]).unwrap()
+        }
+        Err(_) => return None,
+    };
+    if text.contains('\0') {
+        return None;
+    }
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if text.is_empty() {
+        return None;
+    }
+
+    if text.starts_with("<?xml")
+        || text.starts_with("<!")
+        || (XML_ELEMENT.is_match(text) && text.contains("xmlns"))
+    {
+        return Some(RdfFormat::RdfXml);
+    }
+    if looks_like_json(text) {
+        if !text.contains("\"@") && RDF_JSON_TERM.is_match(text) {
+            return Some(RdfFormat::RdfJson);
+        }
+        return Some(RdfFormat::JsonLd);
+    }
+
+    // The last line may be cut short, unless it is the only one.
+    let mut lines: Vec<&str> = text.lines().collect();
+    if lines.len() > 1 && !text.ends_with('\n') {
+        lines.pop();
+    }
+    let mut line_based = Some(RdfFormat::NTriples);
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match LINE_STATEMENT.captures(line) {
+            Some(captures) if captures.get(1).is_some() => line_based = Some(RdfFormat::NQuads),
+            Some(_) => {}
+            None => {
+                line_based = None;
+                break;
+            }
+        }
+    }
+    if let Some(format) = line_based {
+        return Some(format);
+    }
+
+    let code = TURTLE_TOKEN.replace_all(text, " ");
+    if code.contains('{') {
+        Some(RdfFormat::TriG)
+    } else {
+        Some(RdfFormat::Turtle)
+    }
+}
+
+/// Whether `text` is JSON, or starts like a JSON object or array rather than
+/// a Turtle blank node or TriG graph: with a string key, or with an array of
+/// objects, arrays or strings.
+fn looks_like_json(text: &str) -> bool {
+    let Some(rest) = text.strip_prefix(['{', '[']) else {
+        return false;
+    };
+    if serde_json::from_str::<Value>(text).is_ok() {
+        return true;
+    }
+    let rest = rest.trim_start();
+    if text.starts_with('{') {
+        rest.starts_with('"')
+    } else {
+        rest.starts_with(['{', '[', '"'])
+    }
+}
+
+/// Jelly streams start with a delimited frame whose first row holds the
+/// stream options, i.e. a length, two nested field 1 keys and an options
+/// message.
+fn looks_like_jelly(prefix: &[u8]) -> bool {
+    fn skip_varint(bytes: &mut std::slice::Iter<u8>) -> bool {
+        bytes.take(10).any(|byte| byte & 0x80 == 0)
+    }
+
+    let mut bytes = prefix.iter();
+    if !skip_varint(&mut bytes) {
+        return false;
+    }
+    for _ in 0..2 {
+        if bytes.next() != Some(&0x0a) || !skip_varint(&mut bytes) {
+            return false;
+        }
+    }
+    // The options message starts with a field key, not text.
+    bytes
+        .next()
+        .is_some_and(|byte| byte & 0x07 <= 2 && *byte != b' ')
+}
+
+/// Reads quads in `format`. Formats that are not streamed, such as RDF/XML,
+/// are read whole before the first quad is returned.
+///
+/// Errors without a position, such as JSON-LD processing errors, point at the
+/// start of the document; invalid binary data is reported as an
+/// [`io::ErrorKind::InvalidData`] error.
+pub fn read_quads<'a>(format: RdfFormat, reader: impl Read + 'a) -> Quads<'a, ParseError> {
+    if let Some(syntax) = format.turtle_syntax() {
+        return Box::new(TurtleReader::new(
+            BufReader::new(reader),
+            TurtleParser::new(syntax),
+        ));
+    }
+    match format {
+        RdfFormat::Binary => match BinaryReader::new(reader) {
+            Ok(quads) => Box::new(quads.map(|quad| quad.map_err(invalid_data))),
+            Err(error) => error_quads(invalid_data(error)),
+        },
+        RdfFormat::Jelly => read_jelly(reader),
+        RdfFormat::Hdt => read_hdt(reader),
+        format => match read_document(format, reader) {
+            Ok(quads) => Box::new(quads.into_iter().map(Ok)),
+            Err(error) => error_quads(error),
+        },
+    }
+}
+
+/// [`read_quads`] with each quad converted by `factory`. The quads are still
+/// parsed as `rs` terms first, see [`data_factory`](crate::rs::data_factory).
+pub fn read_quads_with_factory<'a, F: QuadImport>(
+    format: RdfFormat,
+    reader: impl Read + 'a,
+    factory: F,
+) -> FactoryQuads<Quads<'a, ParseError>, F> {
+    with_factory(read_quads(format, reader), factory)
+}
+
+fn read_document(format: RdfFormat, reader: impl Read) -> Result<Vec<Quad>, ParseError> {
+    match format {
+        RdfFormat::RdfXml => RdfXmlParser::new().parse_read(reader),
+        RdfFormat::RdfJson => rdf_json::parse_read(reader),
+        _ => {
+            let document: Value = serde_json::from_reader(reader).map_err(|error| {
+                if error.is_io() {
+                    ParseError::Io(error.into())
+                } else {
+                    SyntaxError::new(&error.to_string(), error.line(), error.column()).into()
+                }
+            })?;
+            JsonLdProcessor::new()
+                .to_rdf(&document)
+                .map_err(|error| SyntaxError::new(&error.to_string(), 1, 1).into())
+        }
+    }
+}
+
+#[cfg(feature = "jelly")]
+fn read_jelly<'a>(reader: impl Read + 'a) -> Quads<'a, ParseError> {
+    use crate::rs::jelly::JellyReader;
+
+    match JellyReader::new(reader) {
+        Ok(quads) => Box::new(quads.map(|quad| quad.map_err(invalid_data))),
+        Err(error) => error_quads(invalid_data(error)),
+    }
+}
+
+#[cfg(not(feature = "jelly"))]
+fn read_jelly<'a>(_: impl Read + 'a) -> Quads<'a, ParseError> {
+    error_quads(unsupported("reading Jelly needs the jelly feature").into())
+}
+
+#[cfg(feature = "hdt")]
+fn read_hdt<'a>(mut reader: impl Read + 'a) -> Quads<'a, ParseError> {
+    use crate::rs::hdt::HdtDocument;
+
+    let mut bytes = Vec::new();
+    if let Err(error) = reader.read_to_end(&mut bytes) {
+        return error_quads(error.into());
+    }
+    match HdtDocument::from_bytes(bytes) {
+        Ok(document) => {
+            let quads: Vec<_> = document.triples_matching(None, None, None).collect();
+            Box::new(quads.into_iter().map(|quad| quad.map_err(invalid_data)))
+        }
+        Err(error) => error_quads(invalid_data(error)),
+    }
+}
+
+#[cfg(not(feature = "hdt"))]
+fn read_hdt<'a>(_: impl Read + 'a) -> Quads<'a, ParseError> {
+    error_quads(unsupported("reading HDT needs the hdt feature").into())
+}
+
+fn error_quads<'a>(error: ParseError) -> Quads<'a, ParseError> {
+    Box::new(std::iter::once(Err(error)))
+}
+
+fn invalid_data(error: impl std::error::Error + Send + Sync + 'static) -> ParseError {
+    ParseError::Io(io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+fn unsupported(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, message)
+}
+
+/// Writes `quads` in `format`. Quads the format cannot express fail with
+/// [`io::ErrorKind::InvalidInput`], formats this build cannot write with
+/// [`io::ErrorKind::Unsupported`].
+pub fn write_quads<'a>(
+    format: RdfFormat,
+    mut writer: impl Write,
+    quads: impl IntoIterator<Item = &'a Quad>,
+) -> io::Result<()> {
+    if let Some(syntax) = format.turtle_syntax() {
+        return TurtleSerializer::new(syntax).serialize(writer, quads);
+    }
+    match format {
+        RdfFormat::RdfXml => RdfXmlSerializer::new().serialize(writer, quads),
+        RdfFormat::JsonLd => {
+            let document = JsonLdProcessor::new()
+                .from_rdf(quads)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+            serde_json::to_writer_pretty(&mut writer, &document)?;
+            writer.write_all(b"\n")?;
+            writer.flush()
+        }
+        RdfFormat::RdfJson => rdf_json::serialize(writer, quads),
+        RdfFormat::Binary => BinarySerializer::new().serialize(writer, quads),
+        RdfFormat::Jelly => write_jelly(writer, quads),
+        _ => Err(unsupported(&format!("{} cannot be written", format.name()))),
+    }
+}
+
+#[cfg(feature = "jelly")]
+fn write_jelly<'a>(
+    writer: impl Write,
+    quads: impl IntoIterator<Item = &'a Quad>,
+) -> io::Result<()> {
+    crate::rs::jelly::JellySerializer::new().serialize(writer, quads)
+}
+
+#[cfg(not(feature = "jelly"))]
+fn write_jelly<'a>(_: impl Write, _: impl IntoIterator<Item = &'a Quad>) -> io::Result<()> {
+    Err(unsupported("writing Jelly needs the jelly feature"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::turtle::parse_str;
+
+    const INPUT: &str = "@prefix ex: <http://example.org/> .\n\
+                         ex:a ex:p \"caf\u{e9}\"@fr, 1, ex:c .\n\
+                         ex:g { ex:a ex:p ex:b . }\n";
+
+    #[test]
+    fn formats_are_sniffed() {
+        let cases: [(&[u8], Option<RdfFormat>); 18] = [
+            (b"RDFB\x01\x00", Some(RdfFormat::Binary)),
+            (b"$HDT\x01", Some(RdfFormat::Hdt)),
+            (b"\x10\x0a\x0e\x0a\x0c\x10\x02", Some(RdfFormat::Jelly)),
+            (b"<?xml version=\"1.0\"?>", Some(RdfFormat::RdfXml)),
+            (b"<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">", Some(RdfFormat::RdfXml)),
+            (b"\xef\xbb\xbf{ \"@context\": {}, \"@id\": \"http://a\" }", Some(RdfFormat::JsonLd)),
+            (b"[]", Some(RdfFormat::JsonLd)),
+            (b"[ { \"@id\": \"http://a\"", Some(RdfFormat::JsonLd)),
+            (b"[ <http://b> <http://c> ] <http://d> <http://e> .\n", Some(RdfFormat::Turtle)),
+            (b"{ <http://a> <http://b> <http://c> . }\n", Some(RdfFormat::TriG)),
+            (b"{ \"http://a\": { \"http://b\": [ { \"type\": \"uri\"", Some(RdfFormat::RdfJson)),
+            (b"<http://a> <http://b> \"c\"@en .\n_:d <http://b> <<( <http://a> <http://b> <http://c> )>> .\n<http://a> <http://", Some(RdfFormat::NTriples)),
+            (b"# comment\n<http://a> <http://b> <http://c> <http://g> .\n", Some(RdfFormat::NQuads)),
+            (b"<http://a> <http://b> \"c\"@en--rtl <http://g> .\n", Some(RdfFormat::NQuads)),
+            (b"@prefix ex: <http://example.org/> .\nex:a ex:b \"{\" .", Some(RdfFormat::Turtle)),
+            (b"PREFIX ex: <http://example.org/>\nex:g { ex:a ex:b ex:c }", Some(RdfFormat::TriG)),
+            (b"\x00\x01\x02", None),
+            (b"  \n", None),
+        ];
+        for (prefix, expected) in cases {
+            assert_eq!(
+                sniff(prefix),
+                expected,
+                "{}",
+                String::from_utf8_lossy(prefix)
+            );
+        }
+
+        for format in RdfFormat::ALL {
+            assert_eq!(
+                RdfFormat::from_extension(format.file_extension()),
+                Some(format)
+            );
+            assert_eq!(
+                RdfFormat::from_media_type(format.media_type()),
+                Some(format)
+            );
+        }
+        assert_eq!(
+            RdfFormat::from_media_type("Text/Turtle; charset=utf-8"),
+            Some(RdfFormat::Turtle)
+        );
+    }
+
+    #[test]
+    fn formats_round_trip() {
+        let quads = parse_str(TurtleSyntax::TriG, INPUT, None).unwrap();
+        let triples: Vec<Quad> = quads[..3].to_vec();
+        for format in RdfFormat::ALL {
+            if format == RdfFormat::Hdt || (format == RdfFormat::Jelly && !cfg!(feature = "jelly"))
+            {
+                continue;
+            }
+            let expected = if format.supports_datasets() {
+                &quads
+            } else {
+                &triples
+            };
+            let mut output = Vec::new();
+            write_quads(format, &mut output, expected).unwrap();
+            assert_eq!(sniff(&output), Some(format), "{}", format.name());
+            let read: Vec<Quad> = read_quads(format, output.as_slice())
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(read.len(), expected.len(), "{}", format.name());
+            for quad in expected {
+                assert!(read.contains(quad), "{}: missing {quad:?}", format.name());
+            }
+        }
+
+        let error = write_quads(RdfFormat::Hdt, Vec::new(), &quads).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+        let error = read_quads(RdfFormat::JsonLd, "{ \"@id\": ".as_bytes())
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(error, ParseError::Syntax(_)), "{error}");
+    }
+}
@@ -0,0 +1,1119 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::Display;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::literal::{LanguageDirection, Literal};
+use crate::rs::named_node::NamedNode;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::stream::{Quads, Source};
+use crate::rs::term_like::TermLike;
+use crate::rs::vocab::xsd;
+
+const COOKIE: &[u8] = b"$HDT";
+const DICTIONARY_FOUR: &str = "<http://purl.org/HDT/hdt#dictionaryFour>";
+const TRIPLES_BITMAP: &str = "<http://purl.org/HDT/hdt#triplesBitmap>";
+
+#[derive(Debug)]
+pub enum HdtError {
+    Io(io::Error),
+    /// The file is not an HDT file this module can read.
+    Format(String),
+}
+
+impl Display for HdtError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HdtError::Io(error) => write!(f, "{error}"),
+            HdtError::Format(message) => write!(f, "invalid HDT file: {message}"),
+        }
+    }
+}
+
+impl Error for HdtError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HdtError::Io(error) => Some(error),
+            HdtError::Format(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for HdtError {
+    fn from(error: io::Error) -> Self {
+        HdtError::Io(error)
+    }
+}
+
+fn format_error<T>(message: &str) -> Result<T, HdtError> {
+    Err(HdtError::Format(message.to_owned()))
+}
+
+enum Storage {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+/// A read-only HDT file with a Four-Section dictionary and bitmap triples.
+///
+/// Opening only reads the section headers; terms and triples are decoded
+/// from the mapped file as queries need them. Checksums are not verified,
+/// so damaged terms only surface as errors from the queries reading them.
+/// Patterns with a bound subject use the SPO index; the others scan every
+/// triple, as the optional secondary indexes of HDT are not read.
+pub struct HdtDocument {
+    storage: Storage,
+    header: (usize, usize),
+    dictionary: Dictionary,
+    triples: BitmapTriples,
+}
+
+impl HdtDocument {
+    /// Memory-maps the file at `path`.
+    ///
+    /// The caller must keep the file unchanged while the document is open:
+    /// it is read through the map without copying, so writing to or
+    /// truncating the file, even from another process, is undefined
+    /// behavior.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, HdtError> {
+        let file = File::open(path)?;
+        // SAFETY: the file is only read, and `open` requires callers to keep
+        // it unchanged.
+        let mmap = unsafe { Mmap::map(&file)? };
+        Self::load(Storage::Mapped(mmap))
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, HdtError> {
+        Self::load(Storage::Owned(bytes))
+    }
+
+    fn load(storage: Storage) -> Result<Self, HdtError> {
+        let bytes = match &storage {
+            Storage::Mapped(mmap) => &mmap[..],
+            Storage::Owned(bytes) => &bytes[..],
+        };
+        let mut input = Input { bytes, position: 0 };
+
+        let global = input.control_info(1)?;
+        if !global.format.contains("hdt#HDTv1") {
+            return format_error(&format!("unsupported HDT version {}", global.format));
+        }
+
+        let header = input.control_info(2)?;
+        let length = header
+            .property("length")
+            .and_then(|length| length.parse::<usize>().ok())
+            .ok_or_else(|| HdtError::Format("the header has no length".to_owned()))?;
+        let header = (input.position, input.position + length);
+        input.skip(length)?;
+
+        let dictionary = input.control_info(3)?;
+        if dictionary.format != DICTIONARY_FOUR {
+            return format_error(&format!("unsupported dictionary {}", dictionary.format));
+        }
+        let dictionary = Dictionary {
+            shared: input.section()?,
+            subjects: input.section()?,
+            predicates: input.section()?,
+            objects: input.section()?,
+        };
+
+        let triples = input.control_info(4)?;
+        if triples.format != TRIPLES_BITMAP {
+            return format_error(&format!("unsupported triples {}", triples.format));
+        }
+        if triples.property("order").is_some_and(|order| order != "1") {
+            return format_error("only SPO ordered triples are supported");
+        }
+        let triples = BitmapTriples {
+            bitmap_y: input.bitmap()?,
+            bitmap_z: input.bitmap()?,
+            sequence_y: input.sequence()?,
+            sequence_z: input.sequence()?,
+        };
+        if triples.sequence_y.len != triples.bitmap_y.len
+            || triples.sequence_z.len != triples.bitmap_z.len
+        {
+            return format_error("the triple bitmaps and sequences differ in length");
+        }
+
+        Ok(Self {
+            storage,
+            header,
+            dictionary,
+            triples,
+        })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match &self.storage {
+            Storage::Mapped(mmap) => mmap,
+            Storage::Owned(bytes) => bytes,
+        }
+    }
+
+    /// The N-Triples metadata of the file, as written by its producer.
+    pub fn header(&self) -> &str {
+        std::str::from_utf8(&self.bytes()[self.header.0..self.header.1]).unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.triples.sequence_z.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Lazily decodes the triples matching every given term, `None`
+    /// matching anything, as default graph quads.
+    ///
+    /// Yields an error, and then stops, if the file turns out to be damaged.
+    pub fn triples_matching(
+        &self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+    ) -> HdtTriples<'_> {
+        let bytes = self.bytes();
+        let mut triples = HdtTriples {
+            document: self,
+            pattern: [None; 3],
+            error: None,
+            subject: 1,
+            y: 0,
+            z: 0,
+            z_end: 0,
+        };
+
+        match self.pattern(subject, predicate, object) {
+            Ok(Some(pattern)) => triples.pattern = pattern,
+            Ok(None) => return triples,
+            Err(error) => {
+                triples.error = Some(error);
+                return triples;
+            }
+        }
+
+        let index = &self.triples;
+        match triples.pattern[0] {
+            Some(subject) => {
+                if subject > index.bitmap_y.ones() {
+                    return triples;
+                }
+                let y = index.bitmap_y.list_start(bytes, subject - 1);
+                let y_end = index.bitmap_y.list_start(bytes, subject);
+                triples.subject = subject;
+                triples.y = y;
+                triples.z = index.bitmap_z.list_start(bytes, y);
+                triples.z_end = index.bitmap_z.list_start(bytes, y_end);
+            }
+            None => triples.z_end = index.bitmap_z.len,
+        }
+        triples
+    }
+
+    /// The IDs of the bound terms, or `None` if one of them is not in the
+    /// dictionary and so matches nothing.
+    fn pattern(
+        &self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+    ) -> Result<Option<[Option<u64>; 3]>, HdtError> {
+        let bytes = self.bytes();
+        let dictionary = &self.dictionary;
+        let mut pattern = [None; 3];
+
+        if let Some(subject) = subject.filter(|s| !matches!(s, QuadSubject::Variable(_))) {
+            let Some(term) = subject_string(subject) else {
+                return Ok(None);
+            };
+            let Some(id) = dictionary.subject_id(bytes, &term)? else {
+                return Ok(None);
+            };
+            pattern[0] = Some(id);
+        }
+        if let Some(QuadPredicate::NamedNode(predicate)) = predicate {
+            let Some(id) = dictionary.predicates.locate(bytes, predicate.value())? else {
+                return Ok(None);
+            };
+            pattern[1] = Some(id);
+        }
+        if let Some(object) = object.filter(|o| !matches!(o, QuadObject::Variable(_))) {
+            let Some(id) = dictionary.object_id(bytes, &object_string(object))? else {
+                return Ok(None);
+            };
+            pattern[2] = Some(id);
+        }
+        Ok(Some(pattern))
+    }
+}
+
+impl Source for HdtDocument {
+    type Error = HdtError;
+
+    /// Only the default graph has triples.
+    fn match_quads<'a>(
+        &'a self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> Quads<'a, HdtError> {
+        if graph.is_some_and(|graph| {
+            !matches!(graph, QuadGraph::DefaultGraph(_) | QuadGraph::Variable(_))
+        }) {
+            return Box::new(std::iter::empty());
+        }
+        Box::new(self.triples_matching(subject, predicate, object))
+    }
+}
+
+/// Iterator over the triples of an [`HdtDocument`] matching a pattern.
+pub struct HdtTriples<'a> {
+    document: &'a HdtDocument,
+    /// Subject, predicate and object IDs, `None` matching anything.
+    pattern: [Option<u64>; 3],
+    /// An error met while looking up the pattern, yielded first.
+    error: Option<HdtError>,
+    /// The subject ID, and positions in the predicate and object layers, of
+    /// the next triple.
+    subject: u64,
+    y: u64,
+    z: u64,
+    z_end: u64,
+}
+
+impl Iterator for HdtTriples<'_> {
+    type Item = Result<Quad, HdtError>;
+
+    fn next(&mut self) -> Option<Result<Quad, HdtError>> {
+        if let Some(error) = self.error.take() {
+            return Some(Err(error));
+        }
+        let bytes = self.document.bytes();
+        let triples = &self.document.triples;
+        while self.z < self.z_end {
+            let ids = [
+                self.subject,
+                triples.sequence_y.get(bytes, self.y),
+                triples.sequence_z.get(bytes, self.z),
+            ];
+            if triples.bitmap_z.get(bytes, self.z) {
+                if triples.bitmap_y.get(bytes, self.y) {
+                    self.subject += 1;
+                }
+                self.y += 1;
+            }
+            self.z += 1;
+
+            let matches = ids
+                .iter()
+                .zip(self.pattern)
+                .all(|(id, pattern)| pattern.is_none_or(|pattern| pattern == *id));
+            if matches {
+                let quad = self.document.dictionary.triple(bytes, ids);
+                if quad.is_err() {
+                    self.z = self.z_end;
+                }
+                return Some(quad);
+            }
+        }
+        None
+    }
+}
+
+/// Reads the structures of an HDT file, failing on truncated input.
+struct Input<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+struct ControlInfo {
+    format: String,
+    properties: String,
+}
+
+impl ControlInfo {
+    fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .split(';')
+            .filter_map(|property| property.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+}
+
+impl Input<'_> {
+    fn skip(&mut self, length: usize) -> Result<usize, HdtError> {
+        let start = self.position;
+        match start.checked_add(length) {
+            Some(end) if end <= self.bytes.len() => {
+                self.position = end;
+                Ok(start)
+            }
+            _ => format_error("unexpected end of file"),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, HdtError> {
+        let position = self.skip(1)?;
+        Ok(self.bytes[position])
+    }
+
+    fn vbyte(&mut self) -> Result<u64, HdtError> {
+        let (value, length) = vbyte(&self.bytes[self.position..])
+            .ok_or_else(|| HdtError::Format("invalid variable-length integer".to_owned()))?;
+        self.position += length;
+        Ok(value)
+    }
+
+    fn c_string(&mut self) -> Result<String, HdtError> {
+        let rest = &self.bytes[self.position..];
+        let Some(length) = rest.iter().position(|b| *b == 0) else {
+            return format_error("unterminated string");
+        };
+        self.position += length + 1;
+        Ok(String::from_utf8_lossy(&rest[..length]).into_owned())
+    }
+
+    fn control_info(&mut self, kind: u8) -> Result<ControlInfo, HdtError> {
+        let start = self.skip(COOKIE.len())?;
+        if &self.bytes[start..self.position] != COOKIE || self.byte()? != kind {
+            return format_error("expected a control information block");
+        }
+        let format = self.c_string()?;
+        let properties = self.c_string()?;
+        self.skip(2)?; // CRC16
+        Ok(ControlInfo { format, properties })
+    }
+
+    fn sequence(&mut self) -> Result<Sequence, HdtError> {
+        if self.byte()? != 1 {
+            return format_error("only log64 sequences are supported");
+        }
+        let bits = self.byte()?;
+        if bits > 64 {
+            return format_error("sequence entries are wider than 64 bits");
+        }
+        let len = self.vbyte()?;
+        self.skip(1)?; // CRC8
+        let size = (u128::from(bits) * u128::from(len)).div_ceil(8);
+        let offset = self.skip(usize::try_from(size).unwrap_or(usize::MAX))?;
+        self.skip(4)?; // CRC32
+        Ok(Sequence { offset, bits, len })
+    }
+
+    fn bitmap(&mut self) -> Result<Bitmap, HdtError> {
+        if self.byte()? != 1 {
+            return format_error("unsupported bitmap type");
+        }
+        let len = self.vbyte()?;
+        self.skip(1)?; // CRC8
+        let size = usize::try_from(len.div_ceil(8)).unwrap_or(usize::MAX);
+        let offset = self.skip(size)?;
+        self.skip(4)?; // CRC32
+
+        // Ones before each block, for rank and select without decoding.
+        let data = &self.bytes[offset..offset + size];
+        let mut ranks = Vec::with_capacity(size / BLOCK_BYTES + 1);
+        let mut ones = 0;
+        for block in data.chunks(BLOCK_BYTES) {
+            ranks.push(ones);
+            ones += block.iter().map(|b| u64::from(b.count_ones())).sum::<u64>();
+        }
+        ranks.push(ones);
+        Ok(Bitmap { offset, len, ranks })
+    }
+
+    fn section(&mut self) -> Result<Section, HdtError> {
+        if self.byte()? != 2 {
+            return format_error("only plain front coding dictionary sections are supported");
+        }
+        let count = self.vbyte()?;
+        let size = self.vbyte()?;
+        let block_size = self.vbyte()?;
+        self.skip(1)?; // CRC8
+        let blocks = self.sequence()?;
+        let size = usize::try_from(size).unwrap_or(usize::MAX);
+        let data = self.skip(size)?;
+        self.skip(4)?; // CRC32
+        if block_size == 0 && count > 0 {
+            return format_error("empty dictionary blocks");
+        }
+        Ok(Section {
+            count,
+            block_size,
+            blocks,
+            data: data..data + size,
+        })
+    }
+}
+
+/// Decodes an HDT variable-length integer, whose last byte has its high bit
+/// set, returning it with its length.
+fn vbyte(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (index, byte) in bytes.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 != 0 {
+            return Some((value, index + 1));
+        }
+    }
+    None
+}
+
+/// Packed integers of `bits` bits each, least significant bit first.
+struct Sequence {
+    offset: usize,
+    bits: u8,
+    len: u64,
+}
+
+impl Sequence {
+    fn get(&self, bytes: &[u8], index: u64) -> u64 {
+        if self.bits == 0 || index >= self.len {
+            return 0;
+        }
+        let bit = index * u64::from(self.bits);
+        let start = self.offset + (bit / 8) as usize;
+        let end =
+            (self.offset + (self.len * u64::from(self.bits)).div_ceil(8) as usize).min(start + 16);
+        let mut window = [0u8; 16];
+        window[..end - start].copy_from_slice(&bytes[start..end]);
+        let value = u128::from_le_bytes(window) >> (bit % 8);
+        (value & ((1u128 << self.bits) - 1)) as u64
+    }
+}
+
+const BLOCK_BYTES: usize = 64;
+
+/// Bits least significant first, where a one ends each list of children.
+struct Bitmap {
+    offset: usize,
+    len: u64,
+    ranks: Vec<u64>,
+}
+
+impl Bitmap {
+    fn get(&self, bytes: &[u8], index: u64) -> bool {
+        index < self.len && bytes[self.offset + (index / 8) as usize] & (1 << (index % 8)) != 0
+    }
+
+    fn ones(&self) -> u64 {
+        self.ranks.last().copied().unwrap_or_default()
+    }
+
+    /// The position of the `count`-th one, counting from 1.
+    fn select(&self, bytes: &[u8], count: u64) -> Option<u64> {
+        if count == 0 || count > self.ones() {
+            return None;
+        }
+        let block = self.ranks.partition_point(|ones| *ones < count) - 1;
+        let mut remaining = count - self.ranks[block];
+        let start = self.offset + block * BLOCK_BYTES;
+        for (index, byte) in bytes[start..].iter().enumerate() {
+            let ones = u64::from(byte.count_ones());
+            if remaining <= ones {
+                let mut byte = *byte;
+                for _ in 1..remaining {
+                    byte &= byte - 1;
+                }
+                let bit = (block * BLOCK_BYTES + index) as u64 * 8;
+                return Some(bit + u64::from(byte.trailing_zeros()));
+            }
+            remaining -= ones;
+        }
+        None
+    }
+
+    /// The position of the first child of the list after the `count` first
+    /// ones.
+    fn list_start(&self, bytes: &[u8], count: u64) -> u64 {
+        match count {
+            0 => 0,
+            count => self.select(bytes, count).map_or(self.len, |one| one + 1),
+        }
+    }
+}
+
+struct BitmapTriples {
+    bitmap_y: Bitmap,
+    bitmap_z: Bitmap,
+    sequence_y: Sequence,
+    sequence_z: Sequence,
+}
+
+/// A plain front coded dictionary section: blocks of sorted strings, each
+/// starting with a full string followed by strings sharing a prefix with
+/// the previous one.
+struct Section {
+    count: u64,
+    block_size: u64,
+    blocks: Sequence,
+    data: std::ops::Range<usize>,
+}
+
+impl Section {
+    fn block<'a>(&self, bytes: &'a [u8], block: u64) -> Result<&'a [u8], HdtError> {
+        let start = usize::try_from(self.blocks.get(bytes, block))
+            .ok()
+            .and_then(|offset| self.data.start.checked_add(offset))
+            .filter(|start| *start <= self.data.end);
+        match start {
+            Some(start) => Ok(&bytes[start..self.data.end]),
+            None => format_error("a dictionary block starts past its section"),
+        }
+    }
+
+    /// Calls `visit` with each string of `block` until it returns false.
+    fn scan(
+        &self,
+        bytes: &[u8],
+        block: u64,
+        mut visit: impl FnMut(&[u8]) -> bool,
+    ) -> Result<(), HdtError> {
+        let mut data = self.block(bytes, block)?;
+        let mut current = Vec::new();
+        for index in 0..self.block_size {
+            if block * self.block_size + index >= self.count {
+                break;
+            }
+            if index > 0 {
+                let Some((shared, length)) = vbyte(data) else {
+                    return format_error("a dictionary string is truncated");
+                };
+                current.truncate(shared as usize);
+                data = &data[length..];
+            }
+            let Some(end) = data.iter().position(|b| *b == 0) else {
+                return format_error("a dictionary string is truncated");
+            };
+            current.extend_from_slice(&data[..end]);
+            data = &data[end + 1..];
+            if !visit(&current) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// The string with `id`, counting from 1.
+    fn extract(&self, bytes: &[u8], id: u64) -> Result<String, HdtError> {
+        if id == 0 || id > self.count {
+            return format_error(&format!("the term ID {id} is not in the dictionary"));
+        }
+        let (block, index) = ((id - 1) / self.block_size, (id - 1) % self.block_size);
+        let mut position = 0;
+        let mut result = None;
+        self.scan(bytes, block, |string| {
+            if position == index {
+                result = Some(String::from_utf8_lossy(string).into_owned());
+                return false;
+            }
+            position += 1;
+            true
+        })?;
+        result.map_or_else(
+            || format_error(&format!("the term ID {id} is not in the dictionary")),
+            Ok,
+        )
+    }
+
+    /// The ID of `string`, found by binary search over the first strings of
+    /// the blocks.
+    fn locate(&self, bytes: &[u8], string: &str) -> Result<Option<u64>, HdtError> {
+        let blocks = self.count.div_ceil(self.block_size.max(1));
+        let first = |block: u64| {
+            let data = self.block(bytes, block)?;
+            let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+            Ok::<_, HdtError>(&data[..end])
+        };
+        let (mut low, mut high) = (0, blocks);
+        while low < high {
+            let middle = (low + high) / 2;
+            match first(middle)?.cmp(string.as_bytes()) {
+                Ordering::Greater => high = middle,
+                _ => low = middle + 1,
+            }
+        }
+        let Some(block) = low.checked_sub(1) else {
+            return Ok(None);
+        };
+
+        let mut position = 0;
+        let mut found = None;
+        self.scan(bytes, block, |candidate| {
+            match candidate.cmp(string.as_bytes()) {
+                Ordering::Less => {
+                    position += 1;
+                    true
+                }
+                Ordering::Equal => {
+                    found = Some(block * self.block_size + position + 1);
+                    false
+                }
+                Ordering::Greater => false,
+            }
+        })?;
+        Ok(found)
+    }
+}
+
+/// Terms shared by subjects and objects have the lowest IDs of both.
+struct Dictionary {
+    shared: Section,
+    subjects: Section,
+    predicates: Section,
+    objects: Section,
+}
+
+impl Dictionary {
+    fn subject_id(&self, bytes: &[u8], term: &str) -> Result<Option<u64>, HdtError> {
+        match self.shared.locate(bytes, term)? {
+            Some(id) => Ok(Some(id)),
+            None => Ok(self
+                .subjects
+                .locate(bytes, term)?
+                .map(|id| id + self.shared.count)),
+        }
+    }
+
+    fn object_id(&self, bytes: &[u8], term: &str) -> Result<Option<u64>, HdtError> {
+        match self.shared.locate(bytes, term)? {
+            Some(id) => Ok(Some(id)),
+            None => Ok(self
+                .objects
+                .locate(bytes, term)?
+                .map(|id| id + self.shared.count)),
+        }
+    }
+
+    fn term(&self, bytes: &[u8], id: u64, section: &Section) -> Result<String, HdtError> {
+        match id.checked_sub(self.shared.count) {
+            Some(id) if id > 0 => section.extract(bytes, id),
+            _ => self.shared.extract(bytes, id),
+        }
+    }
+
+    fn triple(
+        &self,
+        bytes: &[u8],
+        [subject, predicate, object]: [u64; 3],
+    ) -> Result<Quad, HdtError> {
+        let subject = match parse_term(&self.term(bytes, subject, &self.subjects)?) {
+            QuadObject::NamedNode(node) => QuadSubject::NamedNode(node),
+            QuadObject::BlankNode(node) => QuadSubject::BlankNode(node),
+            _ => return format_error("a subject is a literal"),
+        };
+        let predicate = NamedNode::new(&self.predicates.extract(bytes, predicate)?);
+        let object = parse_term(&self.term(bytes, object, &self.objects)?);
+        Ok(Quad::new(
+            &subject,
+            &QuadPredicate::NamedNode(predicate),
+            &object,
+            None,
+        ))
+    }
+}
+
+/// Parses a dictionary string: an IRI, `_:label` or an unescaped literal in
+/// N-Triples form.
+fn parse_term(term: &str) -> QuadObject {
+    if let Some(label) = term.strip_prefix("_:") {
+        return QuadObject::BlankNode(BlankNode::new(label));
+    }
+    let Some(rest) = term.strip_prefix('"') else {
+        return QuadObject::NamedNode(NamedNode::new(term));
+    };
+    let (value, suffix) = rest.rsplit_once('"').unwrap_or((rest, ""));
+    let literal = if let Some(language) = suffix.strip_prefix('@') {
+        match language.split_once("--") {
+            Some((language, direction)) => Literal::new(
+                value,
+                Some(language),
+                direction.parse::<LanguageDirection>().ok().as_ref(),
+                None,
+            ),
+            None => Literal::new(value, Some(language), None, None),
+        }
+    } else if let Some(datatype) = suffix
+        .strip_prefix("^^<")
+        .and_then(|datatype| datatype.strip_suffix('>'))
+    {
+        Literal::new(value, None, None, Some(&NamedNode::new(datatype)))
+    } else {
+        Literal::new(value, None, None, None)
+    };
+    QuadObject::Literal(literal)
+}
+
+fn subject_string(subject: &QuadSubject) -> Option<String> {
+    match subject {
+        QuadSubject::NamedNode(node) => Some(node.value().to_owned()),
+        QuadSubject::BlankNode(node) => Some(format!("_:{}", node.value())),
+        QuadSubject::Variable(_) | QuadSubject::Quad(_) => None,
+    }
+}
+
+fn object_string(object: &QuadObject) -> String {
+    match object {
+        QuadObject::NamedNode(node) => node.value().to_owned(),
+        QuadObject::BlankNode(node) => format!("_:{}", node.value()),
+        QuadObject::Literal(literal) => {
+            let mut term = format!("\"{}\"", literal.value());
+            if !literal.language().is_empty() {
+                term.push('@');
+                term.push_str(literal.language());
+                if let Some(direction) = literal.direction() {
+                    term.push_str(&format!("--{direction}"));
+                }
+            } else if *literal.datatype() != *xsd::string {
+                term.push_str(&format!("^^<{}>", literal.datatype().value()));
+            }
+            term
+        }
+        QuadObject::Variable(variable) => variable.value().to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::rs::turtle::{TurtleSyntax, parse_str};
+
+    const INPUT: &str = "@prefix ex: <http://example.org/> .\n\
+                         ex:a ex:knows ex:b, _:c ; ex:name \"A\"@en--ltr, \"A\" .\n\
+                         ex:b ex:knows ex:a ; ex:age 42 .\n\
+                         _:c ex:name \"C\" ; ex:knows ex:d .\n\
+                         ex:e ex:age 42 .\n";
+
+    fn push_vbyte(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push((value & 0x7f) as u8);
+            value >>= 7;
+        }
+        out.push(value as u8 | 0x80);
+    }
+
+    fn push_control_info(out: &mut Vec<u8>, kind: u8, format: &str, properties: &str) {
+        out.extend_from_slice(COOKIE);
+        out.push(kind);
+        for string in [format, properties] {
+            out.extend_from_slice(string.as_bytes());
+            out.push(0);
+        }
+        out.extend_from_slice(&[0; 2]);
+    }
+
+    fn push_sequence(out: &mut Vec<u8>, values: &[u64]) {
+        let bits = values
+            .iter()
+            .max()
+            .map_or(0, |max| 64 - max.leading_zeros()) as usize;
+        let mut data = vec![0u8; (values.len() * bits).div_ceil(8)];
+        for (index, value) in values.iter().enumerate() {
+            for bit in (0..bits).filter(|bit| value >> bit & 1 == 1) {
+                let position = index * bits + bit;
+                data[position / 8] |= 1 << (position % 8);
+            }
+        }
+        out.extend_from_slice(&[1, bits as u8]);
+        push_vbyte(out, values.len() as u64);
+        out.push(0);
+        out.extend_from_slice(&data);
+        out.extend_from_slice(&[0; 4]);
+    }
+
+    fn push_bitmap(out: &mut Vec<u8>, bits: &[bool]) {
+        let mut data = vec![0u8; bits.len().div_ceil(8)];
+        for (position, _) in bits.iter().enumerate().filter(|(_, bit)| **bit) {
+            data[position / 8] |= 1 << (position % 8);
+        }
+        out.push(1);
+        push_vbyte(out, bits.len() as u64);
+        out.push(0);
+        out.extend_from_slice(&data);
+        out.extend_from_slice(&[0; 4]);
+    }
+
+    fn push_section(out: &mut Vec<u8>, strings: &[&String]) {
+        const BLOCK_SIZE: usize = 2;
+        let mut data = Vec::new();
+        let mut offsets = Vec::new();
+        for (index, string) in strings.iter().enumerate() {
+            if index % BLOCK_SIZE == 0 {
+                offsets.push(data.len() as u64);
+                data.extend_from_slice(string.as_bytes());
+            } else {
+                let previous = strings[index - 1].as_bytes();
+                let shared = previous
+                    .iter()
+                    .zip(string.as_bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                push_vbyte(&mut data, shared as u64);
+                data.extend_from_slice(&string.as_bytes()[shared..]);
+            }
+            data.push(0);
+        }
+        offsets.push(data.len() as u64);
+
+        out.push(2);
+        push_vbyte(out, strings.len() as u64);
+        push_vbyte(out, data.len() as u64);
+        push_vbyte(out, BLOCK_SIZE as u64);
+        out.push(0);
+        push_sequence(out, &offsets);
+        out.extend_from_slice(&data);
+        out.extend_from_slice(&[0; 4]);
+    }
+
+    /// Encodes triples like HDT tools do, with small blocks to exercise the
+    /// block search.
+    fn encode(quads: &[Quad]) -> Vec<u8> {
+        let terms: Vec<(String, String, String)> = quads
+            .iter()
+            .map(|quad| {
+                let QuadPredicate::NamedNode(predicate) = quad.predicate() else {
+                    unreachable!()
+                };
+                (
+                    subject_string(quad.subject()).unwrap(),
+                    predicate.value().to_owned(),
+                    object_string(quad.object()),
+                )
+            })
+            .collect();
+        let subjects: BTreeSet<&String> = terms.iter().map(|(s, _, _)| s).collect();
+        let predicates: Vec<&String> = terms
+            .iter()
+            .map(|(_, p, _)| p)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let objects: BTreeSet<&String> = terms.iter().map(|(_, _, o)| o).collect();
+        let shared: Vec<&String> = subjects.intersection(&objects).copied().collect();
+        let subjects: Vec<&String> = subjects.difference(&objects).copied().collect();
+        let objects: Vec<&String> = objects
+            .iter()
+            .filter(|o| !shared.contains(o))
+            .copied()
+            .collect();
+
+        let id = |section: &[&String], term: &String, offset: usize| {
+            let index = shared.iter().position(|s| *s == term);
+            let index = index.or_else(|| Some(section.iter().position(|s| *s == term)? + offset));
+            index.unwrap() as u64 + 1
+        };
+        let ids: BTreeSet<(u64, u64, u64)> = terms
+            .iter()
+            .map(|(s, p, o)| {
+                (
+                    id(&subjects, s, shared.len()),
+                    predicates.iter().position(|x| *x == p).unwrap() as u64 + 1,
+                    id(&objects, o, shared.len()),
+                )
+            })
+            .collect();
+
+        let ids: Vec<_> = ids.into_iter().collect();
+        let mut groups: Vec<(u64, u64)> = ids.iter().map(|(s, p, _)| (*s, *p)).collect();
+        groups.dedup();
+        let sequence_y: Vec<u64> = groups.iter().map(|(_, p)| *p).collect();
+        let bitmap_y: Vec<bool> = (0..groups.len())
+            .map(|i| groups.get(i + 1).is_none_or(|next| next.0 != groups[i].0))
+            .collect();
+        let sequence_z: Vec<u64> = ids.iter().map(|(_, _, o)| *o).collect();
+        let bitmap_z: Vec<bool> = (0..ids.len())
+            .map(|i| {
+                ids.get(i + 1)
+                    .is_none_or(|next| (next.0, next.1) != (ids[i].0, ids[i].1))
+            })
+            .collect();
+
+        let header = "<file> <http://purl.org/dc/terms/title> \"test\" .\n";
+        let mut out = Vec::new();
+        push_control_info(&mut out, 1, "<http://purl.org/HDT/hdt#HDTv1>", "");
+        push_control_info(
+            &mut out,
+            2,
+            "ntriples",
+            &format!("length={};", header.len()),
+        );
+        out.extend_from_slice(header.as_bytes());
+        push_control_info(&mut out, 3, DICTIONARY_FOUR, "mapping=1;");
+        for section in [&shared, &subjects, &predicates, &objects] {
+            push_section(&mut out, section);
+        }
+        push_control_info(&mut out, 4, TRIPLES_BITMAP, "order=1;");
+        push_bitmap(&mut out, &bitmap_y);
+        push_bitmap(&mut out, &bitmap_z);
+        push_sequence(&mut out, &sequence_y);
+        push_sequence(&mut out, &sequence_z);
+        out
+    }
+
+    fn nn(value: &str) -> NamedNode {
+        NamedNode::new(&format!("http://example.org/{value}"))
+    }
+
+    #[test]
+    fn patterns_are_answered_from_the_dictionary_and_bitmaps() {
+        let quads = parse_str(TurtleSyntax::Turtle, INPUT, None).unwrap();
+        let document = HdtDocument::from_bytes(encode(&quads)).unwrap();
+        assert_eq!(document.len(), quads.len());
+        assert!(document.header().contains("\"test\""));
+
+        let all: Vec<Quad> = document
+            .triples_matching(None, None, None)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(all.len(), quads.len());
+        for quad in &quads {
+            assert!(all.contains(quad), "missing {quad:?}");
+        }
+
+        let a = QuadSubject::NamedNode(nn("a"));
+        let knows = QuadPredicate::NamedNode(nn("knows"));
+        assert_eq!(document.triples_matching(Some(&a), None, None).count(), 4);
+        assert_eq!(
+            document
+                .triples_matching(Some(&a), Some(&knows), None)
+                .count(),
+            2
+        );
+
+        let c = QuadSubject::BlankNode(BlankNode::new("c"));
+        let d = QuadObject::NamedNode(nn("d"));
+        assert_eq!(
+            document.triples_matching(Some(&c), None, Some(&d)).count(),
+            1
+        );
+
+        let directional = QuadObject::Literal(Literal::new(
+            "A",
+            Some("en"),
+            Some(&LanguageDirection::LeftToRight),
+            None,
+        ));
+        let matches: Vec<Quad> = document
+            .triples_matching(None, None, Some(&directional))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(*matches[0].subject(), a);
+
+        let age = QuadObject::Literal(Literal::new("42", None, None, Some(&xsd::integer)));
+        assert_eq!(document.triples_matching(None, None, Some(&age)).count(), 2);
+        let unknown = QuadSubject::NamedNode(nn("unknown"));
+        assert_eq!(
+            document
+                .triples_matching(Some(&unknown), None, None)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn damaged_dictionaries_are_reported() {
+        let quads = parse_str(TurtleSyntax::Turtle, INPUT, None).unwrap();
+
+        let mut document = HdtDocument::from_bytes(encode(&quads)).unwrap();
+        document.dictionary.objects.count = 0;
+        let results: Vec<_> = document.triples_matching(None, None, None).collect();
+        assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+        assert!(
+            results
+                .last()
+                .unwrap()
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .ends_with("is not in the dictionary")
+        );
+
+        let mut document = HdtDocument::from_bytes(encode(&quads)).unwrap();
+        document.dictionary.predicates.data.start = usize::MAX;
+        let knows = QuadPredicate::NamedNode(nn("knows"));
+        let results: Vec<_> = document
+            .triples_matching(None, Some(&knows), None)
+            .collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].as_ref().unwrap_err().to_string(),
+            "invalid HDT file: a dictionary block starts past its section"
+        );
+    }
+
+    #[test]
+    fn files_in_the_hdt_cpp_layout_are_read() {
+        // Written by tests/fixtures/hdt/generate.py, which follows the layout
+        // and checksums of rdf2hdt; it is not rdf2hdt output.
+        let document =
+            HdtDocument::from_bytes(include_bytes!("../../tests/fixtures/hdt/small.hdt").to_vec())
+                .unwrap();
+        let expected = parse_str(
+            TurtleSyntax::NTriples,
+            include_str!("../../tests/fixtures/hdt/small.nt"),
+            None,
+        )
+        .unwrap();
+
+        let all: Vec<Quad> = document
+            .triples_matching(None, None, None)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(all.len(), expected.len());
+        for quad in &expected {
+            assert!(all.contains(quad), "missing {quad:?}");
+        }
+
+        let alice = QuadSubject::NamedNode(NamedNode::new("http://example.org/alice"));
+        assert_eq!(
+            document.triples_matching(Some(&alice), None, None).count(),
+            4
+        );
+        assert!(
+            document
+                .header()
+                .contains("http://rdfs.org/ns/void#triples")
+        );
+    }
+
+    #[test]
+    fn files_are_memory_mapped() {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("rdfjs-rust-{}.hdt", std::process::id()));
+        let quads = parse_str(TurtleSyntax::Turtle, INPUT, None).unwrap();
+        std::fs::write(&path, encode(&quads)).unwrap();
+        let document = HdtDocument::open(&path);
+        let _ = std::fs::remove_file(&path);
+        let document = document.unwrap();
+
+        let quads: Vec<Quad> = document
+            .match_quads(None, None, None, None)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(quads.len(), document.len());
+
+        let error = HdtDocument::from_bytes(b"$HDT".to_vec()).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "invalid HDT file: unexpected end of file"
+        );
+    }
+}
@@ -0,0 +1,1123 @@
+//! Reading and writing [Jelly](https://w3id.org/jelly) RDF streams: delimited
+//! protobuf `RdfStreamFrame`s whose rows are stream options, lookup table
+//! entries and statements.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::error::Error;
+use std::fmt::Display;
+use std::io::{self, Read, Write};
+
+use prost::Message;
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::default_graph::DefaultGraph;
+use crate::rs::literal::Literal;
+use crate::rs::named_node::NamedNode;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::vocab::xsd;
+
+use proto::{
+    LiteralKind, LogicalStreamType, PhysicalStreamType, RdfDatatypeEntry, RdfDefaultGraph,
+    RdfGraphEnd, RdfGraphStart, RdfIri, RdfLiteral, RdfNameEntry, RdfPrefixEntry, RdfQuad,
+    RdfStreamFrame, RdfStreamOptions, RdfStreamRow, RdfTriple, Row,
+};
+
+/// The messages of Jelly's `rdf.proto`, version 1.1.
+mod proto {
+    macro_rules! term_oneof {
+        ($name:ident, $iri:tt, $bnode:tt, $literal:tt, $triple:tt) => {
+            #[derive(Clone, PartialEq, prost::Oneof)]
+            pub enum $name {
+                #[prost(message, tag = $iri)]
+                Iri(RdfIri),
+                #[prost(string, tag = $bnode)]
+                Bnode(String),
+                #[prost(message, tag = $literal)]
+                Literal(RdfLiteral),
+                #[prost(message, tag = $triple)]
+                TripleTerm(Box<RdfTriple>),
+            }
+        };
+    }
+
+    macro_rules! graph_oneof {
+        ($name:ident, $iri:tt, $bnode:tt, $default_graph:tt, $literal:tt) => {
+            #[derive(Clone, PartialEq, prost::Oneof)]
+            pub enum $name {
+                #[prost(message, tag = $iri)]
+                Iri(RdfIri),
+                #[prost(string, tag = $bnode)]
+                Bnode(String),
+                #[prost(message, tag = $default_graph)]
+                DefaultGraph(RdfDefaultGraph),
+                #[prost(message, tag = $literal)]
+                Literal(RdfLiteral),
+            }
+        };
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfIri {
+        #[prost(uint32, tag = "1")]
+        pub prefix_id: u32,
+        #[prost(uint32, tag = "2")]
+        pub name_id: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfLiteral {
+        #[prost(string, tag = "1")]
+        pub lex: String,
+        #[prost(oneof = "LiteralKind", tags = "2, 3")]
+        pub literal_kind: Option<LiteralKind>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum LiteralKind {
+        #[prost(string, tag = "2")]
+        Langtag(String),
+        #[prost(uint32, tag = "3")]
+        Datatype(u32),
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfDefaultGraph {}
+
+    term_oneof!(Subject, "1", "2", "3", "4");
+    term_oneof!(Predicate, "5", "6", "7", "8");
+    term_oneof!(Object, "9", "10", "11", "12");
+    graph_oneof!(QuadGraphTerm, "13", "14", "15", "16");
+    graph_oneof!(GraphStartTerm, "1", "2", "3", "4");
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfTriple {
+        #[prost(oneof = "Subject", tags = "1, 2, 3, 4")]
+        pub subject: Option<Subject>,
+        #[prost(oneof = "Predicate", tags = "5, 6, 7, 8")]
+        pub predicate: Option<Predicate>,
+        #[prost(oneof = "Object", tags = "9, 10, 11, 12")]
+        pub object: Option<Object>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfQuad {
+        #[prost(oneof = "Subject", tags = "1, 2, 3, 4")]
+        pub subject: Option<Subject>,
+        #[prost(oneof = "Predicate", tags = "5, 6, 7, 8")]
+        pub predicate: Option<Predicate>,
+        #[prost(oneof = "Object", tags = "9, 10, 11, 12")]
+        pub object: Option<Object>,
+        #[prost(oneof = "QuadGraphTerm", tags = "13, 14, 15, 16")]
+        pub graph: Option<QuadGraphTerm>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfGraphStart {
+        #[prost(oneof = "GraphStartTerm", tags = "1, 2, 3, 4")]
+        pub graph: Option<GraphStartTerm>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfGraphEnd {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfNamespaceDeclaration {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(message, optional, tag = "2")]
+        pub value: Option<RdfIri>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfNameEntry {
+        #[prost(uint32, tag = "1")]
+        pub id: u32,
+        #[prost(string, tag = "2")]
+        pub value: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfPrefixEntry {
+        #[prost(uint32, tag = "1")]
+        pub id: u32,
+        #[prost(string, tag = "2")]
+        pub value: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfDatatypeEntry {
+        #[prost(uint32, tag = "1")]
+        pub id: u32,
+        #[prost(string, tag = "2")]
+        pub value: String,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum PhysicalStreamType {
+        Unspecified = 0,
+        Triples = 1,
+        Quads = 2,
+        Graphs = 3,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum LogicalStreamType {
+        Unspecified = 0,
+        FlatTriples = 1,
+        FlatQuads = 2,
+        Graphs = 3,
+        Datasets = 4,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfStreamOptions {
+        #[prost(string, tag = "1")]
+        pub stream_name: String,
+        #[prost(enumeration = "PhysicalStreamType", tag = "2")]
+        pub physical_type: i32,
+        #[prost(bool, tag = "3")]
+        pub generalized_statements: bool,
+        #[prost(bool, tag = "4")]
+        pub rdf_star: bool,
+        #[prost(uint32, tag = "9")]
+        pub max_name_table_size: u32,
+        #[prost(uint32, tag = "10")]
+        pub max_prefix_table_size: u32,
+        #[prost(uint32, tag = "11")]
+        pub max_datatype_table_size: u32,
+        #[prost(enumeration = "LogicalStreamType", tag = "14")]
+        pub logical_type: i32,
+        #[prost(uint32, tag = "15")]
+        pub version: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Row {
+        #[prost(message, tag = "1")]
+        Options(RdfStreamOptions),
+        #[prost(message, tag = "2")]
+        Triple(RdfTriple),
+        #[prost(message, tag = "3")]
+        Quad(RdfQuad),
+        #[prost(message, tag = "4")]
+        GraphStart(RdfGraphStart),
+        #[prost(message, tag = "5")]
+        GraphEnd(RdfGraphEnd),
+        #[prost(message, tag = "6")]
+        Namespace(RdfNamespaceDeclaration),
+        #[prost(message, tag = "9")]
+        Name(RdfNameEntry),
+        #[prost(message, tag = "10")]
+        Prefix(RdfPrefixEntry),
+        #[prost(message, tag = "11")]
+        Datatype(RdfDatatypeEntry),
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfStreamRow {
+        #[prost(oneof = "Row", tags = "1, 2, 3, 4, 5, 6, 9, 10, 11")]
+        pub row: Option<Row>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RdfStreamFrame {
+        #[prost(message, repeated, tag = "1")]
+        pub rows: Vec<RdfStreamRow>,
+    }
+}
+
+/// Any term of a statement, whatever its position.
+enum Node {
+    Iri(RdfIri),
+    Bnode(String),
+    Literal(RdfLiteral),
+    TripleTerm(Box<RdfTriple>),
+    DefaultGraph,
+}
+
+macro_rules! node_conversions {
+    ($name:ident, $fourth:ident) => {
+        impl From<proto::$name> for Node {
+            fn from(term: proto::$name) -> Self {
+                match term {
+                    proto::$name::Iri(iri) => Node::Iri(iri),
+                    proto::$name::Bnode(label) => Node::Bnode(label),
+                    proto::$name::Literal(literal) => Node::Literal(literal),
+                    proto::$name::$fourth(term) => term.into(),
+                }
+            }
+        }
+
+        impl From<Node> for proto::$name {
+            /// Only called with nodes encoded from terms valid in this position.
+            fn from(node: Node) -> Self {
+                match node {
+                    Node::Iri(iri) => proto::$name::Iri(iri),
+                    Node::Bnode(label) => proto::$name::Bnode(label),
+                    Node::Literal(literal) => proto::$name::Literal(literal),
+                    node => proto::$name::$fourth(node.try_into().unwrap()),
+                }
+            }
+        }
+    };
+}
+
+node_conversions!(Subject, TripleTerm);
+node_conversions!(Predicate, TripleTerm);
+node_conversions!(Object, TripleTerm);
+node_conversions!(QuadGraphTerm, DefaultGraph);
+node_conversions!(GraphStartTerm, DefaultGraph);
+
+impl From<Box<RdfTriple>> for Node {
+    fn from(triple: Box<RdfTriple>) -> Self {
+        Node::TripleTerm(triple)
+    }
+}
+
+impl From<RdfDefaultGraph> for Node {
+    fn from(_: RdfDefaultGraph) -> Self {
+        Node::DefaultGraph
+    }
+}
+
+impl TryFrom<Node> for Box<RdfTriple> {
+    type Error = ();
+
+    fn try_from(node: Node) -> Result<Self, ()> {
+        match node {
+            Node::TripleTerm(triple) => Ok(triple),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<Node> for RdfDefaultGraph {
+    type Error = ();
+
+    fn try_from(node: Node) -> Result<Self, ()> {
+        match node {
+            Node::DefaultGraph => Ok(RdfDefaultGraph {}),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The physical layout of a stream: how statements carry their graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JellyStreamType {
+    /// Triple rows, all in the default graph.
+    Triples,
+    /// Quad rows.
+    Quads,
+    /// Triple rows between graph start and end rows.
+    Graphs,
+}
+
+#[derive(Debug)]
+pub enum JellyError {
+    Io(io::Error),
+    /// The input is not a valid Jelly stream, or uses features this reader
+    /// does not support, such as generalized statements.
+    Format(String),
+}
+
+impl Display for JellyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JellyError::Io(error) => write!(f, "{error}"),
+            JellyError::Format(message) => write!(f, "invalid Jelly stream: {message}"),
+        }
+    }
+}
+
+impl Error for JellyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JellyError::Io(error) => Some(error),
+            JellyError::Format(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for JellyError {
+    fn from(error: io::Error) -> Self {
+        JellyError::Io(error)
+    }
+}
+
+impl From<prost::DecodeError> for JellyError {
+    fn from(error: prost::DecodeError) -> Self {
+        JellyError::Format(error.to_string())
+    }
+}
+
+fn format_error<T>(message: &str) -> Result<T, JellyError> {
+    Err(JellyError::Format(message.to_owned()))
+}
+
+const VERSION: u32 = 1;
+const MAX_FRAME_SIZE: u64 = 64 << 20;
+const MAX_NESTING: usize = 64;
+
+/// Writes quads as a Jelly stream, in frames of about
+/// [`JellySerializer::with_frame_size`] rows.
+#[derive(Clone)]
+pub struct JellySerializer {
+    stream_type: JellyStreamType,
+    frame_size: usize,
+    name_table_size: u32,
+    prefix_table_size: u32,
+    datatype_table_size: u32,
+}
+
+impl Default for JellySerializer {
+    fn default() -> Self {
+        Self {
+            stream_type: JellyStreamType::Quads,
+            frame_size: 256,
+            name_table_size: 4000,
+            prefix_table_size: 150,
+            datatype_table_size: 32,
+        }
+    }
+}
+
+impl JellySerializer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// [`JellyStreamType::Triples`] fails on quads in named graphs.
+    pub fn with_stream_type(mut self, stream_type: JellyStreamType) -> Self {
+        self.stream_type = stream_type;
+        self
+    }
+
+    pub fn with_frame_size(mut self, frame_size: usize) -> Self {
+        self.frame_size = frame_size.max(1);
+        self
+    }
+
+    /// Sizes of the name, prefix and datatype lookup tables. Jelly requires at
+    /// least 8 names.
+    pub fn with_table_sizes(mut self, names: u32, prefixes: u32, datatypes: u32) -> Self {
+        self.name_table_size = names.max(8);
+        self.prefix_table_size = prefixes;
+        self.datatype_table_size = datatypes.max(1);
+        self
+    }
+
+    pub fn serialize<'a>(
+        &self,
+        mut writer: impl Write,
+        quads: impl IntoIterator<Item = &'a Quad>,
+    ) -> io::Result<()> {
+        let (physical_type, logical_type) = match self.stream_type {
+            JellyStreamType::Triples => {
+                (PhysicalStreamType::Triples, LogicalStreamType::FlatTriples)
+            }
+            JellyStreamType::Quads => (PhysicalStreamType::Quads, LogicalStreamType::FlatQuads),
+            JellyStreamType::Graphs => (PhysicalStreamType::Graphs, LogicalStreamType::FlatQuads),
+        };
+        let mut encoder = Encoder {
+            names: LookupEncoder::new(self.name_table_size),
+            prefixes: LookupEncoder::new(self.prefix_table_size),
+            datatypes: LookupEncoder::new(self.datatype_table_size),
+            last_prefix: 0,
+            last_name: 0,
+            previous: Default::default(),
+            rows: vec![Row::Options(RdfStreamOptions {
+                stream_name: String::new(),
+                physical_type: physical_type.into(),
+                generalized_statements: false,
+                rdf_star: true,
+                max_name_table_size: self.name_table_size,
+                max_prefix_table_size: self.prefix_table_size,
+                max_datatype_table_size: self.datatype_table_size,
+                logical_type: logical_type.into(),
+                version: VERSION,
+            })],
+        };
+
+        let mut graph: Option<Term> = None;
+        for quad in quads {
+            encoder.names.start_statement();
+            encoder.prefixes.start_statement();
+            encoder.datatypes.start_statement();
+            let quad_graph = quad.graph().to_term();
+            match self.stream_type {
+                JellyStreamType::Triples if !matches!(quad_graph, Term::DefaultGraph(_)) => {
+                    return Err(invalid_input(
+                        "a Jelly triple stream cannot contain named graphs",
+                    ));
+                }
+                JellyStreamType::Triples | JellyStreamType::Graphs => {
+                    if self.stream_type == JellyStreamType::Graphs
+                        && graph.as_ref() != Some(&quad_graph)
+                    {
+                        if graph.is_some() {
+                            encoder.rows.push(Row::GraphEnd(RdfGraphEnd {}));
+                        }
+                        let node = encoder.node(&quad_graph, 0)?;
+                        encoder.rows.push(Row::GraphStart(RdfGraphStart {
+                            graph: Some(node.into()),
+                        }));
+                        graph = Some(quad_graph);
+                    }
+                    let triple = encoder.triple(quad)?;
+                    encoder.rows.push(Row::Triple(triple));
+                }
+                JellyStreamType::Quads => {
+                    let triple = encoder.triple(quad)?;
+                    let graph = encoder.repeated(3, quad_graph)?;
+                    encoder.rows.push(Row::Quad(RdfQuad {
+                        subject: triple.subject,
+                        predicate: triple.predicate,
+                        object: triple.object,
+                        graph: graph.map(Node::into),
+                    }));
+                }
+            }
+            if encoder.rows.len() >= self.frame_size {
+                write_frame(&mut writer, &mut encoder.rows)?;
+            }
+        }
+        if graph.is_some() {
+            encoder.rows.push(Row::GraphEnd(RdfGraphEnd {}));
+        }
+        if !encoder.rows.is_empty() {
+            write_frame(&mut writer, &mut encoder.rows)?;
+        }
+        writer.flush()
+    }
+}
+
+fn write_frame(writer: &mut impl Write, rows: &mut Vec<Row>) -> io::Result<()> {
+    let frame = RdfStreamFrame {
+        rows: rows
+            .drain(..)
+            .map(|row| RdfStreamRow { row: Some(row) })
+            .collect(),
+    };
+    writer.write_all(&frame.encode_length_delimited_to_vec())
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// A lookup table that evicts its least recently used entry when full.
+struct LookupEncoder {
+    size: u32,
+    ids: HashMap<String, (u32, u64)>,
+    by_use: BTreeMap<u64, u32>,
+    values: Vec<String>,
+    clock: u64,
+    /// Entries used since this time are needed by the statement being
+    /// written, so cannot be replaced.
+    statement_start: u64,
+    last_set: u32,
+}
+
+impl LookupEncoder {
+    fn new(size: u32) -> Self {
+        Self {
+            size,
+            ids: HashMap::new(),
+            by_use: BTreeMap::new(),
+            values: Vec::new(),
+            clock: 0,
+            statement_start: 0,
+            last_set: 0,
+        }
+    }
+
+    fn start_statement(&mut self) {
+        self.statement_start = self.clock + 1;
+    }
+
+    /// The ID of `value`, adding an `entry` row for it to `rows` if it is new.
+    fn id(
+        &mut self,
+        value: &str,
+        rows: &mut Vec<Row>,
+        entry: fn(u32, String) -> Row,
+    ) -> io::Result<u32> {
+        self.clock += 1;
+        if let Some((id, used)) = self.ids.get_mut(value) {
+            self.by_use.remove(used);
+            *used = self.clock;
+            self.by_use.insert(self.clock, *id);
+            return Ok(*id);
+        }
+
+        let id = if (self.values.len() as u32) < self.size {
+            self.values.push(value.to_owned());
+            self.values.len() as u32
+        } else {
+            match self.by_use.first_key_value() {
+                Some((used, _)) if *used < self.statement_start => {}
+                _ => {
+                    return Err(invalid_input(
+                        "a statement has more terms than a Jelly lookup table holds",
+                    ));
+                }
+            }
+            let (_, id) = self.by_use.pop_first().unwrap();
+            let evicted = std::mem::replace(&mut self.values[id as usize - 1], value.to_owned());
+            self.ids.remove(&evicted);
+            id
+        };
+        self.ids.insert(value.to_owned(), (id, self.clock));
+        self.by_use.insert(self.clock, id);
+        rows.push(entry(
+            if id == self.last_set + 1 { 0 } else { id },
+            value.to_owned(),
+        ));
+        self.last_set = id;
+        Ok(id)
+    }
+}
+
+struct Encoder {
+    names: LookupEncoder,
+    prefixes: LookupEncoder,
+    datatypes: LookupEncoder,
+    last_prefix: u32,
+    last_name: u32,
+    /// The subject, predicate, object and graph of the previous statement.
+    previous: [Option<Term>; 4],
+    rows: Vec<Row>,
+}
+
+impl Encoder {
+    fn triple(&mut self, quad: &Quad) -> io::Result<RdfTriple> {
+        Ok(RdfTriple {
+            subject: self.repeated(0, quad.subject().to_term())?.map(Node::into),
+            predicate: self
+                .repeated(1, quad.predicate().to_term())?
+                .map(Node::into),
+            object: self.repeated(2, quad.object().to_term())?.map(Node::into),
+        })
+    }
+
+    /// Leaves out terms equal to the previous statement's in the same
+    /// position, which readers repeat.
+    fn repeated(&mut self, position: usize, term: Term) -> io::Result<Option<Node>> {
+        if self.previous[position].as_ref() == Some(&term) {
+            return Ok(None);
+        }
+        let node = self.node(&term, 0)?;
+        self.previous[position] = Some(term);
+        Ok(Some(node))
+    }
+
+    fn node(&mut self, term: &Term, depth: usize) -> io::Result<Node> {
+        Ok(match term {
+            Term::NamedNode(node) => Node::Iri(self.iri(node.value())?),
+            Term::BlankNode(node) => Node::Bnode(node.value().to_owned()),
+            Term::Literal(literal) => {
+                let literal_kind = if !literal.language().is_empty() {
+                    if literal.direction().is_some() {
+                        return Err(invalid_input(
+                            "Jelly cannot express the base direction of literals",
+                        ));
+                    }
+                    Some(LiteralKind::Langtag(literal.language().to_owned()))
+                } else if *literal.datatype() != *xsd::string {
+                    let id = self.datatypes.id(
+                        literal.datatype().value(),
+                        &mut self.rows,
+                        |id, value| Row::Datatype(RdfDatatypeEntry { id, value }),
+                    )?;
+                    Some(LiteralKind::Datatype(id))
+                } else {
+                    None
+                };
+                Node::Literal(RdfLiteral {
+                    lex: literal.value().to_owned(),
+                    literal_kind,
+                })
+            }
+            Term::Quad(triple) => {
+                if depth >= MAX_NESTING {
+                    return Err(invalid_input("triple terms are nested too deeply"));
+                }
+                let subject = self.node(&triple.subject().to_term(), depth + 1)?;
+                let predicate = self.node(&triple.predicate().to_term(), depth + 1)?;
+                let object = self.node(&triple.object().to_term(), depth + 1)?;
+                Node::TripleTerm(Box::new(RdfTriple {
+                    subject: Some(subject.into()),
+                    predicate: Some(predicate.into()),
+                    object: Some(object.into()),
+                }))
+            }
+            Term::DefaultGraph(_) => Node::DefaultGraph,
+            Term::Variable(_) => {
+                return Err(invalid_input("variables cannot be written as Jelly"));
+            }
+        })
+    }
+
+    /// Splits `iri` after its last `/` or `#` into a prefix and a name,
+    /// sending 0 for the prefix when it repeats and for the name when it
+    /// follows the previous one.
+    fn iri(&mut self, iri: &str) -> io::Result<RdfIri> {
+        let split = iri.rfind(['/', '#']).map_or(0, |index| index + 1);
+        let (prefix, name) = iri.split_at(split);
+
+        let prefix_id = if self.prefixes.size == 0 {
+            0
+        } else {
+            self.prefixes.id(prefix, &mut self.rows, |id, value| {
+                Row::Prefix(RdfPrefixEntry { id, value })
+            })?
+        };
+        let name = if self.prefixes.size == 0 { iri } else { name };
+        let name_id = self.names.id(name, &mut self.rows, |id, value| {
+            Row::Name(RdfNameEntry { id, value })
+        })?;
+
+        let iri = RdfIri {
+            prefix_id: if prefix_id == self.last_prefix {
+                0
+            } else {
+                prefix_id
+            },
+            name_id: if name_id == self.last_name + 1 {
+                0
+            } else {
+                name_id
+            },
+        };
+        self.last_prefix = prefix_id;
+        self.last_name = name_id;
+        Ok(iri)
+    }
+}
+
+struct LookupDecoder {
+    size: u32,
+    values: HashMap<u32, String>,
+    last_set: u32,
+}
+
+impl LookupDecoder {
+    fn new(size: u32) -> Self {
+        Self {
+            size,
+            values: HashMap::new(),
+            last_set: 0,
+        }
+    }
+
+    fn set(&mut self, id: u32, value: String, table: &str) -> Result<(), JellyError> {
+        let id = if id == 0 { self.last_set + 1 } else { id };
+        if id > self.size {
+            return format_error(&format!("{table} ID {id} is outside the table"));
+        }
+        self.values.insert(id, value);
+        self.last_set = id;
+        Ok(())
+    }
+
+    fn get(&self, id: u32, table: &str) -> Result<&str, JellyError> {
+        match self.values.get(&id) {
+            Some(value) => Ok(value),
+            None => format_error(&format!("undefined {table} ID {id}")),
+        }
+    }
+}
+
+/// Iterator over the quads of a Jelly stream read from `R`, one frame in
+/// memory at a time.
+pub struct JellyReader<R> {
+    reader: R,
+    stream_type: JellyStreamType,
+    names: LookupDecoder,
+    prefixes: LookupDecoder,
+    datatypes: LookupDecoder,
+    last_prefix: u32,
+    last_name: u32,
+    previous: [Option<Term>; 4],
+    graph: Option<QuadGraph>,
+    rows: VecDeque<RdfStreamRow>,
+    done: bool,
+}
+
+impl<R: Read> JellyReader<R> {
+    /// Reads up to the stream options, which must be the first row.
+    pub fn new(mut reader: R) -> Result<Self, JellyError> {
+        let mut rows = VecDeque::new();
+        while rows.is_empty() {
+            match read_frame(&mut reader)? {
+                Some(frame) => rows.extend(frame.rows),
+                None => return format_error("missing stream options"),
+            }
+        }
+        let Some(RdfStreamRow {
+            row: Some(Row::Options(options)),
+        }) = rows.pop_front()
+        else {
+            return format_error("the first row must be the stream options");
+        };
+
+        if options.version > 2 {
+            return format_error(&format!("unsupported version {}", options.version));
+        }
+        let stream_type = match PhysicalStreamType::try_from(options.physical_type) {
+            Ok(PhysicalStreamType::Triples) => JellyStreamType::Triples,
+            Ok(PhysicalStreamType::Quads) => JellyStreamType::Quads,
+            Ok(PhysicalStreamType::Graphs) => JellyStreamType::Graphs,
+            _ => return format_error("unsupported physical stream type"),
+        };
+        Ok(Self {
+            reader,
+            stream_type,
+            names: LookupDecoder::new(options.max_name_table_size),
+            prefixes: LookupDecoder::new(options.max_prefix_table_size),
+            datatypes: LookupDecoder::new(options.max_datatype_table_size),
+            last_prefix: 0,
+            last_name: 0,
+            previous: Default::default(),
+            graph: None,
+            rows,
+            done: false,
+        })
+    }
+
+    pub fn stream_type(&self) -> JellyStreamType {
+        self.stream_type
+    }
+
+    fn read_next(&mut self) -> Result<Option<Quad>, JellyError> {
+        loop {
+            let Some(row) = self.rows.pop_front() else {
+                match read_frame(&mut self.reader)? {
+                    Some(frame) => self.rows.extend(frame.rows),
+                    None if self.graph.is_some() => return format_error("unterminated graph"),
+                    None => return Ok(None),
+                }
+                continue;
+            };
+            if let Some(quad) = self.row(row.row)? {
+                return Ok(Some(quad));
+            }
+        }
+    }
+
+    fn row(&mut self, row: Option<Row>) -> Result<Option<Quad>, JellyError> {
+        match row {
+            // Later options only restate the stream's.
+            None | Some(Row::Options(_)) => {}
+            Some(Row::Name(entry)) => self.names.set(entry.id, entry.value, "name")?,
+            Some(Row::Prefix(entry)) => self.prefixes.set(entry.id, entry.value, "prefix")?,
+            Some(Row::Datatype(entry)) => self.datatypes.set(entry.id, entry.value, "datatype")?,
+            Some(Row::Namespace(declaration)) => {
+                // Namespaces are not kept, but their IRI still moves the
+                // previous prefix and name.
+                if let Some(iri) = declaration.value {
+                    self.iri(iri)?;
+                }
+            }
+            Some(Row::GraphStart(start)) => {
+                if self.stream_type != JellyStreamType::Graphs {
+                    return format_error("graph start row outside a graph stream");
+                }
+                let Some(graph) = start.graph else {
+                    return format_error("graph start row without a graph");
+                };
+                let graph = self.term(graph.into(), 0)?;
+                self.graph = Some(QuadGraph::try_from(graph).map_err(|_| invalid("graph"))?);
+            }
+            Some(Row::GraphEnd(_)) => {
+                if self.graph.take().is_none() {
+                    return format_error("graph end row without a graph start");
+                }
+            }
+            Some(Row::Triple(triple)) => {
+                let graph = match self.stream_type {
+                    JellyStreamType::Triples => None,
+                    JellyStreamType::Graphs => match &self.graph {
+                        Some(graph) => Some(graph.clone()),
+                        None => return format_error("triple row outside a graph"),
+                    },
+                    JellyStreamType::Quads => return format_error("triple row in a quad stream"),
+                };
+                let subject = self.repeated(0, triple.subject.map(Node::from))?;
+                let predicate = self.repeated(1, triple.predicate.map(Node::from))?;
+                let object = self.repeated(2, triple.object.map(Node::from))?;
+                let (subject, predicate, object) = statement(subject, predicate, object)?;
+                return Ok(Some(Quad::new(
+                    &subject,
+                    &predicate,
+                    &object,
+                    graph.as_ref(),
+                )));
+            }
+            Some(Row::Quad(quad)) => {
+                if self.stream_type != JellyStreamType::Quads {
+                    return format_error("quad row outside a quad stream");
+                }
+                let subject = self.repeated(0, quad.subject.map(Node::from))?;
+                let predicate = self.repeated(1, quad.predicate.map(Node::from))?;
+                let object = self.repeated(2, quad.object.map(Node::from))?;
+                let graph = self.repeated(3, quad.graph.map(Node::from))?;
+                let (subject, predicate, object) = statement(subject, predicate, object)?;
+                let graph = QuadGraph::try_from(graph).map_err(|_| invalid("graph"))?;
+                return Ok(Some(Quad::new(&subject, &predicate, &object, Some(&graph))));
+            }
+        }
+        Ok(None)
+    }
+
+    /// A missing term repeats the previous statement's in the same position.
+    fn repeated(&mut self, position: usize, node: Option<Node>) -> Result<Term, JellyError> {
+        match node {
+            Some(node) => {
+                let term = self.term(node, 0)?;
+                self.previous[position] = Some(term.clone());
+                Ok(term)
+            }
+            None => match &self.previous[position] {
+                Some(term) => Ok(term.clone()),
+                None => format_error("a statement omits a term without a previous one to repeat"),
+            },
+        }
+    }
+
+    fn term(&mut self, node: Node, depth: usize) -> Result<Term, JellyError> {
+        Ok(match node {
+            Node::Iri(iri) => NamedNode::new(&self.iri(iri)?).to_term(),
+            Node::Bnode(label) => BlankNode::new(&label).to_term(),
+            Node::Literal(literal) => match literal.literal_kind {
+                None => Literal::new(&literal.lex, None, None, None),
+                Some(LiteralKind::Langtag(language)) => {
+                    Literal::new(&literal.lex, Some(&language), None, None)
+                }
+                Some(LiteralKind::Datatype(id)) => {
+                    let datatype = NamedNode::new(self.datatypes.get(id, "datatype")?);
+                    Literal::new(&literal.lex, None, None, Some(&datatype))
+                }
+            }
+            .to_term(),
+            Node::TripleTerm(triple) => {
+                if depth >= MAX_NESTING {
+                    return format_error("triple terms are nested too deeply");
+                }
+                let mut part = |node: Option<Node>| match node {
+                    Some(node) => self.term(node, depth + 1),
+                    None => format_error("a triple term must have all its terms"),
+                };
+                let subject = part(triple.subject.map(Node::from))?;
+                let predicate = part(triple.predicate.map(Node::from))?;
+                let object = part(triple.object.map(Node::from))?;
+                let (subject, predicate, object) = statement(subject, predicate, object)?;
+                Term::Quad(Box::new(Quad::new(&subject, &predicate, &object, None)))
+            }
+            Node::DefaultGraph => DefaultGraph::new().to_term(),
+        })
+    }
+
+    fn iri(&mut self, iri: RdfIri) -> Result<String, JellyError> {
+        let name_id = if iri.name_id == 0 {
+            self.last_name + 1
+        } else {
+            iri.name_id
+        };
+        let name = self.names.get(name_id, "name")?;
+        self.last_name = name_id;
+        if self.prefixes.size == 0 {
+            return Ok(name.to_owned());
+        }
+
+        let prefix_id = if iri.prefix_id == 0 {
+            self.last_prefix
+        } else {
+            iri.prefix_id
+        };
+        let prefix = self.prefixes.get(prefix_id, "prefix")?;
+        self.last_prefix = prefix_id;
+        Ok(format!("{prefix}{name}"))
+    }
+}
+
+impl<R: Read> Iterator for JellyReader<R> {
+    type Item = Result<Quad, JellyError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_next().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+/// Reads a length-delimited frame, or `None` at the end of the input.
+fn read_frame(reader: &mut impl Read) -> Result<Option<RdfStreamFrame>, JellyError> {
+    let mut length = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        if reader.read(&mut byte)? == 0 {
+            if shift == 0 {
+                return Ok(None);
+            }
+            return format_error("unexpected end of stream");
+        }
+        length |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    if length > MAX_FRAME_SIZE {
+        return format_error("frame too large");
+    }
+    let mut data = Vec::new();
+    reader.take(length).read_to_end(&mut data)?;
+    if data.len() as u64 != length {
+        return format_error("unexpected end of stream");
+    }
+    Ok(Some(RdfStreamFrame::decode(data.as_slice())?))
+}
+
+fn invalid(position: &str) -> JellyError {
+    JellyError::Format(format!("unsupported {position} term"))
+}
+
+fn statement(
+    subject: Term,
+    predicate: Term,
+    object: Term,
+) -> Result<(QuadSubject, QuadPredicate, QuadObject), JellyError> {
+    Ok((
+        QuadSubject::try_from(subject).map_err(|_| invalid("subject"))?,
+        QuadPredicate::try_from(predicate).map_err(|_| invalid("predicate"))?,
+        QuadObject::try_from(object).map_err(|_| invalid("object"))?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::turtle::{TurtleSyntax, parse_str};
+
+    const INPUT: &str = "@prefix ex: <http://example.org/> .\n\
+                         @prefix other: <http://other.example/#> .\n\
+                         ex:a ex:p \"caf\u{e9}\"@fr, \"1\"^^ex:t, 2, \"y\", _:b, other:c .\n\
+                         ex:a other:q ex:b ; ex:p ex:c .\n\
+                         << << ex:a ex:p ex:b >> ex:q ex:c >> ex:r ex:a .\n\
+                         ex:g { ex:a ex:p ex:b . _:b ex:p 3 . }\n\
+                         _:g { ex:a ex:p ex:b . }\n";
+
+    fn read_all(input: &[u8]) -> Vec<Quad> {
+        JellyReader::new(input)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn fixture_streams_decode() {
+        for (jelly, expected, stream_type) in [
+            (
+                include_bytes!("../../tests/fixtures/jelly/quads.jelly").as_slice(),
+                include_str!("../../tests/fixtures/jelly/quads.nq"),
+                JellyStreamType::Quads,
+            ),
+            (
+                include_bytes!("../../tests/fixtures/jelly/graphs.jelly").as_slice(),
+                include_str!("../../tests/fixtures/jelly/graphs.nq"),
+                JellyStreamType::Graphs,
+            ),
+        ] {
+            let expected = parse_str(TurtleSyntax::NQuads, expected, None).unwrap();
+            assert_eq!(JellyReader::new(jelly).unwrap().stream_type(), stream_type);
+            assert_eq!(read_all(jelly), expected);
+        }
+
+        let truncated = include_bytes!("../../tests/fixtures/jelly/quads.jelly");
+        let error = JellyReader::new(&truncated[..truncated.len() - 1])
+            .unwrap()
+            .find_map(Result::err)
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "invalid Jelly stream: unexpected end of stream"
+        );
+    }
+
+    #[test]
+    fn quads_round_trip() {
+        let quads = parse_str(TurtleSyntax::TriG, INPUT, None).unwrap();
+        for stream_type in [JellyStreamType::Quads, JellyStreamType::Graphs] {
+            for (frame_size, names) in [(1, 8), (3, 8), (256, 4000)] {
+                let mut output = Vec::new();
+                JellySerializer::new()
+                    .with_stream_type(stream_type)
+                    .with_frame_size(frame_size)
+                    .with_table_sizes(names, 2, 1)
+                    .serialize(&mut output, &quads)
+                    .unwrap();
+                let decoded = read_all(&output);
+                if stream_type == JellyStreamType::Graphs {
+                    // Graph streams group quads by graph.
+                    assert_eq!(decoded.len(), quads.len());
+                    assert!(quads.iter().all(|quad| decoded.contains(quad)));
+                } else {
+                    assert_eq!(decoded, quads);
+                }
+            }
+        }
+
+        let triples: Vec<Quad> = quads
+            .iter()
+            .filter(|quad| matches!(quad.graph(), QuadGraph::DefaultGraph(_)))
+            .cloned()
+            .collect();
+        let mut output = Vec::new();
+        JellySerializer::new()
+            .with_stream_type(JellyStreamType::Triples)
+            .serialize(&mut output, &triples)
+            .unwrap();
+        assert_eq!(read_all(&output), triples);
+    }
+
+    #[test]
+    fn inexpressible_quads_are_rejected() {
+        let quads = parse_str(TurtleSyntax::TriG, INPUT, None).unwrap();
+        let error = JellySerializer::new()
+            .with_stream_type(JellyStreamType::Triples)
+            .serialize(Vec::new(), &quads)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        let directional = parse_str(
+            TurtleSyntax::Turtle,
+            "<http://a> <http://b> \"c\"@en--rtl .",
+            None,
+        )
+        .unwrap();
+        let error = JellySerializer::new()
+            .serialize(Vec::new(), &directional)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        let error = JellySerializer::new()
+            .with_table_sizes(8, 1, 1)
+            .serialize(Vec::new(), &quads)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,772 @@
+mod compact;
+mod context;
+mod expand;
+mod flatten;
+mod frame;
+mod from_rdf;
+mod to_rdf;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use serde_json::{Map, Value};
+
+use crate::rs::jsonld::compact::Compactor;
+use crate::rs::jsonld::context::Context;
+use crate::rs::jsonld::expand::Expander;
+use crate::rs::quad::Quad;
+
+/// Resolves the URLs of remote contexts (and `@import`s) to their JSON
+/// documents. Nothing in this module accesses the network itself.
+pub trait DocumentLoader {
+    fn load(&self, url: &str) -> Result<Value, JsonLdError>;
+}
+
+/// Fails to load any document, for inputs without remote contexts.
+pub struct NoDocumentLoader;
+
+impl DocumentLoader for NoDocumentLoader {
+    fn load(&self, url: &str) -> Result<Value, JsonLdError> {
+        Err(JsonLdError::new(
+            JsonLdErrorCode::LoadingDocumentFailed,
+            &format!("no document loader configured for <{url}>"),
+        ))
+    }
+}
+
+/// Serves documents from memory by their exact URL.
+#[derive(Clone, Debug, Default)]
+pub struct StaticDocumentLoader {
+    documents: HashMap<String, Value>,
+}
+
+impl StaticDocumentLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_document(mut self, url: &str, document: Value) -> Self {
+        self.insert(url, document);
+        self
+    }
+
+    pub fn insert(&mut self, url: &str, document: Value) {
+        self.documents.insert(url.to_owned(), document);
+    }
+}
+
+impl DocumentLoader for StaticDocumentLoader {
+    fn load(&self, url: &str) -> Result<Value, JsonLdError> {
+        self.documents.get(url).cloned().ok_or_else(|| {
+            JsonLdError::new(
+                JsonLdErrorCode::LoadingDocumentFailed,
+                &format!("unknown document <{url}>"),
+            )
+        })
+    }
+}
+
+/// Serves the URLs starting with a prefix from the files below a directory,
+/// e.g. `https://example.org/contexts/person.jsonld` from
+/// `contexts/person.jsonld` for the prefix `https://example.org/`.
+#[derive(Clone, Debug)]
+pub struct DirectoryDocumentLoader {
+    prefix: String,
+    directory: PathBuf,
+}
+
+impl DirectoryDocumentLoader {
+    pub fn new(prefix: &str, directory: impl Into<PathBuf>) -> Self {
+        Self {
+            prefix: prefix.to_owned(),
+            directory: directory.into(),
+        }
+    }
+}
+
+impl DocumentLoader for DirectoryDocumentLoader {
+    fn load(&self, url: &str) -> Result<Value, JsonLdError> {
+        let failed = |message: &str| {
+            JsonLdError::new(
+                JsonLdErrorCode::LoadingDocumentFailed,
+                &format!("<{url}>: {message}"),
+            )
+        };
+
+        let Some(path) = url.strip_prefix(&self.prefix) else {
+            return Err(failed("outside of the served prefix"));
+        };
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        if path
+            .split('/')
+            .any(|segment| segment == ".." || segment.is_empty())
+        {
+            return Err(failed("not a file below the served directory"));
+        }
+
+        let content = std::fs::read_to_string(self.directory.join(path))
+            .map_err(|error| failed(&error.to_string()))?;
+        serde_json::from_str(&content).map_err(|error| failed(&error.to_string()))
+    }
+}
+
+/// Error codes of the JSON-LD 1.1 API, displayed as in the specification.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JsonLdErrorCode {
+    CollidingKeywords,
+    ConflictingIndexes,
+    ContextOverflow,
+    CyclicIriMapping,
+    InvalidBaseDirection,
+    InvalidBaseIri,
+    InvalidContainerMapping,
+    InvalidContextEntry,
+    InvalidContextNullification,
+    InvalidDefaultLanguage,
+    InvalidEmbedValue,
+    InvalidFrame,
+    InvalidIdValue,
+    InvalidImportValue,
+    InvalidIncludedValue,
+    InvalidIndexValue,
+    InvalidIriMapping,
+    InvalidJsonLiteral,
+    InvalidKeywordAlias,
+    InvalidLanguageMapValue,
+    InvalidLanguageMapping,
+    InvalidLanguageTaggedString,
+    InvalidLanguageTaggedValue,
+    InvalidLocalContext,
+    InvalidNestValue,
+    InvalidPrefixValue,
+    InvalidPropagateValue,
+    InvalidProtectedValue,
+    InvalidRemoteContext,
+    InvalidReverseProperty,
+    InvalidReversePropertyMap,
+    InvalidReversePropertyValue,
+    InvalidReverseValue,
+    InvalidSetOrListObject,
+    InvalidTermDefinition,
+    InvalidTypeMapping,
+    InvalidTypeValue,
+    InvalidTypedValue,
+    InvalidValueObject,
+    InvalidValueObjectValue,
+    InvalidVersionValue,
+    InvalidVocabMapping,
+    IriConfusedWithPrefix,
+    KeywordRedefinition,
+    LoadingDocumentFailed,
+    LoadingRemoteContextFailed,
+    ProtectedTermRedefinition,
+}
+
+impl Display for JsonLdErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let code = match self {
+            JsonLdErrorCode::CollidingKeywords => "colliding keywords",
+            JsonLdErrorCode::ConflictingIndexes => "conflicting indexes",
+            JsonLdErrorCode::ContextOverflow => "context overflow",
+            JsonLdErrorCode::CyclicIriMapping => "cyclic IRI mapping",
+            JsonLdErrorCode::InvalidBaseDirection => "invalid base direction",
+            JsonLdErrorCode::InvalidBaseIri => "invalid base IRI",
+            JsonLdErrorCode::InvalidContainerMapping => "invalid container mapping",
+            JsonLdErrorCode::InvalidContextEntry => "invalid context entry",
+            JsonLdErrorCode::InvalidContextNullification => "invalid context nullification",
+            JsonLdErrorCode::InvalidDefaultLanguage => "invalid default language",
+            JsonLdErrorCode::InvalidEmbedValue => "invalid @embed value",
+            JsonLdErrorCode::InvalidFrame => "invalid frame",
+            JsonLdErrorCode::InvalidIdValue => "invalid @id value",
+            JsonLdErrorCode::InvalidImportValue => "invalid @import value",
+            JsonLdErrorCode::InvalidIncludedValue => "invalid @included value",
+            JsonLdErrorCode::InvalidIndexValue => "invalid @index value",
+            JsonLdErrorCode::InvalidIriMapping => "invalid IRI mapping",
+            JsonLdErrorCode::InvalidJsonLiteral => "invalid JSON literal",
+            JsonLdErrorCode::InvalidKeywordAlias => "invalid keyword alias",
+            JsonLdErrorCode::InvalidLanguageMapValue => "invalid language map value",
+            JsonLdErrorCode::InvalidLanguageMapping => "invalid language mapping",
+            JsonLdErrorCode::InvalidLanguageTaggedString => "invalid language-tagged string",
+            JsonLdErrorCode::InvalidLanguageTaggedValue => "invalid language-tagged value",
+            JsonLdErrorCode::InvalidLocalContext => "invalid local context",
+            JsonLdErrorCode::InvalidNestValue => "invalid @nest value",
+            JsonLdErrorCode::InvalidPrefixValue => "invalid @prefix value",
+            JsonLdErrorCode::InvalidPropagateValue => "invalid @propagate value",
+            JsonLdErrorCode::InvalidProtectedValue => "invalid @protected value",
+            JsonLdErrorCode::InvalidRemoteContext => "invalid remote context",
+            JsonLdErrorCode::InvalidReverseProperty => "invalid reverse property",
+            JsonLdErrorCode::InvalidReversePropertyMap => "invalid reverse property map",
+            JsonLdErrorCode::InvalidReversePropertyValue => "invalid reverse property value",
+            JsonLdErrorCode::InvalidReverseValue => "invalid @reverse value",
+            JsonLdErrorCode::InvalidSetOrListObject => "invalid set or list object",
+            JsonLdErrorCode::InvalidTermDefinition => "invalid term definition",
+            JsonLdErrorCode::InvalidTypeMapping => "invalid type mapping",
+            JsonLdErrorCode::InvalidTypeValue => "invalid type value",
+            JsonLdErrorCode::InvalidTypedValue => "invalid typed value",
+            JsonLdErrorCode::InvalidValueObject => "invalid value object",
+            JsonLdErrorCode::InvalidValueObjectValue => "invalid value object value",
+            JsonLdErrorCode::InvalidVersionValue => "invalid @version value",
+            JsonLdErrorCode::InvalidVocabMapping => "invalid vocab mapping",
+            JsonLdErrorCode::IriConfusedWithPrefix => "IRI confused with prefix",
+            JsonLdErrorCode::KeywordRedefinition => "keyword redefinition",
+            JsonLdErrorCode::LoadingDocumentFailed => "loading document failed",
+            JsonLdErrorCode::LoadingRemoteContextFailed => "loading remote context failed",
+            JsonLdErrorCode::ProtectedTermRedefinition => "protected term redefinition",
+        };
+        write!(f, "{code}")
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct JsonLdError {
+    code: JsonLdErrorCode,
+    message: String,
+}
+
+impl JsonLdError {
+    pub fn new(code: JsonLdErrorCode, message: &str) -> Self {
+        Self {
+            code,
+            message: message.to_owned(),
+        }
+    }
+
+    pub fn code(&self) -> JsonLdErrorCode {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for JsonLdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl Error for JsonLdError {}
+
+/// How framing embeds the nodes a node refers to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Embed {
+    /// Embed every reference, except ones back to a node being embedded.
+    Always,
+    /// Embed the first reference below each top-level node only.
+    #[default]
+    Once,
+    /// Keep references as `{"@id": ...}` objects.
+    Never,
+}
+
+/// JSON-LD 1.1 processor implementing the expansion, compaction, flattening,
+/// toRdf and fromRdf algorithms of the JSON-LD 1.1 Processing Algorithms and
+/// API, and JSON-LD 1.1 Framing.
+///
+/// Directional strings become directional literals (RDF 1.2) rather than
+/// being dropped or encoded as the specification's `rdfDirection` options
+/// do, and triple terms, which JSON-LD cannot express, are skipped by
+/// [`JsonLdProcessor::from_rdf`].
+pub struct JsonLdProcessor {
+    loader: Box<dyn DocumentLoader>,
+    base_iri: Option<String>,
+    compact_arrays: bool,
+    native_types: bool,
+    rdf_type: bool,
+    embed: Embed,
+    explicit: bool,
+    omit_default: bool,
+    require_all: bool,
+}
+
+impl JsonLdProcessor {
+    pub fn new() -> Self {
+        Self {
+            loader: Box::new(NoDocumentLoader),
+            base_iri: None,
+            compact_arrays: true,
+            native_types: false,
+            rdf_type: false,
+            embed: Embed::Once,
+            explicit: false,
+            omit_default: false,
+            require_all: false,
+        }
+    }
+
+    pub fn with_document_loader(mut self, loader: impl DocumentLoader + 'static) -> Self {
+        self.loader = Box::new(loader);
+        self
+    }
+
+    /// Base IRI of the input document, against which relative IRIs are
+    /// resolved when expanding and which they are made relative to when
+    /// compacting.
+    pub fn with_base_iri(mut self, base_iri: &str) -> Self {
+        self.base_iri = Some(base_iri.to_owned());
+        self
+    }
+
+    /// Whether compaction replaces arrays of one item by the item, which is
+    /// the default.
+    pub fn with_compact_arrays(mut self, compact_arrays: bool) -> Self {
+        self.compact_arrays = compact_arrays;
+        self
+    }
+
+    /// Whether [`JsonLdProcessor::from_rdf`] turns `xsd:boolean`,
+    /// `xsd:integer` and `xsd:double` literals into JSON booleans and
+    /// numbers.
+    pub fn with_native_types(mut self, native_types: bool) -> Self {
+        self.native_types = native_types;
+        self
+    }
+
+    /// Whether [`JsonLdProcessor::from_rdf`] keeps `rdf:type` as a property
+    /// instead of turning it into `@type`.
+    pub fn with_rdf_type(mut self, rdf_type: bool) -> Self {
+        self.rdf_type = rdf_type;
+        self
+    }
+
+    /// Default of `@embed` in frames.
+    pub fn with_embed(mut self, embed: Embed) -> Self {
+        self.embed = embed;
+        self
+    }
+
+    /// Default of `@explicit` in frames: whether framed nodes only keep the
+    /// properties their frame mentions.
+    pub fn with_explicit(mut self, explicit: bool) -> Self {
+        self.explicit = explicit;
+        self
+    }
+
+    /// Default of `@omitDefault` in frames: whether properties a frame
+    /// mentions but a node lacks are left out rather than set to their
+    /// `@default` (or `null`).
+    pub fn with_omit_default(mut self, omit_default: bool) -> Self {
+        self.omit_default = omit_default;
+        self
+    }
+
+    /// Default of `@requireAll` in frames: whether nodes must match all
+    /// properties of a frame rather than any.
+    pub fn with_require_all(mut self, require_all: bool) -> Self {
+        self.require_all = require_all;
+        self
+    }
+
+    pub fn expand(&self, input: &Value) -> Result<Value, JsonLdError> {
+        Expander::new(self).expand_document(input)
+    }
+
+    /// Compacts `input` with `context`, either a context or a document whose
+    /// `@context` entry is used.
+    pub fn compact(&self, input: &Value, context: &Value) -> Result<Value, JsonLdError> {
+        let expanded = self.expand(input)?;
+        self.compact_expanded(&expanded, context)
+    }
+
+    /// Collects all nodes of `input` at the top level of their graph,
+    /// labelling blank nodes anew, and compacts the result if a context is
+    /// given.
+    pub fn flatten(&self, input: &Value, context: Option<&Value>) -> Result<Value, JsonLdError> {
+        let flattened = flatten::flatten(&self.expand(input)?)?;
+        match context {
+            Some(context) => {
+                let compacted = self.compact_expanded(&flattened, context)?;
+                // Flattened documents always have a top-level @graph.
+                Ok(match compacted {
+                    Value::Object(map) if !is_graph_document(&map) => {
+                        let mut document = Map::new();
+                        if let Some(context) = map.get("@context") {
+                            document.insert("@context".to_owned(), context.clone());
+                        }
+                        let node: Map<String, Value> = map
+                            .into_iter()
+                            .filter(|(key, _)| key != "@context")
+                            .collect();
+                        let graph = if node.is_empty() {
+                            Vec::new()
+                        } else {
+                            vec![Value::Object(node)]
+                        };
+                        document.insert("@graph".to_owned(), Value::Array(graph));
+                        Value::Object(document)
+                    }
+                    compacted => compacted,
+                })
+            }
+            None => Ok(flattened),
+        }
+    }
+
+    /// Shapes `input` after `frame`: the nodes matching the frame become the
+    /// top-level nodes, with the nodes they refer to embedded as the frame
+    /// says, and the result is compacted with the frame's context.
+    pub fn frame(&self, input: &Value, frame: &Value) -> Result<Value, JsonLdError> {
+        self.frame_expanded(&self.expand(input)?, frame)
+    }
+
+    /// [`JsonLdProcessor::frame`] for the document `quads` serialize to.
+    pub fn frame_quads<'a>(
+        &self,
+        quads: impl IntoIterator<Item = &'a Quad>,
+        frame: &Value,
+    ) -> Result<Value, JsonLdError> {
+        self.frame_expanded(&self.from_rdf(quads)?, frame)
+    }
+
+    /// Deserializes the JSON-LD document `input` to quads.
+    pub fn to_rdf(&self, input: &Value) -> Result<Vec<Quad>, JsonLdError> {
+        to_rdf::to_rdf(&self.expand(input)?)
+    }
+
+    /// Serializes `quads` to an expanded JSON-LD document.
+    pub fn from_rdf<'a>(
+        &self,
+        quads: impl IntoIterator<Item = &'a Quad>,
+    ) -> Result<Value, JsonLdError> {
+        from_rdf::from_rdf(quads, self.native_types, self.rdf_type)
+    }
+
+    fn frame_expanded(&self, expanded: &Value, frame: &Value) -> Result<Value, JsonLdError> {
+        let expanded_frame = Expander::for_frame(self).expand_document(frame)?;
+        let flags = frame::Flags {
+            embed: self.embed,
+            explicit: self.explicit,
+            omit_default: self.omit_default,
+            require_all: self.require_all,
+        };
+        let framed = frame::frame(expanded, &expanded_frame, flags)?;
+        let context = frame.get("@context").unwrap_or(&Value::Null);
+        Ok(frame::remove_preserve(
+            self.compact_expanded(&framed, context)?,
+        ))
+    }
+
+    fn compact_expanded(&self, expanded: &Value, context: &Value) -> Result<Value, JsonLdError> {
+        let context = match context {
+            Value::Object(map) if map.contains_key("@context") => &map["@context"],
+            context => context,
+        };
+        let active = self
+            .initial_context()
+            .process(self.loader.as_ref(), context, None)?;
+        let compactor = Compactor::new(self);
+        let compacted = compactor.compact(&active, None, expanded)?;
+
+        let mut document = match compacted {
+            Value::Array(items) if items.is_empty() => Map::new(),
+            Value::Array(items) => {
+                let mut document = Map::new();
+                let graph = compactor.compact_iri(&active, "@graph", None, true, false)?;
+                document.insert(graph, Value::Array(items));
+                document
+            }
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        let has_context = match context {
+            Value::Null => false,
+            Value::Object(map) => !map.is_empty(),
+            Value::Array(items) => !items.is_empty(),
+            _ => true,
+        };
+        if has_context {
+            document.insert("@context".to_owned(), context.clone());
+        }
+        Ok(Value::Object(document))
+    }
+
+    fn initial_context(&self) -> Context {
+        Context::new(self.base_iri.as_deref())
+    }
+}
+
+impl Default for JsonLdProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_graph_document(map: &Map<String, Value>) -> bool {
+    map.keys().all(|key| key == "@context" || key == "@graph")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::rs::dataset::Dataset;
+    use crate::rs::quad_object::QuadObject;
+    use crate::rs::quad_subject::QuadSubject;
+    use crate::rs::term_like::TermLike;
+    use crate::rs::turtle::{TurtleSyntax, parse_str};
+
+    const CONTEXT: &str = r#"{
+        "@vocab": "http://schema.org/",
+        "ex": "http://example.org/",
+        "knows": {"@type": "@id"},
+        "born": {"@id": "birthDate", "@type": "http://www.w3.org/2001/XMLSchema#date"},
+        "label": {"@id": "ex:label", "@container": "@language"},
+        "children": {"@id": "ex:children", "@container": "@list"},
+        "parent": {"@reverse": "ex:children"}
+    }"#;
+
+    fn context() -> Value {
+        serde_json::from_str(CONTEXT).unwrap()
+    }
+
+    fn document() -> Value {
+        json!({
+            "@context": context(),
+            "@id": "ex:alice",
+            "@type": "Person",
+            "name": "Alice",
+            "knows": "ex:bob",
+            "born": "1990-01-01",
+            "label": {"en": "Alice", "fr": "Alice"},
+            "children": [{"@id": "ex:carol"}, {"name": "Dave"}]
+        })
+    }
+
+    #[test]
+    fn documents_expand_and_compact() {
+        let processor = JsonLdProcessor::new();
+        let expanded = processor.expand(&document()).unwrap();
+        assert_eq!(
+            expanded,
+            json!([{
+                "@id": "http://example.org/alice",
+                "@type": ["http://schema.org/Person"],
+                "http://schema.org/name": [{"@value": "Alice"}],
+                "http://schema.org/knows": [{"@id": "http://example.org/bob"}],
+                "http://schema.org/birthDate": [{
+                    "@value": "1990-01-01",
+                    "@type": "http://www.w3.org/2001/XMLSchema#date"
+                }],
+                "http://example.org/label": [
+                    {"@value": "Alice", "@language": "en"},
+                    {"@value": "Alice", "@language": "fr"}
+                ],
+                "http://example.org/children": [{"@list": [
+                    {"@id": "http://example.org/carol"},
+                    {"http://schema.org/name": [{"@value": "Dave"}]}
+                ]}]
+            }])
+        );
+
+        let compacted = processor.compact(&expanded, &context()).unwrap();
+        assert_eq!(compacted, document());
+
+        let reversed = json!({
+            "@context": context(),
+            "@id": "ex:carol",
+            "parent": {"@id": "ex:alice", "name": "Alice"}
+        });
+        let expanded = processor.expand(&reversed).unwrap();
+        assert_eq!(
+            expanded[0]["@reverse"]["http://example.org/children"][0]["@id"],
+            "http://example.org/alice"
+        );
+        assert_eq!(processor.compact(&expanded, &context()).unwrap(), reversed);
+    }
+
+    #[test]
+    fn documents_flatten() {
+        let input = json!({
+            "@context": {"@vocab": "http://schema.org/"},
+            "name": "Alice",
+            "knows": {"name": "Bob", "knows": {"@id": "http://example.org/carol"}}
+        });
+        let flattened = JsonLdProcessor::new()
+            .flatten(&input, Some(&json!({"@vocab": "http://schema.org/"})))
+            .unwrap();
+        assert_eq!(
+            flattened,
+            json!({
+                "@context": {"@vocab": "http://schema.org/"},
+                "@graph": [
+                    {"@id": "_:b0", "name": "Alice", "knows": {"@id": "_:b1"}},
+                    {"@id": "_:b1", "name": "Bob", "knows": {"@id": "http://example.org/carol"}}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn documents_convert_to_and_from_rdf() {
+        let processor = JsonLdProcessor::new();
+        let quads = processor.to_rdf(&document()).unwrap();
+        let expected = parse_str(
+            TurtleSyntax::Turtle,
+            r#"@prefix ex: <http://example.org/> .
+               @prefix s: <http://schema.org/> .
+               ex:alice a s:Person ;
+                   s:name "Alice" ;
+                   s:knows ex:bob ;
+                   s:birthDate "1990-01-01"^^<http://www.w3.org/2001/XMLSchema#date> ;
+                   ex:label "Alice"@en, "Alice"@fr ;
+                   ex:children ( ex:carol [ s:name "Dave" ] ) ."#,
+            None,
+        )
+        .unwrap();
+        assert_eq!(quads.len(), expected.len());
+        let dataset: Dataset = quads.iter().collect();
+        let named = expected
+            .iter()
+            .filter(|quad| {
+                !matches!(quad.subject(), QuadSubject::BlankNode(_))
+                    && !matches!(quad.object(), QuadObject::BlankNode(_))
+            })
+            .all(|quad| dataset.contains(quad));
+        assert!(named);
+
+        let graph = json!({
+            "@context": {"@vocab": "http://example.org/", "@language": "he", "@direction": "rtl"},
+            "@id": "http://example.org/g",
+            "@graph": {
+                "@id": "http://example.org/s",
+                "title": "\u{5e9}\u{5dc}\u{5d5}\u{5dd}",
+                "count": 5,
+                "ratio": 0.5,
+                "flag": true,
+                "data": {"@value": {"b": [1, 2], "a": null}, "@type": "@json"}
+            }
+        });
+        let quads = processor.to_rdf(&graph).unwrap();
+        assert_eq!(quads.len(), 5);
+        assert!(
+            quads
+                .iter()
+                .all(|quad| quad.graph().value() == "http://example.org/g")
+        );
+        let data = quads
+            .iter()
+            .find(|quad| quad.predicate().value() == "http://example.org/data")
+            .unwrap();
+        assert_eq!(data.object().value(), r#"{"a":null,"b":[1,2]}"#);
+
+        let native = JsonLdProcessor::new().with_native_types(true);
+        let round_trip = native.from_rdf(&quads).unwrap();
+        assert_eq!(
+            native
+                .compact(&round_trip, &json!({"@vocab": "http://example.org/"}))
+                .unwrap(),
+            json!({
+                "@context": {"@vocab": "http://example.org/"},
+                "@id": "http://example.org/g",
+                "@graph": [{
+                    "@id": "http://example.org/s",
+                    "count": 5,
+                    "data": {"@value": {"a": null, "b": [1, 2]}, "@type": "@json"},
+                    "flag": true,
+                    "ratio": 0.5,
+                    "title": {
+                        "@value": "\u{5e9}\u{5dc}\u{5d5}\u{5dd}",
+                        "@language": "he",
+                        "@direction": "rtl"
+                    }
+                }]
+            })
+        );
+        assert_eq!(processor.to_rdf(&round_trip).unwrap(), quads);
+    }
+
+    #[test]
+    fn scoped_contexts_apply_to_their_values() {
+        let processor = JsonLdProcessor::new();
+        let input = json!({
+            "@context": {
+                "@vocab": "http://example.org/",
+                "@protected": true,
+                "name": "http://example.org/name",
+                "knows": {"@context": {"name": "http://example.org/nick"}},
+                "Pet": {"@context": {"name": "http://example.org/petName"}}
+            },
+            "name": "Alice",
+            "knows": {"name": "Bob", "knows": {"name": "Carol"}},
+            "owns": {"@type": "Pet", "name": "Rex", "friend": {"name": "Dave"}}
+        });
+        assert_eq!(
+            processor.expand(&input).unwrap(),
+            json!([{
+                "http://example.org/name": [{"@value": "Alice"}],
+                "http://example.org/knows": [{
+                    "http://example.org/nick": [{"@value": "Bob"}],
+                    "http://example.org/knows": [{"http://example.org/nick": [{"@value": "Carol"}]}]
+                }],
+                "http://example.org/owns": [{
+                    "@type": ["http://example.org/Pet"],
+                    "http://example.org/petName": [{"@value": "Rex"}],
+                    "http://example.org/friend": [{"http://example.org/name": [{"@value": "Dave"}]}]
+                }]
+            }])
+        );
+
+        // Unlike scoped contexts, embedded ones cannot override protected
+        // terms.
+        let embedded = json!({
+            "@context": {"@protected": true, "name": "http://example.org/name"},
+            "http://example.org/knows": {
+                "@context": {"name": "http://example.org/nick"},
+                "name": "Bob"
+            }
+        });
+        assert_eq!(
+            processor.expand(&embedded).unwrap_err().code(),
+            JsonLdErrorCode::ProtectedTermRedefinition
+        );
+    }
+
+    #[test]
+    fn remote_contexts_come_from_the_loader() {
+        let directory =
+            std::env::temp_dir().join(format!("rdfjs-rust-{}-jsonld", std::process::id()));
+        std::fs::create_dir_all(directory.join("contexts")).unwrap();
+        std::fs::write(
+            directory.join("contexts/schema.jsonld"),
+            r#"{"@context": {"@vocab": "http://schema.org/", "@protected": true, "name": "name"}}"#,
+        )
+        .unwrap();
+
+        let input =
+            json!({"@context": "https://example.org/contexts/schema.jsonld", "name": "Alice"});
+        let loaders: Vec<Box<dyn DocumentLoader>> = vec![
+            Box::new(DirectoryDocumentLoader::new("https://example.org/", &directory)),
+            Box::new(StaticDocumentLoader::new().with_document(
+                "https://example.org/contexts/schema.jsonld",
+                json!({"@context": {"@vocab": "http://schema.org/", "@protected": true, "name": "name"}}),
+            )),
+        ];
+        for loader in loaders {
+            let processor = JsonLdProcessor {
+                loader,
+                ..JsonLdProcessor::new()
+            };
+            assert_eq!(
+                processor.expand(&input).unwrap(),
+                json!([{"http://schema.org/name": [{"@value": "Alice"}]}])
+            );
+
+            let redefined = json!({
+                "@context": ["https://example.org/contexts/schema.jsonld", {"name": "http://example.org/name"}],
+                "name": "Alice"
+            });
+            assert_eq!(
+                processor.expand(&redefined).unwrap_err().code(),
+                JsonLdErrorCode::ProtectedTermRedefinition
+            );
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let error = JsonLdProcessor::new().expand(&input).unwrap_err();
+        assert_eq!(error.code(), JsonLdErrorCode::LoadingRemoteContextFailed);
+    }
+}
@@ -0,0 +1,857 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::rs::iri::resolve;
+use crate::rs::jsonld::context::{Context, error, split_compact_iri};
+use crate::rs::jsonld::expand::{
+    add_value, as_slice, is_graph_object, is_list_object, is_value_object,
+};
+use crate::rs::jsonld::{JsonLdError, JsonLdErrorCode, JsonLdProcessor};
+
+/// Terms by `@language`, `@type` or `@any` and preferred value.
+type TypeLanguageMap = HashMap<&'static str, HashMap<String, String>>;
+
+/// Terms by IRI, container (the concatenated container keywords or `@none`),
+/// `@language`/`@type`/`@any` and preferred value.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct InverseContext {
+    entries: HashMap<String, HashMap<String, TypeLanguageMap>>,
+}
+
+impl InverseContext {
+    fn new(active: &Context) -> Self {
+        let default_language = default_language(active);
+        let mut terms: Vec<_> = active.terms.iter().collect();
+        terms.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then(a.cmp(b)));
+
+        let mut inverse = Self::default();
+        for (term, definition) in terms {
+            let Some(iri) = &definition.iri else {
+                continue;
+            };
+            let container = if definition.container.is_empty() {
+                "@none".to_owned()
+            } else {
+                definition.container.concat()
+            };
+            let maps = inverse
+                .entries
+                .entry(iri.clone())
+                .or_default()
+                .entry(container)
+                .or_insert_with(|| {
+                    HashMap::from([
+                        ("@language", HashMap::new()),
+                        ("@type", HashMap::new()),
+                        ("@any", HashMap::from([("@none".to_owned(), term.clone())])),
+                    ])
+                });
+            let mut add = |map: &'static str, key: String| {
+                maps.get_mut(map)
+                    .unwrap()
+                    .entry(key)
+                    .or_insert_with(|| term.clone());
+            };
+
+            if definition.reverse {
+                add("@type", "@reverse".to_owned());
+            } else if definition.type_mapping.as_deref() == Some("@none") {
+                add("@language", "@any".to_owned());
+                add("@type", "@any".to_owned());
+            } else if let Some(type_mapping) = &definition.type_mapping {
+                add("@type", type_mapping.clone());
+            } else if let (Some(language), Some(direction)) =
+                (&definition.language, &definition.direction)
+            {
+                let key = match (language, direction) {
+                    (Some(language), Some(direction)) => {
+                        format!("{}_{direction}", language.to_lowercase())
+                    }
+                    (Some(language), None) => language.to_lowercase(),
+                    (None, Some(direction)) => format!("_{direction}"),
+                    (None, None) => "@null".to_owned(),
+                };
+                add("@language", key);
+            } else if let Some(language) = &definition.language {
+                add(
+                    "@language",
+                    language
+                        .as_ref()
+                        .map_or("@null".to_owned(), |l| l.to_lowercase()),
+                );
+            } else if let Some(direction) = &definition.direction {
+                add(
+                    "@language",
+                    direction
+                        .as_ref()
+                        .map_or("@none".to_owned(), |d| format!("_{d}")),
+                );
+            } else {
+                add("@language", default_language.clone());
+                add("@language", "@none".to_owned());
+                add("@type", "@none".to_owned());
+            }
+        }
+        inverse
+    }
+
+    fn select(
+        &self,
+        iri: &str,
+        containers: &[&str],
+        type_language: &str,
+        preferred: &[String],
+    ) -> Option<&str> {
+        let container_map = self.entries.get(iri)?;
+        for container in containers {
+            let Some(maps) = container_map.get(*container) else {
+                continue;
+            };
+            let map = &maps[type_language];
+            if let Some(term) = preferred.iter().find_map(|value| map.get(value)) {
+                return Some(term);
+            }
+        }
+        None
+    }
+}
+
+fn default_language(active: &Context) -> String {
+    match (&active.language, &active.direction) {
+        (Some(language), Some(direction)) => format!("{}_{direction}", language.to_lowercase()),
+        (Some(language), None) => language.to_lowercase(),
+        (None, Some(direction)) => format!("_{direction}"),
+        (None, None) => "@none".to_owned(),
+    }
+}
+
+pub(crate) struct Compactor<'a> {
+    processor: &'a JsonLdProcessor,
+}
+
+impl<'a> Compactor<'a> {
+    pub(crate) fn new(processor: &'a JsonLdProcessor) -> Self {
+        Self { processor }
+    }
+
+    pub(crate) fn compact(
+        &self,
+        active: &Context,
+        active_property: Option<&str>,
+        element: &Value,
+    ) -> Result<Value, JsonLdError> {
+        let map = match element {
+            Value::Array(items) => {
+                let mut result = Vec::new();
+                for item in items {
+                    let compacted = self.compact(active, active_property, item)?;
+                    if !compacted.is_null() {
+                        result.push(compacted);
+                    }
+                }
+                let keeps_array = active
+                    .term(active_property)
+                    .is_some_and(|d| d.has_container("@list") || d.has_container("@set"))
+                    || matches!(active_property, Some("@graph" | "@set"));
+                if result.len() == 1 && self.processor.compact_arrays && !keeps_array {
+                    return Ok(result.pop().unwrap());
+                }
+                return Ok(Value::Array(result));
+            }
+            Value::Object(map) => map,
+            scalar => return Ok(scalar.clone()),
+        };
+
+        let type_scoped = active;
+        let mut active = active.clone();
+        if let Some(previous) = &active.previous
+            && !map.contains_key("@value")
+            && !(map.len() == 1 && map.contains_key("@id"))
+        {
+            active = (**previous).clone();
+        }
+        let property_definition = type_scoped.term(active_property);
+        if let Some(context) = property_definition.and_then(|d| d.context.as_ref()) {
+            let base = property_definition.and_then(|d| d.base_url.as_deref());
+            active = active.process_scoped(self.loader(), context, base, true)?;
+        }
+
+        if map.contains_key("@value") || (map.len() == 1 && map.contains_key("@id")) {
+            let compacted = self.compact_value(&active, active_property, map)?;
+            let is_json = active
+                .term(active_property)
+                .is_some_and(|d| d.type_mapping.as_deref() == Some("@json"));
+            if !compacted.is_object() || is_json {
+                return Ok(compacted);
+            }
+        }
+
+        let definition = active.term(active_property);
+        let index_container = definition.is_some_and(|d| d.has_container("@index"));
+        if is_list_object(element) && definition.is_some_and(|d| d.has_container("@list")) {
+            return self.compact(&active, active_property, &map["@list"]);
+        }
+
+        let inside_reverse = active_property == Some("@reverse");
+        let mut result = Map::new();
+
+        if let Some(types) = map.get("@type") {
+            let mut compacted = Vec::new();
+            for item in as_slice(types) {
+                if let Value::String(item) = item {
+                    compacted.push(self.compact_iri(type_scoped, item, None, true, false)?);
+                }
+            }
+            compacted.sort();
+            for term in compacted {
+                if let Some(definition) = type_scoped.terms.get(&term)
+                    && let Some(context) = &definition.context
+                {
+                    active = active.process_scoped(
+                        self.loader(),
+                        context,
+                        definition.base_url.as_deref(),
+                        false,
+                    )?;
+                }
+            }
+        }
+        let active = &active;
+
+        for (property, value) in map {
+            match property.as_str() {
+                "@id" => {
+                    let id = value.as_str().unwrap_or_default();
+                    let compacted = self.compact_iri(active, id, None, false, false)?;
+                    let alias = self.compact_iri(active, "@id", None, true, false)?;
+                    result.insert(alias, Value::String(compacted));
+                    continue;
+                }
+                "@type" => {
+                    let mut compacted = Vec::new();
+                    for item in as_slice(value) {
+                        if let Value::String(item) = item {
+                            let item = self.compact_iri(type_scoped, item, None, true, false)?;
+                            compacted.push(Value::String(item));
+                        }
+                    }
+                    let alias = self.compact_iri(active, "@type", None, true, false)?;
+                    let as_array = active
+                        .terms
+                        .get(&alias)
+                        .is_some_and(|d| d.has_container("@set"))
+                        || !self.processor.compact_arrays;
+                    let compacted = if compacted.len() == 1 && !as_array {
+                        compacted.pop().unwrap()
+                    } else {
+                        Value::Array(compacted)
+                    };
+                    add_value(&mut result, &alias, compacted, as_array);
+                    continue;
+                }
+                "@reverse" => {
+                    let Value::Object(mut compacted) =
+                        self.compact(active, Some("@reverse"), value)?
+                    else {
+                        continue;
+                    };
+                    let reverse_terms: Vec<String> = compacted
+                        .keys()
+                        .filter(|key| active.terms.get(*key).is_some_and(|d| d.reverse))
+                        .cloned()
+                        .collect();
+                    for term in reverse_terms {
+                        let value = compacted.remove(&term).unwrap();
+                        let as_array = active.terms[&term].has_container("@set")
+                            || !self.processor.compact_arrays;
+                        add_value(&mut result, &term, value, as_array);
+                    }
+                    if !compacted.is_empty() {
+                        let alias = self.compact_iri(active, "@reverse", None, true, false)?;
+                        result.insert(alias, Value::Object(compacted));
+                    }
+                    continue;
+                }
+                "@preserve" => {
+                    let compacted = self.compact(active, active_property, value)?;
+                    result.insert("@preserve".to_owned(), compacted);
+                    continue;
+                }
+                "@index" if index_container => continue,
+                "@direction" | "@index" | "@language" | "@value" => {
+                    let alias = self.compact_iri(active, property, None, true, false)?;
+                    result.insert(alias, value.clone());
+                    continue;
+                }
+                _ => {}
+            }
+
+            let items = as_slice(value);
+            if items.is_empty() {
+                let item_property =
+                    self.compact_iri(active, property, Some(value), true, inside_reverse)?;
+                let target = self.nest_target(active, &mut result, &item_property)?;
+                add_value(target, &item_property, Value::Array(Vec::new()), true);
+            }
+
+            for item in items {
+                let item_property =
+                    self.compact_iri(active, property, Some(item), true, inside_reverse)?;
+                let container = active
+                    .terms
+                    .get(&item_property)
+                    .map(|d| d.container.clone())
+                    .unwrap_or_default();
+                let has = |keyword: &str| container.iter().any(|c| c == keyword);
+                let as_array = has("@set")
+                    || item_property == "@graph"
+                    || item_property == "@list"
+                    || !self.processor.compact_arrays;
+
+                let inner = if is_list_object(item) {
+                    &item["@list"]
+                } else if is_graph_object(item) {
+                    &item["@graph"]
+                } else {
+                    item
+                };
+                let mut compacted = self.compact(active, Some(&item_property), inner)?;
+
+                let target = self.nest_target(active, &mut result, &item_property)?;
+                if is_list_object(item) {
+                    if !compacted.is_array() {
+                        compacted = Value::Array(vec![compacted]);
+                    }
+                    if has("@list") {
+                        target.insert(item_property, compacted);
+                    } else {
+                        let mut list = Map::new();
+                        list.insert(
+                            self.compact_iri(active, "@list", None, true, false)?,
+                            compacted,
+                        );
+                        if let Some(index) = item.get("@index") {
+                            list.insert(
+                                self.compact_iri(active, "@index", None, true, false)?,
+                                index.clone(),
+                            );
+                        }
+                        add_value(target, &item_property, Value::Object(list), as_array);
+                    }
+                } else if is_graph_object(item) {
+                    let id = item.get("@id").and_then(Value::as_str);
+                    let index = item.get("@index").and_then(Value::as_str);
+                    if has("@graph") && has("@id") {
+                        let key = match id {
+                            Some(id) => self.compact_iri(active, id, None, false, false)?,
+                            None => self.compact_iri(active, "@none", None, true, false)?,
+                        };
+                        let map = map_object(target, &item_property);
+                        add_value(map, &key, compacted, as_array);
+                    } else if has("@graph") && has("@index") && id.is_none() {
+                        let key = match index {
+                            Some(index) => index.to_owned(),
+                            None => self.compact_iri(active, "@none", None, true, false)?,
+                        };
+                        let map = map_object(target, &item_property);
+                        add_value(map, &key, compacted, as_array);
+                    } else if has("@graph") && id.is_none() {
+                        if compacted.as_array().is_some_and(|items| items.len() > 1) {
+                            let alias = self.compact_iri(active, "@included", None, true, false)?;
+                            compacted = Value::Object(Map::from_iter([(alias, compacted)]));
+                        }
+                        add_value(target, &item_property, compacted, as_array);
+                    } else {
+                        let mut graph = Map::new();
+                        let alias = self.compact_iri(active, "@graph", None, true, false)?;
+                        graph.insert(alias, compacted);
+                        if let Some(id) = id {
+                            let alias = self.compact_iri(active, "@id", None, true, false)?;
+                            graph.insert(
+                                alias,
+                                Value::String(self.compact_iri(active, id, None, false, false)?),
+                            );
+                        }
+                        if let Some(index) = index {
+                            let alias = self.compact_iri(active, "@index", None, true, false)?;
+                            graph.insert(alias, Value::String(index.to_owned()));
+                        }
+                        add_value(target, &item_property, Value::Object(graph), as_array);
+                    }
+                } else if !has("@graph")
+                    && (has("@language") || has("@index") || has("@id") || has("@type"))
+                {
+                    let index_key = active
+                        .terms
+                        .get(&item_property)
+                        .and_then(|d| d.index.clone())
+                        .unwrap_or_else(|| "@index".to_owned());
+                    let key = if has("@language") {
+                        if let Some(value) = item.get("@value") {
+                            compacted = value.clone();
+                        }
+                        item.get("@language")
+                            .and_then(Value::as_str)
+                            .map(str::to_owned)
+                    } else if has("@index") && index_key == "@index" {
+                        item.get("@index")
+                            .and_then(Value::as_str)
+                            .map(str::to_owned)
+                    } else if has("@index") {
+                        let property = self.compact_iri(active, &index_key, None, true, false)?;
+                        take_first(&mut compacted, &property)
+                    } else if has("@id") {
+                        let alias = self.compact_iri(active, "@id", None, true, false)?;
+                        compacted
+                            .as_object_mut()
+                            .and_then(|map| map.remove(&alias))
+                            .and_then(|id| id.as_str().map(str::to_owned))
+                    } else {
+                        let alias = self.compact_iri(active, "@type", None, true, false)?;
+                        let key = take_first(&mut compacted, &alias);
+                        if compacted.as_object().is_some_and(|map| map.len() == 1)
+                            && let Some(id) = item.get("@id")
+                        {
+                            let reference =
+                                Value::Object(Map::from_iter([("@id".to_owned(), id.clone())]));
+                            compacted = self.compact(active, Some(&item_property), &reference)?;
+                        }
+                        key
+                    };
+                    let key = match key {
+                        Some(key) => key,
+                        None => self.compact_iri(active, "@none", None, true, false)?,
+                    };
+                    let map = map_object(target, &item_property);
+                    add_value(map, &key, compacted, as_array);
+                } else {
+                    add_value(target, &item_property, compacted, as_array);
+                }
+            }
+        }
+        Ok(Value::Object(result))
+    }
+
+    fn loader(&self) -> &dyn crate::rs::jsonld::DocumentLoader {
+        self.processor.loader.as_ref()
+    }
+
+    /// The object the values of `property` go to: `result` or, for terms
+    /// with an `@nest` mapping, the object under the nesting term.
+    fn nest_target<'m>(
+        &self,
+        active: &Context,
+        result: &'m mut Map<String, Value>,
+        property: &str,
+    ) -> Result<&'m mut Map<String, Value>, JsonLdError> {
+        let Some(nest) = active.terms.get(property).and_then(|d| d.nest.as_deref()) else {
+            return Ok(result);
+        };
+        if nest != "@nest" && active.expand_iri(nest, false, true).as_deref() != Some("@nest") {
+            return error(
+                JsonLdErrorCode::InvalidNestValue,
+                &format!("{nest:?} is not an alias of @nest"),
+            );
+        }
+        Ok(map_object(result, nest))
+    }
+
+    fn compact_value(
+        &self,
+        active: &Context,
+        active_property: Option<&str>,
+        value: &Map<String, Value>,
+    ) -> Result<Value, JsonLdError> {
+        let definition = active.term(active_property);
+        let language = definition
+            .and_then(|d| d.language.clone())
+            .unwrap_or_else(|| active.language.clone());
+        let direction = definition
+            .and_then(|d| d.direction.clone())
+            .unwrap_or_else(|| active.direction.clone());
+        let type_mapping = definition.and_then(|d| d.type_mapping.as_deref());
+        let index_container = definition.is_some_and(|d| d.has_container("@index"));
+        let without_index = !value.contains_key("@index") || index_container;
+
+        if let Some(Value::String(id)) = value.get("@id")
+            && (value.len() == 1 || (value.len() == 2 && value.contains_key("@index")))
+        {
+            match type_mapping {
+                Some("@id") if without_index => {
+                    return Ok(Value::String(
+                        self.compact_iri(active, id, None, false, false)?,
+                    ));
+                }
+                Some("@vocab") if without_index => {
+                    return Ok(Value::String(
+                        self.compact_iri(active, id, None, true, false)?,
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        let literal = value.get("@value");
+        let datatype = value.get("@type").and_then(Value::as_str);
+        if let Some(literal) = literal {
+            if datatype.is_some() && datatype == type_mapping && without_index {
+                return Ok(literal.clone());
+            }
+            if type_mapping != Some("@none") && datatype.is_none() && without_index {
+                if !literal.is_string() {
+                    return Ok(literal.clone());
+                }
+                let same_language = value
+                    .get("@language")
+                    .and_then(Value::as_str)
+                    .map(str::to_lowercase)
+                    == language.as_deref().map(str::to_lowercase);
+                let same_direction =
+                    value.get("@direction").and_then(Value::as_str) == direction.as_deref();
+                if same_language && same_direction {
+                    return Ok(literal.clone());
+                }
+            }
+        }
+
+        let mut result = Map::new();
+        for (key, entry) in value {
+            if key == "@index" && index_container {
+                continue;
+            }
+            let alias = self.compact_iri(active, key, None, true, false)?;
+            let entry = match (key.as_str(), entry) {
+                ("@type", Value::String(datatype)) => {
+                    Value::String(self.compact_iri(active, datatype, None, true, false)?)
+                }
+                ("@id", Value::String(id)) => {
+                    Value::String(self.compact_iri(active, id, None, false, false)?)
+                }
+                _ => entry.clone(),
+            };
+            result.insert(alias, entry);
+        }
+        Ok(Value::Object(result))
+    }
+
+    /// IRI compaction: the term, compact IRI or relative IRI best
+    /// representing `iri`, given the `value` it is used with.
+    pub(crate) fn compact_iri(
+        &self,
+        active: &Context,
+        iri: &str,
+        value: Option<&Value>,
+        vocab: bool,
+        reverse: bool,
+    ) -> Result<String, JsonLdError> {
+        let inverse = active.inverse.get_or_init(|| InverseContext::new(active));
+
+        if vocab && inverse.entries.contains_key(iri) {
+            let (containers, type_language, preferred) =
+                self.term_selection_input(active, value, reverse)?;
+            let containers: Vec<&str> = containers.iter().map(String::as_str).collect();
+            if let Some(term) = inverse.select(iri, &containers, type_language, &preferred) {
+                return Ok(term.to_owned());
+            }
+        }
+
+        if vocab
+            && let Some(mapping) = &active.vocab
+            && let Some(suffix) = iri.strip_prefix(mapping.as_str())
+            && !suffix.is_empty()
+            && !active.terms.contains_key(suffix)
+        {
+            return Ok(suffix.to_owned());
+        }
+
+        let mut compact: Option<String> = None;
+        for (term, definition) in &active.terms {
+            let Some(prefix) = &definition.iri else {
+                continue;
+            };
+            if !definition.prefix || prefix == iri || !iri.starts_with(prefix.as_str()) {
+                continue;
+            }
+            let candidate = format!("{term}:{}", &iri[prefix.len()..]);
+            let shorter = compact
+                .as_ref()
+                .is_none_or(|current| (candidate.len(), &candidate) < (current.len(), current));
+            let usable = match active.terms.get(&candidate) {
+                None => true,
+                Some(definition) => definition.iri.as_deref() == Some(iri) && value.is_none(),
+            };
+            if shorter && usable {
+                compact = Some(candidate);
+            }
+        }
+        if let Some(compact) = compact {
+            return Ok(compact);
+        }
+
+        if let Some((prefix, suffix)) = split_compact_iri(iri)
+            && !suffix.starts_with("//")
+            && active.terms.get(prefix).is_some_and(|d| d.prefix)
+        {
+            return error(
+                JsonLdErrorCode::IriConfusedWithPrefix,
+                &format!("<{iri}> would be read as a compact IRI"),
+            );
+        }
+
+        if !vocab && let Some(base) = &active.base {
+            return Ok(relativize(base, iri));
+        }
+        Ok(iri.to_owned())
+    }
+
+    /// The containers, `@language`/`@type` choice and preferred values to
+    /// select a term with, from step 4 of the IRI compaction algorithm.
+    fn term_selection_input(
+        &self,
+        active: &Context,
+        value: Option<&Value>,
+        reverse: bool,
+    ) -> Result<(Vec<String>, &'static str, Vec<String>), JsonLdError> {
+        let default_language = default_language(active);
+        let null = Value::Null;
+        let value = value.unwrap_or(&null);
+        let has = |key: &str| value.get(key).is_some();
+
+        let mut containers: Vec<&str> = Vec::new();
+        let mut type_language = "@language";
+        let mut type_language_value = "@null".to_owned();
+
+        if has("@index") && !is_graph_object(value) {
+            containers.extend(["@index", "@index@set"]);
+        }
+        if reverse {
+            type_language = "@type";
+            type_language_value = "@reverse".to_owned();
+            containers.push("@set");
+        } else if is_list_object(value) {
+            if !has("@index") {
+                containers.push("@list");
+            }
+            let list = as_slice(&value["@list"]);
+            let mut common_language = list.is_empty().then(|| default_language.clone());
+            let mut common_type: Option<String> = None;
+            for item in list {
+                let mut item_language = "@none".to_owned();
+                let mut item_type = "@none".to_owned();
+                if is_value_object(item) {
+                    if let Some(direction) = item.get("@direction").and_then(Value::as_str) {
+                        let language = item
+                            .get("@language")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default();
+                        item_language = format!("{}_{direction}", language.to_lowercase());
+                    } else if let Some(language) = item.get("@language").and_then(Value::as_str) {
+                        item_language = language.to_lowercase();
+                    } else if let Some(datatype) = item.get("@type").and_then(Value::as_str) {
+                        item_type = datatype.to_owned();
+                    } else {
+                        item_language = "@null".to_owned();
+                    }
+                } else {
+                    item_type = "@id".to_owned();
+                }
+                match &common_language {
+                    None => common_language = Some(item_language),
+                    Some(language) if *language != item_language && is_value_object(item) => {
+                        common_language = Some("@none".to_owned());
+                    }
+                    _ => {}
+                }
+                match &common_type {
+                    None => common_type = Some(item_type),
+                    Some(datatype) if *datatype != item_type => {
+                        common_type = Some("@none".to_owned())
+                    }
+                    _ => {}
+                }
+                if common_language.as_deref() == Some("@none")
+                    && common_type.as_deref() == Some("@none")
+                {
+                    break;
+                }
+            }
+            let common_language = common_language.unwrap_or_else(|| "@none".to_owned());
+            let common_type = common_type.unwrap_or_else(|| "@none".to_owned());
+            if common_type != "@none" {
+                type_language = "@type";
+                type_language_value = common_type;
+            } else {
+                type_language_value = common_language;
+            }
+        } else if is_graph_object(value) {
+            if has("@index") {
+                containers.extend(["@graph@index", "@graph@index@set"]);
+            }
+            if has("@id") {
+                containers.extend(["@graph@id", "@graph@id@set"]);
+            }
+            containers.extend(["@graph", "@graph@set", "@set"]);
+            if !has("@index") {
+                containers.extend(["@graph@index", "@graph@index@set"]);
+            }
+            if !has("@id") {
+                containers.extend(["@graph@id", "@graph@id@set"]);
+            }
+            containers.extend(["@index", "@index@set"]);
+            type_language = "@type";
+            type_language_value = "@id".to_owned();
+        } else {
+            if is_value_object(value) {
+                if let Some(direction) = value.get("@direction").and_then(Value::as_str)
+                    && !has("@index")
+                {
+                    let language = value
+                        .get("@language")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default();
+                    type_language_value = format!("{}_{direction}", language.to_lowercase());
+                    containers.extend(["@language", "@language@set"]);
+                } else if let Some(language) = value.get("@language").and_then(Value::as_str)
+                    && !has("@index")
+                {
+                    type_language_value = language.to_lowercase();
+                    containers.extend(["@language", "@language@set"]);
+                } else if let Some(datatype) = value.get("@type").and_then(Value::as_str) {
+                    type_language_value = datatype.to_owned();
+                    type_language = "@type";
+                }
+            } else {
+                type_language = "@type";
+                type_language_value = "@id".to_owned();
+                containers.extend(["@id", "@id@set", "@type", "@set@type"]);
+            }
+            containers.push("@set");
+        }
+
+        containers.push("@none");
+        if !is_graph_object(value) {
+            containers.extend(["@index", "@index@set"]);
+        }
+        if value
+            .as_object()
+            .is_some_and(|map| map.len() == 1 && map.contains_key("@value"))
+        {
+            containers.extend(["@language", "@language@set"]);
+        }
+
+        let mut preferred = Vec::new();
+        if type_language_value == "@reverse" {
+            preferred.push("@reverse".to_owned());
+        }
+        if (type_language_value == "@id" || type_language_value == "@reverse")
+            && let Some(Value::String(id)) = value.get("@id")
+        {
+            let compacted = self.compact_iri(active, id, None, true, false)?;
+            let is_term = active
+                .terms
+                .get(&compacted)
+                .is_some_and(|d| d.iri.as_deref() == Some(id));
+            if is_term {
+                preferred.extend(["@vocab", "@id", "@none"].map(str::to_owned));
+            } else {
+                preferred.extend(["@id", "@vocab", "@none"].map(str::to_owned));
+            }
+        } else {
+            preferred.push(type_language_value.clone());
+            preferred.push("@none".to_owned());
+            if is_list_object(value) && as_slice(&value["@list"]).is_empty() {
+                type_language = "@any";
+            }
+        }
+        preferred.push("@any".to_owned());
+        if let Some(suffix) = preferred
+            .iter()
+            .find_map(|value| value.find('_').map(|index| value[index..].to_owned()))
+        {
+            preferred.push(suffix);
+        }
+
+        Ok((
+            containers.into_iter().map(str::to_owned).collect(),
+            type_language,
+            preferred,
+        ))
+    }
+}
+
+fn map_object<'m>(result: &'m mut Map<String, Value>, key: &str) -> &'m mut Map<String, Value> {
+    let entry = result
+        .entry(key)
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !entry.is_object() {
+        *entry = Value::Object(Map::new());
+    }
+    entry.as_object_mut().unwrap()
+}
+
+/// Removes the first string value of `key` from the object `compacted`.
+fn take_first(compacted: &mut Value, key: &str) -> Option<String> {
+    let map = compacted.as_object_mut()?;
+    let values = map.remove(key)?;
+    let mut values = as_slice(&values).to_vec();
+    let first = match values.first() {
+        Some(Value::String(first)) => Some(first.clone()),
+        _ => return None,
+    };
+    values.remove(0);
+    match values.len() {
+        0 => {}
+        1 => {
+            map.insert(key.to_owned(), values.pop().unwrap());
+        }
+        _ => {
+            map.insert(key.to_owned(), Value::Array(values));
+        }
+    }
+    first
+}
+
+/// Makes `iri` relative to `base` where that resolves back to it.
+fn relativize(base: &str, iri: &str) -> String {
+    let base = base.split('#').next().unwrap_or_default();
+    if iri == base {
+        return String::new();
+    }
+    if let Some(fragment) = iri.strip_prefix(base)
+        && fragment.starts_with('#')
+    {
+        return fragment.to_owned();
+    }
+
+    let base_path = base.split('?').next().unwrap_or_default();
+    let mut directory = &base_path[..base_path.rfind('/').map_or(0, |index| index + 1)];
+    let mut parents = String::new();
+    while directory.len() > 1 {
+        if let Some(rest) = iri.strip_prefix(directory)
+            && !rest.starts_with('/')
+        {
+            let candidate = match (parents.is_empty(), rest) {
+                (true, "") => "./".to_owned(),
+                (true, rest) if rest.split('/').next().unwrap_or_default().contains(':') => {
+                    format!("./{rest}")
+                }
+                (_, rest) => format!("{parents}{rest}"),
+            };
+            if resolve(base, &candidate) == iri {
+                return candidate;
+            }
+            break;
+        }
+        let trimmed = &directory[..directory.len() - 1];
+        match trimmed.rfind('/') {
+            // Stop at the authority.
+            Some(index) if !trimmed[..index].ends_with('/') && trimmed[..index].contains("//") => {
+                directory = &trimmed[..=index];
+                parents.push_str("../");
+            }
+            _ => break,
+        }
+    }
+    iri.to_owned()
+}
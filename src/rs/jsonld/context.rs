@@ -0,0 +1,1066 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::rs::iri::{has_scheme, resolve};
+use crate::rs::jsonld::compact::InverseContext;
+use crate::rs::jsonld::{DocumentLoader, JsonLdError, JsonLdErrorCode};
+
+/// Nesting limit for remote contexts, which also catches cycles.
+const MAX_REMOTE_CONTEXTS: usize = 32;
+
+const TERM_DEFINITION_KEYS: [&str; 11] = [
+    "@container",
+    "@context",
+    "@direction",
+    "@id",
+    "@index",
+    "@language",
+    "@nest",
+    "@prefix",
+    "@protected",
+    "@reverse",
+    "@type",
+];
+
+pub(crate) fn is_keyword(value: &str) -> bool {
+    matches!(
+        value,
+        "@base"
+            | "@container"
+            | "@context"
+            | "@direction"
+            | "@graph"
+            | "@id"
+            | "@import"
+            | "@included"
+            | "@index"
+            | "@json"
+            | "@language"
+            | "@list"
+            | "@nest"
+            | "@none"
+            | "@prefix"
+            | "@propagate"
+            | "@protected"
+            | "@reverse"
+            | "@set"
+            | "@type"
+            | "@value"
+            | "@version"
+            | "@vocab"
+    )
+}
+
+/// Whether `value` has the form of a keyword (`@` and letters), which
+/// processors ignore unless it is one.
+pub(crate) fn has_keyword_form(value: &str) -> bool {
+    value
+        .strip_prefix('@')
+        .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+pub(crate) fn error<T>(code: JsonLdErrorCode, message: &str) -> Result<T, JsonLdError> {
+    Err(JsonLdError::new(code, message))
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct TermDefinition {
+    /// `None` for terms explicitly mapped to `null`.
+    pub(crate) iri: Option<String>,
+    pub(crate) prefix: bool,
+    pub(crate) protected: bool,
+    pub(crate) reverse: bool,
+    pub(crate) type_mapping: Option<String>,
+    /// `Some(None)` when the term removes the default language.
+    pub(crate) language: Option<Option<String>>,
+    pub(crate) direction: Option<Option<String>>,
+    /// Sorted container keywords.
+    pub(crate) container: Vec<String>,
+    pub(crate) index: Option<String>,
+    pub(crate) nest: Option<String>,
+    pub(crate) context: Option<Value>,
+    pub(crate) base_url: Option<String>,
+}
+
+impl TermDefinition {
+    pub(crate) fn has_container(&self, container: &str) -> bool {
+        self.container.iter().any(|c| c == container)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Context {
+    pub(crate) base: Option<String>,
+    pub(crate) original_base: Option<String>,
+    pub(crate) vocab: Option<String>,
+    pub(crate) language: Option<String>,
+    pub(crate) direction: Option<String>,
+    pub(crate) terms: HashMap<String, TermDefinition>,
+    /// Context to revert to when leaving a node, set by non-propagated
+    /// (e.g. type-scoped) contexts.
+    pub(crate) previous: Option<Box<Context>>,
+    pub(crate) inverse: OnceCell<InverseContext>,
+}
+
+impl Context {
+    pub(crate) fn new(base: Option<&str>) -> Self {
+        Self {
+            base: base.map(str::to_owned),
+            original_base: base.map(str::to_owned),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn term(&self, term: Option<&str>) -> Option<&TermDefinition> {
+        self.terms.get(term?)
+    }
+
+    pub(crate) fn process(
+        &self,
+        loader: &dyn DocumentLoader,
+        local: &Value,
+        base_url: Option<&str>,
+    ) -> Result<Context, JsonLdError> {
+        ContextProcessor {
+            loader,
+            remote_contexts: Vec::new(),
+        }
+        .process(self, local, base_url, false, true)
+    }
+
+    /// Processes a property- or type-scoped context, which may override
+    /// protected terms.
+    pub(crate) fn process_scoped(
+        &self,
+        loader: &dyn DocumentLoader,
+        local: &Value,
+        base_url: Option<&str>,
+        propagate: bool,
+    ) -> Result<Context, JsonLdError> {
+        ContextProcessor {
+            loader,
+            remote_contexts: Vec::new(),
+        }
+        .process(self, local, base_url, true, propagate)
+    }
+
+    /// IRI expansion of `value`, or `None` if it maps to `null` or has the
+    /// form of an unknown keyword.
+    pub(crate) fn expand_iri(
+        &self,
+        value: &str,
+        document_relative: bool,
+        vocab: bool,
+    ) -> Option<String> {
+        if is_keyword(value) {
+            return Some(value.to_owned());
+        }
+        if has_keyword_form(value) {
+            return None;
+        }
+
+        if let Some(definition) = self.terms.get(value)
+            && (definition.iri.as_deref().is_some_and(is_keyword) || vocab)
+        {
+            return definition.iri.clone();
+        }
+
+        if let Some((prefix, suffix)) = split_compact_iri(value) {
+            if prefix == "_" || suffix.starts_with("//") {
+                return Some(value.to_owned());
+            }
+            if let Some(definition) = self.terms.get(prefix)
+                && let Some(iri) = &definition.iri
+                && definition.prefix
+            {
+                return Some(format!("{iri}{suffix}"));
+            }
+            if has_scheme(value) {
+                return Some(value.to_owned());
+            }
+        }
+
+        if vocab && let Some(mapping) = &self.vocab {
+            return Some(format!("{mapping}{value}"));
+        }
+        if document_relative && let Some(base) = &self.base {
+            return Some(resolve(base, value));
+        }
+        Some(value.to_owned())
+    }
+}
+
+/// Splits `value` at its first colon, unless that is its first character.
+pub(crate) fn split_compact_iri(value: &str) -> Option<(&str, &str)> {
+    let index = value.get(1..)?.find(':')? + 1;
+    Some((&value[..index], &value[index + 1..]))
+}
+
+struct ContextProcessor<'a> {
+    loader: &'a dyn DocumentLoader,
+    remote_contexts: Vec<String>,
+}
+
+impl ContextProcessor<'_> {
+    fn process(
+        &mut self,
+        active: &Context,
+        local: &Value,
+        base_url: Option<&str>,
+        override_protected: bool,
+        mut propagate: bool,
+    ) -> Result<Context, JsonLdError> {
+        let mut result = active.clone();
+        result.inverse = OnceCell::new();
+
+        if let Value::Object(map) = local
+            && let Some(value) = map.get("@propagate")
+        {
+            let Value::Bool(value) = value else {
+                return error(
+                    JsonLdErrorCode::InvalidPropagateValue,
+                    "@propagate must be a boolean",
+                );
+            };
+            propagate = *value;
+        }
+        if !propagate && result.previous.is_none() {
+            result.previous = Some(Box::new(active.clone()));
+        }
+
+        let items = match local {
+            Value::Array(items) => items.as_slice(),
+            local => std::slice::from_ref(local),
+        };
+        for item in items {
+            match item {
+                Value::Null => {
+                    if !override_protected && result.terms.values().any(|d| d.protected) {
+                        return error(
+                            JsonLdErrorCode::InvalidContextNullification,
+                            "cannot clear a context with protected terms",
+                        );
+                    }
+                    let previous = result.clone();
+                    result = Context::new(active.original_base.as_deref());
+                    if !propagate {
+                        result.previous = Some(Box::new(previous));
+                    }
+                }
+                Value::String(reference) => {
+                    let url = match base_url.or(active.base.as_deref()) {
+                        Some(base) => resolve(base, reference),
+                        None => reference.clone(),
+                    };
+                    if self.remote_contexts.len() >= MAX_REMOTE_CONTEXTS
+                        || self.remote_contexts.contains(&url)
+                    {
+                        return error(
+                            JsonLdErrorCode::ContextOverflow,
+                            &format!("too deeply nested or recursive context <{url}>"),
+                        );
+                    }
+
+                    let document = self.loader.load(&url).map_err(|e| {
+                        JsonLdError::new(JsonLdErrorCode::LoadingRemoteContextFailed, e.message())
+                    })?;
+                    let Some(context) = document.get("@context") else {
+                        return error(
+                            JsonLdErrorCode::InvalidRemoteContext,
+                            &format!("<{url}> has no @context entry"),
+                        );
+                    };
+
+                    self.remote_contexts.push(url.clone());
+                    result =
+                        self.process(&result, context, Some(&url), override_protected, true)?;
+                    self.remote_contexts.pop();
+                }
+                Value::Object(map) => {
+                    self.process_map(&mut result, map, base_url, override_protected)?;
+                }
+                _ => {
+                    return error(
+                        JsonLdErrorCode::InvalidLocalContext,
+                        "contexts must be objects, strings or null",
+                    );
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn process_map(
+        &mut self,
+        result: &mut Context,
+        map: &Map<String, Value>,
+        base_url: Option<&str>,
+        override_protected: bool,
+    ) -> Result<(), JsonLdError> {
+        if let Some(version) = map.get("@version")
+            && version.as_f64() != Some(1.1)
+        {
+            return error(JsonLdErrorCode::InvalidVersionValue, "@version must be 1.1");
+        }
+
+        let mut map = map.clone();
+        if let Some(import) = map.remove("@import") {
+            let Value::String(import) = import else {
+                return error(
+                    JsonLdErrorCode::InvalidImportValue,
+                    "@import must be a string",
+                );
+            };
+            let url = match base_url.or(result.base.as_deref()) {
+                Some(base) => resolve(base, &import),
+                None => import,
+            };
+            let document = self.loader.load(&url).map_err(|e| {
+                JsonLdError::new(JsonLdErrorCode::LoadingRemoteContextFailed, e.message())
+            })?;
+            let Some(Value::Object(imported)) = document.get("@context") else {
+                return error(
+                    JsonLdErrorCode::InvalidRemoteContext,
+                    &format!("<{url}> has no @context object"),
+                );
+            };
+            if imported.contains_key("@import") {
+                return error(
+                    JsonLdErrorCode::InvalidContextEntry,
+                    "imported contexts cannot import others",
+                );
+            }
+            for (key, value) in imported {
+                map.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+
+        if let Some(base) = map.get("@base")
+            && self.remote_contexts.is_empty()
+        {
+            result.base = match base {
+                Value::Null => None,
+                Value::String(base) if has_scheme(base) => Some(base.clone()),
+                Value::String(base) => match &result.base {
+                    Some(current) => Some(resolve(current, base)),
+                    None => {
+                        return error(
+                            JsonLdErrorCode::InvalidBaseIri,
+                            "relative @base without a base IRI",
+                        );
+                    }
+                },
+                _ => return error(JsonLdErrorCode::InvalidBaseIri, "@base must be a string"),
+            };
+        }
+
+        if let Some(vocab) = map.get("@vocab") {
+            result.vocab = match vocab {
+                Value::Null => None,
+                Value::String(vocab) => match result.expand_iri(vocab, true, true) {
+                    Some(iri) if iri.contains(':') || vocab.is_empty() => Some(iri),
+                    _ => {
+                        return error(
+                            JsonLdErrorCode::InvalidVocabMapping,
+                            &format!("invalid @vocab {vocab:?}"),
+                        );
+                    }
+                },
+                _ => {
+                    return error(
+                        JsonLdErrorCode::InvalidVocabMapping,
+                        "@vocab must be a string",
+                    );
+                }
+            };
+        }
+
+        if let Some(language) = map.get("@language") {
+            result.language = match language {
+                Value::Null => None,
+                Value::String(language) => Some(language.clone()),
+                _ => {
+                    return error(
+                        JsonLdErrorCode::InvalidDefaultLanguage,
+                        "@language must be a string",
+                    );
+                }
+            };
+        }
+
+        if let Some(direction) = map.get("@direction") {
+            result.direction = direction_value(direction)?;
+        }
+
+        let protected = match map.get("@protected") {
+            None => false,
+            Some(Value::Bool(protected)) => *protected,
+            Some(_) => {
+                return error(
+                    JsonLdErrorCode::InvalidProtectedValue,
+                    "@protected must be a boolean",
+                );
+            }
+        };
+
+        let mut definer = TermDefiner {
+            local: &map,
+            defined: HashMap::new(),
+            base_url,
+            protected,
+            override_protected,
+        };
+        for term in map.keys() {
+            if !matches!(
+                term.as_str(),
+                "@base"
+                    | "@direction"
+                    | "@language"
+                    | "@propagate"
+                    | "@protected"
+                    | "@version"
+                    | "@vocab"
+            ) {
+                definer.define(result, term)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn direction_value(value: &Value) -> Result<Option<String>, JsonLdError> {
+    match value {
+        Value::Null => Ok(None),
+        Value::String(direction) if direction == "ltr" || direction == "rtl" => {
+            Ok(Some(direction.clone()))
+        }
+        _ => error(
+            JsonLdErrorCode::InvalidBaseDirection,
+            "@direction must be \"ltr\", \"rtl\" or null",
+        ),
+    }
+}
+
+/// Creates the term definitions of one local context, defining the terms
+/// others depend on first.
+struct TermDefiner<'a> {
+    local: &'a Map<String, Value>,
+    defined: HashMap<String, bool>,
+    base_url: Option<&'a str>,
+    protected: bool,
+    override_protected: bool,
+}
+
+impl TermDefiner<'_> {
+    fn define(&mut self, active: &mut Context, term: &str) -> Result<(), JsonLdError> {
+        match self.defined.get(term) {
+            Some(true) => return Ok(()),
+            Some(false) => {
+                return error(
+                    JsonLdErrorCode::CyclicIriMapping,
+                    &format!("{term:?} depends on itself"),
+                );
+            }
+            None => {}
+        }
+        if term.is_empty() {
+            return error(JsonLdErrorCode::InvalidTermDefinition, "empty term");
+        }
+        self.defined.insert(term.to_owned(), false);
+
+        let value = &self.local[term];
+        if term == "@type" {
+            let valid = match value {
+                Value::Object(map) => {
+                    !map.is_empty()
+                        && map.iter().all(|(key, value)| match key.as_str() {
+                            "@container" => value == "@set",
+                            "@protected" => value.is_boolean(),
+                            _ => false,
+                        })
+                }
+                _ => false,
+            };
+            if !valid {
+                return error(
+                    JsonLdErrorCode::KeywordRedefinition,
+                    "@type can only be given a @set container",
+                );
+            }
+        } else if is_keyword(term) {
+            return error(
+                JsonLdErrorCode::KeywordRedefinition,
+                &format!("cannot redefine {term}"),
+            );
+        } else if has_keyword_form(term) {
+            self.defined.insert(term.to_owned(), true);
+            return Ok(());
+        }
+
+        let previous = active.terms.remove(term);
+        let (simple, map) = match value {
+            Value::Null => (false, Map::from_iter([("@id".to_owned(), Value::Null)])),
+            Value::String(id) => (
+                true,
+                Map::from_iter([("@id".to_owned(), Value::String(id.clone()))]),
+            ),
+            Value::Object(map) => (false, map.clone()),
+            _ => {
+                return error(
+                    JsonLdErrorCode::InvalidTermDefinition,
+                    &format!("invalid definition of {term:?}"),
+                );
+            }
+        };
+        if let Some(key) = map
+            .keys()
+            .find(|key| !TERM_DEFINITION_KEYS.contains(&key.as_str()))
+        {
+            return error(
+                JsonLdErrorCode::InvalidTermDefinition,
+                &format!("unknown entry {key:?} in the definition of {term:?}"),
+            );
+        }
+
+        let mut definition = TermDefinition {
+            protected: match map.get("@protected") {
+                None => self.protected,
+                Some(Value::Bool(protected)) => *protected,
+                Some(_) => {
+                    return error(
+                        JsonLdErrorCode::InvalidProtectedValue,
+                        "@protected must be a boolean",
+                    );
+                }
+            },
+            ..TermDefinition::default()
+        };
+
+        if let Some(type_mapping) = map.get("@type") {
+            let Value::String(type_mapping) = type_mapping else {
+                return error(
+                    JsonLdErrorCode::InvalidTypeMapping,
+                    "@type must be a string",
+                );
+            };
+            let expanded = self.expand_iri(active, type_mapping, false, true)?;
+            match expanded {
+                Some(iri)
+                    if matches!(iri.as_str(), "@id" | "@json" | "@none" | "@vocab")
+                        || has_scheme(&iri) =>
+                {
+                    definition.type_mapping = Some(iri);
+                }
+                _ => {
+                    return error(
+                        JsonLdErrorCode::InvalidTypeMapping,
+                        &format!("invalid type mapping {type_mapping:?}"),
+                    );
+                }
+            }
+        }
+
+        if let Some(reverse) = map.get("@reverse") {
+            if map.contains_key("@id") || map.contains_key("@nest") {
+                return error(
+                    JsonLdErrorCode::InvalidReverseProperty,
+                    "reverse properties cannot have @id or @nest",
+                );
+            }
+            let Value::String(reverse) = reverse else {
+                return error(
+                    JsonLdErrorCode::InvalidIriMapping,
+                    "@reverse must be a string",
+                );
+            };
+            if has_keyword_form(reverse) {
+                self.defined.insert(term.to_owned(), true);
+                return Ok(());
+            }
+            match self.expand_iri(active, reverse, false, true)? {
+                Some(iri) if iri.contains(':') => definition.iri = Some(iri),
+                _ => {
+                    return error(
+                        JsonLdErrorCode::InvalidIriMapping,
+                        &format!("invalid reverse property {reverse:?}"),
+                    );
+                }
+            }
+            definition.container = container(map.get("@container"))?;
+            if !definition
+                .container
+                .iter()
+                .all(|c| c == "@set" || c == "@index")
+                || definition.container.len() > 1
+            {
+                return error(
+                    JsonLdErrorCode::InvalidReverseProperty,
+                    "reverse properties only support @set or @index containers",
+                );
+            }
+            definition.reverse = true;
+            return self.finish(active, term, definition, previous);
+        }
+
+        match map.get("@id") {
+            Some(Value::Null) => {}
+            Some(Value::String(id)) if id != term => {
+                if !is_keyword(id) && has_keyword_form(id) {
+                    self.defined.insert(term.to_owned(), true);
+                    return Ok(());
+                }
+                let iri = match self.expand_iri(active, id, false, true)? {
+                    Some(iri) if is_keyword(&iri) || iri.contains(':') => iri,
+                    _ => {
+                        return error(
+                            JsonLdErrorCode::InvalidIriMapping,
+                            &format!("invalid IRI mapping {id:?} for {term:?}"),
+                        );
+                    }
+                };
+                if iri == "@context" {
+                    return error(
+                        JsonLdErrorCode::InvalidKeywordAlias,
+                        "@context cannot be aliased",
+                    );
+                }
+
+                let looks_like_iri = split_compact_iri(term)
+                    .is_some_and(|(_, suffix)| !suffix.is_empty())
+                    || term.contains('/');
+                if looks_like_iri {
+                    self.defined.insert(term.to_owned(), true);
+                    if self.expand_iri(active, term, false, true)?.as_deref() != Some(&iri) {
+                        return error(
+                            JsonLdErrorCode::InvalidIriMapping,
+                            &format!("{term:?} would expand to another IRI than {iri:?}"),
+                        );
+                    }
+                }
+                definition.prefix = !term.contains(':')
+                    && !term.contains('/')
+                    && simple
+                    && (iri.ends_with([':', '/', '?', '#', '[', ']', '@'])
+                        || iri.starts_with("_:"));
+                definition.iri = Some(iri);
+            }
+            Some(Value::String(_)) | None => {
+                definition.iri = Some(if let Some((prefix, suffix)) = split_compact_iri(term) {
+                    if self.local.contains_key(prefix) {
+                        self.define(active, prefix)?;
+                    }
+                    match active.terms.get(prefix).and_then(|d| d.iri.as_ref()) {
+                        Some(iri) => format!("{iri}{suffix}"),
+                        None => term.to_owned(),
+                    }
+                } else if term.contains('/') {
+                    match active.expand_iri(term, false, true) {
+                        Some(iri) if has_scheme(&iri) => iri,
+                        _ => {
+                            return error(
+                                JsonLdErrorCode::InvalidIriMapping,
+                                &format!("{term:?} is not an IRI"),
+                            );
+                        }
+                    }
+                } else if term == "@type" {
+                    "@type".to_owned()
+                } else if let Some(vocab) = &active.vocab {
+                    format!("{vocab}{term}")
+                } else {
+                    return error(
+                        JsonLdErrorCode::InvalidIriMapping,
+                        &format!("{term:?} has no IRI mapping and there is no @vocab"),
+                    );
+                });
+            }
+            Some(_) => {
+                return error(JsonLdErrorCode::InvalidIriMapping, "@id must be a string");
+            }
+        }
+
+        if map.contains_key("@container") {
+            definition.container = container(map.get("@container"))?;
+            if definition.has_container("@type") {
+                match definition.type_mapping.as_deref() {
+                    None => definition.type_mapping = Some("@id".to_owned()),
+                    Some("@id" | "@vocab") => {}
+                    Some(_) => {
+                        return error(
+                            JsonLdErrorCode::InvalidTypeMapping,
+                            "@type containers need @id or @vocab values",
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(index) = map.get("@index") {
+            match index {
+                Value::String(index)
+                    if definition.has_container("@index") && !is_keyword(index) =>
+                {
+                    definition.index = Some(index.clone());
+                }
+                _ => {
+                    return error(
+                        JsonLdErrorCode::InvalidTermDefinition,
+                        "@index needs an @index container and a property",
+                    );
+                }
+            }
+        }
+
+        if let Some(context) = map.get("@context") {
+            definition.context = Some(context.clone());
+            definition.base_url = self.base_url.map(str::to_owned);
+        }
+
+        if !map.contains_key("@type") {
+            if let Some(language) = map.get("@language") {
+                definition.language = Some(match language {
+                    Value::Null => None,
+                    Value::String(language) => Some(language.clone()),
+                    _ => {
+                        return error(
+                            JsonLdErrorCode::InvalidLanguageMapping,
+                            "@language must be a string or null",
+                        );
+                    }
+                });
+            }
+            if let Some(direction) = map.get("@direction") {
+                definition.direction = Some(direction_value(direction)?);
+            }
+        }
+
+        if let Some(nest) = map.get("@nest") {
+            match nest {
+                Value::String(nest) if !is_keyword(nest) || nest == "@nest" => {
+                    definition.nest = Some(nest.clone());
+                }
+                _ => {
+                    return error(
+                        JsonLdErrorCode::InvalidNestValue,
+                        "@nest must be a term or @nest",
+                    );
+                }
+            }
+        }
+
+        if let Some(prefix) = map.get("@prefix") {
+            if term.contains(':') || term.contains('/') {
+                return error(
+                    JsonLdErrorCode::InvalidTermDefinition,
+                    "only simple terms can be prefixes",
+                );
+            }
+            let Value::Bool(prefix) = prefix else {
+                return error(
+                    JsonLdErrorCode::InvalidPrefixValue,
+                    "@prefix must be a boolean",
+                );
+            };
+            if *prefix && definition.iri.as_deref().is_some_and(is_keyword) {
+                return error(
+                    JsonLdErrorCode::InvalidTermDefinition,
+                    "keyword aliases cannot be prefixes",
+                );
+            }
+            definition.prefix = *prefix;
+        }
+
+        self.finish(active, term, definition, previous)
+    }
+
+    fn finish(
+        &mut self,
+        active: &mut Context,
+        term: &str,
+        definition: TermDefinition,
+        previous: Option<TermDefinition>,
+    ) -> Result<(), JsonLdError> {
+        let definition = match previous {
+            Some(previous) if previous.protected && !self.override_protected => {
+                let unchanged = TermDefinition {
+                    protected: true,
+                    ..definition
+                } == previous;
+                if !unchanged {
+                    return error(
+                        JsonLdErrorCode::ProtectedTermRedefinition,
+                        &format!("{term:?} is protected"),
+                    );
+                }
+                previous
+            }
+            _ => definition,
+        };
+        active.terms.insert(term.to_owned(), definition);
+        self.defined.insert(term.to_owned(), true);
+        Ok(())
+    }
+
+    /// [`Context::expand_iri`] that first defines the terms of the local
+    /// context it relies on.
+    fn expand_iri(
+        &mut self,
+        active: &mut Context,
+        value: &str,
+        document_relative: bool,
+        vocab: bool,
+    ) -> Result<Option<String>, JsonLdError> {
+        if !has_keyword_form(value) {
+            if self.local.contains_key(value) && self.defined.get(value) != Some(&true) {
+                self.define(active, value)?;
+            }
+            if let Some((prefix, _)) = split_compact_iri(value)
+                && self.local.contains_key(prefix)
+                && self.defined.get(prefix) != Some(&true)
+            {
+                self.define(active, prefix)?;
+            }
+        }
+        Ok(active.expand_iri(value, document_relative, vocab))
+    }
+}
+
+/// Sorted container mapping, checking that its keywords can be combined.
+fn container(value: Option<&Value>) -> Result<Vec<String>, JsonLdError> {
+    let invalid = || {
+        error(
+            JsonLdErrorCode::InvalidContainerMapping,
+            "invalid @container",
+        )
+    };
+
+    let mut containers = match value {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::String(container)) => vec![container.clone()],
+        Some(Value::Array(items)) => {
+            let mut containers = Vec::new();
+            for item in items {
+                let Value::String(item) = item else {
+                    return invalid();
+                };
+                containers.push(item.clone());
+            }
+            containers
+        }
+        Some(_) => return invalid(),
+    };
+    containers.sort();
+    containers.dedup();
+
+    let known = containers.iter().all(|c| {
+        matches!(
+            c.as_str(),
+            "@graph" | "@id" | "@index" | "@language" | "@list" | "@set" | "@type"
+        )
+    });
+    let has = |c: &str| containers.iter().any(|container| container == c);
+    let others = |allowed: &[&str]| containers.iter().all(|c| allowed.contains(&c.as_str()));
+    let valid = known
+        && match containers.len() {
+            0 | 1 => true,
+            _ if has("@list") => false,
+            _ if has("@graph") => {
+                others(&["@graph", "@id", "@index", "@set"]) && !(has("@id") && has("@index"))
+            }
+            2 => has("@set"),
+            _ => false,
+        };
+    if valid { Ok(containers) } else { invalid() }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::rs::jsonld::NoDocumentLoader;
+
+    fn process(local: Value) -> Result<Context, JsonLdError> {
+        Context::new(Some("http://example.org/base/")).process(&NoDocumentLoader, &local, None)
+    }
+
+    #[test]
+    fn terms_are_defined_in_dependency_order() {
+        let context = process(json!({
+            "@vocab": "http://schema.org/",
+            "ex:name": {"@type": "id"},
+            "id": "@id",
+            "ex": "http://example.org/",
+            "data": {"@id": "ex:data", "@container": ["@set", "@index"]},
+            "local": "relative/path"
+        }))
+        .unwrap();
+
+        assert_eq!(
+            context.terms["ex:name"].iri.as_deref(),
+            Some("http://example.org/name")
+        );
+        assert_eq!(
+            context.terms["ex:name"].type_mapping.as_deref(),
+            Some("@id")
+        );
+        assert!(context.terms["ex"].prefix);
+        assert_eq!(context.terms["data"].container, ["@index", "@set"]);
+        assert_eq!(
+            context.expand_iri("ex:thing", false, false).as_deref(),
+            Some("http://example.org/thing")
+        );
+        assert_eq!(
+            context.expand_iri("other", true, false).as_deref(),
+            Some("http://example.org/base/other")
+        );
+        assert_eq!(
+            context.expand_iri("other", true, true).as_deref(),
+            Some("http://schema.org/other")
+        );
+        assert_eq!(context.expand_iri("@unknown", false, true), None);
+    }
+
+    #[test]
+    fn invalid_contexts_are_rejected() {
+        let code = |local: Value| process(local).unwrap_err().code();
+
+        assert_eq!(
+            code(json!({"a": {"@id": "b"}, "b": {"@id": "a"}})),
+            JsonLdErrorCode::CyclicIriMapping
+        );
+        assert_eq!(
+            code(json!({"@id": "http://example.org/"})),
+            JsonLdErrorCode::KeywordRedefinition
+        );
+        assert_eq!(
+            code(json!({"a": {"@id": "http://example.org/a", "@container": ["@list", "@set"]}})),
+            JsonLdErrorCode::InvalidContainerMapping
+        );
+        assert_eq!(
+            code(json!({"term": {}})),
+            JsonLdErrorCode::InvalidIriMapping
+        );
+        assert_eq!(
+            code(json!([{"@protected": true, "a": "http://example.org/a"}, null])),
+            JsonLdErrorCode::InvalidContextNullification
+        );
+    }
+
+    #[test]
+    fn protected_terms_resist_redefinition() {
+        let context = process(json!({
+            "@protected": true,
+            "a": "http://example.org/a",
+            "b": {"@id": "http://example.org/b", "@protected": false}
+        }))
+        .unwrap();
+        assert!(context.terms["a"].protected);
+        assert!(!context.terms["b"].protected);
+
+        let redefine = |local: Value| context.process(&NoDocumentLoader, &local, None);
+        assert_eq!(
+            redefine(json!({"a": "http://example.org/other"}))
+                .unwrap_err()
+                .code(),
+            JsonLdErrorCode::ProtectedTermRedefinition
+        );
+        assert_eq!(
+            redefine(json!({"a": {"@id": "http://example.org/a", "@type": "@id"}}))
+                .unwrap_err()
+                .code(),
+            JsonLdErrorCode::ProtectedTermRedefinition
+        );
+        assert_eq!(
+            redefine(json!(null)).unwrap_err().code(),
+            JsonLdErrorCode::InvalidContextNullification
+        );
+
+        // The same definition is allowed, and stays protected.
+        let same = redefine(json!({"a": "http://example.org/a"})).unwrap();
+        assert!(same.terms["a"].protected);
+        let other = redefine(json!({"b": "http://example.org/other"})).unwrap();
+        assert_eq!(
+            other.terms["b"].iri.as_deref(),
+            Some("http://example.org/other")
+        );
+
+        // Property-scoped contexts may override protected terms.
+        let scoped = context
+            .process_scoped(
+                &NoDocumentLoader,
+                &json!({"a": "http://example.org/other"}),
+                None,
+                true,
+            )
+            .unwrap();
+        assert_eq!(
+            scoped.terms["a"].iri.as_deref(),
+            Some("http://example.org/other")
+        );
+        assert!(
+            context
+                .process_scoped(&NoDocumentLoader, &json!(null), None, true)
+                .unwrap()
+                .terms
+                .is_empty()
+        );
+
+        assert_eq!(
+            process(json!({"@protected": "yes"})).unwrap_err().code(),
+            JsonLdErrorCode::InvalidProtectedValue
+        );
+        assert_eq!(
+            process(json!({"a": {"@id": "http://example.org/a", "@protected": 1}}))
+                .unwrap_err()
+                .code(),
+            JsonLdErrorCode::InvalidProtectedValue
+        );
+    }
+
+    #[test]
+    fn scoped_contexts_are_kept_and_reverted() {
+        let context = process(json!({
+            "knows": {"@id": "http://example.org/knows", "@context": {"name": "http://example.org/nick"}},
+            "name": "http://example.org/name"
+        }))
+        .unwrap();
+        assert_eq!(
+            context.terms["knows"].context,
+            Some(json!({"name": "http://example.org/nick"}))
+        );
+
+        // Type-scoped contexts do not propagate: the context before them is
+        // kept to revert to.
+        let local = json!({"name": "http://example.org/label"});
+        let typed = context
+            .process_scoped(&NoDocumentLoader, &local, None, false)
+            .unwrap();
+        assert_eq!(
+            typed.terms["name"].iri.as_deref(),
+            Some("http://example.org/label")
+        );
+        let previous = typed.previous.as_deref().unwrap();
+        assert_eq!(
+            previous.terms["name"].iri.as_deref(),
+            Some("http://example.org/name")
+        );
+
+        let propagated = context
+            .process_scoped(
+                &NoDocumentLoader,
+                &json!({"@propagate": true, "name": "http://example.org/label"}),
+                None,
+                false,
+            )
+            .unwrap();
+        assert!(propagated.previous.is_none());
+        assert_eq!(
+            process(json!({"@propagate": "no"})).unwrap_err().code(),
+            JsonLdErrorCode::InvalidPropagateValue
+        );
+    }
+}
@@ -0,0 +1,786 @@
+use serde_json::{Map, Value};
+
+use crate::rs::jsonld::context::{Context, error, is_keyword};
+use crate::rs::jsonld::{JsonLdError, JsonLdErrorCode, JsonLdProcessor};
+
+/// Keywords only found in frames.
+const FRAMING_KEYWORDS: [&str; 5] = [
+    "@default",
+    "@embed",
+    "@explicit",
+    "@omitDefault",
+    "@requireAll",
+];
+
+pub(crate) struct Expander<'a> {
+    processor: &'a JsonLdProcessor,
+    /// Frame expansion keeps framing keywords, wildcards (`{}`) and
+    /// match-none patterns (`[]`), and empty objects.
+    frame: bool,
+}
+
+impl<'a> Expander<'a> {
+    pub(crate) fn new(processor: &'a JsonLdProcessor) -> Self {
+        Self {
+            processor,
+            frame: false,
+        }
+    }
+
+    pub(crate) fn for_frame(processor: &'a JsonLdProcessor) -> Self {
+        Self {
+            processor,
+            frame: true,
+        }
+    }
+
+    /// Expands a whole document, always returning an array of nodes.
+    pub(crate) fn expand_document(&self, input: &Value) -> Result<Value, JsonLdError> {
+        let active = self.processor.initial_context();
+        let expanded = self.expand(
+            &active,
+            None,
+            input,
+            self.processor.base_iri.as_deref(),
+            false,
+        )?;
+
+        Ok(match expanded {
+            Value::Null => Value::Array(Vec::new()),
+            Value::Object(mut map) if map.len() == 1 && map.contains_key("@graph") => {
+                as_array(map.remove("@graph").unwrap())
+            }
+            expanded => as_array(expanded),
+        })
+    }
+
+    fn expand(
+        &self,
+        active: &Context,
+        active_property: Option<&str>,
+        element: &Value,
+        base_url: Option<&str>,
+        from_map: bool,
+    ) -> Result<Value, JsonLdError> {
+        let property_definition = active.term(active_property);
+        let property_context = property_definition.and_then(|d| d.context.as_ref());
+
+        match element {
+            Value::Null => Ok(Value::Null),
+            Value::Array(items) => {
+                let is_list = property_definition.is_some_and(|d| d.has_container("@list"));
+                let mut result = Vec::new();
+                for item in items {
+                    let expanded =
+                        self.expand(active, active_property, item, base_url, from_map)?;
+                    match expanded {
+                        Value::Array(items) if is_list => result.push(list_object(items)),
+                        Value::Array(items) => result.extend(items),
+                        Value::Null => {}
+                        expanded => result.push(expanded),
+                    }
+                }
+                Ok(Value::Array(result))
+            }
+            Value::Object(map) => {
+                let mut active = active.clone();
+
+                if let Some(previous) = &active.previous
+                    && !from_map
+                    && !self.keeps_scoped_context(&active, map)
+                {
+                    active = (**previous).clone();
+                }
+                if let Some(context) = property_context {
+                    let base = property_definition.and_then(|d| d.base_url.as_deref());
+                    active = active.process_scoped(self.loader(), context, base, true)?;
+                }
+                if let Some(context) = map.get("@context") {
+                    active = active.process(self.loader(), context, base_url)?;
+                }
+
+                let type_scoped = active.clone();
+                let mut input_type = None;
+                for (key, value) in map {
+                    if active.expand_iri(key, false, true).as_deref() != Some("@type") {
+                        continue;
+                    }
+                    let mut types: Vec<&str> =
+                        as_slice(value).iter().filter_map(Value::as_str).collect();
+                    types.sort();
+                    for term in &types {
+                        if let Some(definition) = type_scoped.terms.get(*term)
+                            && let Some(context) = &definition.context
+                        {
+                            active = active.process_scoped(
+                                self.loader(),
+                                context,
+                                definition.base_url.as_deref(),
+                                false,
+                            )?;
+                        }
+                    }
+                    if input_type.is_none() {
+                        input_type = as_slice(value)
+                            .last()
+                            .and_then(Value::as_str)
+                            .and_then(|last| type_scoped.expand_iri(last, false, true));
+                    }
+                }
+
+                let mut result = Map::new();
+                self.expand_entries(
+                    &active,
+                    &type_scoped,
+                    active_property,
+                    map,
+                    &mut result,
+                    input_type.as_deref(),
+                    base_url,
+                )?;
+                self.finish_object(result, active_property)
+            }
+            scalar => {
+                if active_property.is_none() || active_property == Some("@graph") {
+                    return Ok(Value::Null);
+                }
+                match property_context {
+                    Some(context) => {
+                        let base = property_definition.and_then(|d| d.base_url.as_deref());
+                        let scoped = active.process_scoped(self.loader(), context, base, true)?;
+                        Ok(expand_value(&scoped, active_property, scalar))
+                    }
+                    None => Ok(expand_value(active, active_property, scalar)),
+                }
+            }
+        }
+    }
+
+    fn loader(&self) -> &dyn crate::rs::jsonld::DocumentLoader {
+        self.processor.loader.as_ref()
+    }
+
+    /// Whether a node keeps a non-propagated context: value objects and
+    /// node references do.
+    fn keeps_scoped_context(&self, active: &Context, map: &Map<String, Value>) -> bool {
+        let expanded: Vec<Option<String>> = map
+            .keys()
+            .map(|key| active.expand_iri(key, false, true))
+            .collect();
+        expanded.iter().any(|key| key.as_deref() == Some("@value"))
+            || (expanded.len() == 1 && expanded[0].as_deref() == Some("@id"))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn expand_entries(
+        &self,
+        active: &Context,
+        type_scoped: &Context,
+        active_property: Option<&str>,
+        element: &Map<String, Value>,
+        result: &mut Map<String, Value>,
+        input_type: Option<&str>,
+        base_url: Option<&str>,
+    ) -> Result<(), JsonLdError> {
+        let mut nests = Vec::new();
+
+        for (key, value) in element {
+            if key == "@context" {
+                continue;
+            }
+            if self.frame && FRAMING_KEYWORDS.contains(&key.as_str()) {
+                let expanded = if key == "@default" && value != "@null" {
+                    as_array(self.expand(active, active_property, value, base_url, false)?)
+                } else {
+                    value.clone()
+                };
+                result.insert(key.clone(), expanded);
+                continue;
+            }
+            let Some(property) = active.expand_iri(key, false, true) else {
+                continue;
+            };
+            if !property.contains(':') && !is_keyword(&property) {
+                continue;
+            }
+
+            if is_keyword(&property) {
+                if active_property == Some("@reverse") {
+                    return error(
+                        JsonLdErrorCode::InvalidReversePropertyMap,
+                        "reverse property maps cannot contain keywords",
+                    );
+                }
+                if result.contains_key(&property) && property != "@included" && property != "@type"
+                {
+                    return error(
+                        JsonLdErrorCode::CollidingKeywords,
+                        &format!("{property} appears more than once"),
+                    );
+                }
+
+                let expanded = match property.as_str() {
+                    "@id" => match value {
+                        Value::String(id) => active
+                            .expand_iri(id, true, false)
+                            .map_or(Value::Null, Value::String),
+                        _ if self.frame => {
+                            let mut expanded = Vec::new();
+                            for item in as_slice(value) {
+                                match item {
+                                    Value::String(id) => {
+                                        if let Some(id) = active.expand_iri(id, true, false) {
+                                            expanded.push(Value::String(id));
+                                        }
+                                    }
+                                    Value::Object(map) if map.is_empty() => {
+                                        expanded.push(item.clone())
+                                    }
+                                    _ => {
+                                        return error(
+                                            JsonLdErrorCode::InvalidIdValue,
+                                            "frame @id must be IRIs or {}",
+                                        );
+                                    }
+                                }
+                            }
+                            Value::Array(expanded)
+                        }
+                        _ => return error(JsonLdErrorCode::InvalidIdValue, "@id must be a string"),
+                    },
+                    "@type" => {
+                        let types = as_slice(value);
+                        let mut expanded = Vec::new();
+                        for item in types {
+                            if self.frame && item.is_object() {
+                                expanded.push(item.clone());
+                                continue;
+                            }
+                            let Value::String(item) = item else {
+                                return error(
+                                    JsonLdErrorCode::InvalidTypeValue,
+                                    "@type must be a string or an array of strings",
+                                );
+                            };
+                            if let Some(iri) = type_scoped.expand_iri(item, true, true) {
+                                expanded.push(Value::String(iri));
+                            }
+                        }
+                        if let Some(existing) = result.remove("@type") {
+                            let mut merged =
+                                as_array(existing).as_array().cloned().unwrap_or_default();
+                            merged.extend(expanded);
+                            Value::Array(merged)
+                        } else if value.is_array() {
+                            Value::Array(expanded)
+                        } else {
+                            expanded.pop().unwrap_or(Value::Null)
+                        }
+                    }
+                    "@graph" => {
+                        as_array(self.expand(active, Some("@graph"), value, base_url, false)?)
+                    }
+                    "@included" => {
+                        let expanded = as_array(self.expand(active, None, value, base_url, false)?);
+                        if !as_slice(&expanded).iter().all(is_node_object) {
+                            return error(
+                                JsonLdErrorCode::InvalidIncludedValue,
+                                "@included must contain node objects",
+                            );
+                        }
+                        match result.remove("@included") {
+                            Some(Value::Array(mut existing)) => {
+                                existing.extend(as_slice(&expanded).iter().cloned());
+                                Value::Array(existing)
+                            }
+                            _ => expanded,
+                        }
+                    }
+                    "@value" => {
+                        if input_type == Some("@json") {
+                            result.insert("@value".to_owned(), value.clone());
+                            continue;
+                        }
+                        if !self.frame && (value.is_object() || value.is_array()) {
+                            return error(
+                                JsonLdErrorCode::InvalidValueObjectValue,
+                                "@value must be a scalar or null",
+                            );
+                        }
+                        result.insert("@value".to_owned(), value.clone());
+                        continue;
+                    }
+                    "@language" if self.frame => value.clone(),
+                    "@language" => {
+                        let Value::String(language) = value else {
+                            return error(
+                                JsonLdErrorCode::InvalidLanguageTaggedString,
+                                "@language must be a string",
+                            );
+                        };
+                        Value::String(language.clone())
+                    }
+                    "@direction" => match value.as_str() {
+                        Some(direction @ ("ltr" | "rtl")) => Value::String(direction.to_owned()),
+                        _ => {
+                            return error(
+                                JsonLdErrorCode::InvalidBaseDirection,
+                                "@direction must be \"ltr\" or \"rtl\"",
+                            );
+                        }
+                    },
+                    "@index" => {
+                        let Value::String(index) = value else {
+                            return error(
+                                JsonLdErrorCode::InvalidIndexValue,
+                                "@index must be a string",
+                            );
+                        };
+                        Value::String(index.clone())
+                    }
+                    "@list" => {
+                        if active_property.is_none() || active_property == Some("@graph") {
+                            continue;
+                        }
+                        as_array(self.expand(active, active_property, value, base_url, false)?)
+                    }
+                    "@set" => self.expand(active, active_property, value, base_url, false)?,
+                    "@reverse" => {
+                        if !value.is_object() {
+                            return error(
+                                JsonLdErrorCode::InvalidReverseValue,
+                                "@reverse must be an object",
+                            );
+                        }
+                        let Value::Object(mut expanded) =
+                            self.expand(active, Some("@reverse"), value, base_url, false)?
+                        else {
+                            continue;
+                        };
+                        if let Some(Value::Object(reversed)) = expanded.remove("@reverse") {
+                            for (property, items) in reversed {
+                                add_value(result, &property, items, true);
+                            }
+                        }
+                        if !expanded.is_empty() {
+                            let reverse_map = reverse_map(result);
+                            for (property, items) in expanded {
+                                for item in as_slice(&items) {
+                                    if is_value_object(item) || is_list_object(item) {
+                                        return error(
+                                            JsonLdErrorCode::InvalidReversePropertyValue,
+                                            "reverse properties cannot have values or lists",
+                                        );
+                                    }
+                                }
+                                add_value(reverse_map, &property, items, true);
+                            }
+                        }
+                        continue;
+                    }
+                    "@nest" => {
+                        nests.push(key);
+                        continue;
+                    }
+                    _ => continue,
+                };
+
+                if !expanded.is_null() {
+                    result.insert(property, expanded);
+                }
+                continue;
+            }
+
+            let definition = active.terms.get(key);
+            let has = |container: &str| definition.is_some_and(|d| d.has_container(container));
+
+            let mut expanded = if definition.and_then(|d| d.type_mapping.as_deref())
+                == Some("@json")
+            {
+                Value::Object(Map::from_iter([
+                    ("@value".to_owned(), value.clone()),
+                    ("@type".to_owned(), Value::String("@json".to_owned())),
+                ]))
+            } else if let (true, Value::Object(languages)) = (has("@language"), value) {
+                let direction = definition
+                    .and_then(|d| d.direction.clone())
+                    .unwrap_or_else(|| active.direction.clone());
+                let mut expanded = Vec::new();
+                for (language, items) in languages {
+                    for item in as_slice(items) {
+                        let item = match item {
+                            Value::Null => continue,
+                            Value::String(item) => item,
+                            _ => {
+                                return error(
+                                    JsonLdErrorCode::InvalidLanguageMapValue,
+                                    "language maps must contain strings",
+                                );
+                            }
+                        };
+                        let mut object = Map::new();
+                        object.insert("@value".to_owned(), Value::String(item.clone()));
+                        if active.expand_iri(language, false, true).as_deref() != Some("@none") {
+                            object.insert("@language".to_owned(), Value::String(language.clone()));
+                        }
+                        if let Some(direction) = &direction {
+                            object
+                                .insert("@direction".to_owned(), Value::String(direction.clone()));
+                        }
+                        expanded.push(Value::Object(object));
+                    }
+                }
+                Value::Array(expanded)
+            } else if let (true, Value::Object(indexes)) =
+                (has("@index") || has("@type") || has("@id"), value)
+            {
+                let index_key = definition
+                    .and_then(|d| d.index.as_deref())
+                    .unwrap_or("@index");
+                let mut expanded = Vec::new();
+                for (index, items) in indexes {
+                    let mut map_context = if has("@id") || has("@index") {
+                        active.previous.as_deref().unwrap_or(active).clone()
+                    } else {
+                        active.clone()
+                    };
+                    if has("@type")
+                        && let Some(scoped) = type_scoped.terms.get(index)
+                        && let Some(context) = &scoped.context
+                    {
+                        map_context = map_context.process_scoped(
+                            self.loader(),
+                            context,
+                            scoped.base_url.as_deref(),
+                            true,
+                        )?;
+                    }
+                    let expanded_index = active.expand_iri(index, false, true);
+                    let is_none = expanded_index.as_deref() == Some("@none");
+
+                    let items = as_array(self.expand(
+                        &map_context,
+                        Some(key),
+                        &as_array(items.clone()),
+                        base_url,
+                        true,
+                    )?);
+                    for item in as_slice(&items) {
+                        let mut item = item.clone();
+                        if has("@graph") && !is_graph_object(&item) {
+                            item = graph_object(as_array(item));
+                        }
+                        let Value::Object(object) = &mut item else {
+                            continue;
+                        };
+                        if has("@index") && index_key != "@index" && !is_none {
+                            let index_value = expand_value(
+                                active,
+                                Some(index_key),
+                                &Value::String(index.clone()),
+                            );
+                            let Some(index_property) = active.expand_iri(index_key, false, true)
+                            else {
+                                continue;
+                            };
+                            let mut values = vec![index_value];
+                            if let Some(existing) = object.remove(&index_property) {
+                                values.extend(as_slice(&existing).iter().cloned());
+                            }
+                            object.insert(index_property, Value::Array(values));
+                            if object.contains_key("@value") {
+                                return error(
+                                    JsonLdErrorCode::InvalidValueObject,
+                                    "property-valued indexes cannot apply to values",
+                                );
+                            }
+                        } else if has("@index") && !object.contains_key("@index") && !is_none {
+                            object.insert("@index".to_owned(), Value::String(index.clone()));
+                        } else if has("@id") && !object.contains_key("@id") && !is_none {
+                            if let Some(id) = active.expand_iri(index, true, false) {
+                                object.insert("@id".to_owned(), Value::String(id));
+                            }
+                        } else if has("@type") && !is_none {
+                            let mut types =
+                                vec![Value::String(expanded_index.clone().unwrap_or_default())];
+                            if let Some(existing) = object.remove("@type") {
+                                types.extend(as_slice(&existing).iter().cloned());
+                            }
+                            object.insert("@type".to_owned(), Value::Array(types));
+                        }
+                        expanded.push(item);
+                    }
+                }
+                Value::Array(expanded)
+            } else {
+                self.expand(active, Some(key), value, base_url, false)?
+            };
+
+            if expanded.is_null() {
+                continue;
+            }
+            if has("@list") && !is_list_object(&expanded) {
+                expanded = list_object(as_slice(&expanded).to_vec());
+            }
+            if has("@graph") && !has("@id") && !has("@index") {
+                expanded = Value::Array(
+                    as_slice(&expanded)
+                        .iter()
+                        .map(|item| graph_object(as_array(item.clone())))
+                        .collect(),
+                );
+            }
+
+            if definition.is_some_and(|d| d.reverse) {
+                let reverse_map = reverse_map(result);
+                for item in as_slice(&expanded) {
+                    if is_value_object(item) || is_list_object(item) {
+                        return error(
+                            JsonLdErrorCode::InvalidReversePropertyValue,
+                            "reverse properties cannot have values or lists",
+                        );
+                    }
+                }
+                add_value(reverse_map, &property, expanded, true);
+            } else {
+                add_value(result, &property, expanded, true);
+            }
+        }
+
+        for key in nests {
+            for nested in as_slice(&element[key]) {
+                let Value::Object(nested) = nested else {
+                    return error(
+                        JsonLdErrorCode::InvalidNestValue,
+                        "nested values must be objects",
+                    );
+                };
+                if nested
+                    .keys()
+                    .any(|key| active.expand_iri(key, false, true).as_deref() == Some("@value"))
+                {
+                    return error(
+                        JsonLdErrorCode::InvalidNestValue,
+                        "nested values cannot be values",
+                    );
+                }
+                self.expand_entries(
+                    active,
+                    type_scoped,
+                    active_property,
+                    nested,
+                    result,
+                    input_type,
+                    base_url,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates and simplifies an expanded object.
+    fn finish_object(
+        &self,
+        mut result: Map<String, Value>,
+        active_property: Option<&str>,
+    ) -> Result<Value, JsonLdError> {
+        if self.frame && result.contains_key("@value") {
+            return Ok(Value::Object(result));
+        }
+        if let Some(value) = result.get("@value") {
+            let allowed = ["@direction", "@index", "@language", "@type", "@value"];
+            if result.keys().any(|key| !allowed.contains(&key.as_str()))
+                || (result.contains_key("@type")
+                    && (result.contains_key("@language") || result.contains_key("@direction")))
+            {
+                return error(
+                    JsonLdErrorCode::InvalidValueObject,
+                    "invalid value object entries",
+                );
+            }
+            let datatype = result.get("@type");
+            if datatype.is_some_and(|t| t == "@json") {
+                return Ok(Value::Object(result));
+            }
+            if value.is_null() {
+                return Ok(Value::Null);
+            }
+            if !value.is_string() && result.contains_key("@language") {
+                return error(
+                    JsonLdErrorCode::InvalidLanguageTaggedValue,
+                    "only strings can have a language",
+                );
+            }
+            if let Some(datatype) = datatype
+                && !datatype
+                    .as_str()
+                    .is_some_and(|t| t.contains(':') && !t.starts_with("_:"))
+            {
+                return error(JsonLdErrorCode::InvalidTypedValue, "datatypes must be IRIs");
+            }
+        } else if let Some(types) = result.get_mut("@type") {
+            if !types.is_array() {
+                *types = Value::Array(vec![types.take()]);
+            }
+        } else if result.contains_key("@set") || result.contains_key("@list") {
+            if result.len() > 2 || (result.len() == 2 && !result.contains_key("@index")) {
+                return error(
+                    JsonLdErrorCode::InvalidSetOrListObject,
+                    "sets and lists can only have an @index besides",
+                );
+            }
+            if let Some(set) = result.remove("@set") {
+                return Ok(set);
+            }
+        }
+
+        if result.len() == 1 && result.contains_key("@language") {
+            return Ok(Value::Null);
+        }
+
+        if !self.frame && (active_property.is_none() || active_property == Some("@graph")) {
+            let free_floating = result.is_empty()
+                || result.contains_key("@value")
+                || result.contains_key("@list")
+                || (result.len() == 1 && result.contains_key("@id"));
+            if free_floating {
+                return Ok(Value::Null);
+            }
+        }
+        Ok(Value::Object(result))
+    }
+}
+
+/// Value expansion: a scalar as a value object or, when coerced, a node
+/// reference.
+pub(crate) fn expand_value(
+    active: &Context,
+    active_property: Option<&str>,
+    value: &Value,
+) -> Value {
+    let definition = active.term(active_property);
+    let type_mapping = definition.and_then(|d| d.type_mapping.as_deref());
+
+    if let Value::String(id) = value {
+        let id = match type_mapping {
+            Some("@id") => Some(active.expand_iri(id, true, false)),
+            Some("@vocab") => Some(active.expand_iri(id, true, true)),
+            _ => None,
+        };
+        if let Some(id) = id {
+            return Value::Object(Map::from_iter([(
+                "@id".to_owned(),
+                id.map_or(Value::Null, Value::String),
+            )]));
+        }
+    }
+
+    let mut result = Map::new();
+    result.insert("@value".to_owned(), value.clone());
+    match type_mapping {
+        Some("@id" | "@vocab" | "@none") | None => {
+            if value.is_string() {
+                let language = definition
+                    .and_then(|d| d.language.clone())
+                    .unwrap_or_else(|| active.language.clone());
+                let direction = definition
+                    .and_then(|d| d.direction.clone())
+                    .unwrap_or_else(|| active.direction.clone());
+                if let Some(language) = language {
+                    result.insert("@language".to_owned(), Value::String(language));
+                }
+                if let Some(direction) = direction {
+                    result.insert("@direction".to_owned(), Value::String(direction));
+                }
+            }
+        }
+        Some(datatype) => {
+            result.insert("@type".to_owned(), Value::String(datatype.to_owned()));
+        }
+    }
+    Value::Object(result)
+}
+
+pub(crate) fn as_array(value: Value) -> Value {
+    match value {
+        Value::Array(_) => value,
+        Value::Null => Value::Array(Vec::new()),
+        value => Value::Array(vec![value]),
+    }
+}
+
+pub(crate) fn as_slice(value: &Value) -> &[Value] {
+    match value {
+        Value::Array(items) => items,
+        value => std::slice::from_ref(value),
+    }
+}
+
+/// Adds `value` (or each item of it) to the `key` entry of `map`, making
+/// that an array if it gets several values or `as_array` is set.
+pub(crate) fn add_value(map: &mut Map<String, Value>, key: &str, value: Value, as_array: bool) {
+    if as_array && !map.contains_key(key) {
+        map.insert(key.to_owned(), Value::Array(Vec::new()));
+    }
+    match value {
+        Value::Array(items) => {
+            if !map.contains_key(key) && items.is_empty() {
+                map.insert(key.to_owned(), Value::Array(Vec::new()));
+            }
+            for item in items {
+                add_value(map, key, item, as_array);
+            }
+        }
+        value => match map.get_mut(key) {
+            Some(Value::Array(items)) => items.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+            }
+            None => {
+                map.insert(key.to_owned(), value);
+            }
+        },
+    }
+}
+
+fn reverse_map(result: &mut Map<String, Value>) -> &mut Map<String, Value> {
+    let reverse = result
+        .entry("@reverse")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !reverse.is_object() {
+        *reverse = Value::Object(Map::new());
+    }
+    reverse.as_object_mut().unwrap()
+}
+
+fn list_object(items: Vec<Value>) -> Value {
+    Value::Object(Map::from_iter([("@list".to_owned(), Value::Array(items))]))
+}
+
+fn graph_object(items: Value) -> Value {
+    Value::Object(Map::from_iter([("@graph".to_owned(), items)]))
+}
+
+pub(crate) fn is_value_object(value: &Value) -> bool {
+    value.get("@value").is_some()
+}
+
+pub(crate) fn is_list_object(value: &Value) -> bool {
+    value.get("@list").is_some()
+}
+
+pub(crate) fn is_graph_object(value: &Value) -> bool {
+    value.as_object().is_some_and(|map| {
+        map.contains_key("@graph")
+            && map
+                .keys()
+                .all(|key| matches!(key.as_str(), "@graph" | "@id" | "@index" | "@context"))
+    })
+}
+
+pub(crate) fn is_node_object(value: &Value) -> bool {
+    value.as_object().is_some_and(|map| {
+        !map.contains_key("@value") && !map.contains_key("@list") && !map.contains_key("@set")
+    })
+}
@@ -0,0 +1,265 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{Map, Value};
+
+use crate::rs::jsonld::context::error;
+use crate::rs::jsonld::expand::{add_value, as_slice};
+use crate::rs::jsonld::{JsonLdError, JsonLdErrorCode};
+
+/// Nodes by graph name (`@default` for the default graph) and `@id`.
+pub(crate) type NodeMap = BTreeMap<String, BTreeMap<String, Map<String, Value>>>;
+
+/// Relabels blank nodes `_:b0`, `_:b1`... in the order they are met.
+#[derive(Default)]
+pub(crate) struct BlankNodeIssuer {
+    issued: HashMap<String, String>,
+    counter: usize,
+}
+
+impl BlankNodeIssuer {
+    pub(crate) fn issue(&mut self, label: Option<&str>) -> String {
+        if let Some(label) = label
+            && let Some(issued) = self.issued.get(label)
+        {
+            return issued.clone();
+        }
+        let issued = format!("_:b{}", self.counter);
+        self.counter += 1;
+        if let Some(label) = label {
+            self.issued.insert(label.to_owned(), issued.clone());
+        }
+        issued
+    }
+}
+
+enum Subject<'a> {
+    Id(&'a str),
+    /// The node referencing the current one through a reverse property.
+    Reverse(&'a Value),
+}
+
+/// Collects the nodes of an expanded document into a [`NodeMap`], with
+/// references in place of embedded nodes.
+pub(crate) fn node_map(
+    expanded: &Value,
+    issuer: &mut BlankNodeIssuer,
+) -> Result<NodeMap, JsonLdError> {
+    let mut generator = NodeMapGenerator {
+        issuer,
+        nodes: NodeMap::new(),
+    };
+    generator
+        .nodes
+        .insert("@default".to_owned(), BTreeMap::new());
+    generator.generate(expanded, "@default", None, None, None)?;
+    Ok(generator.nodes)
+}
+
+struct NodeMapGenerator<'i> {
+    issuer: &'i mut BlankNodeIssuer,
+    nodes: NodeMap,
+}
+
+impl NodeMapGenerator<'_> {
+    fn generate(
+        &mut self,
+        element: &Value,
+        graph: &str,
+        subject: Option<&Subject>,
+        property: Option<&str>,
+        mut list: Option<&mut Vec<Value>>,
+    ) -> Result<(), JsonLdError> {
+        let map = match element {
+            Value::Array(items) => {
+                for item in items {
+                    self.generate(item, graph, subject, property, list.as_deref_mut())?;
+                }
+                return Ok(());
+            }
+            Value::Object(map) => map,
+            _ => return Ok(()),
+        };
+
+        let mut element = map.clone();
+        if let Some(types) = element.get_mut("@type") {
+            let relabelled: Vec<Value> = as_slice(types)
+                .iter()
+                .map(|item| match item.as_str() {
+                    Some(label) if label.starts_with("_:") => {
+                        Value::String(self.issuer.issue(Some(label)))
+                    }
+                    _ => item.clone(),
+                })
+                .collect();
+            *types = if types.is_array() {
+                Value::Array(relabelled)
+            } else {
+                relabelled.into_iter().next().unwrap_or(Value::Null)
+            };
+        }
+
+        if element.contains_key("@value") {
+            let element = Value::Object(element);
+            match list {
+                Some(list) => list.push(element),
+                None => self.add_unique(graph, subject, property, element),
+            }
+            return Ok(());
+        }
+
+        if let Some(items) = element.get("@list") {
+            let mut items_list = Vec::new();
+            self.generate(items, graph, subject, property, Some(&mut items_list))?;
+            let result = Value::Object(Map::from_iter([(
+                "@list".to_owned(),
+                Value::Array(items_list),
+            )]));
+            match list {
+                Some(list) => list.push(result),
+                None => {
+                    if let (Some(Subject::Id(id)), Some(property)) = (subject, property) {
+                        let node = self.node(graph, id);
+                        add_value(node, property, result, true);
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        let id = match element.remove("@id") {
+            Some(Value::String(id)) if id.starts_with("_:") => self.issuer.issue(Some(&id)),
+            Some(Value::String(id)) => id,
+            _ => self.issuer.issue(None),
+        };
+        self.node(graph, &id);
+
+        match subject {
+            Some(Subject::Reverse(referencing)) => {
+                let property = property.unwrap_or_default();
+                let node = self.node(graph, &id);
+                add_unique(node, property, (*referencing).clone());
+            }
+            Some(Subject::Id(_)) => {
+                let reference = Value::Object(Map::from_iter([(
+                    "@id".to_owned(),
+                    Value::String(id.clone()),
+                )]));
+                match list {
+                    Some(list) => list.push(reference),
+                    None => self.add_unique(graph, subject, property, reference),
+                }
+            }
+            None => {}
+        }
+
+        if let Some(types) = element.remove("@type") {
+            let node = self.node(graph, &id);
+            for item in as_slice(&types) {
+                add_unique(node, "@type", item.clone());
+            }
+        }
+
+        if let Some(index) = element.remove("@index") {
+            let node = self.node(graph, &id);
+            if node
+                .get("@index")
+                .is_some_and(|existing| *existing != index)
+            {
+                return error(
+                    JsonLdErrorCode::ConflictingIndexes,
+                    &format!("{id} has several indexes"),
+                );
+            }
+            node.insert("@index".to_owned(), index);
+        }
+
+        if let Some(Value::Object(reverse)) = element.remove("@reverse") {
+            let referencing = Value::Object(Map::from_iter([(
+                "@id".to_owned(),
+                Value::String(id.clone()),
+            )]));
+            let referencing = Subject::Reverse(&referencing);
+            for (property, values) in &reverse {
+                for value in as_slice(values) {
+                    self.generate(value, graph, Some(&referencing), Some(property), None)?;
+                }
+            }
+        }
+
+        if let Some(nested) = element.remove("@graph") {
+            self.nodes.entry(id.clone()).or_default();
+            self.generate(&nested, &id, None, None, None)?;
+        }
+
+        if let Some(included) = element.remove("@included") {
+            self.generate(&included, graph, None, None, None)?;
+        }
+
+        for (property, value) in &element {
+            let property = if property.starts_with("_:") {
+                self.issuer.issue(Some(property))
+            } else {
+                property.clone()
+            };
+            let node = self.node(graph, &id);
+            if !node.contains_key(&property) {
+                node.insert(property.clone(), Value::Array(Vec::new()));
+            }
+            self.generate(value, graph, Some(&Subject::Id(&id)), Some(&property), None)?;
+        }
+        Ok(())
+    }
+
+    fn node(&mut self, graph: &str, id: &str) -> &mut Map<String, Value> {
+        self.nodes
+            .entry(graph.to_owned())
+            .or_default()
+            .entry(id.to_owned())
+            .or_insert_with(|| Map::from_iter([("@id".to_owned(), Value::String(id.to_owned()))]))
+    }
+
+    fn add_unique(
+        &mut self,
+        graph: &str,
+        subject: Option<&Subject>,
+        property: Option<&str>,
+        value: Value,
+    ) {
+        if let (Some(Subject::Id(id)), Some(property)) = (subject, property) {
+            let node = self.node(graph, id);
+            add_unique(node, property, value);
+        }
+    }
+}
+
+fn add_unique(node: &mut Map<String, Value>, property: &str, value: Value) {
+    if !node
+        .get(property)
+        .is_some_and(|existing| as_slice(existing).contains(&value))
+    {
+        add_value(node, property, value, true);
+    }
+}
+
+/// The flattening algorithm, without compaction.
+pub(crate) fn flatten(expanded: &Value) -> Result<Value, JsonLdError> {
+    let mut nodes = node_map(expanded, &mut BlankNodeIssuer::default())?;
+    let mut default_graph = nodes.remove("@default").unwrap_or_default();
+
+    for (name, graph) in nodes {
+        let entry = default_graph
+            .entry(name.clone())
+            .or_insert_with(|| Map::from_iter([("@id".to_owned(), Value::String(name))]));
+        entry.insert("@graph".to_owned(), Value::Array(graph_nodes(graph)));
+    }
+    Ok(Value::Array(graph_nodes(default_graph)))
+}
+
+/// The nodes of a graph ordered by `@id`, leaving out bare references.
+fn graph_nodes(graph: BTreeMap<String, Map<String, Value>>) -> Vec<Value> {
+    graph
+        .into_values()
+        .filter(|node| !(node.len() == 1 && node.contains_key("@id")))
+        .map(Value::Object)
+        .collect()
+}
@@ -0,0 +1,607 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde_json::{Map, Value};
+
+use crate::rs::jsonld::context::{error, is_keyword};
+use crate::rs::jsonld::expand::{add_value, as_slice, is_list_object};
+use crate::rs::jsonld::flatten::{BlankNodeIssuer, node_map};
+use crate::rs::jsonld::{Embed, JsonLdError, JsonLdErrorCode};
+
+type Graph = BTreeMap<String, Map<String, Value>>;
+
+/// Defaults of the framing flags, overridden by `@embed`, `@explicit`,
+/// `@omitDefault` and `@requireAll` in frames.
+#[derive(Clone, Copy)]
+pub(crate) struct Flags {
+    pub(crate) embed: Embed,
+    pub(crate) explicit: bool,
+    pub(crate) omit_default: bool,
+    pub(crate) require_all: bool,
+}
+
+impl Flags {
+    fn of(&self, frame: &Map<String, Value>) -> Result<Flags, JsonLdError> {
+        let flag = |key: &str, default: bool| -> Result<bool, JsonLdError> {
+            match frame.get(key).map(|value| as_slice(value).first()) {
+                None => Ok(default),
+                Some(Some(Value::Bool(value))) => Ok(*value),
+                Some(_) => error(
+                    JsonLdErrorCode::InvalidFrame,
+                    &format!("{key} must be a boolean"),
+                ),
+            }
+        };
+        let embed = match frame.get("@embed").map(|value| as_slice(value).first()) {
+            None => self.embed,
+            Some(Some(Value::Bool(true))) => Embed::Once,
+            Some(Some(Value::Bool(false))) => Embed::Never,
+            Some(Some(Value::String(embed))) => match embed.as_str() {
+                "@always" => Embed::Always,
+                "@once" => Embed::Once,
+                "@never" => Embed::Never,
+                _ => {
+                    return error(
+                        JsonLdErrorCode::InvalidEmbedValue,
+                        &format!("unknown @embed value {embed:?}"),
+                    );
+                }
+            },
+            Some(_) => {
+                return error(
+                    JsonLdErrorCode::InvalidEmbedValue,
+                    "@embed must be a boolean or a keyword",
+                );
+            }
+        };
+        Ok(Flags {
+            embed,
+            explicit: flag("@explicit", self.explicit)?,
+            omit_default: flag("@omitDefault", self.omit_default)?,
+            require_all: flag("@requireAll", self.require_all)?,
+        })
+    }
+}
+
+/// Where framed nodes go: an array, or the values of a property.
+enum Parent<'p> {
+    Array(&'p mut Vec<Value>),
+    Property(&'p mut Map<String, Value>, &'p str),
+}
+
+impl Parent<'_> {
+    fn add(&mut self, output: Value) {
+        match self {
+            Parent::Array(items) => items.push(output),
+            Parent::Property(map, property) => add_value(map, property, output, true),
+        }
+    }
+}
+
+struct Framer {
+    flags: Flags,
+    graphs: BTreeMap<String, Graph>,
+    /// Name of the graph being framed, `@merged` for the top level.
+    graph: String,
+    /// Nodes embedded so far below the current top-level node, by graph.
+    embedded: HashMap<String, HashSet<String>>,
+    /// Nodes being framed, by graph, to detect cycles.
+    stack: Vec<(String, String)>,
+}
+
+/// The framing algorithm on expanded documents, leaving `@preserve`
+/// placeholders for compaction and pruning blank node identifiers used only
+/// once.
+pub(crate) fn frame(expanded: &Value, frame: &Value, flags: Flags) -> Result<Value, JsonLdError> {
+    let frame = match frame {
+        Value::Array(items) if items.len() <= 1 => items.first().cloned().unwrap_or_default(),
+        frame => frame.clone(),
+    };
+    let frame = match frame {
+        Value::Object(frame) => frame,
+        Value::Null => Map::new(),
+        _ => return error(JsonLdErrorCode::InvalidFrame, "frames must be objects"),
+    };
+
+    let mut graphs = node_map(expanded, &mut BlankNodeIssuer::default())?;
+    let merged = merge(&graphs);
+    graphs.insert("@merged".to_owned(), merged);
+
+    let mut framer = Framer {
+        flags,
+        graphs,
+        graph: "@merged".to_owned(),
+        embedded: HashMap::new(),
+        stack: Vec::new(),
+    };
+    let subjects: Vec<String> = framer.graphs["@merged"].keys().cloned().collect();
+    let mut framed = Vec::new();
+    framer.frame(&subjects, &frame, Parent::Array(&mut framed), true)?;
+
+    let mut framed = Value::Array(framed);
+    let mut counts = HashMap::new();
+    count_blank_nodes(&framed, &mut counts);
+    prune_blank_nodes(&mut framed, &counts);
+    Ok(framed)
+}
+
+/// The nodes of all graphs in one graph, with the values of all their
+/// occurrences.
+fn merge(graphs: &BTreeMap<String, Graph>) -> Graph {
+    let mut merged = Graph::new();
+    for graph in graphs.values() {
+        for (id, node) in graph {
+            let merged_node = merged
+                .entry(id.clone())
+                .or_insert_with(|| Map::from_iter([("@id".to_owned(), Value::String(id.clone()))]));
+            for (property, values) in node {
+                if property == "@id" {
+                    continue;
+                }
+                if is_keyword(property) && property != "@type" {
+                    merged_node.insert(property.clone(), values.clone());
+                    continue;
+                }
+                if !merged_node.contains_key(property) {
+                    merged_node.insert(property.clone(), Value::Array(Vec::new()));
+                }
+                for value in as_slice(values) {
+                    let exists = as_slice(&merged_node[property]).contains(value);
+                    if !exists {
+                        add_value(merged_node, property, value.clone(), true);
+                    }
+                }
+            }
+        }
+    }
+    merged
+}
+
+impl Framer {
+    fn frame(
+        &mut self,
+        subjects: &[String],
+        frame: &Map<String, Value>,
+        mut parent: Parent,
+        top_level: bool,
+    ) -> Result<(), JsonLdError> {
+        let flags = self.flags.of(frame)?;
+        let mut matches = Vec::new();
+        for id in subjects {
+            if let Some(node) = self.graphs[&self.graph].get(id)
+                && self.matches(node, frame, flags.require_all)?
+            {
+                matches.push(id.clone());
+            }
+        }
+        matches.sort();
+
+        for id in matches {
+            if top_level {
+                self.embedded.clear();
+            }
+            let mut output = Map::from_iter([("@id".to_owned(), Value::String(id.clone()))]);
+
+            let circular = self.stack.contains(&(self.graph.clone(), id.clone()));
+            let embedded = self.embedded.entry(self.graph.clone()).or_default();
+            if flags.embed == Embed::Never
+                || circular
+                || (flags.embed == Embed::Once && embedded.contains(&id))
+            {
+                parent.add(Value::Object(output));
+                continue;
+            }
+            embedded.insert(id.clone());
+            self.stack.push((self.graph.clone(), id.clone()));
+
+            if self.graphs.contains_key(&id) {
+                let subframe = match frame.get("@graph") {
+                    Some(subframe) => Some(first_frame(subframe)),
+                    None if self.graph != "@merged" => Some(Map::new()),
+                    None => None,
+                };
+                if let Some(subframe) = subframe {
+                    let subjects: Vec<String> = self.graphs[&id].keys().cloned().collect();
+                    let graph = std::mem::replace(&mut self.graph, id.clone());
+                    self.frame(
+                        &subjects,
+                        &subframe,
+                        Parent::Property(&mut output, "@graph"),
+                        false,
+                    )?;
+                    self.graph = graph;
+                }
+            }
+
+            let node = self.graphs[&self.graph][&id].clone();
+            for (property, values) in &node {
+                if property == "@id" {
+                    continue;
+                }
+                if is_keyword(property) {
+                    output.insert(property.clone(), values.clone());
+                    continue;
+                }
+                if flags.explicit && !frame.contains_key(property) {
+                    continue;
+                }
+                let subframe = match frame.get(property) {
+                    Some(subframe) => first_frame(subframe),
+                    None => implicit_frame(flags),
+                };
+                for item in as_slice(values) {
+                    if is_list_object(item) {
+                        let list_frame = subframe
+                            .get("@list")
+                            .map(first_frame)
+                            .unwrap_or_else(|| implicit_frame(flags));
+                        let mut list =
+                            Map::from_iter([("@list".to_owned(), Value::Array(Vec::new()))]);
+                        for list_item in as_slice(&item["@list"]) {
+                            match reference(list_item) {
+                                Some(reference) => self.frame(
+                                    &[reference.to_owned()],
+                                    &list_frame,
+                                    Parent::Property(&mut list, "@list"),
+                                    false,
+                                )?,
+                                None => add_value(&mut list, "@list", list_item.clone(), true),
+                            }
+                        }
+                        add_value(&mut output, property, Value::Object(list), true);
+                    } else if let Some(reference) = reference(item) {
+                        self.frame(
+                            &[reference.to_owned()],
+                            &subframe,
+                            Parent::Property(&mut output, property),
+                            false,
+                        )?;
+                    } else if value_matches(&subframe, item) {
+                        add_value(&mut output, property, item.clone(), true);
+                    }
+                }
+            }
+
+            for (property, subframe) in frame {
+                if property.starts_with('@') || output.contains_key(property) {
+                    continue;
+                }
+                let subframe = first_frame(subframe);
+                if flags.of(&subframe)?.omit_default {
+                    continue;
+                }
+                let preserve = subframe
+                    .get("@default")
+                    .cloned()
+                    .unwrap_or_else(|| Value::String("@null".to_owned()));
+                let preserve = Map::from_iter([(
+                    "@preserve".to_owned(),
+                    Value::Array(as_slice(&preserve).to_vec()),
+                )]);
+                output.insert(
+                    property.clone(),
+                    Value::Array(vec![Value::Object(preserve)]),
+                );
+            }
+
+            if let Some(Value::Object(reverse)) = frame.get("@reverse") {
+                let mut reverse_output = Map::new();
+                for (property, subframe) in reverse {
+                    let subframe = first_frame(subframe);
+                    let referencing: Vec<String> = self.graphs[&self.graph]
+                        .iter()
+                        .filter(|(_, node)| {
+                            node.get(property).is_some_and(|values| {
+                                as_slice(values)
+                                    .iter()
+                                    .any(|value| reference(value) == Some(&id))
+                            })
+                        })
+                        .map(|(subject, _)| subject.clone())
+                        .collect();
+                    if !referencing.is_empty() {
+                        self.frame(
+                            &referencing,
+                            &subframe,
+                            Parent::Property(&mut reverse_output, property),
+                            false,
+                        )?;
+                    }
+                }
+                if !reverse_output.is_empty() {
+                    output.insert("@reverse".to_owned(), Value::Object(reverse_output));
+                }
+            }
+
+            parent.add(Value::Object(output));
+            self.stack.pop();
+        }
+        Ok(())
+    }
+
+    /// Whether `node` matches `frame`, on `@id`, `@type` and property
+    /// values.
+    fn matches(
+        &self,
+        node: &Map<String, Value>,
+        frame: &Map<String, Value>,
+        require_all: bool,
+    ) -> Result<bool, JsonLdError> {
+        let mut wildcard = true;
+        let mut matches_some = false;
+
+        for (key, value) in frame {
+            let patterns = as_slice(value);
+            let values = node.get(key).map(as_slice).unwrap_or_default();
+            let matched = match key.as_str() {
+                "@id" => {
+                    let matched = patterns.iter().any(is_wildcard)
+                        || values.first().is_some_and(|id| patterns.contains(id));
+                    if !require_all {
+                        return Ok(matched);
+                    }
+                    matched
+                }
+                "@type" => {
+                    wildcard = false;
+                    let matched = if patterns.is_empty() {
+                        values.is_empty()
+                    } else if patterns.len() == 1 && is_wildcard(&patterns[0]) {
+                        !values.is_empty()
+                    } else {
+                        patterns.iter().any(|pattern| {
+                            pattern.get("@default").is_some() || values.contains(pattern)
+                        })
+                    };
+                    if !require_all {
+                        return Ok(matched);
+                    }
+                    matched
+                }
+                key if is_keyword(key) || key.starts_with('@') => continue,
+                _ => {
+                    wildcard = false;
+                    let subframe = patterns.first();
+                    if values.is_empty() && subframe.is_some_and(|s| s.get("@default").is_some()) {
+                        continue;
+                    }
+                    match subframe {
+                        None => values.is_empty(),
+                        Some(subframe)
+                            if subframe.get("@value").is_some()
+                                || subframe.get("@language").is_some() =>
+                        {
+                            let subframe = subframe.as_object().cloned().unwrap_or_default();
+                            values.iter().any(|value| value_matches(&subframe, value))
+                        }
+                        Some(Value::Object(subframe))
+                            if subframe.len() == 1 && subframe.contains_key("@id") =>
+                        {
+                            let mut matched = false;
+                            for value in values {
+                                if let Some(node) =
+                                    reference(value).and_then(|id| self.graphs[&self.graph].get(id))
+                                    && self.matches(node, subframe, require_all)?
+                                {
+                                    matched = true;
+                                    break;
+                                }
+                            }
+                            matched
+                        }
+                        Some(Value::Object(_)) => !values.is_empty(),
+                        Some(_) => false,
+                    }
+                }
+            };
+            if !matched && require_all {
+                return Ok(false);
+            }
+            matches_some |= matched;
+        }
+        Ok(wildcard || matches_some)
+    }
+}
+
+fn first_frame(frame: &Value) -> Map<String, Value> {
+    as_slice(frame)
+        .first()
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn implicit_frame(flags: Flags) -> Map<String, Value> {
+    let embed = match flags.embed {
+        Embed::Always => "@always",
+        Embed::Once => "@once",
+        Embed::Never => "@never",
+    };
+    Map::from_iter([
+        ("@embed".to_owned(), Value::String(embed.to_owned())),
+        ("@explicit".to_owned(), Value::Bool(flags.explicit)),
+        ("@requireAll".to_owned(), Value::Bool(flags.require_all)),
+    ])
+}
+
+fn is_wildcard(value: &Value) -> bool {
+    value.as_object().is_some_and(Map::is_empty)
+}
+
+/// The `@id` of a node reference.
+fn reference(value: &Value) -> Option<&String> {
+    match value {
+        Value::Object(map) if map.len() == 1 => match map.get("@id") {
+            Some(Value::String(id)) => Some(id),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether the value object `value` matches the value pattern `pattern`, of
+/// which `@value`, `@type` and `@language` may be wildcards.
+fn value_matches(pattern: &Map<String, Value>, value: &Value) -> bool {
+    let entries = ["@value", "@type", "@language"];
+    if entries.iter().all(|key| !pattern.contains_key(*key)) {
+        return true;
+    }
+    entries.iter().all(|key| {
+        let patterns = pattern.get(*key).map(as_slice).unwrap_or_default();
+        match value.get(*key) {
+            None => patterns.is_empty(),
+            Some(entry) => patterns
+                .iter()
+                .any(|pattern| pattern == entry || is_wildcard(pattern)),
+        }
+    })
+}
+
+fn count_blank_nodes(value: &Value, counts: &mut HashMap<String, usize>) {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .for_each(|item| count_blank_nodes(item, counts)),
+        Value::Object(map) => {
+            if let Some(Value::String(id)) = map.get("@id")
+                && id.starts_with("_:")
+            {
+                *counts.entry(id.clone()).or_default() += 1;
+            }
+            map.values()
+                .for_each(|value| count_blank_nodes(value, counts));
+        }
+        _ => {}
+    }
+}
+
+fn prune_blank_nodes(value: &mut Value, counts: &HashMap<String, usize>) {
+    match value {
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| prune_blank_nodes(item, counts)),
+        Value::Object(map) => {
+            if let Some(Value::String(id)) = map.get("@id")
+                && counts.get(id) == Some(&1)
+            {
+                map.remove("@id");
+            }
+            map.values_mut()
+                .for_each(|value| prune_blank_nodes(value, counts));
+        }
+        _ => {}
+    }
+}
+
+/// Replaces the `@preserve` placeholders of a compacted framed document by
+/// their values, and `@null` by `null`.
+pub(crate) fn remove_preserve(value: Value) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(remove_preserve).collect()),
+        Value::Object(mut map) => match map.remove("@preserve") {
+            Some(preserved) => remove_preserve(preserved),
+            None => Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, remove_preserve(value)))
+                    .collect(),
+            ),
+        },
+        Value::String(value) if value == "@null" => Value::Null,
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::rs::jsonld::{Embed, JsonLdProcessor};
+    use crate::rs::turtle::{TurtleSyntax, parse_str};
+
+    const LIBRARY: &str = r#"
+        @prefix : <http://example.org/> .
+        :library a :Library ; :contains :book .
+        :book a :Book ; :title "Moby Dick" ; :contains :chapter1, :chapter2 .
+        :chapter1 a :Chapter ; :title "Loomings" ; :next :chapter2 .
+        :chapter2 a :Chapter ; :title "The Carpet-Bag" .
+    "#;
+
+    #[test]
+    fn quads_frame_into_nested_documents() {
+        let quads = parse_str(TurtleSyntax::Turtle, LIBRARY, None).unwrap();
+        let framed = JsonLdProcessor::new()
+            .frame_quads(
+                &quads,
+                &json!({
+                    "@context": {"@vocab": "http://example.org/", "@base": "http://example.org/"},
+                    "@type": "Library",
+                    "contains": {
+                        "@type": "Book",
+                        "author": {"@default": "unknown"},
+                        "contains": {"@type": "Chapter", "@explicit": true, "title": {}}
+                    }
+                }),
+            )
+            .unwrap();
+        assert_eq!(
+            framed,
+            json!({
+                "@context": {"@vocab": "http://example.org/", "@base": "http://example.org/"},
+                "@id": "library",
+                "@type": "Library",
+                "contains": {
+                    "@id": "book",
+                    "@type": "Book",
+                    "author": "unknown",
+                    "contains": [
+                        {"@id": "chapter1", "@type": "Chapter", "title": "Loomings"},
+                        {"@id": "chapter2", "@type": "Chapter", "title": "The Carpet-Bag"}
+                    ],
+                    "title": "Moby Dick"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn flags_control_embedding_and_matching() {
+        let quads = parse_str(TurtleSyntax::Turtle, LIBRARY, None).unwrap();
+        let context = json!({"@vocab": "http://example.org/", "@base": "http://example.org/"});
+        let frame = |frame: serde_json::Value| {
+            let mut frame = frame.as_object().unwrap().clone();
+            frame.insert("@context".to_owned(), context.clone());
+            let mut framed = JsonLdProcessor::new()
+                .frame_quads(&quads, &serde_json::Value::Object(frame))
+                .unwrap();
+            framed.as_object_mut().unwrap().remove("@context");
+            framed
+        };
+        let chapter2 = json!({"@id": "chapter2", "@type": "Chapter", "title": "The Carpet-Bag"});
+
+        assert_eq!(
+            frame(json!({"@type": "Book", "contains": {"@embed": "@never"}}))["contains"],
+            json!([{"@id": "chapter1"}, {"@id": "chapter2"}])
+        );
+        // chapter2 is embedded where first met, as the next chapter.
+        let once = frame(json!({"@type": "Book"}));
+        assert_eq!(once["contains"][0]["next"], chapter2);
+        assert_eq!(once["contains"][1], json!({"@id": "chapter2"}));
+        let always = frame(json!({"@type": "Book", "contains": {"@embed": "@always"}}));
+        assert_eq!(always["contains"][1], chapter2);
+
+        assert_eq!(
+            frame(json!({"@type": "Chapter", "next": {}, "@requireAll": true}))["@id"],
+            "chapter1"
+        );
+        assert_eq!(
+            frame(
+                json!({"title": {"@value": "The Carpet-Bag"}, "@explicit": true, "@omitDefault": true, "pages": {}})
+            ),
+            json!({"@id": "chapter2", "@type": "Chapter", "title": "The Carpet-Bag"})
+        );
+        let all = JsonLdProcessor::new()
+            .with_embed(Embed::Never)
+            .frame_quads(&quads, &json!({"@type": {}}))
+            .unwrap();
+        assert_eq!(all["@graph"].as_array().unwrap().len(), 4);
+    }
+}
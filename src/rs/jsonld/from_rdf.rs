@@ -0,0 +1,273 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{Map, Number, Value};
+
+use crate::rs::jsonld::context::error;
+use crate::rs::jsonld::expand::as_slice;
+use crate::rs::jsonld::{JsonLdError, JsonLdErrorCode};
+use crate::rs::literal::Literal;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term_like::TermLike;
+use crate::rs::vocab::{rdf, xsd};
+
+type Graph = BTreeMap<String, Map<String, Value>>;
+
+/// Where a blank node or `rdf:nil` is used: the node, property and index of
+/// the reference in its values.
+#[derive(Clone)]
+struct Usage {
+    node: String,
+    property: String,
+    index: usize,
+}
+
+#[derive(Default)]
+struct GraphState {
+    nodes: Graph,
+    nil_usages: Vec<Usage>,
+    /// `None` once a blank node is used more than once.
+    blank_usages: HashMap<String, Option<Usage>>,
+}
+
+/// Serializes quads to an expanded document, turning well-formed
+/// `rdf:first`/`rdf:rest` chains into lists.
+pub(crate) fn from_rdf<'a>(
+    quads: impl IntoIterator<Item = &'a Quad>,
+    native_types: bool,
+    rdf_type: bool,
+) -> Result<Value, JsonLdError> {
+    let mut graphs: BTreeMap<String, GraphState> = BTreeMap::new();
+    graphs.insert("@default".to_owned(), GraphState::default());
+
+    for quad in quads {
+        let name = match quad.graph() {
+            QuadGraph::DefaultGraph(_) => "@default".to_owned(),
+            QuadGraph::NamedNode(node) => node.value().to_owned(),
+            QuadGraph::BlankNode(node) => format!("_:{}", node.value()),
+            QuadGraph::Variable(_) => continue,
+        };
+        let subject = match quad.subject() {
+            QuadSubject::NamedNode(node) => node.value().to_owned(),
+            QuadSubject::BlankNode(node) => format!("_:{}", node.value()),
+            QuadSubject::Variable(_) | QuadSubject::Quad(_) => continue,
+        };
+        let QuadPredicate::NamedNode(predicate) = quad.predicate() else {
+            continue;
+        };
+        let (object, reference) = match quad.object() {
+            QuadObject::NamedNode(node) => (None, Some(node.value().to_owned())),
+            QuadObject::BlankNode(node) => (None, Some(format!("_:{}", node.value()))),
+            QuadObject::Literal(literal) => (Some(literal_object(literal, native_types)?), None),
+            QuadObject::Variable(_) => continue,
+        };
+
+        if name != "@default" {
+            ensure_node(&mut graphs.get_mut("@default").unwrap().nodes, &name);
+        }
+        let graph = graphs.entry(name).or_default();
+        ensure_node(&mut graph.nodes, &subject);
+
+        let object = match reference {
+            Some(reference) => {
+                ensure_node(&mut graph.nodes, &reference);
+                if predicate == &*rdf::r#type && !rdf_type {
+                    add_unique(
+                        graph.nodes.get_mut(&subject).unwrap(),
+                        "@type",
+                        Value::String(reference),
+                    );
+                    continue;
+                }
+                Value::Object(Map::from_iter([(
+                    "@id".to_owned(),
+                    Value::String(reference),
+                )]))
+            }
+            None => object.unwrap(),
+        };
+
+        let property = predicate.value();
+        let node = graph.nodes.get_mut(&subject).unwrap();
+        let index = add_unique(node, property, object.clone());
+        let usage = Usage {
+            node: subject,
+            property: property.to_owned(),
+            index,
+        };
+        match object.get("@id").and_then(Value::as_str) {
+            Some(id) if id == rdf::nil.value() => graph.nil_usages.push(usage),
+            Some(id) if id.starts_with("_:") => {
+                graph
+                    .blank_usages
+                    .entry(id.to_owned())
+                    .and_modify(|usage| *usage = None)
+                    .or_insert(Some(usage));
+            }
+            _ => {}
+        }
+    }
+
+    for state in graphs.values_mut() {
+        convert_lists(state);
+    }
+
+    let mut graphs: BTreeMap<String, Graph> = graphs
+        .into_iter()
+        .map(|(name, state)| (name, state.nodes))
+        .collect();
+    let default_graph = graphs.remove("@default").unwrap_or_default();
+    let mut result = Vec::new();
+    for (subject, mut node) in default_graph {
+        if let Some(graph) = graphs.remove(&subject) {
+            node.insert("@graph".to_owned(), Value::Array(graph_nodes(graph)));
+        }
+        if !(node.len() == 1 && node.contains_key("@id")) {
+            result.push(Value::Object(node));
+        }
+    }
+    Ok(Value::Array(result))
+}
+
+fn ensure_node(graph: &mut Graph, id: &str) {
+    graph
+        .entry(id.to_owned())
+        .or_insert_with(|| Map::from_iter([("@id".to_owned(), Value::String(id.to_owned()))]));
+}
+
+/// Adds `value` to the `property` values of `node` unless it is there
+/// already, returning its index.
+fn add_unique(node: &mut Map<String, Value>, property: &str, value: Value) -> usize {
+    let values = node
+        .entry(property)
+        .or_insert_with(|| Value::Array(Vec::new()))
+        .as_array_mut()
+        .unwrap();
+    match values.iter().position(|existing| *existing == value) {
+        Some(index) => index,
+        None => {
+            values.push(value);
+            values.len() - 1
+        }
+    }
+}
+
+fn graph_nodes(graph: Graph) -> Vec<Value> {
+    graph
+        .into_values()
+        .filter(|node| !(node.len() == 1 && node.contains_key("@id")))
+        .map(Value::Object)
+        .collect()
+}
+
+/// Replaces the chains of list nodes ending in `rdf:nil` by list objects.
+fn convert_lists(state: &mut GraphState) {
+    let first = rdf::first.value();
+    let rest = rdf::rest.value();
+
+    for nil_usage in state.nil_usages.clone() {
+        let mut usage = nil_usage;
+        let mut items = Vec::new();
+        let mut list_nodes = Vec::new();
+
+        while usage.property == rest && usage.node.starts_with("_:") {
+            let Some(Some(next)) = state.blank_usages.get(&usage.node) else {
+                break;
+            };
+            let Some(node) = state.nodes.get(&usage.node) else {
+                break;
+            };
+            let is_list_node = node.iter().all(|(key, values)| match key.as_str() {
+                "@id" => true,
+                "@type" => as_slice(values) == [Value::String(rdf::List.value().to_owned())],
+                key if key == first || key == rest => as_slice(values).len() == 1,
+                _ => false,
+            }) && node.contains_key(first)
+                && node.contains_key(rest);
+            if !is_list_node {
+                break;
+            }
+            items.push(node[first][0].clone());
+            list_nodes.push(usage.node.clone());
+            usage = next.clone();
+        }
+
+        let Some(node) = state.nodes.get_mut(&usage.node) else {
+            continue;
+        };
+        let Some(head) = node
+            .get_mut(&usage.property)
+            .and_then(|values| values.as_array_mut())
+            .and_then(|values| values.get_mut(usage.index))
+        else {
+            continue;
+        };
+        items.reverse();
+        *head = Value::Object(Map::from_iter([("@list".to_owned(), Value::Array(items))]));
+        for list_node in list_nodes {
+            state.nodes.remove(&list_node);
+        }
+    }
+}
+
+fn literal_object(literal: &Literal, native_types: bool) -> Result<Value, JsonLdError> {
+    let lexical = literal.value();
+    let datatype = literal.datatype().value();
+    let mut result = Map::new();
+
+    if datatype == rdf::JSON.value() {
+        let Ok(value) = serde_json::from_str::<Value>(lexical) else {
+            return error(
+                JsonLdErrorCode::InvalidJsonLiteral,
+                &format!("{lexical:?} is not JSON"),
+            );
+        };
+        result.insert("@value".to_owned(), value);
+        result.insert("@type".to_owned(), Value::String("@json".to_owned()));
+        return Ok(Value::Object(result));
+    }
+
+    if native_types {
+        let native = if datatype == xsd::boolean.value() {
+            match lexical {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            }
+        } else if datatype == xsd::integer.value() {
+            lexical.parse::<i64>().ok().map(Value::from)
+        } else if datatype == xsd::double.value() {
+            lexical
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number)
+        } else {
+            None
+        };
+        if let Some(native) = native {
+            result.insert("@value".to_owned(), native);
+            return Ok(Value::Object(result));
+        }
+    }
+
+    result.insert("@value".to_owned(), Value::String(lexical.to_owned()));
+    if !literal.language().is_empty() {
+        result.insert(
+            "@language".to_owned(),
+            Value::String(literal.language().to_owned()),
+        );
+        if let Some(direction) = literal.direction() {
+            result.insert(
+                "@direction".to_owned(),
+                Value::String(direction.to_string()),
+            );
+        }
+    } else if datatype != xsd::string.value() {
+        result.insert("@type".to_owned(), Value::String(datatype.to_owned()));
+    }
+    Ok(Value::Object(result))
+}
@@ -0,0 +1,180 @@
+use serde_json::Value;
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::iri::has_scheme;
+use crate::rs::jsonld::JsonLdError;
+use crate::rs::jsonld::expand::as_slice;
+use crate::rs::jsonld::flatten::{BlankNodeIssuer, node_map};
+use crate::rs::literal::{LanguageDirection, Literal, is_valid_language_tag};
+use crate::rs::named_node::NamedNode;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::sparql::expression::format_double;
+use crate::rs::term_like::TermLike;
+use crate::rs::vocab::{rdf, xsd};
+
+/// Deserializes an expanded document, skipping relative IRIs, blank node
+/// predicates and invalid language tags as the specification does.
+pub(crate) fn to_rdf(expanded: &Value) -> Result<Vec<Quad>, JsonLdError> {
+    let mut issuer = BlankNodeIssuer::default();
+    let nodes = node_map(expanded, &mut issuer)?;
+    let mut serializer = Serializer {
+        issuer,
+        quads: Vec::new(),
+    };
+
+    for (name, graph) in &nodes {
+        let graph_name = match name.as_str() {
+            "@default" => None,
+            name => match node(name) {
+                Some(QuadObject::NamedNode(node)) => Some(QuadGraph::NamedNode(node)),
+                Some(QuadObject::BlankNode(node)) => Some(QuadGraph::BlankNode(node)),
+                _ => continue,
+            },
+        };
+        for (id, properties) in graph {
+            let subject = match node(id) {
+                Some(QuadObject::NamedNode(node)) => QuadSubject::NamedNode(node),
+                Some(QuadObject::BlankNode(node)) => QuadSubject::BlankNode(node),
+                _ => continue,
+            };
+            for (property, values) in properties {
+                if property == "@type" {
+                    for item in as_slice(values) {
+                        if let Some(object) = item.as_str().and_then(node) {
+                            serializer.push(&subject, &rdf::r#type, object, graph_name.as_ref());
+                        }
+                    }
+                    continue;
+                }
+                if property.starts_with('@') || property.starts_with("_:") || !has_scheme(property)
+                {
+                    continue;
+                }
+                let predicate = NamedNode::new(property);
+                for item in as_slice(values) {
+                    if let Some(object) = serializer.object(item, graph_name.as_ref()) {
+                        serializer.push(&subject, &predicate, object, graph_name.as_ref());
+                    }
+                }
+            }
+        }
+    }
+    Ok(serializer.quads)
+}
+
+/// The node labelled `id`, unless it is a relative IRI.
+fn node(id: &str) -> Option<QuadObject> {
+    if let Some(label) = id.strip_prefix("_:") {
+        Some(QuadObject::BlankNode(BlankNode::new(label)))
+    } else if has_scheme(id) {
+        Some(QuadObject::NamedNode(NamedNode::new(id)))
+    } else {
+        None
+    }
+}
+
+struct Serializer {
+    issuer: BlankNodeIssuer,
+    quads: Vec<Quad>,
+}
+
+impl Serializer {
+    fn push(
+        &mut self,
+        subject: &QuadSubject,
+        predicate: &NamedNode,
+        object: QuadObject,
+        graph: Option<&QuadGraph>,
+    ) {
+        let predicate = QuadPredicate::NamedNode(predicate.clone());
+        self.quads
+            .push(Quad::new(subject, &predicate, &object, graph));
+    }
+
+    /// The object an item of the node map stands for, adding the triples of
+    /// lists.
+    fn object(&mut self, item: &Value, graph: Option<&QuadGraph>) -> Option<QuadObject> {
+        if let Some(id) = item.get("@id") {
+            return node(id.as_str()?);
+        }
+        if let Some(list) = item.get("@list") {
+            return Some(self.list(as_slice(list), graph));
+        }
+
+        let value = item.get("@value")?;
+        let mut datatype = item.get("@type").and_then(Value::as_str).map(str::to_owned);
+        let lexical = match value {
+            _ if datatype.as_deref() == Some("@json") => {
+                datatype = Some(rdf::JSON.value().to_owned());
+                serde_json::to_string(value).ok()?
+            }
+            Value::Bool(value) => {
+                datatype.get_or_insert_with(|| xsd::boolean.value().to_owned());
+                value.to_string()
+            }
+            Value::Number(number) => {
+                let number = number.as_f64()?;
+                let is_double = number.fract() != 0.0
+                    || number.abs() >= 1e21
+                    || datatype.as_deref() == Some(xsd::double.value());
+                if is_double {
+                    datatype.get_or_insert_with(|| xsd::double.value().to_owned());
+                    format_double(number)
+                } else {
+                    datatype.get_or_insert_with(|| xsd::integer.value().to_owned());
+                    format!("{number:.0}")
+                }
+            }
+            Value::String(value) => value.clone(),
+            _ => return None,
+        };
+
+        if let Some(datatype) = &datatype
+            && !has_scheme(datatype)
+        {
+            return None;
+        }
+        let language = item.get("@language").and_then(Value::as_str);
+        if language.is_some_and(|language| !is_valid_language_tag(language)) {
+            return None;
+        }
+        let direction = item
+            .get("@direction")
+            .and_then(Value::as_str)
+            .and_then(|direction| direction.parse::<LanguageDirection>().ok());
+
+        let literal = match language {
+            Some(language) => Literal::new(&lexical, Some(language), direction.as_ref(), None),
+            None => Literal::new(
+                &lexical,
+                None,
+                None,
+                datatype.map(|d| NamedNode::new(&d)).as_ref(),
+            ),
+        };
+        Some(QuadObject::Literal(literal))
+    }
+
+    fn list(&mut self, items: &[Value], graph: Option<&QuadGraph>) -> QuadObject {
+        if items.is_empty() {
+            return QuadObject::NamedNode(rdf::nil.clone());
+        }
+        let labels: Vec<String> = items.iter().map(|_| self.issuer.issue(None)).collect();
+        for (index, item) in items.iter().enumerate() {
+            let subject = QuadSubject::BlankNode(BlankNode::new(&labels[index][2..]));
+            if let Some(object) = self.object(item, graph) {
+                self.push(&subject, &rdf::first, object, graph);
+            }
+            let rest = match labels.get(index + 1) {
+                Some(label) => QuadObject::BlankNode(BlankNode::new(&label[2..])),
+                None => QuadObject::NamedNode(rdf::nil.clone()),
+            };
+            self.push(&subject, &rdf::rest, rest, graph);
+        }
+        QuadObject::BlankNode(BlankNode::new(&labels[0][2..]))
+    }
+}
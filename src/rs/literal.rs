@@ -1,6 +1,10 @@
+use std::error::Error;
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
 use crate::rs::named_node::NamedNode;
+use crate::rs::vocab::{rdf, xsd};
 use crate::rs::{term::Term, term_like::TermLike};
 
 #[derive(Clone, Eq, Debug)]
@@ -27,18 +31,37 @@ impl Literal {
             direction: direction.cloned(),
             datatype: if let Some(_l) = language {
                 if let Some(_d) = direction {
-                    NamedNode::new("http://www.w3.org/1999/02/22-rdf-syntax-ns#dirLangString")
+                    rdf::dirLangString.clone()
                 } else {
-                    NamedNode::new("http://www.w3.org/1999/02/22-rdf-syntax-ns#langString")
+                    rdf::langString.clone()
                 }
             } else if let Some(d) = datatype {
                 d.to_owned()
             } else {
-                NamedNode::new("http://www.w3.org/2001/XMLSchema#string")
+                xsd::string.clone()
             },
         }
     }
 
+    /// Strict counterpart of [`Literal::new`] that rejects contradictory
+    /// combinations instead of silently dropping the datatype.
+    pub fn try_new(
+        value: &str,
+        language: Option<&str>,
+        direction: Option<&LanguageDirection>,
+        datatype: Option<&NamedNode>,
+    ) -> Result<Self, LiteralError> {
+        check_parts(language.unwrap_or(""), direction, datatype)?;
+
+        Ok(Self::new(value, language, direction, datatype))
+    }
+
+    /// Reports the first inconsistency between language, direction and
+    /// datatype, e.g. for literals created through [`Literal::new`].
+    pub fn check(&self) -> Result<(), LiteralError> {
+        check_parts(&self.language, self.direction(), Some(self.datatype()))
+    }
+
     pub fn language(&self) -> &str {
         &self.language
     }
@@ -65,6 +88,15 @@ impl PartialEq for Literal {
     }
 }
 
+impl Hash for Literal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value().hash(state);
+        self.language().hash(state);
+        self.direction().hash(state);
+        self.datatype().hash(state);
+    }
+}
+
 impl PartialEq<Term> for Literal {
     fn eq(&self, other: &Term) -> bool {
         match other {
@@ -105,7 +137,7 @@ impl TermLike for Literal {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum LanguageDirection {
     LeftToRight,
     RightToLeft,
@@ -120,6 +152,113 @@ impl Display for LanguageDirection {
     }
 }
 
+impl FromStr for LanguageDirection {
+    type Err = ParseLanguageDirectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ltr" => Ok(LanguageDirection::LeftToRight),
+            "rtl" => Ok(LanguageDirection::RightToLeft),
+            _ => Err(ParseLanguageDirectionError(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseLanguageDirectionError(String);
+
+impl Display for ParseLanguageDirectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid language direction \"{}\", expected \"ltr\" or \"rtl\"",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseLanguageDirectionError {}
+
+fn check_parts(
+    language: &str,
+    direction: Option<&LanguageDirection>,
+    datatype: Option<&NamedNode>,
+) -> Result<(), LiteralError> {
+    if language.is_empty() {
+        if direction.is_some() {
+            return Err(LiteralError::DirectionWithoutLanguage);
+        }
+
+        return match datatype {
+            Some(d) if *d == *rdf::langString || *d == *rdf::dirLangString => {
+                Err(LiteralError::LanguageDatatypeWithoutLanguage(d.clone()))
+            }
+            _ => Ok(()),
+        };
+    }
+
+    if !is_valid_language_tag(language) {
+        return Err(LiteralError::InvalidLanguage(language.to_owned()));
+    }
+
+    let expected: &NamedNode = if direction.is_some() {
+        &rdf::dirLangString
+    } else {
+        &rdf::langString
+    };
+
+    match datatype {
+        Some(d) if d != expected => Err(LiteralError::DatatypeWithLanguage(d.clone())),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LiteralError {
+    InvalidLanguage(String),
+    DirectionWithoutLanguage,
+    DatatypeWithLanguage(NamedNode),
+    LanguageDatatypeWithoutLanguage(NamedNode),
+}
+
+impl Display for LiteralError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LiteralError::InvalidLanguage(language) => {
+                write!(f, "invalid language tag \"{language}\"")
+            }
+            LiteralError::DirectionWithoutLanguage => {
+                write!(f, "a language direction requires a language")
+            }
+            LiteralError::DatatypeWithLanguage(datatype) => write!(
+                f,
+                "datatype <{}> contradicts the given language",
+                datatype.value()
+            ),
+            LiteralError::LanguageDatatypeWithoutLanguage(datatype) => {
+                write!(f, "datatype <{}> requires a language", datatype.value())
+            }
+        }
+    }
+}
+
+impl Error for LiteralError {}
+
+/// Checks the `LANGTAG` shape shared by Turtle, N-Triples and SPARQL, i.e.
+/// `[a-zA-Z]{1,8}` followed by any number of `-[a-zA-Z0-9]{1,8}` subtags.
+pub fn is_valid_language_tag(language: &str) -> bool {
+    let mut subtags = language.split('-');
+
+    let primary_valid = subtags.next().is_some_and(|primary| {
+        (1..=8).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_alphabetic())
+    });
+
+    primary_valid
+        && subtags.all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,6 +318,110 @@ mod tests {
         assert_ne!(data.term_literal_bar, data.literal_foo_2);
     }
 
+    #[test]
+    fn strict_construction_accepts_consistent_literals() {
+        let lang_string = rdf::langString.clone();
+        let dir_lang_string = rdf::dirLangString.clone();
+
+        assert!(Literal::try_new("foo", None, None, None).is_ok());
+        assert!(Literal::try_new("foo", None, None, Some(&NamedNode::new("foo"))).is_ok());
+        assert!(Literal::try_new("foo", Some("en"), None, None).is_ok());
+        assert!(Literal::try_new("foo", Some("en"), None, Some(&lang_string)).is_ok());
+        assert!(
+            Literal::try_new(
+                "foo",
+                Some("en"),
+                Some(&LanguageDirection::LeftToRight),
+                Some(&dir_lang_string)
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn strict_construction_rejects_contradictions() {
+        let lang_string = rdf::langString.clone();
+        let dir_lang_string = rdf::dirLangString.clone();
+
+        assert_eq!(
+            Literal::try_new("foo", Some("en"), None, Some(&NamedNode::new("foo"))),
+            Err(LiteralError::DatatypeWithLanguage(NamedNode::new("foo")))
+        );
+        assert_eq!(
+            Literal::try_new(
+                "foo",
+                Some("en"),
+                Some(&LanguageDirection::LeftToRight),
+                Some(&lang_string)
+            ),
+            Err(LiteralError::DatatypeWithLanguage(lang_string.clone()))
+        );
+        assert_eq!(
+            Literal::try_new("foo", None, None, Some(&lang_string)),
+            Err(LiteralError::LanguageDatatypeWithoutLanguage(
+                lang_string.clone()
+            ))
+        );
+        assert_eq!(
+            Literal::try_new("foo", None, None, Some(&dir_lang_string)),
+            Err(LiteralError::LanguageDatatypeWithoutLanguage(
+                dir_lang_string
+            ))
+        );
+        assert_eq!(
+            Literal::try_new("foo", None, Some(&LanguageDirection::RightToLeft), None),
+            Err(LiteralError::DirectionWithoutLanguage)
+        );
+        assert_eq!(
+            Literal::try_new("foo", Some("e n"), None, None),
+            Err(LiteralError::InvalidLanguage("e n".to_owned()))
+        );
+    }
+
+    #[test]
+    fn check_finds_lenient_inconsistencies() {
+        let lang_string = rdf::langString.clone();
+
+        assert_eq!(
+            Literal::new("foo", Some("en"), None, Some(&NamedNode::new("foo"))).check(),
+            Ok(())
+        );
+        assert_eq!(
+            Literal::new("foo", None, None, Some(&lang_string)).check(),
+            Err(LiteralError::LanguageDatatypeWithoutLanguage(lang_string))
+        );
+        assert_eq!(
+            Literal::new("foo", None, Some(&LanguageDirection::LeftToRight), None).check(),
+            Err(LiteralError::DirectionWithoutLanguage)
+        );
+    }
+
+    #[test]
+    fn directions_parse() {
+        assert_eq!("ltr".parse(), Ok(LanguageDirection::LeftToRight));
+        assert_eq!("rtl".parse(), Ok(LanguageDirection::RightToLeft));
+        assert!("RTL".parse::<LanguageDirection>().is_err());
+        assert_eq!(
+            LanguageDirection::RightToLeft
+                .to_string()
+                .parse::<LanguageDirection>(),
+            Ok(LanguageDirection::RightToLeft)
+        );
+    }
+
+    #[test]
+    fn language_tags_validate() {
+        assert!(is_valid_language_tag("en"));
+        assert!(is_valid_language_tag("en-GB"));
+        assert!(is_valid_language_tag("zh-Hant-TW"));
+        assert!(is_valid_language_tag("de-1996"));
+        assert!(!is_valid_language_tag(""));
+        assert!(!is_valid_language_tag("1en"));
+        assert!(!is_valid_language_tag("en-"));
+        assert!(!is_valid_language_tag("en_GB"));
+        assert!(!is_valid_language_tag("abcdefghi"));
+    }
+
     #[test]
     fn term_inequality_works() {
         let data = equality_setup();
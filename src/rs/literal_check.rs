@@ -0,0 +1,111 @@
+use crate::rs::literal::{Literal, LiteralError};
+use crate::rs::quad::Quad;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_subject::QuadSubject;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LiteralInconsistency<'a> {
+    pub quad: &'a Quad,
+    pub literal: &'a Literal,
+    pub error: LiteralError,
+}
+
+/// Scans quads (including quads nested as subjects) for literals whose
+/// language, direction and datatype contradict each other.
+pub fn check_quads<'a>(quads: impl IntoIterator<Item = &'a Quad>) -> Vec<LiteralInconsistency<'a>> {
+    let mut inconsistencies = Vec::new();
+
+    for quad in quads {
+        check_quad(quad, quad, &mut inconsistencies);
+    }
+
+    inconsistencies
+}
+
+fn check_quad<'a>(
+    reported: &'a Quad,
+    quad: &'a Quad,
+    inconsistencies: &mut Vec<LiteralInconsistency<'a>>,
+) {
+    if let QuadSubject::Quad(nested) = quad.subject() {
+        check_quad(reported, nested, inconsistencies);
+    }
+
+    if let QuadObject::Literal(literal) = quad.object()
+        && let Err(error) = literal.check()
+    {
+        inconsistencies.push(LiteralInconsistency {
+            quad: reported,
+            literal,
+            error,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::literal::LanguageDirection;
+    use crate::rs::named_node::NamedNode;
+    use crate::rs::quad_predicate::QuadPredicate;
+
+    fn quad_with_object(subject: QuadSubject, literal: Literal) -> Quad {
+        Quad::new(
+            &subject,
+            &QuadPredicate::NamedNode(NamedNode::new("p")),
+            &QuadObject::Literal(literal),
+            None,
+        )
+    }
+
+    #[test]
+    fn consistent_quads_pass() {
+        let quads = vec![
+            quad_with_object(
+                QuadSubject::NamedNode(NamedNode::new("s")),
+                Literal::new("foo", Some("en"), None, None),
+            ),
+            quad_with_object(
+                QuadSubject::NamedNode(NamedNode::new("s")),
+                Literal::new("foo", None, None, None),
+            ),
+        ];
+
+        assert!(check_quads(&quads).is_empty());
+    }
+
+    #[test]
+    fn inconsistent_literals_are_reported() {
+        let lang_string = NamedNode::new("http://www.w3.org/1999/02/22-rdf-syntax-ns#langString");
+        let bad_literal = Literal::new("foo", None, None, Some(&lang_string));
+        let nested = quad_with_object(
+            QuadSubject::NamedNode(NamedNode::new("s")),
+            Literal::new("bar", None, Some(&LanguageDirection::LeftToRight), None),
+        );
+        let quads = vec![
+            quad_with_object(
+                QuadSubject::NamedNode(NamedNode::new("s")),
+                bad_literal.clone(),
+            ),
+            quad_with_object(
+                QuadSubject::Quad(Box::new(nested.clone())),
+                Literal::new("baz", None, None, None),
+            ),
+        ];
+
+        let inconsistencies = check_quads(&quads);
+
+        assert_eq!(inconsistencies.len(), 2);
+        assert_eq!(inconsistencies[0].quad, &quads[0]);
+        assert_eq!(inconsistencies[0].literal, &bad_literal);
+        assert_eq!(
+            inconsistencies[0].error,
+            LiteralError::LanguageDatatypeWithoutLanguage(lang_string)
+        );
+        assert_eq!(inconsistencies[1].quad, &quads[1]);
+        assert_eq!(
+            inconsistencies[1].error,
+            LiteralError::DirectionWithoutLanguage
+        );
+    }
+}
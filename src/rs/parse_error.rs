@@ -0,0 +1,78 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::io;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SyntaxError {
+    message: String,
+    line: usize,
+    column: usize,
+}
+
+impl SyntaxError {
+    pub fn new(message: &str, line: usize, column: usize) -> Self {
+        Self {
+            message: message.to_owned(),
+            line,
+            column,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// 1-based line of the offending input.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// 1-based column (in characters) of the offending input.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for SyntaxError {}
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    Syntax(SyntaxError),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Io(error) => write!(f, "{error}"),
+            ParseError::Syntax(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(error) => Some(error),
+            ParseError::Syntax(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(error: io::Error) -> Self {
+        ParseError::Io(error)
+    }
+}
+
+impl From<SyntaxError> for ParseError {
+    fn from(error: SyntaxError) -> Self {
+        ParseError::Syntax(error)
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::BTreeMap;
+
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::variable::Variable;
+
+/// Terms bound to variables, keyed by variable name.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Bindings {
+    terms: BTreeMap<String, Term>,
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        Self {
+            terms: BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Term> {
+        self.terms.get(name)
+    }
+
+    pub fn get_variable(&self, variable: &Variable) -> Option<&Term> {
+        self.get(variable.value())
+    }
+
+    /// Binds `variable` to `term` unless it is already bound to a different
+    /// term, in which case `false` is returned and nothing changes.
+    pub fn bind(&mut self, variable: &Variable, term: &Term) -> bool {
+        match self.terms.get(variable.value()) {
+            Some(bound) => bound == term,
+            None => {
+                self.terms.insert(variable.value().to_owned(), term.clone());
+                true
+            }
+        }
+    }
+
+    pub fn remove(&mut self, variable: &Variable) -> Option<Term> {
+        self.terms.remove(variable.value())
+    }
+
+    pub fn contains(&self, variable: &Variable) -> bool {
+        self.terms.contains_key(variable.value())
+    }
+
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Variable, &Term)> {
+        self.terms
+            .iter()
+            .map(|(name, term)| (Variable::new(name), term))
+    }
+
+    pub fn variables(&self) -> impl Iterator<Item = Variable> {
+        self.terms.keys().map(|name| Variable::new(name))
+    }
+
+    /// Whether both bindings agree on every variable they share.
+    pub fn is_compatible(&self, other: &Bindings) -> bool {
+        other
+            .terms
+            .iter()
+            .all(|(name, term)| self.terms.get(name).is_none_or(|bound| bound == term))
+    }
+
+    /// Combines compatible bindings, see [`Bindings::is_compatible`].
+    pub fn merge(&self, other: &Bindings) -> Option<Bindings> {
+        if !self.is_compatible(other) {
+            return None;
+        }
+
+        let mut merged = self.clone();
+        for (name, term) in &other.terms {
+            merged
+                .terms
+                .entry(name.clone())
+                .or_insert_with(|| term.clone());
+        }
+
+        Some(merged)
+    }
+}
+
+impl FromIterator<(Variable, Term)> for Bindings {
+    fn from_iter<T: IntoIterator<Item = (Variable, Term)>>(iter: T) -> Self {
+        Self {
+            terms: iter
+                .into_iter()
+                .map(|(variable, term)| (variable.value().to_owned(), term))
+                .collect(),
+        }
+    }
+}
+
+/// Matches a `pattern` whose positions may hold [`Variable`]s against a
+/// concrete `quad`.
+///
+/// Every other term of the pattern, including the default graph, has to be
+/// equal to the term at the same position of `quad`. A variable occurring
+/// several times has to be bound to the same term everywhere.
+pub fn match_quad(pattern: &Quad, quad: &Quad) -> Option<Bindings> {
+    match_quad_with(pattern, quad, &Bindings::new())
+}
+
+/// Like [`match_quad`], extending (and respecting) existing `bindings`.
+pub fn match_quad_with(pattern: &Quad, quad: &Quad, bindings: &Bindings) -> Option<Bindings> {
+    let mut bindings = bindings.clone();
+
+    if match_into(pattern, quad, &mut bindings) {
+        Some(bindings)
+    } else {
+        None
+    }
+}
+
+fn match_into(pattern: &Quad, quad: &Quad, bindings: &mut Bindings) -> bool {
+    match_subject(pattern.subject(), quad.subject(), bindings)
+        && match_term(
+            as_variable_predicate(pattern.predicate()),
+            pattern.predicate(),
+            quad.predicate(),
+            bindings,
+        )
+        && match_term(
+            as_variable_object(pattern.object()),
+            pattern.object(),
+            quad.object(),
+            bindings,
+        )
+        && match_term(
+            as_variable_graph(pattern.graph()),
+            pattern.graph(),
+            quad.graph(),
+            bindings,
+        )
+}
+
+fn match_subject(pattern: &QuadSubject, subject: &QuadSubject, bindings: &mut Bindings) -> bool {
+    match (pattern, subject) {
+        (QuadSubject::Variable(variable), subject) => bindings.bind(variable, &subject.to_term()),
+        (QuadSubject::Quad(pattern), QuadSubject::Quad(quad)) => {
+            match_into(pattern, quad, bindings)
+        }
+        (pattern, subject) => pattern == subject,
+    }
+}
+
+fn match_term<T: TermLike + PartialEq>(
+    variable: Option<&Variable>,
+    pattern: &T,
+    term: &T,
+    bindings: &mut Bindings,
+) -> bool {
+    match variable {
+        Some(variable) => bindings.bind(variable, &term.to_term()),
+        None => pattern == term,
+    }
+}
+
+fn as_variable_predicate(predicate: &QuadPredicate) -> Option<&Variable> {
+    match predicate {
+        QuadPredicate::Variable(variable) => Some(variable),
+        _ => None,
+    }
+}
+
+fn as_variable_object(object: &QuadObject) -> Option<&Variable> {
+    match object {
+        QuadObject::Variable(variable) => Some(variable),
+        _ => None,
+    }
+}
+
+fn as_variable_graph(graph: &QuadGraph) -> Option<&Variable> {
+    match graph {
+        QuadGraph::Variable(variable) => Some(variable),
+        _ => None,
+    }
+}
+
+/// Replaces the bound variables of `pattern` by their terms. Variables bound
+/// to terms that cannot appear at their position are left in place.
+pub fn substitute(pattern: &Quad, bindings: &Bindings) -> Quad {
+    let subject = match pattern.subject() {
+        QuadSubject::Variable(variable) => bindings
+            .get_variable(variable)
+            .and_then(|term| QuadSubject::try_from(term.clone()).ok())
+            .unwrap_or_else(|| pattern.subject().clone()),
+        QuadSubject::Quad(quad) => QuadSubject::Quad(Box::new(substitute(quad, bindings))),
+        subject => subject.clone(),
+    };
+    let predicate = as_variable_predicate(pattern.predicate())
+        .and_then(|variable| bindings.get_variable(variable))
+        .and_then(|term| QuadPredicate::try_from(term.clone()).ok())
+        .unwrap_or_else(|| pattern.predicate().clone());
+    let object = as_variable_object(pattern.object())
+        .and_then(|variable| bindings.get_variable(variable))
+        .and_then(|term| QuadObject::try_from(term.clone()).ok())
+        .unwrap_or_else(|| pattern.object().clone());
+    let graph = as_variable_graph(pattern.graph())
+        .and_then(|variable| bindings.get_variable(variable))
+        .and_then(|term| QuadGraph::try_from(term.clone()).ok())
+        .unwrap_or_else(|| pattern.graph().clone());
+
+    Quad::new(&subject, &predicate, &object, Some(&graph))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::default_graph::DefaultGraph;
+    use crate::rs::literal::Literal;
+    use crate::rs::named_node::NamedNode;
+
+    fn nn(value: &str) -> NamedNode {
+        NamedNode::new(value)
+    }
+
+    fn var(name: &str) -> Variable {
+        Variable::new(name)
+    }
+
+    fn quad(subject: QuadSubject, predicate: QuadPredicate, object: QuadObject) -> Quad {
+        Quad::new(&subject, &predicate, &object, None)
+    }
+
+    #[test]
+    fn variables_bind_to_terms() {
+        let pattern = quad(
+            QuadSubject::Variable(var("s")),
+            QuadPredicate::NamedNode(nn("p")),
+            QuadObject::Variable(var("o")),
+        );
+        let data = quad(
+            QuadSubject::NamedNode(nn("a")),
+            QuadPredicate::NamedNode(nn("p")),
+            QuadObject::Literal(Literal::new("b", None, None, None)),
+        );
+
+        let bindings = match_quad(&pattern, &data).unwrap();
+
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(bindings.get("s"), Some(&Term::NamedNode(nn("a"))));
+        assert_eq!(
+            bindings.get_variable(&var("o")),
+            Some(&Term::Literal(Literal::new("b", None, None, None)))
+        );
+        assert_eq!(substitute(&pattern, &bindings), data);
+    }
+
+    #[test]
+    fn constants_have_to_match() {
+        let pattern = quad(
+            QuadSubject::Variable(var("s")),
+            QuadPredicate::NamedNode(nn("p")),
+            QuadObject::NamedNode(nn("o")),
+        );
+        let data = quad(
+            QuadSubject::NamedNode(nn("a")),
+            QuadPredicate::NamedNode(nn("q")),
+            QuadObject::NamedNode(nn("o")),
+        );
+
+        assert_eq!(match_quad(&pattern, &data), None);
+
+        let named_graph_data = Quad::new(
+            &QuadSubject::NamedNode(nn("a")),
+            &QuadPredicate::NamedNode(nn("p")),
+            &QuadObject::NamedNode(nn("o")),
+            Some(&QuadGraph::NamedNode(nn("g"))),
+        );
+
+        assert_eq!(match_quad(&pattern, &named_graph_data), None);
+    }
+
+    #[test]
+    fn repeated_variables_bind_consistently() {
+        let pattern = quad(
+            QuadSubject::Variable(var("x")),
+            QuadPredicate::NamedNode(nn("p")),
+            QuadObject::Variable(var("x")),
+        );
+        let reflexive = quad(
+            QuadSubject::NamedNode(nn("a")),
+            QuadPredicate::NamedNode(nn("p")),
+            QuadObject::NamedNode(nn("a")),
+        );
+        let other = quad(
+            QuadSubject::NamedNode(nn("a")),
+            QuadPredicate::NamedNode(nn("p")),
+            QuadObject::NamedNode(nn("b")),
+        );
+
+        assert!(match_quad(&pattern, &reflexive).is_some());
+        assert_eq!(match_quad(&pattern, &other), None);
+
+        let existing: Bindings = [(var("x"), Term::NamedNode(nn("b")))].into_iter().collect();
+
+        assert_eq!(match_quad_with(&pattern, &reflexive, &existing), None);
+    }
+
+    #[test]
+    fn graph_variables_bind_to_graphs() {
+        let pattern = Quad::new(
+            &QuadSubject::Variable(var("s")),
+            &QuadPredicate::Variable(var("p")),
+            &QuadObject::Variable(var("o")),
+            Some(&QuadGraph::Variable(var("g"))),
+        );
+        let data = quad(
+            QuadSubject::NamedNode(nn("a")),
+            QuadPredicate::NamedNode(nn("p")),
+            QuadObject::NamedNode(nn("o")),
+        );
+
+        let bindings = match_quad(&pattern, &data).unwrap();
+
+        assert_eq!(
+            bindings.get("g"),
+            Some(&Term::DefaultGraph(DefaultGraph::new()))
+        );
+    }
+
+    #[test]
+    fn nested_triple_terms_match() {
+        let pattern = quad(
+            QuadSubject::Quad(Box::new(quad(
+                QuadSubject::Variable(var("s")),
+                QuadPredicate::NamedNode(nn("p")),
+                QuadObject::Variable(var("o")),
+            ))),
+            QuadPredicate::NamedNode(nn("source")),
+            QuadObject::Variable(var("s")),
+        );
+        let data = quad(
+            QuadSubject::Quad(Box::new(quad(
+                QuadSubject::NamedNode(nn("a")),
+                QuadPredicate::NamedNode(nn("p")),
+                QuadObject::NamedNode(nn("b")),
+            ))),
+            QuadPredicate::NamedNode(nn("source")),
+            QuadObject::NamedNode(nn("a")),
+        );
+        let mismatching = quad(
+            data.subject().clone(),
+            QuadPredicate::NamedNode(nn("source")),
+            QuadObject::NamedNode(nn("c")),
+        );
+
+        let bindings = match_quad(&pattern, &data).unwrap();
+
+        assert_eq!(bindings.get("s"), Some(&Term::NamedNode(nn("a"))));
+        assert_eq!(bindings.get("o"), Some(&Term::NamedNode(nn("b"))));
+        assert_eq!(match_quad(&pattern, &mismatching), None);
+        assert_eq!(substitute(&pattern, &bindings), data);
+    }
+
+    #[test]
+    fn bindings_merge_when_compatible() {
+        let a: Bindings = [(var("x"), Term::NamedNode(nn("a")))].into_iter().collect();
+        let b: Bindings = [
+            (var("x"), Term::NamedNode(nn("a"))),
+            (var("y"), Term::NamedNode(nn("b"))),
+        ]
+        .into_iter()
+        .collect();
+        let c: Bindings = [(var("x"), Term::NamedNode(nn("c")))].into_iter().collect();
+
+        assert_eq!(a.merge(&b), Some(b.clone()));
+        assert_eq!(a.merge(&c), None);
+    }
+}
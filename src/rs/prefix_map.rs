@@ -0,0 +1,440 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Display;
+
+use serde_json::{Map, Value};
+
+use crate::rs::named_node::NamedNode;
+use crate::rs::term_like::TermLike;
+use crate::rs::turtle::{is_pn_chars, is_pn_chars_base, is_pn_chars_u, is_pn_local_escapable};
+use crate::rs::vocab::{dcterms, foaf, owl, prov, rdf, rdfs, sh, skos, xsd};
+
+/// Maps prefixes to namespaces to expand CURIEs such as `ex:foo` into
+/// [`NamedNode`]s and to compact [`NamedNode`]s back into CURIEs.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PrefixMap {
+    prefixes: BTreeMap<String, String>,
+}
+
+impl PrefixMap {
+    pub fn new() -> Self {
+        Self {
+            prefixes: BTreeMap::new(),
+        }
+    }
+
+    /// A map preloaded with the vocabularies of [`crate::rs::vocab`].
+    pub fn with_common_prefixes() -> Self {
+        let mut map = Self::new();
+
+        for (prefix, namespace) in [
+            (rdf::PREFIX, rdf::NAMESPACE),
+            (rdfs::PREFIX, rdfs::NAMESPACE),
+            (xsd::PREFIX, xsd::NAMESPACE),
+            (owl::PREFIX, owl::NAMESPACE),
+            (sh::PREFIX, sh::NAMESPACE),
+            (skos::PREFIX, skos::NAMESPACE),
+            (dcterms::PREFIX, dcterms::NAMESPACE),
+            (foaf::PREFIX, foaf::NAMESPACE),
+            (prov::PREFIX, prov::NAMESPACE),
+        ] {
+            map.prefixes.insert(prefix.to_owned(), namespace.to_owned());
+        }
+
+        map
+    }
+
+    /// Adds or replaces a prefix, returning the namespace it replaced.
+    pub fn insert(&mut self, prefix: &str, namespace: &str) -> Result<Option<String>, PrefixError> {
+        if !is_valid_prefix(prefix) {
+            return Err(PrefixError::InvalidPrefix(prefix.to_owned()));
+        }
+
+        Ok(self
+            .prefixes
+            .insert(prefix.to_owned(), namespace.to_owned()))
+    }
+
+    pub fn remove(&mut self, prefix: &str) -> Option<String> {
+        self.prefixes.remove(prefix)
+    }
+
+    pub fn get(&self, prefix: &str) -> Option<&str> {
+        self.prefixes.get(prefix).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.prefixes
+            .iter()
+            .map(|(prefix, namespace)| (prefix.as_str(), namespace.as_str()))
+    }
+
+    /// Expands a CURIE like `ex:foo` (with Turtle local name escapes such as
+    /// `ex:a\,b`) into a [`NamedNode`].
+    pub fn expand(&self, curie: &str) -> Result<NamedNode, PrefixError> {
+        let Some((prefix, local)) = curie.split_once(':') else {
+            return Err(PrefixError::MissingColon(curie.to_owned()));
+        };
+
+        if !is_valid_prefix(prefix) {
+            return Err(PrefixError::InvalidPrefix(prefix.to_owned()));
+        }
+
+        let Some(namespace) = self.prefixes.get(prefix) else {
+            return Err(PrefixError::UnknownPrefix(prefix.to_owned()));
+        };
+
+        let Some(local) = unescape_local_name(local) else {
+            return Err(PrefixError::InvalidLocalName(local.to_owned()));
+        };
+
+        Ok(NamedNode::new(&format!("{namespace}{local}")))
+    }
+
+    /// Returns the shortest CURIE for `node`, escaping its local name where
+    /// needed, or `None` if no prefix applies.
+    pub fn compact(&self, node: &NamedNode) -> Option<String> {
+        let iri = node.value();
+
+        self.prefixes
+            .iter()
+            .filter_map(|(prefix, namespace)| {
+                let local = iri.strip_prefix(namespace.as_str())?;
+                Some(format!("{prefix}:{}", escape_local_name(local)?))
+            })
+            .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+    }
+
+    /// Reads the prefix definitions of a JSON-LD context (or of a document
+    /// with an `@context` entry). Remote context references are skipped.
+    pub fn from_json_ld_context(context: &Value) -> Result<Self, PrefixError> {
+        let mut map = Self::new();
+        map.import_json_ld_context(context)?;
+        Ok(map)
+    }
+
+    pub fn import_json_ld_context(&mut self, context: &Value) -> Result<(), PrefixError> {
+        match context {
+            Value::Object(object) => match object.get("@context") {
+                Some(inner) => self.import_json_ld_context(inner),
+                None => self.import_json_ld_definitions(object),
+            },
+            Value::Array(contexts) => {
+                for context in contexts {
+                    self.import_json_ld_context(context)?;
+                }
+                Ok(())
+            }
+            Value::String(_) | Value::Null => Ok(()),
+            _ => Err(PrefixError::InvalidContext(context.to_string())),
+        }
+    }
+
+    fn import_json_ld_definitions(
+        &mut self,
+        object: &Map<String, Value>,
+    ) -> Result<(), PrefixError> {
+        for (term, definition) in object {
+            if term.starts_with('@') || !is_valid_prefix(term) {
+                continue;
+            }
+
+            let namespace = match definition {
+                Value::String(iri) if ends_with_gen_delim(iri) => iri,
+                Value::Object(expanded) if expanded.get("@prefix") == Some(&Value::Bool(true)) => {
+                    match expanded.get("@id") {
+                        Some(Value::String(iri)) => iri,
+                        _ => return Err(PrefixError::InvalidContext(definition.to_string())),
+                    }
+                }
+                _ => continue,
+            };
+
+            self.prefixes.insert(term.clone(), namespace.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Writes the prefixes as a JSON-LD document `{"@context": {...}}`.
+    pub fn to_json_ld_context(&self) -> Value {
+        let definitions = self
+            .prefixes
+            .iter()
+            .map(|(prefix, namespace)| {
+                let definition = if ends_with_gen_delim(namespace) {
+                    Value::String(namespace.clone())
+                } else {
+                    let mut expanded = Map::new();
+                    expanded.insert("@id".to_owned(), Value::String(namespace.clone()));
+                    expanded.insert("@prefix".to_owned(), Value::Bool(true));
+                    Value::Object(expanded)
+                };
+                (prefix.clone(), definition)
+            })
+            .collect();
+
+        let mut document = Map::new();
+        document.insert("@context".to_owned(), Value::Object(definitions));
+        Value::Object(document)
+    }
+}
+
+impl<'a> FromIterator<(&'a str, &'a str)> for PrefixMap {
+    fn from_iter<T: IntoIterator<Item = (&'a str, &'a str)>>(iter: T) -> Self {
+        Self {
+            prefixes: iter
+                .into_iter()
+                .filter(|(prefix, _)| is_valid_prefix(prefix))
+                .map(|(prefix, namespace)| (prefix.to_owned(), namespace.to_owned()))
+                .collect(),
+        }
+    }
+}
+
+fn ends_with_gen_delim(iri: &str) -> bool {
+    iri.ends_with(['/', '#', ':', '?', '@', '[', ']'])
+}
+
+/// Checks `PN_PREFIX`, allowing the empty prefix.
+pub fn is_valid_prefix(prefix: &str) -> bool {
+    let mut chars = prefix.chars();
+
+    match chars.next() {
+        None => return true,
+        Some(c) if is_pn_chars_base(c) => {}
+        Some(_) => return false,
+    }
+
+    !prefix.ends_with('.') && chars.all(|c| is_pn_chars(c) || c == '.')
+}
+
+fn unescape_local_name(local: &str) -> Option<String> {
+    let mut value = String::with_capacity(local.len());
+    let mut chars = local.chars().peekable();
+    let mut first = true;
+    let mut last_was_dot = false;
+
+    while let Some(c) = chars.next() {
+        last_was_dot = false;
+        match c {
+            '\\' => value.push(chars.next().filter(|c| is_pn_local_escapable(*c))?),
+            '%' => {
+                value.push('%');
+                for _ in 0..2 {
+                    value.push(chars.next().filter(char::is_ascii_hexdigit)?);
+                }
+            }
+            c if is_pn_chars_u(c) || c == ':' || c.is_ascii_digit() => value.push(c),
+            c if !first && is_pn_chars(c) => value.push(c),
+            '.' if !first => {
+                last_was_dot = true;
+                value.push(c);
+            }
+            _ => return None,
+        }
+        first = false;
+    }
+
+    if last_was_dot { None } else { Some(value) }
+}
+
+fn escape_local_name(local: &str) -> Option<String> {
+    let mut escaped = String::with_capacity(local.len());
+    let chars: Vec<char> = local.chars().collect();
+
+    for (i, c) in chars.iter().copied().enumerate() {
+        let first = i == 0;
+        let last = i == chars.len() - 1;
+
+        match c {
+            '%' if chars.get(i + 1).is_some_and(char::is_ascii_hexdigit)
+                && chars.get(i + 2).is_some_and(char::is_ascii_hexdigit) =>
+            {
+                escaped.push(c)
+            }
+            c if is_pn_chars_u(c) || c == ':' || c.is_ascii_digit() => escaped.push(c),
+            c if !first && is_pn_chars(c) => escaped.push(c),
+            '.' if !first && !last => escaped.push(c),
+            c if is_pn_local_escapable(c) => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => return None,
+        }
+    }
+
+    Some(escaped)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PrefixError {
+    MissingColon(String),
+    InvalidPrefix(String),
+    UnknownPrefix(String),
+    InvalidLocalName(String),
+    InvalidContext(String),
+}
+
+impl Display for PrefixError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrefixError::MissingColon(curie) => write!(f, "\"{curie}\" is not a prefixed name"),
+            PrefixError::InvalidPrefix(prefix) => write!(f, "invalid prefix \"{prefix}\""),
+            PrefixError::UnknownPrefix(prefix) => write!(f, "unknown prefix \"{prefix}\""),
+            PrefixError::InvalidLocalName(local) => write!(f, "invalid local name \"{local}\""),
+            PrefixError::InvalidContext(context) => {
+                write!(f, "invalid JSON-LD context entry {context}")
+            }
+        }
+    }
+}
+
+impl Error for PrefixError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn curies_expand() {
+        let map = PrefixMap::with_common_prefixes();
+
+        assert_eq!(map.expand("rdf:type"), Ok(rdf::r#type.clone()));
+        assert_eq!(map.expand("xsd:string"), Ok(xsd::string.clone()));
+        assert_eq!(
+            map.expand("ex:foo"),
+            Err(PrefixError::UnknownPrefix("ex".to_owned()))
+        );
+        assert_eq!(
+            map.expand("foo"),
+            Err(PrefixError::MissingColon("foo".to_owned()))
+        );
+
+        let mut map = PrefixMap::new();
+        map.insert("ex", "http://example.org/").unwrap();
+        map.insert("", "http://example.org/default#").unwrap();
+
+        assert_eq!(
+            map.expand("ex:a\\,b%20c.d"),
+            Ok(NamedNode::new("http://example.org/a,b%20c.d"))
+        );
+        assert_eq!(
+            map.expand(":foo"),
+            Ok(NamedNode::new("http://example.org/default#foo"))
+        );
+        assert_eq!(map.expand("ex:"), Ok(NamedNode::new("http://example.org/")));
+        assert_eq!(
+            map.expand("ex:foo."),
+            Err(PrefixError::InvalidLocalName("foo.".to_owned()))
+        );
+        assert_eq!(
+            map.expand("ex:a b"),
+            Err(PrefixError::InvalidLocalName("a b".to_owned()))
+        );
+    }
+
+    #[test]
+    fn invalid_prefixes_are_rejected() {
+        let mut map = PrefixMap::new();
+
+        assert!(map.insert("ex.1", "http://example.org/").is_ok());
+        assert_eq!(
+            map.insert("1ex", "http://example.org/"),
+            Err(PrefixError::InvalidPrefix("1ex".to_owned()))
+        );
+        assert_eq!(
+            map.insert("ex.", "http://example.org/"),
+            Err(PrefixError::InvalidPrefix("ex.".to_owned()))
+        );
+        assert_eq!(
+            map.insert("_ex", "http://example.org/"),
+            Err(PrefixError::InvalidPrefix("_ex".to_owned()))
+        );
+    }
+
+    #[test]
+    fn iris_compact_to_shortest_curie() {
+        let mut map = PrefixMap::new();
+        map.insert("ex", "http://example.org/").unwrap();
+        map.insert("exns", "http://example.org/ns/").unwrap();
+
+        assert_eq!(
+            map.compact(&NamedNode::new("http://example.org/ns/foo")),
+            Some("exns:foo".to_owned())
+        );
+        assert_eq!(
+            map.compact(&NamedNode::new("http://example.org/a,b.")),
+            Some("ex:a\\,b\\.".to_owned())
+        );
+        assert_eq!(
+            map.compact(&NamedNode::new("http://example.org/a%20b")),
+            Some("ex:a%20b".to_owned())
+        );
+        assert_eq!(map.compact(&NamedNode::new("http://example.org/a b")), None);
+        assert_eq!(map.compact(&NamedNode::new("http://other.org/a")), None);
+    }
+
+    #[test]
+    fn compaction_round_trips() {
+        let map = PrefixMap::with_common_prefixes();
+
+        for iri in [
+            "http://www.w3.org/2002/07/owl#sameAs",
+            "http://purl.org/dc/terms/a/b?c=d",
+            "http://xmlns.com/foaf/0.1/-x~y.z",
+        ] {
+            let node = NamedNode::new(iri);
+            let curie = map.compact(&node).unwrap();
+
+            assert_eq!(map.expand(&curie), Ok(node), "{curie}");
+        }
+    }
+
+    #[test]
+    fn json_ld_contexts_import_and_export() {
+        let map = PrefixMap::from_json_ld_context(&json!({
+            "@context": [
+                "http://example.org/remote-context.jsonld",
+                {
+                    "@vocab": "http://example.org/vocab#",
+                    "ex": "http://example.org/",
+                    "name": "http://xmlns.com/foaf/0.1/name",
+                    "isbn": { "@id": "urn:isbn:", "@prefix": true },
+                    "knows": { "@id": "http://xmlns.com/foaf/0.1/knows", "@type": "@id" }
+                }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("ex"), Some("http://example.org/"));
+        assert_eq!(map.get("isbn"), Some("urn:isbn:"));
+
+        let mut map = PrefixMap::new();
+        map.insert("ex", "http://example.org/").unwrap();
+        map.insert("isbn", "urn:isbn").unwrap();
+
+        let context = map.to_json_ld_context();
+
+        assert_eq!(
+            context,
+            json!({
+                "@context": {
+                    "ex": "http://example.org/",
+                    "isbn": { "@id": "urn:isbn", "@prefix": true }
+                }
+            })
+        );
+        assert_eq!(PrefixMap::from_json_ld_context(&context), Ok(map));
+    }
+}
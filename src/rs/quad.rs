@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 use crate::rs::{term::Term, term_like::TermLike};
 
 use crate::rs::default_graph::DefaultGraph;
@@ -64,6 +66,15 @@ impl PartialEq for Quad {
     }
 }
 
+impl Hash for Quad {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.subject.hash(state);
+        self.predicate.hash(state);
+        self.object.hash(state);
+        self.graph.hash(state);
+    }
+}
+
 impl PartialEq<Term> for Quad {
     fn eq(&self, other: &Term) -> bool {
         match other {
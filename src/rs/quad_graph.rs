@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 use crate::rs::{term::Term, term_like::TermLike};
 
 use crate::rs::blank_node::BlankNode;
@@ -40,6 +42,18 @@ impl PartialEq for QuadGraph {
     }
 }
 
+impl Hash for QuadGraph {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            QuadGraph::DefaultGraph(term) => term.hash(state),
+            QuadGraph::NamedNode(term) => term.hash(state),
+            QuadGraph::BlankNode(term) => term.hash(state),
+            QuadGraph::Variable(term) => term.hash(state),
+        }
+    }
+}
+
 impl PartialEq<Term> for QuadGraph {
     fn eq(&self, other: &Term) -> bool {
         match self {
@@ -98,3 +112,41 @@ impl TermLike for QuadGraph {
         }
     }
 }
+
+impl TryFrom<Term> for QuadGraph {
+    type Error = Term;
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        match term {
+            Term::DefaultGraph(term) => Ok(QuadGraph::DefaultGraph(term)),
+            Term::NamedNode(term) => Ok(QuadGraph::NamedNode(term)),
+            Term::BlankNode(term) => Ok(QuadGraph::BlankNode(term)),
+            Term::Variable(term) => Ok(QuadGraph::Variable(term)),
+            term => Err(term),
+        }
+    }
+}
+
+impl From<DefaultGraph> for QuadGraph {
+    fn from(term: DefaultGraph) -> Self {
+        QuadGraph::DefaultGraph(term)
+    }
+}
+
+impl From<NamedNode> for QuadGraph {
+    fn from(term: NamedNode) -> Self {
+        QuadGraph::NamedNode(term)
+    }
+}
+
+impl From<BlankNode> for QuadGraph {
+    fn from(term: BlankNode) -> Self {
+        QuadGraph::BlankNode(term)
+    }
+}
+
+impl From<Variable> for QuadGraph {
+    fn from(term: Variable) -> Self {
+        QuadGraph::Variable(term)
+    }
+}
@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 use crate::rs::{term::Term, term_like::TermLike};
 
 use crate::rs::blank_node::BlankNode;
@@ -40,6 +42,18 @@ impl PartialEq for QuadObject {
     }
 }
 
+impl Hash for QuadObject {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            QuadObject::NamedNode(term) => term.hash(state),
+            QuadObject::Literal(term) => term.hash(state),
+            QuadObject::BlankNode(term) => term.hash(state),
+            QuadObject::Variable(term) => term.hash(state),
+        }
+    }
+}
+
 impl PartialEq<Term> for QuadObject {
     fn eq(&self, other: &Term) -> bool {
         match self {
@@ -98,3 +112,41 @@ impl TermLike for QuadObject {
         }
     }
 }
+
+impl TryFrom<Term> for QuadObject {
+    type Error = Term;
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        match term {
+            Term::NamedNode(term) => Ok(QuadObject::NamedNode(term)),
+            Term::Literal(term) => Ok(QuadObject::Literal(term)),
+            Term::BlankNode(term) => Ok(QuadObject::BlankNode(term)),
+            Term::Variable(term) => Ok(QuadObject::Variable(term)),
+            term => Err(term),
+        }
+    }
+}
+
+impl From<NamedNode> for QuadObject {
+    fn from(term: NamedNode) -> Self {
+        QuadObject::NamedNode(term)
+    }
+}
+
+impl From<Literal> for QuadObject {
+    fn from(term: Literal) -> Self {
+        QuadObject::Literal(term)
+    }
+}
+
+impl From<BlankNode> for QuadObject {
+    fn from(term: BlankNode) -> Self {
+        QuadObject::BlankNode(term)
+    }
+}
+
+impl From<Variable> for QuadObject {
+    fn from(term: Variable) -> Self {
+        QuadObject::Variable(term)
+    }
+}
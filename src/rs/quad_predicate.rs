@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 use crate::rs::{term::Term, term_like::TermLike};
 
 use crate::rs::named_node::NamedNode;
@@ -28,6 +30,16 @@ impl PartialEq for QuadPredicate {
     }
 }
 
+impl Hash for QuadPredicate {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            QuadPredicate::NamedNode(term) => term.hash(state),
+            QuadPredicate::Variable(term) => term.hash(state),
+        }
+    }
+}
+
 impl PartialEq<Term> for QuadPredicate {
     fn eq(&self, other: &Term) -> bool {
         match self {
@@ -76,3 +88,27 @@ impl TermLike for QuadPredicate {
         }
     }
 }
+
+impl TryFrom<Term> for QuadPredicate {
+    type Error = Term;
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        match term {
+            Term::NamedNode(term) => Ok(QuadPredicate::NamedNode(term)),
+            Term::Variable(term) => Ok(QuadPredicate::Variable(term)),
+            term => Err(term),
+        }
+    }
+}
+
+impl From<NamedNode> for QuadPredicate {
+    fn from(term: NamedNode) -> Self {
+        QuadPredicate::NamedNode(term)
+    }
+}
+
+impl From<Variable> for QuadPredicate {
+    fn from(term: Variable) -> Self {
+        QuadPredicate::Variable(term)
+    }
+}
@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 use crate::rs::quad::Quad;
 use crate::rs::{term::Term, term_like::TermLike};
 
@@ -40,6 +42,18 @@ impl PartialEq for QuadSubject {
     }
 }
 
+impl Hash for QuadSubject {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            QuadSubject::NamedNode(term) => term.hash(state),
+            QuadSubject::BlankNode(term) => term.hash(state),
+            QuadSubject::Variable(term) => term.hash(state),
+            QuadSubject::Quad(term) => term.hash(state),
+        }
+    }
+}
+
 impl PartialEq<Term> for QuadSubject {
     fn eq(&self, other: &Term) -> bool {
         match self {
@@ -98,3 +112,41 @@ impl TermLike for QuadSubject {
         }
     }
 }
+
+impl TryFrom<Term> for QuadSubject {
+    type Error = Term;
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        match term {
+            Term::NamedNode(term) => Ok(QuadSubject::NamedNode(term)),
+            Term::BlankNode(term) => Ok(QuadSubject::BlankNode(term)),
+            Term::Variable(term) => Ok(QuadSubject::Variable(term)),
+            Term::Quad(term) => Ok(QuadSubject::Quad(term)),
+            term => Err(term),
+        }
+    }
+}
+
+impl From<NamedNode> for QuadSubject {
+    fn from(term: NamedNode) -> Self {
+        QuadSubject::NamedNode(term)
+    }
+}
+
+impl From<BlankNode> for QuadSubject {
+    fn from(term: BlankNode) -> Self {
+        QuadSubject::BlankNode(term)
+    }
+}
+
+impl From<Variable> for QuadSubject {
+    fn from(term: Variable) -> Self {
+        QuadSubject::Variable(term)
+    }
+}
+
+impl From<Quad> for QuadSubject {
+    fn from(term: Quad) -> Self {
+        QuadSubject::Quad(Box::new(term))
+    }
+}
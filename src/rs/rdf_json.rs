@@ -0,0 +1,316 @@
+use std::io::{self, Read, Write};
+
+use serde_json::{Map, Value, json};
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::literal::{LanguageDirection, Literal};
+use crate::rs::named_node::NamedNode;
+use crate::rs::parse_error::{ParseError, SyntaxError};
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::vocab::xsd;
+
+/// Reads an RDF/JSON document, `{ subject: { predicate: [object...] } }`,
+/// into default graph quads.
+pub fn parse_str(input: &str) -> Result<Vec<Quad>, SyntaxError> {
+    let document: Value = serde_json::from_str(input)
+        .map_err(|error| SyntaxError::new(&error.to_string(), error.line(), error.column()))?;
+    let subjects = document
+        .as_object()
+        .ok_or_else(|| invalid("expected a JSON object"))?;
+
+    let mut quads = Vec::new();
+    for (subject, predicates) in subjects {
+        let subject = match subject.strip_prefix("_:") {
+            Some(label) => QuadSubject::BlankNode(BlankNode::new(label)),
+            None => QuadSubject::NamedNode(NamedNode::new(subject)),
+        };
+        let predicates = predicates
+            .as_object()
+            .ok_or_else(|| invalid("the predicates of a subject must be an object"))?;
+        for (predicate, objects) in predicates {
+            let predicate = QuadPredicate::NamedNode(NamedNode::new(predicate));
+            let objects = objects
+                .as_array()
+                .ok_or_else(|| invalid("the objects of a predicate must be an array"))?;
+            for object in objects {
+                let object = QuadObject::try_from(term_from_json(object)?)
+                    .map_err(|_| invalid("invalid object"))?;
+                quads.push(Quad::new(&subject, &predicate, &object, None));
+            }
+        }
+    }
+    Ok(quads)
+}
+
+pub fn parse_read(mut reader: impl Read) -> Result<Vec<Quad>, ParseError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    Ok(parse_str(&input)?)
+}
+
+/// Writes `quads` as one RDF/JSON document. The format has no named graphs,
+/// which fail with [`io::ErrorKind::InvalidInput`].
+pub fn serialize<'a>(
+    mut writer: impl Write,
+    quads: impl IntoIterator<Item = &'a Quad>,
+) -> io::Result<()> {
+    let mut subjects = Map::new();
+    for quad in quads {
+        if !matches!(quad.graph(), QuadGraph::DefaultGraph(_)) {
+            return Err(invalid_input("RDF/JSON cannot express named graphs"));
+        }
+        let subject = match quad.subject() {
+            QuadSubject::NamedNode(node) => node.value().to_owned(),
+            QuadSubject::BlankNode(node) => format!("_:{}", node.value()),
+            _ => {
+                return Err(invalid_input(
+                    "RDF/JSON subjects must be IRIs or blank nodes",
+                ));
+            }
+        };
+        let QuadPredicate::NamedNode(predicate) = quad.predicate() else {
+            return Err(invalid_input("RDF/JSON predicates must be IRIs"));
+        };
+        let object = term_to_json(&quad.object().to_term())
+            .ok_or_else(|| invalid_input("variables cannot be written as RDF/JSON"))?;
+
+        subjects
+            .entry(subject)
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .unwrap()
+            .entry(predicate.value())
+            .or_insert_with(|| Value::Array(Vec::new()))
+            .as_array_mut()
+            .unwrap()
+            .push(object);
+    }
+    serde_json::to_writer(&mut writer, &Value::Object(subjects))?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+/// The RDF/JSON object of `term`, with a `"triple"` type for triple terms.
+/// Variables and the default graph have none.
+pub fn term_to_json(term: &Term) -> Option<Value> {
+    let mut object = Map::new();
+    match term {
+        Term::NamedNode(named_node) => {
+            object.insert("type".to_owned(), json!("uri"));
+            object.insert("value".to_owned(), json!(named_node.value()));
+        }
+        Term::BlankNode(blank_node) => {
+            object.insert("type".to_owned(), json!("bnode"));
+            object.insert(
+                "value".to_owned(),
+                json!(format!("_:{}", blank_node.value())),
+            );
+        }
+        Term::Literal(literal) => {
+            object.insert("type".to_owned(), json!("literal"));
+            object.insert("value".to_owned(), json!(literal.value()));
+            if !literal.language().is_empty() {
+                object.insert("lang".to_owned(), json!(literal.language()));
+            } else if *literal.datatype() != *xsd::string {
+                object.insert("datatype".to_owned(), json!(literal.datatype().value()));
+            }
+            if let Some(direction) = literal.direction() {
+                object.insert("direction".to_owned(), json!(direction.to_string()));
+            }
+        }
+        Term::Quad(triple) => {
+            object.insert("type".to_owned(), json!("triple"));
+            object.insert(
+                "value".to_owned(),
+                json!({
+                    "subject": term_to_json(&triple.subject().to_term())?,
+                    "predicate": term_to_json(&triple.predicate().to_term())?,
+                    "object": term_to_json(&triple.object().to_term())?,
+                }),
+            );
+        }
+        Term::Variable(_) | Term::DefaultGraph(_) => return None,
+    }
+    Some(Value::Object(object))
+}
+
+pub fn term_from_json(term: &Value) -> Result<Term, SyntaxError> {
+    let field = |name: &str| term.get(name).and_then(Value::as_str);
+    let kind = field("type").ok_or_else(|| invalid("an RDF term needs a \"type\""))?;
+
+    if kind == "triple" {
+        let triple = term
+            .get("value")
+            .ok_or_else(|| invalid("a triple term needs a \"value\""))?;
+        let part = |name: &str| {
+            triple
+                .get(name)
+                .ok_or_else(|| invalid(&format!("a triple term needs a \"{name}\"")))
+                .and_then(term_from_json)
+        };
+        let subject = QuadSubject::try_from(part("subject")?)
+            .map_err(|_| invalid("invalid subject in triple term"))?;
+        let predicate = QuadPredicate::try_from(part("predicate")?)
+            .map_err(|_| invalid("invalid predicate in triple term"))?;
+        let object = QuadObject::try_from(part("object")?)
+            .map_err(|_| invalid("invalid object in triple term"))?;
+        return Ok(Term::Quad(Box::new(Quad::new(
+            &subject, &predicate, &object, None,
+        ))));
+    }
+
+    let value = field("value").ok_or_else(|| invalid("an RDF term needs a \"value\""))?;
+    match kind {
+        "uri" => Ok(NamedNode::new(value).to_term()),
+        "bnode" => Ok(BlankNode::new(value.strip_prefix("_:").unwrap_or(value)).to_term()),
+        "literal" => {
+            let direction = field("direction")
+                .map(|direction| {
+                    direction
+                        .parse::<LanguageDirection>()
+                        .map_err(|error| invalid(&error.to_string()))
+                })
+                .transpose()?;
+            let datatype = field("datatype").map(NamedNode::new);
+            Literal::try_new(value, field("lang"), direction.as_ref(), datatype.as_ref())
+                .map(|literal| literal.to_term())
+                .map_err(|error| invalid(&error.to_string()))
+        }
+        _ => Err(invalid(&format!("unknown RDF term type \"{kind}\""))),
+    }
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// serde_json does not keep positions in a parsed [`Value`], so structural
+/// errors point at the start of the document.
+fn invalid(message: &str) -> SyntaxError {
+    SyntaxError::new(message, 1, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::turtle::{self, TurtleSyntax};
+
+    #[test]
+    fn documents_parse() {
+        let quads = parse_str(
+            r#"{
+                "http://example.org/book": {
+                    "http://example.org/title": [
+                        { "type": "literal", "value": "Dune", "lang": "en", "direction": "ltr" },
+                        { "type": "literal", "value": "412",
+                          "datatype": "http://www.w3.org/2001/XMLSchema#integer" }
+                    ],
+                    "http://example.org/author": [ { "type": "bnode", "value": "_:a" } ]
+                },
+                "_:a": {
+                    "http://example.org/name": [ { "type": "literal", "value": "Frank" } ]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let expected = turtle::parse_str(
+            TurtleSyntax::Turtle,
+            r#"@prefix ex: <http://example.org/> .
+               _:a ex:name "Frank" .
+               ex:book ex:author _:a ; ex:title 412 ."#,
+            None,
+        )
+        .unwrap();
+        assert_eq!(quads.len(), 4);
+        let title = QuadObject::Literal(Literal::new(
+            "Dune",
+            Some("en"),
+            Some(&LanguageDirection::LeftToRight),
+            None,
+        ));
+        assert!(quads.iter().any(|quad| *quad.object() == title));
+        for quad in &expected {
+            assert!(quads.contains(quad), "missing {quad:?}");
+        }
+
+        let error =
+            parse_str(r#"{ "http://a": { "http://b": [ { "value": "x" } ] } }"#).unwrap_err();
+        assert_eq!(error.message(), "an RDF term needs a \"type\"");
+    }
+
+    #[test]
+    fn triple_terms_map_to_json() {
+        let json = serde_json::json!({ "type": "triple", "value": {
+            "subject": { "type": "uri", "value": "http://example.org/s" },
+            "predicate": { "type": "uri", "value": "http://example.org/p" },
+            "object": { "type": "literal", "value": "o", "lang": "en" }
+        } });
+        let term = term_from_json(&json).unwrap();
+        let Term::Quad(triple) = &term else {
+            panic!("expected a triple term, got {term:?}");
+        };
+        assert_eq!(
+            *triple.object(),
+            QuadObject::Literal(Literal::new("o", Some("en"), None, None))
+        );
+        assert_eq!(term_to_json(&term), Some(json));
+
+        for (json, message) in [
+            (
+                serde_json::json!({ "type": "literal", "value": "o", "lang": "e n" }),
+                "invalid language tag \"e n\"",
+            ),
+            (
+                serde_json::json!({ "type": "literal", "value": "o", "direction": "ltr" }),
+                "a language direction requires a language",
+            ),
+            (
+                serde_json::json!({ "type": "literal", "value": "1", "lang": "en",
+                                    "datatype": "http://www.w3.org/2001/XMLSchema#integer" }),
+                "datatype <http://www.w3.org/2001/XMLSchema#integer> contradicts the given language",
+            ),
+        ] {
+            assert_eq!(term_from_json(&json).unwrap_err().message(), message);
+        }
+    }
+
+    #[test]
+    fn serialized_documents_round_trip() {
+        let quads = turtle::parse_str(
+            TurtleSyntax::Turtle,
+            r#"@prefix ex: <http://example.org/> .
+               ex:book ex:title "Dune"@en--rtl, "Dune", 412 ;
+                   ex:author _:a .
+               _:a ex:name "Frank" ."#,
+            None,
+        )
+        .unwrap();
+        let mut output = Vec::new();
+        serialize(&mut output, &quads).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains(r#""direction":"rtl","lang":"en","type":"literal""#));
+        assert!(output.contains(r#"{"type":"bnode","value":"_:a"}"#));
+        let reparsed = parse_str(&output).unwrap();
+        assert_eq!(reparsed.len(), quads.len());
+        for quad in &quads {
+            assert!(reparsed.contains(quad));
+        }
+
+        let named = turtle::parse_str(
+            TurtleSyntax::NQuads,
+            "<http://a> <http://b> <http://c> <http://g> .\n",
+            None,
+        )
+        .unwrap();
+        let error = serialize(Vec::new(), &named).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
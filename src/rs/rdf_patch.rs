@@ -0,0 +1,538 @@
+//! [RDF Patch](https://afs.github.io/rdf-delta/rdf-patch.html): a log of
+//! changes to a dataset, one row per change.
+//!
+//! Rows are `H` headers, `TX`/`TC`/`TA` transaction boundaries, `PA`/`PD`
+//! prefix changes and `A`/`D` quad additions and deletions. Terms are written
+//! as in Turtle, with the prefixes declared by earlier `PA` rows. Blank node
+//! labels are matched against the labels of the dataset.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::rs::dataset::Dataset;
+use crate::rs::diff::{Diff, nquad};
+use crate::rs::parse_error::SyntaxError;
+use crate::rs::prefix_map::is_valid_prefix;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::turtle::{TurtleParser, TurtleSyntax, format_canonical_literal, format_iri};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PatchOperation {
+    Header(String, QuadObject),
+    TransactionBegin,
+    TransactionCommit,
+    TransactionAbort,
+    AddPrefix(String, String),
+    DeletePrefix(String),
+    Add(Quad),
+    Delete(Quad),
+}
+
+/// A sequence of patch operations whose transactions are balanced.
+///
+/// Displays in the RDF Patch text format, with full IRIs.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct RdfPatch {
+    operations: Vec<PatchOperation>,
+}
+
+impl RdfPatch {
+    /// Parses the RDF Patch text `input`.
+    pub fn parse(input: &str) -> Result<Self, SyntaxError> {
+        let mut operations = Vec::new();
+        let mut prefixes = BTreeMap::new();
+        let mut transaction = None;
+        let mut last_line = 0;
+
+        for (index, line) in input.lines().enumerate() {
+            let number = index + 1;
+            last_line = number;
+            let error = |message: &str, column: usize| SyntaxError::new(message, number, column);
+            let mut tokens = tokenize(line).map_err(|(message, column)| error(&message, column))?;
+            if tokens.last().is_some_and(|(_, token)| *token == ".") {
+                tokens.pop();
+            }
+            let Some(&(column, code)) = tokens.first() else {
+                continue;
+            };
+            let args = &tokens[1..];
+            let arity = |range: std::ops::RangeInclusive<usize>| {
+                if range.contains(&args.len()) {
+                    Ok(())
+                } else {
+                    Err(error(&format!("wrong number of terms for {code}"), column))
+                }
+            };
+
+            let operation = match code {
+                "H" => {
+                    arity(2..=2)?;
+                    let (value_column, value) = args[1];
+                    let value = parse_term(value, &prefixes, false)
+                        .map_err(|message| error(&message, value_column))?;
+                    let value = QuadObject::try_from(value)
+                        .map_err(|_| error("invalid header value", value_column))?;
+                    PatchOperation::Header(args[0].1.to_owned(), value)
+                }
+                "TX" | "TC" | "TA" => {
+                    arity(0..=0)?;
+                    match (code, transaction) {
+                        ("TX", None) => transaction = Some(number),
+                        ("TX", Some(_)) => return Err(error("nested transaction", column)),
+                        (_, None) => return Err(error("no transaction to end", column)),
+                        (_, Some(_)) => transaction = None,
+                    }
+                    match code {
+                        "TX" => PatchOperation::TransactionBegin,
+                        "TC" => PatchOperation::TransactionCommit,
+                        _ => PatchOperation::TransactionAbort,
+                    }
+                }
+                "PA" | "PD" => {
+                    arity(if code == "PA" { 2..=2 } else { 1..=2 })?;
+                    let (prefix_column, prefix) = args[0];
+                    let prefix = prefix.strip_suffix(':').unwrap_or(prefix);
+                    if !is_valid_prefix(prefix) {
+                        return Err(error("invalid prefix", prefix_column));
+                    }
+                    if code == "PD" {
+                        prefixes.remove(prefix);
+                        PatchOperation::DeletePrefix(prefix.to_owned())
+                    } else {
+                        let (namespace_column, namespace) = args[1];
+                        let Ok(Term::NamedNode(namespace)) =
+                            parse_term(namespace, &prefixes, false)
+                        else {
+                            return Err(error("expected a namespace IRI", namespace_column));
+                        };
+                        prefixes.insert(prefix.to_owned(), namespace.value().to_owned());
+                        PatchOperation::AddPrefix(prefix.to_owned(), namespace.value().to_owned())
+                    }
+                }
+                "A" | "D" => {
+                    arity(3..=4)?;
+                    let mut terms = Vec::new();
+                    for (position, &(term_column, token)) in args.iter().enumerate() {
+                        let term = parse_term(token, &prefixes, position == 0)
+                            .map_err(|message| error(&message, term_column))?;
+                        terms.push((term_column, term));
+                    }
+                    let invalid =
+                        |position: usize| error("invalid term for its position", terms[position].0);
+                    let subject =
+                        QuadSubject::try_from(terms[0].1.clone()).map_err(|_| invalid(0))?;
+                    let predicate =
+                        QuadPredicate::try_from(terms[1].1.clone()).map_err(|_| invalid(1))?;
+                    let object =
+                        QuadObject::try_from(terms[2].1.clone()).map_err(|_| invalid(2))?;
+                    let graph = match terms.get(3) {
+                        Some((_, graph)) => {
+                            Some(QuadGraph::try_from(graph.clone()).map_err(|_| invalid(3))?)
+                        }
+                        None => None,
+                    };
+                    let quad = Quad::new(&subject, &predicate, &object, graph.as_ref());
+                    if code == "A" {
+                        PatchOperation::Add(quad)
+                    } else {
+                        PatchOperation::Delete(quad)
+                    }
+                }
+                code => return Err(error(&format!("unknown row {code:?}"), column)),
+            };
+            operations.push(operation);
+        }
+
+        if transaction.is_some() {
+            return Err(SyntaxError::new("unterminated transaction", last_line, 1));
+        }
+        Ok(Self { operations })
+    }
+
+    /// A patch turning the old side of `diff` into the new one, in a single
+    /// transaction.
+    pub fn from_diff(diff: &Diff) -> Self {
+        if diff.is_empty() {
+            return Self::default();
+        }
+        let mut operations = vec![PatchOperation::TransactionBegin];
+        operations.extend(diff.removed().iter().cloned().map(PatchOperation::Delete));
+        operations.extend(diff.added().iter().cloned().map(PatchOperation::Add));
+        operations.push(PatchOperation::TransactionCommit);
+        Self { operations }
+    }
+
+    pub fn operations(&self) -> &[PatchOperation] {
+        &self.operations
+    }
+
+    /// Applies the changes to `dataset`, skipping aborted transactions.
+    /// Adding a present quad or deleting a missing one does nothing.
+    pub fn apply(&self, dataset: &mut Dataset) {
+        let mut pending: Option<Vec<&PatchOperation>> = None;
+        for operation in &self.operations {
+            match operation {
+                PatchOperation::TransactionBegin => pending = Some(Vec::new()),
+                PatchOperation::TransactionCommit => {
+                    for operation in pending.take().into_iter().flatten() {
+                        apply_change(dataset, operation);
+                    }
+                }
+                PatchOperation::TransactionAbort => pending = None,
+                operation => match &mut pending {
+                    Some(pending) => pending.push(operation),
+                    None => apply_change(dataset, operation),
+                },
+            }
+        }
+    }
+}
+
+impl Display for RdfPatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for operation in &self.operations {
+            match operation {
+                PatchOperation::Header(key, value) => {
+                    writeln!(f, "H {key} {} .", format_term(&value.to_term()))?
+                }
+                PatchOperation::TransactionBegin => writeln!(f, "TX .")?,
+                PatchOperation::TransactionCommit => writeln!(f, "TC .")?,
+                PatchOperation::TransactionAbort => writeln!(f, "TA .")?,
+                PatchOperation::AddPrefix(prefix, namespace) => {
+                    writeln!(f, "PA {prefix}: {} .", format_iri(namespace))?
+                }
+                PatchOperation::DeletePrefix(prefix) => writeln!(f, "PD {prefix}: .")?,
+                PatchOperation::Add(quad) => write!(f, "A {}", nquad(quad))?,
+                PatchOperation::Delete(quad) => write!(f, "D {}", nquad(quad))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn apply_change(dataset: &mut Dataset, operation: &PatchOperation) {
+    match operation {
+        PatchOperation::Add(quad) => {
+            dataset.insert(quad);
+        }
+        PatchOperation::Delete(quad) => {
+            dataset.remove(quad);
+        }
+        _ => {}
+    }
+}
+
+fn format_term(term: &Term) -> String {
+    match term {
+        Term::NamedNode(named_node) => format_iri(named_node.value()),
+        Term::BlankNode(blank_node) => format!("_:{}", blank_node.value()),
+        Term::Literal(literal) => format_canonical_literal(literal),
+        _ => String::new(),
+    }
+}
+
+/// Parses a single Turtle term, as the subject of a statement if `subject`
+/// (where triple terms are allowed) or else as its object.
+fn parse_term(
+    token: &str,
+    prefixes: &BTreeMap<String, String>,
+    subject: bool,
+) -> Result<Term, String> {
+    let mut parser = TurtleParser::new(TurtleSyntax::Turtle);
+    for (prefix, namespace) in prefixes {
+        parser = parser.with_prefix(prefix, namespace);
+    }
+    let statement = match subject {
+        true => format!("{token} <urn:x:p> <urn:x:o> .\n"),
+        false => format!("<urn:x:s> <urn:x:p> {token} .\n"),
+    };
+    parser.extend_from_slice(statement.as_bytes());
+    parser.end();
+    let quad = match parser.read_next() {
+        Some(Ok(quad)) => quad,
+        Some(Err(error)) => return Err(error.message().to_owned()),
+        None => return Err("expected a term".to_owned()),
+    };
+    if parser.read_next().is_some() {
+        return Err("expected a single term".to_owned());
+    }
+    Ok(match subject {
+        true => quad.subject().to_term(),
+        false => quad.object().to_term(),
+    })
+}
+
+/// Splits a row into its code and terms, each with its 1-based column. A `#`
+/// outside of a term starts a comment.
+fn tokenize(line: &str) -> Result<Vec<(usize, &str)>, (String, usize)> {
+    let column = |index: usize| line[..index].chars().count() + 1;
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    loop {
+        start += line[start..].len() - line[start..].trim_start().len();
+        if start == line.len() || line[start..].starts_with('#') {
+            return Ok(tokens);
+        }
+        let end = term_end(line, start).map_err(|message| (message, column(start)))?;
+        tokens.push((column(start), &line[start..end]));
+        start = end;
+    }
+}
+
+/// The end of the term starting at `start`.
+fn term_end(line: &str, start: usize) -> Result<usize, String> {
+    let rest = &line[start..];
+    if rest.starts_with("<<") {
+        let mut end = start + 2;
+        loop {
+            end += line[end..].len() - line[end..].trim_start().len();
+            if line[end..].starts_with(">>") {
+                return Ok(end + 2);
+            }
+            if end == line.len() {
+                return Err("unterminated triple term".to_owned());
+            }
+            end = term_end(line, end)?;
+        }
+    }
+    if rest.starts_with('<') {
+        return match rest.find('>') {
+            Some(end) => Ok(start + end + 1),
+            None => Err("unterminated IRI".to_owned()),
+        };
+    }
+    if rest.starts_with('.') {
+        return Ok(start + 1);
+    }
+
+    let mut chars = rest.char_indices().peekable();
+    if rest.starts_with('"') {
+        chars.next();
+        loop {
+            match chars.next() {
+                Some((_, '\\')) => {
+                    chars.next();
+                }
+                Some((_, '"')) => break,
+                Some(_) => {}
+                None => return Err("unterminated string".to_owned()),
+            }
+        }
+    }
+    // The rest of a literal, e.g. `@en` or `^^xsd:int`, or a name. A dot
+    // followed by a space ends the row.
+    let mut end = rest.len();
+    while let Some((index, c)) = chars.next() {
+        let ends_row = c == '.' && chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+        if c.is_whitespace() || ends_row || c == '>' {
+            end = index;
+            break;
+        }
+        if c == '<' {
+            match rest[index..].find('>') {
+                Some(close) => {
+                    while chars.peek().is_some_and(|(next, _)| *next <= index + close) {
+                        chars.next();
+                    }
+                }
+                None => return Err("unterminated IRI".to_owned()),
+            }
+        }
+    }
+    if end == 0 {
+        return Err("expected a term".to_owned());
+    }
+    Ok(start + end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::diff::diff;
+    use crate::rs::literal::Literal;
+    use crate::rs::named_node::NamedNode;
+    use crate::rs::turtle::parse_str;
+
+    #[test]
+    fn patches_apply_committed_transactions() {
+        let patch = RdfPatch::parse(
+            "H id <uuid:0123> .\n\
+             PA ex <http://example.org/> .\n\
+             # a comment\n\
+             A ex:s ex:p \"a b . c\"@en .\n\
+             TX .\n\
+             A _:b ex:p \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> ex:g .\n\
+             D ex:s ex:p \"a b . c\"@en .\n\
+             TC .\n\
+             TX .\n\
+             A ex:s ex:p ex:aborted .\n\
+             TA .\n\
+             A << ex:s ex:p ex:o >> ex:q ex:r .\n",
+        )
+        .unwrap();
+        assert_eq!(patch.operations().len(), 11);
+
+        let mut dataset = Dataset::new();
+        patch.apply(&mut dataset);
+        let quads: Vec<Quad> = dataset.iter().collect();
+        assert_eq!(quads.len(), 2);
+        assert!(quads.iter().any(|quad| nquad(quad)
+            == "_:b <http://example.org/p> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> <http://example.org/g> .\n"));
+
+        assert_eq!(RdfPatch::parse(&patch.to_string()).unwrap(), patch);
+
+        let error = RdfPatch::parse("TX .\nA <http://a> \"b\" <http://c> .\nTC .\n").unwrap_err();
+        assert_eq!((error.line(), error.column()), (2, 14));
+        let error = RdfPatch::parse("TX .\nA <http://a> <http://b> <http://c> .\n").unwrap_err();
+        assert_eq!(error.message(), "unterminated transaction");
+    }
+
+    #[test]
+    fn patches_from_diffs_reproduce_the_new_side() {
+        let old = parse_str(
+            TurtleSyntax::Turtle,
+            "@prefix ex: <http://example.org/> .\n\
+             ex:C ex:sub [ ex:on ex:p ] , [ ex:on ex:q ] ; ex:label \"C\" .\n",
+            None,
+        )
+        .unwrap();
+        let new = parse_str(
+            TurtleSyntax::Turtle,
+            "@prefix ex: <http://example.org/> .\n\
+             ex:C ex:sub [ ex:on ex:p ] , [ ex:on ex:r ] ; ex:label \"C\" .\n",
+            None,
+        )
+        .unwrap();
+        let patch = RdfPatch::from_diff(&diff(&old, &new).unwrap());
+        assert_eq!(patch.operations().len(), 6);
+
+        let mut dataset: Dataset = old.iter().collect();
+        RdfPatch::parse(&patch.to_string())
+            .unwrap()
+            .apply(&mut dataset);
+        let patched: Vec<Quad> = dataset.iter().collect();
+        assert!(diff(&patched, &new).unwrap().is_empty());
+    }
+
+    #[test]
+    fn headers_prefixes_and_transactions_are_checked() {
+        let patch = RdfPatch::parse(
+            "H id <uuid:0123> .\n\
+             H previous \"v1\" .\n\
+             PA ex: <http://example.org/> .\n\
+             PD ex .\n\
+             TX .\n\
+             TA .\n",
+        )
+        .unwrap();
+        assert_eq!(
+            patch.operations()[..2],
+            [
+                PatchOperation::Header(
+                    "id".to_owned(),
+                    QuadObject::NamedNode(NamedNode::new("uuid:0123"))
+                ),
+                PatchOperation::Header(
+                    "previous".to_owned(),
+                    QuadObject::Literal(Literal::new("v1", None, None, None))
+                ),
+            ]
+        );
+        assert_eq!(
+            patch.to_string(),
+            "H id <uuid:0123> .\n\
+             H previous \"v1\" .\n\
+             PA ex: <http://example.org/> .\n\
+             PD ex: .\n\
+             TX .\n\
+             TA .\n"
+        );
+        assert_eq!(RdfPatch::parse(&patch.to_string()).unwrap(), patch);
+
+        let error = |input: &str| {
+            let error = RdfPatch::parse(input).unwrap_err();
+            (error.message().to_owned(), error.line(), error.column())
+        };
+        let message = |message: &str, line, column| (message.to_owned(), line, column);
+        assert_eq!(
+            error("H id .\n"),
+            message("wrong number of terms for H", 1, 1)
+        );
+        assert_eq!(error("TX .\nTX .\n"), message("nested transaction", 2, 1));
+        assert_eq!(error("TC .\n"), message("no transaction to end", 1, 1));
+        assert_eq!(error("\n  TA .\n"), message("no transaction to end", 2, 3));
+        assert_eq!(
+            error("TX <http://a> .\nTC .\n"),
+            message("wrong number of terms for TX", 1, 1)
+        );
+        assert_eq!(
+            error("PA 1x: <http://a/> .\n"),
+            message("invalid prefix", 1, 4)
+        );
+        assert_eq!(
+            error("PA ex: \"a\" .\n"),
+            message("expected a namespace IRI", 1, 8)
+        );
+        assert_eq!(
+            error("X <http://a> .\n"),
+            message("unknown row \"X\"", 1, 1)
+        );
+        assert_eq!(
+            error("A <http://s> <http://p> <http://o> \"g\" .\n"),
+            message("invalid term for its position", 1, 36)
+        );
+        assert_eq!(
+            error("A <http://s> _:p <http://o> .\n"),
+            message("invalid term for its position", 1, 14)
+        );
+        assert_eq!(error("A <http://s .\n"), message("unterminated IRI", 1, 3));
+        assert_eq!(
+            error("A <http://s> <http://p> \"o .\n"),
+            message("unterminated string", 1, 25)
+        );
+        assert_eq!(
+            error("A << <http://s> <http://p> <http://o> <http://p> <http://o> .\n"),
+            message("unterminated triple term", 1, 3)
+        );
+        // A deleted prefix can no longer be used.
+        let (_, line, column) = error(
+            "PA ex: <http://example.org/> .\n\
+             A ex:s ex:p ex:o .\n\
+             PD ex: .\n\
+             A ex:s ex:p ex:o .\n",
+        );
+        assert_eq!((line, column), (4, 3));
+    }
+
+    #[test]
+    fn patches_ignore_redundant_changes() {
+        let patch = RdfPatch::parse(
+            "A <http://s> <http://p> <http://o> .\n\
+             A <http://s> <http://p> <http://o> .\n\
+             D <http://s> <http://p> <http://missing> .\n\
+             TX .\n\
+             D <http://s> <http://p> <http://o> .\n\
+             TA .\n\
+             TX .\n\
+             TC .\n",
+        )
+        .unwrap();
+        let mut dataset = Dataset::new();
+        patch.apply(&mut dataset);
+        assert_eq!(
+            dataset.iter().map(|quad| nquad(&quad)).collect::<Vec<_>>(),
+            ["<http://s> <http://p> <http://o> .\n"]
+        );
+        assert!(
+            RdfPatch::from_diff(&Diff::default())
+                .operations()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,1279 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, XmlVersion, escape};
+use regex::Regex;
+
+use crate::rs::blank_node::{BlankNode, BlankNodeLabeler};
+use crate::rs::iri;
+use crate::rs::literal::{LanguageDirection, Literal, is_valid_language_tag};
+use crate::rs::named_node::NamedNode;
+use crate::rs::parse_error::{ParseError, SyntaxError};
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term_like::TermLike;
+use crate::rs::vocab::{rdf, xsd};
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XML: &str = "http://www.w3.org/XML/1998/namespace";
+const ITS: &str = "http://www.w3.org/2005/11/its";
+
+/// RDF terms that can only appear as attributes or as `rdf:RDF`.
+const CORE_SYNTAX_TERMS: [&str; 7] = [
+    "RDF",
+    "ID",
+    "about",
+    "parseType",
+    "resource",
+    "nodeID",
+    "datatype",
+];
+
+static ENTITY_DECLARATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<!ENTITY\s+([^\s%]+)\s+(?:"([^"]*)"|'([^']*)')\s*>"#).unwrap());
+
+/// Parser for RDF/XML documents.
+///
+/// The whole document is read before interpretation, so quads are returned
+/// all at once. Internal entities declared in the document type declaration
+/// are expanded, as legacy ontologies commonly use them for namespaces.
+#[derive(Clone, Default)]
+pub struct RdfXmlParser {
+    base: Option<String>,
+}
+
+impl RdfXmlParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_base_iri(mut self, base: &str) -> Self {
+        self.base = Some(base.to_owned());
+        self
+    }
+
+    pub fn base_iri(&self) -> Option<&str> {
+        self.base.as_deref()
+    }
+
+    pub fn parse_str(&self, input: &str) -> Result<Vec<Quad>, SyntaxError> {
+        let root = parse_document(input)?;
+        let mut interpreter = Interpreter {
+            input,
+            blank_nodes: BlankNodeLabeler::new(),
+            ids: HashSet::new(),
+            quads: Vec::new(),
+        };
+        let scope = Scope {
+            base: self.base.clone(),
+            language: None,
+            direction: None,
+        };
+        interpreter.document(&root, &scope)?;
+        Ok(interpreter.quads)
+    }
+
+    pub fn parse_read(&self, mut reader: impl Read) -> Result<Vec<Quad>, ParseError> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+        Ok(self.parse_str(&input)?)
+    }
+}
+
+pub fn parse_str(input: &str, base: Option<&str>) -> Result<Vec<Quad>, SyntaxError> {
+    let mut parser = RdfXmlParser::new();
+    if let Some(base) = base {
+        parser = parser.with_base_iri(base);
+    }
+    parser.parse_str(input)
+}
+
+fn position_of(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset.min(input.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+fn syntax_error(input: &str, message: &str, offset: usize) -> SyntaxError {
+    let (line, column) = position_of(input, offset);
+    SyntaxError::new(message, line, column)
+}
+
+struct Attribute {
+    namespace: String,
+    local: String,
+    value: String,
+}
+
+/// A parsed element with its namespace resolved. Text children are
+/// concatenated; the raw content is kept for `rdf:parseType="Literal"`.
+struct Element {
+    namespace: String,
+    local: String,
+    attributes: Vec<Attribute>,
+    children: Vec<Element>,
+    text: String,
+    content: Range<usize>,
+    offset: usize,
+}
+
+impl Element {
+    fn iri(&self) -> String {
+        format!("{}{}", self.namespace, self.local)
+    }
+
+    fn is_rdf(&self, local: &str) -> bool {
+        self.namespace == RDF && self.local == local
+    }
+
+    fn attribute(&self, namespace: &str, local: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|a| a.namespace == namespace && a.local == local)
+            .map(|a| a.value.as_str())
+    }
+
+    /// The attributes standing for properties of the described resource.
+    fn property_attributes(&self) -> impl Iterator<Item = &Attribute> {
+        self.attributes
+            .iter()
+            .filter(|a| match a.namespace.as_str() {
+                "" | XML | ITS => false,
+                RDF => !CORE_SYNTAX_TERMS.contains(&a.local.as_str()) && a.local != "version",
+                _ => true,
+            })
+    }
+}
+
+fn parse_document(input: &str) -> Result<Element, SyntaxError> {
+    let mut reader = Reader::from_str(input);
+    let mut entities: HashMap<String, String> = HashMap::new();
+    let mut namespaces = Namespaces::new();
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+
+    loop {
+        let offset = reader.buffer_position() as usize;
+        let event = reader.read_event().map_err(|error| {
+            syntax_error(input, &error.to_string(), reader.error_position() as usize)
+        })?;
+        match event {
+            Event::DocType(declaration) => {
+                for captures in ENTITY_DECLARATION.captures_iter(&declaration) {
+                    let value = captures.get(2).or(captures.get(3)).unwrap().as_str();
+                    entities.insert(captures[1].to_owned(), value.to_owned());
+                }
+            }
+            Event::Start(start) => {
+                let mut element = element(input, &start, &entities, &mut namespaces, offset)?;
+                let position = reader.buffer_position() as usize;
+                element.content = position..position;
+                stack.push(element);
+            }
+            Event::Empty(start) => {
+                let element = element(input, &start, &entities, &mut namespaces, offset)?;
+                namespaces.pop();
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::End(_) => {
+                let mut element = stack.pop().expect("the reader checks end tags");
+                namespaces.pop();
+                element.content.end = offset;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::Text(content) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&content.xml10_content());
+                }
+            }
+            Event::CData(content) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&content);
+                }
+            }
+            Event::GeneralRef(reference) => {
+                let resolved = if reference.is_char_ref() {
+                    reference
+                        .resolve_char_ref()
+                        .ok()
+                        .flatten()
+                        .map(|c| c.to_string())
+                } else {
+                    escape::resolve_predefined_entity(&reference)
+                        .map(str::to_owned)
+                        .or_else(|| entities.get(&*reference).cloned())
+                };
+                let Some(resolved) = resolved else {
+                    return Err(syntax_error(input, "unknown entity reference", offset));
+                };
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&resolved);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err(syntax_error(
+            input,
+            "unexpected end of document",
+            input.len(),
+        ));
+    }
+    root.ok_or_else(|| syntax_error(input, "expected a root element", 0))
+}
+
+/// Namespaces declared by each open element, by prefix (empty for the
+/// default namespace).
+type Namespaces = Vec<HashMap<String, String>>;
+
+fn resolve_name(
+    input: &str,
+    namespaces: &Namespaces,
+    name: &str,
+    is_element: bool,
+    offset: usize,
+) -> Result<(String, String), SyntaxError> {
+    let (prefix, local) = match name.split_once(':') {
+        Some((prefix, local)) => (Some(prefix), local),
+        None => (None, name),
+    };
+    let namespace = match prefix {
+        Some("xml") => Some(XML),
+        None if !is_element => Some(""),
+        _ => namespaces
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(prefix.unwrap_or_default()))
+            .map(String::as_str),
+    };
+    match (namespace, prefix) {
+        (Some(namespace), _) => Ok((namespace.to_owned(), local.to_owned())),
+        (None, None) => Ok((String::new(), local.to_owned())),
+        (None, Some(prefix)) => Err(syntax_error(
+            input,
+            &format!("undeclared namespace prefix {prefix:?}"),
+            offset,
+        )),
+    }
+}
+
+/// Parses the start tag of an element, pushing the namespaces it declares.
+fn element(
+    input: &str,
+    start: &BytesStart,
+    entities: &HashMap<String, String>,
+    namespaces: &mut Namespaces,
+    offset: usize,
+) -> Result<Element, SyntaxError> {
+    // Declarations are collected first so that they apply to the element's
+    // own name and attributes. Their values may use entities.
+    let mut declared = HashMap::new();
+    let mut qualified = Vec::new();
+    for attribute in start.attributes() {
+        let attribute =
+            attribute.map_err(|error| syntax_error(input, &error.to_string(), offset))?;
+        let value = attribute
+            .normalized_value_with(XmlVersion::Implicit1_0, 8, |entity| {
+                escape::resolve_predefined_entity(entity)
+                    .or_else(|| entities.get(entity).map(String::as_str))
+            })
+            .map_err(|error| syntax_error(input, &error.to_string(), offset))?
+            .into_owned();
+        let key: &str = attribute.key.as_ref();
+        match key.strip_prefix("xmlns") {
+            Some("") => {
+                declared.insert(String::new(), value);
+            }
+            Some(prefix) if prefix.starts_with(':') => {
+                declared.insert(prefix[1..].to_owned(), value);
+            }
+            _ => qualified.push((key.to_owned(), value)),
+        }
+    }
+    namespaces.push(declared);
+
+    let (namespace, local) = resolve_name(input, namespaces, start.name().as_ref(), true, offset)?;
+    let mut attributes = Vec::new();
+    for (key, value) in qualified {
+        let (namespace, local) = resolve_name(input, namespaces, &key, false, offset)?;
+        attributes.push(Attribute {
+            namespace,
+            local,
+            value,
+        });
+    }
+
+    Ok(Element {
+        namespace,
+        local,
+        attributes,
+        children: Vec::new(),
+        text: String::new(),
+        content: 0..0,
+        offset,
+    })
+}
+
+/// The `xml:base`, `xml:lang` and `its:dir` in effect for an element.
+#[derive(Clone)]
+struct Scope {
+    base: Option<String>,
+    language: Option<String>,
+    direction: Option<LanguageDirection>,
+}
+
+#[derive(Clone)]
+enum Resource {
+    NamedNode(NamedNode),
+    BlankNode(BlankNode),
+}
+
+impl Resource {
+    fn subject(&self) -> QuadSubject {
+        match self {
+            Resource::NamedNode(node) => QuadSubject::NamedNode(node.clone()),
+            Resource::BlankNode(node) => QuadSubject::BlankNode(node.clone()),
+        }
+    }
+
+    fn object(&self) -> QuadObject {
+        match self {
+            Resource::NamedNode(node) => QuadObject::NamedNode(node.clone()),
+            Resource::BlankNode(node) => QuadObject::BlankNode(node.clone()),
+        }
+    }
+}
+
+struct Interpreter<'a> {
+    input: &'a str,
+    blank_nodes: BlankNodeLabeler,
+    /// The IRIs already given by an `rdf:ID`.
+    ids: HashSet<String>,
+    quads: Vec<Quad>,
+}
+
+impl Interpreter<'_> {
+    fn error<T>(&self, message: &str, element: &Element) -> Result<T, SyntaxError> {
+        Err(syntax_error(self.input, message, element.offset))
+    }
+
+    fn fresh_blank_node(&mut self) -> BlankNode {
+        self.blank_nodes.fresh()
+    }
+
+    /// The IRI of an `rdf:ID`, which can only be given once per base IRI.
+    fn id(&mut self, id: &str, scope: &Scope, element: &Element) -> Result<NamedNode, SyntaxError> {
+        let iri = self.resolve(&format!("#{id}"), scope, element)?;
+        if !self.ids.insert(iri.value().to_owned()) {
+            return self.error(&format!("rdf:ID {id:?} is used twice"), element);
+        }
+        Ok(iri)
+    }
+
+    fn push(&mut self, subject: &Resource, predicate: &NamedNode, object: &QuadObject) {
+        self.quads.push(Quad::new(
+            &subject.subject(),
+            &QuadPredicate::NamedNode(predicate.clone()),
+            object,
+            None,
+        ));
+    }
+
+    fn scope(&self, element: &Element, parent: &Scope) -> Result<Scope, SyntaxError> {
+        let mut scope = parent.clone();
+        if let Some(base) = element.attribute(XML, "base") {
+            scope.base = Some(match &parent.base {
+                Some(parent) => iri::resolve(parent, base),
+                None => base.to_owned(),
+            });
+        }
+        if let Some(language) = element.attribute(XML, "lang") {
+            if !language.is_empty() && !is_valid_language_tag(language) {
+                return self.error(&format!("invalid language tag {language:?}"), element);
+            }
+            scope.language = Some(language.to_owned()).filter(|l| !l.is_empty());
+        }
+        if let Some(direction) = element.attribute(ITS, "dir") {
+            match direction.parse::<LanguageDirection>() {
+                Ok(direction) => scope.direction = Some(direction),
+                Err(_) => return self.error(&format!("invalid direction {direction:?}"), element),
+            }
+        }
+        Ok(scope)
+    }
+
+    fn resolve(
+        &self,
+        reference: &str,
+        scope: &Scope,
+        element: &Element,
+    ) -> Result<NamedNode, SyntaxError> {
+        match &scope.base {
+            Some(base) => Ok(NamedNode::new(&iri::resolve(base, reference))),
+            None if iri::has_scheme(reference) => Ok(NamedNode::new(reference)),
+            None => self.error(
+                &format!("relative IRI {reference:?} without a base IRI"),
+                element,
+            ),
+        }
+    }
+
+    fn literal(&self, value: &str, scope: &Scope) -> QuadObject {
+        QuadObject::Literal(match &scope.language {
+            Some(language) => Literal::new(value, Some(language), scope.direction.as_ref(), None),
+            None => Literal::new(value, None, None, None),
+        })
+    }
+
+    fn document(&mut self, root: &Element, scope: &Scope) -> Result<(), SyntaxError> {
+        if !root.is_rdf("RDF") {
+            return self.node_element(root, scope).map(|_| ());
+        }
+        let scope = self.scope(root, scope)?;
+        for child in &root.children {
+            self.node_element(child, &scope)?;
+        }
+        Ok(())
+    }
+
+    fn node_element(&mut self, element: &Element, parent: &Scope) -> Result<Resource, SyntaxError> {
+        let scope = self.scope(element, parent)?;
+        if element.namespace.is_empty() {
+            return self.error("node elements must be namespaced", element);
+        }
+        if element.namespace == RDF
+            && (CORE_SYNTAX_TERMS.contains(&element.local.as_str()) || element.local == "li")
+        {
+            return self.error(
+                &format!("rdf:{} is not a node element", element.local),
+                element,
+            );
+        }
+
+        if element.attribute(RDF, "resource").is_some() {
+            return self.error("rdf:resource is not allowed on a node element", element);
+        }
+
+        let id = element.attribute(RDF, "ID");
+        let about = element.attribute(RDF, "about");
+        let node_id = element.attribute(RDF, "nodeID");
+        let subject = match (id, about, node_id) {
+            (Some(id), None, None) => Resource::NamedNode(self.id(id, &scope, element)?),
+            (None, Some(about), None) => Resource::NamedNode(self.resolve(about, &scope, element)?),
+            (None, None, Some(label)) => Resource::BlankNode(self.blank_nodes.labeled(label)),
+            (None, None, None) => Resource::BlankNode(self.fresh_blank_node()),
+            _ => {
+                return self.error(
+                    "only one of rdf:ID, rdf:about and rdf:nodeID is allowed",
+                    element,
+                );
+            }
+        };
+
+        if !element.is_rdf("Description") {
+            let class = QuadObject::NamedNode(NamedNode::new(&element.iri()));
+            self.push(&subject, &rdf::r#type, &class);
+        }
+        self.property_attributes(element, &subject, &scope)?;
+
+        let mut li = 0;
+        for child in &element.children {
+            self.property_element(child, &subject, &scope, &mut li)?;
+        }
+        Ok(subject)
+    }
+
+    fn property_attributes(
+        &mut self,
+        element: &Element,
+        subject: &Resource,
+        scope: &Scope,
+    ) -> Result<(), SyntaxError> {
+        for attribute in element.property_attributes() {
+            let predicate = NamedNode::new(&format!("{}{}", attribute.namespace, attribute.local));
+            let object = if predicate == *rdf::r#type {
+                QuadObject::NamedNode(self.resolve(&attribute.value, scope, element)?)
+            } else {
+                self.literal(&attribute.value, scope)
+            };
+            self.push(subject, &predicate, &object);
+        }
+        Ok(())
+    }
+
+    fn property_element(
+        &mut self,
+        element: &Element,
+        subject: &Resource,
+        parent: &Scope,
+        li: &mut usize,
+    ) -> Result<(), SyntaxError> {
+        let scope = self.scope(element, parent)?;
+        if element.namespace.is_empty() {
+            return self.error("property elements must be namespaced", element);
+        }
+        if element.namespace == RDF
+            && (CORE_SYNTAX_TERMS.contains(&element.local.as_str())
+                || element.local == "Description")
+        {
+            return self.error(
+                &format!("rdf:{} is not a property element", element.local),
+                element,
+            );
+        }
+
+        let predicate = if element.is_rdf("li") {
+            *li += 1;
+            NamedNode::new(&format!("{RDF}_{li}"))
+        } else {
+            NamedNode::new(&element.iri())
+        };
+
+        let object = match element.attribute(RDF, "parseType") {
+            Some("Resource") => {
+                let node = Resource::BlankNode(self.fresh_blank_node());
+                self.push(subject, &predicate, &node.object());
+                let mut li = 0;
+                for child in &element.children {
+                    self.property_element(child, &node, &scope, &mut li)?;
+                }
+                self.reify(element, subject, &predicate, &node.object(), &scope)?;
+                return Ok(());
+            }
+            Some("Collection") => {
+                let mut items = Vec::new();
+                for child in &element.children {
+                    items.push(self.node_element(child, &scope)?);
+                }
+                self.collection(&items)
+            }
+            // Other parse types are handled like "Literal".
+            Some(_) => QuadObject::Literal(Literal::new(
+                &self.input[element.content.clone()],
+                None,
+                None,
+                Some(&rdf::XMLLiteral),
+            )),
+            None => self.property_value(element, &scope)?,
+        };
+        self.push(subject, &predicate, &object);
+        self.reify(element, subject, &predicate, &object, &scope)
+    }
+
+    /// The object of a property element without `rdf:parseType`: a nested
+    /// node element, a resource described by attributes or a literal.
+    fn property_value(
+        &mut self,
+        element: &Element,
+        scope: &Scope,
+    ) -> Result<QuadObject, SyntaxError> {
+        if let Some(child) = element.children.first() {
+            if element.children.len() > 1 {
+                return self.error(
+                    "a property element can only contain one node element",
+                    element,
+                );
+            }
+            if !element.text.trim().is_empty() {
+                return self.error("a property element cannot have mixed content", element);
+            }
+            return Ok(self.node_element(child, scope)?.object());
+        }
+
+        let resource = element.attribute(RDF, "resource");
+        let node_id = element.attribute(RDF, "nodeID");
+        let has_properties = element.property_attributes().next().is_some();
+        if resource.is_none() && node_id.is_none() && !has_properties {
+            return Ok(match element.attribute(RDF, "datatype") {
+                Some(datatype) => {
+                    let datatype = self.resolve(datatype, scope, element)?;
+                    QuadObject::Literal(Literal::new(&element.text, None, None, Some(&datatype)))
+                }
+                None => self.literal(&element.text, scope),
+            });
+        }
+
+        if !element.text.is_empty() {
+            return self.error(
+                "a property element with resource attributes must be empty",
+                element,
+            );
+        }
+        let object = match (resource, node_id) {
+            (Some(resource), None) => Resource::NamedNode(self.resolve(resource, scope, element)?),
+            (None, Some(label)) => Resource::BlankNode(self.blank_nodes.labeled(label)),
+            (None, None) => Resource::BlankNode(self.fresh_blank_node()),
+            (Some(_), Some(_)) => {
+                return self.error("rdf:resource and rdf:nodeID are exclusive", element);
+            }
+        };
+        self.property_attributes(element, &object, scope)?;
+        Ok(object.object())
+    }
+
+    fn collection(&mut self, items: &[Resource]) -> QuadObject {
+        let nodes: Vec<Resource> = items
+            .iter()
+            .map(|_| Resource::BlankNode(self.fresh_blank_node()))
+            .collect();
+        for (index, (node, item)) in nodes.iter().zip(items).enumerate() {
+            self.push(node, &rdf::first, &item.object());
+            let rest = match nodes.get(index + 1) {
+                Some(next) => next.object(),
+                None => QuadObject::NamedNode(rdf::nil.clone()),
+            };
+            self.push(node, &rdf::rest, &rest);
+        }
+        match nodes.first() {
+            Some(head) => head.object(),
+            None => QuadObject::NamedNode(rdf::nil.clone()),
+        }
+    }
+
+    /// Adds the reification triples of a property element with an `rdf:ID`.
+    fn reify(
+        &mut self,
+        element: &Element,
+        subject: &Resource,
+        predicate: &NamedNode,
+        object: &QuadObject,
+        scope: &Scope,
+    ) -> Result<(), SyntaxError> {
+        let Some(id) = element.attribute(RDF, "ID") else {
+            return Ok(());
+        };
+        let statement = Resource::NamedNode(self.id(id, scope, element)?);
+        let class = QuadObject::NamedNode(rdf::Statement.clone());
+        self.push(&statement, &rdf::r#type, &class);
+        self.push(&statement, &rdf::subject, &subject.object());
+        let predicate = QuadObject::NamedNode(predicate.clone());
+        self.push(&statement, &rdf::predicate, &predicate);
+        self.push(&statement, &rdf::object, object);
+        Ok(())
+    }
+}
+
+/// Serializer producing abbreviated RDF/XML.
+///
+/// Subjects are written as typed node elements when they have a type, and
+/// blank nodes referenced once are nested in the property referencing them.
+#[derive(Clone, Default)]
+pub struct RdfXmlSerializer {
+    prefixes: BTreeMap<String, String>,
+}
+
+impl RdfXmlSerializer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares a prefix for the namespace of element names. Namespaces
+    /// without one get generated prefixes.
+    pub fn with_prefix(mut self, prefix: &str, namespace: &str) -> Self {
+        self.prefixes
+            .insert(prefix.to_owned(), namespace.to_owned());
+        self
+    }
+
+    /// Writes `quads` as one document. Named graphs, triple terms and
+    /// predicates that cannot be element names fail with
+    /// [`io::ErrorKind::InvalidInput`].
+    pub fn serialize<'a>(
+        &self,
+        mut writer: impl Write,
+        quads: impl IntoIterator<Item = &'a Quad>,
+    ) -> io::Result<()> {
+        let mut formatter = RdfXmlFormatter {
+            serializer: self,
+            subjects: Vec::new(),
+            subject_index: HashMap::new(),
+            references: HashMap::new(),
+            written: Vec::new(),
+            namespaces: BTreeMap::from([(RDF.to_owned(), "rdf".to_owned())]),
+            output: String::new(),
+        };
+        for quad in quads {
+            formatter.add(quad)?;
+        }
+        formatter.written = vec![false; formatter.subjects.len()];
+
+        // Blank nodes referenced once are nested where they are referenced,
+        // unless they are part of a cycle of such nodes.
+        for nested in [false, true] {
+            for index in 0..formatter.subjects.len() {
+                if !formatter.written[index] && (nested || !formatter.is_nested(index)) {
+                    formatter.node(index, 1, true)?;
+                }
+            }
+        }
+
+        writeln!(writer, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
+        write!(writer, "<rdf:RDF")?;
+        let mut declarations: Vec<_> = formatter.namespaces.iter().collect();
+        declarations.sort_by_key(|(_, prefix)| prefix.as_str());
+        for (namespace, prefix) in declarations {
+            write!(writer, "\n    xmlns:{prefix}=\"{}\"", attribute(namespace))?;
+        }
+        writeln!(writer, ">")?;
+        writer.write_all(formatter.output.as_bytes())?;
+        writeln!(writer, "</rdf:RDF>")?;
+        writer.flush()
+    }
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn text(value: &str) -> String {
+    // Escaping carriage returns keeps them from being normalized away.
+    escape::escape(value).replace('\r', "&#13;")
+}
+
+fn attribute(value: &str) -> String {
+    text(value).replace('\n', "&#10;").replace('\t', "&#9;")
+}
+
+/// Whether `value` can be written as is as element content: well-formed,
+/// using only predefined entities and the namespace prefixes it declares.
+fn is_xml_content(value: &str) -> bool {
+    if value.contains('\r') {
+        return false;
+    }
+    let wrapped = format!("<literal>{value}</literal>");
+    let mut reader = Reader::from_str(&wrapped);
+    // The prefixes declared by each open element.
+    let mut declared: Vec<Vec<String>> = Vec::new();
+    let mut closed = false;
+
+    loop {
+        let event = match reader.read_event() {
+            Ok(event) => event,
+            Err(_) => return false,
+        };
+        if closed && !matches!(event, Event::Eof) {
+            return false;
+        }
+        match event {
+            Event::Start(ref start) | Event::Empty(ref start) => {
+                let mut prefixes = Vec::new();
+                let mut names = vec![start.name().as_ref().to_owned()];
+                for attribute in start.attributes() {
+                    let Ok(attribute) = attribute else {
+                        return false;
+                    };
+                    let key: &str = attribute.key.as_ref();
+                    if key == "xmlns" {
+                        continue;
+                    }
+                    match key.strip_prefix("xmlns:") {
+                        Some(prefix) => prefixes.push(prefix.to_owned()),
+                        None => names.push(key.to_owned()),
+                    }
+                }
+                declared.push(prefixes);
+                for name in &names {
+                    if let Some((prefix, _)) = name.split_once(':')
+                        && prefix != "xml"
+                        && !declared.iter().flatten().any(|p| p == prefix)
+                    {
+                        return false;
+                    }
+                }
+                if matches!(event, Event::Empty(_)) {
+                    declared.pop();
+                }
+            }
+            Event::End(_) => {
+                declared.pop();
+                closed = declared.is_empty();
+            }
+            Event::GeneralRef(reference) => {
+                let known = if reference.is_char_ref() {
+                    matches!(reference.resolve_char_ref(), Ok(Some(_)))
+                } else {
+                    escape::resolve_predefined_entity(&reference).is_some()
+                };
+                if !known {
+                    return false;
+                }
+            }
+            Event::Decl(_) | Event::DocType(_) => return false,
+            Event::Eof => return closed,
+            _ => {}
+        }
+    }
+}
+
+/// Splits `iri` into a namespace and the longest local name that is an
+/// XML name.
+fn split_iri(iri: &str) -> Option<(&str, &str)> {
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+    let mut start = iri
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_name_char(*c))
+        .last()?
+        .0;
+    while let Some(c) = iri[start..].chars().next()
+        && !(c.is_alphabetic() || c == '_')
+    {
+        start += c.len_utf8();
+    }
+    (start < iri.len()).then(|| iri.split_at(start))
+}
+
+struct RdfXmlFormatter<'a> {
+    serializer: &'a RdfXmlSerializer,
+    subjects: Vec<(&'a QuadSubject, Vec<&'a Quad>)>,
+    subject_index: HashMap<&'a QuadSubject, usize>,
+    /// How many times each blank node is used as an object.
+    references: HashMap<&'a BlankNode, usize>,
+    written: Vec<bool>,
+    /// Prefixes by namespace.
+    namespaces: BTreeMap<String, String>,
+    output: String,
+}
+
+impl<'a> RdfXmlFormatter<'a> {
+    fn add(&mut self, quad: &'a Quad) -> io::Result<()> {
+        if !matches!(quad.graph(), QuadGraph::DefaultGraph(_)) {
+            return Err(invalid_input("RDF/XML cannot express named graphs"));
+        }
+        if !matches!(
+            quad.subject(),
+            QuadSubject::NamedNode(_) | QuadSubject::BlankNode(_)
+        ) {
+            return Err(invalid_input(
+                "RDF/XML subjects must be IRIs or blank nodes",
+            ));
+        }
+        match quad.object() {
+            QuadObject::BlankNode(node) => *self.references.entry(node).or_default() += 1,
+            QuadObject::Variable(_) => {
+                return Err(invalid_input("variables cannot be written as RDF/XML"));
+            }
+            _ => {}
+        }
+
+        let index = *self.subject_index.entry(quad.subject()).or_insert_with(|| {
+            self.subjects.push((quad.subject(), Vec::new()));
+            self.subjects.len() - 1
+        });
+        self.subjects[index].1.push(quad);
+        Ok(())
+    }
+
+    fn is_nested(&self, index: usize) -> bool {
+        match self.subjects[index].0 {
+            QuadSubject::BlankNode(node) => self.references.get(node) == Some(&1),
+            _ => false,
+        }
+    }
+
+    fn qname(&mut self, iri: &str) -> Option<String> {
+        let (namespace, local) = split_iri(iri)?;
+        if let Some(prefix) = self.namespaces.get(namespace) {
+            return Some(format!("{prefix}:{local}"));
+        }
+        let declared = self
+            .serializer
+            .prefixes
+            .iter()
+            .find(|(prefix, candidate)| {
+                *candidate == namespace
+                    && prefix.as_str() != "rdf"
+                    && !self.namespaces.values().any(|used| used == *prefix)
+            })
+            .map(|(prefix, _)| prefix.clone());
+        let prefix = declared.unwrap_or_else(|| {
+            (0..)
+                .map(|n| format!("ns{n}"))
+                .find(|prefix| {
+                    !self.serializer.prefixes.contains_key(prefix)
+                        && !self.namespaces.values().any(|used| used == prefix)
+                })
+                .unwrap()
+        });
+        self.namespaces.insert(namespace.to_owned(), prefix.clone());
+        Some(format!("{prefix}:{local}"))
+    }
+
+    fn indent(&mut self, depth: usize) {
+        for _ in 0..depth {
+            self.output.push_str("  ");
+        }
+    }
+
+    /// Writes the node element of subject `index`, identified unless it is
+    /// a nested blank node.
+    fn node(&mut self, index: usize, depth: usize, identify: bool) -> io::Result<()> {
+        self.written[index] = true;
+        let (subject, quads) = self.subjects[index].clone();
+
+        let mut name = None;
+        let mut properties = Vec::with_capacity(quads.len());
+        for quad in quads {
+            if name.is_none()
+                && let QuadPredicate::NamedNode(predicate) = quad.predicate()
+                && *predicate == *rdf::r#type
+                && let QuadObject::NamedNode(class) = quad.object()
+                && let Some(qname) = self.qname(class.value())
+            {
+                name = Some(qname);
+                continue;
+            }
+            properties.push(quad);
+        }
+        let name = name.unwrap_or_else(|| "rdf:Description".to_owned());
+
+        self.indent(depth);
+        self.output.push_str(&format!("<{name}"));
+        if identify {
+            match subject {
+                QuadSubject::NamedNode(node) => self
+                    .output
+                    .push_str(&format!(" rdf:about=\"{}\"", attribute(node.value()))),
+                QuadSubject::BlankNode(node) => self
+                    .output
+                    .push_str(&format!(" rdf:nodeID=\"{}\"", attribute(node.value()))),
+                _ => {}
+            }
+        }
+        if properties.is_empty() {
+            self.output.push_str("/>\n");
+            return Ok(());
+        }
+        self.output.push_str(">\n");
+        for quad in properties {
+            self.property(quad, depth + 1)?;
+        }
+        self.indent(depth);
+        self.output.push_str(&format!("</{name}>\n"));
+        Ok(())
+    }
+
+    fn property(&mut self, quad: &'a Quad, depth: usize) -> io::Result<()> {
+        let QuadPredicate::NamedNode(predicate) = quad.predicate() else {
+            return Err(invalid_input("variables cannot be written as RDF/XML"));
+        };
+        let Some(name) = self.qname(predicate.value()) else {
+            return Err(invalid_input(&format!(
+                "<{}> cannot be written as an RDF/XML property",
+                predicate.value()
+            )));
+        };
+
+        self.indent(depth);
+        match quad.object() {
+            QuadObject::NamedNode(node) => self.output.push_str(&format!(
+                "<{name} rdf:resource=\"{}\"/>\n",
+                attribute(node.value())
+            )),
+            QuadObject::BlankNode(node) if self.references.get(node) == Some(&1) => {
+                let subject = QuadSubject::BlankNode(node.clone());
+                match self.subject_index.get(&subject).copied() {
+                    None => self
+                        .output
+                        .push_str(&format!("<{name} rdf:parseType=\"Resource\"/>\n")),
+                    Some(index) if self.written[index] => self.output.push_str(&format!(
+                        "<{name} rdf:nodeID=\"{}\"/>\n",
+                        attribute(node.value())
+                    )),
+                    Some(index) => {
+                        self.output.push_str(&format!("<{name}>\n"));
+                        self.node(index, depth + 1, false)?;
+                        self.indent(depth);
+                        self.output.push_str(&format!("</{name}>\n"));
+                    }
+                }
+            }
+            QuadObject::BlankNode(node) => self.output.push_str(&format!(
+                "<{name} rdf:nodeID=\"{}\"/>\n",
+                attribute(node.value())
+            )),
+            QuadObject::Literal(literal) => {
+                let mut attributes = String::new();
+                if !literal.language().is_empty() {
+                    attributes
+                        .push_str(&format!(" xml:lang=\"{}\"", attribute(literal.language())));
+                    if let Some(direction) = literal.direction() {
+                        self.namespaces.insert(ITS.to_owned(), "its".to_owned());
+                        attributes.push_str(&format!(" its:dir=\"{direction}\""));
+                    }
+                } else if *literal.datatype() == *rdf::XMLLiteral && is_xml_content(literal.value())
+                {
+                    self.output.push_str(&format!(
+                        "<{name} rdf:parseType=\"Literal\">{}</{name}>\n",
+                        literal.value()
+                    ));
+                    return Ok(());
+                } else if *literal.datatype() != *xsd::string {
+                    attributes.push_str(&format!(
+                        " rdf:datatype=\"{}\"",
+                        attribute(literal.datatype().value())
+                    ));
+                }
+                self.output.push_str(&format!(
+                    "<{name}{attributes}>{}</{name}>\n",
+                    text(literal.value())
+                ));
+            }
+            _ => return Err(invalid_input("triple terms cannot be written as RDF/XML")),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::canonical::canonical_nquads;
+    use crate::rs::turtle::{self, TurtleSyntax};
+
+    fn turtle(input: &str) -> Vec<Quad> {
+        turtle::parse_str(TurtleSyntax::Turtle, input, None).unwrap()
+    }
+
+    fn sorted(quads: &[Quad]) -> Vec<String> {
+        let mut lines = Vec::new();
+        turtle::TurtleSerializer::new(TurtleSyntax::NTriples)
+            .serialize(&mut lines, quads)
+            .unwrap();
+        let mut lines: Vec<String> = String::from_utf8(lines)
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect();
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn documents_parse() {
+        let quads = parse_str(
+            r##"<?xml version="1.0"?>
+<!DOCTYPE rdf:RDF [<!ENTITY ex "http://example.org/">]>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+         xmlns:ex="&ex;" xml:base="http://example.org/doc">
+  <ex:Book rdf:ID="book" ex:title="Dune" xml:lang="en">
+    <ex:author rdf:parseType="Resource">
+      <ex:name xml:lang="">Frank</ex:name>
+    </ex:author>
+    <ex:pages rdf:datatype="&ex;int">412</ex:pages>
+    <ex:tags rdf:parseType="Collection">
+      <rdf:Description rdf:about="#sf"/>
+    </ex:tags>
+    <ex:note rdf:parseType="Literal"><b>new</b> edition</ex:note>
+    <ex:publisher rdf:ID="claim" rdf:resource="acme"/>
+  </ex:Book>
+  <rdf:Seq rdf:about="list" xml:base="http://example.org/other/">
+    <rdf:li rdf:resource="a"/>
+    <rdf:li>b</rdf:li>
+  </rdf:Seq>
+</rdf:RDF>"##,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            sorted(&quads),
+            sorted(&turtle(
+                r#"@prefix ex: <http://example.org/> .
+                   @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+                   <http://example.org/doc#book> a ex:Book ;
+                       ex:title "Dune"@en ;
+                       ex:author [ ex:name "Frank" ] ;
+                       ex:pages "412"^^ex:int ;
+                       ex:tags ( <http://example.org/doc#sf> ) ;
+                       ex:note "<b>new</b> edition"^^rdf:XMLLiteral ;
+                       ex:publisher ex:acme .
+                   <http://example.org/doc#claim> a rdf:Statement ;
+                       rdf:subject <http://example.org/doc#book> ;
+                       rdf:predicate ex:publisher ;
+                       rdf:object ex:acme .
+                   <http://example.org/other/list> a rdf:Seq ;
+                       rdf:_1 <http://example.org/other/a> ;
+                       rdf:_2 "b" ."#
+            ))
+        );
+
+        let error = parse_str(
+            &format!("<rdf:RDF xmlns:rdf=\"{RDF}\">\n  <p/>\n</rdf:RDF>"),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!((error.line(), error.column()), (2, 3));
+
+        let document = |body: &str| {
+            parse_str(
+                &format!(
+                    "<rdf:RDF xmlns:rdf=\"{RDF}\" xmlns:ex=\"http://example.org/\">{body}</rdf:RDF>"
+                ),
+                Some("http://example.org/doc"),
+            )
+        };
+        let error = document("<ex:A rdf:ID=\"a\"><ex:p rdf:ID=\"a\">x</ex:p></ex:A>").unwrap_err();
+        assert_eq!(error.message(), "rdf:ID \"a\" is used twice");
+        let error = document("<ex:A rdf:about=\"a\" rdf:resource=\"b\"/>").unwrap_err();
+        assert_eq!(
+            error.message(),
+            "rdf:resource is not allowed on a node element"
+        );
+
+        let quads = document("<ex:A rdf:nodeID=\"genid1\"><ex:p><ex:B/></ex:p></ex:A>").unwrap();
+        assert_ne!(quads[0].subject(), quads[1].subject());
+    }
+
+    #[test]
+    fn parse_types_ids_and_bases_follow_the_spec() {
+        let quads = parse_str(
+            &format!(
+                r##"<rdf:RDF xmlns:rdf="{RDF}" xmlns:ex="http://example.org/"
+         xml:base="http://example.org/dir/doc">
+  <rdf:Description rdf:about="">
+    <ex:empty rdf:parseType="Resource"/>
+    <ex:nested rdf:parseType="Resource" rdf:ID="n">
+      <ex:inner rdf:parseType="Resource"><ex:v>1</ex:v></ex:inner>
+    </ex:nested>
+    <ex:none rdf:parseType="Collection"/>
+    <ex:list rdf:parseType="Collection">
+      <ex:Item rdf:about="a"/>
+      <rdf:Description rdf:nodeID="x"><ex:v>2</ex:v></rdf:Description>
+    </ex:list>
+    <ex:xml rdf:parseType="Literal"><ex:b xmlns:ex="http://example.org/">bold</ex:b> &amp; text</ex:xml>
+    <ex:other rdf:parseType="Other"><i>kept</i></ex:other>
+  </rdf:Description>
+  <rdf:Description rdf:about="b" xml:base="sub/">
+    <ex:id rdf:ID="n">same</ex:id>
+  </rdf:Description>
+  <rdf:Description rdf:about="#frag" xml:base="../up/">
+    <ex:p rdf:resource=""/>
+  </rdf:Description>
+</rdf:RDF>"##
+            ),
+            None,
+        )
+        .unwrap();
+
+        let expected = turtle(
+            r#"@prefix ex: <http://example.org/> .
+               @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+               <http://example.org/dir/doc> ex:empty [] ;
+                   ex:nested _:n ;
+                   ex:none rdf:nil ;
+                   ex:list ( <http://example.org/dir/a> _:x ) ;
+                   ex:xml "<ex:b xmlns:ex=\"http://example.org/\">bold</ex:b> &amp; text"^^rdf:XMLLiteral ;
+                   ex:other "<i>kept</i>"^^rdf:XMLLiteral .
+               _:n ex:inner [ ex:v "1" ] .
+               <http://example.org/dir/a> a ex:Item .
+               _:x ex:v "2" .
+               <http://example.org/dir/doc#n> a rdf:Statement ;
+                   rdf:subject <http://example.org/dir/doc> ;
+                   rdf:predicate ex:nested ;
+                   rdf:object _:n .
+               <http://example.org/dir/sub/b> ex:id "same" .
+               <http://example.org/dir/sub/#n> a rdf:Statement ;
+                   rdf:subject <http://example.org/dir/sub/b> ;
+                   rdf:predicate ex:id ;
+                   rdf:object "same" .
+               <http://example.org/up/#frag> ex:p <http://example.org/up/> ."#,
+        );
+        assert_eq!(
+            canonical_nquads(&quads).unwrap(),
+            canonical_nquads(&expected).unwrap()
+        );
+
+        // rdf:ID values are unique per base IRI, whatever its fragment, and
+        // across node and property elements.
+        let document = |body: &str| {
+            parse_str(
+                &format!(
+                    "<rdf:RDF xmlns:rdf=\"{RDF}\" xmlns:ex=\"http://example.org/\">{body}</rdf:RDF>"
+                ),
+                Some("http://example.org/doc"),
+            )
+        };
+        let error = document(
+            "<ex:A rdf:ID=\"a\"/><ex:B xml:base=\"http://example.org/doc#b\" rdf:ID=\"a\"/>",
+        )
+        .unwrap_err();
+        assert_eq!(error.message(), "rdf:ID \"a\" is used twice");
+        let error = document(
+            "<ex:A rdf:about=\"x\"><ex:p rdf:ID=\"a\">1</ex:p></ex:A><ex:B rdf:ID=\"a\"/>",
+        )
+        .unwrap_err();
+        assert_eq!(error.message(), "rdf:ID \"a\" is used twice");
+        assert!(document("<ex:A rdf:ID=\"a\"/><ex:B xml:base=\"other\" rdf:ID=\"a\"/>").is_ok());
+    }
+
+    #[test]
+    fn serialized_documents_are_abbreviated_and_round_trip() {
+        let quads = turtle(
+            r#"@prefix ex: <http://example.org/> .
+               @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+               ex:book a ex:Book ;
+                   ex:title "Dune"@en--rtl, "x < y" ;
+                   ex:author [ ex:name "Frank" ; ex:born 1920 ] ;
+                   ex:note "<b>new</b>"^^rdf:XMLLiteral, "<b>open"^^rdf:XMLLiteral,
+                       "<x:b>prefixed</x:b>"^^rdf:XMLLiteral ;
+                   ex:shared _:s .
+               ex:other ex:shared _:s .
+               _:a ex:next _:b .
+               _:b ex:next _:a ."#,
+        );
+        let mut output = Vec::new();
+        RdfXmlSerializer::new()
+            .with_prefix("ex", "http://example.org/")
+            .serialize(&mut output, &quads)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("<ex:Book rdf:about=\"http://example.org/book\">"));
+        assert!(output.contains("<ex:title xml:lang=\"en\" its:dir=\"rtl\">Dune</ex:title>"));
+        assert!(output.contains("<ex:author>\n      <rdf:Description>"));
+        assert!(output.contains("<ex:shared rdf:nodeID=\"s\"/>"));
+        assert!(output.contains("<ex:note rdf:parseType=\"Literal\"><b>new</b></ex:note>"));
+        assert!(output.contains(&format!(
+            "<ex:note rdf:datatype=\"{RDF}XMLLiteral\">&lt;b&gt;open</ex:note>"
+        )));
+
+        // Blank nodes are relabelled, so only the ground triples compare.
+        let ground = |quads: &[Quad]| -> Vec<String> {
+            sorted(quads)
+                .into_iter()
+                .filter(|line| !line.contains("_:"))
+                .collect()
+        };
+        let reparsed = parse_str(&output, None).unwrap();
+        assert_eq!(reparsed.len(), quads.len());
+        assert_eq!(ground(&reparsed), ground(&quads));
+
+        let named = turtle::parse_str(
+            TurtleSyntax::NQuads,
+            "<http://a> <http://b> <http://c> <http://g> .\n",
+            None,
+        )
+        .unwrap();
+        let error = RdfXmlSerializer::new()
+            .serialize(Vec::new(), &named)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,889 @@
+use std::io::{self, Cursor, Read};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::rs::dataset::Dataset;
+use crate::rs::default_graph::DefaultGraph;
+use crate::rs::named_node::NamedNode;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::sparql::update::{file_iri, file_path};
+use crate::rs::sparql::{
+    GraphTarget, Query, QueryDataset, QueryResults, QueryResultsFormat, Update, UpdateError,
+    UpdateOperation, evaluate_query, execute_update, parse_query, parse_update, write_results,
+};
+use crate::rs::term_like::TermLike;
+use crate::rs::turtle::{TurtleSerializer, TurtleSyntax, parse_str};
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+const RESULT_FORMATS: [(&str, QueryResultsFormat); 5] = [
+    ("application/sparql-results+json", QueryResultsFormat::Json),
+    ("application/json", QueryResultsFormat::Json),
+    ("application/sparql-results+xml", QueryResultsFormat::Xml),
+    ("text/csv", QueryResultsFormat::Csv),
+    ("text/tab-separated-values", QueryResultsFormat::Tsv),
+];
+
+const GRAPH_FORMATS: [(&str, TurtleSyntax); 4] = [
+    ("text/turtle", TurtleSyntax::Turtle),
+    ("application/trig", TurtleSyntax::TriG),
+    ("application/n-triples", TurtleSyntax::NTriples),
+    ("application/n-quads", TurtleSyntax::NQuads),
+];
+
+const DEFAULT_MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// SPARQL 1.1 Protocol and Graph Store HTTP Protocol endpoint over an
+/// in-memory [`Dataset`].
+///
+/// Queries and updates are served at `/sparql`, graphs (identified by the
+/// `default` or `graph` parameters) at `/store`. Requests are handled one at
+/// a time, so every request sees a consistent dataset.
+///
+/// `LOAD` updates are refused unless their file lies in a directory allowed
+/// with [`SparqlServer::with_load_directory`], and request bodies larger
+/// than [`SparqlServer::with_max_body_size`] are refused with 413.
+pub struct SparqlServer {
+    server: tiny_http::Server,
+    dataset: Arc<RwLock<Dataset>>,
+    load_directories: Vec<PathBuf>,
+    max_body_size: usize,
+}
+
+impl SparqlServer {
+    pub fn bind(address: impl ToSocketAddrs, dataset: Dataset) -> io::Result<Self> {
+        let server = tiny_http::Server::http(address).map_err(io::Error::other)?;
+        Ok(Self {
+            server,
+            dataset: Arc::new(RwLock::new(dataset)),
+            load_directories: Vec::new(),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        })
+    }
+
+    /// Allows `LOAD` of `file://` IRIs naming files under `directory`,
+    /// after resolving `..` and symbolic links.
+    pub fn with_load_directory(mut self, directory: impl AsRef<Path>) -> Self {
+        self.load_directories.push(directory.as_ref().to_owned());
+        self
+    }
+
+    /// Largest accepted request body in bytes, 16 MiB by default.
+    pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// The served dataset, e.g. to inspect it while the server runs.
+    pub fn dataset(&self) -> Arc<RwLock<Dataset>> {
+        Arc::clone(&self.dataset)
+    }
+
+    /// Serves requests until [`SparqlServer::shutdown`] is called.
+    pub fn run(&self) {
+        for mut request in self.server.incoming_requests() {
+            let response = self.handle(&mut request);
+            // The client may have gone away, which is no concern of ours.
+            let _ = request.respond(response);
+        }
+    }
+
+    pub fn shutdown(&self) {
+        self.server.unblock();
+    }
+
+    fn handle(&self, request: &mut Request) -> HttpResponse {
+        let (path, query_string) = match request.url().split_once('?') {
+            Some((path, query_string)) => (path.to_owned(), query_string.to_owned()),
+            None => (request.url().to_owned(), String::new()),
+        };
+        let parameters = parse_parameters(&query_string);
+
+        match path.as_str() {
+            "/sparql" => self.sparql(request, parameters),
+            "/store" => self.graph_store(request, &parameters),
+            _ => error(404, "not found"),
+        }
+    }
+
+    fn sparql(&self, request: &mut Request, mut parameters: Vec<(String, String)>) -> HttpResponse {
+        let content_type = media_type(request, "Content-Type");
+        match (request.method(), content_type.as_deref()) {
+            (Method::Get, _) => {}
+            (Method::Post, Some("application/x-www-form-urlencoded")) => match self.body(request) {
+                Ok(body) => parameters.extend(parse_parameters(&body)),
+                Err(response) => return response,
+            },
+            (Method::Post, Some("application/sparql-query")) => match self.body(request) {
+                Ok(body) => parameters.push(("query".to_owned(), body)),
+                Err(response) => return response,
+            },
+            (Method::Post, Some("application/sparql-update")) => match self.body(request) {
+                Ok(body) => parameters.push(("update".to_owned(), body)),
+                Err(response) => return response,
+            },
+            (Method::Post, _) => return error(415, "unsupported content type"),
+            _ => return error(405, "method not allowed"),
+        }
+
+        let queries = values(&parameters, "query");
+        let updates = values(&parameters, "update");
+        match (queries.as_slice(), updates.as_slice()) {
+            ([query], []) => self.query(request, query, &parameters),
+            ([], [update]) if *request.method() == Method::Post => self.update(update, &parameters),
+            ([], [_]) => error(405, "updates must be sent with POST"),
+            _ => error(400, "expected exactly one query or update"),
+        }
+    }
+
+    fn query(
+        &self,
+        request: &Request,
+        query: &str,
+        parameters: &[(String, String)],
+    ) -> HttpResponse {
+        let mut query = match parse_query(query, None) {
+            Ok(query) => query,
+            Err(syntax_error) => return error(400, &syntax_error.to_string()),
+        };
+        let default = graph_names(parameters, "default-graph-uri");
+        let named = graph_names(parameters, "named-graph-uri");
+        if !default.is_empty() || !named.is_empty() {
+            let (Query::Select { dataset, .. }
+            | Query::Construct { dataset, .. }
+            | Query::Describe { dataset, .. }
+            | Query::Ask { dataset, .. }) = &mut query;
+            *dataset = Some(QueryDataset { default, named });
+        }
+
+        let results = {
+            let dataset = self.dataset.read().expect("dataset lock poisoned");
+            match evaluate_query(&dataset, &query) {
+                Ok(results) => results,
+                Err(evaluation_error) => return error(500, &evaluation_error.to_string()),
+            }
+        };
+
+        let accept = header(request, "Accept");
+        match results {
+            QueryResults::Graph(triples) => {
+                let Some((media_type, syntax)) = negotiate(accept, &GRAPH_FORMATS) else {
+                    return error(406, "no acceptable RDF format");
+                };
+                serialize_graph(media_type, syntax, &triples)
+            }
+            results => {
+                let Some((media_type, format)) = negotiate(accept, &RESULT_FORMATS) else {
+                    return error(406, "no acceptable query results format");
+                };
+                let mut body = Vec::new();
+                match write_results(&mut body, &results, format) {
+                    Ok(()) => ok(body, media_type),
+                    Err(io_error) => error(500, &io_error.to_string()),
+                }
+            }
+        }
+    }
+
+    fn update(&self, update: &str, parameters: &[(String, String)]) -> HttpResponse {
+        let mut update = match parse_update(update, None) {
+            Ok(update) => update,
+            Err(syntax_error) => return error(400, &syntax_error.to_string()),
+        };
+
+        let default = graph_names(parameters, "using-graph-uri");
+        let named = graph_names(parameters, "using-named-graph-uri");
+        if !default.is_empty() || !named.is_empty() {
+            for operation in &mut update.operations {
+                if let UpdateOperation::DeleteInsert { with, using, .. } = operation {
+                    if with.is_some() || using.is_some() {
+                        return error(400, "USING and WITH conflict with the protocol dataset");
+                    }
+                    *using = Some(QueryDataset {
+                        default: default.clone(),
+                        named: named.clone(),
+                    });
+                }
+            }
+        }
+
+        for operation in &mut update.operations {
+            if let UpdateOperation::Load { source, .. } = operation {
+                match self.allowed_load_source(source) {
+                    // Load the checked file even if a link changes meanwhile.
+                    Some(path) => *source = NamedNode::new(&file_iri(&path)),
+                    None => {
+                        return error(403, &format!("LOAD of <{}> is not allowed", source.value()));
+                    }
+                }
+            }
+        }
+
+        self.execute(&update)
+    }
+
+    /// The resolved path of a `LOAD` source within the allowed directories.
+    fn allowed_load_source(&self, source: &NamedNode) -> Option<PathBuf> {
+        let path = std::fs::canonicalize(file_path(source.value())?).ok()?;
+        self.load_directories
+            .iter()
+            .filter_map(|directory| std::fs::canonicalize(directory).ok())
+            .any(|directory| path.starts_with(directory))
+            .then_some(path)
+    }
+
+    fn execute(&self, update: &Update) -> HttpResponse {
+        let mut dataset = self.dataset.write().expect("dataset lock poisoned");
+        match execute_update(&mut dataset, update) {
+            Ok(()) => Response::from_data(Vec::new()).with_status_code(204),
+            Err(update_error @ UpdateError::GraphExists(_)) => {
+                error(400, &update_error.to_string())
+            }
+            Err(update_error) => error(500, &update_error.to_string()),
+        }
+    }
+
+    fn graph_store(&self, request: &mut Request, parameters: &[(String, String)]) -> HttpResponse {
+        let graph = match (
+            values(parameters, "default").as_slice(),
+            graph_names(parameters, "graph").as_slice(),
+        ) {
+            ([_], []) => QuadGraph::DefaultGraph(DefaultGraph::new()),
+            ([], [graph]) => QuadGraph::NamedNode(graph.clone()),
+            _ => return error(400, "expected either the default or one graph parameter"),
+        };
+
+        let triples: Vec<Quad> = {
+            let dataset = self.dataset.read().expect("dataset lock poisoned");
+            dataset
+                .quads_for_pattern(None, None, None, Some(&graph))
+                .map(|quad| Quad::new(quad.subject(), quad.predicate(), quad.object(), None))
+                .collect()
+        };
+        let exists = !triples.is_empty() || matches!(graph, QuadGraph::DefaultGraph(_));
+        let target = match &graph {
+            QuadGraph::NamedNode(named_node) => GraphTarget::NamedNode(named_node.clone()),
+            _ => GraphTarget::DefaultGraph,
+        };
+
+        match request.method() {
+            Method::Get | Method::Head if !exists => error(404, "no such graph"),
+            Method::Get | Method::Head => {
+                let Some((media_type, syntax)) =
+                    negotiate(header(request, "Accept"), &GRAPH_FORMATS)
+                else {
+                    return error(406, "no acceptable RDF format");
+                };
+                serialize_graph(media_type, syntax, &triples)
+            }
+            Method::Delete if !exists => error(404, "no such graph"),
+            Method::Delete => self.execute(&Update {
+                operations: vec![UpdateOperation::Drop {
+                    silent: true,
+                    target,
+                }],
+                base_iri: None,
+            }),
+            Method::Put | Method::Post => {
+                let data = match self.read_graph(request, &graph) {
+                    Ok(data) => data,
+                    Err(response) => return response,
+                };
+                let mut operations = Vec::new();
+                if *request.method() == Method::Put {
+                    operations.push(UpdateOperation::Drop {
+                        silent: true,
+                        target,
+                    });
+                }
+                operations.push(UpdateOperation::InsertData { data });
+                let response = self.execute(&Update {
+                    operations,
+                    base_iri: None,
+                });
+                if exists || response.status_code() != 204 {
+                    response
+                } else {
+                    Response::from_data(Vec::new()).with_status_code(201)
+                }
+            }
+            _ => error(405, "method not allowed"),
+        }
+    }
+
+    /// Parses a request body holding the triples of `graph`.
+    fn read_graph(
+        &self,
+        request: &mut Request,
+        graph: &QuadGraph,
+    ) -> Result<Vec<Quad>, HttpResponse> {
+        let syntax = match media_type(request, "Content-Type").as_deref() {
+            Some("text/turtle" | "application/n-triples") | None => TurtleSyntax::Turtle,
+            Some(_) => return Err(error(415, "unsupported content type")),
+        };
+        let body = self.body(request)?;
+        let base_iri = match graph {
+            QuadGraph::NamedNode(named_node) => Some(named_node.value()),
+            _ => None,
+        };
+        let triples = parse_str(syntax, &body, base_iri)
+            .map_err(|syntax_error| error(400, &syntax_error.to_string()))?;
+        Ok(triples
+            .iter()
+            .map(|triple| {
+                Quad::new(
+                    triple.subject(),
+                    triple.predicate(),
+                    triple.object(),
+                    Some(graph),
+                )
+            })
+            .collect())
+    }
+
+    fn body(&self, request: &mut Request) -> Result<String, HttpResponse> {
+        let too_large = || error(413, "request body too large");
+        if request
+            .body_length()
+            .is_some_and(|length| length > self.max_body_size)
+        {
+            return Err(too_large());
+        }
+        let mut body = String::new();
+        request
+            .as_reader()
+            .take(self.max_body_size as u64 + 1)
+            .read_to_string(&mut body)
+            .map_err(|io_error| error(400, &io_error.to_string()))?;
+        if body.len() > self.max_body_size {
+            return Err(too_large());
+        }
+        Ok(body)
+    }
+}
+
+fn ok(body: Vec<u8>, media_type: &str) -> HttpResponse {
+    Response::from_data(body).with_header(content_type(media_type))
+}
+
+fn error(status: u16, message: &str) -> HttpResponse {
+    Response::from_data(format!("{message}\n").into_bytes())
+        .with_status_code(status)
+        .with_header(content_type("text/plain"))
+}
+
+fn content_type(media_type: &str) -> Header {
+    let value = format!("{media_type}; charset=utf-8");
+    Header::from_bytes("Content-Type", value).expect("media types are valid header values")
+}
+
+fn serialize_graph(media_type: &str, syntax: TurtleSyntax, triples: &[Quad]) -> HttpResponse {
+    let mut body = Vec::new();
+    match TurtleSerializer::new(syntax).serialize(&mut body, triples) {
+        Ok(()) => ok(body, media_type),
+        Err(io_error) => error(500, &io_error.to_string()),
+    }
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str())
+}
+
+/// The lowercased media type of a header, without parameters.
+fn media_type(request: &Request, name: &'static str) -> Option<String> {
+    let value = header(request, name)?;
+    Some(value.split(';').next()?.trim().to_ascii_lowercase())
+}
+
+/// Picks the offer the client prefers most, by `q` value and specificity of
+/// the matching range; ties go to the earlier offer. Without an `Accept`
+/// header the first offer is taken.
+fn negotiate<T: Copy>(
+    accept: Option<&str>,
+    offers: &[(&'static str, T)],
+) -> Option<(&'static str, T)> {
+    let Some(accept) = accept.filter(|accept| !accept.trim().is_empty()) else {
+        return offers.first().copied();
+    };
+
+    let ranges: Vec<(String, f32)> = accept
+        .split(',')
+        .map(|range| {
+            let mut parts = range.split(';');
+            let media_range = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+            let quality = parts
+                .filter_map(|parameter| parameter.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (media_range, quality)
+        })
+        .collect();
+
+    let mut best: Option<((&'static str, T), f32)> = None;
+    for &(media_type, value) in offers {
+        let main_type = media_type.split('/').next().unwrap_or("");
+        let quality = ranges
+            .iter()
+            .filter_map(|(range, quality)| {
+                let specificity = if range == media_type {
+                    2
+                } else if range.strip_suffix("/*") == Some(main_type) {
+                    1
+                } else if range == "*/*" {
+                    0
+                } else {
+                    return None;
+                };
+                Some((specificity, *quality))
+            })
+            .max_by_key(|(specificity, _)| *specificity)
+            .map(|(_, quality)| quality);
+        if let Some(quality) = quality
+            && quality > 0.0
+            && best.is_none_or(|(_, best_quality)| quality > best_quality)
+        {
+            best = Some(((media_type, value), quality));
+        }
+    }
+    best.map(|(offer, _)| offer)
+}
+
+fn values<'a>(parameters: &'a [(String, String)], name: &str) -> Vec<&'a str> {
+    parameters
+        .iter()
+        .filter(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+        .collect()
+}
+
+fn graph_names(parameters: &[(String, String)], name: &str) -> Vec<NamedNode> {
+    values(parameters, name)
+        .into_iter()
+        .map(NamedNode::new)
+        .collect()
+}
+
+/// Decodes an `application/x-www-form-urlencoded` string.
+fn parse_parameters(input: &str) -> Vec<(String, String)> {
+    input
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (decode(key), decode(value)),
+            None => (decode(pair), String::new()),
+        })
+        .collect()
+}
+
+fn decode(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if bytes.len() > i + 2
+                && bytes[i + 1].is_ascii_hexdigit()
+                && bytes[i + 2].is_ascii_hexdigit() =>
+            {
+                let hex = |digit: u8| (digit as char).to_digit(16).unwrap_or(0) as u8;
+                decoded.push(hex(bytes[i + 1]) << 4 | hex(bytes[i + 2]));
+                i += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::thread;
+
+    use super::*;
+
+    struct Reply {
+        status: u16,
+        content_type: String,
+        body: String,
+    }
+
+    fn send(address: SocketAddr, request: impl AsRef<[u8]>) -> Reply {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_ref()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head[9..12].parse().unwrap();
+        let content_type = head
+            .lines()
+            .find_map(|line| line.strip_prefix("Content-Type: "))
+            .unwrap_or("")
+            .to_owned();
+        Reply {
+            status,
+            content_type,
+            body: body.to_owned(),
+        }
+    }
+
+    fn request(method: &str, target: &str, headers: &[(&str, &str)], body: &str) -> String {
+        let mut request =
+            format!("{method} {target} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n");
+        for (name, value) in headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+        request.push_str(&format!("Content-Length: {}\r\n\r\n{body}", body.len()));
+        request
+    }
+
+    fn start(server: SparqlServer) -> (Arc<SparqlServer>, SocketAddr, thread::JoinHandle<()>) {
+        let server = Arc::new(server);
+        let address = server.local_addr().unwrap();
+        let handle = {
+            let server = Arc::clone(&server);
+            thread::spawn(move || server.run())
+        };
+        (server, address, handle)
+    }
+
+    #[test]
+    fn serves_queries_updates_and_graphs() {
+        let (server, address, handle) =
+            start(SparqlServer::bind("127.0.0.1:0", Dataset::new()).unwrap());
+
+        let turtle = "<http://example.org/a> <http://example.org/p> \"x\" .";
+        let reply = send(
+            address,
+            &request(
+                "PUT",
+                "/store?graph=http%3A%2F%2Fexample.org%2Fg",
+                &[("Content-Type", "text/turtle")],
+                turtle,
+            ),
+        );
+        assert_eq!(reply.status, 201);
+
+        let reply = send(
+            address,
+            &request(
+                "POST",
+                "/sparql",
+                &[("Content-Type", "application/x-www-form-urlencoded")],
+                "update=INSERT+DATA+%7B+%3Chttp%3A%2F%2Fexample.org%2Fb%3E+%3Chttp%3A%2F%2Fexample.org%2Fp%3E+1+%7D",
+            ),
+        );
+        assert_eq!(reply.status, 204);
+        assert_eq!(server.dataset().read().unwrap().len(), 2);
+
+        let reply = send(
+            address,
+            &request(
+                "GET",
+                "/sparql?query=SELECT+%3Fs+WHERE+%7B+GRAPH+%3Fg+%7B+%3Fs+%3Fp+%3Fo+%7D+%7D",
+                &[("Accept", "text/csv;q=0.5, application/sparql-results+xml")],
+                "",
+            ),
+        );
+        assert_eq!(reply.status, 200);
+        assert!(
+            reply
+                .content_type
+                .starts_with("application/sparql-results+xml")
+        );
+        assert!(reply.body.contains("<uri>http://example.org/a</uri>"));
+
+        let reply = send(
+            address,
+            &request(
+                "POST",
+                "/sparql?default-graph-uri=http%3A%2F%2Fexample.org%2Fg",
+                &[
+                    ("Content-Type", "application/sparql-query"),
+                    ("Accept", "*/*"),
+                ],
+                "ASK { ?s ?p \"x\" }",
+            ),
+        );
+        assert_eq!(reply.status, 200);
+        assert!(
+            reply
+                .content_type
+                .starts_with("application/sparql-results+json")
+        );
+        assert!(reply.body.contains("\"boolean\":true"));
+
+        let reply = send(address, &request("GET", "/store?default", &[], ""));
+        assert_eq!(reply.status, 200);
+        assert!(reply.content_type.starts_with("text/turtle"));
+        assert_eq!(
+            parse_str(TurtleSyntax::Turtle, &reply.body, None)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let reply = send(
+            address,
+            &request(
+                "GET",
+                "/store?default",
+                &[("Accept", "application/n-triples")],
+                "",
+            ),
+        );
+        assert_eq!(reply.status, 200);
+        assert!(reply.content_type.starts_with("application/n-triples"));
+        assert!(reply.body.ends_with(" .\n"));
+
+        let reply = send(
+            address,
+            &request(
+                "DELETE",
+                "/store?graph=http%3A%2F%2Fexample.org%2Fg",
+                &[],
+                "",
+            ),
+        );
+        assert_eq!(reply.status, 204);
+        let reply = send(
+            address,
+            &request("GET", "/store?graph=http%3A%2F%2Fexample.org%2Fg", &[], ""),
+        );
+        assert_eq!(reply.status, 404);
+
+        server.shutdown();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn rejects_bad_requests() {
+        let directory =
+            std::env::temp_dir().join(format!("rdfjs-rust-load-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("my data.ttl");
+        std::fs::write(&file, "<http://example.org/a> <http://example.org/p> 1 .").unwrap();
+        let (server, address, handle) = start(
+            SparqlServer::bind("127.0.0.1:0", Dataset::new())
+                .unwrap()
+                .with_load_directory(&directory)
+                .with_max_body_size(256),
+        );
+        let update = |update: &str| {
+            send(
+                address,
+                &request(
+                    "POST",
+                    "/sparql",
+                    &[("Content-Type", "application/sparql-update")],
+                    update,
+                ),
+            )
+        };
+
+        let reply = send(address, &request("GET", "/sparql?query=SELECT", &[], ""));
+        assert_eq!(reply.status, 400);
+        let reply = send(
+            address,
+            &request(
+                "GET",
+                "/sparql?query=ASK+%7B%7D",
+                &[("Accept", "image/png")],
+                "",
+            ),
+        );
+        assert_eq!(reply.status, 406);
+        let reply = send(
+            address,
+            &request("GET", "/store?default", &[("Accept", "image/png")], ""),
+        );
+        assert_eq!(reply.status, 406);
+        let reply = send(
+            address,
+            &request(
+                "POST",
+                "/sparql",
+                &[("Content-Type", "text/plain")],
+                "ASK {}",
+            ),
+        );
+        assert_eq!(reply.status, 415);
+        let reply = send(
+            address,
+            &request(
+                "PUT",
+                "/store?default",
+                &[("Content-Type", "image/png")],
+                "",
+            ),
+        );
+        assert_eq!(reply.status, 415);
+        let reply = send(
+            address,
+            &request(
+                "POST",
+                "/sparql",
+                &[("Content-Type", "application/sparql-query")],
+                &format!("ASK {{ {} }}", "?s ?p ?o . ".repeat(30)),
+            ),
+        );
+        assert_eq!(reply.status, 413);
+
+        assert_eq!(
+            update("LOAD <file:///etc/passwd> INTO GRAPH <x:g>").status,
+            403
+        );
+        // Sources are percent-decoded before they are checked.
+        let outside = format!("LOAD <file://{}/%2E%2E/etc/passwd>", directory.display());
+        assert_eq!(update(&outside).status, 403);
+        let inside = format!(
+            "LOAD <file://{}/../{}/my%20data.ttl>",
+            directory.display(),
+            directory.file_name().unwrap().to_str().unwrap()
+        );
+        assert_eq!(update(&inside).status, 204);
+        assert_eq!(server.dataset().read().unwrap().len(), 1);
+
+        server.shutdown();
+        handle.join().unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn status_codes_follow_the_protocols() {
+        let (server, address, handle) = start(
+            SparqlServer::bind("127.0.0.1:0", Dataset::new())
+                .unwrap()
+                .with_max_body_size(256),
+        );
+        let post = |target: &str, content_type: &str, body: &str| {
+            send(
+                address,
+                &request("POST", target, &[("Content-Type", content_type)], body),
+            )
+            .status
+        };
+        let get = |target: &str| send(address, &request("GET", target, &[], "")).status;
+
+        assert_eq!(get("/other"), 404);
+        assert_eq!(get("/sparql"), 400);
+        assert_eq!(get("/sparql?query=ASK+%7B%7D&update=CLEAR+ALL"), 400);
+        assert_eq!(get("/sparql?query=ASK+%7B%7D&query=ASK+%7B%7D"), 400);
+        assert_eq!(get("/sparql?update=CLEAR+ALL"), 405);
+        assert_eq!(
+            send(address, &request("DELETE", "/sparql", &[], "")).status,
+            405
+        );
+        assert_eq!(
+            post(
+                "/sparql",
+                "application/x-www-form-urlencoded",
+                "query=SELECT+%3Fs+WHERE"
+            ),
+            400
+        );
+        assert_eq!(
+            post("/sparql", "application/sparql-update", "INSERT { }"),
+            400
+        );
+        assert_eq!(
+            post(
+                "/sparql?using-graph-uri=http%3A%2F%2Fexample.org%2Fg",
+                "application/sparql-update",
+                "WITH <http://example.org/h> DELETE { ?s ?p ?o } WHERE { ?s ?p ?o }"
+            ),
+            400
+        );
+
+        assert_eq!(
+            post(
+                "/sparql",
+                "application/sparql-update",
+                "INSERT DATA { GRAPH <http://example.org/g> { <http://example.org/a> <http://example.org/p> 1 } }"
+            ),
+            204
+        );
+        assert_eq!(
+            post(
+                "/sparql",
+                "application/sparql-update",
+                "CREATE GRAPH <http://example.org/g>"
+            ),
+            400
+        );
+
+        assert_eq!(get("/store"), 400);
+        assert_eq!(get("/store?graph=http%3A%2F%2Fexample.org%2Fmissing"), 404);
+        assert_eq!(
+            send(
+                address,
+                &request(
+                    "PUT",
+                    "/store?graph=http%3A%2F%2Fexample.org%2Fg",
+                    &[("Content-Type", "text/turtle")],
+                    "<a> <b>",
+                ),
+            )
+            .status,
+            400
+        );
+
+        // Bodies over the limit are refused whether or not their length is
+        // announced, and so are bodies that are not UTF-8.
+        let large = "x".repeat(300);
+        assert_eq!(
+            post("/sparql", "application/x-www-form-urlencoded", &large),
+            413
+        );
+        assert_eq!(
+            send(
+                address,
+                &request(
+                    "PUT",
+                    "/store?default",
+                    &[("Content-Type", "text/turtle")],
+                    &large
+                ),
+            )
+            .status,
+            413
+        );
+        let chunked = format!(
+            "POST /sparql HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+             Content-Type: application/sparql-query\r\nTransfer-Encoding: chunked\r\n\r\n\
+             {:x}\r\n{large}\r\n0\r\n\r\n",
+            large.len()
+        );
+        assert_eq!(send(address, &chunked).status, 413);
+        let mut invalid = request(
+            "POST",
+            "/sparql",
+            &[("Content-Type", "application/sparql-query")],
+            "ASK {}",
+        )
+        .into_bytes();
+        let length = invalid.len();
+        invalid[length - 2] = 0xff;
+        assert_eq!(send(address, &invalid).status, 400);
+
+        assert_eq!(server.dataset().read().unwrap().len(), 1);
+        server.shutdown();
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,16 @@
+pub mod algebra;
+pub mod eval;
+pub(crate) mod expression;
+pub mod parser;
+mod printer;
+pub mod results;
+pub mod update;
+
+pub use algebra::{
+    AggregateExpression, AggregateFunction, Expression, Function, GraphPattern, GraphTarget,
+    NamedNodePattern, OrderExpression, PropertyPath, Query, QueryDataset, Update, UpdateOperation,
+};
+pub use eval::{EvaluationError, QueryResults, evaluate_query};
+pub use parser::{parse_query, parse_update};
+pub use results::{QueryResultsFormat, read_results, write_results};
+pub use update::{UpdateError, execute_update};
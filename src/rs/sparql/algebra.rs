@@ -0,0 +1,597 @@
+use std::collections::BTreeSet;
+
+use crate::rs::literal::Literal;
+use crate::rs::named_node::NamedNode;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::variable::Variable;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Query {
+    Select {
+        dataset: Option<QueryDataset>,
+        pattern: GraphPattern,
+        base_iri: Option<String>,
+    },
+    /// `template` holds triple patterns, i.e. quads in the default graph.
+    Construct {
+        template: Vec<Quad>,
+        dataset: Option<QueryDataset>,
+        pattern: GraphPattern,
+        base_iri: Option<String>,
+    },
+    /// An empty `targets` list stands for `DESCRIBE *`.
+    Describe {
+        targets: Vec<NamedNodePattern>,
+        dataset: Option<QueryDataset>,
+        pattern: GraphPattern,
+        base_iri: Option<String>,
+    },
+    Ask {
+        dataset: Option<QueryDataset>,
+        pattern: GraphPattern,
+        base_iri: Option<String>,
+    },
+}
+
+impl Query {
+    pub fn pattern(&self) -> &GraphPattern {
+        match self {
+            Query::Select { pattern, .. }
+            | Query::Construct { pattern, .. }
+            | Query::Describe { pattern, .. }
+            | Query::Ask { pattern, .. } => pattern,
+        }
+    }
+
+    pub fn dataset(&self) -> Option<&QueryDataset> {
+        match self {
+            Query::Select { dataset, .. }
+            | Query::Construct { dataset, .. }
+            | Query::Describe { dataset, .. }
+            | Query::Ask { dataset, .. } => dataset.as_ref(),
+        }
+    }
+
+    pub fn base_iri(&self) -> Option<&str> {
+        match self {
+            Query::Select { base_iri, .. }
+            | Query::Construct { base_iri, .. }
+            | Query::Describe { base_iri, .. }
+            | Query::Ask { base_iri, .. } => base_iri.as_deref(),
+        }
+    }
+}
+
+/// A SPARQL 1.1 Update request, its operations being applied in order.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Update {
+    pub operations: Vec<UpdateOperation>,
+    pub base_iri: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum UpdateOperation {
+    InsertData {
+        data: Vec<Quad>,
+    },
+    DeleteData {
+        data: Vec<Quad>,
+    },
+    /// `DELETE`/`INSERT` ... `WHERE`, including `DELETE WHERE`. Template
+    /// quads in the default graph go to the `with` graph if there is one,
+    /// which is also the default graph of `pattern` unless `using` is given.
+    DeleteInsert {
+        with: Option<NamedNode>,
+        delete: Vec<Quad>,
+        insert: Vec<Quad>,
+        using: Option<QueryDataset>,
+        pattern: Box<GraphPattern>,
+    },
+    Load {
+        silent: bool,
+        source: NamedNode,
+        destination: QuadGraph,
+    },
+    Clear {
+        silent: bool,
+        target: GraphTarget,
+    },
+    Create {
+        silent: bool,
+        graph: NamedNode,
+    },
+    Drop {
+        silent: bool,
+        target: GraphTarget,
+    },
+    Copy {
+        silent: bool,
+        source: QuadGraph,
+        destination: QuadGraph,
+    },
+    Move {
+        silent: bool,
+        source: QuadGraph,
+        destination: QuadGraph,
+    },
+    Add {
+        silent: bool,
+        source: QuadGraph,
+        destination: QuadGraph,
+    },
+}
+
+/// The graphs affected by `CLEAR` and `DROP`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GraphTarget {
+    NamedNode(NamedNode),
+    DefaultGraph,
+    /// All named graphs.
+    Named,
+    /// The default graph and all named graphs.
+    All,
+}
+
+/// The `FROM` and `FROM NAMED` clauses of a query.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct QueryDataset {
+    pub default: Vec<NamedNode>,
+    pub named: Vec<NamedNode>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum NamedNodePattern {
+    NamedNode(NamedNode),
+    Variable(Variable),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GraphPattern {
+    /// Triple patterns, i.e. quads in the default graph; the active graph is
+    /// given by an enclosing [`GraphPattern::Graph`], if any.
+    Bgp {
+        patterns: Vec<Quad>,
+    },
+    Path {
+        subject: Term,
+        path: PropertyPath,
+        object: Term,
+    },
+    Join {
+        left: Box<GraphPattern>,
+        right: Box<GraphPattern>,
+    },
+    LeftJoin {
+        left: Box<GraphPattern>,
+        right: Box<GraphPattern>,
+        expression: Option<Expression>,
+    },
+    Filter {
+        expression: Expression,
+        inner: Box<GraphPattern>,
+    },
+    Union {
+        left: Box<GraphPattern>,
+        right: Box<GraphPattern>,
+    },
+    Graph {
+        name: NamedNodePattern,
+        inner: Box<GraphPattern>,
+    },
+    Extend {
+        inner: Box<GraphPattern>,
+        variable: Variable,
+        expression: Expression,
+    },
+    Minus {
+        left: Box<GraphPattern>,
+        right: Box<GraphPattern>,
+    },
+    /// `None` entries are `UNDEF`.
+    Values {
+        variables: Vec<Variable>,
+        bindings: Vec<Vec<Option<Term>>>,
+    },
+    OrderBy {
+        inner: Box<GraphPattern>,
+        expression: Vec<OrderExpression>,
+    },
+    Project {
+        inner: Box<GraphPattern>,
+        variables: Vec<Variable>,
+    },
+    Distinct {
+        inner: Box<GraphPattern>,
+    },
+    Reduced {
+        inner: Box<GraphPattern>,
+    },
+    Slice {
+        inner: Box<GraphPattern>,
+        start: usize,
+        length: Option<usize>,
+    },
+    /// Aggregates are bound to (fresh) variables which the expressions above
+    /// the group refer to.
+    Group {
+        inner: Box<GraphPattern>,
+        variables: Vec<Variable>,
+        aggregates: Vec<(Variable, AggregateExpression)>,
+    },
+    Service {
+        name: NamedNodePattern,
+        inner: Box<GraphPattern>,
+        silent: bool,
+    },
+}
+
+impl GraphPattern {
+    /// The empty group pattern, which has a single empty solution.
+    pub fn empty() -> Self {
+        GraphPattern::Bgp {
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Variables that may be bound by solutions of this pattern, in order of
+    /// first appearance.
+    pub fn in_scope_variables(&self) -> Vec<Variable> {
+        let mut variables = Vec::new();
+        self.collect_in_scope(&mut variables);
+        variables
+    }
+
+    fn collect_in_scope(&self, variables: &mut Vec<Variable>) {
+        let mut add = |variable: &Variable| {
+            if !variables.contains(variable) {
+                variables.push(variable.clone());
+            }
+        };
+        match self {
+            GraphPattern::Bgp { patterns } => {
+                for pattern in patterns {
+                    for variable in quad_variables(pattern) {
+                        add(&variable);
+                    }
+                }
+            }
+            GraphPattern::Path {
+                subject, object, ..
+            } => {
+                for term in [subject, object] {
+                    for variable in term_variables(term) {
+                        add(&variable);
+                    }
+                }
+            }
+            GraphPattern::Join { left, right }
+            | GraphPattern::LeftJoin { left, right, .. }
+            | GraphPattern::Union { left, right } => {
+                left.collect_in_scope(variables);
+                right.collect_in_scope(variables);
+            }
+            GraphPattern::Graph { name, inner } => {
+                if let NamedNodePattern::Variable(variable) = name {
+                    add(variable);
+                }
+                inner.collect_in_scope(variables);
+            }
+            GraphPattern::Extend {
+                inner, variable, ..
+            } => {
+                inner.collect_in_scope(variables);
+                if !variables.contains(variable) {
+                    variables.push(variable.clone());
+                }
+            }
+            GraphPattern::Values {
+                variables: names, ..
+            } => {
+                for variable in names {
+                    add(variable);
+                }
+            }
+            GraphPattern::Project {
+                variables: names, ..
+            } => {
+                for variable in names {
+                    add(variable);
+                }
+            }
+            GraphPattern::Group {
+                variables: names,
+                aggregates,
+                ..
+            } => {
+                for variable in names {
+                    add(variable);
+                }
+                for (variable, _) in aggregates {
+                    add(variable);
+                }
+            }
+            GraphPattern::Minus { left: inner, .. }
+            | GraphPattern::Filter { inner, .. }
+            | GraphPattern::OrderBy { inner, .. }
+            | GraphPattern::Distinct { inner }
+            | GraphPattern::Reduced { inner }
+            | GraphPattern::Slice { inner, .. }
+            | GraphPattern::Service { inner, .. } => inner.collect_in_scope(variables),
+        }
+    }
+}
+
+fn quad_variables(pattern: &Quad) -> Vec<Variable> {
+    let mut variables = Vec::new();
+    match pattern.subject() {
+        QuadSubject::Variable(variable) => variables.push(variable.clone()),
+        QuadSubject::Quad(quad) => variables.extend(quad_variables(quad)),
+        _ => {}
+    }
+    if let QuadPredicate::Variable(variable) = pattern.predicate() {
+        variables.push(variable.clone());
+    }
+    if let QuadObject::Variable(variable) = pattern.object() {
+        variables.push(variable.clone());
+    }
+    variables
+}
+
+fn term_variables(term: &Term) -> Vec<Variable> {
+    match term {
+        Term::Variable(variable) => vec![variable.clone()],
+        Term::Quad(quad) => quad_variables(quad),
+        _ => Vec::new(),
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PropertyPath {
+    NamedNode(NamedNode),
+    Reverse(Box<PropertyPath>),
+    Sequence(Box<PropertyPath>, Box<PropertyPath>),
+    Alternative(Box<PropertyPath>, Box<PropertyPath>),
+    ZeroOrMore(Box<PropertyPath>),
+    OneOrMore(Box<PropertyPath>),
+    ZeroOrOne(Box<PropertyPath>),
+    /// Matches any predicate not in the list.
+    NegatedPropertySet(Vec<NamedNode>),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Expression {
+    NamedNode(NamedNode),
+    Literal(Literal),
+    Variable(Variable),
+    Or(Box<Expression>, Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Equal(Box<Expression>, Box<Expression>),
+    Greater(Box<Expression>, Box<Expression>),
+    GreaterOrEqual(Box<Expression>, Box<Expression>),
+    Less(Box<Expression>, Box<Expression>),
+    LessOrEqual(Box<Expression>, Box<Expression>),
+    In(Box<Expression>, Vec<Expression>),
+    Add(Box<Expression>, Box<Expression>),
+    Subtract(Box<Expression>, Box<Expression>),
+    Multiply(Box<Expression>, Box<Expression>),
+    Divide(Box<Expression>, Box<Expression>),
+    UnaryPlus(Box<Expression>),
+    UnaryMinus(Box<Expression>),
+    Not(Box<Expression>),
+    Exists(Box<GraphPattern>),
+    Bound(Variable),
+    If(Box<Expression>, Box<Expression>, Box<Expression>),
+    Coalesce(Vec<Expression>),
+    FunctionCall(Function, Vec<Expression>),
+}
+
+impl Expression {
+    /// Names of the variables the expression refers to.
+    pub fn variables(&self) -> BTreeSet<String> {
+        let mut variables = BTreeSet::new();
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables(&self, variables: &mut BTreeSet<String>) {
+        match self {
+            Expression::NamedNode(_) | Expression::Literal(_) => {}
+            Expression::Variable(variable) | Expression::Bound(variable) => {
+                variables.insert(variable.value().to_owned());
+            }
+            Expression::Or(a, b)
+            | Expression::And(a, b)
+            | Expression::Equal(a, b)
+            | Expression::Greater(a, b)
+            | Expression::GreaterOrEqual(a, b)
+            | Expression::Less(a, b)
+            | Expression::LessOrEqual(a, b)
+            | Expression::Add(a, b)
+            | Expression::Subtract(a, b)
+            | Expression::Multiply(a, b)
+            | Expression::Divide(a, b) => {
+                a.collect_variables(variables);
+                b.collect_variables(variables);
+            }
+            Expression::In(a, list) => {
+                a.collect_variables(variables);
+                for e in list {
+                    e.collect_variables(variables);
+                }
+            }
+            Expression::UnaryPlus(a) | Expression::UnaryMinus(a) | Expression::Not(a) => {
+                a.collect_variables(variables)
+            }
+            Expression::Exists(pattern) => variables.extend(
+                pattern
+                    .in_scope_variables()
+                    .iter()
+                    .map(|variable| variable.value().to_owned()),
+            ),
+            Expression::If(a, b, c) => {
+                a.collect_variables(variables);
+                b.collect_variables(variables);
+                c.collect_variables(variables);
+            }
+            Expression::Coalesce(list) | Expression::FunctionCall(_, list) => {
+                for e in list {
+                    e.collect_variables(variables);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Function {
+    Str,
+    Lang,
+    LangMatches,
+    LangDir,
+    Datatype,
+    Iri,
+    BNode,
+    Rand,
+    Abs,
+    Ceil,
+    Floor,
+    Round,
+    Concat,
+    SubStr,
+    StrLen,
+    Replace,
+    UCase,
+    LCase,
+    EncodeForUri,
+    Contains,
+    StrStarts,
+    StrEnds,
+    StrBefore,
+    StrAfter,
+    Year,
+    Month,
+    Day,
+    Hours,
+    Minutes,
+    Seconds,
+    Timezone,
+    Tz,
+    Now,
+    Uuid,
+    StrUuid,
+    Md5,
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+    StrLang,
+    StrLangDir,
+    StrDt,
+    IsIri,
+    IsBlank,
+    IsLiteral,
+    IsNumeric,
+    HasLang,
+    HasLangDir,
+    Regex,
+    SameTerm,
+    Triple,
+    Subject,
+    Predicate,
+    Object,
+    IsTriple,
+    Custom(NamedNode),
+}
+
+/// Built-in functions with their SPARQL keyword and allowed arity.
+pub(crate) const BUILT_IN_FUNCTIONS: &[(&str, Function, usize, usize)] = &[
+    ("STR", Function::Str, 1, 1),
+    ("LANG", Function::Lang, 1, 1),
+    ("LANGMATCHES", Function::LangMatches, 2, 2),
+    ("LANGDIR", Function::LangDir, 1, 1),
+    ("DATATYPE", Function::Datatype, 1, 1),
+    ("IRI", Function::Iri, 1, 1),
+    ("URI", Function::Iri, 1, 1),
+    ("BNODE", Function::BNode, 0, 1),
+    ("RAND", Function::Rand, 0, 0),
+    ("ABS", Function::Abs, 1, 1),
+    ("CEIL", Function::Ceil, 1, 1),
+    ("FLOOR", Function::Floor, 1, 1),
+    ("ROUND", Function::Round, 1, 1),
+    ("CONCAT", Function::Concat, 0, usize::MAX),
+    ("SUBSTR", Function::SubStr, 2, 3),
+    ("STRLEN", Function::StrLen, 1, 1),
+    ("REPLACE", Function::Replace, 3, 4),
+    ("UCASE", Function::UCase, 1, 1),
+    ("LCASE", Function::LCase, 1, 1),
+    ("ENCODE_FOR_URI", Function::EncodeForUri, 1, 1),
+    ("CONTAINS", Function::Contains, 2, 2),
+    ("STRSTARTS", Function::StrStarts, 2, 2),
+    ("STRENDS", Function::StrEnds, 2, 2),
+    ("STRBEFORE", Function::StrBefore, 2, 2),
+    ("STRAFTER", Function::StrAfter, 2, 2),
+    ("YEAR", Function::Year, 1, 1),
+    ("MONTH", Function::Month, 1, 1),
+    ("DAY", Function::Day, 1, 1),
+    ("HOURS", Function::Hours, 1, 1),
+    ("MINUTES", Function::Minutes, 1, 1),
+    ("SECONDS", Function::Seconds, 1, 1),
+    ("TIMEZONE", Function::Timezone, 1, 1),
+    ("TZ", Function::Tz, 1, 1),
+    ("NOW", Function::Now, 0, 0),
+    ("UUID", Function::Uuid, 0, 0),
+    ("STRUUID", Function::StrUuid, 0, 0),
+    ("MD5", Function::Md5, 1, 1),
+    ("SHA1", Function::Sha1, 1, 1),
+    ("SHA256", Function::Sha256, 1, 1),
+    ("SHA384", Function::Sha384, 1, 1),
+    ("SHA512", Function::Sha512, 1, 1),
+    ("STRLANG", Function::StrLang, 2, 2),
+    ("STRLANGDIR", Function::StrLangDir, 3, 3),
+    ("STRDT", Function::StrDt, 2, 2),
+    ("ISIRI", Function::IsIri, 1, 1),
+    ("ISURI", Function::IsIri, 1, 1),
+    ("ISBLANK", Function::IsBlank, 1, 1),
+    ("ISLITERAL", Function::IsLiteral, 1, 1),
+    ("ISNUMERIC", Function::IsNumeric, 1, 1),
+    ("HASLANG", Function::HasLang, 1, 1),
+    ("HASLANGDIR", Function::HasLangDir, 1, 1),
+    ("REGEX", Function::Regex, 2, 3),
+    ("SAMETERM", Function::SameTerm, 2, 2),
+    ("TRIPLE", Function::Triple, 3, 3),
+    ("SUBJECT", Function::Subject, 1, 1),
+    ("PREDICATE", Function::Predicate, 1, 1),
+    ("OBJECT", Function::Object, 1, 1),
+    ("ISTRIPLE", Function::IsTriple, 1, 1),
+];
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum OrderExpression {
+    Asc(Expression),
+    Desc(Expression),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AggregateExpression {
+    pub function: AggregateFunction,
+    /// `None` for `COUNT(*)`.
+    pub expression: Option<Box<Expression>>,
+    pub distinct: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Sample,
+    GroupConcat { separator: Option<String> },
+}
@@ -0,0 +1,1405 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::rs::bgp::evaluate_bgp_with;
+use crate::rs::blank_node::BlankNode;
+use crate::rs::dataset::Dataset;
+use crate::rs::default_graph::DefaultGraph;
+use crate::rs::literal::Literal;
+use crate::rs::named_node::NamedNode;
+use crate::rs::pattern::{Bindings, substitute};
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::sparql::algebra::{
+    AggregateExpression, AggregateFunction, Expression, GraphPattern, NamedNodePattern,
+    OrderExpression, PropertyPath, Query, QueryDataset,
+};
+use crate::rs::sparql::expression::{
+    FunctionContext, Numeric, arithmetic, boolean_term, call_function, compare,
+    effective_boolean_value, equals, negate, numeric, order_terms,
+};
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::variable::Variable;
+
+/// The outcome of [`evaluate_query`], depending on the query form.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum QueryResults {
+    /// SELECT: the projected variables and one [`Bindings`] per solution.
+    Solutions {
+        variables: Vec<Variable>,
+        solutions: Vec<Bindings>,
+    },
+    /// ASK.
+    Boolean(bool),
+    /// CONSTRUCT and DESCRIBE: triples in the default graph.
+    Graph(Vec<Quad>),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum EvaluationError {
+    /// Federated queries are not supported, only `SERVICE SILENT` is.
+    UnsupportedService(String),
+}
+
+impl Display for EvaluationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvaluationError::UnsupportedService(name) => {
+                write!(f, "SERVICE {name} is not supported")
+            }
+        }
+    }
+}
+
+impl Error for EvaluationError {}
+
+/// Evaluates `query` against `dataset`.
+///
+/// Without a FROM clause the default graph of the query is the default graph
+/// of `dataset` and GRAPH ranges over all its other graphs. FROM graphs are
+/// merged into the default graph and FROM NAMED restricts GRAPH to the given
+/// graphs.
+pub fn evaluate_query(dataset: &Dataset, query: &Query) -> Result<QueryResults, EvaluationError> {
+    let evaluator = Evaluator::new(dataset, query);
+    let pattern = query.pattern();
+    let solutions = evaluator.eval(pattern, &ActiveGraph::Default, &Bindings::new())?;
+
+    Ok(match query {
+        Query::Select { .. } => QueryResults::Solutions {
+            variables: projected_variables(pattern),
+            solutions,
+        },
+        Query::Ask { .. } => QueryResults::Boolean(!solutions.is_empty()),
+        Query::Construct { template, .. } => {
+            QueryResults::Graph(evaluator.construct(template, &solutions))
+        }
+        Query::Describe { targets, .. } => {
+            QueryResults::Graph(evaluator.describe(targets, pattern, &solutions))
+        }
+    })
+}
+
+/// Evaluates a graph pattern on its own, e.g. the WHERE clause of an
+/// update, with the given default graphs (merged) and named graphs.
+pub(crate) fn evaluate_pattern(
+    dataset: &Dataset,
+    pattern: &GraphPattern,
+    default_graphs: Vec<QuadGraph>,
+    named_graphs: Vec<QuadGraph>,
+    base_iri: Option<&str>,
+) -> Result<Vec<Bindings>, EvaluationError> {
+    Evaluator::with_graphs(dataset, default_graphs, named_graphs, base_iri).eval(
+        pattern,
+        &ActiveGraph::Default,
+        &Bindings::new(),
+    )
+}
+
+/// The graphs of `dataset` other than the default graph.
+pub(crate) fn named_graphs(dataset: &Dataset) -> Vec<QuadGraph> {
+    dataset
+        .graphs()
+        .into_iter()
+        .filter(|graph| !matches!(graph, QuadGraph::DefaultGraph(_)))
+        .collect()
+}
+
+/// The default and named graphs listed by FROM/USING clauses.
+pub(crate) fn graphs_of(description: &QueryDataset) -> (Vec<QuadGraph>, Vec<QuadGraph>) {
+    let graph = |named_node: &NamedNode| QuadGraph::NamedNode(named_node.clone());
+    (
+        description.default.iter().map(graph).collect(),
+        description.named.iter().map(graph).collect(),
+    )
+}
+
+fn projected_variables(pattern: &GraphPattern) -> Vec<Variable> {
+    match pattern {
+        GraphPattern::Slice { inner, .. }
+        | GraphPattern::Distinct { inner }
+        | GraphPattern::Reduced { inner } => projected_variables(inner),
+        GraphPattern::Project { variables, .. } => variables.clone(),
+        pattern => pattern.in_scope_variables(),
+    }
+}
+
+/// The graph triple patterns are matched against.
+#[derive(Clone)]
+enum ActiveGraph {
+    Default,
+    Named(QuadGraph),
+}
+
+struct Evaluator<'a> {
+    dataset: &'a Dataset,
+    default_graphs: Vec<QuadGraph>,
+    named_graphs: Vec<QuadGraph>,
+    functions: FunctionContext,
+}
+
+/// Variables introduced for blank nodes and graphs of patterns. Their names
+/// cannot clash with SPARQL variable names.
+fn is_internal(name: &str) -> bool {
+    name.starts_with("_:") || name.starts_with('#')
+}
+
+fn blank_node_variable(blank_node: &BlankNode) -> Variable {
+    Variable::new(&format!("_:{}", blank_node.value()))
+}
+
+fn strip_internal(solution: &mut Bindings) {
+    let internal: Vec<Variable> = solution
+        .variables()
+        .filter(|variable| is_internal(variable.value()))
+        .collect();
+    for variable in internal {
+        solution.remove(&variable);
+    }
+}
+
+fn deduplicate(solutions: Vec<Bindings>) -> Vec<Bindings> {
+    let mut seen = HashSet::new();
+    solutions
+        .into_iter()
+        .filter(|solution| seen.insert(solution.clone()))
+        .collect()
+}
+
+/// Turns the blank nodes of a triple pattern into variables and moves it
+/// into `graph`.
+fn to_pattern(quad: &Quad, graph: &QuadGraph) -> Quad {
+    let subject = match quad.subject() {
+        QuadSubject::BlankNode(blank_node) => {
+            QuadSubject::Variable(blank_node_variable(blank_node))
+        }
+        QuadSubject::Quad(inner) => QuadSubject::Quad(Box::new(to_pattern(
+            inner,
+            &QuadGraph::DefaultGraph(DefaultGraph::new()),
+        ))),
+        subject => subject.clone(),
+    };
+    let object = match quad.object() {
+        QuadObject::BlankNode(blank_node) => QuadObject::Variable(blank_node_variable(blank_node)),
+        object => object.clone(),
+    };
+    Quad::new(&subject, quad.predicate(), &object, Some(graph))
+}
+
+/// A path end: either a constant or a variable still to be bound.
+fn endpoint(term: &Term, seed: &Bindings) -> Result<Term, Variable> {
+    let variable = match term {
+        Term::Variable(variable) => variable.clone(),
+        Term::BlankNode(blank_node) => blank_node_variable(blank_node),
+        term => return Ok(term.clone()),
+    };
+    seed.get_variable(&variable).cloned().ok_or(variable)
+}
+
+fn bind_endpoint(solution: &mut Bindings, endpoint: &Result<Term, Variable>, term: &Term) -> bool {
+    match endpoint {
+        Ok(constant) => constant == term,
+        Err(variable) => solution.bind(variable, term),
+    }
+}
+
+fn is_ground(quad: &Quad) -> bool {
+    let subject = match quad.subject() {
+        QuadSubject::Variable(_) => false,
+        QuadSubject::Quad(inner) => is_ground(inner),
+        _ => true,
+    };
+    subject
+        && !matches!(quad.predicate(), QuadPredicate::Variable(_))
+        && !matches!(quad.object(), QuadObject::Variable(_))
+}
+
+impl<'a> Evaluator<'a> {
+    fn new(dataset: &'a Dataset, query: &Query) -> Self {
+        let (default_graphs, named_graphs) = match query.dataset() {
+            Some(description) => graphs_of(description),
+            None => (
+                vec![QuadGraph::DefaultGraph(DefaultGraph::new())],
+                named_graphs(dataset),
+            ),
+        };
+        Self::with_graphs(dataset, default_graphs, named_graphs, query.base_iri())
+    }
+
+    fn with_graphs(
+        dataset: &'a Dataset,
+        default_graphs: Vec<QuadGraph>,
+        named_graphs: Vec<QuadGraph>,
+        base_iri: Option<&str>,
+    ) -> Self {
+        Self {
+            dataset,
+            default_graphs,
+            named_graphs,
+            functions: FunctionContext::new(base_iri),
+        }
+    }
+
+    fn graphs(&self, graph: &ActiveGraph) -> Vec<QuadGraph> {
+        match graph {
+            ActiveGraph::Default => self.default_graphs.clone(),
+            ActiveGraph::Named(graph) => vec![graph.clone()],
+        }
+    }
+
+    fn eval(
+        &self,
+        pattern: &GraphPattern,
+        graph: &ActiveGraph,
+        seed: &Bindings,
+    ) -> Result<Vec<Bindings>, EvaluationError> {
+        Ok(match pattern {
+            GraphPattern::Bgp { patterns } => self.eval_bgp(patterns, graph, seed),
+            GraphPattern::Path {
+                subject,
+                path,
+                object,
+            } => self.eval_path(subject, path, object, graph, seed),
+            GraphPattern::Join { left, right } => {
+                let left = self.eval(left, graph, seed)?;
+                if is_substitutable(right) {
+                    let mut solutions = Vec::new();
+                    for solution in &left {
+                        solutions.extend(self.eval(right, graph, solution)?);
+                    }
+                    solutions
+                } else {
+                    let right = self.eval(right, graph, seed)?;
+                    left.iter()
+                        .flat_map(|l| right.iter().filter_map(|r| l.merge(r)))
+                        .collect()
+                }
+            }
+            GraphPattern::LeftJoin {
+                left,
+                right,
+                expression,
+            } => {
+                let left = self.eval(left, graph, seed)?;
+                let right_solutions = if is_substitutable(right) {
+                    None
+                } else {
+                    Some(self.eval(right, graph, seed)?)
+                };
+
+                let mut solutions = Vec::new();
+                for l in left {
+                    let candidates = match &right_solutions {
+                        Some(right) => right.iter().filter_map(|r| l.merge(r)).collect(),
+                        None => self.eval(right, graph, &l)?,
+                    };
+                    let before = solutions.len();
+                    solutions.extend(candidates.into_iter().filter(|candidate| {
+                        expression
+                            .as_ref()
+                            .is_none_or(|expression| self.holds(expression, candidate, graph))
+                    }));
+                    if solutions.len() == before {
+                        solutions.push(l);
+                    }
+                }
+                solutions
+            }
+            GraphPattern::Filter { expression, inner } => self
+                .eval(inner, graph, seed)?
+                .into_iter()
+                .filter(|solution| self.holds(expression, solution, graph))
+                .collect(),
+            GraphPattern::Union { left, right } => {
+                let mut solutions = self.eval(left, graph, seed)?;
+                solutions.extend(self.eval(right, graph, seed)?);
+                solutions
+            }
+            GraphPattern::Graph { name, inner } => self.eval_graph(name, inner, seed)?,
+            GraphPattern::Extend {
+                inner,
+                variable,
+                expression,
+            } => self
+                .eval(inner, graph, seed)?
+                .into_iter()
+                .filter_map(
+                    |mut solution| match self.expression(expression, &solution, graph) {
+                        Some(term) => solution.bind(variable, &term).then_some(solution),
+                        None => Some(solution),
+                    },
+                )
+                .collect(),
+            GraphPattern::Minus { left, right } => {
+                let left = self.eval(left, graph, seed)?;
+                let right = self.eval(right, graph, &Bindings::new())?;
+                left.into_iter()
+                    .filter(|l| {
+                        !right
+                            .iter()
+                            .any(|r| l.is_compatible(r) && l.variables().any(|v| r.contains(&v)))
+                    })
+                    .collect()
+            }
+            GraphPattern::Values {
+                variables,
+                bindings,
+            } => bindings
+                .iter()
+                .filter_map(|row| {
+                    let row: Bindings = variables
+                        .iter()
+                        .zip(row)
+                        .filter_map(|(variable, term)| Some((variable.clone(), term.clone()?)))
+                        .collect();
+                    seed.merge(&row)
+                })
+                .collect(),
+            GraphPattern::OrderBy { inner, expression } => {
+                let mut keyed: Vec<(Vec<Option<Term>>, Bindings)> = self
+                    .eval(inner, graph, seed)?
+                    .into_iter()
+                    .map(|solution| {
+                        let key = expression
+                            .iter()
+                            .map(|order| match order {
+                                OrderExpression::Asc(e) | OrderExpression::Desc(e) => {
+                                    self.expression(e, &solution, graph)
+                                }
+                            })
+                            .collect();
+                        (key, solution)
+                    })
+                    .collect();
+                keyed.sort_by(|(a, _), (b, _)| {
+                    expression
+                        .iter()
+                        .zip(a.iter().zip(b))
+                        .map(|(order, (a, b))| {
+                            let ordering = order_terms(a.as_ref(), b.as_ref());
+                            match order {
+                                OrderExpression::Asc(_) => ordering,
+                                OrderExpression::Desc(_) => ordering.reverse(),
+                            }
+                        })
+                        .find(|ordering| ordering.is_ne())
+                        .unwrap_or(Ordering::Equal)
+                });
+                keyed.into_iter().map(|(_, solution)| solution).collect()
+            }
+            GraphPattern::Project { inner, variables } => self
+                .eval(inner, graph, &Bindings::new())?
+                .into_iter()
+                .filter_map(|solution| {
+                    let projected: Bindings = variables
+                        .iter()
+                        .filter_map(|variable| {
+                            Some((variable.clone(), solution.get_variable(variable)?.clone()))
+                        })
+                        .collect();
+                    seed.merge(&projected)
+                })
+                .collect(),
+            GraphPattern::Distinct { inner } | GraphPattern::Reduced { inner } => {
+                deduplicate(self.eval(inner, graph, seed)?)
+            }
+            GraphPattern::Slice {
+                inner,
+                start,
+                length,
+            } => self
+                .eval(inner, graph, seed)?
+                .into_iter()
+                .skip(*start)
+                .take(length.unwrap_or(usize::MAX))
+                .collect(),
+            GraphPattern::Group {
+                inner,
+                variables,
+                aggregates,
+            } => {
+                let solutions = self.eval(inner, graph, seed)?;
+                self.group(solutions, variables, aggregates, graph)
+            }
+            GraphPattern::Service {
+                name,
+                inner: _,
+                silent,
+            } => {
+                if !silent {
+                    let name = match name {
+                        NamedNodePattern::NamedNode(named_node) => {
+                            format!("<{}>", named_node.value())
+                        }
+                        NamedNodePattern::Variable(variable) => format!("?{}", variable.value()),
+                    };
+                    return Err(EvaluationError::UnsupportedService(name));
+                }
+                vec![seed.clone()]
+            }
+        })
+    }
+
+    fn eval_bgp(&self, patterns: &[Quad], graph: &ActiveGraph, seed: &Bindings) -> Vec<Bindings> {
+        if patterns.is_empty() {
+            return vec![seed.clone()];
+        }
+
+        let graphs = self.graphs(graph);
+        let mut solutions: Vec<Bindings> = match graphs.as_slice() {
+            [] => Vec::new(),
+            [graph] => {
+                let patterns: Vec<Quad> = patterns
+                    .iter()
+                    .map(|pattern| to_pattern(pattern, graph))
+                    .collect();
+                evaluate_bgp_with(self.dataset, &patterns, seed.clone()).collect()
+            }
+            // Every triple pattern may match in any of the merged graphs.
+            _ => {
+                let graph_variables: Vec<Variable> = (0..patterns.len())
+                    .map(|i| Variable::new(&format!("#graph{i}")))
+                    .collect();
+                let patterns: Vec<Quad> = patterns
+                    .iter()
+                    .zip(&graph_variables)
+                    .map(|(pattern, variable)| {
+                        to_pattern(pattern, &QuadGraph::Variable(variable.clone()))
+                    })
+                    .collect();
+                let solutions = evaluate_bgp_with(self.dataset, &patterns, seed.clone())
+                    .filter(|solution| {
+                        graph_variables.iter().all(|variable| {
+                            solution
+                                .get_variable(variable)
+                                .is_some_and(|term| graphs.iter().any(|graph| graph == term))
+                        })
+                    })
+                    .map(|mut solution| {
+                        for variable in &graph_variables {
+                            solution.remove(variable);
+                        }
+                        solution
+                    })
+                    .collect();
+                deduplicate(solutions)
+            }
+        };
+
+        solutions.iter_mut().for_each(strip_internal);
+        solutions
+    }
+
+    fn eval_graph(
+        &self,
+        name: &NamedNodePattern,
+        inner: &GraphPattern,
+        seed: &Bindings,
+    ) -> Result<Vec<Bindings>, EvaluationError> {
+        let variable = match name {
+            NamedNodePattern::NamedNode(named_node) => {
+                let graph = QuadGraph::NamedNode(named_node.clone());
+                if !self.named_graphs.contains(&graph) {
+                    return Ok(Vec::new());
+                }
+                return self.eval(inner, &ActiveGraph::Named(graph), seed);
+            }
+            NamedNodePattern::Variable(variable) => variable,
+        };
+
+        let mut solutions = Vec::new();
+        for graph in &self.named_graphs {
+            let term = graph.to_term();
+            if seed
+                .get_variable(variable)
+                .is_some_and(|bound| *bound != term)
+            {
+                continue;
+            }
+            let active = ActiveGraph::Named(graph.clone());
+            for mut solution in self.eval(inner, &active, seed)? {
+                if solution.bind(variable, &term) {
+                    solutions.push(solution);
+                }
+            }
+        }
+        Ok(solutions)
+    }
+
+    fn eval_path(
+        &self,
+        subject: &Term,
+        path: &PropertyPath,
+        object: &Term,
+        graph: &ActiveGraph,
+        seed: &Bindings,
+    ) -> Vec<Bindings> {
+        let graphs = self.graphs(graph);
+        let start = endpoint(subject, seed);
+        let end = endpoint(object, seed);
+
+        let pairs: Vec<(Term, Term)> = match (&start, &end) {
+            (Ok(start), _) => self
+                .path_targets(path, start, true, &graphs)
+                .into_iter()
+                .map(|target| (start.clone(), target))
+                .collect(),
+            (Err(_), Ok(end)) => self
+                .path_targets(path, end, false, &graphs)
+                .into_iter()
+                .map(|source| (source, end.clone()))
+                .collect(),
+            (Err(_), Err(_)) => self.path_pairs(path, &graphs),
+        };
+
+        pairs
+            .into_iter()
+            .filter_map(|(source, target)| {
+                let mut solution = seed.clone();
+                (bind_endpoint(&mut solution, &start, &source)
+                    && bind_endpoint(&mut solution, &end, &target))
+                .then(|| {
+                    strip_internal(&mut solution);
+                    solution
+                })
+            })
+            .collect()
+    }
+
+    /// Nodes one `predicate` step (or, without it, a step over any predicate
+    /// not `excluded`) away from `start`.
+    fn step(
+        &self,
+        start: &Term,
+        predicate: Option<&NamedNode>,
+        excluded: &[NamedNode],
+        forward: bool,
+        graphs: &[QuadGraph],
+    ) -> Vec<Term> {
+        let predicate = predicate.map(|p| QuadPredicate::NamedNode(p.clone()));
+        let mut nodes = Vec::new();
+
+        for graph in graphs {
+            let quads = if forward {
+                let Ok(subject) = QuadSubject::try_from(start.clone()) else {
+                    return nodes;
+                };
+                self.dataset.quads_for_pattern(
+                    Some(&subject),
+                    predicate.as_ref(),
+                    None,
+                    Some(graph),
+                )
+            } else {
+                let Ok(object) = QuadObject::try_from(start.clone()) else {
+                    return nodes;
+                };
+                self.dataset
+                    .quads_for_pattern(None, predicate.as_ref(), Some(&object), Some(graph))
+            };
+
+            for quad in quads {
+                if is_excluded(quad.predicate(), excluded) {
+                    continue;
+                }
+                nodes.push(if forward {
+                    quad.object().to_term()
+                } else {
+                    quad.subject().to_term()
+                });
+            }
+        }
+
+        if graphs.len() > 1 {
+            let mut seen = HashSet::new();
+            nodes.retain(|node| seen.insert(node.clone()));
+        }
+        nodes
+    }
+
+    fn path_targets(
+        &self,
+        path: &PropertyPath,
+        start: &Term,
+        forward: bool,
+        graphs: &[QuadGraph],
+    ) -> Vec<Term> {
+        match path {
+            PropertyPath::NamedNode(predicate) => {
+                self.step(start, Some(predicate), &[], forward, graphs)
+            }
+            PropertyPath::Reverse(inner) => self.path_targets(inner, start, !forward, graphs),
+            PropertyPath::Sequence(first, second) => {
+                let (first, second) = if forward {
+                    (first, second)
+                } else {
+                    (second, first)
+                };
+                self.path_targets(first, start, forward, graphs)
+                    .iter()
+                    .flat_map(|middle| self.path_targets(second, middle, forward, graphs))
+                    .collect()
+            }
+            PropertyPath::Alternative(left, right) => {
+                let mut targets = self.path_targets(left, start, forward, graphs);
+                targets.extend(self.path_targets(right, start, forward, graphs));
+                targets
+            }
+            PropertyPath::ZeroOrMore(inner) => self.closure(inner, start, forward, graphs, true),
+            PropertyPath::OneOrMore(inner) => self.closure(inner, start, forward, graphs, false),
+            PropertyPath::ZeroOrOne(inner) => {
+                let mut targets = vec![start.clone()];
+                for target in self.path_targets(inner, start, forward, graphs) {
+                    if !targets.contains(&target) {
+                        targets.push(target);
+                    }
+                }
+                targets
+            }
+            PropertyPath::NegatedPropertySet(excluded) => {
+                self.step(start, None, excluded, forward, graphs)
+            }
+        }
+    }
+
+    /// Distinct nodes reachable by repeating `inner`, breadth first.
+    fn closure(
+        &self,
+        inner: &PropertyPath,
+        start: &Term,
+        forward: bool,
+        graphs: &[QuadGraph],
+        include_start: bool,
+    ) -> Vec<Term> {
+        let mut reached = Vec::new();
+        let mut seen = HashSet::new();
+        if include_start {
+            seen.insert(start.clone());
+            reached.push(start.clone());
+        }
+
+        let mut queue = VecDeque::from([start.clone()]);
+        while let Some(node) = queue.pop_front() {
+            for target in self.path_targets(inner, &node, forward, graphs) {
+                if seen.insert(target.clone()) {
+                    reached.push(target.clone());
+                    queue.push_back(target);
+                }
+            }
+        }
+        reached
+    }
+
+    /// All (subject, object) pairs connected by `path`.
+    fn path_pairs(&self, path: &PropertyPath, graphs: &[QuadGraph]) -> Vec<(Term, Term)> {
+        let edges = |predicate: Option<&NamedNode>, excluded: &[NamedNode]| {
+            let predicate = predicate.map(|p| QuadPredicate::NamedNode(p.clone()));
+            graphs
+                .iter()
+                .flat_map(|graph| {
+                    self.dataset
+                        .quads_for_pattern(None, predicate.as_ref(), None, Some(graph))
+                })
+                .filter(|quad| !is_excluded(quad.predicate(), excluded))
+                .map(|quad| (quad.subject().to_term(), quad.object().to_term()))
+                .collect::<Vec<_>>()
+        };
+
+        match path {
+            PropertyPath::NamedNode(predicate) => edges(Some(predicate), &[]),
+            PropertyPath::NegatedPropertySet(excluded) => edges(None, excluded),
+            PropertyPath::Reverse(inner) => self
+                .path_pairs(inner, graphs)
+                .into_iter()
+                .map(|(subject, object)| (object, subject))
+                .collect(),
+            path => {
+                let mut nodes = Vec::new();
+                let mut seen = HashSet::new();
+                for (subject, object) in edges(None, &[]) {
+                    for node in [subject, object] {
+                        if seen.insert(node.clone()) {
+                            nodes.push(node);
+                        }
+                    }
+                }
+                nodes
+                    .iter()
+                    .flat_map(|node| {
+                        self.path_targets(path, node, true, graphs)
+                            .into_iter()
+                            .map(|target| (node.clone(), target))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn group(
+        &self,
+        solutions: Vec<Bindings>,
+        variables: &[Variable],
+        aggregates: &[(Variable, AggregateExpression)],
+        graph: &ActiveGraph,
+    ) -> Vec<Bindings> {
+        let mut groups: Vec<(Vec<Option<Term>>, Vec<Bindings>)> = Vec::new();
+        let mut index = HashMap::new();
+        for solution in solutions {
+            let key: Vec<Option<Term>> = variables
+                .iter()
+                .map(|variable| solution.get_variable(variable).cloned())
+                .collect();
+            let i = *index.entry(key.clone()).or_insert_with(|| {
+                groups.push((key, Vec::new()));
+                groups.len() - 1
+            });
+            groups[i].1.push(solution);
+        }
+        // Aggregating without GROUP BY yields one group, even when empty.
+        if groups.is_empty() && variables.is_empty() {
+            groups.push((Vec::new(), Vec::new()));
+        }
+
+        groups
+            .into_iter()
+            .map(|(key, members)| {
+                let mut solution: Bindings = variables
+                    .iter()
+                    .zip(key)
+                    .filter_map(|(variable, term)| Some((variable.clone(), term?)))
+                    .collect();
+                for (variable, aggregate) in aggregates {
+                    if let Some(term) = self.aggregate(aggregate, &members, graph) {
+                        solution.bind(variable, &term);
+                    }
+                }
+                solution
+            })
+            .collect()
+    }
+
+    fn aggregate(
+        &self,
+        aggregate: &AggregateExpression,
+        group: &[Bindings],
+        graph: &ActiveGraph,
+    ) -> Option<Term> {
+        let Some(expression) = &aggregate.expression else {
+            // COUNT(*)
+            let count = if aggregate.distinct {
+                group.iter().collect::<HashSet<_>>().len()
+            } else {
+                group.len()
+            };
+            return Some(Numeric::Integer(count as i64).into_term());
+        };
+
+        let mut values: Vec<Option<Term>> = group
+            .iter()
+            .map(|solution| self.expression(expression, solution, graph))
+            .collect();
+        if aggregate.distinct {
+            let mut seen = HashSet::new();
+            values.retain(|value| seen.insert(value.clone()));
+        }
+
+        match &aggregate.function {
+            AggregateFunction::Count => {
+                Some(Numeric::Integer(values.iter().flatten().count() as i64).into_term())
+            }
+            AggregateFunction::Sum => values
+                .iter()
+                .try_fold(Numeric::Integer(0).into_term(), |sum, value| {
+                    arithmetic('+', &sum, value.as_ref()?)
+                }),
+            AggregateFunction::Avg => {
+                if values.is_empty() {
+                    return Some(Numeric::Integer(0).into_term());
+                }
+                let sum = values
+                    .iter()
+                    .try_fold(Numeric::Integer(0).into_term(), |sum, value| {
+                        arithmetic('+', &sum, value.as_ref()?)
+                    })?;
+                arithmetic(
+                    '/',
+                    &sum,
+                    &Numeric::Integer(values.len() as i64).into_term(),
+                )
+            }
+            AggregateFunction::Min => values
+                .into_iter()
+                .flatten()
+                .min_by(|a, b| order_terms(Some(a), Some(b))),
+            AggregateFunction::Max => values
+                .into_iter()
+                .flatten()
+                .max_by(|a, b| order_terms(Some(a), Some(b))),
+            AggregateFunction::Sample => values.into_iter().flatten().next(),
+            AggregateFunction::GroupConcat { separator } => {
+                let parts = values
+                    .iter()
+                    .map(|value| match value.as_ref()? {
+                        Term::Literal(literal) => Some(literal.value().to_owned()),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Term::Literal(Literal::new(
+                    &parts.join(separator.as_deref().unwrap_or(" ")),
+                    None,
+                    None,
+                    None,
+                )))
+            }
+        }
+    }
+
+    /// Whether `expression` has an effective boolean value of true.
+    fn holds(&self, expression: &Expression, solution: &Bindings, graph: &ActiveGraph) -> bool {
+        self.boolean(expression, solution, graph) == Some(true)
+    }
+
+    fn boolean(
+        &self,
+        expression: &Expression,
+        solution: &Bindings,
+        graph: &ActiveGraph,
+    ) -> Option<bool> {
+        effective_boolean_value(&self.expression(expression, solution, graph)?)
+    }
+
+    /// Evaluates `expression` for one solution; `None` is an error or an
+    /// unbound variable.
+    fn expression(
+        &self,
+        expression: &Expression,
+        solution: &Bindings,
+        graph: &ActiveGraph,
+    ) -> Option<Term> {
+        let value = |expression: &Expression| self.expression(expression, solution, graph);
+        let boolean = |expression: &Expression| self.boolean(expression, solution, graph);
+        let comparison = |a: &Expression, b: &Expression, accept: fn(Ordering) -> bool| {
+            Some(boolean_term(accept(compare(&value(a)?, &value(b)?)?)))
+        };
+
+        match expression {
+            Expression::NamedNode(named_node) => Some(Term::NamedNode(named_node.clone())),
+            Expression::Literal(literal) => Some(Term::Literal(literal.clone())),
+            Expression::Variable(variable) => solution.get_variable(variable).cloned(),
+            Expression::Or(a, b) => match (boolean(a), boolean(b)) {
+                (Some(true), _) | (_, Some(true)) => Some(boolean_term(true)),
+                (Some(false), Some(false)) => Some(boolean_term(false)),
+                _ => None,
+            },
+            Expression::And(a, b) => match (boolean(a), boolean(b)) {
+                (Some(false), _) | (_, Some(false)) => Some(boolean_term(false)),
+                (Some(true), Some(true)) => Some(boolean_term(true)),
+                _ => None,
+            },
+            Expression::Equal(a, b) => Some(boolean_term(equals(&value(a)?, &value(b)?)?)),
+            Expression::Greater(a, b) => comparison(a, b, Ordering::is_gt),
+            Expression::GreaterOrEqual(a, b) => comparison(a, b, Ordering::is_ge),
+            Expression::Less(a, b) => comparison(a, b, Ordering::is_lt),
+            Expression::LessOrEqual(a, b) => comparison(a, b, Ordering::is_le),
+            Expression::Add(a, b) => arithmetic('+', &value(a)?, &value(b)?),
+            Expression::Subtract(a, b) => arithmetic('-', &value(a)?, &value(b)?),
+            Expression::Multiply(a, b) => arithmetic('*', &value(a)?, &value(b)?),
+            Expression::Divide(a, b) => arithmetic('/', &value(a)?, &value(b)?),
+            Expression::In(needle, haystack) => {
+                let needle = value(needle)?;
+                let mut error = false;
+                for candidate in haystack {
+                    match value(candidate).and_then(|candidate| equals(&needle, &candidate)) {
+                        Some(true) => return Some(boolean_term(true)),
+                        Some(false) => {}
+                        None => error = true,
+                    }
+                }
+                (!error).then(|| boolean_term(false))
+            }
+            Expression::UnaryPlus(inner) => {
+                let inner = value(inner)?;
+                numeric(&inner).map(|_| inner)
+            }
+            Expression::UnaryMinus(inner) => negate(&value(inner)?),
+            Expression::Not(inner) => Some(boolean_term(!boolean(inner)?)),
+            Expression::Exists(pattern) => {
+                let solutions = self.eval(pattern, graph, solution).ok()?;
+                Some(boolean_term(!solutions.is_empty()))
+            }
+            Expression::Bound(variable) => Some(boolean_term(solution.contains(variable))),
+            Expression::If(condition, then, otherwise) => {
+                if boolean(condition)? {
+                    value(then)
+                } else {
+                    value(otherwise)
+                }
+            }
+            Expression::Coalesce(alternatives) => alternatives.iter().find_map(value),
+            Expression::FunctionCall(function, arguments) => {
+                let arguments = arguments.iter().map(value).collect::<Option<Vec<_>>>()?;
+                call_function(function, &arguments, &self.functions)
+            }
+        }
+    }
+
+    fn construct(&self, template: &[Quad], solutions: &[Bindings]) -> Vec<Quad> {
+        let mut triples = Vec::new();
+        let mut seen = HashSet::new();
+
+        for solution in solutions {
+            // Template blank nodes are fresh for every solution.
+            let mut blank_nodes: HashMap<String, BlankNode> = HashMap::new();
+            let mut fresh = |blank_node: &BlankNode| {
+                blank_nodes
+                    .entry(blank_node.value().to_owned())
+                    .or_insert_with(|| self.functions.fresh_blank_node())
+                    .clone()
+            };
+
+            for pattern in template {
+                let subject = match pattern.subject() {
+                    QuadSubject::BlankNode(blank_node) => QuadSubject::BlankNode(fresh(blank_node)),
+                    subject => subject.clone(),
+                };
+                let object = match pattern.object() {
+                    QuadObject::BlankNode(blank_node) => QuadObject::BlankNode(fresh(blank_node)),
+                    object => object.clone(),
+                };
+                let triple = substitute(
+                    &Quad::new(&subject, pattern.predicate(), &object, None),
+                    solution,
+                );
+                if is_ground(&triple) && seen.insert(triple.clone()) {
+                    triples.push(triple);
+                }
+            }
+        }
+
+        triples
+    }
+
+    /// The triples about every described resource, following blank node
+    /// objects.
+    fn describe(
+        &self,
+        targets: &[NamedNodePattern],
+        pattern: &GraphPattern,
+        solutions: &[Bindings],
+    ) -> Vec<Quad> {
+        let mut resources = Vec::new();
+        if targets.is_empty() {
+            for solution in solutions {
+                for variable in pattern.in_scope_variables() {
+                    resources.extend(solution.get_variable(&variable).cloned());
+                }
+            }
+        }
+        for target in targets {
+            match target {
+                NamedNodePattern::NamedNode(named_node) => {
+                    resources.push(Term::NamedNode(named_node.clone()))
+                }
+                NamedNodePattern::Variable(variable) => resources.extend(
+                    solutions
+                        .iter()
+                        .filter_map(|solution| solution.get_variable(variable).cloned()),
+                ),
+            }
+        }
+
+        let mut triples = Vec::new();
+        let mut seen = HashSet::new();
+        let mut described = HashSet::new();
+        let mut queue: VecDeque<Term> = resources.into();
+        while let Some(resource) = queue.pop_front() {
+            if !described.insert(resource.clone()) {
+                continue;
+            }
+            let Ok(subject) = QuadSubject::try_from(resource) else {
+                continue;
+            };
+            for graph in &self.default_graphs {
+                for quad in self
+                    .dataset
+                    .quads_for_pattern(Some(&subject), None, None, Some(graph))
+                {
+                    if let QuadObject::BlankNode(blank_node) = quad.object() {
+                        queue.push_back(Term::BlankNode(blank_node.clone()));
+                    }
+                    let triple = Quad::new(quad.subject(), quad.predicate(), quad.object(), None);
+                    if seen.insert(triple.clone()) {
+                        triples.push(triple);
+                    }
+                }
+            }
+        }
+
+        triples
+    }
+}
+
+/// Patterns that can be evaluated once per solution of the left side of a
+/// join, with that solution substituted in.
+fn is_substitutable(pattern: &GraphPattern) -> bool {
+    matches!(
+        pattern,
+        GraphPattern::Bgp { .. } | GraphPattern::Path { .. }
+    )
+}
+
+fn is_excluded(predicate: &QuadPredicate, excluded: &[NamedNode]) -> bool {
+    matches!(predicate, QuadPredicate::NamedNode(named_node) if excluded.contains(named_node))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::sparql::parse_query;
+    use crate::rs::turtle::{TurtleSyntax, parse_str};
+
+    const DATA: &str = r#"
+        @prefix ex: <http://example.org/> .
+        @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+        ex:alice ex:name "Alice"@en ; ex:age 34 ; ex:knows ex:bob ;
+            ex:born "1990-05-17T08:30:00Z"^^xsd:dateTime .
+        ex:bob ex:name "Bob" ; ex:age 27 ; ex:knows ex:carol .
+        ex:carol ex:name "Carol" ; ex:age 27.5 .
+
+        ex:g1 { ex:alice ex:likes ex:tea . }
+        ex:g2 { ex:bob ex:likes ex:coffee . ex:carol ex:likes ex:tea . }
+    "#;
+
+    fn dataset() -> Dataset {
+        parse_str(TurtleSyntax::TriG, DATA, None)
+            .unwrap()
+            .iter()
+            .collect()
+    }
+
+    fn evaluate(query: &str) -> QueryResults {
+        let query = parse_query(
+            &format!("PREFIX ex: <http://example.org/> PREFIX xsd: <http://www.w3.org/2001/XMLSchema#> {query}"),
+            None,
+        )
+        .unwrap();
+        evaluate_query(&dataset(), &query).unwrap()
+    }
+
+    /// One line per solution, the values of the projected variables
+    /// separated by spaces, `-` standing for unbound.
+    fn select(query: &str) -> Vec<String> {
+        let QueryResults::Solutions {
+            variables,
+            solutions,
+        } = evaluate(query)
+        else {
+            panic!("not a SELECT query");
+        };
+        solutions
+            .iter()
+            .map(|solution| {
+                variables
+                    .iter()
+                    .map(|variable| solution.get_variable(variable).map_or("-", |t| t.value()))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn filters_and_orders_solutions() {
+        assert_eq!(
+            select(
+                "SELECT ?name WHERE { ?p ex:name ?name ; ex:age ?age FILTER(?age > 27) } ORDER BY DESC(?age)"
+            ),
+            ["Alice", "Carol"]
+        );
+        assert_eq!(
+            select("SELECT ?p WHERE { ?p ex:age ?age } ORDER BY ?age ?p"),
+            [
+                "http://example.org/bob",
+                "http://example.org/carol",
+                "http://example.org/alice"
+            ]
+        );
+        assert_eq!(
+            select(
+                "SELECT ?p ?q WHERE { ?p ex:name ?n OPTIONAL { ?p ex:knows ?q } FILTER(!isBlank(?p)) } ORDER BY ?p"
+            ),
+            [
+                "http://example.org/alice http://example.org/bob",
+                "http://example.org/bob http://example.org/carol",
+                "http://example.org/carol -"
+            ]
+        );
+    }
+
+    #[test]
+    fn evaluates_functions() {
+        assert_eq!(
+            select(
+                r#"SELECT (UCASE(?n) AS ?u) (LANG(?n) AS ?l) (STRLEN(?n) AS ?len) (datatype(?a) AS ?dt)
+                   WHERE { ex:alice ex:name ?n ; ex:age ?a }"#
+            ),
+            ["ALICE en 5 http://www.w3.org/2001/XMLSchema#integer"]
+        );
+        assert_eq!(
+            select(
+                r#"SELECT (YEAR(?b) AS ?y) (MONTH(?b) AS ?m) (HOURS(?b) AS ?h) (TZ(?b) AS ?tz)
+                   WHERE { ex:alice ex:born ?b }"#
+            ),
+            ["1990 5 8 Z"]
+        );
+        assert_eq!(
+            select(
+                r#"SELECT ?n WHERE { ?p ex:name ?n FILTER(REGEX(?n, "^b|L$", "i") && isIRI(?p)) }"#
+            ),
+            ["Bob", "Carol"]
+        );
+        assert_eq!(
+            select(
+                r#"SELECT (1 + 2 * 3 AS ?a) (7 / 2 AS ?b) (1.5 + 1 AS ?c) (ROUND(-2.5) AS ?d)
+                          (CONCAT("a", "b") AS ?e) (MD5("abc") AS ?f) (xsd:integer("42") AS ?g)
+                   WHERE {}"#
+            ),
+            ["7 3.5 2.5 -2.0 ab 900150983cd24fb0d6963f7d28e17f72 42"]
+        );
+    }
+
+    #[test]
+    fn groups_and_aggregates() {
+        assert_eq!(
+            select(
+                r#"SELECT ?age (COUNT(*) AS ?n) (GROUP_CONCAT(?name; SEPARATOR=",") AS ?names)
+                   WHERE { ?p ex:age ?age ; ex:name ?name } GROUP BY ?age HAVING (COUNT(*) = 1) ORDER BY ?age"#
+            ),
+            ["27 1 Bob", "27.5 1 Carol", "34 1 Alice"]
+        );
+        assert_eq!(
+            select(
+                "SELECT (SUM(?age) AS ?sum) (AVG(?age) AS ?avg) (MIN(?age) AS ?min) (MAX(?age) AS ?max) WHERE { ?p ex:age ?age }"
+            ),
+            ["88.5 29.5 27 34"]
+        );
+        assert_eq!(
+            select("SELECT (COUNT(*) AS ?n) WHERE { ?p ex:missing ?o }"),
+            ["0"]
+        );
+    }
+
+    #[test]
+    fn filters_drop_solutions_on_type_errors() {
+        // Comparing a string with a number is an error, and so is its
+        // negation.
+        assert_eq!(
+            select("SELECT ?n WHERE { ?p ex:name ?n FILTER(?n > 3) }"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            select("SELECT ?n WHERE { ?p ex:name ?n FILTER(!(?n > 3)) }"),
+            Vec::<String>::new()
+        );
+        // An error on one side of || or && is hidden by a decisive other side.
+        assert_eq!(
+            select("SELECT ?n WHERE { ?p ex:name ?n FILTER(?unbound || ?n = \"Bob\") }"),
+            ["Bob"]
+        );
+        assert_eq!(
+            select("SELECT ?n WHERE { ?p ex:name ?n FILTER(!(?unbound && false)) } ORDER BY ?n"),
+            ["Alice", "Bob", "Carol"]
+        );
+        assert_eq!(
+            select(
+                r#"SELECT ?n WHERE { ?p ex:name ?n
+                   FILTER(langMatches(lang(?n), "*") && STRSTARTS(?n, "A") && isLiteral(?n)) }"#
+            ),
+            ["Alice"]
+        );
+        assert_eq!(
+            select(
+                r#"SELECT ?p WHERE { ?p ex:age ?a FILTER(isNumeric(?a) && ?a IN (27, 34) && sameTerm(?a, 27)) }"#
+            ),
+            ["http://example.org/bob"]
+        );
+        assert_eq!(
+            select("SELECT ?p WHERE { ?p ex:age ?a FILTER(xsd:integer(\"x\") = ?a) }"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn evaluates_optional_and_minus() {
+        assert_eq!(
+            select(
+                "SELECT ?p ?a WHERE { ?p ex:name ?n OPTIONAL { ?p ex:age ?a FILTER(?a > 30) } } ORDER BY ?p"
+            ),
+            [
+                "http://example.org/alice 34",
+                "http://example.org/bob -",
+                "http://example.org/carol -"
+            ]
+        );
+        assert_eq!(
+            select(
+                r#"SELECT ?p ?q ?n WHERE { ?p ex:age ?a
+                   OPTIONAL { ?p ex:knows ?q OPTIONAL { ?q ex:knows ?r . ?r ex:name ?n } } } ORDER BY ?p"#
+            ),
+            [
+                "http://example.org/alice http://example.org/bob Carol",
+                "http://example.org/bob http://example.org/carol -",
+                "http://example.org/carol - -"
+            ]
+        );
+        assert_eq!(
+            select("SELECT ?p WHERE { ?p ex:name ?n MINUS { ?p ex:knows ?q } }"),
+            ["http://example.org/carol"]
+        );
+        // MINUS without shared variables removes nothing.
+        assert_eq!(
+            select("SELECT ?p WHERE { ?p ex:name ?n MINUS { ?x ex:knows ?y } }").len(),
+            3
+        );
+        assert_eq!(
+            select(
+                "SELECT ?p WHERE { ?p ex:name ?n MINUS { ?p ex:age ?a FILTER(?a < 30) } } ORDER BY ?p"
+            ),
+            ["http://example.org/alice"]
+        );
+    }
+
+    #[test]
+    fn aggregates_skip_unbound_values_and_errors() {
+        assert_eq!(
+            select(
+                r#"SELECT (COUNT(?q) AS ?known) (COUNT(DISTINCT ?a) AS ?ages) (COUNT(*) AS ?all)
+                   WHERE { ?p ex:age ?a OPTIONAL { ?p ex:knows ?q } }"#
+            ),
+            ["2 3 3"]
+        );
+        // SUM over a name is an error, leaving the aggregate unbound.
+        assert_eq!(
+            select("SELECT (SUM(?n) AS ?sum) (MAX(?n) AS ?max) WHERE { ?p ex:name ?n }"),
+            ["- Carol"]
+        );
+        assert_eq!(
+            select(
+                r#"SELECT ?old (COUNT(*) AS ?n) (SAMPLE(?p) AS ?s)
+                   WHERE { ?p ex:age ?a FILTER(?a >= 30) } GROUP BY (?a > 30 AS ?old)"#
+            ),
+            ["true 1 http://example.org/alice"]
+        );
+        assert_eq!(
+            select("SELECT (AVG(?a) AS ?avg) (SUM(?a) AS ?sum) WHERE { ?p ex:missing ?a }"),
+            ["0 0"]
+        );
+        assert_eq!(
+            select(
+                "SELECT ?q (COUNT(?p) AS ?n) WHERE { ?p ex:name ?name OPTIONAL { ?p ex:knows ?q } } GROUP BY ?q ORDER BY ?q"
+            ),
+            [
+                "- 1",
+                "http://example.org/bob 1",
+                "http://example.org/carol 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_named_graphs() {
+        assert_eq!(
+            select("SELECT ?g ?p WHERE { GRAPH ?g { ?p ex:likes ex:tea } } ORDER BY ?g"),
+            [
+                "http://example.org/g1 http://example.org/alice",
+                "http://example.org/g2 http://example.org/carol"
+            ]
+        );
+        assert_eq!(
+            select("SELECT ?p WHERE { ?p ex:likes ?o }"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            select("SELECT ?p FROM ex:g1 FROM ex:g2 WHERE { ?p ex:likes ex:tea } ORDER BY ?p"),
+            ["http://example.org/alice", "http://example.org/carol"]
+        );
+        assert_eq!(
+            select("SELECT ?g FROM NAMED ex:g2 WHERE { GRAPH ?g { ?s ?p ?o } }"),
+            ["http://example.org/g2", "http://example.org/g2"]
+        );
+    }
+
+    #[test]
+    fn follows_property_paths() {
+        assert_eq!(
+            select("SELECT ?x WHERE { ex:alice ex:knows+ ?x } ORDER BY ?x"),
+            ["http://example.org/bob", "http://example.org/carol"]
+        );
+        assert_eq!(
+            select("SELECT ?x WHERE { ?x ^ex:knows/ex:name \"Alice\"@en }"),
+            ["http://example.org/bob"]
+        );
+        assert_eq!(
+            select("SELECT ?x WHERE { ?x ex:knows/ex:knows ex:carol }"),
+            ["http://example.org/alice"]
+        );
+        assert_eq!(
+            select("SELECT ?x WHERE { ex:carol ex:knows* ?x }"),
+            ["http://example.org/carol"]
+        );
+    }
+
+    #[test]
+    fn evaluates_ask_construct_and_describe() {
+        assert_eq!(
+            evaluate("ASK { ex:alice ex:knows ex:bob }"),
+            QueryResults::Boolean(true)
+        );
+        assert_eq!(
+            evaluate("ASK { ex:bob ex:knows ex:alice }"),
+            QueryResults::Boolean(false)
+        );
+
+        let QueryResults::Graph(triples) =
+            evaluate("CONSTRUCT { ?b ex:knownBy ?a . ?a ex:tag [] } WHERE { ?a ex:knows ?b }")
+        else {
+            panic!("not a graph");
+        };
+        assert_eq!(triples.len(), 4);
+        assert_eq!(
+            triples
+                .iter()
+                .filter(|triple| matches!(triple.object(), QuadObject::BlankNode(_)))
+                .map(|triple| triple.object().value().to_owned())
+                .collect::<HashSet<_>>()
+                .len(),
+            2
+        );
+
+        let QueryResults::Graph(triples) = evaluate("DESCRIBE ex:carol") else {
+            panic!("not a graph");
+        };
+        assert_eq!(triples.len(), 2);
+    }
+
+    #[test]
+    fn rejects_services() {
+        let query = parse_query(
+            "SELECT * WHERE { SERVICE <http://example.org/sparql> { ?s ?p ?o } }",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            evaluate_query(&dataset(), &query),
+            Err(EvaluationError::UnsupportedService(
+                "<http://example.org/sparql>".to_owned()
+            ))
+        );
+    }
+}
@@ -0,0 +1,1360 @@
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use md5::Md5;
+use regex::{Regex, RegexBuilder};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::iri;
+use crate::rs::literal::{LanguageDirection, Literal};
+use crate::rs::named_node::NamedNode;
+use crate::rs::quad::Quad;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::sparql::algebra::Function;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::vocab::xsd;
+
+const INTEGER_TYPES: &[&str] = &[
+    "integer",
+    "long",
+    "int",
+    "short",
+    "byte",
+    "nonNegativeInteger",
+    "nonPositiveInteger",
+    "positiveInteger",
+    "negativeInteger",
+    "unsignedLong",
+    "unsignedInt",
+    "unsignedShort",
+    "unsignedByte",
+];
+
+/// Fixed-point `xsd:decimal` with 18 fractional digits.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub(crate) struct Decimal(i128);
+
+const DECIMAL_SCALE: i128 = 1_000_000_000_000_000_000;
+const DECIMAL_DIGITS: usize = 18;
+
+impl Decimal {
+    fn from_integer(value: i64) -> Option<Self> {
+        (value as i128).checked_mul(DECIMAL_SCALE).map(Decimal)
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let (negative, digits) = match value.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, value.strip_prefix('+').unwrap_or(value)),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if (integer.is_empty() && fraction.is_empty())
+            || !integer.chars().all(|c| c.is_ascii_digit())
+            || !fraction.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        let mut scaled: i128 = 0;
+        for c in integer.chars() {
+            scaled = scaled
+                .checked_mul(10)?
+                .checked_add(c as i128 - '0' as i128)?;
+        }
+        scaled = scaled.checked_mul(DECIMAL_SCALE)?;
+        let mut unit = DECIMAL_SCALE;
+        for c in fraction.chars().take(DECIMAL_DIGITS) {
+            unit /= 10;
+            scaled += (c as i128 - '0' as i128) * unit;
+        }
+        Some(Decimal(if negative { -scaled } else { scaled }))
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0 as f64 / DECIMAL_SCALE as f64
+    }
+
+    fn checked_mul(self, other: Decimal) -> Option<Decimal> {
+        let (ai, af) = (self.0 / DECIMAL_SCALE, self.0 % DECIMAL_SCALE);
+        let (bi, bf) = (other.0 / DECIMAL_SCALE, other.0 % DECIMAL_SCALE);
+        ai.checked_mul(bi)?
+            .checked_mul(DECIMAL_SCALE)?
+            .checked_add(ai.checked_mul(bf)?)?
+            .checked_add(af.checked_mul(bi)?)?
+            .checked_add(af * bf / DECIMAL_SCALE)
+            .map(Decimal)
+    }
+
+    fn checked_div(self, other: Decimal) -> Option<Decimal> {
+        if other.0 == 0 {
+            return None;
+        }
+        let negative = (self.0 < 0) != (other.0 < 0);
+        let (a, b) = (self.0.checked_abs()?, other.0.checked_abs()?);
+        let mut result = (a / b).checked_mul(DECIMAL_SCALE)?;
+        let mut remainder = a % b;
+        let mut unit = DECIMAL_SCALE;
+        while unit > 1 && remainder != 0 {
+            unit /= 10;
+            remainder *= 10;
+            result += remainder / b * unit;
+            remainder %= b;
+        }
+        Some(Decimal(if negative { -result } else { result }))
+    }
+
+    fn floor(self) -> Decimal {
+        Decimal(self.0.div_euclid(DECIMAL_SCALE) * DECIMAL_SCALE)
+    }
+
+    fn ceil(self) -> Decimal {
+        Decimal(-(Decimal(-self.0).floor().0))
+    }
+
+    fn round(self) -> Decimal {
+        Decimal(self.0 + DECIMAL_SCALE / 2).floor()
+    }
+
+    fn to_lexical(self) -> String {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let magnitude = self.0.unsigned_abs();
+        let scale = DECIMAL_SCALE as u128;
+        let fraction = format!("{:018}", magnitude % scale);
+        let fraction = fraction.trim_end_matches('0');
+        let fraction = if fraction.is_empty() { "0" } else { fraction };
+        format!("{sign}{}.{fraction}", magnitude / scale)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Numeric {
+    Integer(i64),
+    Decimal(Decimal),
+    Float(f32),
+    Double(f64),
+}
+
+impl Numeric {
+    fn rank(&self) -> u8 {
+        match self {
+            Numeric::Integer(_) => 0,
+            Numeric::Decimal(_) => 1,
+            Numeric::Float(_) => 2,
+            Numeric::Double(_) => 3,
+        }
+    }
+
+    fn to_decimal(self) -> Option<Decimal> {
+        match self {
+            Numeric::Integer(value) => Decimal::from_integer(value),
+            Numeric::Decimal(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn to_f64(self) -> f64 {
+        match self {
+            Numeric::Integer(value) => value as f64,
+            Numeric::Decimal(value) => value.to_f64(),
+            Numeric::Float(value) => value as f64,
+            Numeric::Double(value) => value,
+        }
+    }
+
+    pub(crate) fn into_term(self) -> Term {
+        let (value, datatype) = match self {
+            Numeric::Integer(value) => (value.to_string(), &xsd::integer),
+            Numeric::Decimal(value) => (value.to_lexical(), &xsd::decimal),
+            Numeric::Float(value) => (format_double(value as f64), &xsd::float),
+            Numeric::Double(value) => (format_double(value), &xsd::double),
+        };
+        Term::Literal(Literal::new(&value, None, None, Some(datatype)))
+    }
+}
+
+pub(crate) fn format_double(value: f64) -> String {
+    if value.is_nan() {
+        return "NaN".to_owned();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "INF" } else { "-INF" }.to_owned();
+    }
+    let formatted = format!("{value:E}");
+    match formatted.split_once('E') {
+        Some((mantissa, exponent)) if !mantissa.contains('.') => {
+            format!("{mantissa}.0E{exponent}")
+        }
+        _ => formatted,
+    }
+}
+
+fn is_integer_type(datatype: &NamedNode) -> bool {
+    datatype
+        .value()
+        .strip_prefix(xsd::NAMESPACE)
+        .is_some_and(|local| INTEGER_TYPES.contains(&local))
+}
+
+pub(crate) fn numeric(term: &Term) -> Option<Numeric> {
+    let Term::Literal(literal) = term else {
+        return None;
+    };
+    let datatype = literal.datatype();
+    let value = literal.value().trim();
+    if is_integer_type(datatype) {
+        value
+            .strip_prefix('+')
+            .unwrap_or(value)
+            .parse()
+            .ok()
+            .map(Numeric::Integer)
+    } else if *datatype == *xsd::decimal {
+        Decimal::parse(value).map(Numeric::Decimal)
+    } else if *datatype == *xsd::float {
+        parse_double(value).map(|value| Numeric::Float(value as f32))
+    } else if *datatype == *xsd::double {
+        parse_double(value).map(Numeric::Double)
+    } else {
+        None
+    }
+}
+
+fn parse_double(value: &str) -> Option<f64> {
+    match value {
+        "INF" | "+INF" => Some(f64::INFINITY),
+        "-INF" => Some(f64::NEG_INFINITY),
+        "NaN" => Some(f64::NAN),
+        _ if value.contains(|c: char| c.is_ascii_alphabetic() && c != 'e' && c != 'E') => None,
+        _ => value.parse().ok(),
+    }
+}
+
+/// Applies an arithmetic operator after promoting both operands to a
+/// common numeric type.
+pub(crate) fn arithmetic(operator: char, a: &Term, b: &Term) -> Option<Term> {
+    let (a, b) = (numeric(a)?, numeric(b)?);
+    let rank = a
+        .rank()
+        .max(b.rank())
+        .max(if operator == '/' { 1 } else { 0 });
+
+    let result = match rank {
+        0 => {
+            let (Numeric::Integer(a), Numeric::Integer(b)) = (a, b) else {
+                unreachable!()
+            };
+            Numeric::Integer(match operator {
+                '+' => a.checked_add(b)?,
+                '-' => a.checked_sub(b)?,
+                _ => a.checked_mul(b)?,
+            })
+        }
+        1 => {
+            let (a, b) = (a.to_decimal()?, b.to_decimal()?);
+            Numeric::Decimal(match operator {
+                '+' => Decimal(a.0.checked_add(b.0)?),
+                '-' => Decimal(a.0.checked_sub(b.0)?),
+                '*' => a.checked_mul(b)?,
+                _ => a.checked_div(b)?,
+            })
+        }
+        _ => {
+            let (a, b) = (a.to_f64(), b.to_f64());
+            let value = match operator {
+                '+' => a + b,
+                '-' => a - b,
+                '*' => a * b,
+                _ => a / b,
+            };
+            if rank == 2 {
+                Numeric::Float(value as f32)
+            } else {
+                Numeric::Double(value)
+            }
+        }
+    };
+    Some(result.into_term())
+}
+
+pub(crate) fn negate(term: &Term) -> Option<Term> {
+    Some(
+        match numeric(term)? {
+            Numeric::Integer(value) => Numeric::Integer(value.checked_neg()?),
+            Numeric::Decimal(value) => Numeric::Decimal(Decimal(-value.0)),
+            Numeric::Float(value) => Numeric::Float(-value),
+            Numeric::Double(value) => Numeric::Double(-value),
+        }
+        .into_term(),
+    )
+}
+
+fn compare_numeric(a: Numeric, b: Numeric) -> Option<Ordering> {
+    match a.rank().max(b.rank()) {
+        0 | 1 => Some(a.to_decimal()?.cmp(&b.to_decimal()?)),
+        _ => a.to_f64().partial_cmp(&b.to_f64()),
+    }
+}
+
+fn is_string_literal(literal: &Literal) -> bool {
+    *literal.datatype() == *xsd::string
+}
+
+fn has_language(literal: &Literal) -> bool {
+    !literal.language().is_empty()
+}
+
+fn boolean(term: &Term) -> Option<bool> {
+    match term {
+        Term::Literal(literal) if *literal.datatype() == *xsd::boolean => match literal.value() {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub(crate) fn boolean_term(value: bool) -> Term {
+    Term::Literal(Literal::new(
+        if value { "true" } else { "false" },
+        None,
+        None,
+        Some(&xsd::boolean),
+    ))
+}
+
+/// The effective boolean value of a term, `None` being a type error.
+pub(crate) fn effective_boolean_value(term: &Term) -> Option<bool> {
+    let Term::Literal(literal) = term else {
+        return None;
+    };
+    if *literal.datatype() == *xsd::boolean {
+        return Some(boolean(term).unwrap_or(false));
+    }
+    if is_string_literal(literal) {
+        return Some(!literal.value().is_empty());
+    }
+    match numeric(term) {
+        Some(Numeric::Integer(value)) => Some(value != 0),
+        Some(Numeric::Decimal(value)) => Some(value.0 != 0),
+        Some(value) => {
+            let value = value.to_f64();
+            Some(value != 0.0 && !value.is_nan())
+        }
+        None if numeric_datatype(literal) => Some(false),
+        None => None,
+    }
+}
+
+fn numeric_datatype(literal: &Literal) -> bool {
+    let datatype = literal.datatype();
+    is_integer_type(datatype)
+        || *datatype == *xsd::decimal
+        || *datatype == *xsd::float
+        || *datatype == *xsd::double
+}
+
+/// A parsed `xsd:dateTime` or `xsd:date`.
+#[derive(Clone, Debug)]
+struct DateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: Decimal,
+    /// Offset from UTC in minutes.
+    timezone: Option<i32>,
+}
+
+impl DateTime {
+    fn parse(literal: &Literal) -> Option<Self> {
+        let value = literal.value().trim();
+        if *literal.datatype() == *xsd::dateTime {
+            let (date, time) = value.split_once('T')?;
+            let mut date_time = Self::parse_date(date)?;
+            let (time, timezone) = split_timezone(time)?;
+            let mut parts = time.splitn(3, ':');
+            date_time.hour = parts.next()?.parse().ok()?;
+            date_time.minute = parts.next()?.parse().ok()?;
+            date_time.second = Decimal::parse(parts.next()?)?;
+            date_time.timezone = timezone;
+            (date_time.hour <= 24 && date_time.minute < 60).then_some(date_time)
+        } else if *literal.datatype() == *xsd::date {
+            let (date, timezone) = split_timezone_from_date(value)?;
+            let mut date_time = Self::parse_date(date)?;
+            date_time.timezone = timezone;
+            Some(date_time)
+        } else {
+            None
+        }
+    }
+
+    fn parse_date(date: &str) -> Option<Self> {
+        let (negative, date) = match date.strip_prefix('-') {
+            Some(date) => (true, date),
+            None => (false, date),
+        };
+        let mut parts = date.splitn(3, '-');
+        let year: i64 = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        Some(Self {
+            year: if negative { -year } else { year },
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: Decimal(0),
+            timezone: None,
+        })
+    }
+
+    /// Seconds since the epoch, treating missing timezones as UTC.
+    fn timestamp(&self) -> Option<Decimal> {
+        let days = days_from_civil(self.year, self.month, self.day);
+        let minutes =
+            days * 1440 + (self.hour * 60 + self.minute) as i64 - self.timezone.unwrap_or(0) as i64;
+        let seconds = Decimal::from_integer(minutes.checked_mul(60)?)?;
+        Some(Decimal(seconds.0.checked_add(self.second.0)?))
+    }
+}
+
+fn split_timezone(time: &str) -> Option<(&str, Option<i32>)> {
+    if let Some(time) = time.strip_suffix('Z') {
+        return Some((time, Some(0)));
+    }
+    match time.rfind(['+', '-']) {
+        Some(i) => Some((&time[..i], Some(parse_offset(&time[i..])?))),
+        None => Some((time, None)),
+    }
+}
+
+fn split_timezone_from_date(date: &str) -> Option<(&str, Option<i32>)> {
+    if let Some(date) = date.strip_suffix('Z') {
+        return Some((date, Some(0)));
+    }
+    // A timezone offset has the shape ±hh:mm after the day.
+    if date.len() > 6 && date.as_bytes()[date.len() - 3] == b':' {
+        let i = date.len() - 6;
+        return Some((&date[..i], Some(parse_offset(&date[i..])?)));
+    }
+    Some((date, None))
+}
+
+fn parse_offset(offset: &str) -> Option<i32> {
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let (hours, minutes) = offset[1..].split_once(':')?;
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    Some(sign * (hours * 60 + minutes))
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// The current time as a UTC `xsd:dateTime` literal.
+pub(crate) fn now() -> Literal {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = elapsed.as_secs() as i64;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let time = seconds.rem_euclid(86_400);
+    let value = format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        elapsed.subsec_millis()
+    );
+    Literal::new(&value, None, None, Some(&xsd::dateTime))
+}
+
+/// SPARQL `=`: `None` when the operands cannot be compared.
+pub(crate) fn equals(a: &Term, b: &Term) -> Option<bool> {
+    match (a, b) {
+        (Term::Literal(x), Term::Literal(y)) => {
+            if let (Some(x), Some(y)) = (numeric(a), numeric(b)) {
+                return compare_numeric(x, y).map(|ordering| ordering == Ordering::Equal);
+            }
+            if let Some(ordering) = compare_literals(x, y) {
+                return Some(ordering == Ordering::Equal);
+            }
+            if x == y {
+                Some(true)
+            } else if has_language(x) || has_language(y) || is_known_type(x) && is_known_type(y) {
+                Some(false)
+            } else {
+                None
+            }
+        }
+        (Term::Quad(x), Term::Quad(y)) => {
+            let mut all = true;
+            for (x, y) in quad_terms(x).iter().zip(quad_terms(y).iter()) {
+                all &= equals(x, y)?;
+            }
+            Some(all)
+        }
+        _ => Some(a == b),
+    }
+}
+
+fn quad_terms(quad: &Quad) -> [Term; 3] {
+    [
+        quad.subject().to_term(),
+        quad.predicate().to_term(),
+        quad.object().to_term(),
+    ]
+}
+
+fn is_known_type(literal: &Literal) -> bool {
+    let datatype = literal.datatype();
+    numeric_datatype(literal)
+        || *datatype == *xsd::string
+        || *datatype == *xsd::boolean
+        || *datatype == *xsd::dateTime
+        || *datatype == *xsd::date
+}
+
+fn compare_literals(a: &Literal, b: &Literal) -> Option<Ordering> {
+    if is_string_literal(a) && is_string_literal(b) {
+        return Some(a.value().cmp(b.value()));
+    }
+    if has_language(a) && has_language(b) && a.language() == b.language() {
+        return Some(a.value().cmp(b.value()));
+    }
+    let (a_term, b_term) = (Term::Literal(a.clone()), Term::Literal(b.clone()));
+    if let (Some(x), Some(y)) = (boolean(&a_term), boolean(&b_term)) {
+        return Some(x.cmp(&y));
+    }
+    if let (Some(x), Some(y)) = (DateTime::parse(a), DateTime::parse(b))
+        && *a.datatype() == *b.datatype()
+    {
+        return Some(x.timestamp()?.cmp(&y.timestamp()?));
+    }
+    None
+}
+
+/// SPARQL `<` and friends: `None` when the operands are not comparable.
+pub(crate) fn compare(a: &Term, b: &Term) -> Option<Ordering> {
+    if let (Some(x), Some(y)) = (numeric(a), numeric(b)) {
+        return compare_numeric(x, y);
+    }
+    match (a, b) {
+        (Term::Literal(x), Term::Literal(y)) => compare_literals(x, y),
+        _ => None,
+    }
+}
+
+/// Total order used by ORDER BY: unbound, blank nodes, IRIs, literals and
+/// triple terms, comparing values where SPARQL defines an order.
+pub(crate) fn order_terms(a: Option<&Term>, b: Option<&Term>) -> Ordering {
+    let rank = |term: Option<&Term>| match term {
+        None => 0,
+        Some(Term::BlankNode(_)) => 1,
+        Some(Term::NamedNode(_)) => 2,
+        Some(Term::Literal(_)) => 3,
+        Some(Term::Quad(_)) => 4,
+        Some(_) => 5,
+    };
+
+    match (a, b) {
+        (Some(Term::Literal(x)), Some(Term::Literal(y))) => compare(a.unwrap(), b.unwrap())
+            .filter(|ordering| ordering.is_ne() || x == y)
+            .unwrap_or_else(|| {
+                (x.value(), x.datatype().value(), x.language()).cmp(&(
+                    y.value(),
+                    y.datatype().value(),
+                    y.language(),
+                ))
+            }),
+        (Some(Term::Quad(x)), Some(Term::Quad(y))) => quad_terms(x)
+            .iter()
+            .zip(quad_terms(y).iter())
+            .map(|(x, y)| order_terms(Some(x), Some(y)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal),
+        (Some(x), Some(y)) if rank(a) == rank(b) => x.value().cmp(y.value()),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// State shared by the function calls of one query evaluation.
+pub(crate) struct FunctionContext {
+    pub base_iri: Option<String>,
+    now: Literal,
+    blank_node_count: Cell<usize>,
+    random_state: Cell<u64>,
+}
+
+impl FunctionContext {
+    pub fn new(base_iri: Option<&str>) -> Self {
+        Self {
+            base_iri: base_iri.map(str::to_owned),
+            now: now(),
+            blank_node_count: Cell::new(0),
+            random_state: Cell::new(RandomState::new().hash_one(0u8) | 1),
+        }
+    }
+
+    pub fn fresh_blank_node(&self) -> BlankNode {
+        let count = self.blank_node_count.get() + 1;
+        self.blank_node_count.set(count);
+        BlankNode::new(&format!("bnode{count}"))
+    }
+
+    /// xorshift64*, good enough for RAND() and UUID().
+    fn random(&self) -> u64 {
+        let mut x = self.random_state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.random_state.set(x);
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn uuid(&self) -> String {
+        let bits = ((self.random() as u128) << 64) | self.random() as u128;
+        let bits = (bits & !(0xF000 << 64) | (0x4000 << 64)) & !(0xC << 60) | (0x8 << 60);
+        let hex = format!("{bits:032x}");
+        format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )
+    }
+}
+
+fn simple(value: &str) -> Term {
+    Term::Literal(Literal::new(value, None, None, None))
+}
+
+fn integer(value: i64) -> Term {
+    Numeric::Integer(value).into_term()
+}
+
+/// A string literal argument: its value and language/direction.
+fn string_argument(term: &Term) -> Option<(&str, &Literal)> {
+    match term {
+        Term::Literal(literal) if is_string_literal(literal) || has_language(literal) => {
+            Some((literal.value(), literal))
+        }
+        _ => None,
+    }
+}
+
+/// A literal with the language and direction of `like`.
+fn string_like(value: &str, like: &Literal) -> Term {
+    if has_language(like) {
+        Term::Literal(Literal::new(
+            value,
+            Some(like.language()),
+            like.direction(),
+            None,
+        ))
+    } else {
+        simple(value)
+    }
+}
+
+/// Whether two string arguments may be combined: the second has no
+/// language or the same one as the first.
+fn compatible(a: &Literal, b: &Literal) -> bool {
+    !has_language(b) || a.language() == b.language()
+}
+
+fn regex(pattern: &str, flags: Option<&str>) -> Option<Regex> {
+    let flags = flags.unwrap_or("");
+    if flags.contains(|flag| !"imsxq".contains(flag)) {
+        return None;
+    }
+    let pattern = if flags.contains('q') {
+        regex::escape(pattern)
+    } else {
+        pattern.to_owned()
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(flags.contains('i'))
+        .multi_line(flags.contains('m'))
+        .dot_matches_new_line(flags.contains('s'))
+        .ignore_whitespace(flags.contains('x'))
+        .build()
+        .ok()
+}
+
+fn hash<D: Digest>(value: &str) -> Term {
+    let digest = D::digest(value.as_bytes());
+    simple(
+        &digest
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>(),
+    )
+}
+
+fn encode_for_uri(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+fn lang_matches(tag: &str, range: &str) -> bool {
+    if range == "*" {
+        return !tag.is_empty();
+    }
+    let (tag, range) = (tag.to_ascii_lowercase(), range.to_ascii_lowercase());
+    tag == range || tag.starts_with(&format!("{range}-"))
+}
+
+fn substring(value: &str, start: f64, length: Option<f64>) -> String {
+    let start = (start + 0.5).floor();
+    let end = length.map(|length| start + (length + 0.5).floor());
+    value
+        .chars()
+        .enumerate()
+        .filter(|(i, _)| {
+            let position = (*i + 1) as f64;
+            position >= start && end.is_none_or(|end| position < end)
+        })
+        .map(|(_, c)| c)
+        .collect()
+}
+
+fn triple(subject: &Term, predicate: &Term, object: &Term) -> Option<Term> {
+    let subject = QuadSubject::try_from(subject.clone()).ok()?;
+    let predicate = QuadPredicate::try_from(predicate.clone()).ok()?;
+    let object = QuadObject::try_from(object.clone()).ok()?;
+    if matches!(subject, QuadSubject::Variable(_))
+        || matches!(predicate, QuadPredicate::Variable(_))
+        || matches!(object, QuadObject::Variable(_))
+    {
+        return None;
+    }
+    Some(Term::Quad(Box::new(Quad::new(
+        &subject, &predicate, &object, None,
+    ))))
+}
+
+/// Evaluates a function on already evaluated arguments; `None` is an
+/// expression error.
+pub(crate) fn call_function(
+    function: &Function,
+    arguments: &[Term],
+    context: &FunctionContext,
+) -> Option<Term> {
+    let argument = |i: usize| arguments.get(i);
+    let string = |i: usize| argument(i).and_then(string_argument);
+    let float = |i: usize| argument(i).and_then(numeric).map(Numeric::to_f64);
+    let date_time = |i: usize| match argument(i)? {
+        Term::Literal(literal) => DateTime::parse(literal),
+        _ => None,
+    };
+
+    match function {
+        Function::Str => match argument(0)? {
+            Term::NamedNode(named_node) => Some(simple(named_node.value())),
+            Term::Literal(literal) => Some(simple(literal.value())),
+            _ => None,
+        },
+        Function::Lang => match argument(0)? {
+            Term::Literal(literal) => Some(simple(literal.language())),
+            _ => None,
+        },
+        Function::LangDir => match argument(0)? {
+            Term::Literal(literal) => Some(simple(
+                &literal.direction().map_or(String::new(), |d| d.to_string()),
+            )),
+            _ => None,
+        },
+        Function::LangMatches => {
+            let (tag, _) = string(0)?;
+            let (range, _) = string(1)?;
+            Some(boolean_term(lang_matches(tag, range)))
+        }
+        Function::Datatype => match argument(0)? {
+            Term::Literal(literal) => Some(Term::NamedNode(literal.datatype().clone())),
+            _ => None,
+        },
+        Function::Iri => match argument(0)? {
+            Term::NamedNode(named_node) => Some(Term::NamedNode(named_node.clone())),
+            Term::Literal(literal) if is_string_literal(literal) => {
+                let value = match &context.base_iri {
+                    Some(base) if !iri::has_scheme(literal.value()) => {
+                        iri::resolve(base, literal.value())
+                    }
+                    _ => literal.value().to_owned(),
+                };
+                Some(Term::NamedNode(NamedNode::new(&value)))
+            }
+            _ => None,
+        },
+        Function::BNode => match argument(0) {
+            None => Some(Term::BlankNode(context.fresh_blank_node())),
+            Some(_) => {
+                let (label, _) = string(0)?;
+                let label: String = label.chars().filter(|c| c.is_alphanumeric()).collect();
+                Some(Term::BlankNode(BlankNode::new(&format!("b{label}"))))
+            }
+        },
+        Function::Rand => {
+            Some(Numeric::Double((context.random() >> 11) as f64 / (1u64 << 53) as f64).into_term())
+        }
+        Function::Abs | Function::Ceil | Function::Floor | Function::Round => {
+            let value = numeric(argument(0)?)?;
+            let result = match value {
+                Numeric::Integer(value) => match function {
+                    Function::Abs => Numeric::Integer(value.checked_abs()?),
+                    _ => Numeric::Integer(value),
+                },
+                Numeric::Decimal(value) => Numeric::Decimal(match function {
+                    Function::Abs => Decimal(value.0.checked_abs()?),
+                    Function::Ceil => value.ceil(),
+                    Function::Floor => value.floor(),
+                    _ => value.round(),
+                }),
+                Numeric::Float(value) => Numeric::Float(match function {
+                    Function::Abs => value.abs(),
+                    Function::Ceil => value.ceil(),
+                    Function::Floor => value.floor(),
+                    _ => (value + 0.5).floor(),
+                }),
+                Numeric::Double(value) => Numeric::Double(match function {
+                    Function::Abs => value.abs(),
+                    Function::Ceil => value.ceil(),
+                    Function::Floor => value.floor(),
+                    _ => (value + 0.5).floor(),
+                }),
+            };
+            Some(result.into_term())
+        }
+        Function::Concat => {
+            let mut value = String::new();
+            let mut language: Option<&Literal> = None;
+            let mut same_language = true;
+            for i in 0..arguments.len() {
+                let (part, literal) = string(i)?;
+                value.push_str(part);
+                match language {
+                    None if i == 0 => language = Some(literal),
+                    Some(first) if first.language() == literal.language() => {}
+                    _ => same_language = false,
+                }
+            }
+            match language {
+                Some(literal) if same_language => Some(string_like(&value, literal)),
+                _ => Some(simple(&value)),
+            }
+        }
+        Function::SubStr => {
+            let (value, literal) = string(0)?;
+            let start = float(1)?;
+            let length = match argument(2) {
+                Some(_) => Some(float(2)?),
+                None => None,
+            };
+            Some(string_like(&substring(value, start, length), literal))
+        }
+        Function::StrLen => Some(integer(string(0)?.0.chars().count() as i64)),
+        Function::Replace => {
+            let (value, literal) = string(0)?;
+            let (pattern, _) = string(1)?;
+            let (replacement, _) = string(2)?;
+            let flags = match argument(3) {
+                Some(_) => Some(string(3)?.0),
+                None => None,
+            };
+            let regex = regex(pattern, flags)?;
+            Some(string_like(&regex.replace_all(value, replacement), literal))
+        }
+        Function::UCase => {
+            let (value, literal) = string(0)?;
+            Some(string_like(&value.to_uppercase(), literal))
+        }
+        Function::LCase => {
+            let (value, literal) = string(0)?;
+            Some(string_like(&value.to_lowercase(), literal))
+        }
+        Function::EncodeForUri => Some(simple(&encode_for_uri(string(0)?.0))),
+        Function::Contains | Function::StrStarts | Function::StrEnds => {
+            let (a, a_literal) = string(0)?;
+            let (b, b_literal) = string(1)?;
+            if !compatible(a_literal, b_literal) {
+                return None;
+            }
+            Some(boolean_term(match function {
+                Function::Contains => a.contains(b),
+                Function::StrStarts => a.starts_with(b),
+                _ => a.ends_with(b),
+            }))
+        }
+        Function::StrBefore | Function::StrAfter => {
+            let (a, a_literal) = string(0)?;
+            let (b, b_literal) = string(1)?;
+            if !compatible(a_literal, b_literal) {
+                return None;
+            }
+            Some(match a.find(b) {
+                Some(i) if *function == Function::StrBefore => string_like(&a[..i], a_literal),
+                Some(i) => string_like(&a[i + b.len()..], a_literal),
+                None => simple(""),
+            })
+        }
+        Function::Year => Some(integer(date_time(0)?.year)),
+        Function::Month => Some(integer(date_time(0)?.month as i64)),
+        Function::Day => Some(integer(date_time(0)?.day as i64)),
+        Function::Hours => Some(integer(date_time(0)?.hour as i64)),
+        Function::Minutes => Some(integer(date_time(0)?.minute as i64)),
+        Function::Seconds => Some(Numeric::Decimal(date_time(0)?.second).into_term()),
+        Function::Timezone => {
+            let offset = date_time(0)?.timezone?;
+            let sign = if offset < 0 { "-" } else { "" };
+            let (hours, minutes) = (offset.abs() / 60, offset.abs() % 60);
+            let value = match (hours, minutes) {
+                (0, 0) => "PT0S".to_owned(),
+                (hours, 0) => format!("{sign}PT{hours}H"),
+                (0, minutes) => format!("{sign}PT{minutes}M"),
+                (hours, minutes) => format!("{sign}PT{hours}H{minutes}M"),
+            };
+            Some(Term::Literal(Literal::new(
+                &value,
+                None,
+                None,
+                Some(&xsd::dayTimeDuration),
+            )))
+        }
+        Function::Tz => Some(simple(&match date_time(0)?.timezone {
+            None => String::new(),
+            Some(0) => "Z".to_owned(),
+            Some(offset) => format!(
+                "{}{:02}:{:02}",
+                if offset < 0 { '-' } else { '+' },
+                offset.abs() / 60,
+                offset.abs() % 60
+            ),
+        })),
+        Function::Now => Some(Term::Literal(context.now.clone())),
+        Function::Uuid => Some(Term::NamedNode(NamedNode::new(&format!(
+            "urn:uuid:{}",
+            context.uuid()
+        )))),
+        Function::StrUuid => Some(simple(&context.uuid())),
+        Function::Md5 | Function::Sha1 | Function::Sha256 | Function::Sha384 | Function::Sha512 => {
+            let (value, literal) = string(0)?;
+            if has_language(literal) {
+                return None;
+            }
+            Some(match function {
+                Function::Md5 => hash::<Md5>(value),
+                Function::Sha1 => hash::<Sha1>(value),
+                Function::Sha256 => hash::<Sha256>(value),
+                Function::Sha384 => hash::<Sha384>(value),
+                _ => hash::<Sha512>(value),
+            })
+        }
+        Function::StrLang => {
+            let (value, literal) = string(0)?;
+            let (language, _) = string(1)?;
+            if has_language(literal) || language.is_empty() {
+                return None;
+            }
+            Some(Term::Literal(Literal::new(
+                value,
+                Some(language),
+                None,
+                None,
+            )))
+        }
+        Function::StrLangDir => {
+            let (value, literal) = string(0)?;
+            let (language, _) = string(1)?;
+            let direction: LanguageDirection = string(2)?.0.parse().ok()?;
+            if has_language(literal) || language.is_empty() {
+                return None;
+            }
+            Some(Term::Literal(Literal::new(
+                value,
+                Some(language),
+                Some(&direction),
+                None,
+            )))
+        }
+        Function::StrDt => {
+            let (value, literal) = string(0)?;
+            let Term::NamedNode(datatype) = argument(1)? else {
+                return None;
+            };
+            if has_language(literal) {
+                return None;
+            }
+            Some(Term::Literal(Literal::new(
+                value,
+                None,
+                None,
+                Some(datatype),
+            )))
+        }
+        Function::IsIri => Some(boolean_term(matches!(argument(0)?, Term::NamedNode(_)))),
+        Function::IsBlank => Some(boolean_term(matches!(argument(0)?, Term::BlankNode(_)))),
+        Function::IsLiteral => Some(boolean_term(matches!(argument(0)?, Term::Literal(_)))),
+        Function::IsNumeric => Some(boolean_term(numeric(argument(0)?).is_some())),
+        Function::IsTriple => Some(boolean_term(matches!(argument(0)?, Term::Quad(_)))),
+        Function::HasLang => match argument(0)? {
+            Term::Literal(literal) => Some(boolean_term(has_language(literal))),
+            _ => None,
+        },
+        Function::HasLangDir => match argument(0)? {
+            Term::Literal(literal) => Some(boolean_term(literal.direction().is_some())),
+            _ => None,
+        },
+        Function::Regex => {
+            let (value, _) = string(0)?;
+            let (pattern, _) = string(1)?;
+            let flags = match argument(2) {
+                Some(_) => Some(string(2)?.0),
+                None => None,
+            };
+            Some(boolean_term(regex(pattern, flags)?.is_match(value)))
+        }
+        Function::SameTerm => Some(boolean_term(argument(0)? == argument(1)?)),
+        Function::Triple => triple(argument(0)?, argument(1)?, argument(2)?),
+        Function::Subject | Function::Predicate | Function::Object => match argument(0)? {
+            Term::Quad(quad) => Some(match function {
+                Function::Subject => quad.subject().to_term(),
+                Function::Predicate => quad.predicate().to_term(),
+                _ => quad.object().to_term(),
+            }),
+            _ => None,
+        },
+        Function::Custom(name) => cast(name, argument(0)?),
+    }
+}
+
+/// XPath constructor functions such as `xsd:integer(?x)`.
+fn cast(datatype: &NamedNode, term: &Term) -> Option<Term> {
+    let lexical = match term {
+        Term::Literal(literal) => literal.value().trim().to_owned(),
+        Term::NamedNode(named_node) if *datatype == *xsd::string => named_node.value().to_owned(),
+        _ => return None,
+    };
+    let number = numeric(term);
+    let flag = boolean(term);
+
+    let value = if *datatype == *xsd::string {
+        lexical
+    } else if *datatype == *xsd::boolean {
+        match (flag, number) {
+            (Some(flag), _) => flag.to_string(),
+            (_, Some(number)) => {
+                let value = number.to_f64();
+                (value != 0.0 && !value.is_nan()).to_string()
+            }
+            _ => match lexical.as_str() {
+                "true" | "1" => "true".to_owned(),
+                "false" | "0" => "false".to_owned(),
+                _ => return None,
+            },
+        }
+    } else if is_integer_type(datatype) {
+        match (flag, number) {
+            (Some(flag), _) => (flag as i64).to_string(),
+            (_, Some(Numeric::Integer(value))) => value.to_string(),
+            (_, Some(Numeric::Decimal(value))) => (value.0 / DECIMAL_SCALE).to_string(),
+            (_, Some(value)) => {
+                let value = value.to_f64().trunc();
+                if !value.is_finite() {
+                    return None;
+                }
+                (value as i64).to_string()
+            }
+            _ => lexical.parse::<i64>().ok()?.to_string(),
+        }
+    } else if *datatype == *xsd::decimal {
+        match (flag, number) {
+            (Some(flag), _) => Decimal::from_integer(flag as i64)?.to_lexical(),
+            (_, Some(Numeric::Float(_) | Numeric::Double(_))) => {
+                Decimal::parse(&format!("{:.18}", number?.to_f64()))?.to_lexical()
+            }
+            (_, Some(number)) => number.to_decimal()?.to_lexical(),
+            _ => Decimal::parse(&lexical)?.to_lexical(),
+        }
+    } else if *datatype == *xsd::double || *datatype == *xsd::float {
+        let value = match (flag, number) {
+            (Some(flag), _) => flag as i64 as f64,
+            (_, Some(number)) => number.to_f64(),
+            _ => parse_double(&lexical)?,
+        };
+        format_double(if *datatype == *xsd::float {
+            value as f32 as f64
+        } else {
+            value
+        })
+    } else if *datatype == *xsd::dateTime || *datatype == *xsd::date {
+        let Term::Literal(literal) = term else {
+            return None;
+        };
+        DateTime::parse(&Literal::new(&lexical, None, None, Some(datatype)))?;
+        if !is_string_literal(literal) && literal.datatype() != datatype {
+            return None;
+        }
+        lexical
+    } else {
+        return None;
+    };
+
+    Some(Term::Literal(Literal::new(
+        &value,
+        None,
+        None,
+        Some(datatype),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(value: &str, datatype: &NamedNode) -> Term {
+        Term::Literal(Literal::new(value, None, None, Some(datatype)))
+    }
+
+    #[test]
+    fn promotes_numeric_operands() {
+        let result = |operator, a: &Term, b: &Term| {
+            let Term::Literal(literal) = arithmetic(operator, a, b).unwrap() else {
+                panic!("not a literal");
+            };
+            (
+                literal.value().to_owned(),
+                literal.datatype().value().to_owned(),
+            )
+        };
+
+        let two = typed("2", &xsd::integer);
+        let third = typed("0.333", &xsd::decimal);
+        assert_eq!(
+            result('+', &two, &two),
+            ("4".into(), xsd::integer.value().into())
+        );
+        assert_eq!(result('/', &two, &typed("8", &xsd::integer)).0, "0.25");
+        assert_eq!(result('*', &third, &typed("-30", &xsd::integer)).0, "-9.99");
+        assert_eq!(result('-', &two, &typed("5e-1", &xsd::double)).0, "1.5E0");
+        assert_eq!(arithmetic('/', &two, &typed("0", &xsd::integer)), None);
+    }
+
+    #[test]
+    fn compares_and_orders_terms() {
+        let one = typed("1", &xsd::integer);
+        assert_eq!(equals(&one, &typed("1.0", &xsd::decimal)), Some(true));
+        assert_eq!(equals(&one, &typed("1", &xsd::string)), Some(false));
+        assert_eq!(
+            equals(&typed("x", &NamedNode::new("http://example.org/t")), &one),
+            None
+        );
+        assert_eq!(
+            compare(
+                &typed("2000-01-01T01:00:00+01:00", &xsd::dateTime),
+                &typed("2000-01-01T00:00:00Z", &xsd::dateTime)
+            ),
+            Some(Ordering::Equal)
+        );
+
+        let blank = Term::BlankNode(BlankNode::new("b"));
+        let iri = Term::NamedNode(NamedNode::new("http://example.org/"));
+        assert_eq!(order_terms(None, Some(&blank)), Ordering::Less);
+        assert_eq!(order_terms(Some(&blank), Some(&iri)), Ordering::Less);
+        assert_eq!(order_terms(Some(&iri), Some(&one)), Ordering::Less);
+        assert_eq!(
+            order_terms(
+                Some(&typed("10", &xsd::integer)),
+                Some(&typed("9.5", &xsd::decimal))
+            ),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn built_ins_reject_ill_typed_arguments() {
+        let context = FunctionContext::new(None);
+        let call =
+            |function: Function, arguments: &[Term]| call_function(&function, arguments, &context);
+        let string = |value: &str| simple(value);
+        let tagged = |value: &str, language: &str| {
+            Term::Literal(Literal::new(value, Some(language), None, None))
+        };
+        let iri = Term::NamedNode(NamedNode::new("http://example.org/a"));
+        let blank = Term::BlankNode(BlankNode::new("b"));
+        let two = typed("2", &xsd::integer);
+
+        assert_eq!(call(Function::StrLen, &[iri.clone()]), None);
+        assert_eq!(call(Function::UCase, &[two.clone()]), None);
+        assert_eq!(call(Function::Abs, &[string("-2")]), None);
+        assert_eq!(
+            call(Function::Abs, &[typed("-2", &xsd::integer)]),
+            Some(two.clone())
+        );
+        assert_eq!(
+            call(Function::Year, &[string("2000-01-01T00:00:00Z")]),
+            None
+        );
+        assert_eq!(call(Function::Str, &[blank.clone()]), None);
+        assert_eq!(call(Function::Lang, &[iri.clone()]), None);
+        assert_eq!(
+            call(
+                Function::SubStr,
+                &[string("hello"), two.clone(), typed("3", &xsd::integer)]
+            ),
+            Some(string("ell"))
+        );
+        assert_eq!(call(Function::SubStr, &[iri.clone(), two.clone()]), None);
+
+        // String functions need compatible arguments and keep the tag of the
+        // first one.
+        assert_eq!(
+            call(
+                Function::Contains,
+                &[tagged("abc", "en"), tagged("b", "fr")]
+            ),
+            None
+        );
+        assert_eq!(
+            call(Function::Contains, &[tagged("abc", "en"), string("b")]),
+            Some(boolean_term(true))
+        );
+        assert_eq!(
+            call(Function::StrAfter, &[tagged("abc", "en"), string("a")]),
+            Some(tagged("bc", "en"))
+        );
+        assert_eq!(
+            call(Function::StrBefore, &[tagged("abc", "en"), string("z")]),
+            Some(string(""))
+        );
+        assert_eq!(
+            call(Function::Concat, &[tagged("a", "en"), tagged("b", "en")]),
+            Some(tagged("ab", "en"))
+        );
+        assert_eq!(
+            call(Function::Concat, &[tagged("a", "en"), string("b")]),
+            Some(string("ab"))
+        );
+
+        // Constructors only take simple literals.
+        assert_eq!(
+            call(Function::StrLang, &[string("a"), string("en")]),
+            Some(tagged("a", "en"))
+        );
+        assert_eq!(
+            call(Function::StrLang, &[tagged("a", "en"), string("fr")]),
+            None
+        );
+        assert_eq!(
+            call(
+                Function::StrDt,
+                &[string("2"), Term::NamedNode(xsd::integer.clone())]
+            ),
+            Some(two.clone())
+        );
+        assert_eq!(
+            call(
+                Function::StrDt,
+                &[tagged("2", "en"), Term::NamedNode(xsd::integer.clone())]
+            ),
+            None
+        );
+
+        assert_eq!(
+            call(Function::LangMatches, &[string("en-US"), string("en")]),
+            Some(boolean_term(true))
+        );
+        assert_eq!(
+            call(Function::LangMatches, &[string(""), string("*")]),
+            Some(boolean_term(false))
+        );
+        assert_eq!(call(Function::Regex, &[string("a"), string("(")]), None);
+        assert_eq!(
+            call(Function::IsNumeric, &[typed("x", &xsd::integer)]),
+            Some(boolean_term(false))
+        );
+        assert_eq!(
+            call(Function::IsNumeric, &[two.clone()]),
+            Some(boolean_term(true))
+        );
+    }
+
+    #[test]
+    fn effective_boolean_values_follow_the_spec() {
+        assert_eq!(effective_boolean_value(&simple("")), Some(false));
+        assert_eq!(effective_boolean_value(&simple("false")), Some(true));
+        assert_eq!(
+            effective_boolean_value(&typed("0", &xsd::integer)),
+            Some(false)
+        );
+        assert_eq!(
+            effective_boolean_value(&typed("NaN", &xsd::double)),
+            Some(false)
+        );
+        // Ill-typed numbers and booleans are false, other terms are errors.
+        assert_eq!(
+            effective_boolean_value(&typed("abc", &xsd::integer)),
+            Some(false)
+        );
+        assert_eq!(
+            effective_boolean_value(&typed("yes", &xsd::boolean)),
+            Some(false)
+        );
+        assert_eq!(
+            effective_boolean_value(&Term::NamedNode(NamedNode::new("http://example.org/"))),
+            None
+        );
+        assert_eq!(
+            effective_boolean_value(&typed("x", &NamedNode::new("http://example.org/t"))),
+            None
+        );
+    }
+}
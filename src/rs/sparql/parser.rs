@@ -0,0 +1,2573 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::default_graph::DefaultGraph;
+use crate::rs::iri;
+use crate::rs::literal::{LanguageDirection, Literal, is_valid_language_tag};
+use crate::rs::named_node::NamedNode;
+use crate::rs::parse_error::SyntaxError;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::sparql::algebra::{
+    AggregateExpression, AggregateFunction, BUILT_IN_FUNCTIONS, Expression, Function, GraphPattern,
+    GraphTarget, NamedNodePattern, OrderExpression, PropertyPath, Query, QueryDataset, Update,
+    UpdateOperation,
+};
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::turtle::{
+    is_iri_forbidden, is_pn_chars, is_pn_chars_base, is_pn_chars_u, is_pn_local_escapable,
+};
+use crate::rs::variable::Variable;
+use crate::rs::vocab::{rdf, xsd};
+
+type ParseResult<T> = Result<T, SyntaxError>;
+
+/// Parses a SPARQL 1.1 query. Relative IRIs are resolved against `base_iri`
+/// unless the query declares its own `BASE`.
+pub fn parse_query(query: &str, base_iri: Option<&str>) -> Result<Query, SyntaxError> {
+    let tokens = Lexer::new(query).tokenize()?;
+    Parser::new(query, tokens, base_iri).parse_query_unit()
+}
+
+/// Parses a SPARQL 1.1 Update request, see [`parse_query`] for `base_iri`.
+pub fn parse_update(update: &str, base_iri: Option<&str>) -> Result<Update, SyntaxError> {
+    let tokens = Lexer::new(update).tokenize()?;
+    Parser::new(update, tokens, base_iri).parse_update_request()
+}
+
+/// Parses a single ground RDF term in SPARQL syntax, e.g. a TSV result cell.
+pub(crate) fn parse_term(term: &str) -> Result<Term, SyntaxError> {
+    let tokens = Lexer::new(term).tokenize()?;
+    let mut parser = Parser::new(term, tokens, None);
+    let parsed = parser.parse_var_or_term()?;
+    let is_ground = match &parsed {
+        Term::Variable(_) => false,
+        Term::Quad(quad) => !has_variables(quad),
+        _ => true,
+    };
+    if !is_ground {
+        return Err(syntax_error(term, "variables are not allowed here", 0));
+    }
+    if *parser.peek() != TokenKind::End {
+        return parser.unexpected("the end of the term");
+    }
+    Ok(parsed)
+}
+
+fn syntax_error(input: &str, message: &str, offset: usize) -> SyntaxError {
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let column = before[line_start..].chars().count() + 1;
+    SyntaxError::new(message, line, column)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum TokenKind {
+    Iri(String),
+    PrefixedName(String, String),
+    BlankNodeLabel(String),
+    Variable(String),
+    LangTag(String),
+    Integer(String),
+    Decimal(String),
+    Double(String),
+    String(String),
+    Name(String),
+    Punct(&'static str),
+    End,
+}
+
+impl TokenKind {
+    fn is_signed_number(&self) -> bool {
+        match self {
+            TokenKind::Integer(value) | TokenKind::Decimal(value) | TokenKind::Double(value) => {
+                value.starts_with(['+', '-'])
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Iri(value) => write!(f, "<{value}>"),
+            TokenKind::PrefixedName(prefix, local) => write!(f, "{prefix}:{local}"),
+            TokenKind::BlankNodeLabel(label) => write!(f, "_:{label}"),
+            TokenKind::Variable(name) => write!(f, "?{name}"),
+            TokenKind::LangTag(tag) => write!(f, "@{tag}"),
+            TokenKind::Integer(value) | TokenKind::Decimal(value) | TokenKind::Double(value) => {
+                write!(f, "{value}")
+            }
+            TokenKind::String(_) => write!(f, "a string"),
+            TokenKind::Name(name) => write!(f, "'{name}'"),
+            TokenKind::Punct(punct) => write!(f, "'{punct}'"),
+            TokenKind::End => write!(f, "end of input"),
+        }
+    }
+}
+
+struct Token {
+    kind: TokenKind,
+    start: usize,
+}
+
+const PUNCTUATION: &[&str] = &[
+    "||", "&&", "!=", "^^", "{", "}", "(", ")", "[", "]", ".", ",", ";", "*", "/", "+", "-", "!",
+    "^", "|", "=",
+];
+
+struct Lexer<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, position: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.input[self.position..].chars().nth(n)
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        self.input[self.position..].starts_with(prefix)
+    }
+
+    fn error<T>(&self, message: &str, position: usize) -> ParseResult<T> {
+        Err(syntax_error(self.input, message, position))
+    }
+
+    fn tokenize(mut self) -> ParseResult<Vec<Token>> {
+        let mut tokens = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            let Some(c) = self.peek() else {
+                tokens.push(Token {
+                    kind: TokenKind::End,
+                    start,
+                });
+                return Ok(tokens);
+            };
+            let kind = self.lex_token(c, start)?;
+            tokens.push(Token { kind, start });
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                while !matches!(self.advance(), Some('\n') | None) {}
+            } else if c.is_whitespace() {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn lex_token(&mut self, c: char, start: usize) -> ParseResult<TokenKind> {
+        let next = self.peek_nth(1);
+        let starts_number = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+
+        match c {
+            '<' => {
+                if self.starts_with("<<") {
+                    self.position += 2;
+                    return Ok(TokenKind::Punct("<<"));
+                }
+                if let Some(iri) = self.lex_iri_ref()? {
+                    return Ok(TokenKind::Iri(iri));
+                }
+                self.advance();
+                Ok(self.punct_with_equals("<", "<="))
+            }
+            '>' => {
+                if self.starts_with(">>") {
+                    self.position += 2;
+                    return Ok(TokenKind::Punct(">>"));
+                }
+                self.advance();
+                Ok(self.punct_with_equals(">", ">="))
+            }
+            '"' | '\'' => Ok(TokenKind::String(self.lex_string(c)?)),
+            '?' | '$' if next.is_some_and(|c| is_pn_chars_u(c) || c.is_ascii_digit()) => {
+                self.advance();
+                Ok(TokenKind::Variable(self.lex_variable_name()))
+            }
+            '@' => {
+                self.advance();
+                self.lex_language_tag(start)
+            }
+            '_' if next == Some(':') => {
+                self.position += 2;
+                self.lex_blank_node_label(start)
+            }
+            '0'..='9' => Ok(self.lex_number()),
+            '.' if starts_number(next) => Ok(self.lex_number()),
+            '+' | '-'
+                if starts_number(next)
+                    || (next == Some('.') && starts_number(self.peek_nth(2))) =>
+            {
+                Ok(self.lex_number())
+            }
+            ':' => {
+                self.advance();
+                Ok(TokenKind::PrefixedName(
+                    String::new(),
+                    self.lex_local_name()?,
+                ))
+            }
+            c if is_pn_chars_base(c) => self.lex_name(start),
+            '?' => {
+                self.advance();
+                Ok(TokenKind::Punct("?"))
+            }
+            _ => {
+                for punct in PUNCTUATION {
+                    if self.starts_with(punct) {
+                        self.position += punct.len();
+                        return Ok(TokenKind::Punct(punct));
+                    }
+                }
+                self.error(&format!("unexpected character '{c}'"), start)
+            }
+        }
+    }
+
+    fn punct_with_equals(&mut self, single: &'static str, double: &'static str) -> TokenKind {
+        if self.peek() == Some('=') {
+            self.advance();
+            TokenKind::Punct(double)
+        } else {
+            TokenKind::Punct(single)
+        }
+    }
+
+    /// An IRI reference if the input at `<` forms one, otherwise `None` so
+    /// that `<` is read as an operator.
+    fn lex_iri_ref(&mut self) -> ParseResult<Option<String>> {
+        let start = self.position;
+        self.advance();
+
+        let mut value = String::new();
+        loop {
+            match self.advance() {
+                Some('>') => return Ok(Some(value)),
+                Some('\\') => {
+                    let escape_start = self.position - 1;
+                    let c = match self.advance() {
+                        Some('u') => self.lex_hex_char(4, escape_start)?,
+                        Some('U') => self.lex_hex_char(8, escape_start)?,
+                        _ => return self.error("invalid IRI escape", escape_start),
+                    };
+                    value.push(c);
+                }
+                Some(c) if !is_iri_forbidden(c) => value.push(c),
+                _ => {
+                    self.position = start;
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    fn lex_hex_char(&mut self, length: usize, start: usize) -> ParseResult<char> {
+        let digits = self.input[self.position..]
+            .get(..length)
+            .filter(|digits| digits.chars().all(|c| c.is_ascii_hexdigit()));
+        let Some(c) = digits
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .and_then(char::from_u32)
+        else {
+            return self.error("invalid unicode escape", start);
+        };
+        self.position += length;
+        Ok(c)
+    }
+
+    fn lex_string(&mut self, quote: char) -> ParseResult<String> {
+        let start = self.position;
+        let long: String = [quote; 3].iter().collect();
+        let is_long = self.starts_with(&long);
+        self.position += if is_long { 3 } else { 1 };
+
+        let mut value = String::new();
+        loop {
+            if is_long && self.starts_with(&long) {
+                self.position += 3;
+                return Ok(value);
+            }
+            match self.advance() {
+                Some(c) if c == quote && !is_long => return Ok(value),
+                Some('\\') => {
+                    let escape_start = self.position - 1;
+                    value.push(match self.advance() {
+                        Some('t') => '\t',
+                        Some('b') => '\u{8}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('f') => '\u{c}',
+                        Some('"') => '"',
+                        Some('\'') => '\'',
+                        Some('\\') => '\\',
+                        Some('u') => self.lex_hex_char(4, escape_start)?,
+                        Some('U') => self.lex_hex_char(8, escape_start)?,
+                        _ => return self.error("invalid string escape", escape_start),
+                    });
+                }
+                Some('\n' | '\r') if !is_long => {
+                    return self.error("unterminated string", start);
+                }
+                Some(c) => value.push(c),
+                None => return self.error("unterminated string", start),
+            }
+        }
+    }
+
+    fn lex_variable_name(&mut self) -> String {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if c != '-' && is_pn_chars(c) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.input[start..self.position].to_owned()
+    }
+
+    fn lex_language_tag(&mut self, start: usize) -> ParseResult<TokenKind> {
+        let tag_start = self.position;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        let tag = &self.input[tag_start..self.position];
+        let language = tag.split_once("--").map_or(tag, |(language, _)| language);
+        if !is_valid_language_tag(language) {
+            return self.error(&format!("invalid language tag \"{tag}\""), start);
+        }
+        Ok(TokenKind::LangTag(tag.to_owned()))
+    }
+
+    fn lex_blank_node_label(&mut self, start: usize) -> ParseResult<TokenKind> {
+        let label_start = self.position;
+        let mut end = label_start;
+        while let Some(c) = self.peek() {
+            let first = self.position == label_start;
+            if is_pn_chars_u(c) || c.is_ascii_digit() || (!first && (is_pn_chars(c) || c == '.')) {
+                self.advance();
+                if c != '.' {
+                    end = self.position;
+                }
+            } else {
+                break;
+            }
+        }
+        self.position = end;
+        if end == label_start {
+            return self.error("invalid blank node label", start);
+        }
+        Ok(TokenKind::BlankNodeLabel(
+            self.input[label_start..end].to_owned(),
+        ))
+    }
+
+    fn lex_number(&mut self) -> TokenKind {
+        let start = self.position;
+        if matches!(self.peek(), Some('+' | '-')) {
+            self.advance();
+        }
+        self.skip_digits();
+
+        let mut kind = 0;
+        if self.peek() == Some('.') && self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.advance();
+            self.skip_digits();
+            kind = 1;
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let digits_at = match self.peek_nth(1) {
+                Some('+' | '-') => 2,
+                _ => 1,
+            };
+            if self.peek_nth(digits_at).is_some_and(|c| c.is_ascii_digit()) {
+                self.position += digits_at;
+                self.skip_digits();
+                kind = 2;
+            }
+        }
+
+        let value = self.input[start..self.position].to_owned();
+        match kind {
+            0 => TokenKind::Integer(value),
+            1 => TokenKind::Decimal(value),
+            _ => TokenKind::Double(value),
+        }
+    }
+
+    fn skip_digits(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.advance();
+        }
+    }
+
+    fn lex_name(&mut self, start: usize) -> ParseResult<TokenKind> {
+        let rest = &self.input[start..];
+
+        let mut prefix_end = 0;
+        for (i, c) in rest.char_indices() {
+            if is_pn_chars(c) {
+                prefix_end = i + c.len_utf8();
+            } else if c != '.' {
+                break;
+            }
+        }
+        if rest[prefix_end..].starts_with(':') {
+            let prefix = rest[..prefix_end].to_owned();
+            self.position = start + prefix_end + 1;
+            return Ok(TokenKind::PrefixedName(prefix, self.lex_local_name()?));
+        }
+
+        let length = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return self.error("unexpected character", start);
+        }
+        self.position += length;
+        Ok(TokenKind::Name(rest[..length].to_owned()))
+    }
+
+    fn lex_local_name(&mut self) -> ParseResult<String> {
+        let mut local = String::new();
+        let mut committed = (self.position, 0);
+        while let Some(c) = self.peek() {
+            let first = local.is_empty();
+            if c == '%' {
+                let start = self.position;
+                let hex = self.input[self.position + 1..]
+                    .get(..2)
+                    .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()));
+                let Some(hex) = hex else {
+                    return self.error("invalid percent escape in local name", start);
+                };
+                local.push('%');
+                local.push_str(hex);
+                self.position += 3;
+            } else if c == '\\' {
+                match self.peek_nth(1) {
+                    Some(escaped) if is_pn_local_escapable(escaped) => {
+                        local.push(escaped);
+                        self.position += 1 + escaped.len_utf8();
+                    }
+                    _ => return self.error("invalid escape in local name", self.position),
+                }
+            } else if is_pn_chars_u(c)
+                || c == ':'
+                || c.is_ascii_digit()
+                || (!first && (is_pn_chars(c) || c == '.'))
+            {
+                local.push(c);
+                self.advance();
+            } else {
+                break;
+            }
+            if c != '.' {
+                committed = (self.position, local.len());
+            }
+        }
+        self.position = committed.0;
+        local.truncate(committed.1);
+        Ok(local)
+    }
+}
+
+enum TripleItem {
+    Triple(Quad),
+    Path(Term, PropertyPath, Term),
+}
+
+enum Verb {
+    Predicate(QuadPredicate),
+    Path(PropertyPath),
+}
+
+struct SelectClause {
+    distinct: bool,
+    reduced: bool,
+    /// `None` for `SELECT *`.
+    projection: Option<Vec<(Variable, Option<Expression>)>>,
+}
+
+#[derive(Default)]
+struct SolutionModifier {
+    group: Option<Vec<(Expression, Option<Variable>)>>,
+    having: Vec<Expression>,
+    order: Vec<OrderExpression>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+const AGGREGATES: &[&str] = &[
+    "COUNT",
+    "SUM",
+    "MIN",
+    "MAX",
+    "AVG",
+    "SAMPLE",
+    "GROUP_CONCAT",
+];
+
+const SPECIAL_FORMS: &[&str] = &["BOUND", "IF", "COALESCE", "EXISTS", "NOT"];
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    index: usize,
+    base_iri: Option<String>,
+    prefixes: HashMap<String, String>,
+    blank_node_count: usize,
+    aggregate_count: usize,
+    group_count: usize,
+    aggregates: Vec<(Variable, AggregateExpression)>,
+    aggregates_allowed: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str, tokens: Vec<Token>, base_iri: Option<&str>) -> Self {
+        Self {
+            input,
+            tokens,
+            index: 0,
+            base_iri: base_iri.map(str::to_owned),
+            prefixes: HashMap::new(),
+            blank_node_count: 0,
+            aggregate_count: 0,
+            group_count: 0,
+            aggregates: Vec::new(),
+            aggregates_allowed: false,
+        }
+    }
+
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[self.index].kind
+    }
+
+    fn peek_nth(&self, n: usize) -> &TokenKind {
+        &self.tokens[(self.index + n).min(self.tokens.len() - 1)].kind
+    }
+
+    fn next_token(&mut self) -> TokenKind {
+        let kind = self.tokens[self.index].kind.clone();
+        if kind != TokenKind::End {
+            self.index += 1;
+        }
+        kind
+    }
+
+    fn error<T>(&self, message: &str) -> ParseResult<T> {
+        Err(syntax_error(
+            self.input,
+            message,
+            self.tokens[self.index].start,
+        ))
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> ParseResult<T> {
+        self.error(&format!("expected {expected}, found {}", self.peek()))
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), TokenKind::Name(name) if name.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> ParseResult<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            self.unexpected(keyword)
+        }
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), TokenKind::Punct(p) if *p == punct)
+    }
+
+    fn eat_punct(&mut self, punct: &str) -> bool {
+        let found = self.is_punct(punct);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn expect_punct(&mut self, punct: &str) -> ParseResult<()> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("'{punct}'"))
+        }
+    }
+
+    fn with_aggregates<T>(
+        &mut self,
+        allowed: bool,
+        parse: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        let previous = std::mem::replace(&mut self.aggregates_allowed, allowed);
+        let result = parse(self);
+        self.aggregates_allowed = previous;
+        result
+    }
+
+    fn fresh_blank_node(&mut self) -> Term {
+        self.blank_node_count += 1;
+        Term::BlankNode(BlankNode::new(&format!("genid{}", self.blank_node_count)))
+    }
+
+    fn parse_query_unit(mut self) -> ParseResult<Query> {
+        self.parse_prologue()?;
+
+        let query = if self.eat_keyword("SELECT") {
+            let select = self.with_aggregates(true, Self::parse_select_clause)?;
+            let dataset = self.parse_dataset_clause()?;
+            let pattern = self.parse_query_body(Some(select))?;
+            Query::Select {
+                dataset,
+                pattern,
+                base_iri: self.base_iri.clone(),
+            }
+        } else if self.eat_keyword("CONSTRUCT") {
+            self.parse_construct_query()?
+        } else if self.eat_keyword("DESCRIBE") {
+            let mut targets = Vec::new();
+            if !self.eat_punct("*") {
+                while let Some(target) = self.parse_var_or_iri()? {
+                    targets.push(target);
+                }
+                if targets.is_empty() {
+                    return self.unexpected("a variable, an IRI or '*'");
+                }
+            }
+            let dataset = self.parse_dataset_clause()?;
+            let pattern = if self.is_keyword("WHERE") || self.is_punct("{") {
+                self.parse_query_body(None)?
+            } else {
+                let modifier = self.parse_solution_modifier()?;
+                let values = self.parse_values_clause()?;
+                self.build_pattern(GraphPattern::empty(), None, modifier, values)?
+            };
+            Query::Describe {
+                targets,
+                dataset,
+                pattern,
+                base_iri: self.base_iri.clone(),
+            }
+        } else if self.eat_keyword("ASK") {
+            let dataset = self.parse_dataset_clause()?;
+            let pattern = self.parse_query_body(None)?;
+            Query::Ask {
+                dataset,
+                pattern,
+                base_iri: self.base_iri.clone(),
+            }
+        } else {
+            return self.unexpected("SELECT, CONSTRUCT, DESCRIBE or ASK");
+        };
+
+        if *self.peek() != TokenKind::End {
+            return self.unexpected("end of query");
+        }
+        Ok(query)
+    }
+
+    fn parse_update_request(mut self) -> ParseResult<Update> {
+        let mut operations = Vec::new();
+        loop {
+            self.parse_prologue()?;
+            if *self.peek() == TokenKind::End {
+                break;
+            }
+            operations.push(self.parse_update_operation()?);
+            if !self.eat_punct(";") {
+                break;
+            }
+        }
+
+        if *self.peek() != TokenKind::End {
+            return self.unexpected("end of update");
+        }
+        Ok(Update {
+            operations,
+            base_iri: self.base_iri.clone(),
+        })
+    }
+
+    fn parse_update_operation(&mut self) -> ParseResult<UpdateOperation> {
+        if self.eat_keyword("LOAD") {
+            let silent = self.eat_keyword("SILENT");
+            let source = self.parse_iri()?;
+            let destination = if self.eat_keyword("INTO") {
+                self.expect_keyword("GRAPH")?;
+                QuadGraph::NamedNode(self.parse_iri()?)
+            } else {
+                QuadGraph::DefaultGraph(DefaultGraph::new())
+            };
+            return Ok(UpdateOperation::Load {
+                silent,
+                source,
+                destination,
+            });
+        }
+        if self.eat_keyword("CLEAR") {
+            let silent = self.eat_keyword("SILENT");
+            let target = self.parse_graph_target()?;
+            return Ok(UpdateOperation::Clear { silent, target });
+        }
+        if self.eat_keyword("DROP") {
+            let silent = self.eat_keyword("SILENT");
+            let target = self.parse_graph_target()?;
+            return Ok(UpdateOperation::Drop { silent, target });
+        }
+        if self.eat_keyword("CREATE") {
+            let silent = self.eat_keyword("SILENT");
+            self.expect_keyword("GRAPH")?;
+            let graph = self.parse_iri()?;
+            return Ok(UpdateOperation::Create { silent, graph });
+        }
+        for keyword in ["COPY", "MOVE", "ADD"] {
+            if self.eat_keyword(keyword) {
+                let silent = self.eat_keyword("SILENT");
+                let source = self.parse_graph_or_default()?;
+                self.expect_keyword("TO")?;
+                let destination = self.parse_graph_or_default()?;
+                return Ok(match keyword {
+                    "COPY" => UpdateOperation::Copy {
+                        silent,
+                        source,
+                        destination,
+                    },
+                    "MOVE" => UpdateOperation::Move {
+                        silent,
+                        source,
+                        destination,
+                    },
+                    _ => UpdateOperation::Add {
+                        silent,
+                        source,
+                        destination,
+                    },
+                });
+            }
+        }
+        if self.eat_keyword("INSERT") {
+            if self.eat_keyword("DATA") {
+                let data = self.parse_quad_data(false)?;
+                return Ok(UpdateOperation::InsertData { data });
+            }
+            return self.parse_modify(None, false);
+        }
+        if self.eat_keyword("DELETE") {
+            if self.eat_keyword("DATA") {
+                let data = self.parse_quad_data(true)?;
+                return Ok(UpdateOperation::DeleteData { data });
+            }
+            if self.eat_keyword("WHERE") {
+                let delete = self.parse_delete_template()?;
+                let pattern = Box::new(delete_where_pattern(&delete));
+                return Ok(UpdateOperation::DeleteInsert {
+                    with: None,
+                    delete,
+                    insert: Vec::new(),
+                    using: None,
+                    pattern,
+                });
+            }
+            return self.parse_modify(None, true);
+        }
+        if self.eat_keyword("WITH") {
+            let with = Some(self.parse_iri()?);
+            if self.eat_keyword("DELETE") {
+                return self.parse_modify(with, true);
+            }
+            if self.eat_keyword("INSERT") {
+                return self.parse_modify(with, false);
+            }
+            return self.unexpected("DELETE or INSERT");
+        }
+
+        self.unexpected("an update operation")
+    }
+
+    fn parse_graph_target(&mut self) -> ParseResult<GraphTarget> {
+        Ok(if self.eat_keyword("DEFAULT") {
+            GraphTarget::DefaultGraph
+        } else if self.eat_keyword("NAMED") {
+            GraphTarget::Named
+        } else if self.eat_keyword("ALL") {
+            GraphTarget::All
+        } else {
+            self.expect_keyword("GRAPH")?;
+            GraphTarget::NamedNode(self.parse_iri()?)
+        })
+    }
+
+    fn parse_graph_or_default(&mut self) -> ParseResult<QuadGraph> {
+        if self.eat_keyword("DEFAULT") {
+            return Ok(QuadGraph::DefaultGraph(DefaultGraph::new()));
+        }
+        self.eat_keyword("GRAPH");
+        Ok(QuadGraph::NamedNode(self.parse_iri()?))
+    }
+
+    /// `DELETE`/`INSERT` templates followed by `USING` clauses and `WHERE`,
+    /// the first keyword having been consumed.
+    fn parse_modify(
+        &mut self,
+        with: Option<NamedNode>,
+        starts_with_delete: bool,
+    ) -> ParseResult<UpdateOperation> {
+        let mut delete = Vec::new();
+        let mut insert = Vec::new();
+        if starts_with_delete {
+            delete = self.parse_delete_template()?;
+            if self.eat_keyword("INSERT") {
+                insert = self.parse_quad_pattern()?;
+            }
+        } else {
+            insert = self.parse_quad_pattern()?;
+        }
+
+        let mut using = QueryDataset::default();
+        while self.eat_keyword("USING") {
+            if self.eat_keyword("NAMED") {
+                using.named.push(self.parse_iri()?);
+            } else {
+                using.default.push(self.parse_iri()?);
+            }
+        }
+        self.expect_keyword("WHERE")?;
+        let pattern = Box::new(self.parse_group_graph_pattern()?);
+
+        Ok(UpdateOperation::DeleteInsert {
+            with,
+            delete,
+            insert,
+            using: (!using.default.is_empty() || !using.named.is_empty()).then_some(using),
+            pattern,
+        })
+    }
+
+    fn parse_delete_template(&mut self) -> ParseResult<Vec<Quad>> {
+        let start = self.index;
+        let quads = self.parse_quad_pattern()?;
+        if quads.iter().any(has_blank_nodes) {
+            self.index = start;
+            return self.error("blank nodes are not allowed in DELETE templates");
+        }
+        Ok(quads)
+    }
+
+    fn parse_quad_data(&mut self, deleting: bool) -> ParseResult<Vec<Quad>> {
+        let start = self.index;
+        let quads = self.parse_quad_pattern()?;
+        if quads.iter().any(has_variables) {
+            self.index = start;
+            return self.error("variables are not allowed in data");
+        }
+        if deleting && quads.iter().any(has_blank_nodes) {
+            self.index = start;
+            return self.error("blank nodes are not allowed in DELETE DATA");
+        }
+        Ok(quads)
+    }
+
+    /// Triples, possibly inside `GRAPH` blocks, between braces.
+    fn parse_quad_pattern(&mut self) -> ParseResult<Vec<Quad>> {
+        self.expect_punct("{")?;
+        let mut quads = Vec::new();
+        loop {
+            if self.eat_punct("}") {
+                break;
+            } else if self.eat_keyword("GRAPH") {
+                let graph = match self.parse_var_or_iri()? {
+                    Some(NamedNodePattern::NamedNode(named_node)) => {
+                        QuadGraph::NamedNode(named_node)
+                    }
+                    Some(NamedNodePattern::Variable(variable)) => QuadGraph::Variable(variable),
+                    None => return self.unexpected("a variable or an IRI"),
+                };
+                for triple in self.parse_construct_template()? {
+                    quads.push(Quad::new(
+                        triple.subject(),
+                        triple.predicate(),
+                        triple.object(),
+                        Some(&graph),
+                    ));
+                }
+            } else if self.starts_triples() {
+                let mut items = Vec::new();
+                loop {
+                    self.parse_triples_same_subject(&mut items, false)?;
+                    if !self.eat_punct(".") || !self.starts_triples() {
+                        break;
+                    }
+                }
+                quads.extend(items.into_iter().filter_map(|item| match item {
+                    TripleItem::Triple(quad) => Some(quad),
+                    TripleItem::Path(..) => None,
+                }));
+            } else if !self.eat_punct(".") {
+                return self.unexpected("triples, GRAPH or '}'");
+            }
+        }
+        Ok(quads)
+    }
+
+    fn parse_prologue(&mut self) -> ParseResult<()> {
+        loop {
+            if self.eat_keyword("BASE") {
+                let TokenKind::Iri(value) = self.next_token() else {
+                    self.index -= 1;
+                    return self.unexpected("an IRI");
+                };
+                self.base_iri = Some(self.resolve(value));
+            } else if self.eat_keyword("PREFIX") {
+                let TokenKind::PrefixedName(prefix, local) = self.next_token() else {
+                    self.index -= 1;
+                    return self.unexpected("a prefix declaration");
+                };
+                if !local.is_empty() {
+                    self.index -= 1;
+                    return self.error("prefix names must end with ':'");
+                }
+                let TokenKind::Iri(value) = self.next_token() else {
+                    self.index -= 1;
+                    return self.unexpected("an IRI");
+                };
+                let namespace = self.resolve(value);
+                self.prefixes.insert(prefix, namespace);
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn resolve(&self, value: String) -> String {
+        match &self.base_iri {
+            Some(base) if !iri::has_scheme(&value) => iri::resolve(base, &value),
+            _ => value,
+        }
+    }
+
+    fn parse_construct_query(&mut self) -> ParseResult<Query> {
+        let (template, dataset, pattern) = if self.is_punct("{") {
+            let template = self.parse_construct_template()?;
+            let dataset = self.parse_dataset_clause()?;
+            let pattern = self.parse_query_body(None)?;
+            (template, dataset, pattern)
+        } else {
+            let dataset = self.parse_dataset_clause()?;
+            self.expect_keyword("WHERE")?;
+            let template = self.parse_construct_template()?;
+            let modifier = self.parse_solution_modifier()?;
+            let values = self.parse_values_clause()?;
+            let where_pattern = GraphPattern::Bgp {
+                patterns: template.clone(),
+            };
+            let pattern = self.build_pattern(where_pattern, None, modifier, values)?;
+            (template, dataset, pattern)
+        };
+
+        Ok(Query::Construct {
+            template,
+            dataset,
+            pattern,
+            base_iri: self.base_iri.clone(),
+        })
+    }
+
+    fn parse_construct_template(&mut self) -> ParseResult<Vec<Quad>> {
+        self.expect_punct("{")?;
+        let mut items = Vec::new();
+        while !self.is_punct("}") {
+            self.parse_triples_same_subject(&mut items, false)?;
+            if !self.eat_punct(".") {
+                break;
+            }
+        }
+        self.expect_punct("}")?;
+
+        Ok(items
+            .into_iter()
+            .filter_map(|item| match item {
+                TripleItem::Triple(quad) => Some(quad),
+                TripleItem::Path(..) => None,
+            })
+            .collect())
+    }
+
+    /// The WHERE clause, solution modifiers and trailing VALUES of a query.
+    fn parse_query_body(&mut self, select: Option<SelectClause>) -> ParseResult<GraphPattern> {
+        let where_pattern = self.parse_where_clause()?;
+        let modifier = self.parse_solution_modifier()?;
+        let values = self.parse_values_clause()?;
+        self.build_pattern(where_pattern, select, modifier, values)
+    }
+
+    fn parse_dataset_clause(&mut self) -> ParseResult<Option<QueryDataset>> {
+        let mut dataset = QueryDataset::default();
+        while self.eat_keyword("FROM") {
+            if self.eat_keyword("NAMED") {
+                dataset.named.push(self.parse_iri()?);
+            } else {
+                dataset.default.push(self.parse_iri()?);
+            }
+        }
+
+        Ok((!dataset.default.is_empty() || !dataset.named.is_empty()).then_some(dataset))
+    }
+
+    fn parse_select_clause(&mut self) -> ParseResult<SelectClause> {
+        let distinct = self.eat_keyword("DISTINCT");
+        let reduced = !distinct && self.eat_keyword("REDUCED");
+
+        if self.eat_punct("*") {
+            return Ok(SelectClause {
+                distinct,
+                reduced,
+                projection: None,
+            });
+        }
+
+        let mut projection: Vec<(Variable, Option<Expression>)> = Vec::new();
+        loop {
+            let item = if let TokenKind::Variable(name) = self.peek() {
+                let variable = Variable::new(name);
+                self.index += 1;
+                (variable, None)
+            } else if self.eat_punct("(") {
+                let expression = self.parse_expression()?;
+                self.expect_keyword("AS")?;
+                let variable = self.parse_variable()?;
+                self.expect_punct(")")?;
+                (variable, Some(expression))
+            } else {
+                break;
+            };
+            if projection.iter().any(|(variable, _)| *variable == item.0) {
+                return self.error("variables may only be projected once");
+            }
+            projection.push(item);
+        }
+        if projection.is_empty() {
+            return self.unexpected("a projection");
+        }
+
+        Ok(SelectClause {
+            distinct,
+            reduced,
+            projection: Some(projection),
+        })
+    }
+
+    fn parse_sub_select(&mut self) -> ParseResult<GraphPattern> {
+        self.expect_keyword("SELECT")?;
+        let outer = std::mem::take(&mut self.aggregates);
+
+        let select = self.with_aggregates(true, Self::parse_select_clause)?;
+        let pattern = self.parse_query_body(Some(select));
+
+        self.aggregates = outer;
+        pattern
+    }
+
+    fn parse_where_clause(&mut self) -> ParseResult<GraphPattern> {
+        self.eat_keyword("WHERE");
+        self.with_aggregates(false, Self::parse_group_graph_pattern)
+    }
+
+    fn parse_solution_modifier(&mut self) -> ParseResult<SolutionModifier> {
+        let mut modifier = SolutionModifier::default();
+
+        if self.eat_keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            let mut conditions = Vec::new();
+            loop {
+                if let TokenKind::Variable(name) = self.peek() {
+                    let variable = Variable::new(name);
+                    self.index += 1;
+                    conditions.push((Expression::Variable(variable), None));
+                } else if self.eat_punct("(") {
+                    let expression = self.parse_expression()?;
+                    let variable = if self.eat_keyword("AS") {
+                        Some(self.parse_variable()?)
+                    } else {
+                        None
+                    };
+                    self.expect_punct(")")?;
+                    conditions.push((expression, variable));
+                } else if self.starts_function_call() {
+                    conditions.push((self.parse_primary_expression()?, None));
+                } else {
+                    break;
+                }
+            }
+            if conditions.is_empty() {
+                return self.unexpected("a group condition");
+            }
+            modifier.group = Some(conditions);
+        }
+
+        if self.eat_keyword("HAVING") {
+            while self.is_punct("(") || self.starts_function_call() {
+                let condition = self.with_aggregates(true, Self::parse_constraint)?;
+                modifier.having.push(condition);
+            }
+            if modifier.having.is_empty() {
+                return self.unexpected("a HAVING condition");
+            }
+        }
+
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let condition = self.with_aggregates(true, |parser| {
+                    if parser.eat_keyword("ASC") {
+                        parser
+                            .parse_bracketted_expression()
+                            .map(|e| Some(OrderExpression::Asc(e)))
+                    } else if parser.eat_keyword("DESC") {
+                        parser
+                            .parse_bracketted_expression()
+                            .map(|e| Some(OrderExpression::Desc(e)))
+                    } else if matches!(parser.peek(), TokenKind::Variable(_))
+                        || parser.is_punct("(")
+                        || parser.starts_function_call()
+                    {
+                        parser
+                            .parse_primary_expression()
+                            .map(|e| Some(OrderExpression::Asc(e)))
+                    } else {
+                        Ok(None)
+                    }
+                })?;
+                match condition {
+                    Some(condition) => modifier.order.push(condition),
+                    None => break,
+                }
+            }
+            if modifier.order.is_empty() {
+                return self.unexpected("an order condition");
+            }
+        }
+
+        loop {
+            if modifier.limit.is_none() && self.eat_keyword("LIMIT") {
+                modifier.limit = Some(self.parse_count()?);
+            } else if modifier.offset.is_none() && self.eat_keyword("OFFSET") {
+                modifier.offset = Some(self.parse_count()?);
+            } else {
+                break;
+            }
+        }
+
+        Ok(modifier)
+    }
+
+    fn parse_count(&mut self) -> ParseResult<usize> {
+        if let TokenKind::Integer(value) = self.peek()
+            && let Ok(count) = value.parse()
+        {
+            self.index += 1;
+            return Ok(count);
+        }
+        self.unexpected("a non-negative integer")
+    }
+
+    fn parse_values_clause(&mut self) -> ParseResult<Option<GraphPattern>> {
+        if self.eat_keyword("VALUES") {
+            Ok(Some(self.parse_data_block()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Applies grouping, HAVING, VALUES, select expressions, ORDER BY,
+    /// projection, DISTINCT/REDUCED and slicing to `pattern`, in the order
+    /// prescribed by the SPARQL algebra translation.
+    fn build_pattern(
+        &mut self,
+        mut pattern: GraphPattern,
+        select: Option<SelectClause>,
+        modifier: SolutionModifier,
+        values: Option<GraphPattern>,
+    ) -> ParseResult<GraphPattern> {
+        let aggregates = std::mem::take(&mut self.aggregates);
+        let grouped =
+            modifier.group.is_some() || !modifier.having.is_empty() || !aggregates.is_empty();
+
+        let mut group_variables = Vec::new();
+        for (expression, variable) in modifier.group.into_iter().flatten() {
+            match (expression, variable) {
+                (Expression::Variable(variable), None) => group_variables.push(variable),
+                (expression, variable) => {
+                    let variable = variable.unwrap_or_else(|| {
+                        self.group_count += 1;
+                        Variable::new(&format!("__group{}", self.group_count))
+                    });
+                    pattern = GraphPattern::Extend {
+                        inner: Box::new(pattern),
+                        variable: variable.clone(),
+                        expression,
+                    };
+                    group_variables.push(variable);
+                }
+            }
+        }
+        if grouped {
+            pattern = GraphPattern::Group {
+                inner: Box::new(pattern),
+                variables: group_variables.clone(),
+                aggregates,
+            };
+        }
+
+        if let Some(expression) = conjunction(modifier.having) {
+            pattern = GraphPattern::Filter {
+                expression,
+                inner: Box::new(pattern),
+            };
+        }
+
+        if let Some(values) = values {
+            pattern = GraphPattern::Join {
+                left: Box::new(pattern),
+                right: Box::new(values),
+            };
+        }
+
+        let mut projection = None;
+        let (mut distinct, mut reduced) = (false, false);
+        if let Some(select) = select {
+            distinct = select.distinct;
+            reduced = select.reduced;
+            let variables = match select.projection {
+                Some(items) => {
+                    let mut variables = Vec::with_capacity(items.len());
+                    for (variable, expression) in items {
+                        match expression {
+                            Some(expression) => {
+                                if pattern.in_scope_variables().contains(&variable) {
+                                    return self.error(&format!(
+                                        "variable ?{} is already in scope",
+                                        variable.value()
+                                    ));
+                                }
+                                pattern = GraphPattern::Extend {
+                                    inner: Box::new(pattern),
+                                    variable: variable.clone(),
+                                    expression,
+                                };
+                            }
+                            None if grouped && !group_variables.contains(&variable) => {
+                                return self.error(&format!(
+                                    "variable ?{} is projected but not grouped",
+                                    variable.value()
+                                ));
+                            }
+                            None => {}
+                        }
+                        variables.push(variable);
+                    }
+                    variables
+                }
+                None if grouped => return self.error("SELECT * is not allowed with GROUP BY"),
+                None => pattern.in_scope_variables(),
+            };
+            projection = Some(variables);
+        }
+
+        if !modifier.order.is_empty() {
+            pattern = GraphPattern::OrderBy {
+                inner: Box::new(pattern),
+                expression: modifier.order,
+            };
+        }
+        if let Some(variables) = projection {
+            pattern = GraphPattern::Project {
+                inner: Box::new(pattern),
+                variables,
+            };
+        }
+        if distinct {
+            pattern = GraphPattern::Distinct {
+                inner: Box::new(pattern),
+            };
+        }
+        if reduced {
+            pattern = GraphPattern::Reduced {
+                inner: Box::new(pattern),
+            };
+        }
+        if modifier.offset.is_some() || modifier.limit.is_some() {
+            pattern = GraphPattern::Slice {
+                inner: Box::new(pattern),
+                start: modifier.offset.unwrap_or(0),
+                length: modifier.limit,
+            };
+        }
+
+        Ok(pattern)
+    }
+
+    fn parse_group_graph_pattern(&mut self) -> ParseResult<GraphPattern> {
+        self.expect_punct("{")?;
+        if self.is_keyword("SELECT") {
+            let pattern = self.parse_sub_select()?;
+            self.expect_punct("}")?;
+            return Ok(pattern);
+        }
+
+        let mut group: Option<GraphPattern> = None;
+        let mut filters = Vec::new();
+        loop {
+            if self.eat_punct("}") {
+                break;
+            } else if self.starts_triples() {
+                let block = self.parse_triples_block()?;
+                group = Some(join(group, block));
+            } else if self.is_punct("{") {
+                let mut pattern = self.parse_group_graph_pattern()?;
+                while self.eat_keyword("UNION") {
+                    pattern = GraphPattern::Union {
+                        left: Box::new(pattern),
+                        right: Box::new(self.parse_group_graph_pattern()?),
+                    };
+                }
+                group = Some(join(group, pattern));
+            } else if self.eat_keyword("OPTIONAL") {
+                let (right, expression) = match self.parse_group_graph_pattern()? {
+                    GraphPattern::Filter { expression, inner } => (inner, Some(expression)),
+                    pattern => (Box::new(pattern), None),
+                };
+                group = Some(GraphPattern::LeftJoin {
+                    left: Box::new(group.unwrap_or_else(GraphPattern::empty)),
+                    right,
+                    expression,
+                });
+            } else if self.eat_keyword("MINUS") {
+                group = Some(GraphPattern::Minus {
+                    left: Box::new(group.unwrap_or_else(GraphPattern::empty)),
+                    right: Box::new(self.parse_group_graph_pattern()?),
+                });
+            } else if self.eat_keyword("GRAPH") {
+                let Some(name) = self.parse_var_or_iri()? else {
+                    return self.unexpected("a variable or an IRI");
+                };
+                let inner = Box::new(self.parse_group_graph_pattern()?);
+                group = Some(join(group, GraphPattern::Graph { name, inner }));
+            } else if self.eat_keyword("SERVICE") {
+                let silent = self.eat_keyword("SILENT");
+                let Some(name) = self.parse_var_or_iri()? else {
+                    return self.unexpected("a variable or an IRI");
+                };
+                let inner = Box::new(self.parse_group_graph_pattern()?);
+                group = Some(join(
+                    group,
+                    GraphPattern::Service {
+                        name,
+                        inner,
+                        silent,
+                    },
+                ));
+            } else if self.eat_keyword("FILTER") {
+                filters.push(self.parse_constraint()?);
+            } else if self.eat_keyword("BIND") {
+                self.expect_punct("(")?;
+                let expression = self.parse_expression()?;
+                self.expect_keyword("AS")?;
+                let variable = self.parse_variable()?;
+                self.expect_punct(")")?;
+                let inner = group.unwrap_or_else(GraphPattern::empty);
+                if inner.in_scope_variables().contains(&variable) {
+                    return self.error(&format!(
+                        "variable ?{} is already in scope",
+                        variable.value()
+                    ));
+                }
+                group = Some(GraphPattern::Extend {
+                    inner: Box::new(inner),
+                    variable,
+                    expression,
+                });
+            } else if self.eat_keyword("VALUES") {
+                let values = self.parse_data_block()?;
+                group = Some(join(group, values));
+            } else if !self.eat_punct(".") {
+                return self.unexpected("a graph pattern or '}'");
+            }
+        }
+
+        let pattern = group.unwrap_or_else(GraphPattern::empty);
+        Ok(match conjunction(filters) {
+            Some(expression) => GraphPattern::Filter {
+                expression,
+                inner: Box::new(pattern),
+            },
+            None => pattern,
+        })
+    }
+
+    fn starts_triples(&self) -> bool {
+        match self.peek() {
+            TokenKind::Iri(_)
+            | TokenKind::PrefixedName(..)
+            | TokenKind::BlankNodeLabel(_)
+            | TokenKind::Variable(_)
+            | TokenKind::Integer(_)
+            | TokenKind::Decimal(_)
+            | TokenKind::Double(_)
+            | TokenKind::String(_) => true,
+            TokenKind::Punct(punct) => matches!(*punct, "(" | "[" | "<<"),
+            TokenKind::Name(_) => self.is_keyword("true") || self.is_keyword("false"),
+            _ => false,
+        }
+    }
+
+    /// Consecutive triples form one basic graph pattern; property paths split
+    /// the block into a join.
+    fn parse_triples_block(&mut self) -> ParseResult<GraphPattern> {
+        let mut items = Vec::new();
+        loop {
+            self.parse_triples_same_subject(&mut items, true)?;
+            if !self.eat_punct(".") || !self.starts_triples() {
+                break;
+            }
+        }
+
+        let mut group = None;
+        let mut patterns = Vec::new();
+        for item in items {
+            match item {
+                TripleItem::Triple(quad) => patterns.push(quad),
+                TripleItem::Path(subject, path, object) => {
+                    if !patterns.is_empty() {
+                        let bgp = GraphPattern::Bgp {
+                            patterns: std::mem::take(&mut patterns),
+                        };
+                        group = Some(join(group, bgp));
+                    }
+                    group = Some(join(
+                        group,
+                        GraphPattern::Path {
+                            subject,
+                            path,
+                            object,
+                        },
+                    ));
+                }
+            }
+        }
+        Ok(join(group, GraphPattern::Bgp { patterns }))
+    }
+
+    fn parse_triples_same_subject(
+        &mut self,
+        items: &mut Vec<TripleItem>,
+        allow_paths: bool,
+    ) -> ParseResult<()> {
+        let (subject, is_triples_node) = self.parse_graph_node(items, allow_paths)?;
+        if is_triples_node && !self.starts_verb(allow_paths) {
+            return Ok(());
+        }
+        self.parse_property_list(&subject, items, allow_paths)
+    }
+
+    fn starts_verb(&self, allow_paths: bool) -> bool {
+        match self.peek() {
+            TokenKind::Variable(_) | TokenKind::Iri(_) | TokenKind::PrefixedName(..) => true,
+            TokenKind::Name(name) => name == "a",
+            TokenKind::Punct(punct) => allow_paths && matches!(*punct, "^" | "(" | "!"),
+            _ => false,
+        }
+    }
+
+    fn parse_property_list(
+        &mut self,
+        subject: &Term,
+        items: &mut Vec<TripleItem>,
+        allow_paths: bool,
+    ) -> ParseResult<()> {
+        loop {
+            let verb = self.parse_verb(allow_paths)?;
+            loop {
+                let (object, _) = self.parse_graph_node(items, allow_paths)?;
+                self.emit(subject, &verb, object, items)?;
+                if !self.eat_punct(",") {
+                    break;
+                }
+            }
+
+            if !self.eat_punct(";") {
+                return Ok(());
+            }
+            while self.eat_punct(";") {}
+            if !self.starts_verb(allow_paths) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_verb(&mut self, allow_paths: bool) -> ParseResult<Verb> {
+        if let TokenKind::Variable(name) = self.peek() {
+            let variable = Variable::new(name);
+            self.index += 1;
+            return Ok(Verb::Predicate(QuadPredicate::Variable(variable)));
+        }
+
+        let path = if allow_paths {
+            self.parse_path()?
+        } else {
+            PropertyPath::NamedNode(self.parse_iri_or_a()?)
+        };
+        Ok(match path {
+            PropertyPath::NamedNode(predicate) => {
+                Verb::Predicate(QuadPredicate::NamedNode(predicate))
+            }
+            path => Verb::Path(path),
+        })
+    }
+
+    fn emit(
+        &self,
+        subject: &Term,
+        verb: &Verb,
+        object: Term,
+        items: &mut Vec<TripleItem>,
+    ) -> ParseResult<()> {
+        match verb {
+            Verb::Predicate(predicate) => {
+                let quad = self.triple(subject.clone(), predicate, object)?;
+                items.push(TripleItem::Triple(quad));
+            }
+            Verb::Path(path) => items.push(TripleItem::Path(subject.clone(), path.clone(), object)),
+        }
+        Ok(())
+    }
+
+    fn triple(&self, subject: Term, predicate: &QuadPredicate, object: Term) -> ParseResult<Quad> {
+        let Ok(subject) = QuadSubject::try_from(subject) else {
+            return self.error("literals are not allowed as subjects");
+        };
+        let Ok(object) = QuadObject::try_from(object) else {
+            return self.error("quoted triples are only supported as subjects");
+        };
+        Ok(Quad::new(&subject, predicate, &object, None))
+    }
+
+    /// A term, collection or blank node property list; the flag tells whether
+    /// it produced triples of its own.
+    fn parse_graph_node(
+        &mut self,
+        items: &mut Vec<TripleItem>,
+        allow_paths: bool,
+    ) -> ParseResult<(Term, bool)> {
+        if self.eat_punct("(") {
+            let mut elements = Vec::new();
+            while !self.eat_punct(")") {
+                elements.push(self.parse_graph_node(items, allow_paths)?.0);
+            }
+            if elements.is_empty() {
+                return Ok((Term::NamedNode(rdf::nil.clone()), false));
+            }
+
+            let head = self.fresh_blank_node();
+            let mut node = head.clone();
+            let count = elements.len();
+            for (i, element) in elements.into_iter().enumerate() {
+                let first = QuadPredicate::NamedNode(rdf::first.clone());
+                items.push(TripleItem::Triple(self.triple(
+                    node.clone(),
+                    &first,
+                    element,
+                )?));
+                let rest = if i + 1 == count {
+                    Term::NamedNode(rdf::nil.clone())
+                } else {
+                    self.fresh_blank_node()
+                };
+                let predicate = QuadPredicate::NamedNode(rdf::rest.clone());
+                items.push(TripleItem::Triple(self.triple(
+                    node,
+                    &predicate,
+                    rest.clone(),
+                )?));
+                node = rest;
+            }
+            return Ok((head, true));
+        }
+
+        if self.eat_punct("[") {
+            let node = self.fresh_blank_node();
+            if self.eat_punct("]") {
+                return Ok((node, false));
+            }
+            self.parse_property_list(&node, items, allow_paths)?;
+            self.expect_punct("]")?;
+            return Ok((node, true));
+        }
+
+        Ok((self.parse_var_or_term()?, false))
+    }
+
+    fn parse_var_or_term(&mut self) -> ParseResult<Term> {
+        match self.peek().clone() {
+            TokenKind::Variable(name) => {
+                self.index += 1;
+                Ok(Term::Variable(Variable::new(&name)))
+            }
+            TokenKind::BlankNodeLabel(label) => {
+                self.index += 1;
+                Ok(Term::BlankNode(BlankNode::new(&label)))
+            }
+            TokenKind::Punct("[") if *self.peek_nth(1) == TokenKind::Punct("]") => {
+                self.index += 2;
+                Ok(self.fresh_blank_node())
+            }
+            TokenKind::Punct("<<") => self.parse_quoted_triple(),
+            TokenKind::Iri(_) | TokenKind::PrefixedName(..) => {
+                Ok(Term::NamedNode(self.parse_iri()?))
+            }
+            _ => match self.parse_literal()? {
+                Some(literal) => Ok(Term::Literal(literal)),
+                None => self.unexpected("an RDF term"),
+            },
+        }
+    }
+
+    fn parse_quoted_triple(&mut self) -> ParseResult<Term> {
+        self.expect_punct("<<")?;
+        let subject = self.parse_var_or_term()?;
+        let predicate = match self.parse_verb(false)? {
+            Verb::Predicate(predicate) => predicate,
+            Verb::Path(_) => return self.unexpected("a predicate"),
+        };
+        let object = self.parse_var_or_term()?;
+        self.expect_punct(">>")?;
+        Ok(Term::Quad(Box::new(
+            self.triple(subject, &predicate, object)?,
+        )))
+    }
+
+    fn parse_literal(&mut self) -> ParseResult<Option<Literal>> {
+        let datatype = match self.peek().clone() {
+            TokenKind::String(value) => {
+                self.index += 1;
+                return self.parse_literal_suffix(&value).map(Some);
+            }
+            TokenKind::Integer(_) => &xsd::integer,
+            TokenKind::Decimal(_) => &xsd::decimal,
+            TokenKind::Double(_) => &xsd::double,
+            TokenKind::Name(name) if name == "true" || name == "false" => &xsd::boolean,
+            _ => return Ok(None),
+        };
+        let (TokenKind::Integer(value)
+        | TokenKind::Decimal(value)
+        | TokenKind::Double(value)
+        | TokenKind::Name(value)) = self.next_token()
+        else {
+            unreachable!()
+        };
+        Ok(Some(Literal::new(&value, None, None, Some(datatype))))
+    }
+
+    fn parse_literal_suffix(&mut self, value: &str) -> ParseResult<Literal> {
+        if let TokenKind::LangTag(tag) = self.peek().clone() {
+            let (language, direction) = match tag.split_once("--") {
+                Some((language, direction)) => match direction.parse::<LanguageDirection>() {
+                    Ok(direction) => (language.to_owned(), Some(direction)),
+                    Err(error) => return self.error(&error.to_string()),
+                },
+                None => (tag, None),
+            };
+            self.index += 1;
+            return Ok(Literal::new(
+                value,
+                Some(&language),
+                direction.as_ref(),
+                None,
+            ));
+        }
+        if self.eat_punct("^^") {
+            let datatype = self.parse_iri()?;
+            return Ok(Literal::new(value, None, None, Some(&datatype)));
+        }
+        Ok(Literal::new(value, None, None, None))
+    }
+
+    fn parse_iri(&mut self) -> ParseResult<NamedNode> {
+        match self.peek().clone() {
+            TokenKind::Iri(value) => {
+                self.index += 1;
+                Ok(NamedNode::new(&self.resolve(value)))
+            }
+            TokenKind::PrefixedName(prefix, local) => match self.prefixes.get(&prefix) {
+                Some(namespace) => {
+                    let iri = format!("{namespace}{local}");
+                    self.index += 1;
+                    Ok(NamedNode::new(&iri))
+                }
+                None => self.error(&format!("unknown prefix \"{prefix}\"")),
+            },
+            _ => self.unexpected("an IRI"),
+        }
+    }
+
+    fn parse_iri_or_a(&mut self) -> ParseResult<NamedNode> {
+        if matches!(self.peek(), TokenKind::Name(name) if name == "a") {
+            self.index += 1;
+            return Ok(rdf::r#type.clone());
+        }
+        self.parse_iri()
+    }
+
+    fn parse_var_or_iri(&mut self) -> ParseResult<Option<NamedNodePattern>> {
+        match self.peek() {
+            TokenKind::Variable(name) => {
+                let variable = Variable::new(name);
+                self.index += 1;
+                Ok(Some(NamedNodePattern::Variable(variable)))
+            }
+            TokenKind::Iri(_) | TokenKind::PrefixedName(..) => {
+                Ok(Some(NamedNodePattern::NamedNode(self.parse_iri()?)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn parse_variable(&mut self) -> ParseResult<Variable> {
+        match self.peek() {
+            TokenKind::Variable(name) => {
+                let variable = Variable::new(name);
+                self.index += 1;
+                Ok(variable)
+            }
+            _ => self.unexpected("a variable"),
+        }
+    }
+
+    fn parse_path(&mut self) -> ParseResult<PropertyPath> {
+        let mut path = self.parse_path_sequence()?;
+        while self.eat_punct("|") {
+            let right = self.parse_path_sequence()?;
+            path = PropertyPath::Alternative(Box::new(path), Box::new(right));
+        }
+        Ok(path)
+    }
+
+    fn parse_path_sequence(&mut self) -> ParseResult<PropertyPath> {
+        let mut path = self.parse_path_element_or_inverse()?;
+        while self.eat_punct("/") {
+            let right = self.parse_path_element_or_inverse()?;
+            path = PropertyPath::Sequence(Box::new(path), Box::new(right));
+        }
+        Ok(path)
+    }
+
+    fn parse_path_element_or_inverse(&mut self) -> ParseResult<PropertyPath> {
+        if self.eat_punct("^") {
+            return Ok(PropertyPath::Reverse(Box::new(self.parse_path_element()?)));
+        }
+        self.parse_path_element()
+    }
+
+    fn parse_path_element(&mut self) -> ParseResult<PropertyPath> {
+        let primary = if self.eat_punct("(") {
+            let path = self.parse_path()?;
+            self.expect_punct(")")?;
+            path
+        } else if self.eat_punct("!") {
+            self.parse_negated_property_set()?
+        } else {
+            PropertyPath::NamedNode(self.parse_iri_or_a()?)
+        };
+
+        Ok(if self.eat_punct("*") {
+            PropertyPath::ZeroOrMore(Box::new(primary))
+        } else if self.eat_punct("+") {
+            PropertyPath::OneOrMore(Box::new(primary))
+        } else if self.eat_punct("?") {
+            PropertyPath::ZeroOrOne(Box::new(primary))
+        } else {
+            primary
+        })
+    }
+
+    fn parse_negated_property_set(&mut self) -> ParseResult<PropertyPath> {
+        let mut forward = Vec::new();
+        let mut inverse = Vec::new();
+        let mut parse_one = |parser: &mut Self| -> ParseResult<()> {
+            if parser.eat_punct("^") {
+                inverse.push(parser.parse_iri_or_a()?);
+            } else {
+                forward.push(parser.parse_iri_or_a()?);
+            }
+            Ok(())
+        };
+
+        if self.eat_punct("(") {
+            if !self.eat_punct(")") {
+                parse_one(self)?;
+                while self.eat_punct("|") {
+                    parse_one(self)?;
+                }
+                self.expect_punct(")")?;
+            }
+        } else {
+            parse_one(self)?;
+        }
+
+        Ok(match (forward.is_empty(), inverse.is_empty()) {
+            (_, true) => PropertyPath::NegatedPropertySet(forward),
+            (true, false) => {
+                PropertyPath::Reverse(Box::new(PropertyPath::NegatedPropertySet(inverse)))
+            }
+            (false, false) => PropertyPath::Alternative(
+                Box::new(PropertyPath::NegatedPropertySet(forward)),
+                Box::new(PropertyPath::Reverse(Box::new(
+                    PropertyPath::NegatedPropertySet(inverse),
+                ))),
+            ),
+        })
+    }
+
+    fn parse_data_block(&mut self) -> ParseResult<GraphPattern> {
+        let mut variables = Vec::new();
+        let mut bindings = Vec::new();
+
+        if let TokenKind::Variable(name) = self.peek() {
+            variables.push(Variable::new(name));
+            self.index += 1;
+            self.expect_punct("{")?;
+            while !self.eat_punct("}") {
+                bindings.push(vec![self.parse_data_value()?]);
+            }
+        } else {
+            self.expect_punct("(")?;
+            while !self.eat_punct(")") {
+                variables.push(self.parse_variable()?);
+            }
+            self.expect_punct("{")?;
+            while !self.eat_punct("}") {
+                self.expect_punct("(")?;
+                let mut row = Vec::with_capacity(variables.len());
+                while !self.eat_punct(")") {
+                    row.push(self.parse_data_value()?);
+                }
+                if row.len() != variables.len() {
+                    return self.error("VALUES rows must have one value per variable");
+                }
+                bindings.push(row);
+            }
+        }
+
+        Ok(GraphPattern::Values {
+            variables,
+            bindings,
+        })
+    }
+
+    fn parse_data_value(&mut self) -> ParseResult<Option<Term>> {
+        if self.eat_keyword("UNDEF") {
+            return Ok(None);
+        }
+        match self.peek() {
+            TokenKind::Iri(_) | TokenKind::PrefixedName(..) => {
+                Ok(Some(Term::NamedNode(self.parse_iri()?)))
+            }
+            TokenKind::Punct("<<") => self.parse_quoted_triple().map(Some),
+            _ => match self.parse_literal()? {
+                Some(literal) => Ok(Some(Term::Literal(literal))),
+                None => self.unexpected("a value or UNDEF"),
+            },
+        }
+    }
+
+    fn parse_constraint(&mut self) -> ParseResult<Expression> {
+        if self.is_punct("(") {
+            self.parse_bracketted_expression()
+        } else if self.starts_function_call() {
+            self.parse_primary_expression()
+        } else {
+            self.unexpected("a constraint")
+        }
+    }
+
+    fn starts_function_call(&self) -> bool {
+        match self.peek() {
+            TokenKind::Iri(_) | TokenKind::PrefixedName(..) => {
+                *self.peek_nth(1) == TokenKind::Punct("(")
+            }
+            TokenKind::Name(name) => {
+                let name = name.to_ascii_uppercase();
+                AGGREGATES.contains(&name.as_str())
+                    || SPECIAL_FORMS.contains(&name.as_str())
+                    || BUILT_IN_FUNCTIONS
+                        .iter()
+                        .any(|(keyword, ..)| *keyword == name)
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_bracketted_expression(&mut self) -> ParseResult<Expression> {
+        self.expect_punct("(")?;
+        let expression = self.parse_expression()?;
+        self.expect_punct(")")?;
+        Ok(expression)
+    }
+
+    fn parse_expression(&mut self) -> ParseResult<Expression> {
+        let mut expression = self.parse_and_expression()?;
+        while self.eat_punct("||") {
+            let right = self.parse_and_expression()?;
+            expression = Expression::Or(Box::new(expression), Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn parse_and_expression(&mut self) -> ParseResult<Expression> {
+        let mut expression = self.parse_relational_expression()?;
+        while self.eat_punct("&&") {
+            let right = self.parse_relational_expression()?;
+            expression = Expression::And(Box::new(expression), Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn parse_relational_expression(&mut self) -> ParseResult<Expression> {
+        let left = Box::new(self.parse_additive_expression()?);
+
+        let operator = match self.peek() {
+            TokenKind::Punct(punct @ ("=" | "!=" | "<" | ">" | "<=" | ">=")) => *punct,
+            _ if self.is_keyword("IN") => "IN",
+            _ if self.is_keyword("NOT") => {
+                self.index += 1;
+                self.expect_keyword("IN")?;
+                let list = self.parse_expression_list()?;
+                return Ok(Expression::Not(Box::new(Expression::In(left, list))));
+            }
+            _ => return Ok(*left),
+        };
+        self.index += 1;
+
+        if operator == "IN" {
+            return Ok(Expression::In(left, self.parse_expression_list()?));
+        }
+        let right = Box::new(self.parse_additive_expression()?);
+        Ok(match operator {
+            "=" => Expression::Equal(left, right),
+            "!=" => Expression::Not(Box::new(Expression::Equal(left, right))),
+            "<" => Expression::Less(left, right),
+            ">" => Expression::Greater(left, right),
+            "<=" => Expression::LessOrEqual(left, right),
+            _ => Expression::GreaterOrEqual(left, right),
+        })
+    }
+
+    fn parse_additive_expression(&mut self) -> ParseResult<Expression> {
+        let mut expression = self.parse_multiplicative_expression()?;
+        loop {
+            if self.eat_punct("+") {
+                let right = self.parse_multiplicative_expression()?;
+                expression = Expression::Add(Box::new(expression), Box::new(right));
+            } else if self.eat_punct("-") {
+                let right = self.parse_multiplicative_expression()?;
+                expression = Expression::Subtract(Box::new(expression), Box::new(right));
+            } else if self.peek().is_signed_number() {
+                // `?a -1` reads as `?a + (-1)`.
+                let number = Expression::Literal(self.parse_literal()?.unwrap());
+                let right = self.parse_multiplicative_tail(number)?;
+                expression = Expression::Add(Box::new(expression), Box::new(right));
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    fn parse_multiplicative_expression(&mut self) -> ParseResult<Expression> {
+        let expression = self.parse_unary_expression()?;
+        self.parse_multiplicative_tail(expression)
+    }
+
+    fn parse_multiplicative_tail(&mut self, mut expression: Expression) -> ParseResult<Expression> {
+        loop {
+            if self.eat_punct("*") {
+                let right = self.parse_unary_expression()?;
+                expression = Expression::Multiply(Box::new(expression), Box::new(right));
+            } else if self.eat_punct("/") {
+                let right = self.parse_unary_expression()?;
+                expression = Expression::Divide(Box::new(expression), Box::new(right));
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    fn parse_unary_expression(&mut self) -> ParseResult<Expression> {
+        if self.eat_punct("!") {
+            Ok(Expression::Not(Box::new(self.parse_primary_expression()?)))
+        } else if self.eat_punct("+") {
+            Ok(Expression::UnaryPlus(Box::new(
+                self.parse_primary_expression()?,
+            )))
+        } else if self.eat_punct("-") {
+            Ok(Expression::UnaryMinus(Box::new(
+                self.parse_primary_expression()?,
+            )))
+        } else {
+            self.parse_primary_expression()
+        }
+    }
+
+    fn parse_primary_expression(&mut self) -> ParseResult<Expression> {
+        match self.peek().clone() {
+            TokenKind::Punct("(") => self.parse_bracketted_expression(),
+            TokenKind::Variable(name) => {
+                self.index += 1;
+                Ok(Expression::Variable(Variable::new(&name)))
+            }
+            TokenKind::Iri(_) | TokenKind::PrefixedName(..) => {
+                let iri = self.parse_iri()?;
+                if self.is_punct("(") {
+                    let arguments = self.parse_expression_list()?;
+                    Ok(Expression::FunctionCall(Function::Custom(iri), arguments))
+                } else {
+                    Ok(Expression::NamedNode(iri))
+                }
+            }
+            TokenKind::Name(name) if name != "true" && name != "false" => {
+                self.parse_built_in_call(&name.to_ascii_uppercase())
+            }
+            _ => match self.parse_literal()? {
+                Some(literal) => Ok(Expression::Literal(literal)),
+                None => self.unexpected("an expression"),
+            },
+        }
+    }
+
+    fn parse_built_in_call(&mut self, name: &str) -> ParseResult<Expression> {
+        if AGGREGATES.contains(&name) {
+            return self.parse_aggregate(name);
+        }
+        self.index += 1;
+
+        match name {
+            "BOUND" => {
+                self.expect_punct("(")?;
+                let variable = self.parse_variable()?;
+                self.expect_punct(")")?;
+                return Ok(Expression::Bound(variable));
+            }
+            "EXISTS" => {
+                let pattern = self.with_aggregates(false, Self::parse_group_graph_pattern)?;
+                return Ok(Expression::Exists(Box::new(pattern)));
+            }
+            "NOT" => {
+                self.expect_keyword("EXISTS")?;
+                let pattern = self.with_aggregates(false, Self::parse_group_graph_pattern)?;
+                return Ok(Expression::Not(Box::new(Expression::Exists(Box::new(
+                    pattern,
+                )))));
+            }
+            _ => {}
+        }
+
+        let mut arguments = self.parse_expression_list()?;
+        match name {
+            "IF" => {
+                if arguments.len() != 3 {
+                    return self.error("IF expects 3 arguments");
+                }
+                let c = arguments.pop().unwrap();
+                let b = arguments.pop().unwrap();
+                let a = arguments.pop().unwrap();
+                Ok(Expression::If(Box::new(a), Box::new(b), Box::new(c)))
+            }
+            "COALESCE" => Ok(Expression::Coalesce(arguments)),
+            _ => match BUILT_IN_FUNCTIONS
+                .iter()
+                .find(|(keyword, ..)| *keyword == name)
+            {
+                Some((_, function, min, max)) if (*min..=*max).contains(&arguments.len()) => {
+                    Ok(Expression::FunctionCall(function.clone(), arguments))
+                }
+                Some(_) => self.error(&format!("wrong number of arguments for {name}")),
+                None => self.error(&format!("unknown function {name}")),
+            },
+        }
+    }
+
+    fn parse_expression_list(&mut self) -> ParseResult<Vec<Expression>> {
+        self.expect_punct("(")?;
+        let mut expressions = Vec::new();
+        if self.eat_punct(")") {
+            return Ok(expressions);
+        }
+        loop {
+            expressions.push(self.parse_expression()?);
+            if !self.eat_punct(",") {
+                break;
+            }
+        }
+        self.expect_punct(")")?;
+        Ok(expressions)
+    }
+
+    /// Registers the aggregate with the enclosing query level and refers to
+    /// it through a fresh variable; identical aggregates share one variable.
+    fn parse_aggregate(&mut self, name: &str) -> ParseResult<Expression> {
+        if !self.aggregates_allowed {
+            return self.error("aggregates are only allowed in SELECT, HAVING and ORDER BY");
+        }
+        self.index += 1;
+        self.expect_punct("(")?;
+        let distinct = self.eat_keyword("DISTINCT");
+
+        let expression = if name == "COUNT" && self.eat_punct("*") {
+            None
+        } else {
+            let expression = self.with_aggregates(false, Self::parse_expression)?;
+            Some(Box::new(expression))
+        };
+        let function = match name {
+            "COUNT" => AggregateFunction::Count,
+            "SUM" => AggregateFunction::Sum,
+            "MIN" => AggregateFunction::Min,
+            "MAX" => AggregateFunction::Max,
+            "AVG" => AggregateFunction::Avg,
+            "SAMPLE" => AggregateFunction::Sample,
+            _ => {
+                let mut separator = None;
+                if self.eat_punct(";") {
+                    self.expect_keyword("SEPARATOR")?;
+                    self.expect_punct("=")?;
+                    match self.next_token() {
+                        TokenKind::String(value) => separator = Some(value),
+                        _ => {
+                            self.index -= 1;
+                            return self.unexpected("a string");
+                        }
+                    }
+                }
+                AggregateFunction::GroupConcat { separator }
+            }
+        };
+        self.expect_punct(")")?;
+
+        let aggregate = AggregateExpression {
+            function,
+            expression,
+            distinct,
+        };
+        let existing = self
+            .aggregates
+            .iter()
+            .find(|(_, other)| *other == aggregate)
+            .map(|(variable, _)| variable.clone());
+        let variable = match existing {
+            Some(variable) => variable,
+            None => {
+                self.aggregate_count += 1;
+                let variable = Variable::new(&format!("__agg{}", self.aggregate_count));
+                self.aggregates.push((variable.clone(), aggregate));
+                variable
+            }
+        };
+        Ok(Expression::Variable(variable))
+    }
+}
+
+/// Joins `pattern` onto the group built so far, dropping empty groups.
+fn join(group: Option<GraphPattern>, pattern: GraphPattern) -> GraphPattern {
+    match group {
+        None => pattern,
+        Some(left) if left == GraphPattern::empty() => pattern,
+        Some(left) if pattern == GraphPattern::empty() => left,
+        Some(left) => GraphPattern::Join {
+            left: Box::new(left),
+            right: Box::new(pattern),
+        },
+    }
+}
+
+/// The WHERE pattern of `DELETE WHERE`, matching its `template`.
+pub(crate) fn delete_where_pattern(template: &[Quad]) -> GraphPattern {
+    let mut group = None;
+    let mut start = 0;
+    while start < template.len() {
+        let graph = template[start].graph();
+        let end = template[start..]
+            .iter()
+            .position(|quad| quad.graph() != graph)
+            .map_or(template.len(), |length| start + length);
+        let bgp = GraphPattern::Bgp {
+            patterns: template[start..end]
+                .iter()
+                .map(|quad| Quad::new(quad.subject(), quad.predicate(), quad.object(), None))
+                .collect(),
+        };
+        let name = match graph {
+            QuadGraph::NamedNode(named_node) => {
+                Some(NamedNodePattern::NamedNode(named_node.clone()))
+            }
+            QuadGraph::Variable(variable) => Some(NamedNodePattern::Variable(variable.clone())),
+            _ => None,
+        };
+        let pattern = match name {
+            Some(name) => GraphPattern::Graph {
+                name,
+                inner: Box::new(bgp),
+            },
+            None => bgp,
+        };
+        group = Some(join(group, pattern));
+        start = end;
+    }
+    group.unwrap_or_else(GraphPattern::empty)
+}
+
+fn has_variables(quad: &Quad) -> bool {
+    let subject = match quad.subject() {
+        QuadSubject::Variable(_) => true,
+        QuadSubject::Quad(inner) => has_variables(inner),
+        _ => false,
+    };
+    subject
+        || matches!(quad.predicate(), QuadPredicate::Variable(_))
+        || matches!(quad.object(), QuadObject::Variable(_))
+        || matches!(quad.graph(), QuadGraph::Variable(_))
+}
+
+fn has_blank_nodes(quad: &Quad) -> bool {
+    let subject = match quad.subject() {
+        QuadSubject::BlankNode(_) => true,
+        QuadSubject::Quad(inner) => has_blank_nodes(inner),
+        _ => false,
+    };
+    subject || matches!(quad.object(), QuadObject::BlankNode(_))
+}
+
+fn conjunction(expressions: Vec<Expression>) -> Option<Expression> {
+    expressions
+        .into_iter()
+        .reduce(|left, right| Expression::And(Box::new(left), Box::new(right)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nn(value: &str) -> NamedNode {
+        NamedNode::new(&format!("http://example.org/{value}"))
+    }
+
+    fn var(name: &str) -> Variable {
+        Variable::new(name)
+    }
+
+    fn where_pattern(query: &str) -> GraphPattern {
+        match parse_query(query, None).unwrap() {
+            Query::Select { pattern, .. } => match pattern {
+                GraphPattern::Project { inner, .. } => *inner,
+                pattern => pattern,
+            },
+            query => panic!("not a SELECT query: {query:?}"),
+        }
+    }
+
+    #[test]
+    fn triple_patterns_form_a_bgp() {
+        let pattern = where_pattern(
+            "PREFIX ex: <http://example.org/> SELECT * WHERE { ?s ex:p ?o , ex:b ; a ex:C }",
+        );
+
+        let triple = |object: QuadObject, predicate: NamedNode| {
+            Quad::new(
+                &QuadSubject::Variable(var("s")),
+                &QuadPredicate::NamedNode(predicate),
+                &object,
+                None,
+            )
+        };
+        assert_eq!(
+            pattern,
+            GraphPattern::Bgp {
+                patterns: vec![
+                    triple(QuadObject::Variable(var("o")), nn("p")),
+                    triple(QuadObject::NamedNode(nn("b")), nn("p")),
+                    triple(QuadObject::NamedNode(nn("C")), rdf::r#type.clone()),
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn optional_filters_become_left_join_conditions() {
+        let pattern = where_pattern(
+            "SELECT * { ?s <http://example.org/p> ?o OPTIONAL { ?o <http://example.org/q> ?v FILTER(?v) } }",
+        );
+
+        let GraphPattern::LeftJoin {
+            right, expression, ..
+        } = pattern
+        else {
+            panic!("expected a left join: {pattern:?}");
+        };
+        assert!(matches!(*right, GraphPattern::Bgp { .. }));
+        assert_eq!(expression, Some(Expression::Variable(var("v"))));
+    }
+
+    #[test]
+    fn property_paths_are_kept_apart_from_triples() {
+        let pattern = where_pattern(
+            "PREFIX ex: <http://example.org/> SELECT * { ?s ex:p ?o . ?o ^ex:q/ex:r* ?x }",
+        );
+
+        let GraphPattern::Join { left, right } = pattern else {
+            panic!("expected a join: {pattern:?}");
+        };
+        assert!(matches!(*left, GraphPattern::Bgp { ref patterns } if patterns.len() == 1));
+        assert_eq!(
+            *right,
+            GraphPattern::Path {
+                subject: Term::Variable(var("o")),
+                path: PropertyPath::Sequence(
+                    Box::new(PropertyPath::Reverse(Box::new(PropertyPath::NamedNode(
+                        nn("q")
+                    )))),
+                    Box::new(PropertyPath::ZeroOrMore(Box::new(PropertyPath::NamedNode(
+                        nn("r")
+                    )))),
+                ),
+                object: Term::Variable(var("x")),
+            }
+        );
+    }
+
+    #[test]
+    fn aggregates_are_bound_by_the_group() {
+        let pattern = where_pattern(
+            "SELECT ?s (COUNT(*) AS ?n) { ?s ?p ?o } GROUP BY ?s HAVING (COUNT(*) > 2)",
+        );
+
+        let GraphPattern::Extend {
+            inner,
+            variable,
+            expression: Expression::Variable(aggregate),
+        } = pattern
+        else {
+            panic!("expected a select expression: {pattern:?}");
+        };
+        assert_eq!(variable, var("n"));
+        let GraphPattern::Filter { inner, .. } = *inner else {
+            panic!("expected a HAVING filter");
+        };
+        let GraphPattern::Group {
+            variables,
+            aggregates,
+            ..
+        } = *inner
+        else {
+            panic!("expected a group");
+        };
+        assert_eq!(variables, vec![var("s")]);
+        assert_eq!(aggregates.len(), 1);
+        assert_eq!(aggregates[0].0, aggregate);
+        assert_eq!(aggregates[0].1.function, AggregateFunction::Count);
+    }
+
+    #[test]
+    fn select_star_projects_in_scope_variables() {
+        let query = parse_query(
+            "SELECT * { ?s ?p ?o BIND(1 AS ?one) } ORDER BY ?s LIMIT 5",
+            None,
+        )
+        .unwrap();
+
+        let Query::Select {
+            pattern:
+                GraphPattern::Slice {
+                    inner,
+                    start,
+                    length,
+                },
+            ..
+        } = query
+        else {
+            panic!("expected a slice");
+        };
+        assert_eq!((start, length), (0, Some(5)));
+        let GraphPattern::Project { variables, .. } = *inner else {
+            panic!("expected a projection");
+        };
+        assert_eq!(variables, vec![var("s"), var("p"), var("o"), var("one")]);
+    }
+
+    #[test]
+    fn expressions_follow_precedence() {
+        let pattern = where_pattern("SELECT * { FILTER(?a || ?b && ?c = 1 + 2 * -3) }");
+
+        let literal = |value: &str| {
+            Box::new(Expression::Literal(Literal::new(
+                value,
+                None,
+                None,
+                Some(&xsd::integer),
+            )))
+        };
+        let v = |name: &str| Box::new(Expression::Variable(var(name)));
+        assert_eq!(
+            pattern,
+            GraphPattern::Filter {
+                expression: Expression::Or(
+                    v("a"),
+                    Box::new(Expression::And(
+                        v("b"),
+                        Box::new(Expression::Equal(
+                            v("c"),
+                            Box::new(Expression::Add(
+                                literal("1"),
+                                Box::new(Expression::Multiply(literal("2"), literal("-3"))),
+                            )),
+                        )),
+                    )),
+                ),
+                inner: Box::new(GraphPattern::empty()),
+            }
+        );
+    }
+
+    #[test]
+    fn prologue_resolves_iris() {
+        let query = parse_query(
+            "BASE <http://example.org/a/> PREFIX ex: <../ns#> ASK { <b> ex:p \"x\"@en }",
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(query.base_iri(), Some("http://example.org/a/"));
+        let GraphPattern::Bgp { patterns } = query.pattern() else {
+            panic!("expected a bgp");
+        };
+        assert_eq!(
+            patterns[0].subject(),
+            &QuadSubject::NamedNode(NamedNode::new("http://example.org/a/b"))
+        );
+        assert_eq!(
+            patterns[0].predicate(),
+            &QuadPredicate::NamedNode(NamedNode::new("http://example.org/ns#p"))
+        );
+    }
+
+    #[test]
+    fn errors_report_positions() {
+        let error = parse_query("SELECT ?x\nWHERE { ?x ex:p ?y }", None).unwrap_err();
+        assert_eq!((error.line(), error.column()), (2, 12));
+        assert_eq!(error.message(), "unknown prefix \"ex\"");
+
+        for query in [
+            "SELECT WHERE { ?s ?p ?o }",
+            "SELECT * { ?s ?p }",
+            "SELECT * { ?s ?p ?o } GROUP BY ?s",
+            "SELECT ?o { ?s ?p ?o } GROUP BY ?s",
+            "SELECT * { \"a\" ?p ?o }",
+            "SELECT * { FILTER(SUM(?x)) }",
+            "SELECT * { BIND(1 AS ?x) BIND(2 AS ?x) }",
+            "SELECT * { VALUES (?a ?b) { (1) } }",
+            "SELECT * { ?s ?p ?o } trailing",
+        ] {
+            assert!(parse_query(query, None).is_err(), "{query}");
+        }
+    }
+
+    #[test]
+    fn delete_where_matches_its_template() {
+        let update = parse_update(
+            "PREFIX ex: <http://example.org/> DELETE WHERE { ?s ex:p ?o . GRAPH ex:g { ?o ex:q ?z } }",
+            None,
+        )
+        .unwrap();
+        let [
+            UpdateOperation::DeleteInsert {
+                delete, pattern, ..
+            },
+        ] = update.operations.as_slice()
+        else {
+            panic!("expected a single DELETE WHERE");
+        };
+
+        assert_eq!(delete.len(), 2);
+        assert_eq!(*delete[1].graph(), QuadGraph::NamedNode(nn("g")));
+        let GraphPattern::Join { left, right } = &**pattern else {
+            panic!("expected a join");
+        };
+        assert!(matches!(**left, GraphPattern::Bgp { .. }));
+        assert!(matches!(**right, GraphPattern::Graph { .. }));
+    }
+
+    #[test]
+    fn update_data_is_checked() {
+        for (update, message) in [
+            (
+                "INSERT DATA { ?s <p> <o> }",
+                "variables are not allowed in data",
+            ),
+            (
+                "DELETE DATA { _:b <p> <o> }",
+                "blank nodes are not allowed in DELETE DATA",
+            ),
+            (
+                "DELETE { [] <p> ?o } WHERE { ?s <p> ?o }",
+                "blank nodes are not allowed in DELETE templates",
+            ),
+        ] {
+            assert_eq!(parse_update(update, None).unwrap_err().message(), message);
+        }
+        assert!(parse_update("CLEAR <g>", None).is_err());
+        assert!(parse_update("INSERT { <s> <p> <o> }", None).is_err());
+        assert_eq!(parse_update("", None).unwrap().operations, []);
+    }
+}
@@ -0,0 +1,846 @@
+use std::fmt::Display;
+
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::sparql::algebra::{
+    AggregateExpression, AggregateFunction, BUILT_IN_FUNCTIONS, Expression, Function, GraphPattern,
+    GraphTarget, NamedNodePattern, OrderExpression, PropertyPath, Query, Update, UpdateOperation,
+};
+use crate::rs::sparql::parser::delete_where_pattern;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::turtle::{format_iri, format_literal, format_string};
+use crate::rs::variable::Variable;
+use crate::rs::vocab::rdf;
+
+impl Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut printer = Printer::default();
+        printer.query(self);
+        f.write_str(printer.out.trim_end())
+    }
+}
+
+impl Display for Update {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut printer = Printer::default();
+        printer.update(self);
+        f.write_str(printer.out.trim_end())
+    }
+}
+
+impl Display for GraphPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut printer = Printer::default();
+        printer.group(self);
+        f.write_str(printer.out.trim_end())
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&Printer::default().expression(self))
+    }
+}
+
+impl Display for PropertyPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&path(self))
+    }
+}
+
+/// The solution modifiers wrapped around the WHERE pattern of a query, as
+/// produced by the parser.
+#[derive(Default)]
+struct Modifiers<'a> {
+    slice: Option<(usize, Option<usize>)>,
+    distinct: bool,
+    reduced: bool,
+    projection: Option<&'a [Variable]>,
+    order: &'a [OrderExpression],
+    select_expressions: Vec<(&'a Variable, &'a Expression)>,
+    values: Option<&'a GraphPattern>,
+    having: Option<&'a Expression>,
+    group: Option<&'a [Variable]>,
+    aggregates: &'a [(Variable, AggregateExpression)],
+}
+
+impl<'a> Modifiers<'a> {
+    fn peel(mut pattern: &'a GraphPattern) -> (Self, &'a GraphPattern) {
+        let mut modifiers = Modifiers::default();
+
+        if let GraphPattern::Slice {
+            inner,
+            start,
+            length,
+        } = pattern
+        {
+            modifiers.slice = Some((*start, *length));
+            pattern = inner;
+        }
+        match pattern {
+            GraphPattern::Distinct { inner } => {
+                modifiers.distinct = true;
+                pattern = inner;
+            }
+            GraphPattern::Reduced { inner } => {
+                modifiers.reduced = true;
+                pattern = inner;
+            }
+            _ => {}
+        }
+        if let GraphPattern::Project { inner, variables } = pattern {
+            modifiers.projection = Some(variables);
+            pattern = inner;
+        }
+        if let GraphPattern::OrderBy { inner, expression } = pattern {
+            modifiers.order = expression;
+            pattern = inner;
+        }
+
+        // Select expressions are extensions of projected variables, applied
+        // in projection order.
+        if let Some(projection) = modifiers.projection {
+            let mut last = projection.len();
+            while let GraphPattern::Extend {
+                inner,
+                variable,
+                expression,
+            } = pattern
+            {
+                match projection.iter().position(|v| v == variable) {
+                    Some(position) if position < last => {
+                        last = position;
+                        modifiers.select_expressions.push((variable, expression));
+                        pattern = inner;
+                    }
+                    _ => break,
+                }
+            }
+            modifiers.select_expressions.reverse();
+        }
+
+        let is_grouped = |pattern: &GraphPattern| match pattern {
+            GraphPattern::Group { .. } => true,
+            GraphPattern::Filter { inner, .. } => matches!(**inner, GraphPattern::Group { .. }),
+            _ => false,
+        };
+        if let GraphPattern::Join { left, right } = pattern
+            && matches!(**right, GraphPattern::Values { .. })
+            && is_grouped(left)
+        {
+            modifiers.values = Some(right);
+            pattern = left;
+        }
+        if let GraphPattern::Filter { expression, inner } = pattern
+            && matches!(**inner, GraphPattern::Group { .. })
+        {
+            modifiers.having = Some(expression);
+            pattern = inner;
+        }
+        if let GraphPattern::Group {
+            inner,
+            variables,
+            aggregates,
+        } = pattern
+        {
+            modifiers.group = Some(variables);
+            modifiers.aggregates = aggregates;
+            pattern = inner;
+        }
+
+        (modifiers, pattern)
+    }
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    indent: usize,
+    /// Aggregates of the query level being printed, substituted for their
+    /// variables in expressions.
+    aggregates: Vec<(Variable, AggregateExpression)>,
+}
+
+impl Printer {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str("  ");
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn query(&mut self, query: &Query) {
+        if let Some(base) = query.base_iri() {
+            self.line(&format!("BASE {}", format_iri(base)));
+        }
+
+        let (modifiers, pattern) = Modifiers::peel(query.pattern());
+        self.aggregates = modifiers.aggregates.to_vec();
+        match query {
+            Query::Select { .. } => self.select_clause(&modifiers),
+            Query::Construct { template, .. } => {
+                self.line("CONSTRUCT {");
+                self.indent += 1;
+                for triple in template {
+                    self.triple(triple);
+                }
+                self.indent -= 1;
+                self.line("}");
+            }
+            Query::Describe { targets, .. } => {
+                let targets: Vec<String> = targets.iter().map(named_node_pattern).collect();
+                if targets.is_empty() {
+                    self.line("DESCRIBE *");
+                } else {
+                    self.line(&format!("DESCRIBE {}", targets.join(" ")));
+                }
+            }
+            Query::Ask { .. } => self.line("ASK"),
+        }
+
+        if let Some(dataset) = query.dataset() {
+            for graph in &dataset.default {
+                self.line(&format!("FROM {}", format_iri(graph.value())));
+            }
+            for graph in &dataset.named {
+                self.line(&format!("FROM NAMED {}", format_iri(graph.value())));
+            }
+        }
+
+        self.where_clause(&modifiers, pattern);
+    }
+
+    fn update(&mut self, update: &Update) {
+        if let Some(base) = &update.base_iri {
+            self.line(&format!("BASE {}", format_iri(base)));
+        }
+        for (i, operation) in update.operations.iter().enumerate() {
+            if i > 0 {
+                self.line(";");
+            }
+            self.update_operation(operation);
+        }
+    }
+
+    fn update_operation(&mut self, operation: &UpdateOperation) {
+        let silent = |silent: &bool| if *silent { " SILENT" } else { "" };
+        match operation {
+            UpdateOperation::InsertData { data } => self.quads("INSERT DATA", data),
+            UpdateOperation::DeleteData { data } => self.quads("DELETE DATA", data),
+            UpdateOperation::DeleteInsert {
+                with: None,
+                delete,
+                insert,
+                using: None,
+                pattern,
+            } if insert.is_empty() && **pattern == delete_where_pattern(delete) => {
+                self.quads("DELETE WHERE", delete)
+            }
+            UpdateOperation::DeleteInsert {
+                with,
+                delete,
+                insert,
+                using,
+                pattern,
+            } => {
+                if let Some(with) = with {
+                    self.line(&format!("WITH {}", format_iri(with.value())));
+                }
+                if !delete.is_empty() || insert.is_empty() {
+                    self.quads("DELETE", delete);
+                }
+                if !insert.is_empty() {
+                    self.quads("INSERT", insert);
+                }
+                if let Some(using) = using {
+                    for graph in &using.default {
+                        self.line(&format!("USING {}", format_iri(graph.value())));
+                    }
+                    for graph in &using.named {
+                        self.line(&format!("USING NAMED {}", format_iri(graph.value())));
+                    }
+                }
+                self.line("WHERE {");
+                self.indent += 1;
+                self.elements(pattern);
+                self.indent -= 1;
+                self.line("}");
+            }
+            UpdateOperation::Load {
+                silent: is_silent,
+                source,
+                destination,
+            } => {
+                let mut text = format!("LOAD{} {}", silent(is_silent), format_iri(source.value()));
+                if let QuadGraph::NamedNode(graph) = destination {
+                    text.push_str(&format!(" INTO GRAPH {}", format_iri(graph.value())));
+                }
+                self.line(&text);
+            }
+            UpdateOperation::Clear {
+                silent: is_silent,
+                target,
+            } => self.line(&format!(
+                "CLEAR{} {}",
+                silent(is_silent),
+                graph_target(target)
+            )),
+            UpdateOperation::Drop {
+                silent: is_silent,
+                target,
+            } => self.line(&format!(
+                "DROP{} {}",
+                silent(is_silent),
+                graph_target(target)
+            )),
+            UpdateOperation::Create {
+                silent: is_silent,
+                graph,
+            } => self.line(&format!(
+                "CREATE{} GRAPH {}",
+                silent(is_silent),
+                format_iri(graph.value())
+            )),
+            UpdateOperation::Copy {
+                silent: is_silent,
+                source,
+                destination,
+            } => self.line(&format!(
+                "COPY{} {} TO {}",
+                silent(is_silent),
+                graph_or_default(source),
+                graph_or_default(destination)
+            )),
+            UpdateOperation::Move {
+                silent: is_silent,
+                source,
+                destination,
+            } => self.line(&format!(
+                "MOVE{} {} TO {}",
+                silent(is_silent),
+                graph_or_default(source),
+                graph_or_default(destination)
+            )),
+            UpdateOperation::Add {
+                silent: is_silent,
+                source,
+                destination,
+            } => self.line(&format!(
+                "ADD{} {} TO {}",
+                silent(is_silent),
+                graph_or_default(source),
+                graph_or_default(destination)
+            )),
+        }
+    }
+
+    /// A quad template or quad data block, runs of quads in the same named
+    /// graph being wrapped in `GRAPH`.
+    fn quads(&mut self, keyword: &str, quads: &[Quad]) {
+        self.line(&format!("{keyword} {{"));
+        self.indent += 1;
+        let mut start = 0;
+        while start < quads.len() {
+            let graph = quads[start].graph();
+            let end = quads[start..]
+                .iter()
+                .position(|quad| quad.graph() != graph)
+                .map_or(quads.len(), |length| start + length);
+            if let QuadGraph::DefaultGraph(_) = graph {
+                for quad in &quads[start..end] {
+                    self.triple(quad);
+                }
+            } else {
+                self.line(&format!("GRAPH {} {{", term(&graph.to_term())));
+                self.indent += 1;
+                for quad in &quads[start..end] {
+                    self.triple(quad);
+                }
+                self.indent -= 1;
+                self.line("}");
+            }
+            start = end;
+        }
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    fn sub_select(&mut self, pattern: &GraphPattern) {
+        let (modifiers, pattern) = Modifiers::peel(pattern);
+        let outer = std::mem::replace(&mut self.aggregates, modifiers.aggregates.to_vec());
+        self.select_clause(&modifiers);
+        self.where_clause(&modifiers, pattern);
+        self.aggregates = outer;
+    }
+
+    fn select_clause(&mut self, modifiers: &Modifiers) {
+        let mut text = String::from("SELECT");
+        if modifiers.distinct {
+            text.push_str(" DISTINCT");
+        }
+        if modifiers.reduced {
+            text.push_str(" REDUCED");
+        }
+        match modifiers.projection {
+            Some(variables) if !variables.is_empty() => {
+                for variable in variables {
+                    let expression = modifiers
+                        .select_expressions
+                        .iter()
+                        .find(|(v, _)| *v == variable);
+                    text.push(' ');
+                    match expression {
+                        Some((_, expression)) => text.push_str(&format!(
+                            "({} AS {})",
+                            self.expression(expression),
+                            self.variable(variable)
+                        )),
+                        None => text.push_str(&self.variable(variable)),
+                    }
+                }
+            }
+            _ => text.push_str(" *"),
+        }
+        self.line(&text);
+    }
+
+    fn where_clause(&mut self, modifiers: &Modifiers, pattern: &GraphPattern) {
+        self.line("WHERE {");
+        self.indent += 1;
+        let aggregates = std::mem::take(&mut self.aggregates);
+        self.elements(pattern);
+        self.aggregates = aggregates;
+        self.indent -= 1;
+        self.line("}");
+
+        if let Some(variables) = modifiers.group
+            && !variables.is_empty()
+        {
+            let variables: Vec<String> = variables.iter().map(|v| self.variable(v)).collect();
+            self.line(&format!("GROUP BY {}", variables.join(" ")));
+        }
+        if let Some(having) = modifiers.having {
+            self.line(&format!("HAVING ({})", self.expression(having)));
+        }
+        if !modifiers.order.is_empty() {
+            let conditions: Vec<String> = modifiers
+                .order
+                .iter()
+                .map(|condition| match condition {
+                    OrderExpression::Asc(e) => format!("ASC({})", self.expression(e)),
+                    OrderExpression::Desc(e) => format!("DESC({})", self.expression(e)),
+                })
+                .collect();
+            self.line(&format!("ORDER BY {}", conditions.join(" ")));
+        }
+        if let Some((start, length)) = modifiers.slice {
+            if start > 0 || length.is_none() {
+                self.line(&format!("OFFSET {start}"));
+            }
+            if let Some(length) = length {
+                self.line(&format!("LIMIT {length}"));
+            }
+        }
+        if let Some(values) = modifiers.values {
+            self.elements(values);
+        }
+    }
+
+    fn group(&mut self, pattern: &GraphPattern) {
+        self.line("{");
+        self.indent += 1;
+        self.elements(pattern);
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    /// Prints `pattern` as the contents of a group graph pattern, so that
+    /// parsing `{ <output> }` yields `pattern` again.
+    fn elements(&mut self, pattern: &GraphPattern) {
+        match pattern {
+            GraphPattern::Bgp { patterns } => {
+                for triple in patterns {
+                    self.triple(triple);
+                }
+            }
+            GraphPattern::Path {
+                subject,
+                path: property_path,
+                object,
+            } => {
+                let line = format!(
+                    "{} {} {} .",
+                    term(subject),
+                    path(property_path),
+                    term(object)
+                );
+                self.line(&line);
+            }
+            GraphPattern::Join { left, right } => {
+                self.leading_elements(left);
+                match **right {
+                    GraphPattern::Union { .. }
+                    | GraphPattern::Graph { .. }
+                    | GraphPattern::Service { .. }
+                    | GraphPattern::Values { .. }
+                    | GraphPattern::OrderBy { .. }
+                    | GraphPattern::Project { .. }
+                    | GraphPattern::Distinct { .. }
+                    | GraphPattern::Reduced { .. }
+                    | GraphPattern::Slice { .. }
+                    | GraphPattern::Group { .. } => self.elements(right),
+                    _ => self.group(right),
+                }
+            }
+            GraphPattern::LeftJoin {
+                left,
+                right,
+                expression,
+            } => {
+                self.leading_elements(left);
+                self.line("OPTIONAL {");
+                self.indent += 1;
+                self.elements(right);
+                if let Some(expression) = expression {
+                    let line = format!("FILTER({})", self.expression(expression));
+                    self.line(&line);
+                }
+                self.indent -= 1;
+                self.line("}");
+            }
+            GraphPattern::Minus { left, right } => {
+                self.leading_elements(left);
+                self.line("MINUS {");
+                self.indent += 1;
+                self.elements(right);
+                self.indent -= 1;
+                self.line("}");
+            }
+            GraphPattern::Filter { expression, inner } => {
+                self.leading_elements(inner);
+                let line = format!("FILTER({})", self.expression(expression));
+                self.line(&line);
+            }
+            GraphPattern::Extend {
+                inner,
+                variable,
+                expression,
+            } => {
+                self.leading_elements(inner);
+                let line = format!(
+                    "BIND({} AS {})",
+                    self.expression(expression),
+                    self.variable(variable)
+                );
+                self.line(&line);
+            }
+            GraphPattern::Union { left, right } => {
+                match **left {
+                    GraphPattern::Union { .. } => self.elements(left),
+                    _ => self.group(left),
+                }
+                self.line("UNION");
+                self.group(right);
+            }
+            GraphPattern::Graph { name, inner } => {
+                self.line(&format!("GRAPH {} {{", named_node_pattern(name)));
+                self.indent += 1;
+                self.elements(inner);
+                self.indent -= 1;
+                self.line("}");
+            }
+            GraphPattern::Service {
+                name,
+                inner,
+                silent,
+            } => {
+                let silent = if *silent { "SILENT " } else { "" };
+                self.line(&format!("SERVICE {silent}{} {{", named_node_pattern(name)));
+                self.indent += 1;
+                self.elements(inner);
+                self.indent -= 1;
+                self.line("}");
+            }
+            GraphPattern::Values {
+                variables,
+                bindings,
+            } => {
+                let variables: Vec<String> = variables.iter().map(|v| self.variable(v)).collect();
+                let rows: Vec<String> = bindings
+                    .iter()
+                    .map(|row| {
+                        let values: Vec<String> = row
+                            .iter()
+                            .map(|value| value.as_ref().map_or("UNDEF".to_owned(), term))
+                            .collect();
+                        format!("({})", values.join(" "))
+                    })
+                    .collect();
+                self.line(&format!(
+                    "VALUES ({}) {{ {} }}",
+                    variables.join(" "),
+                    rows.join(" ")
+                ));
+            }
+            GraphPattern::OrderBy { .. }
+            | GraphPattern::Project { .. }
+            | GraphPattern::Distinct { .. }
+            | GraphPattern::Reduced { .. }
+            | GraphPattern::Slice { .. }
+            | GraphPattern::Group { .. } => {
+                self.line("{");
+                self.indent += 1;
+                self.sub_select(pattern);
+                self.indent -= 1;
+                self.line("}");
+            }
+        }
+    }
+
+    /// Prints the elements a later element of the same group builds on. A
+    /// filter applies to its whole group, so it needs a group of its own.
+    fn leading_elements(&mut self, pattern: &GraphPattern) {
+        match pattern {
+            GraphPattern::Filter { .. } => self.group(pattern),
+            _ => self.elements(pattern),
+        }
+    }
+
+    fn triple(&mut self, triple: &Quad) {
+        let line = format!("{} .", quoted_triple_body(triple));
+        self.line(&line);
+    }
+
+    fn variable(&self, variable: &Variable) -> String {
+        format!("?{}", variable.value())
+    }
+
+    fn expression(&self, expression: &Expression) -> String {
+        let binary = |operator: &str, a: &Expression, b: &Expression| {
+            format!("({} {operator} {})", self.expression(a), self.expression(b))
+        };
+        let list = |expressions: &[Expression]| {
+            expressions
+                .iter()
+                .map(|e| self.expression(e))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match expression {
+            Expression::NamedNode(named_node) => format_iri(named_node.value()),
+            Expression::Literal(l) => format_literal(l),
+            Expression::Variable(variable) => {
+                match self.aggregates.iter().find(|(v, _)| v == variable) {
+                    Some((_, aggregate)) => self.aggregate(aggregate),
+                    None => self.variable(variable),
+                }
+            }
+            Expression::Or(a, b) => binary("||", a, b),
+            Expression::And(a, b) => binary("&&", a, b),
+            Expression::Equal(a, b) => binary("=", a, b),
+            Expression::Greater(a, b) => binary(">", a, b),
+            Expression::GreaterOrEqual(a, b) => binary(">=", a, b),
+            Expression::Less(a, b) => binary("<", a, b),
+            Expression::LessOrEqual(a, b) => binary("<=", a, b),
+            Expression::Add(a, b) => binary("+", a, b),
+            Expression::Subtract(a, b) => binary("-", a, b),
+            Expression::Multiply(a, b) => binary("*", a, b),
+            Expression::Divide(a, b) => binary("/", a, b),
+            Expression::In(a, expressions) => {
+                format!("({} IN ({}))", self.expression(a), list(expressions))
+            }
+            Expression::UnaryPlus(a) => format!("+({})", self.expression(a)),
+            Expression::UnaryMinus(a) => format!("-({})", self.expression(a)),
+            Expression::Not(a) => format!("!({})", self.expression(a)),
+            Expression::Exists(pattern) => {
+                let mut printer = Printer {
+                    indent: self.indent,
+                    ..Printer::default()
+                };
+                printer.group(pattern);
+                format!("EXISTS {}", printer.out.trim())
+            }
+            Expression::Bound(variable) => format!("BOUND({})", self.variable(variable)),
+            Expression::If(a, b, c) => format!(
+                "IF({}, {}, {})",
+                self.expression(a),
+                self.expression(b),
+                self.expression(c)
+            ),
+            Expression::Coalesce(expressions) => format!("COALESCE({})", list(expressions)),
+            Expression::FunctionCall(Function::Custom(name), expressions) => {
+                format!("{}({})", format_iri(name.value()), list(expressions))
+            }
+            Expression::FunctionCall(function, expressions) => {
+                let (keyword, ..) = BUILT_IN_FUNCTIONS
+                    .iter()
+                    .find(|(_, f, ..)| f == function)
+                    .unwrap();
+                format!("{keyword}({})", list(expressions))
+            }
+        }
+    }
+
+    fn aggregate(&self, aggregate: &AggregateExpression) -> String {
+        let name = match aggregate.function {
+            AggregateFunction::Count => "COUNT",
+            AggregateFunction::Sum => "SUM",
+            AggregateFunction::Avg => "AVG",
+            AggregateFunction::Min => "MIN",
+            AggregateFunction::Max => "MAX",
+            AggregateFunction::Sample => "SAMPLE",
+            AggregateFunction::GroupConcat { .. } => "GROUP_CONCAT",
+        };
+        let distinct = if aggregate.distinct { "DISTINCT " } else { "" };
+        let argument = match &aggregate.expression {
+            Some(expression) => self.expression(expression),
+            None => "*".to_owned(),
+        };
+        let separator = match &aggregate.function {
+            AggregateFunction::GroupConcat {
+                separator: Some(separator),
+            } => format!("; SEPARATOR = {}", format_string(separator)),
+            _ => String::new(),
+        };
+        format!("{name}({distinct}{argument}{separator})")
+    }
+}
+
+pub(crate) fn term(term: &Term) -> String {
+    match term {
+        Term::NamedNode(named_node) => format_iri(named_node.value()),
+        Term::BlankNode(blank_node) => format!("_:{}", blank_node.value()),
+        Term::Literal(l) => format_literal(l),
+        Term::Variable(variable) => format!("?{}", variable.value()),
+        Term::DefaultGraph(_) => "DEFAULT".to_owned(),
+        Term::Quad(quad) => format!("<< {} >>", quoted_triple_body(quad)),
+    }
+}
+
+fn quoted_triple_body(triple: &Quad) -> String {
+    let predicate = match triple.predicate() {
+        QuadPredicate::NamedNode(predicate) if *predicate == *rdf::r#type => "a".to_owned(),
+        predicate => term(&predicate.to_term()),
+    };
+    format!(
+        "{} {predicate} {}",
+        term(&triple.subject().to_term()),
+        term(&triple.object().to_term())
+    )
+}
+
+fn named_node_pattern(pattern: &NamedNodePattern) -> String {
+    match pattern {
+        NamedNodePattern::NamedNode(named_node) => format_iri(named_node.value()),
+        NamedNodePattern::Variable(variable) => format!("?{}", variable.value()),
+    }
+}
+
+fn graph_target(target: &GraphTarget) -> String {
+    match target {
+        GraphTarget::NamedNode(named_node) => format!("GRAPH {}", format_iri(named_node.value())),
+        GraphTarget::DefaultGraph => "DEFAULT".to_owned(),
+        GraphTarget::Named => "NAMED".to_owned(),
+        GraphTarget::All => "ALL".to_owned(),
+    }
+}
+
+fn graph_or_default(graph: &QuadGraph) -> String {
+    match graph {
+        QuadGraph::NamedNode(named_node) => format!("GRAPH {}", format_iri(named_node.value())),
+        graph => term(&graph.to_term()),
+    }
+}
+
+fn path(property_path: &PropertyPath) -> String {
+    match property_path {
+        PropertyPath::NamedNode(named_node) => format_iri(named_node.value()),
+        PropertyPath::Reverse(inner) => format!("^{}", path_primary(inner)),
+        PropertyPath::Sequence(a, b) => format!("({} / {})", path(a), path(b)),
+        PropertyPath::Alternative(a, b) => format!("({} | {})", path(a), path(b)),
+        PropertyPath::ZeroOrMore(inner) => format!("{}*", path_primary(inner)),
+        PropertyPath::OneOrMore(inner) => format!("{}+", path_primary(inner)),
+        PropertyPath::ZeroOrOne(inner) => format!("{}?", path_primary(inner)),
+        PropertyPath::NegatedPropertySet(named_nodes) => {
+            let named_nodes: Vec<String> =
+                named_nodes.iter().map(|n| format_iri(n.value())).collect();
+            format!("!({})", named_nodes.join(" | "))
+        }
+    }
+}
+
+fn path_primary(property_path: &PropertyPath) -> String {
+    match property_path {
+        PropertyPath::NamedNode(_) => path(property_path),
+        _ => format!("({})", path(property_path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rs::sparql::parser::{parse_query, parse_update};
+
+    fn assert_round_trip(query: &str) {
+        let parsed = parse_query(query, None).unwrap();
+        let printed = parsed.to_string();
+        let reparsed =
+            parse_query(&printed, None).unwrap_or_else(|error| panic!("{error} in\n{printed}"));
+        assert_eq!(parsed, reparsed, "{printed}");
+    }
+
+    #[test]
+    fn queries_round_trip() {
+        for query in [
+            "PREFIX ex: <http://example.org/> SELECT ?s ?o WHERE { ?s ex:p ?o ; a ex:C . OPTIONAL { ?o ex:q ?z FILTER(?z > 3) } FILTER(?o != ex:x && BOUND(?z)) } ORDER BY DESC(?o) LIMIT 10 OFFSET 2",
+            "PREFIX ex: <http://example.org/> SELECT DISTINCT * WHERE { { ?s ex:p ?o } UNION { ?s ex:q ?o } UNION { ?s ex:r ?o } MINUS { ?s ex:no ?o } BIND(STR(?o) AS ?str) VALUES (?s) { (ex:a) (UNDEF) } }",
+            "PREFIX ex: <http://example.org/> SELECT ?s (COUNT(DISTINCT ?o) AS ?n) (GROUP_CONCAT(?o; SEPARATOR=\", \") AS ?all) WHERE { ?s ex:p/ex:q* ?o . ?o ^ex:r|!(ex:a|^ex:b) ?x . ?x (ex:a/ex:b)+ ?y } GROUP BY ?s HAVING (COUNT(DISTINCT ?o) > 1) ORDER BY ?n",
+            "PREFIX ex: <http://example.org/> CONSTRUCT { ?s ex:p [ ex:q ?o ] } FROM <http://g> FROM NAMED <http://h> WHERE { GRAPH ?g { ?s ex:p ( 1 2.5 -3e2 \"x\"@en--ltr ) } { SELECT ?s WHERE { ?s ?p ?o } LIMIT 1 } }",
+            "BASE <http://example.org/> ASK { <a> <b> \"c\"^^<d> . FILTER NOT EXISTS { ?x <p> true } FILTER(?x IN (1, 2) || ?x NOT IN ()) FILTER(REGEX(?x, \"a\\n\", \"i\") && -?y < ?z -1 * 2) }",
+            "DESCRIBE ?x <http://a> WHERE { << ?x <p> ?y >> <q> ?z . SERVICE SILENT <http://s> { ?x ?y ?z } }",
+            "DESCRIBE <http://a>",
+            "CONSTRUCT WHERE { ?s <p> ?o }",
+            "SELECT (SUM(?x) AS ?s) WHERE { ?a <p> ?x } GROUP BY (STR(?a) AS ?k) STR(?x) VALUES ?k { \"a\" }",
+            "SELECT ?x { ?x <p> ?y FILTER(?y) ?y <q> ?z OPTIONAL { ?z <r> ?w } }",
+            "SELECT * { { ?a <p> ?b FILTER(?b) } UNION { { ?c <q> ?d } UNION { ?e <r> ?f } } }",
+            "SELECT (COUNT(*) AS ?n) { { SELECT ?x (MAX(?y) AS ?m) { ?x <p> ?y } GROUP BY ?x } }",
+        ] {
+            assert_round_trip(query);
+        }
+    }
+
+    #[test]
+    fn updates_round_trip() {
+        for update in [
+            "PREFIX ex: <http://example.org/> INSERT DATA { ex:a ex:p \"x\", _:b . GRAPH ex:g { ex:a ex:q 1 } } ; DELETE DATA { ex:a ex:p 2 }",
+            "DELETE WHERE { ?s <p> ?o . GRAPH ?g { ?o <q> ?z } }",
+            "WITH <http://g> DELETE { ?s <p> ?o } INSERT { ?s <q> [ <r> ?o ] } USING <http://h> USING NAMED <http://i> WHERE { ?s <p> ?o OPTIONAL { ?o <x> ?y } }",
+            "INSERT { GRAPH ?g { ?s a <C> } } WHERE { GRAPH ?g { ?s ?p ?o } }",
+            "LOAD SILENT <file:///tmp/data.ttl> INTO GRAPH <http://g>; CLEAR DEFAULT; DROP SILENT ALL; CLEAR NAMED; CREATE GRAPH <http://g>",
+            "COPY DEFAULT TO GRAPH <http://g>; MOVE SILENT <http://g> TO DEFAULT; ADD GRAPH <http://a> TO <http://b>",
+        ] {
+            let parsed = parse_update(update, None).unwrap();
+            let printed = parsed.to_string();
+            let reparsed = parse_update(&printed, None)
+                .unwrap_or_else(|error| panic!("{error} in\n{printed}"));
+            assert_eq!(parsed, reparsed, "{printed}");
+        }
+    }
+
+    #[test]
+    fn literals_print_in_short_form_when_possible() {
+        let query = parse_query(
+            "SELECT * { ?s <p> 1, -2.5, 3e1, true, \"4\"^^<http://www.w3.org/2001/XMLSchema#integer>, \"x\" }",
+            None,
+        )
+        .unwrap();
+
+        let printed = query.to_string();
+        for expected in [" 1 .", " -2.5 .", " 3e1 .", " true .", " 4 .", " \"x\" ."] {
+            assert!(printed.contains(expected), "{expected} in {printed}");
+        }
+    }
+}
@@ -0,0 +1,279 @@
+mod csv;
+mod json;
+mod xml;
+
+use std::io::{self, Read, Write};
+
+use crate::rs::parse_error::{ParseError, SyntaxError};
+use crate::rs::sparql::eval::QueryResults;
+
+/// The serializations of SELECT and ASK results defined by SPARQL 1.1.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum QueryResultsFormat {
+    Json,
+    Xml,
+    Csv,
+    Tsv,
+}
+
+impl QueryResultsFormat {
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            QueryResultsFormat::Json => "application/sparql-results+json",
+            QueryResultsFormat::Xml => "application/sparql-results+xml",
+            QueryResultsFormat::Csv => "text/csv",
+            QueryResultsFormat::Tsv => "text/tab-separated-values",
+        }
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            QueryResultsFormat::Json => "srj",
+            QueryResultsFormat::Xml => "srx",
+            QueryResultsFormat::Csv => "csv",
+            QueryResultsFormat::Tsv => "tsv",
+        }
+    }
+
+    /// Looks up a format by media type, ignoring parameters such as
+    /// `charset` and letter case.
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        let essence = media_type.split(';').next()?.trim().to_ascii_lowercase();
+        match essence.as_str() {
+            "application/sparql-results+json" | "application/json" => {
+                Some(QueryResultsFormat::Json)
+            }
+            "application/sparql-results+xml" | "application/xml" => Some(QueryResultsFormat::Xml),
+            "text/csv" => Some(QueryResultsFormat::Csv),
+            "text/tab-separated-values" => Some(QueryResultsFormat::Tsv),
+            _ => None,
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "srj" | "json" => Some(QueryResultsFormat::Json),
+            "srx" | "xml" => Some(QueryResultsFormat::Xml),
+            "csv" => Some(QueryResultsFormat::Csv),
+            "tsv" => Some(QueryResultsFormat::Tsv),
+            _ => None,
+        }
+    }
+}
+
+/// Serializes SELECT or ASK results. Graph results have no serialization in
+/// these formats and fail with [`io::ErrorKind::InvalidInput`].
+pub fn write_results(
+    mut writer: impl Write,
+    results: &QueryResults,
+    format: QueryResultsFormat,
+) -> io::Result<()> {
+    if let QueryResults::Graph(_) = results {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "graph results can not be written as SPARQL query results",
+        ));
+    }
+
+    match format {
+        QueryResultsFormat::Json => json::write(&mut writer, results),
+        QueryResultsFormat::Xml => xml::write(&mut writer, results),
+        QueryResultsFormat::Csv => csv::write_csv(&mut writer, results),
+        QueryResultsFormat::Tsv => csv::write_tsv(&mut writer, results),
+    }?;
+    writer.flush()
+}
+
+/// Parses SELECT or ASK results. CSV is lossy: literals lose their datatype
+/// and language, and anything that looks like an absolute IRI becomes one.
+pub fn read_results(
+    mut reader: impl Read,
+    format: QueryResultsFormat,
+) -> Result<QueryResults, ParseError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    let input = input.strip_prefix('\u{feff}').unwrap_or(&input);
+
+    let results = match format {
+        QueryResultsFormat::Json => json::read(input),
+        QueryResultsFormat::Xml => xml::read(input),
+        QueryResultsFormat::Csv => csv::read_csv(input),
+        QueryResultsFormat::Tsv => csv::read_tsv(input),
+    }?;
+    Ok(results)
+}
+
+fn position_of(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset.min(input.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+fn syntax_error(input: &str, message: &str, offset: usize) -> SyntaxError {
+    let (line, column) = position_of(input, offset);
+    SyntaxError::new(message, line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::blank_node::BlankNode;
+    use crate::rs::literal::{LanguageDirection, Literal};
+    use crate::rs::named_node::NamedNode;
+    use crate::rs::pattern::Bindings;
+    use crate::rs::quad::Quad;
+    use crate::rs::quad_object::QuadObject;
+    use crate::rs::quad_predicate::QuadPredicate;
+    use crate::rs::quad_subject::QuadSubject;
+    use crate::rs::term::Term;
+    use crate::rs::term_like::TermLike;
+    use crate::rs::variable::Variable;
+    use crate::rs::vocab::xsd;
+
+    fn nn(value: &str) -> NamedNode {
+        NamedNode::new(&format!("http://example.org/{value}"))
+    }
+
+    fn solutions() -> QueryResults {
+        let triple = Quad::new(
+            &QuadSubject::NamedNode(nn("a")),
+            &QuadPredicate::NamedNode(nn("p")),
+            &QuadObject::Literal(Literal::new("1", None, None, Some(&xsd::integer))),
+            None,
+        );
+        let variables = vec![Variable::new("s"), Variable::new("o")];
+        let solutions = vec![
+            Bindings::from_iter([
+                (Variable::new("s"), nn("a").to_term()),
+                (
+                    Variable::new("o"),
+                    Literal::new("say \"hi\",\n<&>", Some("en"), None, None).to_term(),
+                ),
+            ]),
+            Bindings::from_iter([(Variable::new("s"), BlankNode::new("b0").to_term())]),
+            Bindings::from_iter([
+                (Variable::new("s"), Term::Quad(Box::new(triple))),
+                (
+                    Variable::new("o"),
+                    Literal::new("2.5", None, None, Some(&xsd::decimal)).to_term(),
+                ),
+            ]),
+            Bindings::from_iter([(
+                Variable::new("o"),
+                Literal::new(
+                    "\u{5d0}",
+                    Some("he"),
+                    Some(&LanguageDirection::RightToLeft),
+                    None,
+                )
+                .to_term(),
+            )]),
+        ];
+        QueryResults::Solutions {
+            variables,
+            solutions,
+        }
+    }
+
+    fn round_trip(results: &QueryResults, format: QueryResultsFormat) -> QueryResults {
+        let mut buffer = Vec::new();
+        write_results(&mut buffer, results, format).unwrap();
+        read_results(buffer.as_slice(), format).unwrap()
+    }
+
+    #[test]
+    fn lossless_formats_round_trip() {
+        for format in [
+            QueryResultsFormat::Json,
+            QueryResultsFormat::Xml,
+            QueryResultsFormat::Tsv,
+        ] {
+            assert_eq!(round_trip(&solutions(), format), solutions(), "{format:?}");
+        }
+        for format in [
+            QueryResultsFormat::Json,
+            QueryResultsFormat::Xml,
+            QueryResultsFormat::Csv,
+            QueryResultsFormat::Tsv,
+        ] {
+            for value in [true, false] {
+                let results = QueryResults::Boolean(value);
+                assert_eq!(round_trip(&results, format), results, "{format:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn csv_keeps_only_lexical_forms() {
+        let mut buffer = Vec::new();
+        let results = QueryResults::Solutions {
+            variables: vec![Variable::new("s"), Variable::new("o")],
+            solutions: vec![
+                Bindings::from_iter([
+                    (Variable::new("s"), nn("a").to_term()),
+                    (
+                        Variable::new("o"),
+                        Literal::new("a, \"b\"", Some("en"), None, None).to_term(),
+                    ),
+                ]),
+                Bindings::from_iter([(Variable::new("s"), BlankNode::new("x").to_term())]),
+            ],
+        };
+        write_results(&mut buffer, &results, QueryResultsFormat::Csv).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer.clone()).unwrap(),
+            "s,o\r\nhttp://example.org/a,\"a, \"\"b\"\"\"\r\n_:x,\r\n"
+        );
+
+        let QueryResults::Solutions { solutions, .. } =
+            read_results(buffer.as_slice(), QueryResultsFormat::Csv).unwrap()
+        else {
+            panic!("expected solutions");
+        };
+        assert_eq!(solutions[0].get("s"), Some(&nn("a").to_term()));
+        assert_eq!(
+            solutions[0].get("o"),
+            Some(&Literal::new("a, \"b\"", None, None, None).to_term())
+        );
+        assert_eq!(solutions[1].get("o"), None);
+    }
+
+    #[test]
+    fn json_accepts_legacy_and_reports_errors() {
+        let input = r#"{
+            "head": {"vars": ["x"], "link": ["http://example.org/about"]},
+            "results": {"bindings": [
+                {"x": {"type": "typed-literal", "value": "1",
+                       "datatype": "http://www.w3.org/2001/XMLSchema#integer"}}
+            ]}
+        }"#;
+        let QueryResults::Solutions { solutions, .. } =
+            read_results(input.as_bytes(), QueryResultsFormat::Json).unwrap()
+        else {
+            panic!("expected solutions");
+        };
+        assert_eq!(
+            solutions[0].get("x"),
+            Some(&Literal::new("1", None, None, Some(&xsd::integer)).to_term())
+        );
+
+        let error = read_results(
+            r#"{"head": {"vars": ["x"]}, "results": {"bindings": [{"x": {"type": "uri"}}]}}"#
+                .as_bytes(),
+            QueryResultsFormat::Json,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("value"), "{error}");
+
+        let ParseError::Syntax(error) = read_results(
+            "<sparql xmlns=\"http://www.w3.org/2005/sparql-results#\">\n<head>\n  <variable/>\n</head>\n</sparql>"
+                .as_bytes(),
+            QueryResultsFormat::Xml,
+        )
+        .unwrap_err() else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!(error.line(), 3);
+    }
+}
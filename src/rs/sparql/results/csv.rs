@@ -0,0 +1,265 @@
+use std::io::{self, Write};
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::literal::Literal;
+use crate::rs::named_node::NamedNode;
+use crate::rs::parse_error::SyntaxError;
+use crate::rs::pattern::Bindings;
+use crate::rs::sparql::eval::QueryResults;
+use crate::rs::sparql::parser::parse_term;
+use crate::rs::sparql::printer;
+use crate::rs::sparql::results::{position_of, syntax_error};
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::variable::Variable;
+
+/// Neither format can express ASK results, so they are written as a single
+/// `_askResult` column like other stores do.
+const ASK_RESULT: &str = "_askResult";
+
+pub(super) fn write_csv(writer: &mut impl Write, results: &QueryResults) -> io::Result<()> {
+    let (variables, solutions) = match results {
+        QueryResults::Boolean(value) => {
+            return write!(writer, "{ASK_RESULT}\r\n{value}\r\n");
+        }
+        QueryResults::Solutions {
+            variables,
+            solutions,
+        } => (variables, solutions),
+        QueryResults::Graph(_) => unreachable!("rejected by write_results"),
+    };
+
+    let header: Vec<String> = variables.iter().map(|v| csv_field(v.value())).collect();
+    write!(writer, "{}\r\n", header.join(","))?;
+    for solution in solutions {
+        let row: Vec<String> = variables
+            .iter()
+            .map(|variable| match solution.get_variable(variable) {
+                Some(Term::BlankNode(blank_node)) => format!("_:{}", blank_node.value()),
+                Some(Term::Quad(triple)) => csv_field(&printer::term(&Term::Quad(triple.clone()))),
+                Some(term) => csv_field(term.value()),
+                None => String::new(),
+            })
+            .collect();
+        write!(writer, "{}\r\n", row.join(","))?;
+    }
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+pub(super) fn write_tsv(writer: &mut impl Write, results: &QueryResults) -> io::Result<()> {
+    let (variables, solutions) = match results {
+        QueryResults::Boolean(value) => {
+            return write!(writer, "?{ASK_RESULT}\n{value}\n");
+        }
+        QueryResults::Solutions {
+            variables,
+            solutions,
+        } => (variables, solutions),
+        QueryResults::Graph(_) => unreachable!("rejected by write_results"),
+    };
+
+    let header: Vec<String> = variables
+        .iter()
+        .map(|v| format!("?{}", v.value()))
+        .collect();
+    writeln!(writer, "{}", header.join("\t"))?;
+    for solution in solutions {
+        let row: Vec<String> = variables
+            .iter()
+            .map(|variable| match solution.get_variable(variable) {
+                Some(term) => printer::term(term),
+                None => String::new(),
+            })
+            .collect();
+        writeln!(writer, "{}", row.join("\t"))?;
+    }
+    Ok(())
+}
+
+/// Splits CSV input into records of fields, each with its byte offset.
+fn csv_records(input: &str) -> Result<Vec<Vec<(String, usize)>>, SyntaxError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while chars.peek().is_some() {
+        let (start, _) = *chars.peek().unwrap();
+        let mut field = String::new();
+        if chars.next_if(|&(_, c)| c == '"').is_some() {
+            loop {
+                match chars.next() {
+                    Some((_, '"')) if chars.next_if(|&(_, c)| c == '"').is_some() => {
+                        field.push('"')
+                    }
+                    Some((_, '"')) => break,
+                    Some((_, c)) => field.push(c),
+                    None => return Err(syntax_error(input, "unterminated quoted field", start)),
+                }
+            }
+        }
+        while let Some((_, c)) = chars.next_if(|&(_, c)| !matches!(c, ',' | '\r' | '\n')) {
+            field.push(c);
+        }
+        record.push((field, start));
+
+        match chars.next() {
+            Some((_, ',')) => {
+                if chars.peek().is_none() {
+                    record.push((String::new(), input.len()));
+                }
+            }
+            Some((_, '\r')) => {
+                chars.next_if(|&(_, c)| c == '\n');
+                records.push(std::mem::take(&mut record));
+            }
+            _ => records.push(std::mem::take(&mut record)),
+        }
+    }
+    if !record.is_empty() {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+pub(super) fn read_csv(input: &str) -> Result<QueryResults, SyntaxError> {
+    let mut records = csv_records(input)?.into_iter();
+    let header = records.next().unwrap_or_default();
+    let variables: Vec<Variable> = header.iter().map(|(name, _)| Variable::new(name)).collect();
+
+    if let [variable] = variables.as_slice()
+        && variable.value() == ASK_RESULT
+    {
+        return read_boolean(input, records.next().and_then(|r| r.into_iter().next()));
+    }
+
+    let mut solutions = Vec::new();
+    for record in records {
+        check_width(input, &variables, &record)?;
+        let solution: Bindings = variables
+            .iter()
+            .zip(record)
+            .filter(|(_, (field, _))| !field.is_empty())
+            .map(|(variable, (field, _))| (variable.clone(), csv_term(&field)))
+            .collect();
+        solutions.push(solution);
+    }
+    Ok(QueryResults::Solutions {
+        variables,
+        solutions,
+    })
+}
+
+/// CSV drops term types, so this guesses: `_:` prefixes mark blank nodes and
+/// values with a URI scheme and no whitespace are taken for IRIs.
+fn csv_term(field: &str) -> Term {
+    if let Some(label) = field.strip_prefix("_:") {
+        return BlankNode::new(label).to_term();
+    }
+    let looks_like_iri = field.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    }) && !field.contains(char::is_whitespace);
+    if looks_like_iri {
+        NamedNode::new(field).to_term()
+    } else {
+        Literal::new(field, None, None, None).to_term()
+    }
+}
+
+pub(super) fn read_tsv(input: &str) -> Result<QueryResults, SyntaxError> {
+    let mut lines = input.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((line.trim_end_matches(['\r', '\n']), start))
+    });
+
+    let mut variables = Vec::new();
+    if let Some((header, start)) = lines.next().filter(|(header, _)| !header.is_empty()) {
+        for (name, offset) in fields(header, start) {
+            match name.strip_prefix(['?', '$']) {
+                Some(name) => variables.push(Variable::new(name)),
+                None => return Err(syntax_error(input, "expected a variable", offset)),
+            }
+        }
+    }
+
+    if let [variable] = variables.as_slice()
+        && variable.value() == ASK_RESULT
+    {
+        let value = lines.next().map(|(line, start)| (line.to_owned(), start));
+        return read_boolean(input, value);
+    }
+
+    let mut solutions = Vec::new();
+    for (line, start) in lines {
+        let record: Vec<(String, usize)> = fields(line, start)
+            .map(|(field, offset)| (field.to_owned(), offset))
+            .collect();
+        check_width(input, &variables, &record)?;
+        let mut solution = Bindings::new();
+        for (variable, (field, offset)) in variables.iter().zip(&record) {
+            let field = field.trim();
+            if field.is_empty() {
+                continue;
+            }
+            let term = parse_term(field).map_err(|error| {
+                let (line, column) = position_of(input, *offset);
+                SyntaxError::new(error.message(), line, column + error.column() - 1)
+            })?;
+            solution.bind(variable, &term);
+        }
+        solutions.push(solution);
+    }
+    Ok(QueryResults::Solutions {
+        variables,
+        solutions,
+    })
+}
+
+fn fields(line: &str, start: usize) -> impl Iterator<Item = (&str, usize)> {
+    line.split('\t').scan(start, |offset, field| {
+        let field_start = *offset;
+        *offset += field.len() + 1;
+        Some((field, field_start))
+    })
+}
+
+fn check_width(
+    input: &str,
+    variables: &[Variable],
+    record: &[(String, usize)],
+) -> Result<(), SyntaxError> {
+    let is_empty_row = matches!(record, [(field, _)] if field.is_empty());
+    if record.len() == variables.len() || (variables.is_empty() && is_empty_row) {
+        return Ok(());
+    }
+    let offset = record.first().map_or(input.len(), |(_, offset)| *offset);
+    Err(syntax_error(
+        input,
+        &format!(
+            "expected {} fields but found {}",
+            variables.len(),
+            record.len()
+        ),
+        offset,
+    ))
+}
+
+fn read_boolean(input: &str, value: Option<(String, usize)>) -> Result<QueryResults, SyntaxError> {
+    match value {
+        Some((value, _)) if value.trim() == "true" => Ok(QueryResults::Boolean(true)),
+        Some((value, _)) if value.trim() == "false" => Ok(QueryResults::Boolean(false)),
+        Some((_, offset)) => Err(syntax_error(input, "expected true or false", offset)),
+        None => Err(syntax_error(input, "expected true or false", input.len())),
+    }
+}
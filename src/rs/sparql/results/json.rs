@@ -0,0 +1,192 @@
+use std::io::{self, Write};
+
+use serde_json::{Map, Value, json};
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::literal::{LanguageDirection, Literal};
+use crate::rs::named_node::NamedNode;
+use crate::rs::parse_error::SyntaxError;
+use crate::rs::pattern::Bindings;
+use crate::rs::quad::Quad;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::sparql::eval::QueryResults;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::variable::Variable;
+use crate::rs::vocab::{rdf, xsd};
+
+pub(super) fn write(writer: &mut impl Write, results: &QueryResults) -> io::Result<()> {
+    let document = match results {
+        QueryResults::Boolean(value) => json!({ "head": {}, "boolean": value }),
+        QueryResults::Solutions {
+            variables,
+            solutions,
+        } => {
+            let bindings: Vec<Value> = solutions
+                .iter()
+                .map(|solution| {
+                    let mut object = Map::new();
+                    for (variable, term) in solution.iter() {
+                        if let Some(term) = term_to_json(term) {
+                            object.insert(variable.value().to_owned(), term);
+                        }
+                    }
+                    Value::Object(object)
+                })
+                .collect();
+            let variables: Vec<&str> = variables.iter().map(|v| v.value()).collect();
+            json!({ "head": { "vars": variables }, "results": { "bindings": bindings } })
+        }
+        QueryResults::Graph(_) => unreachable!("rejected by write_results"),
+    };
+    serde_json::to_writer(&mut *writer, &document)?;
+    writer.write_all(b"\n")
+}
+
+fn term_to_json(term: &Term) -> Option<Value> {
+    let mut object = Map::new();
+    match term {
+        Term::NamedNode(named_node) => {
+            object.insert("type".to_owned(), json!("uri"));
+            object.insert("value".to_owned(), json!(named_node.value()));
+        }
+        Term::BlankNode(blank_node) => {
+            object.insert("type".to_owned(), json!("bnode"));
+            object.insert("value".to_owned(), json!(blank_node.value()));
+        }
+        Term::Literal(literal) => {
+            object.insert("type".to_owned(), json!("literal"));
+            object.insert("value".to_owned(), json!(literal.value()));
+            if !literal.language().is_empty() {
+                object.insert("xml:lang".to_owned(), json!(literal.language()));
+            } else if *literal.datatype() != *xsd::string {
+                object.insert("datatype".to_owned(), json!(literal.datatype().value()));
+            }
+            if let Some(direction) = literal.direction() {
+                object.insert("its:dir".to_owned(), json!(direction.to_string()));
+            }
+        }
+        Term::Quad(triple) => {
+            object.insert("type".to_owned(), json!("triple"));
+            object.insert(
+                "value".to_owned(),
+                json!({
+                    "subject": term_to_json(&triple.subject().to_term())?,
+                    "predicate": term_to_json(&triple.predicate().to_term())?,
+                    "object": term_to_json(&triple.object().to_term())?,
+                }),
+            );
+        }
+        Term::Variable(_) | Term::DefaultGraph(_) => return None,
+    }
+    Some(Value::Object(object))
+}
+
+pub(super) fn read(input: &str) -> Result<QueryResults, SyntaxError> {
+    let document: Value = serde_json::from_str(input)
+        .map_err(|error| SyntaxError::new(&error.to_string(), error.line(), error.column()))?;
+    let document = document
+        .as_object()
+        .ok_or_else(|| invalid("expected a JSON object"))?;
+
+    if let Some(value) = document.get("boolean") {
+        return value
+            .as_bool()
+            .map(QueryResults::Boolean)
+            .ok_or_else(|| invalid("\"boolean\" must be true or false"));
+    }
+
+    let variables = match document.get("head").and_then(|head| head.get("vars")) {
+        Some(Value::Array(names)) => names
+            .iter()
+            .map(|name| name.as_str().map(Variable::new))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| invalid("\"vars\" must contain strings"))?,
+        Some(_) => return Err(invalid("\"vars\" must be an array")),
+        None => Vec::new(),
+    };
+
+    let bindings = document
+        .get("results")
+        .and_then(|results| results.get("bindings"))
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid("expected \"results\" with a \"bindings\" array"))?;
+    let mut solutions = Vec::with_capacity(bindings.len());
+    for binding in bindings {
+        let binding = binding
+            .as_object()
+            .ok_or_else(|| invalid("a binding must be an object"))?;
+        let mut solution = Bindings::new();
+        for (name, term) in binding {
+            solution.bind(&Variable::new(name), &json_to_term(term)?);
+        }
+        solutions.push(solution);
+    }
+
+    Ok(QueryResults::Solutions {
+        variables,
+        solutions,
+    })
+}
+
+fn json_to_term(term: &Value) -> Result<Term, SyntaxError> {
+    let field = |name: &str| term.get(name).and_then(Value::as_str);
+    let kind = field("type").ok_or_else(|| invalid("an RDF term needs a \"type\""))?;
+
+    if kind == "triple" {
+        let triple = term
+            .get("value")
+            .ok_or_else(|| invalid("a triple term needs a \"value\""))?;
+        let part = |name: &str| {
+            triple
+                .get(name)
+                .ok_or_else(|| invalid(&format!("a triple term needs a \"{name}\"")))
+                .and_then(json_to_term)
+        };
+        let subject = QuadSubject::try_from(part("subject")?)
+            .map_err(|_| invalid("invalid subject in triple term"))?;
+        let predicate = QuadPredicate::try_from(part("predicate")?)
+            .map_err(|_| invalid("invalid predicate in triple term"))?;
+        let object = QuadObject::try_from(part("object")?)
+            .map_err(|_| invalid("invalid object in triple term"))?;
+        return Ok(Term::Quad(Box::new(Quad::new(
+            &subject, &predicate, &object, None,
+        ))));
+    }
+
+    let value = field("value").ok_or_else(|| invalid("an RDF term needs a \"value\""))?;
+    match kind {
+        "uri" => Ok(NamedNode::new(value).to_term()),
+        "bnode" => Ok(BlankNode::new(value).to_term()),
+        "literal" | "typed-literal" => {
+            let direction = field("its:dir")
+                .map(|direction| {
+                    direction
+                        .parse::<LanguageDirection>()
+                        .map_err(|error| invalid(&error.to_string()))
+                })
+                .transpose()?;
+            let datatype = field("datatype")
+                .filter(|datatype| {
+                    *datatype != rdf::langString.value() && *datatype != rdf::dirLangString.value()
+                })
+                .map(NamedNode::new);
+            Ok(Literal::new(
+                value,
+                field("xml:lang"),
+                direction.as_ref(),
+                datatype.as_ref(),
+            )
+            .to_term())
+        }
+        _ => Err(invalid(&format!("unknown RDF term type \"{kind}\""))),
+    }
+}
+
+/// serde_json does not keep positions in a parsed [`Value`], so structural
+/// errors point at the start of the document.
+fn invalid(message: &str) -> SyntaxError {
+    SyntaxError::new(message, 1, 1)
+}
@@ -0,0 +1,324 @@
+use std::io::{self, Write};
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, XmlVersion, escape};
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::literal::{LanguageDirection, Literal};
+use crate::rs::named_node::NamedNode;
+use crate::rs::parse_error::SyntaxError;
+use crate::rs::pattern::Bindings;
+use crate::rs::quad::Quad;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::sparql::eval::QueryResults;
+use crate::rs::sparql::results::syntax_error;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::variable::Variable;
+use crate::rs::vocab::{rdf, xsd};
+
+const NAMESPACE: &str = "http://www.w3.org/2005/sparql-results#";
+const ITS_NAMESPACE: &str = "http://www.w3.org/2005/11/its";
+
+pub(super) fn write(writer: &mut impl Write, results: &QueryResults) -> io::Result<()> {
+    writeln!(writer, "<?xml version=\"1.0\"?>")?;
+    writeln!(
+        writer,
+        "<sparql xmlns=\"{NAMESPACE}\" xmlns:its=\"{ITS_NAMESPACE}\">"
+    )?;
+    match results {
+        QueryResults::Boolean(value) => {
+            writeln!(writer, "  <head/>")?;
+            writeln!(writer, "  <boolean>{value}</boolean>")?;
+        }
+        QueryResults::Solutions {
+            variables,
+            solutions,
+        } => {
+            writeln!(writer, "  <head>")?;
+            for variable in variables {
+                writeln!(
+                    writer,
+                    "    <variable name=\"{}\"/>",
+                    text(variable.value())
+                )?;
+            }
+            writeln!(writer, "  </head>")?;
+            writeln!(writer, "  <results>")?;
+            for solution in solutions {
+                writeln!(writer, "    <result>")?;
+                for (variable, term) in solution.iter() {
+                    if let Some(term) = term_to_xml(term) {
+                        writeln!(
+                            writer,
+                            "      <binding name=\"{}\">{term}</binding>",
+                            text(variable.value())
+                        )?;
+                    }
+                }
+                writeln!(writer, "    </result>")?;
+            }
+            writeln!(writer, "  </results>")?;
+        }
+        QueryResults::Graph(_) => unreachable!("rejected by write_results"),
+    }
+    writeln!(writer, "</sparql>")
+}
+
+fn text(value: &str) -> String {
+    // Escaping carriage returns keeps them from being normalized away.
+    escape::escape(value).replace('\r', "&#13;")
+}
+
+fn term_to_xml(term: &Term) -> Option<String> {
+    Some(match term {
+        Term::NamedNode(named_node) => format!("<uri>{}</uri>", text(named_node.value())),
+        Term::BlankNode(blank_node) => format!("<bnode>{}</bnode>", text(blank_node.value())),
+        Term::Literal(literal) => {
+            let mut attributes = String::new();
+            if !literal.language().is_empty() {
+                attributes.push_str(&format!(" xml:lang=\"{}\"", text(literal.language())));
+            } else if *literal.datatype() != *xsd::string {
+                attributes.push_str(&format!(
+                    " datatype=\"{}\"",
+                    text(literal.datatype().value())
+                ));
+            }
+            if let Some(direction) = literal.direction() {
+                attributes.push_str(&format!(" its:dir=\"{direction}\""));
+            }
+            format!("<literal{attributes}>{}</literal>", text(literal.value()))
+        }
+        Term::Quad(triple) => format!(
+            "<triple><subject>{}</subject><predicate>{}</predicate><object>{}</object></triple>",
+            term_to_xml(&triple.subject().to_term())?,
+            term_to_xml(&triple.predicate().to_term())?,
+            term_to_xml(&triple.object().to_term())?
+        ),
+        Term::Variable(_) | Term::DefaultGraph(_) => return None,
+    })
+}
+
+/// A parsed element; mixed content is not used by the format, so all text
+/// children are concatenated.
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+    offset: usize,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
+
+struct Interpreter<'a> {
+    input: &'a str,
+}
+
+pub(super) fn read(input: &str) -> Result<QueryResults, SyntaxError> {
+    let root = parse_document(input)?;
+    Interpreter { input }.results(&root)
+}
+
+fn parse_document(input: &str) -> Result<Element, SyntaxError> {
+    let mut reader = Reader::from_str(input);
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+
+    loop {
+        let offset = reader.buffer_position() as usize;
+        let event = reader.read_event().map_err(|error| {
+            syntax_error(input, &error.to_string(), reader.error_position() as usize)
+        })?;
+        match event {
+            Event::Start(start) => stack.push(element(input, &start, offset)?),
+            Event::Empty(start) => {
+                let element = element(input, &start, offset)?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::End(_) => {
+                let element = stack.pop().expect("the reader checks end tags");
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::Text(content) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&content.xml10_content());
+                }
+            }
+            Event::CData(content) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&content);
+                }
+            }
+            Event::GeneralRef(reference) => {
+                let resolved = if reference.is_char_ref() {
+                    reference.resolve_char_ref().ok().flatten()
+                } else {
+                    escape::resolve_predefined_entity(&reference).and_then(|s| s.chars().next())
+                };
+                let Some(c) = resolved else {
+                    return Err(syntax_error(input, "unknown entity reference", offset));
+                };
+                if let Some(current) = stack.last_mut() {
+                    current.text.push(c);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err(syntax_error(
+            input,
+            "unexpected end of document",
+            input.len(),
+        ));
+    }
+    root.ok_or_else(|| syntax_error(input, "expected a <sparql> element", 0))
+}
+
+fn element(input: &str, start: &BytesStart, offset: usize) -> Result<Element, SyntaxError> {
+    let name = start.local_name().as_ref().to_owned();
+    let mut attributes = Vec::new();
+    for attribute in start.attributes() {
+        let attribute =
+            attribute.map_err(|error| syntax_error(input, &error.to_string(), offset))?;
+        let value = attribute
+            .normalized_value(XmlVersion::Implicit1_0)
+            .map_err(|error| syntax_error(input, &error.to_string(), offset))?;
+        attributes.push((attribute.key.as_ref().to_owned(), value.into_owned()));
+    }
+    Ok(Element {
+        name,
+        attributes,
+        children: Vec::new(),
+        text: String::new(),
+        offset,
+    })
+}
+
+impl Interpreter<'_> {
+    fn error<T>(&self, message: &str, element: &Element) -> Result<T, SyntaxError> {
+        Err(syntax_error(self.input, message, element.offset))
+    }
+
+    fn results(&self, root: &Element) -> Result<QueryResults, SyntaxError> {
+        if root.name != "sparql" {
+            return self.error("expected a <sparql> element", root);
+        }
+
+        if let Some(boolean) = root.child("boolean") {
+            return match boolean.text.trim() {
+                "true" => Ok(QueryResults::Boolean(true)),
+                "false" => Ok(QueryResults::Boolean(false)),
+                _ => self.error("<boolean> must contain true or false", boolean),
+            };
+        }
+
+        let mut variables = Vec::new();
+        if let Some(head) = root.child("head") {
+            for child in head.children.iter().filter(|c| c.name == "variable") {
+                match child.attribute("name") {
+                    Some(name) => variables.push(Variable::new(name)),
+                    None => return self.error("<variable> needs a name", child),
+                }
+            }
+        }
+
+        let Some(results) = root.child("results") else {
+            return self.error("expected <results> or <boolean>", root);
+        };
+        let mut solutions = Vec::new();
+        for result in results.children.iter().filter(|c| c.name == "result") {
+            let mut solution = Bindings::new();
+            for binding in result.children.iter().filter(|c| c.name == "binding") {
+                let Some(name) = binding.attribute("name") else {
+                    return self.error("<binding> needs a name", binding);
+                };
+                solution.bind(&Variable::new(name), &self.single_term(binding)?);
+            }
+            solutions.push(solution);
+        }
+
+        Ok(QueryResults::Solutions {
+            variables,
+            solutions,
+        })
+    }
+
+    fn single_term(&self, parent: &Element) -> Result<Term, SyntaxError> {
+        match parent.children.as_slice() {
+            [term] => self.term(term),
+            _ => self.error("expected exactly one RDF term", parent),
+        }
+    }
+
+    fn term(&self, element: &Element) -> Result<Term, SyntaxError> {
+        match element.name.as_str() {
+            "uri" => Ok(NamedNode::new(&element.text).to_term()),
+            "bnode" => Ok(BlankNode::new(&element.text).to_term()),
+            "literal" => {
+                let direction = match element.attribute("its:dir") {
+                    Some(direction) => match direction.parse::<LanguageDirection>() {
+                        Ok(direction) => Some(direction),
+                        Err(error) => return self.error(&error.to_string(), element),
+                    },
+                    None => None,
+                };
+                let datatype = element
+                    .attribute("datatype")
+                    .filter(|datatype| {
+                        *datatype != rdf::langString.value()
+                            && *datatype != rdf::dirLangString.value()
+                    })
+                    .map(NamedNode::new);
+                Ok(Literal::new(
+                    &element.text,
+                    element.attribute("xml:lang"),
+                    direction.as_ref(),
+                    datatype.as_ref(),
+                )
+                .to_term())
+            }
+            "triple" => {
+                let part = |name: &str| match element.child(name) {
+                    Some(part) => self.single_term(part),
+                    None => self.error(&format!("<triple> needs a <{name}>"), element),
+                };
+                let Ok(subject) = QuadSubject::try_from(part("subject")?) else {
+                    return self.error("invalid subject in triple term", element);
+                };
+                let Ok(predicate) = QuadPredicate::try_from(part("predicate")?) else {
+                    return self.error("invalid predicate in triple term", element);
+                };
+                let Ok(object) = QuadObject::try_from(part("object")?) else {
+                    return self.error("invalid object in triple term", element);
+                };
+                Ok(Term::Quad(Box::new(Quad::new(
+                    &subject, &predicate, &object, None,
+                ))))
+            }
+            name => self.error(&format!("unknown RDF term <{name}>"), element),
+        }
+    }
+}
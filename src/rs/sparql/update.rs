@@ -0,0 +1,593 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Display;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::canonical::{blank_nodes, quad_terms};
+use crate::rs::dataset::Dataset;
+use crate::rs::default_graph::DefaultGraph;
+use crate::rs::named_node::NamedNode;
+use crate::rs::parse_error::ParseError;
+use crate::rs::pattern::{Bindings, substitute};
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::sparql::algebra::{GraphTarget, Update, UpdateOperation};
+use crate::rs::sparql::eval::{EvaluationError, evaluate_pattern, graphs_of, named_graphs};
+use crate::rs::term_like::TermLike;
+use crate::rs::turtle::{TurtleSyntax, parse_str};
+
+#[derive(Debug)]
+pub enum UpdateError {
+    Evaluation(EvaluationError),
+    /// `LOAD` only reads local files, i.e. `file:` IRIs.
+    UnsupportedSource(NamedNode),
+    Load(ParseError),
+    /// `CREATE GRAPH` of a graph holding quads.
+    GraphExists(NamedNode),
+}
+
+impl Display for UpdateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdateError::Evaluation(error) => write!(f, "{error}"),
+            UpdateError::UnsupportedSource(source) => {
+                write!(
+                    f,
+                    "cannot load <{}>, only file: IRIs are supported",
+                    source.value()
+                )
+            }
+            UpdateError::Load(error) => write!(f, "{error}"),
+            UpdateError::GraphExists(graph) => {
+                write!(f, "graph <{}> already exists", graph.value())
+            }
+        }
+    }
+}
+
+impl Error for UpdateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UpdateError::Evaluation(error) => Some(error),
+            UpdateError::Load(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<EvaluationError> for UpdateError {
+    fn from(error: EvaluationError) -> Self {
+        UpdateError::Evaluation(error)
+    }
+}
+
+/// Applies the operations of `update` to `dataset` in order.
+///
+/// The request is atomic: if an operation fails, `dataset` is left as it
+/// was before the request. Graphs exist as long as they hold quads, so
+/// `CREATE` and `DROP` only check for and remove quads.
+pub fn execute_update(dataset: &mut Dataset, update: &Update) -> Result<(), UpdateError> {
+    let mut updated = dataset.clone();
+    let mut executor = Executor {
+        dataset: &mut updated,
+        base_iri: update.base_iri.as_deref(),
+        blank_node_count: 0,
+        used_blank_nodes: None,
+    };
+    for operation in &update.operations {
+        executor.execute(operation)?;
+    }
+
+    *dataset = updated;
+    Ok(())
+}
+
+struct Executor<'a> {
+    dataset: &'a mut Dataset,
+    base_iri: Option<&'a str>,
+    blank_node_count: usize,
+    /// Blank node labels of the dataset and of fresh nodes, collected on
+    /// first use.
+    used_blank_nodes: Option<HashSet<String>>,
+}
+
+fn default_graph() -> QuadGraph {
+    QuadGraph::DefaultGraph(DefaultGraph::new())
+}
+
+fn is_ground(quad: &Quad) -> bool {
+    let subject = match quad.subject() {
+        QuadSubject::Variable(_) => false,
+        QuadSubject::Quad(inner) => is_ground(inner),
+        _ => true,
+    };
+    subject
+        && !matches!(quad.predicate(), QuadPredicate::Variable(_))
+        && !matches!(quad.object(), QuadObject::Variable(_))
+        && !matches!(quad.graph(), QuadGraph::Variable(_))
+}
+
+impl Executor<'_> {
+    fn execute(&mut self, operation: &UpdateOperation) -> Result<(), UpdateError> {
+        match operation {
+            UpdateOperation::InsertData { data } => {
+                let mut blank_nodes = HashMap::new();
+                let quads: Vec<Quad> = data
+                    .iter()
+                    .map(|quad| self.with_fresh_blank_nodes(quad, &mut blank_nodes))
+                    .collect();
+                for quad in &quads {
+                    self.dataset.insert(quad);
+                }
+            }
+            UpdateOperation::DeleteData { data } => {
+                for quad in data {
+                    self.dataset.remove(quad);
+                }
+            }
+            UpdateOperation::DeleteInsert {
+                with,
+                delete,
+                insert,
+                using,
+                pattern,
+            } => {
+                let (default_graphs, named) = match (using, with) {
+                    (Some(using), _) => graphs_of(using),
+                    (None, Some(with)) => (
+                        vec![QuadGraph::NamedNode(with.clone())],
+                        named_graphs(self.dataset),
+                    ),
+                    (None, None) => (vec![default_graph()], named_graphs(self.dataset)),
+                };
+                let solutions =
+                    evaluate_pattern(self.dataset, pattern, default_graphs, named, self.base_iri)?;
+                let target = with
+                    .as_ref()
+                    .map_or_else(default_graph, |with| QuadGraph::NamedNode(with.clone()));
+
+                let mut deleted = Vec::new();
+                let mut inserted = Vec::new();
+                for solution in &solutions {
+                    deleted.extend(instantiate(delete, solution, &target));
+                    let mut blank_nodes = HashMap::new();
+                    let template: Vec<Quad> = insert
+                        .iter()
+                        .map(|quad| self.with_fresh_blank_nodes(quad, &mut blank_nodes))
+                        .collect();
+                    inserted.extend(instantiate(&template, solution, &target));
+                }
+
+                for quad in &deleted {
+                    self.dataset.remove(quad);
+                }
+                for quad in &inserted {
+                    self.dataset.insert(quad);
+                }
+            }
+            UpdateOperation::Load {
+                silent,
+                source,
+                destination,
+            } => match self.load(source, destination) {
+                Err(_) if *silent => {}
+                result => result?,
+            },
+            UpdateOperation::Clear { target, .. } | UpdateOperation::Drop { target, .. } => {
+                for graph in self.target_graphs(target) {
+                    self.clear(&graph);
+                }
+            }
+            UpdateOperation::Create { silent, graph } => {
+                if !silent && !self.quads(&QuadGraph::NamedNode(graph.clone())).is_empty() {
+                    return Err(UpdateError::GraphExists(graph.clone()));
+                }
+            }
+            UpdateOperation::Add {
+                source,
+                destination,
+                ..
+            } => self.add(source, destination),
+            UpdateOperation::Copy {
+                source,
+                destination,
+                ..
+            } => {
+                if source != destination {
+                    self.clear(destination);
+                    self.add(source, destination);
+                }
+            }
+            UpdateOperation::Move {
+                source,
+                destination,
+                ..
+            } => {
+                if source != destination {
+                    self.clear(destination);
+                    self.add(source, destination);
+                    self.clear(source);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn quads(&self, graph: &QuadGraph) -> Vec<Quad> {
+        self.dataset
+            .quads_for_pattern(None, None, None, Some(graph))
+            .collect()
+    }
+
+    fn clear(&mut self, graph: &QuadGraph) {
+        for quad in self.quads(graph) {
+            self.dataset.remove(&quad);
+        }
+    }
+
+    fn add(&mut self, source: &QuadGraph, destination: &QuadGraph) {
+        for quad in self.quads(source) {
+            self.dataset.insert(&Quad::new(
+                quad.subject(),
+                quad.predicate(),
+                quad.object(),
+                Some(destination),
+            ));
+        }
+    }
+
+    fn target_graphs(&self, target: &GraphTarget) -> Vec<QuadGraph> {
+        match target {
+            GraphTarget::NamedNode(named_node) => vec![QuadGraph::NamedNode(named_node.clone())],
+            GraphTarget::DefaultGraph => vec![default_graph()],
+            GraphTarget::Named => named_graphs(self.dataset),
+            GraphTarget::All => self.dataset.graphs(),
+        }
+    }
+
+    /// Reads a Turtle, N-Triples or (by its `.trig` or `.nq` extension) TriG
+    /// or N-Quads file. Triples go to `destination`, quads of named graphs
+    /// keep their graph.
+    fn load(&mut self, source: &NamedNode, destination: &QuadGraph) -> Result<(), UpdateError> {
+        let Some(path) = file_path(source.value()) else {
+            return Err(UpdateError::UnsupportedSource(source.clone()));
+        };
+        let syntax = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("trig") => TurtleSyntax::TriG,
+            Some("nq") => TurtleSyntax::NQuads,
+            _ => TurtleSyntax::Turtle,
+        };
+
+        let input = fs::read_to_string(&path).map_err(|error| UpdateError::Load(error.into()))?;
+        let quads = parse_str(syntax, &input, Some(source.value()))
+            .map_err(|error| UpdateError::Load(error.into()))?;
+
+        let mut blank_nodes = HashMap::new();
+        for quad in &quads {
+            let quad = self.with_fresh_blank_nodes(quad, &mut blank_nodes);
+            let graph = match quad.graph() {
+                QuadGraph::DefaultGraph(_) => destination,
+                graph => graph,
+            };
+            self.dataset.insert(&Quad::new(
+                quad.subject(),
+                quad.predicate(),
+                quad.object(),
+                Some(graph),
+            ));
+        }
+        Ok(())
+    }
+
+    /// A blank node label used nowhere in the dataset, triple terms
+    /// included.
+    fn fresh_blank_node(&mut self) -> BlankNode {
+        let dataset = &*self.dataset;
+        let used = self.used_blank_nodes.get_or_insert_with(|| {
+            let mut labels = Vec::new();
+            for quad in dataset.iter() {
+                for term in quad_terms(&quad) {
+                    blank_nodes(&term, &mut labels);
+                }
+            }
+            labels.into_iter().collect()
+        });
+        loop {
+            self.blank_node_count += 1;
+            let label = format!("b{}", self.blank_node_count);
+            if used.insert(label.clone()) {
+                return BlankNode::new(&label);
+            }
+        }
+    }
+
+    /// Replaces the blank nodes of `quad`, including those in triple terms,
+    /// with fresh ones, consistently through `blank_nodes`.
+    fn with_fresh_blank_nodes(
+        &mut self,
+        quad: &Quad,
+        blank_nodes: &mut HashMap<String, BlankNode>,
+    ) -> Quad {
+        let subject = match quad.subject() {
+            QuadSubject::BlankNode(blank_node) => {
+                QuadSubject::BlankNode(self.fresh_for(blank_node, blank_nodes))
+            }
+            QuadSubject::Quad(triple) => {
+                QuadSubject::Quad(Box::new(self.with_fresh_blank_nodes(triple, blank_nodes)))
+            }
+            subject => subject.clone(),
+        };
+        let object = match quad.object() {
+            QuadObject::BlankNode(blank_node) => {
+                QuadObject::BlankNode(self.fresh_for(blank_node, blank_nodes))
+            }
+            object => object.clone(),
+        };
+        let graph = match quad.graph() {
+            QuadGraph::BlankNode(blank_node) => {
+                QuadGraph::BlankNode(self.fresh_for(blank_node, blank_nodes))
+            }
+            graph => graph.clone(),
+        };
+        Quad::new(&subject, quad.predicate(), &object, Some(&graph))
+    }
+
+    fn fresh_for(
+        &mut self,
+        blank_node: &BlankNode,
+        blank_nodes: &mut HashMap<String, BlankNode>,
+    ) -> BlankNode {
+        if let Some(fresh) = blank_nodes.get(blank_node.value()) {
+            return fresh.clone();
+        }
+        let fresh = self.fresh_blank_node();
+        blank_nodes.insert(blank_node.value().to_owned(), fresh.clone());
+        fresh
+    }
+}
+
+/// The local path of a `file:` IRI, percent-decoded.
+pub(crate) fn file_path(iri: &str) -> Option<PathBuf> {
+    let path = iri.strip_prefix("file://")?;
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+/// The `file:` IRI of `path`, which [`file_path`] reads back.
+#[cfg(any(feature = "server", test))]
+pub(crate) fn file_iri(path: &std::path::Path) -> String {
+    let mut iri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            iri.push(char::from(byte));
+        } else {
+            iri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    iri
+}
+
+/// The ground quads of `template` for one solution, quads of the default
+/// graph going to `target`.
+fn instantiate(template: &[Quad], solution: &Bindings, target: &QuadGraph) -> Vec<Quad> {
+    template
+        .iter()
+        .map(|quad| {
+            let graph = match quad.graph() {
+                QuadGraph::DefaultGraph(_) => target,
+                graph => graph,
+            };
+            substitute(
+                &Quad::new(quad.subject(), quad.predicate(), quad.object(), Some(graph)),
+                solution,
+            )
+        })
+        .filter(is_ground)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::sparql::parse_update;
+
+    const PREFIX: &str = "PREFIX ex: <http://example.org/> ";
+
+    fn update(dataset: &mut Dataset, update: &str) -> Result<(), UpdateError> {
+        let update = parse_update(&format!("{PREFIX}{update}"), None).unwrap();
+        execute_update(dataset, &update)
+    }
+
+    fn graph(name: &str) -> QuadGraph {
+        QuadGraph::NamedNode(NamedNode::new(&format!("http://example.org/{name}")))
+    }
+
+    fn count(dataset: &Dataset, graph: &QuadGraph) -> usize {
+        dataset.count_for_pattern(None, None, None, Some(graph))
+    }
+
+    #[test]
+    fn inserts_and_deletes_data() {
+        let mut dataset = Dataset::new();
+        update(
+            &mut dataset,
+            "INSERT DATA { ex:a ex:p ex:b, _:x . _:x ex:q 1 . GRAPH ex:g { ex:a ex:p ex:c } }",
+        )
+        .unwrap();
+        assert_eq!(dataset.len(), 4);
+        assert_eq!(count(&dataset, &graph("g")), 1);
+
+        // Blank nodes of different requests are distinct.
+        update(&mut dataset, "INSERT DATA { _:x ex:q 1 }").unwrap();
+        assert_eq!(dataset.len(), 5);
+
+        update(
+            &mut dataset,
+            "DELETE DATA { ex:a ex:p ex:b . GRAPH ex:g { ex:a ex:p ex:c } }",
+        )
+        .unwrap();
+        assert_eq!(dataset.len(), 3);
+        assert_eq!(count(&dataset, &graph("g")), 0);
+    }
+
+    #[test]
+    fn deletes_and_inserts_matches() {
+        let mut dataset = Dataset::new();
+        update(
+            &mut dataset,
+            "INSERT DATA { GRAPH ex:g { ex:a ex:age 1 . ex:b ex:age 2 } ex:c ex:age 3 }",
+        )
+        .unwrap();
+
+        update(
+            &mut dataset,
+            "WITH ex:g DELETE { ?s ex:age ?age } INSERT { ?s ex:age ?next } WHERE { ?s ex:age ?age BIND(?age + 10 AS ?next) }",
+        )
+        .unwrap();
+        let ages: Vec<String> = dataset
+            .quads_for_pattern(None, None, None, Some(&graph("g")))
+            .map(|quad| quad.object().value().to_owned())
+            .collect();
+        assert_eq!(ages.len(), 2);
+        assert!(ages.contains(&"11".to_owned()) && ages.contains(&"12".to_owned()));
+
+        update(&mut dataset, "DELETE WHERE { ?s ex:age 3 }").unwrap();
+        assert_eq!(dataset.len(), 2);
+
+        update(
+            &mut dataset,
+            "INSERT { GRAPH ex:copy { ?s ex:seen [] } } WHERE { GRAPH ?g { ?s ?p ?o } }",
+        )
+        .unwrap();
+        assert_eq!(count(&dataset, &graph("copy")), 2);
+    }
+
+    #[test]
+    fn manages_graphs() {
+        let mut dataset = Dataset::new();
+        update(
+            &mut dataset,
+            "INSERT DATA { ex:a ex:p 1 . GRAPH ex:g { ex:a ex:p 2 } GRAPH ex:h { ex:a ex:p 3 } }",
+        )
+        .unwrap();
+
+        update(&mut dataset, "ADD ex:g TO ex:h").unwrap();
+        assert_eq!(count(&dataset, &graph("h")), 2);
+        update(&mut dataset, "COPY DEFAULT TO ex:h").unwrap();
+        assert_eq!(count(&dataset, &graph("h")), 1);
+        update(&mut dataset, "MOVE ex:g TO ex:i").unwrap();
+        assert_eq!(count(&dataset, &graph("g")), 0);
+        assert_eq!(count(&dataset, &graph("i")), 1);
+
+        assert!(matches!(
+            update(&mut dataset, "CREATE GRAPH ex:i"),
+            Err(UpdateError::GraphExists(_))
+        ));
+        update(
+            &mut dataset,
+            "CREATE SILENT GRAPH ex:i ; CREATE GRAPH ex:new",
+        )
+        .unwrap();
+
+        update(&mut dataset, "CLEAR NAMED").unwrap();
+        assert_eq!(dataset.len(), 1);
+        update(&mut dataset, "DROP ALL").unwrap();
+        assert!(dataset.is_empty());
+    }
+
+    #[test]
+    fn blank_nodes_in_triple_terms_are_renamed() {
+        let ex = |name: &str| NamedNode::new(&format!("http://example.org/{name}"));
+        let triple = |subject: BlankNode| {
+            QuadSubject::Quad(Box::new(Quad::new(
+                &QuadSubject::BlankNode(subject),
+                &QuadPredicate::NamedNode(ex("p")),
+                &QuadObject::NamedNode(ex("o")),
+                None,
+            )))
+        };
+        let quoted = |subject: BlankNode| {
+            Quad::new(
+                &triple(subject),
+                &QuadPredicate::NamedNode(ex("q")),
+                &QuadObject::NamedNode(ex("r")),
+                None,
+            )
+        };
+
+        // b1 only appears inside a triple term.
+        let mut dataset = Dataset::new();
+        dataset.insert(&quoted(BlankNode::new("b1")));
+        update(
+            &mut dataset,
+            "INSERT DATA { << _:b ex:p ex:o >> ex:q ex:r . _:b ex:s 1 }",
+        )
+        .unwrap();
+        assert_eq!(dataset.len(), 3);
+
+        let s = QuadPredicate::NamedNode(ex("s"));
+        let inserted = dataset
+            .quads_for_pattern(None, Some(&s), None, None)
+            .next()
+            .unwrap();
+        let QuadSubject::BlankNode(fresh) = inserted.subject() else {
+            panic!("expected a blank node");
+        };
+        assert_ne!(fresh.value(), "b1");
+        assert_ne!(fresh.value(), "b");
+        assert!(dataset.contains(&quoted(fresh.clone())));
+    }
+
+    #[test]
+    fn loads_files_atomically() {
+        let path = std::env::temp_dir().join(format!("update load {}.ttl", std::process::id()));
+        fs::write(
+            &path,
+            "<http://example.org/a> <http://example.org/p> [ <http://example.org/q> 1 ] .",
+        )
+        .unwrap();
+        let source = file_iri(&path);
+        assert!(source.contains("%20"));
+        assert_eq!(file_path(&source), Some(path.clone()));
+
+        let mut dataset = Dataset::new();
+        update(&mut dataset, &format!("LOAD <{source}> INTO GRAPH ex:g")).unwrap();
+        assert_eq!(count(&dataset, &graph("g")), 2);
+
+        let result = update(
+            &mut dataset,
+            &format!("INSERT DATA {{ ex:a ex:p 1 }} ; LOAD <{source}.missing>"),
+        );
+        assert!(matches!(result, Err(UpdateError::Load(ParseError::Io(_)))));
+        assert_eq!(dataset.len(), 2);
+
+        update(&mut dataset, "LOAD SILENT <http://example.org/remote>").unwrap();
+        assert!(matches!(
+            update(&mut dataset, "LOAD <http://example.org/remote>"),
+            Err(UpdateError::UnsupportedSource(_))
+        ));
+
+        fs::remove_file(path).unwrap();
+    }
+}
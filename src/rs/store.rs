@@ -0,0 +1,850 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+
+use redb::{
+    Database, Range, ReadableDatabase, ReadableTable, ReadableTableMetadata, Table,
+    TableDefinition, WriteTransaction,
+};
+
+use crate::rs::blank_node::BlankNode;
+use crate::rs::dataset::{
+    GRAPH, INDEX_ORDERS, OBJECT, PREDICATE, SUBJECT, choose_index, permute, unpermute,
+};
+use crate::rs::default_graph::DefaultGraph;
+use crate::rs::literal::{LanguageDirection, Literal};
+use crate::rs::named_node::NamedNode;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::stream::{ImportError, Quads, Sink, Source, Store};
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::variable::Variable;
+
+type EncodedQuad = [u64; 4];
+
+const TERMS: TableDefinition<u64, &[u8]> = TableDefinition::new("terms");
+const IDS: TableDefinition<&[u8], u64> = TableDefinition::new("ids");
+/// One table per entry of [`INDEX_ORDERS`], keyed by the permuted quad.
+const INDEXES: [TableDefinition<EncodedQuad, ()>; 4] = [
+    TableDefinition::new("spog"),
+    TableDefinition::new("posg"),
+    TableDefinition::new("ospg"),
+    TableDefinition::new("gspo"),
+];
+
+/// Set of quads persisted in a single file, indexed like a
+/// [`Dataset`](crate::rs::dataset::Dataset) with SPOG/POSG/OSPG/GSPO B-trees
+/// over dictionary-encoded terms.
+///
+/// Every change is an atomic and durable transaction. After a crash the file
+/// holds the last committed state, which [`QuadStore::open`] repairs if
+/// needed.
+pub struct QuadStore {
+    database: Database,
+}
+
+impl QuadStore {
+    /// Opens the store at `path`, creating it if the file does not exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        let database = Database::create(path)?;
+
+        // Opening the tables once creates them, so readers can rely on them.
+        let transaction = database.begin_write()?;
+        StoreTransaction::new(&transaction)?;
+        transaction.commit()?;
+
+        Ok(Self { database })
+    }
+
+    pub fn len(&self) -> Result<usize, StoreError> {
+        let transaction = self.database.begin_read()?;
+        Ok(transaction.open_table(INDEXES[0])?.len()? as usize)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, StoreError> {
+        Ok(self.len()? == 0)
+    }
+
+    pub fn contains(&self, quad: &Quad) -> Result<bool, StoreError> {
+        let transaction = self.database.begin_read()?;
+        match lookup(&transaction.open_table(IDS)?, quad)? {
+            Some(encoded) => Ok(transaction.open_table(INDEXES[0])?.get(encoded)?.is_some()),
+            None => Ok(false),
+        }
+    }
+
+    /// Distinct graphs holding at least one quad, including the default graph.
+    pub fn graphs(&self) -> Result<Vec<QuadGraph>, StoreError> {
+        let transaction = self.database.begin_read()?;
+        graphs(
+            &transaction.open_table(TERMS)?,
+            &transaction.open_table(INDEXES[3])?,
+        )
+    }
+
+    /// Iterates over the quads matching every given term (`None` matches
+    /// anything), like [`Dataset::quads_for_pattern`]. The iterator reads a
+    /// snapshot, so later changes to the store do not show up in it.
+    ///
+    /// [`Dataset::quads_for_pattern`]: crate::rs::dataset::Dataset::quads_for_pattern
+    pub fn quads_for_pattern(
+        &self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> Quads<'static, StoreError> {
+        let snapshot = || -> Result<Quads<'static, StoreError>, StoreError> {
+            let transaction = self.database.begin_read()?;
+            let Some(pattern) = encode_pattern(
+                &transaction.open_table(IDS)?,
+                subject,
+                predicate,
+                object,
+                graph,
+            )?
+            else {
+                return Ok(Box::new(std::iter::empty()));
+            };
+
+            let (index, range) = bounds(&pattern);
+            let terms = transaction.open_table(TERMS)?;
+            let range = transaction.open_table(INDEXES[index])?.range(range)?;
+            Ok(Box::new(
+                matching(range, index, pattern).map(move |encoded| decode(&terms, &encoded?)),
+            ))
+        };
+
+        snapshot().unwrap_or_else(|error| Box::new(std::iter::once(Err(error))))
+    }
+
+    /// Number of quads [`QuadStore::quads_for_pattern`] would return.
+    pub fn count_for_pattern(
+        &self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> Result<usize, StoreError> {
+        let transaction = self.database.begin_read()?;
+        let Some(pattern) = encode_pattern(
+            &transaction.open_table(IDS)?,
+            subject,
+            predicate,
+            object,
+            graph,
+        )?
+        else {
+            return Ok(0);
+        };
+
+        let (index, range) = bounds(&pattern);
+        let range = transaction.open_table(INDEXES[index])?.range(range)?;
+        matching(range, index, pattern).try_fold(0, |count, encoded| encoded.map(|_| count + 1))
+    }
+
+    /// Adds `quad` in a transaction of its own, returning whether it was not
+    /// present yet.
+    pub fn insert(&self, quad: &Quad) -> Result<bool, StoreError> {
+        self.transaction(|transaction| transaction.insert(quad))
+    }
+
+    /// Removes `quad` in a transaction of its own, returning whether it was
+    /// present.
+    pub fn remove(&self, quad: &Quad) -> Result<bool, StoreError> {
+        self.transaction(|transaction| transaction.remove(quad))
+    }
+
+    pub fn clear(&self) -> Result<(), StoreError> {
+        self.transaction(|transaction| transaction.clear())
+    }
+
+    /// Runs `f` in a write transaction that is committed if `f` returns `Ok`
+    /// and rolled back otherwise. Write transactions run one at a time, while
+    /// readers keep seeing the last committed state.
+    pub fn transaction<T, E: From<StoreError>>(
+        &self,
+        f: impl FnOnce(&mut StoreTransaction<'_>) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let transaction = self.database.begin_write().map_err(StoreError::from)?;
+        let result = f(&mut StoreTransaction::new(&transaction)?)?;
+        transaction.commit().map_err(StoreError::from)?;
+        Ok(result)
+    }
+}
+
+/// Changes to a [`QuadStore`] made within [`QuadStore::transaction`], which
+/// its reads already take into account.
+pub struct StoreTransaction<'a> {
+    terms: Table<'a, u64, &'static [u8]>,
+    ids: Table<'a, &'static [u8], u64>,
+    indexes: [Table<'a, EncodedQuad, ()>; 4],
+}
+
+impl<'a> StoreTransaction<'a> {
+    fn new(transaction: &'a WriteTransaction) -> Result<Self, StoreError> {
+        let index = |i: usize| transaction.open_table(INDEXES[i]);
+        Ok(Self {
+            terms: transaction.open_table(TERMS)?,
+            ids: transaction.open_table(IDS)?,
+            indexes: [index(0)?, index(1)?, index(2)?, index(3)?],
+        })
+    }
+
+    pub fn len(&self) -> Result<usize, StoreError> {
+        Ok(self.indexes[0].len()? as usize)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, StoreError> {
+        Ok(self.len()? == 0)
+    }
+
+    pub fn contains(&self, quad: &Quad) -> Result<bool, StoreError> {
+        match lookup(&self.ids, quad)? {
+            Some(encoded) => Ok(self.indexes[0].get(encoded)?.is_some()),
+            None => Ok(false),
+        }
+    }
+
+    pub fn graphs(&self) -> Result<Vec<QuadGraph>, StoreError> {
+        graphs(&self.terms, &self.indexes[3])
+    }
+
+    pub fn quads_for_pattern(
+        &self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> Quads<'_, StoreError> {
+        let pattern = match encode_pattern(&self.ids, subject, predicate, object, graph) {
+            Ok(Some(pattern)) => pattern,
+            Ok(None) => return Box::new(std::iter::empty()),
+            Err(error) => return Box::new(std::iter::once(Err(error))),
+        };
+
+        let (index, range) = bounds(&pattern);
+        match self.indexes[index].range(range) {
+            Ok(range) => Box::new(
+                matching(range, index, pattern).map(move |encoded| decode(&self.terms, &encoded?)),
+            ),
+            Err(error) => Box::new(std::iter::once(Err(error.into()))),
+        }
+    }
+
+    /// Adds `quad`, returning whether it was not present yet.
+    pub fn insert(&mut self, quad: &Quad) -> Result<bool, StoreError> {
+        let encoded = [
+            self.encode(&quad.subject().to_term())?,
+            self.encode(&quad.predicate().to_term())?,
+            self.encode(&quad.object().to_term())?,
+            self.encode(&quad.graph().to_term())?,
+        ];
+
+        if self.indexes[0].insert(encoded, ())?.is_some() {
+            return Ok(false);
+        }
+        for (index, order) in self.indexes.iter_mut().zip(INDEX_ORDERS.iter()).skip(1) {
+            index.insert(permute(order, &encoded), ())?;
+        }
+        Ok(true)
+    }
+
+    /// Removes `quad`, returning whether it was present.
+    pub fn remove(&mut self, quad: &Quad) -> Result<bool, StoreError> {
+        let Some(encoded) = lookup(&self.ids, quad)? else {
+            return Ok(false);
+        };
+
+        if self.indexes[0].remove(encoded)?.is_none() {
+            return Ok(false);
+        }
+        for (index, order) in self.indexes.iter_mut().zip(INDEX_ORDERS.iter()).skip(1) {
+            index.remove(permute(order, &encoded))?;
+        }
+        Ok(true)
+    }
+
+    /// Removes all quads along with the term dictionary.
+    pub fn clear(&mut self) -> Result<(), StoreError> {
+        self.terms.retain(|_, _| false)?;
+        self.ids.retain(|_, _| false)?;
+        for index in &mut self.indexes {
+            index.retain(|_, _| false)?;
+        }
+        Ok(())
+    }
+
+    fn encode(&mut self, term: &Term) -> Result<u64, StoreError> {
+        let bytes = term_to_bytes(term);
+        if let Some(id) = self.ids.get(bytes.as_slice())? {
+            return Ok(id.value());
+        }
+
+        let id = self.terms.last()?.map_or(0, |(id, _)| id.value() + 1);
+        self.terms.insert(id, bytes.as_slice())?;
+        self.ids.insert(bytes.as_slice(), id)?;
+        Ok(id)
+    }
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Storage(redb::Error),
+    /// The file holds data this module did not write.
+    Corrupted(String),
+}
+
+impl Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreError::Storage(error) => write!(f, "{error}"),
+            StoreError::Corrupted(message) => write!(f, "corrupted quad store: {message}"),
+        }
+    }
+}
+
+impl Error for StoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StoreError::Storage(error) => Some(error),
+            StoreError::Corrupted(_) => None,
+        }
+    }
+}
+
+impl From<redb::Error> for StoreError {
+    fn from(error: redb::Error) -> Self {
+        StoreError::Storage(error)
+    }
+}
+
+impl From<redb::DatabaseError> for StoreError {
+    fn from(error: redb::DatabaseError) -> Self {
+        StoreError::Storage(error.into())
+    }
+}
+
+impl From<redb::TransactionError> for StoreError {
+    fn from(error: redb::TransactionError) -> Self {
+        StoreError::Storage(error.into())
+    }
+}
+
+impl From<redb::TableError> for StoreError {
+    fn from(error: redb::TableError) -> Self {
+        StoreError::Storage(error.into())
+    }
+}
+
+impl From<redb::StorageError> for StoreError {
+    fn from(error: redb::StorageError) -> Self {
+        StoreError::Storage(error.into())
+    }
+}
+
+impl From<redb::CommitError> for StoreError {
+    fn from(error: redb::CommitError) -> Self {
+        StoreError::Storage(error.into())
+    }
+}
+
+impl<S> From<StoreError> for ImportError<S, StoreError> {
+    fn from(error: StoreError) -> Self {
+        ImportError::Sink(error)
+    }
+}
+
+impl Source for QuadStore {
+    type Error = StoreError;
+
+    fn match_quads<'a>(
+        &'a self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> Quads<'a, StoreError> {
+        self.quads_for_pattern(subject, predicate, object, graph)
+    }
+}
+
+impl Sink for QuadStore {
+    type Error = StoreError;
+
+    /// Imports all quads in one transaction, so nothing is kept if either
+    /// side fails.
+    fn import<E>(
+        &mut self,
+        quads: impl IntoIterator<Item = Result<Quad, E>>,
+    ) -> Result<(), ImportError<E, StoreError>> {
+        self.transaction(|transaction| {
+            for quad in quads {
+                transaction.insert(&quad.map_err(ImportError::Source)?)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Store for QuadStore {
+    fn remove<E>(
+        &mut self,
+        quads: impl IntoIterator<Item = Result<Quad, E>>,
+    ) -> Result<(), ImportError<E, StoreError>> {
+        self.transaction(|transaction| {
+            for quad in quads {
+                transaction.remove(&quad.map_err(ImportError::Source)?)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn remove_matches(
+        &mut self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> Result<(), StoreError> {
+        self.transaction(|transaction| {
+            let matches = transaction
+                .quads_for_pattern(subject, predicate, object, graph)
+                .collect::<Result<Vec<_>, _>>()?;
+            for quad in &matches {
+                transaction.remove(quad)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+fn lookup(
+    ids: &impl ReadableTable<&'static [u8], u64>,
+    quad: &Quad,
+) -> Result<Option<EncodedQuad>, StoreError> {
+    let mut encoded = [0; 4];
+    for (id, term) in encoded.iter_mut().zip([
+        quad.subject().to_term(),
+        quad.predicate().to_term(),
+        quad.object().to_term(),
+        quad.graph().to_term(),
+    ]) {
+        match ids.get(term_to_bytes(&term).as_slice())? {
+            Some(found) => *id = found.value(),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(encoded))
+}
+
+/// Ids of the given terms, or `None` if one of them was never stored and so
+/// nothing can match.
+fn encode_pattern(
+    ids: &impl ReadableTable<&'static [u8], u64>,
+    subject: Option<&QuadSubject>,
+    predicate: Option<&QuadPredicate>,
+    object: Option<&QuadObject>,
+    graph: Option<&QuadGraph>,
+) -> Result<Option<[Option<u64>; 4]>, StoreError> {
+    let mut pattern = [None; 4];
+
+    for (position, term) in [
+        subject.map(|s| s.to_term()),
+        predicate.map(|p| p.to_term()),
+        object.map(|o| o.to_term()),
+        graph.map(|g| g.to_term()),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        if let Some(term) = term {
+            match ids.get(term_to_bytes(&term).as_slice())? {
+                Some(id) => pattern[position] = Some(id.value()),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    Ok(Some(pattern))
+}
+
+/// Index to scan for `pattern` and the range of its keys to read.
+fn bounds(pattern: &[Option<u64>; 4]) -> (usize, std::ops::RangeInclusive<EncodedQuad>) {
+    let (index, prefix_length) = choose_index(pattern);
+    let order = &INDEX_ORDERS[index];
+
+    let mut lower = [0; 4];
+    let mut upper = [u64::MAX; 4];
+    for i in 0..prefix_length {
+        let id = pattern[order[i]].unwrap();
+        lower[i] = id;
+        upper[i] = id;
+    }
+
+    (index, lower..=upper)
+}
+
+fn matching<'a>(
+    range: Range<'a, EncodedQuad, ()>,
+    index: usize,
+    pattern: [Option<u64>; 4],
+) -> impl Iterator<Item = Result<EncodedQuad, StoreError>> + 'a {
+    let order = &INDEX_ORDERS[index];
+    range
+        .map(move |entry| Ok(unpermute(order, &entry?.0.value())))
+        .filter(move |encoded| match encoded {
+            Ok(encoded) => pattern
+                .iter()
+                .zip(encoded.iter())
+                .all(|(expected, id)| expected.is_none_or(|expected| expected == *id)),
+            Err(_) => true,
+        })
+}
+
+fn graphs(
+    terms: &impl ReadableTable<u64, &'static [u8]>,
+    gspo: &impl ReadableTable<EncodedQuad, ()>,
+) -> Result<Vec<QuadGraph>, StoreError> {
+    let mut graphs = Vec::new();
+    let mut lower = [0; 4];
+
+    // Skips from each graph straight to the next one.
+    while let Some(entry) = gspo.range(lower..)?.next() {
+        let id = entry?.0.value()[0];
+        let graph = QuadGraph::try_from(term(terms, id)?)
+            .map_err(|_| StoreError::Corrupted(format!("term {id} is not a graph")))?;
+        graphs.push(graph);
+        if id == u64::MAX {
+            break;
+        }
+        lower = [id + 1, 0, 0, 0];
+    }
+
+    Ok(graphs)
+}
+
+fn term(terms: &impl ReadableTable<u64, &'static [u8]>, id: u64) -> Result<Term, StoreError> {
+    let Some(bytes) = terms.get(id)? else {
+        return Err(StoreError::Corrupted(format!("unknown term {id}")));
+    };
+    let mut bytes = bytes.value();
+    let term = term_from_bytes(&mut bytes)?;
+    if !bytes.is_empty() {
+        return Err(StoreError::Corrupted(format!(
+            "trailing bytes in term {id}"
+        )));
+    }
+    Ok(term)
+}
+
+fn decode(
+    terms: &impl ReadableTable<u64, &'static [u8]>,
+    encoded: &EncodedQuad,
+) -> Result<Quad, StoreError> {
+    let invalid = |position: &str| StoreError::Corrupted(format!("invalid {position} term"));
+
+    let subject =
+        QuadSubject::try_from(term(terms, encoded[SUBJECT])?).map_err(|_| invalid("subject"))?;
+    let predicate = QuadPredicate::try_from(term(terms, encoded[PREDICATE])?)
+        .map_err(|_| invalid("predicate"))?;
+    let object =
+        QuadObject::try_from(term(terms, encoded[OBJECT])?).map_err(|_| invalid("object"))?;
+    let graph = QuadGraph::try_from(term(terms, encoded[GRAPH])?).map_err(|_| invalid("graph"))?;
+
+    Ok(Quad::new(&subject, &predicate, &object, Some(&graph)))
+}
+
+const DEFAULT_GRAPH_TAG: u8 = 0;
+const NAMED_NODE_TAG: u8 = 1;
+const BLANK_NODE_TAG: u8 = 2;
+const LITERAL_TAG: u8 = 3;
+const VARIABLE_TAG: u8 = 4;
+const QUAD_TAG: u8 = 5;
+
+/// Dictionary key of `term`: a tag byte followed by its length-prefixed
+/// strings, or by its nested terms for quoted triples.
+fn term_to_bytes(term: &Term) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_term(term, &mut bytes);
+    bytes
+}
+
+fn write_term(term: &Term, bytes: &mut Vec<u8>) {
+    match term {
+        Term::DefaultGraph(_) => bytes.push(DEFAULT_GRAPH_TAG),
+        Term::NamedNode(named_node) => {
+            bytes.push(NAMED_NODE_TAG);
+            write_str(named_node.value(), bytes);
+        }
+        Term::BlankNode(blank_node) => {
+            bytes.push(BLANK_NODE_TAG);
+            write_str(blank_node.value(), bytes);
+        }
+        Term::Literal(literal) => {
+            bytes.push(LITERAL_TAG);
+            write_str(literal.value(), bytes);
+            write_str(literal.language(), bytes);
+            bytes.push(match literal.direction() {
+                None => 0,
+                Some(LanguageDirection::LeftToRight) => 1,
+                Some(LanguageDirection::RightToLeft) => 2,
+            });
+            write_str(literal.datatype().value(), bytes);
+        }
+        Term::Variable(variable) => {
+            bytes.push(VARIABLE_TAG);
+            write_str(variable.value(), bytes);
+        }
+        Term::Quad(quad) => {
+            bytes.push(QUAD_TAG);
+            write_term(&quad.subject().to_term(), bytes);
+            write_term(&quad.predicate().to_term(), bytes);
+            write_term(&quad.object().to_term(), bytes);
+            write_term(&quad.graph().to_term(), bytes);
+        }
+    }
+}
+
+fn write_str(value: &str, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+fn term_from_bytes(bytes: &mut &[u8]) -> Result<Term, StoreError> {
+    let Some((&tag, rest)) = bytes.split_first() else {
+        return Err(StoreError::Corrupted("truncated term".to_owned()));
+    };
+    *bytes = rest;
+
+    Ok(match tag {
+        DEFAULT_GRAPH_TAG => DefaultGraph::new().to_term(),
+        NAMED_NODE_TAG => NamedNode::new(read_str(bytes)?).to_term(),
+        BLANK_NODE_TAG => BlankNode::new(read_str(bytes)?).to_term(),
+        LITERAL_TAG => {
+            let value = read_str(bytes)?;
+            let language = read_str(bytes)?;
+            let direction = match read_bytes(bytes, 1)? {
+                [0] => None,
+                [1] => Some(LanguageDirection::LeftToRight),
+                [2] => Some(LanguageDirection::RightToLeft),
+                _ => return Err(StoreError::Corrupted("invalid direction".to_owned())),
+            };
+            let datatype = NamedNode::new(read_str(bytes)?);
+            let language = Some(language).filter(|language| !language.is_empty());
+            Literal::new(value, language, direction.as_ref(), Some(&datatype)).to_term()
+        }
+        VARIABLE_TAG => Variable::new(read_str(bytes)?).to_term(),
+        QUAD_TAG => {
+            let invalid = || StoreError::Corrupted("invalid quoted triple".to_owned());
+            let subject = QuadSubject::try_from(term_from_bytes(bytes)?).map_err(|_| invalid())?;
+            let predicate =
+                QuadPredicate::try_from(term_from_bytes(bytes)?).map_err(|_| invalid())?;
+            let object = QuadObject::try_from(term_from_bytes(bytes)?).map_err(|_| invalid())?;
+            let graph = QuadGraph::try_from(term_from_bytes(bytes)?).map_err(|_| invalid())?;
+            Term::Quad(Box::new(Quad::new(
+                &subject,
+                &predicate,
+                &object,
+                Some(&graph),
+            )))
+        }
+        tag => return Err(StoreError::Corrupted(format!("unknown term tag {tag}"))),
+    })
+}
+
+fn read_bytes<'a>(bytes: &mut &'a [u8], length: usize) -> Result<&'a [u8], StoreError> {
+    if bytes.len() < length {
+        return Err(StoreError::Corrupted("truncated term".to_owned()));
+    }
+    let (read, rest) = bytes.split_at(length);
+    *bytes = rest;
+    Ok(read)
+}
+
+fn read_str<'a>(bytes: &mut &'a [u8]) -> Result<&'a str, StoreError> {
+    let length = u32::from_le_bytes(read_bytes(bytes, 4)?.try_into().unwrap()) as usize;
+    std::str::from_utf8(read_bytes(bytes, length)?)
+        .map_err(|_| StoreError::Corrupted("term is not UTF-8".to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::rs::stream::ok_quads;
+    use crate::rs::turtle::{TurtleSyntax, parse_str};
+
+    const INPUT: &str = "@prefix ex: <http://example.org/> .\n\
+                         ex:a ex:p \"caf\u{e9}\"@fr--rtl, 1.5, _:b .\n\
+                         << ex:a ex:p ex:b >> ex:q ex:c .\n\
+                         ex:g { ex:a ex:p ex:b . ex:b ex:p ex:c . }\n";
+
+    /// Removes the file when dropped, even if the test fails.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("rdfjs-rust-{}-{name}.redb", std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn nn(value: &str) -> NamedNode {
+        NamedNode::new(&format!("http://example.org/{value}"))
+    }
+
+    #[test]
+    fn quads_persist_across_reopening() {
+        let file = TempFile::new("persist");
+        let quads = parse_str(TurtleSyntax::TriG, INPUT, None).unwrap();
+
+        {
+            let mut store = QuadStore::open(&file.0).unwrap();
+            store.import(ok_quads(quads.clone())).unwrap();
+            assert!(!store.insert(&quads[0]).unwrap());
+        }
+
+        let store = QuadStore::open(&file.0).unwrap();
+        assert_eq!(store.len().unwrap(), quads.len());
+        for quad in &quads {
+            assert!(store.contains(quad).unwrap(), "{quad:?}");
+        }
+
+        let a = QuadSubject::NamedNode(nn("a"));
+        let p = QuadPredicate::NamedNode(nn("p"));
+        let g = QuadGraph::NamedNode(nn("g"));
+        assert_eq!(
+            store.count_for_pattern(Some(&a), None, None, None).unwrap(),
+            4
+        );
+        assert_eq!(
+            store
+                .count_for_pattern(None, Some(&p), None, Some(&g))
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            store
+                .quads_for_pattern(Some(&a), Some(&p), None, Some(&g))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![quads[4].clone()]
+        );
+        let unknown = QuadSubject::NamedNode(nn("unknown"));
+        assert_eq!(
+            store
+                .count_for_pattern(Some(&unknown), None, None, None)
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            store.graphs().unwrap(),
+            vec![QuadGraph::DefaultGraph(DefaultGraph::new()), g.clone()]
+        );
+
+        assert!(store.remove(&quads[4]).unwrap());
+        assert!(!store.remove(&quads[4]).unwrap());
+        assert_eq!(store.len().unwrap(), quads.len() - 1);
+    }
+
+    #[test]
+    fn transactions_are_atomic() {
+        let file = TempFile::new("atomic");
+        let mut store = QuadStore::open(&file.0).unwrap();
+        let quads = parse_str(TurtleSyntax::TriG, INPUT, None).unwrap();
+        store.import(ok_quads(quads.clone())).unwrap();
+        let snapshot = store.quads_for_pattern(None, None, None, None);
+
+        let result: Result<(), StoreError> = store.transaction(|transaction| {
+            transaction.clear()?;
+            assert!(transaction.is_empty()?);
+            Err(StoreError::Corrupted("rolled back".to_owned()))
+        });
+        assert!(result.is_err());
+        assert_eq!(store.len().unwrap(), quads.len());
+
+        let failing = quads.iter().cloned().map(Ok).chain([Err("broken input")]);
+        assert!(matches!(
+            Store::remove(&mut store, failing),
+            Err(ImportError::Source("broken input"))
+        ));
+        assert_eq!(store.len().unwrap(), quads.len());
+
+        store.delete_graph(&QuadGraph::NamedNode(nn("g"))).unwrap();
+        assert_eq!(store.len().unwrap(), quads.len() - 2);
+        store.clear().unwrap();
+        assert!(store.is_empty().unwrap());
+        assert_eq!(snapshot.count(), quads.len());
+    }
+
+    #[test]
+    fn stores_reopen_after_unfinished_transactions() {
+        let file = TempFile::new("reopen");
+        let quads = parse_str(TurtleSyntax::TriG, INPUT, None).unwrap();
+        let extra = Quad::new(
+            &QuadSubject::NamedNode(nn("new")),
+            &QuadPredicate::NamedNode(nn("p")),
+            &QuadObject::NamedNode(nn("newer")),
+            None,
+        );
+
+        {
+            let mut store = QuadStore::open(&file.0).unwrap();
+            store.import(ok_quads(quads.clone())).unwrap();
+
+            let result: Result<(), StoreError> = store.transaction(|transaction| {
+                transaction.insert(&extra)?;
+                transaction.remove(&quads[0])?;
+                Err(StoreError::Corrupted("aborted".to_owned()))
+            });
+            assert!(result.is_err());
+
+            // A panic drops the transaction without committing it.
+            let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                store.transaction(|transaction| -> Result<(), StoreError> {
+                    transaction.clear()?;
+                    panic!("dropped");
+                })
+            }));
+            assert!(panicked.is_err());
+        }
+
+        {
+            let store = QuadStore::open(&file.0).unwrap();
+            assert_eq!(store.len().unwrap(), quads.len());
+            for quad in &quads {
+                assert!(store.contains(quad).unwrap(), "{quad:?}");
+            }
+            assert!(!store.contains(&extra).unwrap());
+            let new = QuadSubject::NamedNode(nn("new"));
+            assert_eq!(
+                store
+                    .count_for_pattern(Some(&new), None, None, None)
+                    .unwrap(),
+                0
+            );
+
+            // The terms of the aborted writes can be added again.
+            assert!(store.insert(&extra).unwrap());
+        }
+
+        let store = QuadStore::open(&file.0).unwrap();
+        assert_eq!(store.len().unwrap(), quads.len() + 1);
+        assert!(store.contains(&extra).unwrap());
+        assert!(store.contains(&quads[0]).unwrap());
+    }
+}
@@ -0,0 +1,471 @@
+use std::cell::OnceCell;
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+use crate::rs::dataset::Dataset;
+use crate::rs::parse_error::SyntaxError;
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::turtle::{TurtleSerializer, TurtleSyntax, parse_str};
+
+/// Fallible iterator of quads, the counterpart of an RDF/JS `Stream`.
+pub type Quads<'a, E> = Box<dyn Iterator<Item = Result<Quad, E>> + 'a>;
+
+/// Mirrors the RDF/JS `Source` interface.
+pub trait Source {
+    type Error;
+
+    /// Quads matching every given term, `None` matching anything, like
+    /// RDF/JS `Source.match`.
+    fn match_quads<'a>(
+        &'a self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> Quads<'a, Self::Error>;
+}
+
+/// Mirrors the RDF/JS `Sink` interface.
+pub trait Sink {
+    type Error;
+
+    /// Consumes `quads` up to the first error of either side, like RDF/JS
+    /// `Sink.import`.
+    fn import<E>(
+        &mut self,
+        quads: impl IntoIterator<Item = Result<Quad, E>>,
+    ) -> Result<(), ImportError<E, Self::Error>>;
+}
+
+/// Mirrors the RDF/JS `Store` interface.
+pub trait Store: Source + Sink {
+    fn remove<E>(
+        &mut self,
+        quads: impl IntoIterator<Item = Result<Quad, E>>,
+    ) -> Result<(), ImportError<E, <Self as Sink>::Error>>;
+
+    fn remove_matches(
+        &mut self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> Result<(), <Self as Sink>::Error>;
+
+    fn delete_graph(&mut self, graph: &QuadGraph) -> Result<(), <Self as Sink>::Error> {
+        self.remove_matches(None, None, None, Some(graph))
+    }
+}
+
+/// Failure of [`Sink::import`] or [`Store::remove`], either while reading
+/// the incoming quads or while consuming them.
+#[derive(Debug)]
+pub enum ImportError<S, K> {
+    Source(S),
+    Sink(K),
+}
+
+impl<S: Display, K: Display> Display for ImportError<S, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::Source(error) => write!(f, "{error}"),
+            ImportError::Sink(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<S: Error + 'static, K: Error + 'static> Error for ImportError<S, K> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ImportError::Source(error) => Some(error),
+            ImportError::Sink(error) => Some(error),
+        }
+    }
+}
+
+/// Infallible quads, e.g. to [`Sink::import`] a plain collection.
+pub fn ok_quads<I: IntoIterator<Item = Quad>>(
+    quads: I,
+) -> impl Iterator<Item = Result<Quad, Infallible>> {
+    quads.into_iter().map(Ok)
+}
+
+impl Source for Dataset {
+    type Error = Infallible;
+
+    fn match_quads<'a>(
+        &'a self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> Quads<'a, Infallible> {
+        Box::new(
+            self.quads_for_pattern(subject, predicate, object, graph)
+                .map(Ok),
+        )
+    }
+}
+
+impl Sink for Dataset {
+    type Error = Infallible;
+
+    fn import<E>(
+        &mut self,
+        quads: impl IntoIterator<Item = Result<Quad, E>>,
+    ) -> Result<(), ImportError<E, Infallible>> {
+        for quad in quads {
+            self.insert(&quad.map_err(ImportError::Source)?);
+        }
+        Ok(())
+    }
+}
+
+impl Store for Dataset {
+    fn remove<E>(
+        &mut self,
+        quads: impl IntoIterator<Item = Result<Quad, E>>,
+    ) -> Result<(), ImportError<E, Infallible>> {
+        for quad in quads {
+            Dataset::remove(self, &quad.map_err(ImportError::Source)?);
+        }
+        Ok(())
+    }
+
+    fn remove_matches(
+        &mut self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> Result<(), Infallible> {
+        let matches: Vec<Quad> = self
+            .quads_for_pattern(subject, predicate, object, graph)
+            .collect();
+        for quad in &matches {
+            Dataset::remove(self, quad);
+        }
+        Ok(())
+    }
+}
+
+/// A Turtle or TriG document as a [`Source`].
+///
+/// The document is parsed into a [`Dataset`] on the first match, so matches
+/// see each quad once, and a document with a syntax error only yields that
+/// error.
+pub struct TurtleSource {
+    syntax: TurtleSyntax,
+    input: String,
+    base_iri: Option<String>,
+    dataset: OnceCell<Result<Dataset, SyntaxError>>,
+}
+
+impl TurtleSource {
+    pub fn new(syntax: TurtleSyntax, input: &str) -> Self {
+        Self {
+            syntax,
+            input: input.to_owned(),
+            base_iri: None,
+            dataset: OnceCell::new(),
+        }
+    }
+
+    pub fn with_base_iri(mut self, base_iri: &str) -> Self {
+        self.base_iri = Some(base_iri.to_owned());
+        self.dataset = OnceCell::new();
+        self
+    }
+
+    fn dataset(&self) -> &Result<Dataset, SyntaxError> {
+        self.dataset.get_or_init(|| {
+            let quads = parse_str(self.syntax, &self.input, self.base_iri.as_deref())?;
+            Ok(quads.into_iter().collect())
+        })
+    }
+}
+
+impl Source for TurtleSource {
+    type Error = SyntaxError;
+
+    fn match_quads<'a>(
+        &'a self,
+        subject: Option<&QuadSubject>,
+        predicate: Option<&QuadPredicate>,
+        object: Option<&QuadObject>,
+        graph: Option<&QuadGraph>,
+    ) -> Quads<'a, SyntaxError> {
+        match self.dataset() {
+            Ok(dataset) => Box::new(
+                dataset
+                    .quads_for_pattern(subject, predicate, object, graph)
+                    .map(Ok),
+            ),
+            Err(error) => Box::new(std::iter::once(Err(error.clone()))),
+        }
+    }
+}
+
+/// A [`TurtleSerializer`] writing every import to `W` as a [`Sink`].
+///
+/// Quads are written as they come, so only runs of quads sharing their graph
+/// and subject are grouped.
+pub struct TurtleSink<W> {
+    writer: W,
+    serializer: TurtleSerializer,
+}
+
+impl<W: Write> TurtleSink<W> {
+    pub fn new(writer: W, serializer: TurtleSerializer) -> Self {
+        Self { writer, serializer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Sink for TurtleSink<W> {
+    type Error = io::Error;
+
+    /// The quads written before an error of the incoming quads stay written,
+    /// as a complete document.
+    fn import<E>(
+        &mut self,
+        quads: impl IntoIterator<Item = Result<Quad, E>>,
+    ) -> Result<(), ImportError<E, io::Error>> {
+        let mut formatter = self.serializer.formatter();
+        let mut output = String::new();
+        let mut result = Ok(());
+        for quad in quads {
+            let quad = match quad {
+                Ok(quad) => quad,
+                Err(error) => {
+                    result = Err(ImportError::Source(error));
+                    break;
+                }
+            };
+            formatter
+                .format(&quad, &mut output)
+                .map_err(ImportError::Sink)?;
+            if output.len() >= 8192 {
+                self.writer
+                    .write_all(output.as_bytes())
+                    .map_err(ImportError::Sink)?;
+                output.clear();
+            }
+        }
+        formatter.finish(&mut output);
+        self.writer
+            .write_all(output.as_bytes())
+            .and_then(|()| self.writer.flush())
+            .map_err(ImportError::Sink)?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::default_graph::DefaultGraph;
+    use crate::rs::named_node::NamedNode;
+
+    const INPUT: &str = "@prefix ex: <http://example.org/> .\n\
+                         ex:a ex:p ex:b .\n\
+                         ex:g { ex:a ex:q ex:c . ex:b ex:p ex:c . }\n";
+
+    fn nn(value: &str) -> NamedNode {
+        NamedNode::new(&format!("http://example.org/{value}"))
+    }
+
+    #[test]
+    fn sources_pipe_into_sinks() {
+        let source = TurtleSource::new(TurtleSyntax::TriG, INPUT);
+        let subject = QuadSubject::NamedNode(nn("a"));
+        assert_eq!(
+            source.match_quads(Some(&subject), None, None, None).count(),
+            2
+        );
+
+        let mut dataset = Dataset::new();
+        dataset
+            .import(source.match_quads(None, None, None, None))
+            .unwrap();
+        assert_eq!(dataset.len(), 3);
+
+        let mut sink = TurtleSink::new(Vec::new(), TurtleSerializer::new(TurtleSyntax::TriG));
+        sink.import(dataset.match_quads(None, None, None, None))
+            .unwrap();
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!(
+            TurtleSource::new(TurtleSyntax::TriG, &output)
+                .match_quads(None, None, None, None)
+                .count(),
+            3
+        );
+
+        let broken = TurtleSource::new(TurtleSyntax::Turtle, "<a> <b> .");
+        let result = dataset.import(broken.match_quads(None, None, None, None));
+        assert!(matches!(result, Err(ImportError::Source(_))));
+
+        let mut sink = TurtleSink::new(Vec::new(), TurtleSerializer::new(TurtleSyntax::TriG));
+        let quads = dataset
+            .match_quads(None, None, None, None)
+            .take(1)
+            .map(|quad| quad.map_err(|_| "unreachable"))
+            .chain([Err("broken")]);
+        assert!(matches!(
+            sink.import(quads),
+            Err(ImportError::Source("broken"))
+        ));
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!(
+            parse_str(TurtleSyntax::TriG, &output, None).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn stores_remove_quads() {
+        let mut dataset = Dataset::new();
+        dataset
+            .import(
+                TurtleSource::new(TurtleSyntax::TriG, INPUT).match_quads(None, None, None, None),
+            )
+            .unwrap();
+
+        let graph = QuadGraph::NamedNode(nn("g"));
+        let predicate = QuadPredicate::NamedNode(nn("p"));
+        dataset
+            .remove_matches(None, Some(&predicate), None, None)
+            .unwrap();
+        assert_eq!(dataset.len(), 1);
+
+        dataset.delete_graph(&graph).unwrap();
+        assert!(dataset.is_empty());
+
+        let quad = Quad::new(
+            &QuadSubject::NamedNode(nn("a")),
+            &predicate,
+            &QuadObject::NamedNode(nn("b")),
+            None,
+        );
+        dataset.import(ok_quads([quad.clone()])).unwrap();
+        Store::remove(&mut dataset, ok_quads([quad])).unwrap();
+        assert!(dataset.is_empty());
+    }
+
+    #[test]
+    fn turtle_sources_match_patterns_and_keep_their_error() {
+        let source = TurtleSource::new(TurtleSyntax::TriG, "<a> <p> <b> . <g> { <a> <p> <c> . }")
+            .with_base_iri("http://example.org/");
+        let graph = QuadGraph::NamedNode(nn("g"));
+        let default = QuadGraph::DefaultGraph(DefaultGraph::new());
+        let object = QuadObject::NamedNode(nn("c"));
+        assert_eq!(source.match_quads(None, None, None, None).count(), 2);
+        assert_eq!(
+            source
+                .match_quads(None, None, None, Some(&graph))
+                .map(Result::unwrap)
+                .collect::<Vec<_>>(),
+            [Quad::new(
+                &QuadSubject::NamedNode(nn("a")),
+                &QuadPredicate::NamedNode(nn("p")),
+                &object,
+                Some(&graph)
+            )]
+        );
+        assert_eq!(
+            source
+                .match_quads(None, None, Some(&object), Some(&default))
+                .count(),
+            0
+        );
+
+        // Every match reports the syntax error, at the same position.
+        let broken = TurtleSource::new(TurtleSyntax::Turtle, "<a> <b> <c> .\n<a> <b> .");
+        for _ in 0..2 {
+            let results: Vec<_> = broken.match_quads(None, None, None, None).collect();
+            assert_eq!(results.len(), 1);
+            let error = results[0].as_ref().unwrap_err();
+            assert_eq!(error.line(), 2);
+        }
+    }
+
+    /// Counts the writes it receives, failing once `fail_after` is reached.
+    struct Writes {
+        output: Vec<u8>,
+        count: usize,
+        fail_after: usize,
+    }
+
+    impl Write for Writes {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.count == self.fail_after {
+                return Err(io::Error::other("disk full"));
+            }
+            self.count += 1;
+            self.output.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn turtle_sinks_write_as_they_go() {
+        let quads: Vec<Quad> = (0..500)
+            .map(|i| {
+                Quad::new(
+                    &QuadSubject::NamedNode(nn(&format!("s{i}"))),
+                    &QuadPredicate::NamedNode(nn("p")),
+                    &QuadObject::NamedNode(nn(&format!("o{i}"))),
+                    None,
+                )
+            })
+            .collect();
+        let writes = Writes {
+            output: Vec::new(),
+            count: 0,
+            fail_after: usize::MAX,
+        };
+        let mut sink = TurtleSink::new(writes, TurtleSerializer::new(TurtleSyntax::NTriples));
+        sink.import(ok_quads(quads.clone())).unwrap();
+        sink.import(ok_quads(quads[..1].to_vec())).unwrap();
+        let writes = sink.into_inner();
+        assert!(writes.count > 2, "{} writes", writes.count);
+        let output = String::from_utf8(writes.output).unwrap();
+        let parsed = parse_str(TurtleSyntax::NTriples, &output, None).unwrap();
+        assert_eq!(parsed.len(), quads.len() + 1);
+        assert_eq!(parsed[..quads.len()], quads[..]);
+
+        let failing = Writes {
+            output: Vec::new(),
+            count: 0,
+            fail_after: 1,
+        };
+        let mut sink = TurtleSink::new(failing, TurtleSerializer::new(TurtleSyntax::NTriples));
+        let result = sink.import(ok_quads(quads.clone()));
+        assert!(
+            matches!(result, Err(ImportError::Sink(error)) if error.to_string() == "disk full")
+        );
+
+        // Turtle has no named graphs.
+        let mut sink = TurtleSink::new(Vec::new(), TurtleSerializer::new(TurtleSyntax::Turtle));
+        let named = TurtleSource::new(TurtleSyntax::TriG, INPUT);
+        let result =
+            sink.import(named.match_quads(None, None, None, Some(&QuadGraph::NamedNode(nn("g")))));
+        assert!(matches!(
+            result,
+            Err(ImportError::Sink(error)) if error.kind() == io::ErrorKind::InvalidInput
+        ));
+    }
+}
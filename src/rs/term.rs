@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 use crate::rs::term_like::TermLike;
 
 use crate::rs::blank_node::BlankNode;
@@ -52,6 +54,20 @@ impl PartialEq for Term {
     }
 }
 
+impl Hash for Term {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Term::NamedNode(term) => term.hash(state),
+            Term::BlankNode(term) => term.hash(state),
+            Term::Literal(term) => term.hash(state),
+            Term::Variable(term) => term.hash(state),
+            Term::DefaultGraph(term) => term.hash(state),
+            Term::Quad(term) => term.hash(state),
+        }
+    }
+}
+
 impl TermLike for Term {
     fn value(&self) -> &str {
         match self {
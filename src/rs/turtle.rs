@@ -0,0 +1,1970 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{self, BufRead, Write};
+
+use crate::rs::blank_node::{BlankNode, BlankNodeLabeler};
+use crate::rs::data_factory::{FactoryQuads, QuadImport, with_factory};
+use crate::rs::iri;
+use crate::rs::literal::{LanguageDirection, Literal, is_valid_language_tag};
+use crate::rs::named_node::NamedNode;
+use crate::rs::parse_error::{ParseError, SyntaxError};
+use crate::rs::quad::Quad;
+use crate::rs::quad_graph::QuadGraph;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term::Term;
+use crate::rs::term_like::TermLike;
+use crate::rs::vocab::{rdf, xsd};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TurtleSyntax {
+    Turtle,
+    TriG,
+    NTriples,
+    NQuads,
+}
+
+impl TurtleSyntax {
+    /// Whether this is N-Triples or N-Quads, with one statement per line
+    /// and no abbreviations.
+    pub fn is_line_based(&self) -> bool {
+        matches!(self, TurtleSyntax::NTriples | TurtleSyntax::NQuads)
+    }
+}
+
+/// Push-based parser for the Turtle family (Turtle, TriG, N-Triples and
+/// N-Quads).
+///
+/// Input is fed in chunks with [`TurtleParser::extend_from_slice`] and quads
+/// are pulled with [`TurtleParser::read_next`]. Only the statement currently
+/// being parsed is buffered, so arbitrarily large documents can be parsed
+/// with bounded memory. The statements of a TriG graph block are parsed one
+/// at a time too, so a large block is not buffered whole.
+pub struct TurtleParser {
+    syntax: TurtleSyntax,
+    buffer: String,
+    incomplete_utf8: Vec<u8>,
+    is_end: bool,
+    failed: bool,
+    line: usize,
+    column: usize,
+    base: Option<String>,
+    prefixes: BTreeMap<String, String>,
+    blank_nodes: BlankNodeLabeler,
+    in_graph_block: bool,
+    graph: Option<QuadGraph>,
+    pending: VecDeque<Quad>,
+}
+
+impl TurtleParser {
+    pub fn new(syntax: TurtleSyntax) -> Self {
+        Self {
+            syntax,
+            buffer: String::new(),
+            incomplete_utf8: Vec::new(),
+            is_end: false,
+            failed: false,
+            line: 1,
+            column: 1,
+            base: None,
+            prefixes: BTreeMap::new(),
+            blank_nodes: BlankNodeLabeler::new(),
+            in_graph_block: false,
+            graph: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn with_base_iri(mut self, base: &str) -> Self {
+        self.base = Some(base.to_owned());
+        self
+    }
+
+    pub fn with_prefix(mut self, prefix: &str, namespace: &str) -> Self {
+        self.prefixes
+            .insert(prefix.to_owned(), namespace.to_owned());
+        self
+    }
+
+    pub fn syntax(&self) -> TurtleSyntax {
+        self.syntax
+    }
+
+    pub fn base_iri(&self) -> Option<&str> {
+        self.base.as_deref()
+    }
+
+    /// Prefixes declared so far.
+    pub fn prefixes(&self) -> &BTreeMap<String, String> {
+        &self.prefixes
+    }
+
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        let mut data = data;
+        let joined;
+
+        if !self.incomplete_utf8.is_empty() {
+            let mut bytes = std::mem::take(&mut self.incomplete_utf8);
+            bytes.extend_from_slice(data);
+            joined = bytes;
+            data = &joined;
+        }
+
+        loop {
+            match std::str::from_utf8(data) {
+                Ok(text) => {
+                    self.buffer.push_str(text);
+                    return;
+                }
+                Err(error) => {
+                    let (valid, rest) = data.split_at(error.valid_up_to());
+                    self.buffer
+                        .push_str(std::str::from_utf8(valid).unwrap_or_default());
+
+                    match error.error_len() {
+                        Some(length) => {
+                            self.buffer.push(char::REPLACEMENT_CHARACTER);
+                            data = &rest[length..];
+                        }
+                        None => {
+                            self.incomplete_utf8.extend_from_slice(rest);
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Signals that no more input will follow.
+    pub fn end(&mut self) {
+        if !self.incomplete_utf8.is_empty() {
+            self.incomplete_utf8.clear();
+            self.buffer.push(char::REPLACEMENT_CHARACTER);
+        }
+        self.is_end = true;
+    }
+
+    /// Whether all input has been parsed (or parsing stopped on an error).
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+            && (self.failed || (self.is_end && self.buffer.is_empty() && !self.in_graph_block))
+    }
+
+    /// Returns the next quad, or `None` if more input is needed (or the
+    /// document is done, see [`TurtleParser::is_done`]).
+    pub fn read_next(&mut self) -> Option<Result<Quad, SyntaxError>> {
+        loop {
+            if let Some(quad) = self.pending.pop_front() {
+                return Some(Ok(quad));
+            }
+            if self.failed {
+                return None;
+            }
+
+            let mut statement = StatementParser {
+                input: &self.buffer,
+                position: 0,
+                hit_end: false,
+                is_end: self.is_end,
+                syntax: self.syntax,
+                base: self.base.as_deref(),
+                prefixes: &self.prefixes,
+                blank_nodes: &mut self.blank_nodes,
+                in_graph_block: self.in_graph_block,
+                graph: if self.in_graph_block {
+                    self.graph.clone()
+                } else {
+                    None
+                },
+                quads: Vec::new(),
+                directive: None,
+            };
+
+            match statement.parse_statement() {
+                Ok(true) => {
+                    let consumed = statement.position;
+                    let quads = std::mem::take(&mut statement.quads);
+                    let directive = statement.directive.take();
+                    self.in_graph_block = statement.in_graph_block;
+                    self.graph = statement.graph.take();
+                    self.blank_nodes.commit();
+
+                    match directive {
+                        Some(Directive::Prefix(prefix, namespace)) => {
+                            self.prefixes.insert(prefix, namespace);
+                        }
+                        Some(Directive::Base(base)) => self.base = Some(base),
+                        None => {}
+                    }
+
+                    self.pending.extend(quads);
+                    self.consume(consumed);
+                }
+                Ok(false) => {
+                    let consumed = statement.position;
+                    self.blank_nodes.commit();
+                    self.consume(consumed);
+                    return None;
+                }
+                Err(Failure::Incomplete) => {
+                    self.blank_nodes.rollback();
+                    return None;
+                }
+                Err(Failure::Syntax(message, position)) => {
+                    self.failed = true;
+                    let (line, column) = self.position_of(position);
+                    return Some(Err(SyntaxError::new(&message, line, column)));
+                }
+            }
+        }
+    }
+
+    fn position_of(&self, offset: usize) -> (usize, usize) {
+        let mut line = self.line;
+        let mut column = self.column;
+
+        for c in self.buffer[..offset].chars() {
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+
+        (line, column)
+    }
+
+    fn consume(&mut self, length: usize) {
+        let (line, column) = self.position_of(length);
+        self.line = line;
+        self.column = column;
+        self.buffer.drain(..length);
+    }
+}
+
+/// Iterator of the quads of a Turtle/TriG document read from `R`.
+pub struct TurtleReader<R> {
+    reader: R,
+    parser: TurtleParser,
+}
+
+impl<R: BufRead> TurtleReader<R> {
+    pub fn new(reader: R, parser: TurtleParser) -> Self {
+        Self { reader, parser }
+    }
+
+    pub fn parser(&self) -> &TurtleParser {
+        &self.parser
+    }
+
+    /// Converts each parsed quad with `factory`. The quads are still parsed
+    /// as `rs` terms first, see [`data_factory`](crate::rs::data_factory).
+    pub fn with_factory<F: QuadImport>(self, factory: F) -> FactoryQuads<Self, F> {
+        with_factory(self, factory)
+    }
+}
+
+impl<R: BufRead> Iterator for TurtleReader<R> {
+    type Item = Result<Quad, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.parser.read_next() {
+                return Some(result.map_err(ParseError::from));
+            }
+            if self.parser.is_done() {
+                return None;
+            }
+
+            let chunk = match self.reader.fill_buf() {
+                Ok(chunk) => chunk,
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(error) => return Some(Err(error.into())),
+            };
+
+            if chunk.is_empty() {
+                self.parser.end();
+            } else {
+                let length = chunk.len();
+                self.parser.extend_from_slice(chunk);
+                self.reader.consume(length);
+            }
+        }
+    }
+}
+
+pub fn parse_str(
+    syntax: TurtleSyntax,
+    input: &str,
+    base: Option<&str>,
+) -> Result<Vec<Quad>, SyntaxError> {
+    let mut parser = TurtleParser::new(syntax);
+    if let Some(base) = base {
+        parser = parser.with_base_iri(base);
+    }
+
+    parser.extend_from_slice(input.as_bytes());
+    parser.end();
+
+    let mut quads = Vec::new();
+    while let Some(quad) = parser.read_next() {
+        quads.push(quad?);
+    }
+
+    Ok(quads)
+}
+
+/// Serializer for the Turtle family.
+///
+/// Turtle and TriG output groups triples by graph and subject; N-Triples and
+/// N-Quads output is written one quad per line as it comes.
+#[derive(Clone)]
+pub struct TurtleSerializer {
+    syntax: TurtleSyntax,
+    prefixes: BTreeMap<String, String>,
+}
+
+impl TurtleSerializer {
+    pub fn new(syntax: TurtleSyntax) -> Self {
+        Self {
+            syntax,
+            prefixes: BTreeMap::new(),
+        }
+    }
+
+    /// Declares a prefix to abbreviate IRIs with. Line-based syntaxes have
+    /// no prefixes and ignore it.
+    pub fn with_prefix(mut self, prefix: &str, namespace: &str) -> Self {
+        self.prefixes
+            .insert(prefix.to_owned(), namespace.to_owned());
+        self
+    }
+
+    pub fn syntax(&self) -> TurtleSyntax {
+        self.syntax
+    }
+
+    /// Writes `quads`, grouped in order of first appearance. Syntaxes
+    /// without named graphs fail on them with [`io::ErrorKind::InvalidInput`].
+    pub fn serialize<'a>(
+        &self,
+        mut writer: impl Write,
+        quads: impl IntoIterator<Item = &'a Quad>,
+    ) -> io::Result<()> {
+        let mut formatter = self.formatter();
+        let mut output = String::new();
+
+        if self.syntax.is_line_based() {
+            for quad in quads {
+                formatter.format(quad, &mut output)?;
+                if output.len() >= 8192 {
+                    writer.write_all(output.as_bytes())?;
+                    output.clear();
+                }
+            }
+        } else {
+            for quad in group_by_graph_and_subject(quads) {
+                formatter.format(quad, &mut output)?;
+            }
+        }
+
+        formatter.finish(&mut output);
+        writer.write_all(output.as_bytes())?;
+        writer.flush()
+    }
+
+    /// A formatter writing quads incrementally, abbreviating runs of quads
+    /// that share their graph and subject.
+    pub(crate) fn formatter(&self) -> TurtleFormatter {
+        TurtleFormatter {
+            serializer: self.clone(),
+            started: false,
+            graph: None,
+            subject: None,
+            predicate: None,
+        }
+    }
+
+    fn predicate(&self, predicate: &QuadPredicate) -> String {
+        match predicate {
+            QuadPredicate::NamedNode(named_node)
+                if *named_node == *rdf::r#type && !self.syntax.is_line_based() =>
+            {
+                "a".to_owned()
+            }
+            predicate => self.term(&predicate.to_term()),
+        }
+    }
+
+    fn term(&self, term: &Term) -> String {
+        match term {
+            Term::NamedNode(named_node) => self
+                .prefixed_name(named_node.value())
+                .unwrap_or_else(|| format_iri(named_node.value())),
+            Term::BlankNode(blank_node) => format!("_:{}", blank_node.value()),
+            Term::Literal(literal) if self.syntax.is_line_based() => {
+                format_canonical_literal(literal)
+            }
+            Term::Literal(literal) => format_literal(literal),
+            Term::Quad(triple) if self.syntax.is_line_based() => format!(
+                "<<( {} {} {} )>>",
+                self.term(&triple.subject().to_term()),
+                self.predicate(triple.predicate()),
+                self.term(&triple.object().to_term())
+            ),
+            Term::Quad(triple) => format!(
+                "<< {} {} {} >>",
+                self.term(&triple.subject().to_term()),
+                self.predicate(triple.predicate()),
+                self.term(&triple.object().to_term())
+            ),
+            Term::Variable(variable) => format!("?{}", variable.value()),
+            Term::DefaultGraph(_) => String::new(),
+        }
+    }
+
+    /// Abbreviates `iri` with the longest matching namespace whose remainder
+    /// is a local name that needs no escapes.
+    fn prefixed_name(&self, iri: &str) -> Option<String> {
+        if self.syntax.is_line_based() {
+            return None;
+        }
+        self.prefixes
+            .iter()
+            .filter_map(|(prefix, namespace)| Some((prefix, iri.strip_prefix(namespace.as_str())?)))
+            .filter(|(_, local)| is_plain_local_name(local))
+            .min_by_key(|(_, local)| local.len())
+            .map(|(prefix, local)| format!("{prefix}:{local}"))
+    }
+}
+
+/// Orders `quads` by graph, then subject, then predicate, each in order of
+/// first appearance.
+fn group_by_graph_and_subject<'a>(quads: impl IntoIterator<Item = &'a Quad>) -> Vec<&'a Quad> {
+    type Subjects<'a> = Vec<(&'a QuadSubject, Vec<(&'a QuadPredicate, Vec<&'a Quad>)>)>;
+    let mut graphs: Vec<Subjects> = Vec::new();
+    let mut graph_index: HashMap<&QuadGraph, usize> = HashMap::new();
+    let mut subject_index: HashMap<(usize, &QuadSubject), usize> = HashMap::new();
+
+    for quad in quads {
+        let g = *graph_index.entry(quad.graph()).or_insert_with(|| {
+            graphs.push(Vec::new());
+            graphs.len() - 1
+        });
+        let subjects = &mut graphs[g];
+        let s = *subject_index.entry((g, quad.subject())).or_insert_with(|| {
+            subjects.push((quad.subject(), Vec::new()));
+            subjects.len() - 1
+        });
+        let predicates = &mut subjects[s].1;
+        match predicates.iter_mut().find(|(p, _)| *p == quad.predicate()) {
+            Some((_, quads)) => quads.push(quad),
+            None => predicates.push((quad.predicate(), vec![quad])),
+        }
+    }
+
+    graphs
+        .into_iter()
+        .flatten()
+        .flat_map(|(_, predicates)| predicates)
+        .flat_map(|(_, quads)| quads)
+        .collect()
+}
+
+/// Incremental state of a [`TurtleSerializer`]: the graph block and the
+/// statement currently open.
+pub(crate) struct TurtleFormatter {
+    serializer: TurtleSerializer,
+    started: bool,
+    graph: Option<QuadGraph>,
+    subject: Option<QuadSubject>,
+    predicate: Option<QuadPredicate>,
+}
+
+impl TurtleFormatter {
+    pub(crate) fn format(&mut self, quad: &Quad, output: &mut String) -> io::Result<()> {
+        let syntax = self.serializer.syntax;
+        let is_default_graph = matches!(quad.graph(), QuadGraph::DefaultGraph(_));
+        if !is_default_graph && matches!(syntax, TurtleSyntax::Turtle | TurtleSyntax::NTriples) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the syntax can not contain named graphs",
+            ));
+        }
+        self.start(output);
+
+        let subject = self.serializer.term(&quad.subject().to_term());
+        let predicate = self.serializer.predicate(quad.predicate());
+        let object = self.serializer.term(&quad.object().to_term());
+        let graph = self.serializer.term(&quad.graph().to_term());
+
+        if syntax.is_line_based() {
+            output.push_str(&format!("{subject} {predicate} {object}"));
+            if !is_default_graph {
+                output.push_str(&format!(" {graph}"));
+            }
+            output.push_str(" .\n");
+            return Ok(());
+        }
+
+        if self.graph.as_ref() != Some(quad.graph()) {
+            self.close_graph(output);
+            if !is_default_graph {
+                output.push_str(&format!("{graph} {{\n"));
+            }
+            self.graph = Some(quad.graph().clone());
+        }
+        let indent = if is_default_graph { "" } else { "    " };
+
+        if self.subject.as_ref() != Some(quad.subject()) {
+            self.close_statement(output);
+            output.push_str(&format!("{indent}{subject} {predicate} {object}"));
+            self.subject = Some(quad.subject().clone());
+        } else if self.predicate.as_ref() != Some(quad.predicate()) {
+            output.push_str(&format!(" ;\n{indent}    {predicate} {object}"));
+        } else {
+            output.push_str(&format!(", {object}"));
+        }
+        self.predicate = Some(quad.predicate().clone());
+        Ok(())
+    }
+
+    /// Closes whatever is still open.
+    pub(crate) fn finish(&mut self, output: &mut String) {
+        self.start(output);
+        self.close_graph(output);
+    }
+
+    fn start(&mut self, output: &mut String) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        if self.serializer.syntax.is_line_based() || self.serializer.prefixes.is_empty() {
+            return;
+        }
+        for (prefix, namespace) in &self.serializer.prefixes {
+            output.push_str(&format!("@prefix {prefix}: {} .\n", format_iri(namespace)));
+        }
+        output.push('\n');
+    }
+
+    fn close_statement(&mut self, output: &mut String) {
+        if self.subject.take().is_some() {
+            output.push_str(" .\n");
+        }
+        self.predicate = None;
+    }
+
+    fn close_graph(&mut self, output: &mut String) {
+        self.close_statement(output);
+        if let Some(QuadGraph::DefaultGraph(_)) | None = self.graph.take() {
+            return;
+        }
+        output.push_str("}\n");
+    }
+}
+
+fn is_plain_local_name(local: &str) -> bool {
+    let mut chars = local.chars();
+    let Some(first) = chars.next() else {
+        return true;
+    };
+    (is_pn_chars_u(first) || first.is_ascii_digit())
+        && chars.all(|c| is_pn_chars(c) || c == '.')
+        && !local.ends_with('.')
+}
+
+pub(crate) fn format_iri(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('<');
+    for c in value.chars() {
+        if is_iri_forbidden(c) {
+            out.push_str(&format!("\\u{:04X}", c as u32));
+        } else {
+            out.push(c);
+        }
+    }
+    out.push('>');
+    out
+}
+
+pub(crate) fn format_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub(crate) fn format_literal(literal: &Literal) -> String {
+    let value = literal.value();
+    if !literal.language().is_empty() {
+        return match literal.direction() {
+            Some(direction) => format!(
+                "{}@{}--{direction}",
+                format_string(value),
+                literal.language()
+            ),
+            None => format!("{}@{}", format_string(value), literal.language()),
+        };
+    }
+
+    let datatype = literal.datatype();
+    let is_short_form = if *datatype == *xsd::integer {
+        is_integer(value)
+    } else if *datatype == *xsd::decimal {
+        is_decimal(value)
+    } else if *datatype == *xsd::double {
+        is_double(value)
+    } else if *datatype == *xsd::boolean {
+        value == "true" || value == "false"
+    } else {
+        false
+    };
+
+    if is_short_form {
+        value.to_owned()
+    } else if *datatype == *xsd::string {
+        format_string(value)
+    } else {
+        format!("{}^^{}", format_string(value), format_iri(datatype.value()))
+    }
+}
+
+/// A literal in its full form, as N-Triples requires.
+pub(crate) fn format_canonical_literal(literal: &Literal) -> String {
+    if !literal.language().is_empty() {
+        return format_literal(literal);
+    }
+    let value = format_string(literal.value());
+    if *literal.datatype() == *xsd::string {
+        value
+    } else {
+        format!("{value}^^{}", format_iri(literal.datatype().value()))
+    }
+}
+
+fn unsigned(value: &str) -> &str {
+    value.strip_prefix(['+', '-']).unwrap_or(value)
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_integer(value: &str) -> bool {
+    is_digits(unsigned(value))
+}
+
+fn is_decimal(value: &str) -> bool {
+    match unsigned(value).split_once('.') {
+        Some((integer, fraction)) => {
+            (integer.is_empty() || is_digits(integer)) && is_digits(fraction)
+        }
+        None => false,
+    }
+}
+
+fn is_double(value: &str) -> bool {
+    let Some((mantissa, exponent)) = unsigned(value).split_once(['e', 'E']) else {
+        return false;
+    };
+    let mantissa_ok = match mantissa.split_once('.') {
+        Some((integer, fraction)) => {
+            (integer.is_empty() || is_digits(integer)) && is_digits(fraction)
+        }
+        None => is_digits(mantissa),
+    };
+    mantissa_ok && is_integer(exponent)
+}
+
+pub(crate) fn is_pn_chars_base(c: char) -> bool {
+    matches!(c,
+        'A'..='Z'
+        | 'a'..='z'
+        | '\u{00C0}'..='\u{00D6}'
+        | '\u{00D8}'..='\u{00F6}'
+        | '\u{00F8}'..='\u{02FF}'
+        | '\u{0370}'..='\u{037D}'
+        | '\u{037F}'..='\u{1FFF}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}'
+        | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}'
+        | '\u{10000}'..='\u{EFFFF}')
+}
+
+pub(crate) fn is_pn_chars_u(c: char) -> bool {
+    is_pn_chars_base(c) || c == '_'
+}
+
+pub(crate) fn is_pn_chars(c: char) -> bool {
+    is_pn_chars_u(c)
+        || matches!(c,
+            '-' | '0'..='9' | '\u{00B7}' | '\u{0300}'..='\u{036F}' | '\u{203F}'..='\u{2040}')
+}
+
+/// Characters that may be escaped with a backslash in a local name.
+pub(crate) fn is_pn_local_escapable(c: char) -> bool {
+    matches!(
+        c,
+        '_' | '~'
+            | '.'
+            | '-'
+            | '!'
+            | '$'
+            | '&'
+            | '\''
+            | '('
+            | ')'
+            | '*'
+            | '+'
+            | ','
+            | ';'
+            | '='
+            | '/'
+            | '?'
+            | '#'
+            | '@'
+            | '%'
+    )
+}
+
+pub(crate) fn is_iri_forbidden(c: char) -> bool {
+    matches!(c, '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\') || c <= ' '
+}
+
+enum Directive {
+    Prefix(String, String),
+    Base(String),
+}
+
+enum Failure {
+    Incomplete,
+    Syntax(String, usize),
+}
+
+enum Node {
+    NamedNode(NamedNode),
+    BlankNode(BlankNode),
+    Literal(Literal),
+    Quad(Box<Quad>),
+}
+
+struct StatementParser<'a> {
+    input: &'a str,
+    position: usize,
+    hit_end: bool,
+    is_end: bool,
+    syntax: TurtleSyntax,
+    base: Option<&'a str>,
+    prefixes: &'a BTreeMap<String, String>,
+    blank_nodes: &'a mut BlankNodeLabeler,
+    in_graph_block: bool,
+    graph: Option<QuadGraph>,
+    quads: Vec<Quad>,
+    directive: Option<Directive>,
+}
+
+type ParseResult<T> = Result<T, Failure>;
+
+impl StatementParser<'_> {
+    fn peek(&mut self) -> Option<char> {
+        let c = self.input[self.position..].chars().next();
+        if c.is_none() {
+            self.hit_end = true;
+        }
+        c
+    }
+
+    fn peek_nth(&mut self, n: usize) -> Option<char> {
+        let c = self.input[self.position..].chars().nth(n);
+        if c.is_none() {
+            self.hit_end = true;
+        }
+        c
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn starts_with(&mut self, prefix: &str) -> bool {
+        let rest = &self.input[self.position..];
+        if rest.len() < prefix.len() && prefix.starts_with(rest) {
+            self.hit_end = true;
+        }
+        rest.starts_with(prefix)
+    }
+
+    fn starts_with_keyword(&mut self, keyword: &str) -> bool {
+        let rest = &self.input[self.position..];
+        let Some(candidate) = rest.get(..keyword.len()) else {
+            if keyword
+                .to_ascii_lowercase()
+                .starts_with(&rest.to_ascii_lowercase())
+            {
+                self.hit_end = true;
+            }
+            return false;
+        };
+
+        if !candidate.eq_ignore_ascii_case(keyword) {
+            return false;
+        }
+
+        match rest[keyword.len()..].chars().next() {
+            Some(c) => !is_pn_chars(c) && c != ':' && c != '.',
+            None => {
+                self.hit_end = true;
+                true
+            }
+        }
+    }
+
+    fn error<T>(&self, message: &str, position: usize) -> ParseResult<T> {
+        if self.hit_end && !self.is_end {
+            Err(Failure::Incomplete)
+        } else {
+            Err(Failure::Syntax(message.to_owned(), position))
+        }
+    }
+
+    fn unexpected<T>(&mut self, expected: &str) -> ParseResult<T> {
+        let position = self.position;
+        match self.peek() {
+            Some(c) => self.error(&format!("expected {expected}, found '{c}'"), position),
+            None => self.error(
+                &format!("expected {expected}, found end of input"),
+                position,
+            ),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> ParseResult<()> {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.advance();
+            Ok(())
+        } else {
+            self.unexpected(&format!("'{c}'"))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' | '\n' => {
+                    self.advance();
+                }
+                '#' => {
+                    while let Some(c) = self.peek() {
+                        if c == '\n' {
+                            break;
+                        }
+                        self.advance();
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Parses one statement; returns `false` at the end of the document.
+    fn parse_statement(&mut self) -> ParseResult<bool> {
+        self.skip_whitespace();
+
+        if self.peek().is_none() {
+            return if !self.is_end {
+                Err(Failure::Incomplete)
+            } else if self.in_graph_block {
+                self.unexpected("'}'")
+            } else {
+                Ok(false)
+            };
+        }
+
+        if self.in_graph_block {
+            self.parse_graph_statement()?;
+        } else if self.syntax.is_line_based() {
+            self.parse_line_statement()?;
+        } else if self.starts_with("@prefix") {
+            self.position += "@prefix".len();
+            self.parse_prefix_declaration()?;
+            self.expect('.')?;
+        } else if self.starts_with("@base") {
+            self.position += "@base".len();
+            self.parse_base_declaration()?;
+            self.expect('.')?;
+        } else if self.starts_with_keyword("PREFIX") {
+            self.position += "PREFIX".len();
+            self.parse_prefix_declaration()?;
+        } else if self.starts_with_keyword("BASE") {
+            self.position += "BASE".len();
+            self.parse_base_declaration()?;
+        } else if self.syntax == TurtleSyntax::TriG {
+            self.parse_trig_block()?;
+        } else {
+            self.parse_triples()?;
+            self.expect('.')?;
+        }
+
+        if self.hit_end && !self.is_end {
+            return Err(Failure::Incomplete);
+        }
+
+        Ok(true)
+    }
+
+    /// An N-Triples or N-Quads statement. Turtle terms are accepted in it,
+    /// like most N-Triples parsers do.
+    fn parse_line_statement(&mut self) -> ParseResult<()> {
+        let start = self.position;
+        let node = self.parse_subject()?;
+        let subject = self.to_subject(node, start)?;
+        self.skip_whitespace();
+        let predicate = self.parse_verb()?;
+        self.skip_whitespace();
+        let start = self.position;
+        let node = self.parse_object()?;
+        let object = self.to_object(node, start)?;
+        self.skip_whitespace();
+        if self.syntax == TurtleSyntax::NQuads && self.peek() != Some('.') {
+            self.graph = Some(self.parse_graph_label()?);
+            self.skip_whitespace();
+        }
+        self.emit(&subject, &predicate, &object);
+        self.expect('.')
+    }
+
+    fn parse_prefix_declaration(&mut self) -> ParseResult<()> {
+        self.skip_whitespace();
+        let prefix = self.parse_pn_prefix()?;
+        self.expect(':')?;
+        self.skip_whitespace();
+        let namespace = self.parse_iri_ref()?;
+        self.directive = Some(Directive::Prefix(prefix, namespace));
+        Ok(())
+    }
+
+    fn parse_base_declaration(&mut self) -> ParseResult<()> {
+        self.skip_whitespace();
+        let base = self.parse_iri_ref()?;
+        self.directive = Some(Directive::Base(base));
+        Ok(())
+    }
+
+    fn parse_trig_block(&mut self) -> ParseResult<()> {
+        if self.starts_with_keyword("GRAPH") {
+            self.position += "GRAPH".len();
+            self.skip_whitespace();
+            let graph = self.parse_graph_label()?;
+            self.graph = Some(graph);
+            self.expect('{')?;
+            self.in_graph_block = true;
+            return Ok(());
+        }
+
+        if self.peek() == Some('{') {
+            self.advance();
+            self.in_graph_block = true;
+            return Ok(());
+        }
+
+        if self.peek() == Some('[') {
+            let after_bracket = self.next_non_whitespace(self.position + 1);
+            if let Some((close, ']')) = after_bracket
+                && let Some((open, '{')) = self.next_non_whitespace(close + 1)
+            {
+                self.position = open + 1;
+                self.graph = Some(QuadGraph::BlankNode(self.fresh_blank_node()));
+                self.in_graph_block = true;
+                return Ok(());
+            }
+
+            self.parse_triples()?;
+            return self.expect('.');
+        }
+
+        let start = self.position;
+        let subject = self.parse_subject()?;
+        self.skip_whitespace();
+
+        if self.peek() == Some('{') {
+            self.graph = Some(match subject {
+                Node::NamedNode(nn) => QuadGraph::NamedNode(nn),
+                Node::BlankNode(bn) => QuadGraph::BlankNode(bn),
+                _ => return self.error("invalid graph name", start),
+            });
+            self.advance();
+            self.in_graph_block = true;
+            return Ok(());
+        }
+
+        let subject = self.to_subject(subject, start)?;
+        self.parse_predicate_object_list(&subject)?;
+        self.expect('.')
+    }
+
+    fn next_non_whitespace(&mut self, from: usize) -> Option<(usize, char)> {
+        let found = self.input[from..]
+            .char_indices()
+            .find(|(_, c)| !c.is_whitespace())
+            .map(|(i, c)| (from + i, c));
+        if found.is_none() {
+            self.hit_end = true;
+        }
+        found
+    }
+
+    fn parse_graph_label(&mut self) -> ParseResult<QuadGraph> {
+        let start = self.position;
+        if self.starts_with("[") {
+            self.advance();
+            self.expect(']')?;
+            return Ok(QuadGraph::BlankNode(self.fresh_blank_node()));
+        }
+        match self.parse_subject()? {
+            Node::NamedNode(nn) => Ok(QuadGraph::NamedNode(nn)),
+            Node::BlankNode(bn) => Ok(QuadGraph::BlankNode(bn)),
+            _ => self.error("invalid graph name", start),
+        }
+    }
+
+    /// One triples statement of a graph block, or the `}` closing it.
+    fn parse_graph_statement(&mut self) -> ParseResult<()> {
+        if self.peek() == Some('}') {
+            self.advance();
+            self.in_graph_block = false;
+            self.graph = None;
+            return Ok(());
+        }
+
+        self.parse_triples()?;
+        self.skip_whitespace();
+        match self.peek() {
+            Some('.') => {
+                self.advance();
+                Ok(())
+            }
+            Some('}') => Ok(()),
+            _ => self.unexpected("'.' or '}'"),
+        }
+    }
+
+    fn parse_triples(&mut self) -> ParseResult<()> {
+        self.skip_whitespace();
+        let start = self.position;
+
+        if self.peek() == Some('[') {
+            let (node, has_properties) = self.parse_blank_node_property_list()?;
+            let subject = self.to_subject(node, start)?;
+            self.skip_whitespace();
+            if !has_properties || !matches!(self.peek(), Some('.' | '}')) {
+                self.parse_predicate_object_list(&subject)?;
+            }
+            return Ok(());
+        }
+
+        let node = self.parse_subject()?;
+        let subject = self.to_subject(node, start)?;
+        self.parse_predicate_object_list(&subject)
+    }
+
+    fn parse_subject(&mut self) -> ParseResult<Node> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('<') if self.starts_with("<<") => self.parse_quoted_triple(),
+            Some('<') => Ok(Node::NamedNode(NamedNode::new(&self.parse_iri_ref()?))),
+            Some('_') if self.peek_nth(1) == Some(':') => {
+                Ok(Node::BlankNode(self.parse_blank_node_label()?))
+            }
+            Some('(') => self.parse_collection(),
+            Some('[') => Ok(self.parse_blank_node_property_list()?.0),
+            Some(c) if is_pn_chars_base(c) || c == ':' => {
+                Ok(Node::NamedNode(self.parse_prefixed_name()?))
+            }
+            _ => self.unexpected("a subject"),
+        }
+    }
+
+    /// A quoted triple `<< s p o >>`, or a triple term `<<( s p o )>>` as
+    /// written by N-Triples 1.2.
+    fn parse_quoted_triple(&mut self) -> ParseResult<Node> {
+        let close = if self.starts_with("<<(") { ")>>" } else { ">>" };
+        self.position += close.len();
+
+        let subject_start = self.position;
+        let subject = self.parse_subject()?;
+        let subject = self.to_subject(subject, subject_start)?;
+        self.skip_whitespace();
+        let predicate = self.parse_verb()?;
+        self.skip_whitespace();
+        let object_start = self.position;
+        let object = self.parse_object()?;
+        let object = self.to_object(object, object_start)?;
+        self.skip_whitespace();
+
+        if !self.starts_with(close) {
+            return self.unexpected(&format!("'{close}'"));
+        }
+        self.position += close.len();
+
+        Ok(Node::Quad(Box::new(Quad::new(
+            &subject, &predicate, &object, None,
+        ))))
+    }
+
+    fn parse_predicate_object_list(&mut self, subject: &QuadSubject) -> ParseResult<()> {
+        loop {
+            self.skip_whitespace();
+            let predicate = self.parse_verb()?;
+            self.parse_object_list(subject, &predicate)?;
+            self.skip_whitespace();
+
+            if self.peek() != Some(';') {
+                return Ok(());
+            }
+            while self.peek() == Some(';') {
+                self.advance();
+                self.skip_whitespace();
+            }
+            if matches!(self.peek(), Some('.' | ']' | '}')) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_object_list(
+        &mut self,
+        subject: &QuadSubject,
+        predicate: &QuadPredicate,
+    ) -> ParseResult<()> {
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            let object = self.parse_object()?;
+            let object = self.to_object(object, start)?;
+            self.emit(subject, predicate, &object);
+            self.skip_whitespace();
+
+            if self.peek() == Some(',') {
+                self.advance();
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_verb(&mut self) -> ParseResult<QuadPredicate> {
+        if self.peek() == Some('a') {
+            match self.peek_nth(1) {
+                Some(c) if is_pn_chars(c) || c == ':' || c == '.' => {}
+                _ => {
+                    self.advance();
+                    return Ok(QuadPredicate::NamedNode(rdf::r#type.clone()));
+                }
+            }
+        }
+
+        match self.peek() {
+            Some('<') if !self.starts_with("<<") => Ok(QuadPredicate::NamedNode(NamedNode::new(
+                &self.parse_iri_ref()?,
+            ))),
+            Some(c) if is_pn_chars_base(c) || c == ':' => {
+                Ok(QuadPredicate::NamedNode(self.parse_prefixed_name()?))
+            }
+            _ => self.unexpected("a predicate"),
+        }
+    }
+
+    fn parse_object(&mut self) -> ParseResult<Node> {
+        match self.peek() {
+            Some('"' | '\'') => Ok(Node::Literal(self.parse_rdf_literal()?)),
+            Some('0'..='9' | '+' | '-' | '.') => Ok(Node::Literal(self.parse_numeric_literal()?)),
+            Some(_) if self.starts_with_keyword("true") => {
+                self.position += 4;
+                Ok(Node::Literal(Literal::new(
+                    "true",
+                    None,
+                    None,
+                    Some(&*xsd::boolean),
+                )))
+            }
+            Some(_) if self.starts_with_keyword("false") => {
+                self.position += 5;
+                Ok(Node::Literal(Literal::new(
+                    "false",
+                    None,
+                    None,
+                    Some(&*xsd::boolean),
+                )))
+            }
+            _ => self.parse_subject(),
+        }
+    }
+
+    fn parse_collection(&mut self) -> ParseResult<Node> {
+        self.advance();
+        let mut items = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(')') => {
+                    self.advance();
+                    break;
+                }
+                Some(_) => {
+                    let start = self.position;
+                    let item = self.parse_object()?;
+                    items.push(self.to_object(item, start)?);
+                }
+                None => return self.unexpected("')'"),
+            }
+        }
+
+        if items.is_empty() {
+            return Ok(Node::NamedNode(rdf::nil.clone()));
+        }
+
+        let nodes: Vec<BlankNode> = items.iter().map(|_| self.fresh_blank_node()).collect();
+        let first = QuadPredicate::NamedNode(rdf::first.clone());
+        let rest = QuadPredicate::NamedNode(rdf::rest.clone());
+
+        for (i, item) in items.iter().enumerate() {
+            let subject = QuadSubject::BlankNode(nodes[i].clone());
+            self.emit(&subject, &first, item);
+
+            let next = match nodes.get(i + 1) {
+                Some(next) => QuadObject::BlankNode(next.clone()),
+                None => QuadObject::NamedNode(rdf::nil.clone()),
+            };
+            self.emit(&subject, &rest, &next);
+        }
+
+        Ok(Node::BlankNode(nodes[0].clone()))
+    }
+
+    fn parse_blank_node_property_list(&mut self) -> ParseResult<(Node, bool)> {
+        self.advance();
+        self.skip_whitespace();
+        let node = self.fresh_blank_node();
+
+        if self.peek() == Some(']') {
+            self.advance();
+            return Ok((Node::BlankNode(node), false));
+        }
+
+        self.parse_predicate_object_list(&QuadSubject::BlankNode(node.clone()))?;
+        self.expect(']')?;
+
+        Ok((Node::BlankNode(node), true))
+    }
+
+    fn parse_rdf_literal(&mut self) -> ParseResult<Literal> {
+        let value = self.parse_string()?;
+
+        match self.peek() {
+            Some('@') => {
+                self.advance();
+                let start = self.position;
+                while let Some(c) = self.peek() {
+                    if c.is_ascii_alphanumeric() || (c == '-' && self.peek_nth(1) != Some('-')) {
+                        self.advance();
+                    } else {
+                        break;
+                    }
+                }
+                let language = &self.input[start..self.position];
+                if !is_valid_language_tag(language) {
+                    return self.error(&format!("invalid language tag \"{language}\""), start);
+                }
+
+                let direction = if self.starts_with("--") {
+                    self.position += 2;
+                    let direction_start = self.position;
+                    while let Some(c) = self.peek() {
+                        if c.is_ascii_alphabetic() {
+                            self.advance();
+                        } else {
+                            break;
+                        }
+                    }
+                    match self.input[direction_start..self.position].parse::<LanguageDirection>() {
+                        Ok(direction) => Some(direction),
+                        Err(error) => return self.error(&error.to_string(), direction_start),
+                    }
+                } else {
+                    None
+                };
+
+                Ok(Literal::new(
+                    &value,
+                    Some(language),
+                    direction.as_ref(),
+                    None,
+                ))
+            }
+            Some('^') if self.starts_with("^^") => {
+                self.position += 2;
+                let datatype = match self.peek() {
+                    Some('<') => NamedNode::new(&self.parse_iri_ref()?),
+                    _ => self.parse_prefixed_name()?,
+                };
+                Ok(Literal::new(&value, None, None, Some(&datatype)))
+            }
+            _ => Ok(Literal::new(&value, None, None, None)),
+        }
+    }
+
+    fn parse_string(&mut self) -> ParseResult<String> {
+        let start = self.position;
+        let Some(quote) = self.advance() else {
+            return self.unexpected("a string");
+        };
+        let long = self.peek() == Some(quote) && self.peek_nth(1) == Some(quote);
+        if long {
+            self.position += 2;
+        } else if self.peek() == Some(quote) {
+            self.advance();
+            return Ok(String::new());
+        }
+
+        let mut value = String::new();
+
+        loop {
+            let Some(c) = self.advance() else {
+                return self.error("unterminated string", start);
+            };
+
+            match c {
+                c if c == quote => {
+                    if !long {
+                        return Ok(value);
+                    }
+                    if self.peek() == Some(quote) && self.peek_nth(1) == Some(quote) {
+                        // A long string may end with up to two quotes of its own.
+                        while self.peek_nth(2) == Some(quote) {
+                            value.push(quote);
+                            self.advance();
+                        }
+                        self.position += 2;
+                        return Ok(value);
+                    }
+                    value.push(c);
+                }
+                '\\' => value.push(self.parse_escape()?),
+                '\n' | '\r' if !long => {
+                    return self.error("line break in a short string", self.position - 1);
+                }
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self) -> ParseResult<char> {
+        let start = self.position - 1;
+        match self.advance() {
+            Some('t') => Ok('\t'),
+            Some('b') => Ok('\u{8}'),
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('f') => Ok('\u{C}'),
+            Some('"') => Ok('"'),
+            Some('\'') => Ok('\''),
+            Some('\\') => Ok('\\'),
+            Some('u') => self.parse_hex_char(4, start),
+            Some('U') => self.parse_hex_char(8, start),
+            _ => self.error("invalid escape sequence", start),
+        }
+    }
+
+    fn parse_hex_char(&mut self, length: usize, start: usize) -> ParseResult<char> {
+        let mut code = 0;
+        for _ in 0..length {
+            match self.advance().and_then(|c| c.to_digit(16)) {
+                Some(digit) => code = code * 16 + digit,
+                None => return self.error("invalid escape sequence", start),
+            }
+        }
+        match char::from_u32(code) {
+            Some(c) => Ok(c),
+            None => self.error("invalid code point", start),
+        }
+    }
+
+    fn parse_numeric_literal(&mut self) -> ParseResult<Literal> {
+        let start = self.position;
+
+        if matches!(self.peek(), Some('+' | '-')) {
+            self.advance();
+        }
+
+        let mut integer_digits = 0;
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.advance();
+            integer_digits += 1;
+        }
+
+        let mut datatype: &NamedNode = &xsd::integer;
+        let mut fraction_digits = 0;
+        let mut has_point = false;
+
+        if self.peek() == Some('.') {
+            match self.peek_nth(1) {
+                Some('0'..='9') => {
+                    self.advance();
+                    while matches!(self.peek(), Some('0'..='9')) {
+                        self.advance();
+                        fraction_digits += 1;
+                    }
+                    datatype = &xsd::decimal;
+                    has_point = true;
+                }
+                Some('e' | 'E') if integer_digits > 0 => {
+                    self.advance();
+                    has_point = true;
+                }
+                _ => {}
+            }
+        }
+
+        if integer_digits == 0 && fraction_digits == 0 {
+            return self.error("invalid number", start);
+        }
+
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.advance();
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.advance();
+            }
+            let mut exponent_digits = 0;
+            while matches!(self.peek(), Some('0'..='9')) {
+                self.advance();
+                exponent_digits += 1;
+            }
+            if exponent_digits == 0 {
+                return self.error("invalid exponent", start);
+            }
+            datatype = &xsd::double;
+        } else if has_point && fraction_digits == 0 {
+            return self.error("invalid number", start);
+        }
+
+        Ok(Literal::new(
+            &self.input[start..self.position],
+            None,
+            None,
+            Some(datatype),
+        ))
+    }
+
+    fn parse_iri_ref(&mut self) -> ParseResult<String> {
+        let start = self.position;
+        if self.peek() != Some('<') {
+            return self.unexpected("an IRI");
+        }
+        self.advance();
+
+        let mut value = String::new();
+        loop {
+            match self.advance() {
+                Some('>') => break,
+                Some('\\') => {
+                    let escape_start = self.position - 1;
+                    let c = match self.advance() {
+                        Some('u') => self.parse_hex_char(4, escape_start)?,
+                        Some('U') => self.parse_hex_char(8, escape_start)?,
+                        _ => return self.error("invalid IRI escape", escape_start),
+                    };
+                    if is_iri_forbidden(c) {
+                        return self.error("invalid character in IRI", escape_start);
+                    }
+                    value.push(c);
+                }
+                Some(c) if is_iri_forbidden(c) => {
+                    return self.error("invalid character in IRI", self.position - 1);
+                }
+                Some(c) => value.push(c),
+                None => return self.error("unterminated IRI", start),
+            }
+        }
+
+        Ok(match self.base {
+            Some(base) if !iri::has_scheme(&value) => iri::resolve(base, &value),
+            _ => value,
+        })
+    }
+
+    fn parse_pn_prefix(&mut self) -> ParseResult<String> {
+        let start = self.position;
+
+        match self.peek() {
+            Some(c) if is_pn_chars_base(c) => {
+                self.advance();
+            }
+            _ => return Ok(String::new()),
+        }
+
+        while let Some(c) = self.peek() {
+            let continues = is_pn_chars(c)
+                || (c == '.' && self.peek_nth(1).is_some_and(|n| is_pn_chars(n) || n == '.'));
+            if !continues {
+                break;
+            }
+            self.advance();
+        }
+
+        Ok(self.input[start..self.position].to_owned())
+    }
+
+    fn parse_prefixed_name(&mut self) -> ParseResult<NamedNode> {
+        let start = self.position;
+        let prefix = self.parse_pn_prefix()?;
+
+        if self.peek() != Some(':') {
+            return self.unexpected("':'");
+        }
+        self.advance();
+
+        let Some(namespace) = self.prefixes.get(&prefix) else {
+            return self.error(&format!("undefined prefix \"{prefix}:\""), start);
+        };
+        let mut value = namespace.clone();
+
+        let mut first = true;
+        loop {
+            match self.peek() {
+                Some(c) if is_pn_chars_u(c) || c == ':' || c.is_ascii_digit() => {
+                    value.push(c);
+                    self.advance();
+                }
+                Some(c) if !first && is_pn_chars(c) => {
+                    value.push(c);
+                    self.advance();
+                }
+                Some('.') if !first => match self.peek_nth(1) {
+                    Some(n) if is_pn_chars(n) || matches!(n, ':' | '%' | '\\' | '.') => {
+                        value.push('.');
+                        self.advance();
+                    }
+                    _ => break,
+                },
+                Some('%') => {
+                    let escape_start = self.position;
+                    self.advance();
+                    for _ in 0..2 {
+                        match self.advance() {
+                            Some(c) if c.is_ascii_hexdigit() => {}
+                            _ => return self.error("invalid percent escape", escape_start),
+                        }
+                    }
+                    value.push_str(&self.input[escape_start..self.position]);
+                }
+                Some('\\') => {
+                    let escape_start = self.position;
+                    self.advance();
+                    match self.advance() {
+                        Some(c) if is_pn_local_escapable(c) => value.push(c),
+                        _ => return self.error("invalid local name escape", escape_start),
+                    }
+                }
+                _ => break,
+            }
+            first = false;
+        }
+
+        Ok(NamedNode::new(&value))
+    }
+
+    fn parse_blank_node_label(&mut self) -> ParseResult<BlankNode> {
+        let start = self.position;
+        self.position += 2;
+
+        match self.peek() {
+            Some(c) if is_pn_chars_u(c) || c.is_ascii_digit() => {
+                self.advance();
+            }
+            _ => return self.error("invalid blank node label", start),
+        }
+
+        while let Some(c) = self.peek() {
+            let continues = is_pn_chars(c)
+                || (c == '.' && self.peek_nth(1).is_some_and(|n| is_pn_chars(n) || n == '.'));
+            if !continues {
+                break;
+            }
+            self.advance();
+        }
+
+        Ok(self
+            .blank_nodes
+            .labeled(&self.input[start + 2..self.position]))
+    }
+
+    fn fresh_blank_node(&mut self) -> BlankNode {
+        self.blank_nodes.fresh()
+    }
+
+    fn to_subject(&self, node: Node, position: usize) -> ParseResult<QuadSubject> {
+        match node {
+            Node::NamedNode(nn) => Ok(QuadSubject::NamedNode(nn)),
+            Node::BlankNode(bn) => Ok(QuadSubject::BlankNode(bn)),
+            Node::Quad(q) => Ok(QuadSubject::Quad(q)),
+            Node::Literal(_) => self.error("a literal cannot be a subject", position),
+        }
+    }
+
+    fn to_object(&self, node: Node, position: usize) -> ParseResult<QuadObject> {
+        match node {
+            Node::NamedNode(nn) => Ok(QuadObject::NamedNode(nn)),
+            Node::BlankNode(bn) => Ok(QuadObject::BlankNode(bn)),
+            Node::Literal(l) => Ok(QuadObject::Literal(l)),
+            Node::Quad(_) => self.error("triple terms are only supported as subjects", position),
+        }
+    }
+
+    fn emit(&mut self, subject: &QuadSubject, predicate: &QuadPredicate, object: &QuadObject) {
+        self.quads
+            .push(Quad::new(subject, predicate, object, self.graph.as_ref()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::term_like::TermLike;
+
+    fn nn(value: &str) -> NamedNode {
+        NamedNode::new(value)
+    }
+
+    fn triple(subject: QuadSubject, predicate: &str, object: QuadObject) -> Quad {
+        Quad::new(
+            &subject,
+            &QuadPredicate::NamedNode(nn(predicate)),
+            &object,
+            None,
+        )
+    }
+
+    #[test]
+    fn triples_parse() {
+        let quads = parse_str(
+            TurtleSyntax::Turtle,
+            r#"
+                @prefix ex: <http://example.org/> .
+                PREFIX : <http://example.org/default#>
+                @base <http://example.org/base/> .
+
+                ex:s a ex:C ;
+                    ex:p "foo", 'bar'@en-GB, """multi
+line"""^^ex:dt ;
+                    ex:n -1, 2.5, 3e2, true ;
+                    :q <relative> .
+            "#,
+            None,
+        )
+        .unwrap();
+
+        let s = QuadSubject::NamedNode(nn("http://example.org/s"));
+
+        assert_eq!(
+            quads,
+            vec![
+                triple(
+                    s.clone(),
+                    rdf::r#type.value(),
+                    QuadObject::NamedNode(nn("http://example.org/C"))
+                ),
+                triple(
+                    s.clone(),
+                    "http://example.org/p",
+                    QuadObject::Literal(Literal::new("foo", None, None, None))
+                ),
+                triple(
+                    s.clone(),
+                    "http://example.org/p",
+                    QuadObject::Literal(Literal::new("bar", Some("en-GB"), None, None))
+                ),
+                triple(
+                    s.clone(),
+                    "http://example.org/p",
+                    QuadObject::Literal(Literal::new(
+                        "multi\nline",
+                        None,
+                        None,
+                        Some(&nn("http://example.org/dt"))
+                    ))
+                ),
+                triple(
+                    s.clone(),
+                    "http://example.org/n",
+                    QuadObject::Literal(Literal::new("-1", None, None, Some(&xsd::integer)))
+                ),
+                triple(
+                    s.clone(),
+                    "http://example.org/n",
+                    QuadObject::Literal(Literal::new("2.5", None, None, Some(&xsd::decimal)))
+                ),
+                triple(
+                    s.clone(),
+                    "http://example.org/n",
+                    QuadObject::Literal(Literal::new("3e2", None, None, Some(&xsd::double)))
+                ),
+                triple(
+                    s.clone(),
+                    "http://example.org/n",
+                    QuadObject::Literal(Literal::new("true", None, None, Some(&xsd::boolean)))
+                ),
+                triple(
+                    s,
+                    "http://example.org/default#q",
+                    QuadObject::NamedNode(nn("http://example.org/base/relative"))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn blank_nodes_and_collections_parse() {
+        let quads = parse_str(
+            TurtleSyntax::Turtle,
+            "@prefix ex: <http://example.org/> .
+             _:a ex:p [ ex:q ( 1 ex:o ) ] .
+             [] ex:p () .",
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(quads.len(), 7);
+        assert_eq!(
+            quads[0].subject(),
+            &QuadSubject::BlankNode(BlankNode::new("genid2"))
+        );
+        assert_eq!(quads[0].predicate().value(), rdf::first.value());
+        assert_eq!(quads[4].predicate().value(), "http://example.org/q");
+        assert_eq!(
+            quads[5],
+            triple(
+                QuadSubject::BlankNode(BlankNode::new("a")),
+                "http://example.org/p",
+                QuadObject::BlankNode(BlankNode::new("genid1"))
+            )
+        );
+        assert_eq!(quads[6].object(), &QuadObject::NamedNode(rdf::nil.clone()));
+    }
+
+    #[test]
+    fn document_labels_do_not_meet_generated_ones() {
+        let quads = parse_str(
+            TurtleSyntax::Turtle,
+            "_:genid1 <http://p> [ <http://q> <http://r> ] .",
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(quads.len(), 2);
+        assert_eq!(quads[1].subject().value(), "genid1");
+        assert_eq!(quads[1].object().value(), "genid2");
+
+        let document = "[] <http://p> _:genid1 . _:genid1 <http://p> _:genid3 .\n\
+                        [] <http://p> _:genid02 .";
+        let quads = parse_str(TurtleSyntax::Turtle, document, None).unwrap();
+        let labels: Vec<_> = quads
+            .iter()
+            .map(|quad| (quad.subject().value(), quad.object().value()))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                ("genid1", "genid2"),
+                ("genid2", "genid3"),
+                ("genid4", "genid02")
+            ]
+        );
+
+        let mut parser = TurtleParser::new(TurtleSyntax::Turtle);
+        let mut chunked = Vec::new();
+        for byte in document.bytes() {
+            parser.extend_from_slice(&[byte]);
+            while let Some(quad) = parser.read_next() {
+                chunked.push(quad.unwrap());
+            }
+        }
+        parser.end();
+        while let Some(quad) = parser.read_next() {
+            chunked.push(quad.unwrap());
+        }
+        assert_eq!(chunked, quads);
+    }
+
+    #[test]
+    fn escapes_and_directions_parse() {
+        let quads = parse_str(
+            TurtleSyntax::Turtle,
+            r#"@prefix ex: <http://example.org/> .
+               ex:a\~b ex:p "tab\thereé"@ar--rtl ; ex:q ex:c.d .
+               <<ex:s ex:p ex:o>> ex:p ex:o ."#,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(quads[0].subject().value(), "http://example.org/a~b");
+        assert_eq!(
+            quads[0].object(),
+            &QuadObject::Literal(Literal::new(
+                "tab\there\u{e9}",
+                Some("ar"),
+                Some(&LanguageDirection::RightToLeft),
+                None
+            ))
+        );
+        assert_eq!(quads[1].object().value(), "http://example.org/c.d");
+        assert!(matches!(quads[2].subject(), QuadSubject::Quad(_)));
+    }
+
+    #[test]
+    fn trig_graphs_parse() {
+        let quads = parse_str(
+            TurtleSyntax::TriG,
+            "@prefix ex: <http://example.org/> .
+             ex:s ex:p ex:o .
+             ex:g { ex:s ex:p ex:o . ex:s ex:p ex:o2 }
+             GRAPH ex:h { ex:s ex:p ex:o }
+             { ex:s ex:p ex:o3 }",
+            None,
+        )
+        .unwrap();
+
+        let graphs: Vec<&QuadGraph> = quads.iter().map(|q| q.graph()).collect();
+        let g = QuadGraph::NamedNode(nn("http://example.org/g"));
+        let h = QuadGraph::NamedNode(nn("http://example.org/h"));
+
+        assert_eq!(quads.len(), 5);
+        assert!(matches!(graphs[0], QuadGraph::DefaultGraph(_)));
+        assert_eq!(graphs[1], &g);
+        assert_eq!(graphs[2], &g);
+        assert_eq!(graphs[3], &h);
+        assert!(matches!(graphs[4], QuadGraph::DefaultGraph(_)));
+
+        let mut parser = TurtleParser::new(TurtleSyntax::TriG);
+        parser.extend_from_slice(b"<g> { <s> <p> <o> . <s> <p> <o2> . <s> <p>");
+        assert_eq!(
+            parser.read_next().unwrap().unwrap().graph(),
+            &QuadGraph::NamedNode(nn("g"))
+        );
+        assert!(parser.read_next().unwrap().is_ok());
+        assert!(parser.read_next().is_none());
+        parser.extend_from_slice(b" <o3> }");
+        assert!(parser.read_next().unwrap().is_ok());
+        parser.end();
+        assert!(parser.read_next().is_none());
+        assert!(parser.is_done());
+
+        assert!(parse_str(TurtleSyntax::TriG, "<g> { <s> <p> <o> .", None).is_err());
+        assert!(parse_str(TurtleSyntax::TriG, "<g> { @prefix ex: <e#> . }", None).is_err());
+    }
+
+    #[test]
+    fn errors_report_positions() {
+        let error = parse_str(
+            TurtleSyntax::Turtle,
+            "@prefix ex: <http://example.org/> .\nex:s ex:p\n  ex:o ex:q .",
+            None,
+        )
+        .unwrap_err();
+
+        assert_eq!((error.line(), error.column()), (3, 8));
+
+        let error = parse_str(TurtleSyntax::Turtle, "foo:s <p> <o> .", None).unwrap_err();
+
+        assert_eq!(error.message(), "undefined prefix \"foo:\"");
+        assert_eq!((error.line(), error.column()), (1, 1));
+    }
+
+    #[test]
+    fn chunked_input_parses_like_whole_input() {
+        let input = "@prefix ex: <http://example.org/> .\n\
+                     ex:s ex:p \"caf\u{e9}\", 1.5, ex:o.\n\
+                     ex:s ex:p \"\"\"a \"quoted\" string\"\"\" .\n";
+        let expected = parse_str(TurtleSyntax::Turtle, input, None).unwrap();
+
+        for chunk_size in 1..8 {
+            let reader = TurtleReader::new(
+                std::io::BufReader::with_capacity(chunk_size, input.as_bytes()),
+                TurtleParser::new(TurtleSyntax::Turtle),
+            );
+            let quads: Vec<Quad> = reader.map(|q| q.unwrap()).collect();
+
+            assert_eq!(quads, expected, "chunk size {chunk_size}");
+        }
+    }
+
+    #[test]
+    fn serialized_documents_parse_back() {
+        let input = "@prefix ex: <http://example.org/> .\n\
+                     ex:s a ex:C ; ex:p \"caf\u{e9}\"@fr, 1.5, -2, \"x\\ty\"^^ex:d, _:b .\n\
+                     << ex:s ex:p ex:o >> ex:q \"\"\"two\nlines\"\"\"@en--ltr .\n\
+                     ex:g { ex:s ex:p ex:o . ex:t ex:p ex:s . }\n";
+        let quads = parse_str(TurtleSyntax::TriG, input, None).unwrap();
+
+        let mut output = Vec::new();
+        TurtleSerializer::new(TurtleSyntax::TriG)
+            .with_prefix("ex", "http://example.org/")
+            .serialize(&mut output, &quads)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with("@prefix ex: <http://example.org/> .\n\nex:s a ex:C ;"));
+        assert_eq!(parse_str(TurtleSyntax::TriG, &output, None).unwrap(), quads);
+
+        let error = TurtleSerializer::new(TurtleSyntax::Turtle)
+            .serialize(&mut Vec::new(), &quads)
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn line_based_syntaxes_round_trip() {
+        let input = "<http://example.org/s> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> \"1\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n\
+                     _:b <http://example.org/p> \"x\"@en <http://example.org/g> .\n\
+                     <<( _:b <http://example.org/p> \"x\" )>> <http://example.org/q> _:b .\n";
+        let quads = parse_str(TurtleSyntax::NQuads, input, None).unwrap();
+        assert_eq!(quads.len(), 3);
+        assert!(matches!(quads[2].subject(), QuadSubject::Quad(_)));
+        assert_eq!(quads[1].graph().value(), "http://example.org/g");
+
+        let mut output = Vec::new();
+        TurtleSerializer::new(TurtleSyntax::NQuads)
+            .with_prefix("ex", "http://example.org/")
+            .serialize(&mut output, &quads)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+
+        let error = parse_str(
+            TurtleSyntax::NTriples,
+            "_:b <http://example.org/p> _:c _:g .",
+            None,
+        )
+        .unwrap_err();
+        assert_eq!((error.line(), error.column()), (1, 32));
+    }
+}
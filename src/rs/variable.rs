@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 use crate::rs::{term::Term, term_like::TermLike};
 
 #[derive(Clone, Eq, Debug)]
@@ -23,6 +25,12 @@ impl PartialEq for Variable {
     }
 }
 
+impl Hash for Variable {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value().hash(state);
+    }
+}
+
 impl PartialEq<Term> for Variable {
     fn eq(&self, other: &Term) -> bool {
         match other {
@@ -0,0 +1,701 @@
+//! Ready-made [`NamedNode`]s for well-known vocabularies.
+//!
+//! Every vocabulary is a module exposing its `PREFIX`, its `NAMESPACE` and a
+//! lazily-built static per term, named exactly like the term's local name
+//! (Rust keywords use raw identifiers, e.g. `rdf::r#type`).
+//!
+//! Modules of the same shape can be generated for other vocabularies from a
+//! Turtle ontology at build time, e.g. in `build.rs`:
+//!
+//! ```ignore
+//! fn main() {
+//!     let out_dir = std::env::var("OUT_DIR").unwrap();
+//!
+//!     rdfjs_rust::rs::vocab::generate_module_file(
+//!         "ontology/example.ttl".as_ref(),
+//!         std::path::Path::new(&out_dir).join("example.rs").as_ref(),
+//!         "ex",
+//!         "http://example.org/ns#",
+//!     )
+//!     .unwrap();
+//! }
+//! ```
+//!
+//! and then `pub mod ex { include!(concat!(env!("OUT_DIR"), "/example.rs")); }`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt::{Display, Write};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::rs::named_node::NamedNode;
+use crate::rs::parse_error::SyntaxError;
+use crate::rs::quad_object::QuadObject;
+use crate::rs::quad_predicate::QuadPredicate;
+use crate::rs::quad_subject::QuadSubject;
+use crate::rs::term_like::TermLike;
+use crate::rs::turtle::{self, TurtleSyntax};
+
+macro_rules! vocabulary {
+    ($prefix:ident, $namespace:literal, [$($term:ident),* $(,)?]) => {
+        #[allow(non_upper_case_globals)]
+        pub mod $prefix {
+            use std::sync::LazyLock;
+
+            use crate::rs::named_node::NamedNode;
+
+            pub const PREFIX: &str = stringify!($prefix);
+            pub const NAMESPACE: &str = $namespace;
+
+            $(
+                pub static $term: LazyLock<NamedNode> = LazyLock::new(|| {
+                    NamedNode::new(&format!(
+                        "{NAMESPACE}{}",
+                        stringify!($term).trim_start_matches("r#")
+                    ))
+                });
+            )*
+        }
+    };
+}
+
+vocabulary!(
+    rdf,
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#",
+    [
+        Alt,
+        Bag,
+        CompoundLiteral,
+        HTML,
+        JSON,
+        List,
+        PlainLiteral,
+        Property,
+        Seq,
+        Statement,
+        XMLLiteral,
+        dirLangString,
+        direction,
+        first,
+        langString,
+        language,
+        nil,
+        object,
+        predicate,
+        reifies,
+        rest,
+        subject,
+        r#type,
+        value,
+    ]
+);
+
+vocabulary!(
+    rdfs,
+    "http://www.w3.org/2000/01/rdf-schema#",
+    [
+        Class,
+        Container,
+        ContainerMembershipProperty,
+        Datatype,
+        Literal,
+        Resource,
+        comment,
+        domain,
+        isDefinedBy,
+        label,
+        member,
+        range,
+        seeAlso,
+        subClassOf,
+        subPropertyOf,
+    ]
+);
+
+vocabulary!(
+    xsd,
+    "http://www.w3.org/2001/XMLSchema#",
+    [
+        ENTITY,
+        ID,
+        IDREF,
+        NCName,
+        NMTOKEN,
+        Name,
+        anyURI,
+        base64Binary,
+        boolean,
+        byte,
+        date,
+        dateTime,
+        dateTimeStamp,
+        dayTimeDuration,
+        decimal,
+        double,
+        duration,
+        float,
+        gDay,
+        gMonth,
+        gMonthDay,
+        gYear,
+        gYearMonth,
+        hexBinary,
+        int,
+        integer,
+        language,
+        long,
+        negativeInteger,
+        nonNegativeInteger,
+        nonPositiveInteger,
+        normalizedString,
+        positiveInteger,
+        short,
+        string,
+        time,
+        token,
+        unsignedByte,
+        unsignedInt,
+        unsignedLong,
+        unsignedShort,
+        yearMonthDuration,
+    ]
+);
+
+vocabulary!(
+    owl,
+    "http://www.w3.org/2002/07/owl#",
+    [
+        AllDifferent,
+        AnnotationProperty,
+        AsymmetricProperty,
+        Class,
+        DatatypeProperty,
+        DeprecatedClass,
+        DeprecatedProperty,
+        FunctionalProperty,
+        InverseFunctionalProperty,
+        IrreflexiveProperty,
+        NamedIndividual,
+        Nothing,
+        ObjectProperty,
+        Ontology,
+        ReflexiveProperty,
+        Restriction,
+        SymmetricProperty,
+        Thing,
+        TransitiveProperty,
+        allValuesFrom,
+        cardinality,
+        complementOf,
+        deprecated,
+        differentFrom,
+        disjointWith,
+        distinctMembers,
+        equivalentClass,
+        equivalentProperty,
+        hasValue,
+        imports,
+        intersectionOf,
+        inverseOf,
+        maxCardinality,
+        minCardinality,
+        onClass,
+        onProperty,
+        oneOf,
+        priorVersion,
+        qualifiedCardinality,
+        sameAs,
+        someValuesFrom,
+        unionOf,
+        versionIRI,
+        versionInfo,
+    ]
+);
+
+vocabulary!(
+    sh,
+    "http://www.w3.org/ns/shacl#",
+    [
+        BlankNode,
+        BlankNodeOrIRI,
+        BlankNodeOrLiteral,
+        IRI,
+        IRIOrLiteral,
+        Info,
+        Literal,
+        NodeShape,
+        PropertyShape,
+        ValidationReport,
+        ValidationResult,
+        Violation,
+        Warning,
+        and,
+        class,
+        closed,
+        conforms,
+        datatype,
+        deactivated,
+        description,
+        flags,
+        focusNode,
+        group,
+        hasValue,
+        ignoredProperties,
+        r#in,
+        languageIn,
+        maxCount,
+        maxExclusive,
+        maxInclusive,
+        maxLength,
+        message,
+        minCount,
+        minExclusive,
+        minInclusive,
+        minLength,
+        name,
+        node,
+        nodeKind,
+        not,
+        or,
+        order,
+        path,
+        pattern,
+        property,
+        qualifiedValueShape,
+        result,
+        resultMessage,
+        resultPath,
+        resultSeverity,
+        severity,
+        sourceConstraintComponent,
+        sourceShape,
+        targetClass,
+        targetNode,
+        targetObjectsOf,
+        targetSubjectsOf,
+        uniqueLang,
+        value,
+        xone,
+    ]
+);
+
+vocabulary!(
+    skos,
+    "http://www.w3.org/2004/02/skos/core#",
+    [
+        Collection,
+        Concept,
+        ConceptScheme,
+        OrderedCollection,
+        altLabel,
+        broadMatch,
+        broader,
+        broaderTransitive,
+        changeNote,
+        closeMatch,
+        definition,
+        editorialNote,
+        exactMatch,
+        example,
+        hasTopConcept,
+        hiddenLabel,
+        historyNote,
+        inScheme,
+        mappingRelation,
+        member,
+        memberList,
+        narrowMatch,
+        narrower,
+        narrowerTransitive,
+        notation,
+        note,
+        prefLabel,
+        related,
+        relatedMatch,
+        scopeNote,
+        semanticRelation,
+        topConceptOf,
+    ]
+);
+
+vocabulary!(
+    dcterms,
+    "http://purl.org/dc/terms/",
+    [
+        Agent,
+        BibliographicResource,
+        LicenseDocument,
+        Location,
+        MediaType,
+        PeriodOfTime,
+        r#abstract,
+        accessRights,
+        alternative,
+        audience,
+        available,
+        bibliographicCitation,
+        conformsTo,
+        contributor,
+        coverage,
+        created,
+        creator,
+        date,
+        dateAccepted,
+        dateCopyrighted,
+        dateSubmitted,
+        description,
+        extent,
+        format,
+        hasFormat,
+        hasPart,
+        hasVersion,
+        identifier,
+        isFormatOf,
+        isPartOf,
+        isReferencedBy,
+        isReplacedBy,
+        isRequiredBy,
+        isVersionOf,
+        issued,
+        language,
+        license,
+        modified,
+        provenance,
+        publisher,
+        references,
+        relation,
+        replaces,
+        requires,
+        rights,
+        rightsHolder,
+        source,
+        spatial,
+        subject,
+        tableOfContents,
+        temporal,
+        title,
+        r#type,
+        valid,
+    ]
+);
+
+vocabulary!(
+    foaf,
+    "http://xmlns.com/foaf/0.1/",
+    [
+        Agent,
+        Document,
+        Group,
+        Image,
+        OnlineAccount,
+        Organization,
+        Person,
+        Project,
+        account,
+        accountName,
+        age,
+        based_near,
+        birthday,
+        depiction,
+        familyName,
+        firstName,
+        gender,
+        givenName,
+        homepage,
+        img,
+        interest,
+        isPrimaryTopicOf,
+        knows,
+        lastName,
+        logo,
+        made,
+        maker,
+        mbox,
+        mbox_sha1sum,
+        member,
+        name,
+        nick,
+        page,
+        phone,
+        primaryTopic,
+        title,
+        topic,
+        weblog,
+    ]
+);
+
+vocabulary!(
+    prov,
+    "http://www.w3.org/ns/prov#",
+    [
+        Activity,
+        Agent,
+        Association,
+        Attribution,
+        Bundle,
+        Collection,
+        Delegation,
+        Derivation,
+        Entity,
+        Generation,
+        Organization,
+        Person,
+        Plan,
+        SoftwareAgent,
+        Usage,
+        actedOnBehalfOf,
+        activity,
+        agent,
+        atTime,
+        endedAtTime,
+        entity,
+        generatedAtTime,
+        hadMember,
+        hadPlan,
+        hadPrimarySource,
+        invalidatedAtTime,
+        qualifiedAssociation,
+        qualifiedAttribution,
+        qualifiedDerivation,
+        qualifiedGeneration,
+        qualifiedUsage,
+        startedAtTime,
+        used,
+        value,
+        wasAssociatedWith,
+        wasAttributedTo,
+        wasDerivedFrom,
+        wasEndedBy,
+        wasGeneratedBy,
+        wasInformedBy,
+        wasInvalidatedBy,
+        wasQuotedFrom,
+        wasRevisionOf,
+        wasStartedBy,
+    ]
+);
+
+const STRICT_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+const UNRAWABLE_KEYWORDS: &[&str] = &["crate", "self", "Self", "super", "_"];
+
+fn identifier_for(local_name: &str) -> String {
+    let mut identifier: String = local_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+
+    if UNRAWABLE_KEYWORDS.contains(&identifier.as_str()) {
+        identifier.push('_');
+    } else if STRICT_KEYWORDS.contains(&identifier.as_str()) {
+        identifier.insert_str(0, "r#");
+    }
+
+    identifier
+}
+
+fn literal_text(object: &QuadObject) -> Option<&str> {
+    match object {
+        QuadObject::Literal(literal)
+            if literal.language().is_empty() || literal.language().starts_with("en") =>
+        {
+            Some(literal.value())
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub enum VocabularyError {
+    Io(io::Error),
+    Syntax(SyntaxError),
+    DuplicateIdentifier(String),
+}
+
+impl Display for VocabularyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VocabularyError::Io(error) => write!(f, "{error}"),
+            VocabularyError::Syntax(error) => write!(f, "{error}"),
+            VocabularyError::DuplicateIdentifier(identifier) => {
+                write!(f, "several terms map to the identifier `{identifier}`")
+            }
+        }
+    }
+}
+
+impl Error for VocabularyError {}
+
+/// Generates the source of a vocabulary module (in the shape of the modules
+/// above) for every term of `namespace` defined as a subject in `turtle`.
+///
+/// `rdfs:label` and `rdfs:comment` values become `#[doc]` attributes, with
+/// the text escaped as a string literal.
+pub fn generate_module(
+    turtle: &str,
+    prefix: &str,
+    namespace: &str,
+) -> Result<String, VocabularyError> {
+    let quads =
+        turtle::parse_str(TurtleSyntax::Turtle, turtle, None).map_err(VocabularyError::Syntax)?;
+
+    let mut terms: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for quad in &quads {
+        let QuadSubject::NamedNode(subject) = quad.subject() else {
+            continue;
+        };
+        let Some(local_name) = subject.value().strip_prefix(namespace) else {
+            continue;
+        };
+        if local_name.is_empty() {
+            continue;
+        }
+
+        let docs = terms.entry(local_name.to_owned()).or_default();
+
+        if let QuadPredicate::NamedNode(predicate) = quad.predicate()
+            && (predicate == &*rdfs::label || predicate == &*rdfs::comment)
+            && let Some(text) = literal_text(quad.object())
+        {
+            docs.push(text.to_owned());
+        }
+    }
+
+    let mut identifiers = BTreeSet::new();
+    let mut source = String::new();
+
+    writeln!(source, "pub const PREFIX: &str = {prefix:?};").unwrap();
+    writeln!(source, "pub const NAMESPACE: &str = {namespace:?};").unwrap();
+
+    for (local_name, docs) in &terms {
+        let identifier = identifier_for(local_name);
+
+        if !identifiers.insert(identifier.clone()) {
+            return Err(VocabularyError::DuplicateIdentifier(identifier));
+        }
+
+        source.push('\n');
+        for doc in docs {
+            let text: Vec<_> = doc.lines().map(str::trim).collect();
+            writeln!(source, "#[doc = {:?}]", text.join("\n")).unwrap();
+        }
+        writeln!(source, "#[allow(non_upper_case_globals)]").unwrap();
+        writeln!(
+            source,
+            "pub static {identifier}: std::sync::LazyLock<rdfjs_rust::rs::named_node::NamedNode> ="
+        )
+        .unwrap();
+        writeln!(
+            source,
+            "    std::sync::LazyLock::new(|| rdfjs_rust::rs::named_node::NamedNode::new({:?}));",
+            NamedNode::new(&format!("{namespace}{local_name}")).value()
+        )
+        .unwrap();
+    }
+
+    Ok(source)
+}
+
+/// [`generate_module`] for build scripts: reads `ontology` and writes the
+/// module source to `output`.
+pub fn generate_module_file(
+    ontology: &Path,
+    output: &Path,
+    prefix: &str,
+    namespace: &str,
+) -> Result<(), VocabularyError> {
+    let turtle = fs::read_to_string(ontology).map_err(VocabularyError::Io)?;
+    let source = generate_module(&turtle, prefix, namespace)?;
+
+    fs::write(output, source).map_err(VocabularyError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rs::literal::Literal;
+
+    #[test]
+    fn terms_expand_to_namespace_iris() {
+        assert_eq!(
+            rdf::r#type.value(),
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#type"
+        );
+        assert_eq!(
+            xsd::string.value(),
+            "http://www.w3.org/2001/XMLSchema#string"
+        );
+        assert_eq!(*sh::r#in, NamedNode::new("http://www.w3.org/ns/shacl#in"));
+        assert_eq!(prov::Agent.value(), "http://www.w3.org/ns/prov#Agent");
+        assert_eq!(prov::agent.value(), "http://www.w3.org/ns/prov#agent");
+        assert_eq!(foaf::PREFIX, "foaf");
+        assert_eq!(dcterms::NAMESPACE, "http://purl.org/dc/terms/");
+    }
+
+    #[test]
+    fn literals_use_vocabulary_datatypes() {
+        assert_eq!(
+            *Literal::new("foo", None, None, None).datatype(),
+            *xsd::string
+        );
+        assert_eq!(
+            *Literal::new("foo", Some("en"), None, None).datatype(),
+            *rdf::langString
+        );
+    }
+
+    #[test]
+    fn modules_are_generated_from_ontologies() {
+        let ontology = r#"
+            @prefix ex: <http://example.org/ns#> .
+            @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+
+            ex: a <http://www.w3.org/2002/07/owl#Ontology> .
+            ex:Person a rdfs:Class ;
+                rdfs:label "Person"@en ;
+                rdfs:comment "Somebody.\n  */ \"quoted\" \\ \r!" .
+            ex:type a rdf:Property .
+            ex:self-link a rdf:Property .
+            <http://example.org/other#Thing> a rdfs:Class .
+        "#;
+
+        let source = generate_module(ontology, "ex", "http://example.org/ns#").unwrap();
+
+        assert!(source.contains("pub const NAMESPACE: &str = \"http://example.org/ns#\";"));
+        assert!(source.contains(
+            "#[doc = \"Person\"]\n#[doc = \"Somebody.\\n*/ \\\"quoted\\\" \\\\ \\r!\"]\n"
+        ));
+        assert!(source.contains("pub static Person:"));
+        assert!(source.contains("pub static r#type:"));
+        assert!(source.contains("pub static self_link:"));
+        assert!(source.contains("NamedNode::new(\"http://example.org/ns#self-link\")"));
+        assert!(!source.contains("Thing"));
+    }
+
+    #[test]
+    fn colliding_identifiers_are_rejected() {
+        let ontology = "
+            <http://example.org/ns#a-b> a <http://example.org/ns#C> .
+            <http://example.org/ns#a_b> a <http://example.org/ns#C> .
+        ";
+
+        assert!(matches!(
+            generate_module(ontology, "ex", "http://example.org/ns#"),
+            Err(VocabularyError::DuplicateIdentifier(identifier)) if identifier == "a_b"
+        ));
+    }
+}
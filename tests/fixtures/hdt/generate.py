@@ -0,0 +1,159 @@
+"""Writes small.hdt from small.nt, laid out like rdf2hdt (hdt-cpp) does.
+
+Four-section dictionary with plain front coding in blocks of 16 strings,
+SPO bitmap triples, log sequences and the CRC8/CRC16/CRC32C checksums of
+hdt-cpp. Run from this directory: python3 generate.py
+
+small.hdt was not produced by rdf2hdt itself: hdt-cpp was not available
+when it was committed. Replacing it with the output of
+`rdf2hdt small.nt small.hdt` should keep the tests passing.
+"""
+
+import re
+
+
+def crc8(data):
+    crc = 0
+    for byte in data:
+        crc ^= byte
+        for _ in range(8):
+            crc = ((crc << 1) ^ 0x07) & 0xFF if crc & 0x80 else (crc << 1) & 0xFF
+    return bytes([crc])
+
+
+def crc16(data):
+    crc = 0
+    for byte in data:
+        crc ^= byte
+        for _ in range(8):
+            crc = (crc >> 1) ^ 0xA001 if crc & 1 else crc >> 1
+    return crc.to_bytes(2, "little")
+
+
+def crc32c(data):
+    crc = 0xFFFFFFFF
+    for byte in data:
+        crc ^= byte
+        for _ in range(8):
+            crc = (crc >> 1) ^ 0x82F63B78 if crc & 1 else crc >> 1
+    return (crc ^ 0xFFFFFFFF).to_bytes(4, "little")
+
+
+def vbyte(value):
+    out = bytearray()
+    while value >= 0x80:
+        out.append(value & 0x7F)
+        value >>= 7
+    out.append(value | 0x80)
+    return bytes(out)
+
+
+def control_info(kind, format, properties):
+    block = b"$HDT" + bytes([kind]) + format.encode() + b"\0" + properties.encode() + b"\0"
+    return block + crc16(block)
+
+
+def sequence(values):
+    bits = max(values, default=0).bit_length()
+    packed = 0
+    for index, value in enumerate(values):
+        packed |= value << (index * bits)
+    data = packed.to_bytes((len(values) * bits + 7) // 8, "little")
+    preamble = bytes([1, bits]) + vbyte(len(values))
+    return preamble + crc8(preamble) + data + crc32c(data)
+
+
+def bitmap(bits):
+    packed = sum(1 << index for index, bit in enumerate(bits) if bit)
+    data = packed.to_bytes((len(bits) + 7) // 8, "little")
+    preamble = bytes([1]) + vbyte(len(bits))
+    return preamble + crc8(preamble) + data + crc32c(data)
+
+
+def section(strings, block_size=16):
+    data = bytearray()
+    offsets = []
+    for index, string in enumerate(strings):
+        string = string.encode()
+        if index % block_size == 0:
+            offsets.append(len(data))
+            data += string
+        else:
+            previous = strings[index - 1].encode()
+            shared = 0
+            while shared < min(len(previous), len(string)) and previous[shared] == string[shared]:
+                shared += 1
+            data += vbyte(shared) + string[shared:]
+        data += b"\0"
+    offsets.append(len(data))
+    preamble = bytes([2]) + vbyte(len(strings)) + vbyte(len(data)) + vbyte(block_size)
+    return preamble + crc8(preamble) + sequence(offsets) + bytes(data) + crc32c(bytes(data))
+
+
+TERM = re.compile(r'<[^>]*>|_:\S+|"(?:[^"\\]|\\.)*"(?:@[\w-]+|\^\^<[^>]*>)?')
+
+
+def dictionary_string(term):
+    """IRIs lose their brackets and literals their escapes, as in hdt-cpp."""
+    if term.startswith("<"):
+        return term[1:-1]
+    if term.startswith('"'):
+        return term.replace('\\"', '"').replace("\\n", "\n").replace("\\\\", "\\")
+    return term
+
+
+triples = []
+with open("small.nt", encoding="utf-8") as lines:
+    for line in lines:
+        if line.strip():
+            triples.append(tuple(dictionary_string(t) for t in TERM.findall(line)[:3]))
+
+subjects = {s for s, _, _ in triples}
+objects = {o for _, _, o in triples}
+shared = sorted(subjects & objects, key=str.encode)
+only_subjects = sorted(subjects - objects, key=str.encode)
+only_objects = sorted(objects - subjects, key=str.encode)
+predicates = sorted({p for _, p, _ in triples}, key=str.encode)
+
+
+def term_id(term, section):
+    if term in shared:
+        return shared.index(term) + 1
+    return len(shared) + section.index(term) + 1
+
+
+ids = sorted(
+    {(term_id(s, only_subjects), predicates.index(p) + 1, term_id(o, only_objects)) for s, p, o in triples}
+)
+pairs = sorted({(s, p) for s, p, _ in ids})
+bitmap_y = [i + 1 == len(pairs) or pairs[i + 1][0] != pairs[i][0] for i in range(len(pairs))]
+bitmap_z = [i + 1 == len(ids) or ids[i + 1][:2] != ids[i][:2] for i in range(len(ids))]
+
+base = "http://example.org/small.nt"
+header = (
+    f"<{base}> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://purl.org/HDT/hdt#Dataset> .\n"
+    f"<{base}> <http://rdfs.org/ns/void#triples> \"{len(ids)}\" .\n"
+).encode()
+
+dictionary = b"".join(section(s) for s in [shared, only_subjects, predicates, only_objects])
+size = sum(len(s.encode()) + 1 for s in shared + only_subjects + predicates + only_objects)
+hdt = b"".join(
+    [
+        control_info(1, "<http://purl.org/HDT/hdt#HDTv1>", f"BaseUri={base};"),
+        control_info(2, "ntriples", f"length={len(header)};"),
+        header,
+        control_info(
+            3,
+            "<http://purl.org/HDT/hdt#dictionaryFour>",
+            f"mapping=1;sizeStrings={size};elements={len(shared) + len(only_subjects) + len(predicates) + len(only_objects)};",
+        ),
+        dictionary,
+        control_info(4, "<http://purl.org/HDT/hdt#triplesBitmap>", f"order=1;numTriples={len(ids)};"),
+        bitmap(bitmap_y),
+        bitmap(bitmap_z),
+        sequence([p for _, p in pairs]),
+        sequence([o for _, _, o in ids]),
+    ]
+)
+with open("small.hdt", "wb") as out:
+    out.write(hdt)